@@ -492,10 +492,19 @@ fn register_array(ctx: &mut InferenceContext) {
         Type::dict(Type::Var(k), Type::Var(v)),
     );
 
-    // len: [a] -> number, string -> number
-    let a = ctx.fresh_var();
-    register_unary(ctx, "len", Type::array(Type::Var(a)), Type::Number);
-    register_unary(ctx, "len", Type::String, Type::Number);
+    // len: (string | bytes | [a] | {k: v}) -> number
+    let (a, k, v) = (ctx.fresh_var(), ctx.fresh_var(), ctx.fresh_var());
+    register_unary(
+        ctx,
+        "len",
+        Type::union(vec![
+            Type::String,
+            Type::Bytes,
+            Type::array(Type::Var(a)),
+            Type::dict(Type::Var(k), Type::Var(v)),
+        ]),
+        Type::Number,
+    );
 
     // slice: ([a], number, number) -> [a]
     let a = ctx.fresh_var();
@@ -707,10 +716,6 @@ fn register_dict(ctx: &mut InferenceContext) {
         Type::dict(Type::Var(k), Type::Var(v)),
     );
 
-    // len: ({k: v}) -> number
-    let (k, v) = (ctx.fresh_var(), ctx.fresh_var());
-    register_unary(ctx, "len", Type::dict(Type::Var(k), Type::Var(v)), Type::Number);
-
     // get: ([a], number) -> a (array element access)
     let a = ctx.fresh_var();
     register_binary(ctx, "get", Type::array(Type::Var(a)), Type::Number, Type::Var(a));
@@ -789,12 +794,18 @@ fn register_type_checks(ctx: &mut InferenceContext) {
         register_unary(ctx, name, Type::Var(a), Type::Bool);
     }
 
-    // is_empty: (string) -> bool, ([a]) -> bool, ({k: v}) -> bool, (a) -> bool
-    register_unary(ctx, "is_empty", Type::String, Type::Bool);
-    let a = ctx.fresh_var();
-    register_unary(ctx, "is_empty", Type::array(Type::Var(a)), Type::Bool);
-    let (k, v) = (ctx.fresh_var(), ctx.fresh_var());
-    register_unary(ctx, "is_empty", Type::dict(Type::Var(k), Type::Var(v)), Type::Bool);
+    // is_empty: (string | [a] | {k: v}) -> bool, (a) -> bool
+    let (a, k, v) = (ctx.fresh_var(), ctx.fresh_var(), ctx.fresh_var());
+    register_unary(
+        ctx,
+        "is_empty",
+        Type::union(vec![
+            Type::String,
+            Type::array(Type::Var(a)),
+            Type::dict(Type::Var(k), Type::Var(v)),
+        ]),
+        Type::Bool,
+    );
     // Generic fallback for dynamically typed values (e.g., via selectors/index access)
     let a = ctx.fresh_var();
     register_unary(ctx, "is_empty", Type::Var(a), Type::Bool);
@@ -1334,9 +1345,6 @@ fn register_bytes(ctx: &mut InferenceContext) {
     // reverse: (bytes) -> bytes
     register_unary(ctx, "reverse", Type::Bytes, Type::Bytes);
 
-    // len: (bytes) -> number
-    register_unary(ctx, "len", Type::Bytes, Type::Number);
-
     // base64: (bytes) -> string
     register_unary(ctx, "base64", Type::Bytes, Type::String);
 
@@ -0,0 +1,141 @@
+//! Project configuration for the `mq-check` CLI, read from the `[check]` section of `mq.toml`.
+//!
+//! ```toml
+//! [check]
+//! include_paths = ["modules"]
+//! strictness = "warn"
+//! strict_array = true
+//! signatures = ["types/host.mqi"]
+//! ```
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mq_check::Strictness;
+use serde::Deserialize;
+
+/// File name of the project configuration.
+pub(crate) const CONFIG_FILE_NAME: &str = "mq.toml";
+
+/// The `[check]` section of `mq.toml`. Relative paths are resolved against the directory
+/// containing the file.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct CheckConfig {
+    /// Directories searched for included modules
+    pub(crate) include_paths: Vec<PathBuf>,
+    /// How to report dynamic constructs: `off`, `warn`, or `strict`
+    pub(crate) strictness: Option<String>,
+    /// Reject heterogeneous arrays
+    pub(crate) strict_array: Option<bool>,
+    /// Signature files (.mqi) loaded for every checked file
+    pub(crate) signatures: Vec<PathBuf>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Manifest {
+    #[serde(default)]
+    check: CheckConfig,
+}
+
+impl CheckConfig {
+    /// Parses the `[check]` section of an `mq.toml` located in `base_dir`. Other sections are
+    /// ignored, since the file is shared with other mq tools.
+    pub(crate) fn parse(input: &str, base_dir: &Path) -> Result<Self, String> {
+        let manifest: Manifest = toml::from_str(input).map_err(|e| e.to_string())?;
+        let mut config = manifest.check;
+
+        if let Some(strictness) = &config.strictness {
+            strictness.parse::<Strictness>()?;
+        }
+        for path in config.include_paths.iter_mut().chain(config.signatures.iter_mut()) {
+            *path = base_dir.join(&*path);
+        }
+
+        Ok(config)
+    }
+
+    /// Reads and parses the configuration file at `path`.
+    pub(crate) fn load(path: &Path) -> io::Result<Self> {
+        let input = std::fs::read_to_string(path)
+            .map_err(|e| io::Error::other(format!("reading config {}: {}", path.display(), e)))?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+
+        Self::parse(&input, base_dir).map_err(|e| io::Error::other(format!("parsing config {}: {}", path.display(), e)))
+    }
+
+    /// Returns the configured strictness, if any. Validated by [`CheckConfig::parse`].
+    pub(crate) fn strictness(&self) -> Option<Strictness> {
+        self.strictness
+            .as_deref()
+            .and_then(|strictness| strictness.parse().ok())
+    }
+}
+
+/// Returns the nearest `mq.toml` in `start` or one of its ancestors.
+pub(crate) fn find_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_parse_check_section() {
+        let config = CheckConfig::parse(
+            r#"
+[check]
+include_paths = ["modules", "/abs"]
+strictness = "warn"
+strict_array = true
+signatures = ["host.mqi"]
+
+[other]
+key = 1
+"#,
+            Path::new("/project"),
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.include_paths,
+            vec![PathBuf::from("/project/modules"), PathBuf::from("/abs")]
+        );
+        assert_eq!(config.strictness(), Some(Strictness::Warn));
+        assert_eq!(config.strict_array, Some(true));
+        assert_eq!(config.signatures, vec![PathBuf::from("/project/host.mqi")]);
+    }
+
+    #[rstest]
+    #[case::empty("")]
+    #[case::other_sections_only("[format]\nindent_width = 2\n")]
+    fn test_parse_without_check_section(#[case] input: &str) {
+        assert_eq!(
+            CheckConfig::parse(input, Path::new("")).unwrap(),
+            CheckConfig::default()
+        );
+    }
+
+    #[rstest]
+    #[case::unknown_strictness("[check]\nstrictness = \"loud\"\n")]
+    #[case::unknown_key("[check]\ninclude_path = [\"modules\"]\n")]
+    #[case::invalid_toml("[check\n")]
+    fn test_parse_invalid(#[case] input: &str) {
+        assert!(CheckConfig::parse(input, Path::new("")).is_err());
+    }
+
+    #[test]
+    fn test_find_config_searches_ancestors() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join(CONFIG_FILE_NAME), "[check]\n").unwrap();
+
+        assert_eq!(find_config(&nested), Some(dir.path().join(CONFIG_FILE_NAME)));
+    }
+}
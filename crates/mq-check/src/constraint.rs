@@ -13,8 +13,8 @@ use categories::categorize_symbols;
 use helpers::{
     build_piped_call_args, collect_break_value_types, collect_pattern_variable_descendants, find_enclosing_function,
     find_lambda_function_child, get_post_loop_siblings, get_symbol_range, is_foreach_iterable_ref,
-    is_inside_quote_block, merge_loop_types, might_receive_piped_input, resolve_builtin_call, resolve_pattern_type,
-    resolve_whole_type_pattern, spread_element_type,
+    is_inside_quote_block, merge_loop_types, might_receive_piped_input, qualified_function_name, resolve_builtin_call,
+    resolve_pattern_type, resolve_whole_type_pattern, spread_element_type,
 };
 use pipe::{generate_block_constraints, generate_function_body_pipe_constraints, resolve_branch_body_type};
 
@@ -27,6 +27,7 @@ use crate::types::Type;
 use crate::unify::range_to_span;
 use crate::{TypeError, infer};
 use mq_hir::{Hir, SymbolId, SymbolKind};
+use rustc_hash::FxHashSet;
 
 use smol_str::SmolStr;
 use std::fmt;
@@ -82,6 +83,29 @@ impl fmt::Display for Constraint {
     }
 }
 
+/// Returns the top-level function definitions whose names are declared by a signature file,
+/// together with all of their descendants.
+fn declared_definitions(hir: &Hir, ctx: &InferenceContext, children_index: &ChildrenIndex) -> FxHashSet<SymbolId> {
+    let mut declared = FxHashSet::default();
+    let mut stack: Vec<SymbolId> = hir
+        .symbols()
+        .filter(|(_, symbol)| {
+            symbol.parent.is_none()
+                && matches!(symbol.kind, SymbolKind::Function(_))
+                && !hir.is_builtin_symbol(symbol)
+                && symbol.value.as_deref().is_some_and(|name| ctx.is_declared(name))
+        })
+        .map(|(symbol_id, _)| symbol_id)
+        .collect();
+
+    while let Some(symbol_id) = stack.pop() {
+        if declared.insert(symbol_id) {
+            stack.extend(get_children(children_index, symbol_id));
+        }
+    }
+    declared
+}
+
 /// Generates type constraints from HIR.
 ///
 /// Returns the children index built during constraint generation so callers can
@@ -92,7 +116,16 @@ pub fn generate_constraints(hir: &Hir, ctx: &mut InferenceContext) -> ChildrenIn
     let children_index = build_children_index(hir);
 
     // Categorize symbols in a single pass (replaces 5 separate iterations)
-    let cats = categorize_symbols(hir);
+    let mut cats = categorize_symbols(hir);
+
+    // Top-level definitions with a declared signature are typed by it, so their bodies aren't inferred.
+    let declared = declared_definitions(hir, ctx, &children_index);
+    if !declared.is_empty() {
+        cats.pass1_symbols.retain(|(id, _)| !declared.contains(id));
+        cats.assign_symbols.retain(|(id, _)| !declared.contains(id));
+        cats.pass3_symbols.retain(|(id, _)| !declared.contains(id));
+        cats.pass4_functions.retain(|id| !declared.contains(id));
+    }
 
     // Pass 1: Assign types to literals, variables, and simple constructs.
     //
@@ -534,7 +567,7 @@ pub(super) fn generate_symbol_constraints(
                                 }
                             } else {
                                 let range = get_symbol_range(hir, symbol_id);
-                                ctx.report_no_matching_overload(name.as_str(), &arg_tys, range);
+                                ctx.report_no_matching_overload(name.as_str(), &arg_tys, &[piped_ty], range);
                                 let ty_var = ctx.fresh_var();
                                 ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
                                 return;
@@ -729,7 +762,12 @@ pub(super) fn generate_symbol_constraints(
                                 }
                             } else {
                                 // No matching overload found - collect error
-                                ctx.report_no_matching_overload(op_name, &[resolved_left, resolved_right], range);
+                                ctx.report_no_matching_overload(
+                                    op_name,
+                                    &[resolved_left, resolved_right],
+                                    &[left_ty, right_ty],
+                                    range,
+                                );
                                 let ty_var = ctx.fresh_var();
                                 ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
                             }
@@ -828,7 +866,12 @@ pub(super) fn generate_symbol_constraints(
                                     // Update variable type to result
                                     ctx.set_symbol_type(var_id, *ret_ty);
                                 } else if ctx.resolve_overload(base_op, &arg_types).is_none() {
-                                    ctx.report_no_matching_overload(base_op, &[resolved_left, resolved_right], range);
+                                    ctx.report_no_matching_overload(
+                                        base_op,
+                                        &[resolved_left, resolved_right],
+                                        &[current_var_ty, rhs_ty],
+                                        range,
+                                    );
                                 }
                             }
                         }
@@ -896,7 +939,7 @@ pub(super) fn generate_symbol_constraints(
                                 }
                             } else {
                                 // No matching overload found - collect error
-                                ctx.report_no_matching_overload(op_name, &[resolved_operand], range);
+                                ctx.report_no_matching_overload(op_name, &[resolved_operand], &[operand_ty], range);
                                 let ty_var = ctx.fresh_var();
                                 ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
                             }
@@ -930,7 +973,13 @@ pub(super) fn generate_symbol_constraints(
                     // Try user-defined function first (via HIR reference resolution)
                     if let Some(def_id) = hir.resolve_reference_symbol(symbol_id) {
                         let def_symbol = hir.symbol(def_id);
-                        let is_user_defined = def_symbol.map(|s| !hir.is_builtin_symbol(s)).unwrap_or(false);
+                        // A declared signature takes the place of a top-level definition, or of one
+                        // from another source such as an included module, and is resolved like a builtin.
+                        let is_user_defined = def_symbol.is_some_and(|s| {
+                            let declared = ctx.is_declared(func_name)
+                                && (s.parent.is_none() || s.source.source_id != call_symbol.source.source_id);
+                            !hir.is_builtin_symbol(s) && !declared
+                        });
 
                         if is_user_defined {
                             // When `def_id` is a Variable holding a lambda (e.g. `let f = fn(x): x - 1;`),
@@ -2097,10 +2146,26 @@ pub(super) fn generate_symbol_constraints(
             }
         }
 
-        // QualifiedAccess doesn't need deep type checking
+        // Qualified calls (`module::function(...)`) are only checked against declared signatures
         SymbolKind::QualifiedAccess => {
-            let ty_var = ctx.fresh_var();
-            ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
+            match qualified_function_name(hir, symbol_id, children_index).filter(|name| ctx.is_declared(name)) {
+                Some(func_name) => {
+                    // The first child is the function reference; the rest are arguments
+                    let explicit_arg_tys: Vec<Type> = get_non_keyword_children(hir, symbol_id, children_index)
+                        .iter()
+                        .skip(1)
+                        .map(|&arg_id| ctx.get_or_create_symbol_type(arg_id))
+                        .collect();
+                    let range = get_symbol_range(hir, symbol_id);
+                    let arg_tys = build_piped_call_args(ctx, symbol_id, &explicit_arg_tys, &func_name);
+                    let defer = might_receive_piped_input(hir, symbol_id) || is_inside_quote_block(hir, symbol_id);
+                    resolve_builtin_call(ctx, symbol_id, &func_name, &arg_tys, range, defer);
+                }
+                None => {
+                    let ty_var = ctx.fresh_var();
+                    ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
+                }
+            }
         }
 
         // `break: value` carries the type of its value expression.
@@ -244,6 +244,33 @@ pub(super) fn is_inside_quote_block(hir: &Hir, symbol_id: SymbolId) -> bool {
     }
 }
 
+/// Returns the `module::function` name of a qualified access such as `csv::csv_parse(...)`.
+///
+/// The module is named after the module it resolves to, so `import "csv" as c` followed by
+/// `c::csv_parse(...)` is still `csv::csv_parse`. The name as written is used otherwise.
+pub(super) fn qualified_function_name(
+    hir: &Hir,
+    symbol_id: SymbolId,
+    children_index: &ChildrenIndex,
+) -> Option<String> {
+    let symbol = hir.symbol(symbol_id)?;
+    let function_id = *get_children(children_index, symbol_id).first()?;
+    let function = hir.symbol(function_id)?;
+
+    let module_name = hir
+        .resolve_reference_symbol(function_id)
+        .and_then(|def_id| hir.symbol(def_id)?.source.source_id)
+        .and_then(|source_id| {
+            hir.symbols().find_map(|(_, module)| match module.kind {
+                SymbolKind::Import(id) | SymbolKind::Module(id) if id == source_id => module.value.clone(),
+                _ => None,
+            })
+        })
+        .or_else(|| symbol.value.clone())?;
+
+    Some(format!("{module_name}::{}", function.value.as_deref()?))
+}
+
 /// Builds the argument type list for a piped builtin function call.
 ///
 /// When a function is called via pipe (e.g., `arr | join(",")`) the piped value
@@ -326,7 +353,7 @@ pub(super) fn resolve_builtin_call(
             ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
         }
     } else if is_builtin && !defer_error {
-        ctx.report_no_matching_overload(func_name, &resolved_arg_tys, range);
+        ctx.report_no_matching_overload(func_name, &resolved_arg_tys, arg_tys, range);
         let ty_var = ctx.fresh_var();
         ctx.set_symbol_type(symbol_id, Type::Var(ty_var));
     } else {
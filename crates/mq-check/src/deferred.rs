@@ -561,6 +561,7 @@ pub(crate) fn resolve_deferred_overloads(ctx: &mut InferenceContext) {
                                 bad_member.display_renumbered(),
                                 d.op_name
                             )),
+                            origins: ctx.type_origins_outside(&d.operand_tys, d.range),
                         });
                         continue;
                     }
@@ -576,6 +577,7 @@ pub(crate) fn resolve_deferred_overloads(ctx: &mut InferenceContext) {
                             span: d.range.as_ref().map(unify::range_to_span),
                             location: d.range,
                             context: None,
+                            origins: ctx.type_origins_outside(&d.operand_tys, d.range),
                         });
                         continue;
                     }
@@ -594,7 +596,7 @@ pub(crate) fn resolve_deferred_overloads(ctx: &mut InferenceContext) {
                 let overload_count = ctx.get_builtin_overloads(&d.op_name).map(|o| o.len()).unwrap_or(0);
                 if overload_count > 1 {
                     if ctx.resolve_overload(&d.op_name, &resolved_operands).is_none() {
-                        ctx.report_no_matching_overload(&d.op_name, &resolved_operands, d.range);
+                        ctx.report_no_matching_overload(&d.op_name, &resolved_operands, &d.operand_tys, d.range);
                     } else {
                         next_remaining.push(idx);
                     }
@@ -619,7 +621,7 @@ pub(crate) fn resolve_deferred_overloads(ctx: &mut InferenceContext) {
                     unify::solve_constraints(ctx);
                 }
             } else if all_concrete {
-                ctx.report_no_matching_overload(&d.op_name, &resolved_operands, d.range);
+                ctx.report_no_matching_overload(&d.op_name, &resolved_operands, &d.operand_tys, d.range);
             } else {
                 // Some operands resolved but no match — defer to next pass
                 next_remaining.push(idx);
@@ -662,7 +664,7 @@ pub(crate) fn resolve_deferred_overloads(ctx: &mut InferenceContext) {
                 } else {
                     let all_concrete = resolved_operands.iter().all(|ty| ty.is_concrete());
                     if all_concrete {
-                        ctx.report_no_matching_overload(&d.op_name, &resolved_operands, d.range);
+                        ctx.report_no_matching_overload(&d.op_name, &resolved_operands, &d.operand_tys, d.range);
                     } else {
                         // Still unresolved — store back for later processing
                         store_back_for_later.push(idx);
@@ -936,7 +938,7 @@ fn check_deferred_overloads_iteratively(
                 made_progress = true;
             } else {
                 // No matching overload — report error
-                ctx.report_no_matching_overload(&d.op_name, &substituted_operands, error_range);
+                ctx.report_no_matching_overload(&d.op_name, &substituted_operands, &d.operand_tys, error_range);
                 made_progress = true;
             }
         }
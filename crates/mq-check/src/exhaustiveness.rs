@@ -1,7 +1,8 @@
-//! Exhaustiveness checking for pattern match expressions.
+//! Exhaustiveness and reachability checking for pattern match expressions.
 //!
 //! After type inference resolves the type of the matched expression, this module
-//! verifies that all possible values are covered by at least one unconditional arm.
+//! verifies that all possible values are covered by at least one unconditional arm,
+//! and that every arm can be reached.
 //!
 //! # Coverage rules
 //!
@@ -9,18 +10,32 @@
 //! |---|---|
 //! | `Bool` | wildcard/var arm (no guard) **or** both `true` and `false` arms (no guard) |
 //! | `None` | wildcard/var arm (no guard) **or** `none` arm (no guard) |
-//! | other concrete types | wildcard/var arm (no guard) |
+//! | other concrete types | wildcard/var arm (no guard) **or** a type-label arm such as `:string:` (no guard) |
 //! | `Union(ts)` | every member type is individually covered |
 //! | `Var` / unknown | skip — cannot statically determine coverage |
+//!
+//! # Unreachable arms
+//!
+//! An arm is unreachable when preceding unconditional arms already cover every value of
+//! the matched type, when it repeats the pattern of a preceding unconditional arm, or when
+//! its pattern can never match a value of the matched type.
 
 use mq_hir::{Hir, SymbolId, SymbolKind};
 
 use crate::TypeError;
 use crate::constraint::{ChildrenIndex, get_children};
 use crate::infer::InferenceContext;
+use crate::narrowing::{literal_symbol_type, type_name_to_type};
 use crate::types::Type;
 use crate::unify::range_to_span;
 
+/// Type names accepted by type-label patterns such as `:string:`.
+///
+/// Node-kind labels (`:h1`, `:code`) are not included: they match only some markdown nodes.
+const TYPE_LABELS: &[&str] = &[
+    "string", "number", "bool", "none", "symbol", "markdown", "bytes", "array", "dict",
+];
+
 /// Classifies what values a single match arm pattern covers.
 #[derive(Debug, Clone, PartialEq)]
 enum PatternKind {
@@ -28,29 +43,115 @@ enum PatternKind {
     Wildcard,
     /// Variable binding `x` — has a `PatternVariable` child, no literal child.
     VarBinding,
+    /// Array or dict pattern without nested patterns, e.g. `[]` or `{a}`.
+    ///
+    /// Its shape is not analyzed, so it counts as covering for exhaustiveness but never
+    /// makes later arms unreachable.
+    Destructure,
     /// Literal `true`.
     BoolTrue,
     /// Literal `false`.
     BoolFalse,
     /// Literal `none`.
     NoneVal,
+    /// Type-label pattern such as `:string:`, covering every value of the type.
+    TypeLabel(Type),
     /// Any other specific literal (number, string, symbol, array, dict).
-    Specific,
+    ///
+    /// Holds the type of a scalar literal and its source text, used to detect arms that
+    /// can never match or repeat a preceding literal.
+    Specific(Option<(Type, String)>),
+}
+
+impl PatternKind {
+    /// Returns the type of values this pattern can match, if it is restricted to one.
+    fn pattern_type(&self) -> Option<&Type> {
+        match self {
+            PatternKind::BoolTrue | PatternKind::BoolFalse => Some(&Type::Bool),
+            PatternKind::NoneVal => Some(&Type::None),
+            PatternKind::TypeLabel(ty) | PatternKind::Specific(Some((ty, _))) => Some(ty),
+            PatternKind::Wildcard
+            | PatternKind::VarBinding
+            | PatternKind::Destructure
+            | PatternKind::Specific(None) => None,
+        }
+    }
+
+    /// Returns `true` if this pattern matches no value that `earlier` does not already match.
+    fn is_subsumed_by(&self, earlier: &PatternKind) -> bool {
+        match (self, earlier) {
+            (PatternKind::TypeLabel(t1), PatternKind::TypeLabel(t2)) => {
+                std::mem::discriminant(t1) == std::mem::discriminant(t2)
+            }
+            (PatternKind::Specific(Some((t1, v1))), PatternKind::Specific(Some((t2, v2)))) => {
+                std::mem::discriminant(t1) == std::mem::discriminant(t2) && v1 == v2
+            }
+            (PatternKind::Specific(Some((ty, _))), PatternKind::TypeLabel(label)) => {
+                std::mem::discriminant(ty) == std::mem::discriminant(label)
+            }
+            (PatternKind::BoolTrue | PatternKind::BoolFalse, PatternKind::TypeLabel(Type::Bool))
+            | (PatternKind::NoneVal, PatternKind::TypeLabel(Type::None)) => true,
+            (PatternKind::Specific(None) | PatternKind::Destructure, _)
+            | (PatternKind::Wildcard | PatternKind::VarBinding, _) => false,
+            _ => self == earlier,
+        }
+    }
+
+    /// Describes the pattern for diagnostics.
+    fn describe(&self) -> String {
+        match self {
+            PatternKind::Wildcard => "_".to_string(),
+            PatternKind::VarBinding => "variable binding".to_string(),
+            PatternKind::Destructure | PatternKind::Specific(None) => "pattern".to_string(),
+            PatternKind::BoolTrue => "true".to_string(),
+            PatternKind::BoolFalse => "false".to_string(),
+            PatternKind::NoneVal => "none".to_string(),
+            PatternKind::TypeLabel(ty) => format!(":{}:", type_label_name(ty)),
+            PatternKind::Specific(Some((Type::String, value))) => format!("{value:?}"),
+            PatternKind::Specific(Some((Type::Symbol, value))) => format!(":{value}"),
+            PatternKind::Specific(Some((_, value))) => value.clone(),
+        }
+    }
+}
+
+/// Returns the type-label name for `ty`, e.g. `array` for `[number]`.
+fn type_label_name(ty: &Type) -> String {
+    match ty {
+        Type::Array(_) | Type::Tuple(_) => "array".to_string(),
+        Type::Dict(..) | Type::Record(..) => "dict".to_string(),
+        other => other.to_string(),
+    }
 }
 
 /// Information about a single match arm relevant for exhaustiveness.
 #[derive(Debug)]
 struct ArmInfo {
+    id: SymbolId,
     pattern: PatternKind,
     has_guard: bool,
 }
 
 /// Inspects the `Pattern` child of a `MatchArm` and returns a `PatternKind`.
-fn classify_pattern(hir: &Hir, pattern_id: SymbolId, children_index: &ChildrenIndex) -> PatternKind {
+fn classify_pattern(
+    hir: &Hir,
+    pattern_id: SymbolId,
+    ctx: &mut InferenceContext,
+    children_index: &ChildrenIndex,
+) -> PatternKind {
     let children = get_children(children_index, pattern_id);
+    let (is_wildcard, is_dict) = hir.symbol(pattern_id).map_or((true, false), |s| {
+        (
+            s.value.as_deref() == Some("_"),
+            matches!(s.kind, SymbolKind::Pattern { is_dict: true }),
+        )
+    });
 
     if children.is_empty() {
-        return PatternKind::Wildcard;
+        return if is_wildcard && !is_dict {
+            PatternKind::Wildcard
+        } else {
+            PatternKind::Destructure
+        };
     }
 
     let mut has_pattern_var = false;
@@ -66,12 +167,31 @@ fn classify_pattern(hir: &Hir, pattern_id: SymbolId, children_index: &ChildrenIn
                     }
                 }
                 SymbolKind::None => return PatternKind::NoneVal,
-                SymbolKind::Number | SymbolKind::String | SymbolKind::Symbol | SymbolKind::Array => {
-                    return PatternKind::Specific;
+                SymbolKind::Symbol => {
+                    let name = sym.value.as_deref().unwrap_or("");
+                    if TYPE_LABELS.contains(&name)
+                        && let Some(ty) = type_name_to_type(name, ctx)
+                    {
+                        return PatternKind::TypeLabel(ty);
+                    }
+                    // Node-kind labels (`:h1`) narrow to markdown but only match some nodes.
+                    if type_name_to_type(name, ctx).is_some() {
+                        return PatternKind::Specific(None);
+                    }
+                    return PatternKind::Specific(Some((Type::Symbol, name.to_string())));
+                }
+                SymbolKind::Number | SymbolKind::String => {
+                    return PatternKind::Specific(
+                        literal_symbol_type(hir, child_id).zip(sym.value.as_ref().map(|v| v.to_string())),
+                    );
                 }
-                SymbolKind::Pattern { .. } => {
-                    // Nested array/dict pattern — specific structure.
-                    return PatternKind::Specific;
+                SymbolKind::Array | SymbolKind::Pattern { .. } => {
+                    // Array literal or nested array/dict pattern — specific structure.
+                    return PatternKind::Specific(None);
+                }
+                // `none` is lowered as a binding named `none`, but matches only `None`.
+                SymbolKind::PatternVariable { .. } if sym.value.as_deref() == Some("none") => {
+                    return PatternKind::NoneVal;
                 }
                 SymbolKind::PatternVariable { .. } => {
                     has_pattern_var = true;
@@ -81,11 +201,11 @@ fn classify_pattern(hir: &Hir, pattern_id: SymbolId, children_index: &ChildrenIn
         }
     }
 
-    if has_pattern_var {
+    if has_pattern_var && !is_dict {
         PatternKind::VarBinding
     } else {
-        // Fallback: treat as wildcard (e.g. dict/array shorthand bindings).
-        PatternKind::Wildcard
+        // Dict shorthand bindings (`{a}`) and other unrecognized shapes.
+        PatternKind::Destructure
     }
 }
 
@@ -94,16 +214,45 @@ fn is_catch_all(arm: &ArmInfo) -> bool {
     !arm.has_guard && matches!(arm.pattern, PatternKind::Wildcard | PatternKind::VarBinding)
 }
 
+/// Returns `true` if an unguarded type-label arm covers every value of `ty`.
+fn has_type_label_for(ty: &Type, arms: &[&ArmInfo]) -> bool {
+    arms.iter().any(|arm| {
+        !arm.has_guard
+            && matches!(&arm.pattern, PatternKind::TypeLabel(label)
+                if std::mem::discriminant(label) == std::mem::discriminant(ty)
+                    || matches!((label, ty), (Type::Array(_), Type::Tuple(_)) | (Type::Dict(..), Type::Record(..))))
+    })
+}
+
 /// Returns `Some(missing)` if the match on `ty` is non-exhaustive, `None` if exhaustive.
 ///
 /// `missing` is a human-readable description of the uncovered case(s).
-fn missing_cases(ty: &Type, arms: &[ArmInfo]) -> Option<String> {
+fn missing_cases(ty: &Type, arms: &[&ArmInfo]) -> Option<String> {
     // A catch-all arm always makes the match exhaustive.
-    if arms.iter().any(is_catch_all) {
+    if arms
+        .iter()
+        .any(|arm| is_catch_all(arm) || (!arm.has_guard && arm.pattern == PatternKind::Destructure))
+    {
         return None;
     }
 
     match ty {
+        // For type variables, we cannot statically determine exhaustiveness.
+        Type::Var(_) => None,
+        Type::Union(types) => {
+            let mut missing: Vec<String> = Vec::new();
+            for member in types {
+                if let Some(m) = missing_cases(member, arms) {
+                    missing.push(m);
+                }
+            }
+            if missing.is_empty() {
+                None
+            } else {
+                Some(missing.join(", "))
+            }
+        }
+        _ if has_type_label_for(ty, arms) => None,
         Type::Bool => {
             let has_true = arms.iter().any(|a| !a.has_guard && a.pattern == PatternKind::BoolTrue);
             let has_false = arms.iter().any(|a| !a.has_guard && a.pattern == PatternKind::BoolFalse);
@@ -118,28 +267,75 @@ fn missing_cases(ty: &Type, arms: &[ArmInfo]) -> Option<String> {
             let has_none = arms.iter().any(|a| !a.has_guard && a.pattern == PatternKind::NoneVal);
             if has_none { None } else { Some("none".to_string()) }
         }
-        Type::Union(types) => {
-            let mut missing: Vec<String> = Vec::new();
-            for member in types {
-                if let Some(m) = missing_cases(member, arms) {
-                    missing.push(m);
-                }
-            }
-            if missing.is_empty() {
-                None
-            } else {
-                Some(missing.join(", "))
-            }
-        }
-        // For type variables, we cannot statically determine exhaustiveness.
-        Type::Var(_) => None,
-        // For all other concrete types, only a catch-all covers them.
-        // (Already handled above by the catch-all check.)
+        // For all other concrete types, only a catch-all or type label covers them.
         other => Some(format!("{}", other)),
     }
 }
 
-/// Checks all match expressions in the HIR for exhaustiveness and returns a list of errors.
+/// Returns the reason `arm` can never be reached, given the arms that precede it.
+fn unreachable_reason(ty: &Type, arm: &ArmInfo, preceding: &[ArmInfo]) -> Option<String> {
+    if preceding.iter().any(is_catch_all) {
+        return Some("match arm is unreachable: a preceding arm matches every value".to_string());
+    }
+
+    // Coverage is only known when the matched type is fully inferred. Destructuring arms
+    // are left out: their shapes are not analyzed, so they cannot prove coverage.
+    let known = ty.is_concrete() && !ty.is_dynamic();
+    let analyzed: Vec<&ArmInfo> = preceding
+        .iter()
+        .filter(|arm| arm.pattern != PatternKind::Destructure)
+        .collect();
+    if known && !analyzed.is_empty() && missing_cases(ty, &analyzed).is_none() {
+        return Some(format!(
+            "match arm is unreachable: preceding arms cover every value of type {ty}"
+        ));
+    }
+
+    if let Some(earlier) = preceding
+        .iter()
+        .find(|earlier| !earlier.has_guard && arm.pattern.is_subsumed_by(&earlier.pattern))
+    {
+        return Some(format!(
+            "match arm is unreachable: pattern `{}` is already matched by `{}`",
+            arm.pattern.describe(),
+            earlier.pattern.describe()
+        ));
+    }
+
+    if known
+        && let Some(pattern_ty) = arm.pattern.pattern_type()
+        && !pattern_ty.can_match(ty)
+    {
+        return Some(format!(
+            "match arm is unreachable: pattern `{}` can never match a value of type {ty}",
+            arm.pattern.describe()
+        ));
+    }
+
+    None
+}
+
+/// Returns the range spanning the pattern, guard, and body of a match arm.
+///
+/// The `MatchArm` symbol itself carries no usable range, and neither does a `_` pattern.
+fn arm_range(hir: &Hir, arm_id: SymbolId, children_index: &ChildrenIndex) -> Option<mq_lang::Range> {
+    let ranges: Vec<mq_lang::Range> = get_children(children_index, arm_id)
+        .iter()
+        .filter_map(|&id| hir.symbol(id).and_then(|s| s.source.text_range))
+        .filter(|range| *range != mq_lang::Range::default())
+        .collect();
+
+    Some(mq_lang::Range {
+        start: ranges.iter().map(|r| r.start).min()?,
+        end: ranges.iter().map(|r| r.end).max()?,
+    })
+}
+
+/// Checks all match expressions in the HIR and returns a list of errors.
+///
+/// Reports an [`TypeError::UnreachableCode`] for every arm that can never be reached and,
+/// when `check_exhaustive` is true, a [`TypeError::NonExhaustiveMatch`] for every match
+/// that does not cover its matched type.
 ///
 /// `children_index` is passed in to avoid rebuilding it — it is already constructed
 /// by `generate_constraints` and reused here to save an O(N) full-HIR scan.
@@ -147,6 +343,7 @@ pub(crate) fn check_match_exhaustiveness(
     hir: &Hir,
     ctx: &mut InferenceContext,
     children_index: &ChildrenIndex,
+    check_exhaustive: bool,
 ) -> Vec<TypeError> {
     let mut errors = Vec::new();
 
@@ -176,27 +373,38 @@ pub(crate) fn check_match_exhaustiveness(
 
                 // Find the Pattern child of this arm.
                 let arm_children = get_children(children_index, arm_id);
-                let pattern_kind = arm_children
-                    .iter()
-                    .find_map(|&child_id| {
-                        hir.symbol(child_id).and_then(|s| {
-                            if matches!(s.kind, SymbolKind::Pattern { .. }) {
-                                Some(classify_pattern(hir, child_id, children_index))
-                            } else {
-                                None
-                            }
-                        })
-                    })
+                let pattern_id = arm_children.iter().copied().find(|&child_id| {
+                    hir.symbol(child_id)
+                        .is_some_and(|s| matches!(s.kind, SymbolKind::Pattern { .. }))
+                });
+                let pattern_kind = pattern_id
+                    .map(|id| classify_pattern(hir, id, ctx, children_index))
                     .unwrap_or(PatternKind::Wildcard);
 
                 arms.push(ArmInfo {
+                    id: arm_id,
                     pattern: pattern_kind,
                     has_guard,
                 });
             }
         }
 
-        if let Some(missing) = missing_cases(&match_ty, &arms) {
+        for (i, arm) in arms.iter().enumerate() {
+            if let Some(reason) = unreachable_reason(&match_ty, arm, &arms[..i]) {
+                let range = arm_range(hir, arm.id, children_index);
+                errors.push(TypeError::UnreachableCode {
+                    reason,
+                    span: range.as_ref().map(range_to_span),
+                    location: range,
+                });
+            }
+        }
+
+        if !check_exhaustive {
+            continue;
+        }
+
+        if let Some(missing) = missing_cases(&match_ty, &arms.iter().collect::<Vec<_>>()) {
             let range = hir.symbol(match_id).and_then(|s| s.source.text_range);
             errors.push(TypeError::NonExhaustiveMatch {
                 missing: missing.clone(),
@@ -5,8 +5,10 @@ mod sarif;
 
 use std::io::{self, Write};
 
-use mq_check::TypeError;
-use mq_hir::{Hir, HirError, HirWarning};
+use miette::Diagnostic;
+use mq_check::signature::Signatures;
+use mq_check::{Strictness, TypeError};
+use mq_hir::{Hir, HirError, HirWarning, SymbolKind};
 
 /// Severity of a check diagnostic.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -46,16 +48,38 @@ pub(crate) enum OutputFormat {
     Sarif,
 }
 
+/// Returns the errors on `hir`, except unresolved references to functions declared in
+/// `signatures`, which are typically registered by a host application.
+pub(crate) fn hir_errors(hir: &Hir, signatures: &Signatures) -> Vec<HirError> {
+    hir.errors()
+        .into_iter()
+        .filter(|error| {
+            !matches!(error, HirError::UnresolvedSymbol { symbol, .. }
+                if symbol.value.as_deref().is_some_and(|name| signatures.declares(name)))
+        })
+        .collect()
+}
+
 /// Returns the syntax errors and warnings on `hir` as [`CheckDiagnostic`]s.
-pub(crate) fn syntax_diagnostics(hir: &Hir) -> Vec<CheckDiagnostic> {
-    let mut diagnostics: Vec<CheckDiagnostic> = hir
-        .errors()
+pub(crate) fn syntax_diagnostics(hir: &Hir, signatures: &Signatures) -> Vec<CheckDiagnostic> {
+    let mut diagnostics: Vec<CheckDiagnostic> = hir_errors(hir, signatures)
         .iter()
-        .map(|error| CheckDiagnostic {
-            severity: Severity::Error,
-            code: hir_error_code(error),
-            message: error.to_string(),
-            range: Some(hir_error_range(error)),
+        .map(|error| match undefined_symbol_error(hir, error) {
+            Some(undefined) => CheckDiagnostic {
+                severity: Severity::Error,
+                code: type_error_code(&undefined),
+                message: match undefined.help() {
+                    Some(help) => format!("{undefined}; {help}"),
+                    None => undefined.to_string(),
+                },
+                range: undefined.location(),
+            },
+            None => CheckDiagnostic {
+                severity: Severity::Error,
+                code: hir_error_code(error),
+                message: error.to_string(),
+                range: Some(hir_error_range(error)),
+            },
         })
         .collect();
 
@@ -69,18 +93,41 @@ pub(crate) fn syntax_diagnostics(hir: &Hir) -> Vec<CheckDiagnostic> {
     diagnostics
 }
 
+/// Converts an unresolved reference into a [`TypeError::UndefinedSymbol`] that suggests the
+/// functions and variables defined in `hir` whose names are closest to it.
+pub(crate) fn undefined_symbol_error(hir: &Hir, error: &HirError) -> Option<TypeError> {
+    let HirError::UnresolvedSymbol { symbol, .. } = error else {
+        return None;
+    };
+    let name = symbol.value.as_deref()?;
+    let candidates: Vec<&str> = hir
+        .symbols()
+        .filter(|(_, symbol)| {
+            matches!(
+                symbol.kind,
+                SymbolKind::Function(_) | SymbolKind::Variable | SymbolKind::Parameter
+            )
+        })
+        .filter_map(|(_, symbol)| symbol.value.as_deref())
+        .collect();
+
+    Some(TypeError::undefined_symbol(name, symbol.source.text_range, &candidates))
+}
+
 /// Returns a stable rule code for a [`HirError`] variant.
 fn hir_error_code(error: &HirError) -> &'static str {
     match error {
         HirError::UnresolvedSymbol { .. } => "hir::unresolved_symbol",
         HirError::ModuleNotFound { .. } => "hir::module_not_found",
+        HirError::PrivateSymbol { .. } => "hir::private_symbol",
     }
 }
 
-fn hir_error_range(error: &HirError) -> mq_lang::Range {
+pub(crate) fn hir_error_range(error: &HirError) -> mq_lang::Range {
     match error {
         HirError::UnresolvedSymbol { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
         HirError::ModuleNotFound { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
+        HirError::PrivateSymbol { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
     }
 }
 
@@ -102,7 +149,11 @@ pub(crate) fn type_diagnostics(errors: &[TypeError]) -> Vec<CheckDiagnostic> {
     errors
         .iter()
         .map(|error| CheckDiagnostic {
-            severity: Severity::Error,
+            severity: if is_warning(error) {
+                Severity::Warning
+            } else {
+                Severity::Error
+            },
             code: type_error_code(error),
             message: error.to_string(),
             range: error.location(),
@@ -116,7 +167,7 @@ fn type_error_code(error: &TypeError) -> &'static str {
         TypeError::Mismatch { .. } => "typechecker::type_mismatch",
         TypeError::UnificationError { .. } => "typechecker::unification_error",
         TypeError::OccursCheck { .. } => "typechecker::occurs_check",
-        TypeError::UndefinedSymbol { .. } => "typechecker::undefined_symbol",
+        TypeError::UndefinedSymbol { .. } => "hir::unresolved_symbol",
         TypeError::WrongArity { .. } => "typechecker::wrong_arity",
         TypeError::UndefinedField { .. } => "typechecker::undefined_field",
         TypeError::HeterogeneousArray { .. } => "typechecker::heterogeneous_array",
@@ -125,9 +176,21 @@ fn type_error_code(error: &TypeError) -> &'static str {
         TypeError::NullablePropagation { .. } => "typechecker::nullable_propagation",
         TypeError::UnreachableCode { .. } => "typechecker::unreachable_code",
         TypeError::NonExhaustiveMatch { .. } => "typechecker::non_exhaustive_patterns",
+        TypeError::DynamicConstruct { .. } => "typechecker::dynamic_construct",
     }
 }
 
+/// Returns `true` for type errors reported as warnings, which do not fail the check.
+pub(crate) fn is_warning(error: &TypeError) -> bool {
+    matches!(
+        error,
+        TypeError::DynamicConstruct {
+            strictness: Strictness::Warn,
+            ..
+        }
+    )
+}
+
 /// Dispatches to the writer for the requested machine-readable output format.
 ///
 /// Must not be called with [`OutputFormat::Text`], which is rendered separately.
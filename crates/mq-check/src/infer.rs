@@ -2,11 +2,14 @@
 
 use crate::constraint::{Constraint, ConstraintOrigin};
 use crate::types::{Substitution, Type, TypeScheme, TypeVarContext, TypeVarId, format_type_list};
-use crate::{TypeEnv, TypeError, unify};
+use crate::{TypeEnv, TypeError, TypeOrigin, unify};
 use mq_hir::SymbolId;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use smol_str::SmolStr;
 
+/// Maximum number of origins attached to a single type error.
+pub const MAX_ORIGINS: usize = 3;
+
 /// A deferred overload resolution for operators with unresolved type variable operands.
 ///
 /// When a binary or unary operator's operands are still type variables during constraint
@@ -206,8 +209,12 @@ pub struct InferenceContext {
     constraints: Vec<Constraint>,
     /// Type variable substitutions (unified types)
     substitutions: FxHashMap<TypeVarId, Type>,
+    /// The range and origin of the constraint that bound each type variable
+    binding_origins: FxHashMap<TypeVarId, (mq_lang::Range, ConstraintOrigin)>,
     /// Builtin function/operator type signatures (can have multiple overloads)
     builtins: FxHashMap<SmolStr, Vec<Type>>,
+    /// Names whose overloads were declared by a signature file
+    declared: FxHashSet<SmolStr>,
     /// Collected type errors (for non-fatal error reporting)
     errors: Vec<TypeError>,
     /// Piped input types for symbols in a pipe chain
@@ -250,7 +257,9 @@ impl InferenceContext {
             symbol_types: FxHashMap::default(),
             constraints: Vec::new(),
             substitutions: FxHashMap::default(),
+            binding_origins: FxHashMap::default(),
             builtins: FxHashMap::default(),
+            declared: FxHashSet::default(),
             errors: Vec::new(),
             piped_inputs: FxHashMap::default(),
             deferred_overloads: FxHashMap::default(),
@@ -277,6 +286,17 @@ impl InferenceContext {
         self.builtins.entry(SmolStr::new(name)).or_default().push(ty);
     }
 
+    /// Declares the overloads of a function from a signature file, replacing any builtin ones
+    pub fn declare_function(&mut self, name: &str, overloads: Vec<Type>) {
+        self.declared.insert(SmolStr::new(name));
+        self.builtins.insert(SmolStr::new(name), overloads);
+    }
+
+    /// Returns whether the overloads of `name` were declared by a signature file
+    pub fn is_declared(&self, name: &str) -> bool {
+        self.declared.contains(name)
+    }
+
     /// Gets all overloaded types for a builtin function or operator
     pub fn get_builtin_overloads(&self, name: &str) -> Option<&[Type]> {
         self.builtins.get(name).map(|v| v.as_slice())
@@ -292,6 +312,34 @@ impl InferenceContext {
         std::mem::take(&mut self.errors)
     }
 
+    /// Returns the number of errors collected so far
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Adds `origins` to the mismatch errors collected since `error_count` was `since`,
+    /// unless they already explain their types.
+    pub fn attach_origins(&mut self, since: usize, origins: &[TypeOrigin]) {
+        if origins.is_empty() {
+            return;
+        }
+        for error in self.errors.iter_mut().skip(since) {
+            if let TypeError::Mismatch {
+                origins: error_origins,
+                location,
+                ..
+            } = error
+                && error_origins.is_empty()
+            {
+                *error_origins = origins
+                    .iter()
+                    .filter(|origin| !location.is_some_and(|range| contains(&range, &origin.location)))
+                    .cloned()
+                    .collect();
+            }
+        }
+    }
+
     /// Sets the piped input type for a symbol
     pub fn set_piped_input(&mut self, symbol: SymbolId, ty: Type) {
         self.piped_inputs.insert(symbol, ty);
@@ -424,6 +472,7 @@ impl InferenceContext {
             span: range.as_ref().map(unify::range_to_span),
             location: range,
             context: origin.to_context_string(),
+            origins: Vec::new(),
         });
     }
 
@@ -460,15 +509,26 @@ impl InferenceContext {
     }
 
     /// Reports a "no matching overload" error with formatted argument types.
-    pub fn report_no_matching_overload(&mut self, op_name: &str, arg_tys: &[Type], range: Option<mq_lang::Range>) {
+    ///
+    /// `operand_tys` are the argument types before resolution; the constraints that bound
+    /// their type variables are reported as the origins of the argument types.
+    pub fn report_no_matching_overload(
+        &mut self,
+        op_name: &str,
+        arg_tys: &[Type],
+        operand_tys: &[Type],
+        range: Option<mq_lang::Range>,
+    ) {
         let args_str = format_type_list(arg_tys);
         let context = self.format_available_overloads(op_name);
+        let origins = self.type_origins_outside(operand_tys, range);
         self.add_error(TypeError::UnificationError {
             left: format!("{} with arguments ({})", op_name, args_str),
             right: "no matching overload".to_string(),
             span: range.as_ref().map(unify::range_to_span),
             location: range,
             context,
+            origins,
         });
     }
 
@@ -599,6 +659,65 @@ impl InferenceContext {
         self.substitutions.insert(var, ty);
     }
 
+    /// Records the constraint that bound `var`, keeping the first one recorded
+    pub fn record_binding_origin(&mut self, var: TypeVarId, range: Option<mq_lang::Range>, origin: &ConstraintOrigin) {
+        if let Some(range) = range {
+            self.binding_origins
+                .entry(var)
+                .or_insert_with(|| (range, origin.clone()));
+        }
+    }
+
+    /// Returns where the type variables in `tys` got their types, following each chain of
+    /// variable bindings to its end, at most [`MAX_ORIGINS`] in total.
+    pub fn type_origins(&self, tys: &[Type]) -> Vec<TypeOrigin> {
+        let mut origins: Vec<TypeOrigin> = Vec::new();
+
+        for ty in tys {
+            // The last binding in the chain is where the type was actually determined
+            let mut visited = FxHashSet::default();
+            let mut current = ty.clone();
+            let mut deepest = None;
+            while let Type::Var(var) = current {
+                if !visited.insert(var) {
+                    break;
+                }
+                if let Some(binding) = self.binding_origins.get(&var) {
+                    deepest = Some((var, binding));
+                }
+                let Some(next) = self.substitutions.get(&var) else {
+                    break;
+                };
+                current = next.clone();
+            }
+
+            if let Some((var, (range, origin))) = deepest
+                && !origins.iter().any(|o| o.location == *range)
+            {
+                let resolved = self.resolve_type(&Type::Var(var)).display_renumbered();
+                origins.push(TypeOrigin {
+                    message: match origin.to_context_string() {
+                        Some(context) => format!("{resolved} inferred here, {context}"),
+                        None => format!("{resolved} inferred here"),
+                    },
+                    location: *range,
+                });
+            }
+        }
+
+        origins.truncate(MAX_ORIGINS);
+        origins
+    }
+
+    /// Like [`Self::type_origins`], leaving out origins inside `range`, which the error
+    /// location already points at.
+    pub fn type_origins_outside(&self, tys: &[Type], range: Option<mq_lang::Range>) -> Vec<TypeOrigin> {
+        self.type_origins(tys)
+            .into_iter()
+            .filter(|origin| !range.is_some_and(|range| contains(&range, &origin.location)))
+            .collect()
+    }
+
     /// Gets the bound type for a type variable
     pub fn get_type_var(&self, var: TypeVarId) -> Option<Type> {
         self.substitutions.get(&var).cloned()
@@ -678,6 +797,11 @@ impl InferenceContext {
     }
 }
 
+/// Returns true if `inner` lies within `outer`.
+fn contains(outer: &mq_lang::Range, inner: &mq_lang::Range) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
 impl Default for InferenceContext {
     fn default() -> Self {
         Self::new()
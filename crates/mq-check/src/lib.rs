@@ -30,12 +30,18 @@ pub(crate) mod deferred;
 pub(crate) mod exhaustiveness;
 pub mod infer;
 pub mod narrowing;
+pub mod signature;
+pub(crate) mod strictness;
+pub(crate) mod suggest;
 pub mod types;
 pub mod unify;
 
 use miette::Diagnostic;
-use mq_hir::{Hir, SymbolId};
+use mq_hir::{Hir, SourceId, SymbolId};
 use rustc_hash::FxHashMap;
+use signature::Signatures;
+use std::fmt;
+use std::str::FromStr;
 use thiserror::Error;
 use types::TypeScheme;
 
@@ -92,6 +98,8 @@ pub enum TypeError {
         location: Option<mq_lang::Range>,
         #[help]
         context: Option<String>,
+        /// Where the mismatched types were inferred, following the constraint chain.
+        origins: Vec<TypeOrigin>,
     },
     #[error("Cannot unify types: {left} and {right}")]
     #[diagnostic(code(typechecker::unification_error))]
@@ -104,6 +112,8 @@ pub enum TypeError {
         location: Option<mq_lang::Range>,
         #[help]
         context: Option<String>,
+        /// Where the argument types were inferred, following the constraint chain.
+        origins: Vec<TypeOrigin>,
     },
     #[error("Occurs check failed: type variable {var} occurs in {ty}")]
     #[diagnostic(code(typechecker::occurs_check))]
@@ -115,14 +125,19 @@ pub enum TypeError {
         span: Option<miette::SourceSpan>,
         location: Option<mq_lang::Range>,
     },
+    /// An unresolved reference found by the HIR, reported under the HIR's error code.
     #[error("Undefined symbol: {name}")]
-    #[diagnostic(code(typechecker::undefined_symbol))]
+    #[diagnostic(code(hir::unresolved_symbol))]
     #[allow(dead_code)]
     UndefinedSymbol {
         name: String,
         #[label("undefined symbol")]
         span: Option<miette::SourceSpan>,
         location: Option<mq_lang::Range>,
+        /// Defined names close to `name`, closest first.
+        suggestions: Vec<String>,
+        #[help]
+        context: Option<String>,
     },
     #[error("Wrong number of arguments: expected {expected}, found {found}")]
     #[diagnostic(code(typechecker::wrong_arity))]
@@ -176,7 +191,8 @@ pub enum TypeError {
         context: Option<String>,
     },
     /// Emitted when a branch is statically determined to be unreachable based on
-    /// the known type of a variable and the type predicate in the condition.
+    /// the known type of a variable and the type predicate in the condition, or when
+    /// a match arm is covered by preceding arms or can never match the matched type.
     #[error("Unreachable code: this branch can never be executed")]
     #[diagnostic(code(typechecker::unreachable_code))]
     #[allow(dead_code)]
@@ -198,9 +214,62 @@ pub enum TypeError {
         #[help]
         context: Option<String>,
     },
+    /// Emitted for a construct that cannot be typed statically, such as dict access on a value
+    /// of unknown type or branching on `type()`, unless [`Strictness::Off`] is configured.
+    #[error("Dynamic construct: {construct}")]
+    #[diagnostic(code(typechecker::dynamic_construct))]
+    #[allow(dead_code)]
+    DynamicConstruct {
+        construct: String,
+        /// The strictness the construct was reported under.
+        strictness: Strictness,
+        #[label("not statically typed")]
+        span: Option<miette::SourceSpan>,
+        location: Option<mq_lang::Range>,
+        #[help]
+        context: Option<String>,
+    },
+}
+
+/// A source location that explains part of a [`TypeError`], such as where one of the
+/// mismatched types was inferred.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeOrigin {
+    pub message: String,
+    pub location: mq_lang::Range,
 }
 
 impl TypeError {
+    /// Creates a [`TypeError::UndefinedSymbol`] for `name`, suggesting the `candidates`
+    /// closest to it.
+    pub fn undefined_symbol<S: AsRef<str>>(name: &str, location: Option<mq_lang::Range>, candidates: &[S]) -> Self {
+        let suggestions = suggest::did_you_mean(name, candidates);
+        let context = match suggestions.as_slice() {
+            [] => None,
+            [only] => Some(format!("did you mean `{only}`?")),
+            several => Some(format!(
+                "did you mean one of {}?",
+                several.iter().map(|s| format!("`{s}`")).collect::<Vec<_>>().join(", ")
+            )),
+        };
+
+        TypeError::UndefinedSymbol {
+            name: name.to_string(),
+            span: location.as_ref().map(unify::range_to_span),
+            location,
+            suggestions,
+            context,
+        }
+    }
+
+    /// Returns the locations that explain how the types in this error were inferred.
+    pub fn origins(&self) -> &[TypeOrigin] {
+        match self {
+            TypeError::Mismatch { origins, .. } | TypeError::UnificationError { origins, .. } => origins,
+            _ => &[],
+        }
+    }
+
     /// Returns the location (range) of the error, if available.
     pub fn location(&self) -> Option<mq_lang::Range> {
         match self {
@@ -213,7 +282,8 @@ impl TypeError {
             | TypeError::HeterogeneousArray { location, .. }
             | TypeError::NullablePropagation { location, .. }
             | TypeError::UnreachableCode { location, .. }
-            | TypeError::NonExhaustiveMatch { location, .. } => *location,
+            | TypeError::NonExhaustiveMatch { location, .. }
+            | TypeError::DynamicConstruct { location, .. } => *location,
             _ => None,
         }
     }
@@ -242,6 +312,43 @@ pub(crate) fn walk_ancestors(
     })
 }
 
+/// How dynamic constructs that cannot be typed statically are reported.
+///
+/// See [`TypeError::DynamicConstruct`] for the constructs covered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Dynamic constructs are not reported.
+    #[default]
+    Off,
+    /// Dynamic constructs are reported as warnings.
+    Warn,
+    /// Dynamic constructs are reported as errors.
+    Strict,
+}
+
+impl fmt::Display for Strictness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Strictness::Off => write!(f, "off"),
+            Strictness::Warn => write!(f, "warn"),
+            Strictness::Strict => write!(f, "strict"),
+        }
+    }
+}
+
+impl FromStr for Strictness {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Strictness::Off),
+            "warn" => Ok(Strictness::Warn),
+            "strict" => Ok(Strictness::Strict),
+            _ => Err(format!("Unknown strictness: {s} (expected off, warn, or strict)")),
+        }
+    }
+}
+
 /// Options for configuring the type checker behavior
 #[derive(Debug, Clone, Copy, Default)]
 pub struct TypeCheckerOptions {
@@ -250,35 +357,61 @@ pub struct TypeCheckerOptions {
     pub strict_array: bool,
     /// When true, exhaustiveness checking for pattern match expressions is disabled.
     pub no_exhaustive_patterns: bool,
+    /// How dynamic constructs such as untyped dict access and `type()` branching are reported.
+    pub strictness: Strictness,
+}
+
+/// The inferred type of a symbol that has a source range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanType {
+    pub symbol_id: SymbolId,
+    pub source_id: SourceId,
+    pub range: mq_lang::Range,
+    pub type_scheme: TypeScheme,
 }
 
 /// Type checker for mq programs
 ///
 /// Provides type inference and checking capabilities based on HIR information.
+#[derive(Debug)]
 pub struct TypeChecker {
     /// Symbol type mappings
     symbol_types: TypeEnv,
+    /// Typed symbol ranges, ordered by source and start position
+    span_types: Vec<SpanType>,
     /// Type checker options
     options: TypeCheckerOptions,
+    /// Declared function signatures loaded from `.mqi` files
+    signatures: Signatures,
 }
 
 impl TypeChecker {
     /// Creates a new type checker with default options
     pub fn new() -> Self {
-        Self {
-            symbol_types: TypeEnv::default(),
-            options: TypeCheckerOptions::default(),
-        }
+        Self::with_options(TypeCheckerOptions::default())
     }
 
     /// Creates a new type checker with the given options
     pub fn with_options(options: TypeCheckerOptions) -> Self {
         Self {
             symbol_types: TypeEnv::default(),
+            span_types: Vec::new(),
             options,
+            signatures: Signatures::default(),
         }
     }
 
+    /// Uses the declared `signatures` instead of inferring the functions they name
+    ///
+    /// Calls to a declared function that is defined at the top level, in an included module, or not
+    /// defined in mq at all, are checked against its signature, and the bodies of top-level
+    /// definitions with a signature aren't checked. Declarations replace builtin signatures of the
+    /// same name.
+    pub fn with_signatures(mut self, signatures: Signatures) -> Self {
+        self.signatures = signatures;
+        self
+    }
+
     /// Runs type inference on the given HIR
     ///
     /// Returns a list of type errors found. An empty list means no errors.
@@ -287,6 +420,7 @@ impl TypeChecker {
         let mut ctx = infer::InferenceContext::with_options(self.options.strict_array);
 
         builtin::register_all(&mut ctx);
+        self.signatures.register(&mut ctx);
 
         // Generate constraints from HIR (collects errors internally).
         // Returns the children index so it can be reused by later passes.
@@ -308,14 +442,7 @@ impl TypeChecker {
         }
 
         // Apply cross-arm narrowings from match expressions.
-        let dead_arms = narrowing::resolve_cross_arm_narrowings(hir, &mut ctx);
-        for (reason, range) in dead_arms {
-            ctx.add_error(TypeError::UnreachableCode {
-                reason,
-                span: range.as_ref().map(unify::range_to_span),
-                location: range,
-            });
-        }
+        narrowing::resolve_cross_arm_narrowings(hir, &mut ctx);
 
         // Resolve deferred tuple index accesses now that variable types are known.
         if deferred::resolve_deferred_tuple_accesses(&mut ctx) {
@@ -365,11 +492,20 @@ impl TypeChecker {
         // global state, so multiple call sites don't interfere.
         deferred::check_user_call_body_operators(hir, &mut ctx);
 
-        // Check pattern match exhaustiveness (reuses the children index from constraint generation)
-        if !self.options.no_exhaustive_patterns {
-            for e in exhaustiveness::check_match_exhaustiveness(hir, &mut ctx, &children_index) {
-                ctx.add_error(e);
-            }
+        // Check pattern match exhaustiveness and unreachable arms (reuses the children index
+        // from constraint generation)
+        for e in exhaustiveness::check_match_exhaustiveness(
+            hir,
+            &mut ctx,
+            &children_index,
+            !self.options.no_exhaustive_patterns,
+        ) {
+            ctx.add_error(e);
+        }
+
+        // Report dynamic constructs according to the configured strictness
+        for e in strictness::check_dynamic_constructs(hir, &ctx, &children_index, self.options.strictness) {
+            ctx.add_error(e);
         }
 
         // Collect errors before finalizing
@@ -377,10 +513,20 @@ impl TypeChecker {
 
         // Store inferred types
         self.symbol_types = ctx.finalize();
+        self.span_types = span_types(hir, &self.symbol_types);
 
         errors
     }
 
+    /// Returns the inferred signatures of the functions that the module at `source_id` exports,
+    /// as the contents of a signature file (`.mqi`).
+    ///
+    /// Call after [`TypeChecker::check`]. Loading the result with [`Signatures::parse`] lets code
+    /// that includes the module be checked without inferring the module again.
+    pub fn declarations(&self, hir: &Hir, source_id: SourceId) -> String {
+        signature::format_declarations(hir, &self.symbol_types, source_id)
+    }
+
     /// Gets the type of a symbol
     pub fn type_of(&self, symbol: SymbolId) -> Option<&TypeScheme> {
         self.symbol_types.get(&symbol)
@@ -395,6 +541,43 @@ impl TypeChecker {
     pub fn symbol_type(&self, symbol_id: SymbolId) -> Option<&TypeScheme> {
         self.symbol_types.get(&symbol_id)
     }
+
+    /// Gets the inferred types of all symbols with a source range, ordered by source and start position.
+    pub fn types_by_span(&self) -> &[SpanType] {
+        &self.span_types
+    }
+
+    /// Gets the type of the innermost symbol whose range contains `position` in the given source.
+    pub fn type_at(&self, source_id: SourceId, position: mq_lang::Position) -> Option<&SpanType> {
+        self.span_types
+            .iter()
+            .filter(|span_type| span_type.source_id == source_id && span_type.range.contains(&position))
+            .min_by_key(|span_type| {
+                (
+                    span_type.range.end.line - span_type.range.start.line,
+                    span_type.range.end.column.abs_diff(span_type.range.start.column),
+                )
+            })
+    }
+}
+
+/// Collects the inferred types of the symbols in `hir` that have a source range.
+fn span_types(hir: &Hir, symbol_types: &TypeEnv) -> Vec<SpanType> {
+    let mut span_types = symbol_types
+        .into_iter()
+        .filter_map(|(symbol_id, type_scheme)| {
+            let symbol = hir.symbol(*symbol_id)?;
+            Some(SpanType {
+                symbol_id: *symbol_id,
+                source_id: symbol.source.source_id?,
+                range: symbol.source.text_range?,
+                type_scheme: type_scheme.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    span_types.sort_by_key(|span_type| (span_type.source_id, span_type.range.start, span_type.range.end));
+    span_types
 }
 
 impl Default for TypeChecker {
@@ -440,13 +623,97 @@ mod tests {
     }
 
     #[rstest]
-    #[case(TypeError::Mismatch { expected: "n".into(), found: "s".into(), span: None, location: Some(mq_lang::Range { start: mq_lang::Position { line: 1, column: 5 }, end: mq_lang::Position { line: 1, column: 6 } }), context: None }, Some(mq_lang::Range { start: mq_lang::Position { line: 1, column: 5 }, end: mq_lang::Position { line: 1, column: 6 } }))]
-    #[case(TypeError::UnificationError { left: "l".into(), right: "r".into(), span: None, location: Some(mq_lang::Range { start: mq_lang::Position { line: 2, column: 10 }, end: mq_lang::Position { line: 2, column: 11 } }), context: None }, Some(mq_lang::Range { start: mq_lang::Position { line: 2, column: 10 }, end: mq_lang::Position { line: 2, column: 11 } }))]
+    #[case(TypeError::Mismatch { expected: "n".into(), found: "s".into(), span: None, location: Some(mq_lang::Range { start: mq_lang::Position { line: 1, column: 5 }, end: mq_lang::Position { line: 1, column: 6 } }), context: None, origins: vec![] }, Some(mq_lang::Range { start: mq_lang::Position { line: 1, column: 5 }, end: mq_lang::Position { line: 1, column: 6 } }))]
+    #[case(TypeError::UnificationError { left: "l".into(), right: "r".into(), span: None, location: Some(mq_lang::Range { start: mq_lang::Position { line: 2, column: 10 }, end: mq_lang::Position { line: 2, column: 11 } }), context: None, origins: vec![] }, Some(mq_lang::Range { start: mq_lang::Position { line: 2, column: 10 }, end: mq_lang::Position { line: 2, column: 11 } }))]
     #[case(TypeError::TypeVarNotFound("a".into()), None)]
     fn test_type_error_location(#[case] err: TypeError, #[case] expected: Option<mq_lang::Range>) {
         assert_eq!(err.location(), expected);
     }
 
+    #[rstest]
+    #[case::none("xyz", vec!["upcase"], vec![], None)]
+    #[case::one("upcse", vec!["upcase", "downcase"], vec!["upcase"], Some("did you mean `upcase`?"))]
+    #[case::several("tst", vec!["test", "text"], vec!["test", "text"], Some("did you mean one of `test`, `text`?"))]
+    fn test_undefined_symbol_suggestions(
+        #[case] name: &str,
+        #[case] candidates: Vec<&str>,
+        #[case] expected_suggestions: Vec<&str>,
+        #[case] expected_help: Option<&str>,
+    ) {
+        let TypeError::UndefinedSymbol {
+            suggestions, context, ..
+        } = TypeError::undefined_symbol(name, None, &candidates)
+        else {
+            panic!("expected an undefined symbol error");
+        };
+        assert_eq!(suggestions, expected_suggestions);
+        assert_eq!(context.as_deref(), expected_help);
+    }
+
+    #[test]
+    fn test_mismatch_origins() {
+        let mut hir = Hir::default();
+        hir.add_code(
+            None,
+            "let a = 1\n| let b = if (true): a else: 2;\n| [b, \"x\"] | join(\",\")",
+        );
+        let errors = TypeChecker::new().check(&hir);
+
+        let origin_lines: Vec<Vec<u32>> = errors
+            .iter()
+            .map(|error| {
+                let mut lines: Vec<u32> = error
+                    .origins()
+                    .iter()
+                    .map(|origin| origin.location.start.line)
+                    .collect();
+                lines.sort_unstable();
+                lines
+            })
+            .collect();
+        assert!(origin_lines.contains(&vec![1, 3]), "{origin_lines:?}");
+    }
+
+    #[test]
+    fn test_type_at() {
+        let mut hir = Hir::default();
+        let (source_id, _) = hir.add_code(None, "let x = 1 | let y = \"a\" | x + 2");
+        let mut checker = TypeChecker::new();
+        checker.check(&hir);
+
+        let type_at = |column| {
+            checker
+                .type_at(source_id, mq_lang::Position { line: 1, column })
+                .map(|span_type| span_type.type_scheme.ty.clone())
+        };
+
+        assert_eq!(type_at(5), Some(types::Type::Number));
+        assert_eq!(type_at(21), Some(types::Type::String));
+        assert_eq!(type_at(29), Some(types::Type::Number));
+    }
+
+    #[test]
+    fn test_types_by_span() {
+        let mut hir = Hir::default();
+        let (source_id, _) = hir.add_code(None, "let x = 1\n| let y = x");
+        let mut checker = TypeChecker::new();
+        checker.check(&hir);
+
+        let spans = checker
+            .types_by_span()
+            .iter()
+            .filter(|span_type| span_type.source_id == source_id)
+            .collect::<Vec<_>>();
+
+        assert!(!spans.is_empty());
+        assert!(spans.windows(2).all(|pair| pair[0].range.start <= pair[1].range.start));
+        assert!(
+            spans
+                .iter()
+                .all(|span_type| { checker.symbol_type(span_type.symbol_id) == Some(&span_type.type_scheme) })
+        );
+    }
+
     #[test]
     fn test_walk_ancestors() {
         let mut hir = Hir::default();
@@ -1,21 +1,30 @@
+mod config;
 mod format;
+mod project;
 
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{self, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::time::{Duration, SystemTime};
 
 use clap::Parser;
 use colored::Colorize;
+use config::CheckConfig;
 use format::OutputFormat;
-use mq_check::{TypeChecker, TypeCheckerOptions, TypeError};
-use mq_hir::Hir;
+use miette::{Diagnostic, GraphicalReportHandler, LabeledSpan, MietteDiagnostic, Severity, SourceSpan};
+use mq_check::signature::Signatures;
+use mq_check::{Strictness, TypeChecker, TypeCheckerOptions, TypeError};
+use mq_hir::{Hir, SourceId};
+use project::{Cache, Project};
 use url::Url;
 
 /// Type checker for mq programs
 #[derive(Parser)]
 #[command(name = "mq-typecheck", about = "Type check mq programs")]
 struct Cli {
-    /// Paths to .mq files to type check (reads from stdin if omitted)
+    /// Paths to .mq files, directories, or glob patterns to type check (reads from stdin if
+    /// omitted). Directories are searched recursively for .mq files
     files: Vec<PathBuf>,
 
     /// Display inferred types for all symbols
@@ -34,19 +43,62 @@ struct Cli {
     #[arg(long)]
     no_exhaustive_patterns: bool,
 
+    /// How to report dynamic constructs such as untyped dict access and `type()` branching:
+    /// `off` (default), `warn`, or `strict` (reported as errors)
+    #[arg(long)]
+    strictness: Option<Strictness>,
+
+    /// Signature file (.mqi) declaring the types of included modules and host-registered functions.
+    /// Can be given multiple times
+    #[arg(long = "signatures", value_name = "FILE")]
+    signatures: Vec<PathBuf>,
+
     /// Diagnostic output format: `text` (human-readable), `json` (a single JSON array of
     /// diagnostics), or `sarif` (SARIF 2.1.0 JSON, for GitHub code scanning and other SARIF
     /// consumers)
     #[arg(long, value_enum, default_value_t)]
     format: OutputFormat,
+
+    /// Project config file. Defaults to the nearest mq.toml in the current directory or its parents
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Re-check files whenever they change
+    #[arg(long)]
+    watch: bool,
+
+    /// Write the inferred signatures of each file's exported functions to a .mqi file next to it
+    /// (to stdout when reading from stdin). Files with type errors are skipped
+    #[arg(long)]
+    emit_decls: bool,
+
+    /// Skip files that passed their last check and haven't changed since, nor have the modules
+    /// they include. Results are kept in .mq-check-cache next to mq.toml (or in the current directory)
+    #[arg(long)]
+    cache: bool,
+}
+
+/// The result of checking a single source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Outcome {
+    Clean,
+    Warnings,
+    Errors,
 }
 
+/// How often watch mode polls the checked files for changes.
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
 /// Options for a single file check
 struct CheckOptions<'a> {
     show_types: bool,
     label: Option<&'a str>,
     no_builtins: bool,
     type_checker_options: TypeCheckerOptions,
+    signatures: &'a Signatures,
+    include_paths: &'a [PathBuf],
+    /// Where to write the inferred declarations, `-` for stdout
+    decls_path: Option<&'a Path>,
 }
 
 fn main() -> ExitCode {
@@ -62,16 +114,19 @@ fn main() -> ExitCode {
 fn run() -> io::Result<()> {
     let cli = Cli::parse();
 
+    if cli.watch {
+        return run_watch(&cli);
+    }
+
     if cli.format != OutputFormat::Text {
+        if cli.emit_decls {
+            return Err(io::Error::other("--emit-decls only supports the text format"));
+        }
         return run_machine_format(&cli);
     }
 
     let mut w = BufWriter::new(io::stderr());
-    let multi = cli.files.len() > 1;
-    let tc_options = TypeCheckerOptions {
-        strict_array: cli.strict_array,
-        no_exhaustive_patterns: cli.no_exhaustive_patterns,
-    };
+    let settings = Settings::load(&cli)?;
 
     if cli.files.is_empty() {
         // Read from stdin
@@ -79,30 +134,63 @@ fn run() -> io::Result<()> {
         io::stdin().read_to_string(&mut code)?;
         let source_url = Url::parse("file:///stdin").ok();
         let opts = CheckOptions {
-            show_types: cli.show_types,
-            label: None,
-            no_builtins: cli.no_builtins,
-            type_checker_options: tc_options,
+            decls_path: cli.emit_decls.then_some(Path::new("-")),
+            ..settings.check_options(&cli, None)
         };
-        let had_errors = check_file(&mut w, &code, source_url, &opts)?;
-        if had_errors {
+        if check_file(&mut w, &code, source_url, &opts)? == Outcome::Errors {
             return Err(io::Error::other("type check failed"));
         }
         return Ok(());
     }
 
+    let total_errors = check_paths(&mut w, &cli, &settings, &settings.files)?;
+
+    if total_errors > 0 {
+        Err(io::Error::other("type check failed"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks each of `paths`, included modules before the files including them, and writes the
+/// text report, returning the number of files with errors.
+fn check_paths(w: &mut impl Write, cli: &Cli, settings: &Settings, paths: &[PathBuf]) -> io::Result<usize> {
+    let multi = settings.files.len() > 1;
+    let project = Project::load(paths, &settings.search_paths)?;
+    let mut cache = settings.load_cache();
     let mut total_errors = 0usize;
 
-    for path in &cli.files {
-        let code = std::fs::read_to_string(path)
-            .map_err(|e| io::Error::other(format!("reading file {}: {}", path.display(), e)))?;
-        let source_url = Url::from_file_path(std::fs::canonicalize(path).unwrap_or(path.clone())).ok();
+    for path in &project.files {
+        let code = project.source(path);
+        let canonical = project.canonical_path(path);
+        let source_url = Url::from_file_path(canonical).ok();
         let label = if multi { Some(path.display().to_string()) } else { None };
+        let fingerprint = project.fingerprint(path, settings.fingerprint);
+
+        if cache
+            .as_ref()
+            .is_some_and(|cache| cache.is_clean(canonical, fingerprint))
+        {
+            if let Some(lbl) = &label {
+                writeln!(w, "{} {}", "──".dimmed(), lbl.bold())?;
+            }
+            writeln!(
+                w,
+                "{}  {} {}",
+                "✓".bright_green().bold(),
+                "No type errors found.".bright_green(),
+                "(cached)".dimmed(),
+            )?;
+            if multi {
+                writeln!(w)?;
+            }
+            continue;
+        }
 
         // Debug: dump HIR structure
         if std::env::var("DUMP_HIR").is_ok() {
-            let mut hir = Hir::default();
-            hir.add_code(source_url.clone(), &code);
+            let mut hir = new_hir(cli.no_builtins, &settings.include_paths);
+            hir.add_code(source_url.clone(), code);
             for (id, symbol) in hir.symbols() {
                 writeln!(
                     w,
@@ -113,36 +201,98 @@ fn run() -> io::Result<()> {
             writeln!(w, "---")?;
         }
 
+        let decls_path = path.with_extension("mqi");
         let opts = CheckOptions {
-            show_types: cli.show_types,
-            label: label.as_deref(),
-            no_builtins: cli.no_builtins,
-            type_checker_options: tc_options,
+            decls_path: cli.emit_decls.then_some(decls_path.as_path()),
+            ..settings.check_options(cli, label.as_deref())
         };
-        let had_errors = check_file(&mut w, &code, source_url, &opts)?;
-        if had_errors {
+        let outcome = check_file(w, code, source_url, &opts)?;
+        if outcome == Outcome::Errors {
             total_errors += 1;
         }
+        if let Some(cache) = cache.as_mut() {
+            cache.record(canonical, fingerprint, outcome == Outcome::Clean);
+        }
 
         if multi {
             writeln!(w)?;
         }
     }
 
-    if total_errors > 0 {
-        Err(io::Error::other("type check failed"))
-    } else {
-        Ok(())
+    settings.save_cache(cache.as_ref())?;
+    Ok(total_errors)
+}
+
+/// Checks the files, then re-checks each file whenever it is modified. Changes to the config,
+/// signature files, or modules in the include paths reload the settings and re-check every file.
+fn run_watch(cli: &Cli) -> io::Result<()> {
+    if cli.files.is_empty() {
+        return Err(io::Error::other("--watch requires at least one file"));
+    }
+    if cli.format != OutputFormat::Text {
+        return Err(io::Error::other("--watch only supports the text format"));
+    }
+
+    let mut settings = Settings::load(cli)?;
+    let mut file_times = modified_times(&settings.files);
+    let mut dependency_times = modified_times(&settings.dependencies());
+    let mut pending = settings.files.clone();
+
+    loop {
+        if !pending.is_empty() {
+            let mut w = BufWriter::new(io::stderr());
+            if let Err(e) = check_paths(&mut w, cli, &settings, &pending) {
+                writeln!(w, "{} {}", "error:".bright_red().bold(), e)?;
+            }
+            writeln!(w, "{}", "Watching for changes...".dimmed())?;
+            w.flush()?;
+        }
+
+        std::thread::sleep(WATCH_INTERVAL);
+
+        let current_dependency_times = modified_times(&settings.dependencies());
+        if current_dependency_times != dependency_times {
+            match Settings::load(cli) {
+                Ok(reloaded) => settings = reloaded,
+                Err(e) => eprintln!("{} {}", "error:".bright_red().bold(), e),
+            }
+            dependency_times = modified_times(&settings.dependencies());
+            file_times = modified_times(&settings.files);
+            pending = settings.files.clone();
+            continue;
+        }
+
+        let current_file_times = modified_times(&settings.files);
+        pending = changed_paths(&file_times, &current_file_times);
+        file_times = current_file_times;
     }
 }
 
+/// Returns the modification time of each of `paths`, or `None` if it cannot be read.
+fn modified_times(paths: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+    paths
+        .iter()
+        .map(|path| {
+            let modified = std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+            (path.clone(), modified)
+        })
+        .collect()
+}
+
+/// Returns the paths in `after` whose modification time differs from the one in `before`.
+fn changed_paths(before: &[(PathBuf, Option<SystemTime>)], after: &[(PathBuf, Option<SystemTime>)]) -> Vec<PathBuf> {
+    after
+        .iter()
+        .filter(|entry| !before.contains(entry))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
 /// Runs syntax and type checks across all inputs and writes a single machine-readable
 /// report (JSON or SARIF) to stdout, instead of the colored text report.
 fn run_machine_format(cli: &Cli) -> io::Result<()> {
-    let tc_options = TypeCheckerOptions {
-        strict_array: cli.strict_array,
-        no_exhaustive_patterns: cli.no_exhaustive_patterns,
-    };
+    let settings = Settings::load(cli)?;
+    let opts = settings.check_options(cli, None);
 
     let mut results: Vec<(String, Vec<format::CheckDiagnostic>)> = Vec::new();
 
@@ -152,18 +302,32 @@ fn run_machine_format(cli: &Cli) -> io::Result<()> {
         let source_url = Url::parse("file:///stdin").ok();
         results.push((
             "<stdin>".to_string(),
-            collect_check_diagnostics(&code, source_url, cli.no_builtins, &tc_options),
+            collect_check_diagnostics(&code, source_url, &opts),
         ));
     } else {
-        for path in &cli.files {
-            let code = std::fs::read_to_string(path)
-                .map_err(|e| io::Error::other(format!("reading file {}: {}", path.display(), e)))?;
-            let source_url = Url::from_file_path(std::fs::canonicalize(path).unwrap_or(path.clone())).ok();
-            results.push((
-                path.display().to_string(),
-                collect_check_diagnostics(&code, source_url, cli.no_builtins, &tc_options),
-            ));
+        let project = Project::load(&settings.files, &settings.search_paths)?;
+        let mut cache = settings.load_cache();
+
+        for path in &project.files {
+            let canonical = project.canonical_path(path);
+            let fingerprint = project.fingerprint(path, settings.fingerprint);
+            let diagnostics = if cache
+                .as_ref()
+                .is_some_and(|cache| cache.is_clean(canonical, fingerprint))
+            {
+                Vec::new()
+            } else {
+                let source_url = Url::from_file_path(canonical).ok();
+                let diagnostics = collect_check_diagnostics(project.source(path), source_url, &opts);
+                if let Some(cache) = cache.as_mut() {
+                    cache.record(canonical, fingerprint, diagnostics.is_empty());
+                }
+                diagnostics
+            };
+            results.push((path.display().to_string(), diagnostics));
         }
+
+        settings.save_cache(cache.as_ref())?;
     }
 
     let had_errors = results
@@ -181,27 +345,160 @@ fn run_machine_format(cli: &Cli) -> io::Result<()> {
     }
 }
 
+/// Reads and parses the given signature files into a single set of declarations.
+fn load_signatures(paths: &[PathBuf]) -> io::Result<Signatures> {
+    let mut signatures = Signatures::default();
+
+    for path in paths {
+        let input = std::fs::read_to_string(path)
+            .map_err(|e| io::Error::other(format!("reading signatures {}: {}", path.display(), e)))?;
+        let parsed = Signatures::parse(&input)
+            .map_err(|e| io::Error::other(format!("parsing signatures {}:{}", path.display(), e)))?;
+        signatures.extend(parsed);
+    }
+
+    Ok(signatures)
+}
+
+/// Check settings from the command line and the `[check]` section of `mq.toml`. Command-line
+/// flags take precedence over the config.
+struct Settings {
+    type_checker_options: TypeCheckerOptions,
+    signatures: Signatures,
+    include_paths: Vec<PathBuf>,
+    /// The files to check, with directories and glob patterns expanded
+    files: Vec<PathBuf>,
+    /// The include paths followed by the directories of the checked files
+    search_paths: Vec<PathBuf>,
+    /// The config and signature files the settings were read from
+    sources: Vec<PathBuf>,
+    /// Where to cache the files that passed, if `--cache` is given
+    cache_path: Option<PathBuf>,
+    /// Hash of everything besides the checked sources that a result depends on
+    fingerprint: u64,
+}
+
+impl Settings {
+    /// Loads the config given by `--config`, or the nearest `mq.toml` found from the current
+    /// directory upwards, and merges it with the command-line flags.
+    fn load(cli: &Cli) -> io::Result<Self> {
+        let config_path = match &cli.config {
+            Some(path) => Some(path.clone()),
+            None => std::env::current_dir().ok().and_then(|dir| config::find_config(&dir)),
+        };
+        let config = config_path
+            .as_deref()
+            .map(CheckConfig::load)
+            .transpose()?
+            .unwrap_or_default();
+
+        let signature_paths: Vec<PathBuf> = config.signatures.iter().chain(&cli.signatures).cloned().collect();
+        let type_checker_options = TypeCheckerOptions {
+            strict_array: cli.strict_array || config.strict_array.unwrap_or(false),
+            no_exhaustive_patterns: cli.no_exhaustive_patterns,
+            strictness: cli.strictness.or(config.strictness()).unwrap_or_default(),
+        };
+
+        let files = project::expand_paths(&cli.files)?;
+        let search_paths = project::search_paths(&files, &config.include_paths);
+        // Inferred types and declarations are only produced by an actual check.
+        let cache_path = (cli.cache && !cli.show_types && !cli.emit_decls).then(|| {
+            config_path
+                .as_deref()
+                .and_then(Path::parent)
+                .unwrap_or(Path::new(""))
+                .join(project::CACHE_FILE_NAME)
+        });
+        let sources: Vec<PathBuf> = config_path.into_iter().chain(signature_paths.iter().cloned()).collect();
+
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", type_checker_options).hash(&mut hasher);
+        cli.no_builtins.hash(&mut hasher);
+        search_paths.hash(&mut hasher);
+        for source in &sources {
+            std::fs::read_to_string(source).ok().hash(&mut hasher);
+        }
+
+        Ok(Self {
+            type_checker_options,
+            signatures: load_signatures(&signature_paths)?,
+            include_paths: config.include_paths,
+            files,
+            search_paths,
+            sources,
+            cache_path,
+            fingerprint: hasher.finish(),
+        })
+    }
+
+    fn load_cache(&self) -> Option<Cache> {
+        self.cache_path.as_deref().map(Cache::load)
+    }
+
+    fn save_cache(&self, cache: Option<&Cache>) -> io::Result<()> {
+        match (cache, &self.cache_path) {
+            (Some(cache), Some(path)) => cache.save(path),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_options<'a>(&'a self, cli: &Cli, label: Option<&'a str>) -> CheckOptions<'a> {
+        CheckOptions {
+            show_types: cli.show_types,
+            label,
+            no_builtins: cli.no_builtins,
+            type_checker_options: self.type_checker_options,
+            signatures: &self.signatures,
+            include_paths: &self.search_paths,
+            decls_path: None,
+        }
+    }
+
+    /// Returns the files that every check depends on: the config, the signature files, and
+    /// the modules in the include paths.
+    fn dependencies(&self) -> Vec<PathBuf> {
+        let modules = self
+            .include_paths
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "mq"));
+        let mut paths: Vec<PathBuf> = self.sources.iter().cloned().chain(modules).collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// Creates a HIR that resolves includes from `include_paths`, or from the default module
+/// directories if it is empty.
+fn new_hir(no_builtins: bool, include_paths: &[PathBuf]) -> Hir {
+    let resolver = mq_lang::DefaultModuleResolver::new(include_paths.to_vec());
+    let mut hir = Hir::new(mq_lang::ModuleLoader::new(resolver));
+
+    if no_builtins {
+        hir.builtin.disabled = true;
+    }
+
+    hir
+}
+
 /// Runs syntax and type checks on a single source, returning every diagnostic found.
 /// Type checking is skipped when syntax errors are present, matching the text report's behavior.
 fn collect_check_diagnostics(
     code: &str,
     source_url: Option<Url>,
-    no_builtins: bool,
-    type_checker_options: &TypeCheckerOptions,
+    opts: &CheckOptions<'_>,
 ) -> Vec<format::CheckDiagnostic> {
-    let mut hir = Hir::default();
-
-    if no_builtins {
-        hir.builtin.disabled = true;
-    }
-
+    let mut hir = new_hir(opts.no_builtins, opts.include_paths);
     hir.add_code(source_url, code);
 
-    let mut diagnostics = format::syntax_diagnostics(&hir);
+    let mut diagnostics = format::syntax_diagnostics(&hir, opts.signatures);
     let has_syntax_errors = diagnostics.iter().any(|d| d.severity == format::Severity::Error);
 
     if !has_syntax_errors {
-        let mut checker = TypeChecker::with_options(*type_checker_options);
+        let mut checker = TypeChecker::with_options(opts.type_checker_options).with_signatures(opts.signatures.clone());
         let errors = checker.check(&hir);
         diagnostics.extend(format::type_diagnostics(&errors));
     }
@@ -209,39 +506,46 @@ fn collect_check_diagnostics(
     diagnostics
 }
 
-/// Runs syntax and type checks on a single source, returns `true` if any errors were found.
-fn check_file(w: &mut impl Write, code: &str, source_url: Option<Url>, opts: &CheckOptions<'_>) -> io::Result<bool> {
-    let mut hir = Hir::default();
-
-    if opts.no_builtins {
-        hir.builtin.disabled = true;
-    }
-
-    hir.add_code(source_url, code);
+/// Runs syntax and type checks on a single source, returning whether anything was reported.
+fn check_file(w: &mut impl Write, code: &str, source_url: Option<Url>, opts: &CheckOptions<'_>) -> io::Result<Outcome> {
+    let mut hir = new_hir(opts.no_builtins, opts.include_paths);
+    let (source_id, _) = hir.add_code(source_url, code);
 
     if let Some(lbl) = opts.label {
         writeln!(w, "{} {}", "──".dimmed(), lbl.bold())?;
     }
 
-    let syntax_errors = check_syntax(w, &hir)?;
-    if syntax_errors {
-        return Ok(true);
+    let syntax = check_syntax(w, code, &hir, opts.signatures)?;
+    if syntax == Outcome::Errors {
+        return Ok(Outcome::Errors);
     }
 
-    check_type(w, code, &hir, opts.show_types, &opts.type_checker_options)
+    Ok(syntax.max(check_type(w, code, &hir, source_id, opts)?))
 }
 
 /// Checks HIR for syntax errors/warnings and writes them in a unified format.
-/// Returns `true` if any errors or warnings were found.
-fn check_syntax(w: &mut impl Write, hir: &mq_hir::Hir) -> io::Result<bool> {
-    let errors = hir.error_ranges();
+fn check_syntax(w: &mut impl Write, code: &str, hir: &mq_hir::Hir, signatures: &Signatures) -> io::Result<Outcome> {
+    let hir_errors = format::hir_errors(hir, signatures);
     let warnings = hir.warning_ranges();
 
-    if errors.is_empty() && warnings.is_empty() {
-        return Ok(false);
+    if hir_errors.is_empty() && warnings.is_empty() {
+        return Ok(Outcome::Clean);
+    }
+
+    // Undefined symbols are rendered like type errors, with "did you mean" suggestions
+    let (undefined, errors): (Vec<_>, Vec<_>) = hir_errors
+        .iter()
+        .map(|error| match format::undefined_symbol_error(hir, error) {
+            Some(undefined) => Ok(undefined),
+            None => Err((error.to_string(), format::hir_error_range(error))),
+        })
+        .partition(Result::is_ok);
+
+    for undefined in undefined.into_iter().flatten() {
+        write_error(w, &undefined, code)?;
     }
 
-    for (message, range) in &errors {
+    for (message, range) in errors.into_iter().filter_map(Result::err) {
         writeln!(
             w,
             "  {} {} {} {}",
@@ -264,7 +568,7 @@ fn check_syntax(w: &mut impl Write, hir: &mq_hir::Hir) -> io::Result<bool> {
     }
 
     writeln!(w)?;
-    let error_count = errors.len();
+    let error_count = hir_errors.len();
     let warning_count = warnings.len();
 
     if error_count > 0 {
@@ -286,33 +590,50 @@ fn check_syntax(w: &mut impl Write, hir: &mq_hir::Hir) -> io::Result<bool> {
         )?;
     }
 
-    Ok(error_count > 0)
+    Ok(if error_count > 0 {
+        Outcome::Errors
+    } else {
+        Outcome::Warnings
+    })
 }
 
 /// Runs type inference and writes errors in a unified format.
-/// Returns `true` if any type errors were found.
 fn check_type(
     w: &mut impl Write,
     code: &str,
     hir: &mq_hir::Hir,
-    show_types: bool,
-    options: &TypeCheckerOptions,
-) -> io::Result<bool> {
-    let mut checker = TypeChecker::with_options(*options);
+    source_id: SourceId,
+    opts: &CheckOptions<'_>,
+) -> io::Result<Outcome> {
+    let show_types = opts.show_types;
+    let mut checker = TypeChecker::with_options(opts.type_checker_options).with_signatures(opts.signatures.clone());
     let mut errors = checker.check(hir);
 
     errors.sort_by_key(|a| a.location());
 
     let total = errors.len();
-    for (i, error) in errors.iter().enumerate() {
-        write_error(w, error, code, i + 1, total)?;
+    for error in &errors {
+        write_error(w, error, code)?;
     }
 
     if show_types {
         write_inferred_types(w, &checker, hir)?;
     }
 
-    if errors.is_empty() {
+    let warning_count = errors.iter().filter(|error| format::is_warning(error)).count();
+    let error_count = total - warning_count;
+
+    if warning_count > 0 {
+        writeln!(
+            w,
+            "{}  {} warning{}.",
+            "⚠".bright_yellow().bold(),
+            warning_count.to_string().bright_yellow().bold(),
+            if warning_count == 1 { "" } else { "s" },
+        )?;
+    }
+
+    if error_count == 0 {
         if !show_types {
             writeln!(
                 w,
@@ -321,53 +642,40 @@ fn check_type(
                 "No type errors found.".bright_green(),
             )?;
         }
-        Ok(false)
+        if let Some(path) = opts.decls_path {
+            write_declarations(w, &checker.declarations(hir, source_id), path)?;
+        }
+        Ok(if warning_count > 0 {
+            Outcome::Warnings
+        } else {
+            Outcome::Clean
+        })
     } else {
         writeln!(
             w,
             "{} {} type error{} found.",
             "✗".bright_red().bold(),
-            total.to_string().bright_red().bold(),
-            if total == 1 { "" } else { "s" },
+            error_count.to_string().bright_red().bold(),
+            if error_count == 1 { "" } else { "s" },
         )?;
-        Ok(true)
+        Ok(Outcome::Errors)
     }
 }
 
-/// Writes a source snippet for the error location with a caret underline.
-///
-/// `prefix_width` is the visual width of the location prefix (e.g. "1:5" = 3)
-/// used to align the gutter with the surrounding error message lines.
-fn write_snippet(w: &mut impl Write, code: &str, range: &mq_lang::Range, prefix_width: usize) -> io::Result<()> {
-    let sep = "│".dimmed();
-    let lines: Vec<&str> = code.lines().collect();
-    let line_idx = range.start.line.saturating_sub(1) as usize;
-    let Some(source_line) = lines.get(line_idx) else {
-        return Ok(());
-    };
-
-    let col_start = range.start.column.saturating_sub(1);
-    let col_end = if range.end.line == range.start.line {
-        range.end.column.saturating_sub(1)
-    } else {
-        source_line.len()
-    };
-    let underline_len = col_end.saturating_sub(col_start).max(1);
-
-    let line_num = range.start.line.to_string();
-    // Pad gutter to align with the loc_str prefix used in error message lines
-    let gutter = " ".repeat(prefix_width.max(line_num.len()));
+/// Writes inferred declarations to `path`, or to stdout if `path` is `-`.
+fn write_declarations(w: &mut impl Write, declarations: &str, path: &Path) -> io::Result<()> {
+    if path == Path::new("-") {
+        return io::stdout().write_all(declarations.as_bytes());
+    }
 
-    writeln!(w, "  {} {} {}", gutter, sep, source_line.dimmed())?;
+    std::fs::write(path, declarations)
+        .map_err(|e| io::Error::other(format!("writing declarations {}: {}", path.display(), e)))?;
     writeln!(
         w,
-        "  {} {} {}{}",
-        format!("{:>width$}", line_num, width = prefix_width).dimmed(),
-        sep,
-        " ".repeat(col_start),
-        "^".repeat(underline_len).bright_red().bold(),
-    )?;
-    Ok(())
+        "{}  {}",
+        "✓".bright_green().bold(),
+        format!("Wrote {}", path.display()).bright_green(),
+    )
 }
 
 /// Returns a short one-line title for an error (used in the numbered header).
@@ -397,81 +705,82 @@ fn error_title(error: &TypeError) -> String {
         TypeError::NonExhaustiveMatch { missing, .. } => {
             format!("non-exhaustive patterns: missing case for {missing}")
         }
+        TypeError::DynamicConstruct { construct, .. } => format!("dynamic construct: {construct}"),
     }
 }
 
-/// Writes a single type error with rich formatting to the given writer.
-///
-/// `index` is the 1-based error number; `total` is the total error count.
-/// - Single error  → compact inline format  (`loc │ message` + snippet)
-/// - Multiple errors → Rust-style block format (`error[E000N] title` / ` --> loc` / snippet)
-fn write_error(w: &mut impl Write, error: &TypeError, code: &str, index: usize, total: usize) -> io::Result<()> {
-    let location = error.location();
-    let loc_plain = location
-        .map(|range| format!("{}:{}", range.start.line, range.start.column))
-        .unwrap_or_default();
-
-    if total > 1 {
-        // ── Rust-style block ──────────────────────────────────────────────────
-        writeln!(
-            w,
-            "{} {}",
-            format!("error[E{index:04}]").bright_red().bold(),
-            error_title(error).white().bold(),
-        )?;
-        if !loc_plain.is_empty() {
-            writeln!(w, "  {} {}", "-->".dimmed(), loc_plain.dimmed())?;
-        }
-        if let Some(range) = &location {
-            write_snippet(w, code, range, loc_plain.len())?;
-        }
-        if let Some(ctx) = error_help(error) {
-            writeln!(
-                w,
-                "  {} {} {}",
-                " ".repeat(loc_plain.len()),
-                "│".dimmed(),
-                format!("help: {ctx}").bright_blue(),
-            )?;
-        }
-        writeln!(w)?; // blank line between blocks
-    } else {
-        // ── Compact inline format ─────────────────────────────────────────────
-        let prefix_width = loc_plain.len();
-        let loc_str = if loc_plain.is_empty() {
-            String::new()
+/// Writes a type error as a `miette` diagnostic. The error location is the primary label, and
+/// the places its types were inferred at are secondary labels.
+fn write_error(w: &mut impl Write, error: &TypeError, code: &str) -> io::Result<()> {
+    let mut labels = Vec::new();
+    if let Some(range) = error.location() {
+        labels.push(LabeledSpan::new_primary_with_span(
+            Some(error_label(error)),
+            byte_span(code, &range),
+        ));
+    }
+    labels.extend(
+        error
+            .origins()
+            .iter()
+            .map(|origin| LabeledSpan::new_with_span(Some(origin.message.clone()), byte_span(code, &origin.location))),
+    );
+
+    let mut diagnostic = MietteDiagnostic::new(error_title(error))
+        .with_severity(if format::is_warning(error) {
+            Severity::Warning
         } else {
-            loc_plain.dimmed().to_string()
-        };
-        let sep = "│".dimmed();
-
-        writeln!(w, "  {} {} {}", loc_str, sep, error_title(error).white(),)?;
-        if let Some(range) = &location {
-            write_snippet(w, code, range, prefix_width)?;
-        }
-        if let Some(ctx) = error_help(error) {
-            writeln!(
-                w,
-                "  {} {} {}",
-                " ".repeat(prefix_width),
-                sep,
-                format!("help: {ctx}").bright_blue(),
-            )?;
-        }
+            Severity::Error
+        })
+        .with_labels(labels);
+    if let Some(error_code) = error.code() {
+        diagnostic = diagnostic.with_code(error_code.to_string());
     }
-    Ok(())
+    if let Some(help) = error.help() {
+        diagnostic = diagnostic.with_help(help.to_string());
+    }
+
+    let report = miette::Report::new(diagnostic).with_source_code(code.to_string());
+    let mut rendered = String::new();
+    GraphicalReportHandler::new()
+        .render_report(&mut rendered, report.as_ref())
+        .map_err(io::Error::other)?;
+    write!(w, "{rendered}")
 }
 
-/// Returns the optional help/context string for an error.
-fn error_help(error: &TypeError) -> Option<&str> {
+/// Returns the text of the primary label, which spells out both types of a mismatch.
+fn error_label(error: &TypeError) -> String {
     match error {
-        TypeError::Mismatch { context, .. }
-        | TypeError::UnificationError { context, .. }
-        | TypeError::WrongArity { context, .. } => context.as_deref(),
-        _ => None,
+        TypeError::Mismatch { expected, found, .. } => format!("expected {expected}, found {found}"),
+        _ => error
+            .labels()
+            .and_then(|mut labels| labels.next())
+            .and_then(|label| label.label().map(str::to_string))
+            .unwrap_or_else(|| "here".to_string()),
     }
 }
 
+/// Converts a line/column range into a byte span of `code`.
+fn byte_span(code: &str, range: &mq_lang::Range) -> SourceSpan {
+    let offset = |position: &mq_lang::Position| {
+        let line_start: usize = code
+            .split_inclusive('\n')
+            .take(position.line.saturating_sub(1) as usize)
+            .map(str::len)
+            .sum();
+        let line = code[line_start..].lines().next().unwrap_or_default();
+        let column = line
+            .char_indices()
+            .nth(position.column.saturating_sub(1))
+            .map_or(line.len(), |(index, _)| index);
+        line_start + column
+    };
+    let start = offset(&range.start);
+    let end = offset(&range.end).max(start);
+
+    SourceSpan::new(start.into(), end - start)
+}
+
 /// Writes inferred types with rich formatting to the given writer.
 fn write_inferred_types(w: &mut impl Write, checker: &TypeChecker, hir: &Hir) -> io::Result<()> {
     writeln!(w)?;
@@ -508,6 +817,22 @@ mod tests {
     use clap::Parser;
     use rstest::rstest;
 
+    fn check_options(
+        no_builtins: bool,
+        type_checker_options: TypeCheckerOptions,
+        signatures: &Signatures,
+    ) -> CheckOptions<'_> {
+        CheckOptions {
+            show_types: false,
+            label: None,
+            no_builtins,
+            type_checker_options,
+            signatures,
+            include_paths: &[],
+            decls_path: None,
+        }
+    }
+
     #[rstest]
     #[case(vec!["mq-check", "test.mq"], vec!["test.mq"], false, false)]
     #[case(vec!["mq-check", "test.mq", "--strict-array"], vec!["test.mq"], true, false)]
@@ -528,6 +853,189 @@ mod tests {
         assert_eq!(cli.show_types, expected_show_types);
     }
 
+    #[rstest]
+    #[case(vec!["mq-check"], None)]
+    #[case(vec!["mq-check", "--strictness", "warn"], Some(Strictness::Warn))]
+    #[case(vec!["mq-check", "--strictness", "strict"], Some(Strictness::Strict))]
+    fn test_cli_strictness(#[case] args: Vec<&str>, #[case] expected: Option<Strictness>) {
+        let cli = Cli::try_parse_from(args).unwrap();
+        assert_eq!(cli.strictness, expected);
+    }
+
+    #[test]
+    fn test_collect_check_diagnostics_strictness() {
+        let code = "def f(x): x[\"a\"];";
+        let severities = |strictness| {
+            let options = TypeCheckerOptions {
+                strictness,
+                ..Default::default()
+            };
+            collect_check_diagnostics(code, None, &check_options(true, options, &Signatures::default()))
+                .into_iter()
+                .filter(|d| d.code == "typechecker::dynamic_construct")
+                .map(|d| d.severity)
+                .collect::<Vec<_>>()
+        };
+
+        assert!(severities(Strictness::Off).is_empty());
+        assert_eq!(severities(Strictness::Warn), vec![format::Severity::Warning]);
+        assert_eq!(severities(Strictness::Strict), vec![format::Severity::Error]);
+    }
+
+    #[test]
+    fn test_cli_signatures() {
+        let cli = Cli::try_parse_from(["mq-check", "--signatures", "a.mqi", "--signatures", "b.mqi"]).unwrap();
+        assert_eq!(cli.signatures, vec![PathBuf::from("a.mqi"), PathBuf::from("b.mqi")]);
+    }
+
+    #[test]
+    fn test_collect_check_diagnostics_declared_host_function() {
+        let signatures = Signatures::parse("def host_fn(string): string;").unwrap();
+        let codes = |code| {
+            collect_check_diagnostics(
+                code,
+                None,
+                &check_options(true, TypeCheckerOptions::default(), &signatures),
+            )
+            .into_iter()
+            .map(|d| d.code)
+            .collect::<Vec<_>>()
+        };
+
+        assert!(codes("host_fn(\"a\")").is_empty());
+        assert_eq!(codes("host_fn(1)"), vec!["typechecker::unification_error"]);
+        assert_eq!(codes("other_fn(1)"), vec!["hir::unresolved_symbol"]);
+    }
+
+    #[test]
+    fn test_settings_merge_config_and_flags() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join(config::CONFIG_FILE_NAME);
+        std::fs::write(
+            &config_path,
+            "[check]\ninclude_paths = [\"modules\"]\nstrictness = \"warn\"\nstrict_array = true\n",
+        )
+        .unwrap();
+        let config_arg = config_path.to_str().unwrap();
+
+        let settings = Settings::load(&Cli::try_parse_from(["mq-check", "--config", config_arg]).unwrap()).unwrap();
+        assert_eq!(settings.type_checker_options.strictness, Strictness::Warn);
+        assert!(settings.type_checker_options.strict_array);
+        assert_eq!(settings.include_paths, vec![dir.path().join("modules")]);
+        assert_eq!(settings.sources, vec![config_path.clone()]);
+
+        let cli = Cli::try_parse_from(["mq-check", "--config", config_arg, "--strictness", "off"]).unwrap();
+        let settings = Settings::load(&cli).unwrap();
+        assert_eq!(settings.type_checker_options.strictness, Strictness::Off);
+    }
+
+    #[test]
+    fn test_collect_check_diagnostics_include_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("util.mq"), "def twice(x): x * 2;").unwrap();
+        let include_paths = vec![dir.path().to_path_buf()];
+        let signatures = Signatures::default();
+        let opts = CheckOptions {
+            include_paths: &include_paths,
+            ..check_options(false, TypeCheckerOptions::default(), &signatures)
+        };
+
+        let codes = |opts| {
+            collect_check_diagnostics("include \"util\" | twice(1)", None, opts)
+                .into_iter()
+                .map(|d| d.code)
+                .collect::<Vec<_>>()
+        };
+        assert!(codes(&opts).is_empty());
+        assert_eq!(
+            codes(&check_options(false, TypeCheckerOptions::default(), &signatures)),
+            vec!["hir::module_not_found", "hir::unresolved_symbol"]
+        );
+    }
+
+    #[test]
+    fn test_check_paths_emit_decls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("util.mq");
+        std::fs::write(&path, "def ident(x): x;").unwrap();
+
+        let cli = Cli::try_parse_from(["mq-check", "--emit-decls", path.to_str().unwrap()]).unwrap();
+        let settings = Settings::load(&cli).unwrap();
+        let mut output = Vec::new();
+
+        assert_eq!(check_paths(&mut output, &cli, &settings, &cli.files).unwrap(), 0);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("util.mqi")).unwrap(),
+            "def ident(x: 'a): 'a;\n"
+        );
+    }
+
+    #[test]
+    fn test_check_paths_project_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join(config::CONFIG_FILE_NAME);
+        std::fs::write(&config_path, "[check]\n").unwrap();
+        std::fs::create_dir(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("a.mq"), "include \"util\" | twice(1)").unwrap();
+        std::fs::write(dir.path().join("lib").join("util.mq"), "def twice(x): x * 2;").unwrap();
+
+        let cli = Cli::try_parse_from([
+            "mq-check",
+            "--cache",
+            "--config",
+            config_path.to_str().unwrap(),
+            dir.path().to_str().unwrap(),
+        ])
+        .unwrap();
+        let settings = Settings::load(&cli).unwrap();
+        let check = || {
+            let mut output = Vec::new();
+            let errors = check_paths(&mut output, &cli, &settings, &settings.files).unwrap();
+            (errors, String::from_utf8(output).unwrap())
+        };
+
+        let (errors, output) = check();
+        assert_eq!(errors, 0);
+        // The included module is reported before the file including it.
+        assert!(output.find("util.mq").unwrap() < output.find("a.mq").unwrap());
+        assert!(!output.contains("(cached)"));
+        assert!(dir.path().join(project::CACHE_FILE_NAME).is_file());
+
+        let (_, output) = check();
+        assert_eq!(output.matches("(cached)").count(), 2);
+
+        std::fs::write(dir.path().join("lib").join("util.mq"), "def twice(x): x + x;").unwrap();
+        let (_, output) = check();
+        assert_eq!(output.matches("(cached)").count(), 0);
+    }
+
+    #[test]
+    fn test_changed_paths() {
+        let time = |secs| Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+        let before = vec![
+            (PathBuf::from("a.mq"), time(1)),
+            (PathBuf::from("b.mq"), time(1)),
+            (PathBuf::from("c.mq"), None),
+        ];
+        let after = vec![
+            (PathBuf::from("a.mq"), time(1)),
+            (PathBuf::from("b.mq"), time(2)),
+            (PathBuf::from("c.mq"), time(1)),
+        ];
+
+        assert_eq!(
+            changed_paths(&before, &after),
+            vec![PathBuf::from("b.mq"), PathBuf::from("c.mq")]
+        );
+        assert!(changed_paths(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_cli_watch() {
+        let cli = Cli::try_parse_from(["mq-check", "--watch", "a.mq"]).unwrap();
+        assert!(cli.watch);
+    }
+
     #[test]
     fn test_cli_no_builtins() {
         let cli = Cli::try_parse_from(["mq-check", "--no-builtins"]).unwrap();
@@ -552,18 +1060,53 @@ mod tests {
 
     #[test]
     fn test_collect_check_diagnostics_reports_undefined_symbol() {
-        let diagnostics = collect_check_diagnostics("undefined_fn()", None, false, &TypeCheckerOptions::default());
-        assert!(
-            diagnostics
-                .iter()
-                .any(|d| d.code == "typechecker::undefined_symbol" || d.code == "hir::unresolved_symbol")
+        let diagnostics = collect_check_diagnostics(
+            "undefined_fn()",
+            None,
+            &check_options(false, TypeCheckerOptions::default(), &Signatures::default()),
         );
+        assert!(diagnostics.iter().any(|d| d.code == "hir::unresolved_symbol"));
         assert!(diagnostics.iter().any(|d| d.severity == format::Severity::Error));
     }
 
+    #[test]
+    fn test_collect_check_diagnostics_suggests_defined_names() {
+        let diagnostics = collect_check_diagnostics(
+            "def greet(name): name; | gret(\"a\")",
+            None,
+            &check_options(true, TypeCheckerOptions::default(), &Signatures::default()),
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].message, "Undefined symbol: gret; did you mean `greet`?");
+    }
+
+    #[rstest]
+    #[case::first_line("let x = 1", (1, 5), (1, 6), 4, 1)]
+    #[case::second_line("let x = 1\n| upcase(x)", (2, 3), (2, 9), 12, 6)]
+    #[case::multibyte("\"あい\" | x", (1, 8), (1, 9), 11, 1)]
+    #[case::past_line_end("ab\ncd", (1, 5), (2, 2), 2, 2)]
+    fn test_byte_span(
+        #[case] code: &str,
+        #[case] start: (u32, usize),
+        #[case] end: (u32, usize),
+        #[case] expected_offset: usize,
+        #[case] expected_len: usize,
+    ) {
+        let range = mq_lang::Range {
+            start: mq_lang::Position::new(start.0, start.1),
+            end: mq_lang::Position::new(end.0, end.1),
+        };
+        let span = byte_span(code, &range);
+        assert_eq!((span.offset(), span.len()), (expected_offset, expected_len));
+    }
+
     #[test]
     fn test_collect_check_diagnostics_no_errors_on_valid_code() {
-        let diagnostics = collect_check_diagnostics(".h1", None, true, &TypeCheckerOptions::default());
+        let diagnostics = collect_check_diagnostics(
+            ".h1",
+            None,
+            &check_options(true, TypeCheckerOptions::default(), &Signatures::default()),
+        );
         assert!(!diagnostics.iter().any(|d| d.severity == format::Severity::Error));
     }
 }
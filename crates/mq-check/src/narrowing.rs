@@ -661,14 +661,12 @@ fn detect_dead_then_branch(
 ///
 /// For each arm body, subtracts all preceding whole-type pattern types from the matched
 /// variable's union type and applies the result to Ref symbols within that arm body.
-/// Reports dead arms where subtraction leaves `Never`.
-pub(crate) fn resolve_cross_arm_narrowings(
-    hir: &Hir,
-    ctx: &mut InferenceContext,
-) -> Vec<(String, Option<mq_lang::Range>)> {
+/// Arms where subtraction leaves `Never` are left as they are; they are reported as
+/// unreachable by the exhaustiveness check.
+pub(crate) fn resolve_cross_arm_narrowings(hir: &Hir, ctx: &mut InferenceContext) {
     let narrowings = ctx.take_cross_arm_narrowings();
     if narrowings.is_empty() {
-        return Vec::new();
+        return;
     }
 
     let mut tracked_branches: rustc_hash::FxHashSet<SymbolId> = rustc_hash::FxHashSet::default();
@@ -708,8 +706,6 @@ pub(crate) fn resolve_cross_arm_narrowings(
         }
     }
 
-    let mut dead_branches: Vec<(String, Option<mq_lang::Range>)> = Vec::new();
-
     for CrossArmNarrowing {
         def_id,
         exclude_types,
@@ -744,25 +740,10 @@ pub(crate) fn resolve_cross_arm_narrowings(
             Type::union(remaining_members)
         };
 
-        if remaining.is_never() {
-            let var_name = hir
-                .symbol(*def_id)
-                .and_then(|s| s.value.as_deref())
-                .unwrap_or("the variable");
-            let range = hir.symbol(*branch_id).and_then(|s| s.source.text_range);
-            dead_branches.push((
-                format!(
-                    "match arm is unreachable: all types of `{}` are covered by preceding arms",
-                    var_name
-                ),
-                range,
-            ));
-        } else {
+        if !remaining.is_never() {
             apply_narrowing_to_branch(ctx, *def_id, &remaining, *branch_id, &def_to_refs, &branch_descendants);
         }
     }
-
-    dead_branches
 }
 
 /// Applies a type narrowing to all Ref symbols within a branch that reference
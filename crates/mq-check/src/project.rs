@@ -0,0 +1,363 @@
+//! Project-wide checking for the `mq-check` CLI: expanding directory and glob arguments into the
+//! files to check, ordering them so that included modules are reported before the files
+//! including them, and caching the files that passed.
+
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mq_lang::ModuleResolver;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+/// File name of the check cache, written next to `mq.toml` (or in the current directory).
+pub(crate) const CACHE_FILE_NAME: &str = ".mq-check-cache";
+
+/// Expands directories into the `.mq` files below them and glob patterns into their matches.
+/// Other paths are kept as given, so that missing files are still reported when read.
+pub(crate) fn expand_paths(args: &[PathBuf]) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+
+    for arg in args {
+        let pattern = if arg.is_dir() {
+            arg.join("**").join("*.mq").to_string_lossy().into_owned()
+        } else if arg.to_string_lossy().contains(['*', '?', '[']) {
+            arg.to_string_lossy().into_owned()
+        } else {
+            paths.push(arg.clone());
+            continue;
+        };
+
+        let mut matches = glob::glob(&pattern)
+            .map_err(|e| io::Error::other(format!("invalid pattern {}: {}", pattern, e)))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(io::Error::other)?;
+        matches.sort();
+        paths.extend(matches.into_iter().filter(|path| path.is_file()));
+    }
+
+    let mut seen = FxHashSet::default();
+    paths.retain(|path| seen.insert(path.clone()));
+    Ok(paths)
+}
+
+/// Returns the module search path for checking `files`: the configured include paths (or the
+/// default module directories if none are configured), followed by the directories of the files
+/// themselves, so that files in the project can include each other.
+pub(crate) fn search_paths(files: &[PathBuf], include_paths: &[PathBuf]) -> Vec<PathBuf> {
+    if files.is_empty() {
+        return include_paths.to_vec();
+    }
+
+    let mut paths = if include_paths.is_empty() {
+        mq_lang::DefaultModuleResolver::default().search_paths()
+    } else {
+        include_paths.to_vec()
+    };
+    for dir in files.iter().filter_map(|file| file.parent()) {
+        let dir = if dir.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            dir.to_path_buf()
+        };
+        if !paths.contains(&dir) {
+            paths.push(dir);
+        }
+    }
+    paths
+}
+
+/// Returns the names of the modules `code` includes or imports.
+fn module_names(code: &str) -> Vec<String> {
+    fn collect(node: &mq_lang::CstNode, names: &mut Vec<String>) {
+        if matches!(node.kind, mq_lang::CstNodeKind::Include | mq_lang::CstNodeKind::Import) {
+            let name = node.children.first().and_then(|child| match child.token.as_deref() {
+                Some(mq_lang::Token {
+                    kind: mq_lang::TokenKind::StringLiteral(name),
+                    ..
+                }) => Some(name.clone()),
+                _ => None,
+            });
+            names.extend(name);
+        }
+        for child in &node.children {
+            collect(child, names);
+        }
+    }
+
+    let (nodes, _) = mq_lang::parse_recovery(code);
+    let mut names = Vec::new();
+    for node in &nodes {
+        collect(node, &mut names);
+    }
+    names
+}
+
+/// The files being checked, along with every module they include that was found on the search
+/// path.
+pub(crate) struct Project {
+    /// The checked files, each after the checked files it (transitively) includes
+    pub(crate) files: Vec<PathBuf>,
+    /// Source of each checked file and included module, keyed by canonical path
+    sources: FxHashMap<PathBuf, String>,
+    /// Canonical paths of the modules each source includes
+    includes: FxHashMap<PathBuf, Vec<PathBuf>>,
+    /// Canonical path of each checked file
+    canonical: FxHashMap<PathBuf, PathBuf>,
+}
+
+impl Project {
+    /// Reads `files` and the modules they include, resolved against `search_paths`.
+    pub(crate) fn load(files: &[PathBuf], search_paths: &[PathBuf]) -> io::Result<Self> {
+        let resolver = mq_lang::DefaultModuleResolver::new(search_paths.to_vec());
+        let mut project = Self {
+            files: Vec::new(),
+            sources: FxHashMap::default(),
+            includes: FxHashMap::default(),
+            canonical: FxHashMap::default(),
+        };
+
+        let mut pending = Vec::new();
+        for file in files {
+            let code = std::fs::read_to_string(file)
+                .map_err(|e| io::Error::other(format!("reading file {}: {}", file.display(), e)))?;
+            let canonical = std::fs::canonicalize(file).unwrap_or(file.clone());
+            project.canonical.insert(file.clone(), canonical.clone());
+            project.sources.insert(canonical.clone(), code);
+            pending.push(canonical);
+        }
+
+        while let Some(path) = pending.pop() {
+            if project.includes.contains_key(&path) {
+                continue;
+            }
+            let includes: Vec<PathBuf> = module_names(&project.sources[&path])
+                .iter()
+                .filter_map(|name| resolver.get_path(name).ok())
+                .map(PathBuf::from)
+                .filter(|module| module.is_file())
+                .filter_map(|module| std::fs::canonicalize(module).ok())
+                .collect();
+            for module in &includes {
+                if !project.sources.contains_key(module)
+                    && let Ok(code) = std::fs::read_to_string(module)
+                {
+                    project.sources.insert(module.clone(), code);
+                }
+                if project.sources.contains_key(module) {
+                    pending.push(module.clone());
+                }
+            }
+            project.includes.insert(path, includes);
+        }
+
+        project.files = project.dependency_order(files);
+        Ok(project)
+    }
+
+    /// Orders `files` so that each comes after the files it includes. Otherwise the given order
+    /// is kept; include cycles are broken at the first file reached.
+    fn dependency_order(&self, files: &[PathBuf]) -> Vec<PathBuf> {
+        let checked: FxHashMap<&PathBuf, &PathBuf> = files.iter().map(|file| (&self.canonical[file], file)).collect();
+        let mut visited = FxHashSet::default();
+        let mut order = Vec::with_capacity(files.len());
+
+        fn visit<'a>(
+            project: &'a Project,
+            path: &'a PathBuf,
+            checked: &FxHashMap<&PathBuf, &PathBuf>,
+            visited: &mut FxHashSet<&'a PathBuf>,
+            order: &mut Vec<PathBuf>,
+        ) {
+            if !visited.insert(path) {
+                return;
+            }
+            for module in project.includes.get(path).into_iter().flatten() {
+                visit(project, module, checked, visited, order);
+            }
+            if let Some(file) = checked.get(path) {
+                order.push((*file).clone());
+            }
+        }
+
+        for file in files {
+            visit(self, &self.canonical[file], &checked, &mut visited, &mut order);
+        }
+        order
+    }
+
+    /// Returns the source of a checked file.
+    pub(crate) fn source(&self, file: &Path) -> &str {
+        &self.sources[&self.canonical[file]]
+    }
+
+    /// Returns the canonical path of a checked file.
+    pub(crate) fn canonical_path(&self, file: &Path) -> &Path {
+        &self.canonical[file]
+    }
+
+    /// Hashes the source of a checked file together with every module it transitively includes
+    /// and `seed`, which covers everything else the result depends on.
+    pub(crate) fn fingerprint(&self, file: &Path, seed: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+
+        let mut stack = vec![&self.canonical[file]];
+        let mut visited = FxHashSet::default();
+        while let Some(path) = stack.pop() {
+            if !visited.insert(path) {
+                continue;
+            }
+            path.hash(&mut hasher);
+            self.sources.get(path).hash(&mut hasher);
+            stack.extend(self.includes.get(path).into_iter().flatten());
+        }
+
+        hasher.finish()
+    }
+}
+
+/// Fingerprints of the files whose last check found nothing to report, keyed by canonical path.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Cache {
+    version: String,
+    files: BTreeMap<PathBuf, u64>,
+}
+
+impl Cache {
+    /// Reads the cache at `path`. A missing or unreadable cache, or one written by another
+    /// version of mq-check, is treated as empty.
+    pub(crate) fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|input| serde_json::from_str::<Self>(&input).ok())
+            .filter(|cache| cache.version == env!("CARGO_PKG_VERSION"))
+            .unwrap_or_else(|| Self {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                files: BTreeMap::new(),
+            })
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> io::Result<()> {
+        let output = serde_json::to_string(self).map_err(io::Error::other)?;
+        std::fs::write(path, output).map_err(|e| io::Error::other(format!("writing cache {}: {}", path.display(), e)))
+    }
+
+    /// Returns `true` if `file` passed its last check with the same `fingerprint`.
+    pub(crate) fn is_clean(&self, file: &Path, fingerprint: u64) -> bool {
+        self.files.get(file) == Some(&fingerprint)
+    }
+
+    /// Records the result of checking `file`; only clean files are kept.
+    pub(crate) fn record(&mut self, file: &Path, fingerprint: u64, clean: bool) {
+        if clean {
+            self.files.insert(file.to_path_buf(), fingerprint);
+        } else {
+            self.files.remove(file);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_expand_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = write(dir.path(), "a.mq", ".h1");
+        let b = write(dir.path(), "lib/b.mq", ".h2");
+        write(dir.path(), "notes.md", "# Notes");
+
+        assert_eq!(
+            expand_paths(&[dir.path().to_path_buf()]).unwrap(),
+            vec![a.clone(), b.clone()]
+        );
+        assert_eq!(
+            expand_paths(&[dir.path().join("*.mq"), a.clone()]).unwrap(),
+            vec![a.clone()]
+        );
+        assert_eq!(
+            expand_paths(&[dir.path().join("missing.mq")]).unwrap(),
+            vec![dir.path().join("missing.mq")]
+        );
+    }
+
+    #[rstest]
+    #[case("include \"util\" | .h1", vec!["util"])]
+    #[case("import \"csv\" | def f(): include \"a\" | 1;", vec!["csv", "a"])]
+    #[case(".h1", vec![])]
+    fn test_module_names(#[case] code: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(module_names(code), expected);
+    }
+
+    #[test]
+    fn test_search_paths() {
+        let files = vec![
+            PathBuf::from("src/a.mq"),
+            PathBuf::from("src/b.mq"),
+            PathBuf::from("c.mq"),
+        ];
+        assert_eq!(
+            search_paths(&files, &[PathBuf::from("modules")]),
+            vec![PathBuf::from("modules"), PathBuf::from("src"), PathBuf::from(".")]
+        );
+        assert_eq!(
+            search_paths(&[], &[PathBuf::from("modules")]),
+            vec![PathBuf::from("modules")]
+        );
+    }
+
+    #[test]
+    fn test_project_dependency_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(dir.path(), "main.mq", "include \"util\" | twice(1)");
+        let util = write(dir.path(), "util.mq", "include \"base\" | def twice(x): x * 2;");
+        let base = write(dir.path(), "base.mq", "def one(): 1;");
+        let files = vec![main.clone(), util.clone(), base.clone()];
+
+        let project = Project::load(&files, &search_paths(&files, &[])).unwrap();
+        assert_eq!(project.files, vec![base, util, main]);
+    }
+
+    #[test]
+    fn test_project_fingerprint_covers_includes() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = write(dir.path(), "main.mq", "include \"util\" | twice(1)");
+        let util = write(dir.path(), "util.mq", "def twice(x): x * 2;");
+        let files = vec![main.clone()];
+        let paths = search_paths(&files, &[]);
+
+        let before = Project::load(&files, &paths).unwrap().fingerprint(&main, 0);
+        assert_eq!(Project::load(&files, &paths).unwrap().fingerprint(&main, 0), before);
+        assert_ne!(Project::load(&files, &paths).unwrap().fingerprint(&main, 1), before);
+
+        std::fs::write(&util, "def twice(x): x + x;").unwrap();
+        assert_ne!(Project::load(&files, &paths).unwrap().fingerprint(&main, 0), before);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CACHE_FILE_NAME);
+
+        let mut cache = Cache::load(&path);
+        cache.record(Path::new("a.mq"), 1, true);
+        cache.record(Path::new("b.mq"), 2, false);
+        cache.save(&path).unwrap();
+
+        let cache = Cache::load(&path);
+        assert!(cache.is_clean(Path::new("a.mq"), 1));
+        assert!(!cache.is_clean(Path::new("a.mq"), 2));
+        assert!(!cache.is_clean(Path::new("b.mq"), 2));
+    }
+}
@@ -0,0 +1,702 @@
+//! Signature files (`.mqi`) that declare the types of functions.
+//!
+//! A declared signature is used instead of inferring a function from its body. This keeps
+//! checking fast for code that includes large modules, and gives a type to functions that have
+//! no mq body at all, such as those registered by a host application.
+//!
+//! ```text
+//! # Functions declared at the top level are available to `include` and unqualified calls.
+//! def twice(x: number): number;
+//! def first_or(['a], 'a): 'a;
+//!
+//! # Functions in a `module` block are used for qualified calls such as `csv::csv_parse(...)`.
+//! module csv:
+//!   def csv_parse(string, bool): [[string]];
+//! end
+//! ```
+//!
+//! Types are written the way the checker displays them:
+//!
+//! | Syntax | Type |
+//! |---|---|
+//! | `number`, `string`, `bool`, `symbol`, `none`, `markdown`, `bytes`, `int`, `float`, `never` | Scalars |
+//! | `dynamic` | A value whose type is not checked |
+//! | `'a` | A type variable, scoped to its declaration |
+//! | `[T]` | Array |
+//! | `(T, U)` | Tuple |
+//! | `{K: V}` | Dict |
+//! | `{name: T, other: U}`, `{name: T \| 'r}` | Closed and open records |
+//! | `(T, U) -> R` | Function |
+//! | `T \| U` | Union |
+//!
+//! Declaring a name more than once declares overloads. Declarations replace the builtin
+//! overloads of the same name, so a signature file can also describe the builtin set.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use mq_hir::{Hir, SourceId, SymbolKind};
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::TypeEnv;
+use crate::infer::InferenceContext;
+use crate::types::{Type, TypeVarId};
+
+/// An error in the syntax of a signature file.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{line}:{column}: {message}")]
+pub struct SignatureError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Function signatures parsed from one or more `.mqi` files.
+#[derive(Debug, Clone, Default)]
+pub struct Signatures {
+    declarations: Vec<Declaration>,
+}
+
+impl Signatures {
+    /// Parses the contents of a signature file.
+    pub fn parse(input: &str) -> Result<Self, SignatureError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let mut declarations = Vec::new();
+        parser.parse_items("", &mut declarations)?;
+
+        if let Some(token) = parser.peek() {
+            return Err(token.error(format!("unexpected `{}`", token.kind)));
+        }
+
+        Ok(Self { declarations })
+    }
+
+    /// Adds the declarations of `other`, e.g. when loading several signature files.
+    pub fn extend(&mut self, other: Signatures) {
+        self.declarations.extend(other.declarations);
+    }
+
+    /// Returns `true` if a function named `name` is declared.
+    ///
+    /// Functions declared in a `module` block are named `module::function`.
+    pub fn declares(&self, name: &str) -> bool {
+        self.declarations.iter().any(|declaration| declaration.name == name)
+    }
+
+    /// Returns the number of declarations.
+    pub fn len(&self) -> usize {
+        self.declarations.len()
+    }
+
+    /// Returns `true` if nothing is declared.
+    pub fn is_empty(&self) -> bool {
+        self.declarations.is_empty()
+    }
+
+    /// Declares every function in the inference context, replacing builtin overloads of the same name.
+    pub(crate) fn register(&self, ctx: &mut InferenceContext) {
+        let mut overloads: FxHashMap<&str, Vec<Type>> = FxHashMap::default();
+
+        for declaration in &self.declarations {
+            let mut vars = FxHashMap::default();
+            let params = declaration
+                .params
+                .iter()
+                .map(|param| param.to_type(ctx, &mut vars))
+                .collect();
+            let ret = declaration.ret.to_type(ctx, &mut vars);
+            overloads
+                .entry(declaration.name.as_str())
+                .or_default()
+                .push(Type::function(params, ret));
+        }
+
+        for (name, overloads) in overloads {
+            ctx.declare_function(name, overloads);
+        }
+    }
+}
+
+/// Formats the functions that the module at `source_id` exports as the contents of a signature
+/// file, with the types in `types`.
+///
+/// A function with default parameters gets an overload for each number of arguments it accepts.
+pub(crate) fn format_declarations(hir: &Hir, types: &TypeEnv, source_id: SourceId) -> String {
+    let mut functions = hir
+        .exports(source_id)
+        .into_iter()
+        .filter_map(
+            |(symbol_id, symbol)| match (&symbol.kind, &symbol.value, types.get(&symbol_id)) {
+                (SymbolKind::Function(params), Some(name), Some(scheme)) => match &scheme.ty {
+                    Type::Function(param_tys, ret) if param_tys.len() == params.len() => {
+                        Some((symbol.source.text_range, name, params, param_tys, ret))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            },
+        )
+        .collect::<Vec<_>>();
+    functions.sort_by_key(|(range, ..)| *range);
+
+    let mut output = String::new();
+    for (_, name, params, param_tys, ret) in functions {
+        let required = params.iter().take_while(|param| !param.has_default).count();
+
+        for arity in required..=params.len() {
+            // Type variables are scoped to a declaration, so each one is renumbered from 'a.
+            let mut var_map = FxHashMap::default();
+            let mut counter = 0;
+            let params_str = params
+                .iter()
+                .zip(param_tys)
+                .take(arity)
+                .map(|(param, ty)| format!("{}: {}", param.name, ty.fmt_renumbered(&mut var_map, &mut counter)))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let ret_str = ret.fmt_renumbered(&mut var_map, &mut counter);
+            let _ = writeln!(output, "def {name}({params_str}): {ret_str};");
+        }
+    }
+
+    output
+}
+
+/// A single `def` in a signature file.
+#[derive(Debug, Clone)]
+struct Declaration {
+    name: String,
+    params: Vec<SignatureType>,
+    ret: SignatureType,
+}
+
+/// A type as written in a signature file, before its variables are allocated.
+#[derive(Debug, Clone)]
+enum SignatureType {
+    Concrete(Type),
+    Var(String),
+    Array(Box<SignatureType>),
+    Tuple(Vec<SignatureType>),
+    Dict(Box<SignatureType>, Box<SignatureType>),
+    Function(Vec<SignatureType>, Box<SignatureType>),
+    Union(Vec<SignatureType>),
+    Record(Vec<(String, SignatureType)>, Option<String>),
+}
+
+impl SignatureType {
+    fn to_type(&self, ctx: &mut InferenceContext, vars: &mut FxHashMap<String, TypeVarId>) -> Type {
+        match self {
+            SignatureType::Concrete(ty) => ty.clone(),
+            SignatureType::Var(name) => Type::Var(var(ctx, vars, name)),
+            SignatureType::Array(elem) => Type::array(elem.to_type(ctx, vars)),
+            SignatureType::Tuple(elems) => Type::tuple(elems.iter().map(|elem| elem.to_type(ctx, vars)).collect()),
+            SignatureType::Dict(key, value) => Type::dict(key.to_type(ctx, vars), value.to_type(ctx, vars)),
+            SignatureType::Function(params, ret) => Type::function(
+                params.iter().map(|param| param.to_type(ctx, vars)).collect(),
+                ret.to_type(ctx, vars),
+            ),
+            SignatureType::Union(members) => {
+                Type::union(members.iter().map(|member| member.to_type(ctx, vars)).collect())
+            }
+            SignatureType::Record(fields, rest) => {
+                let fields = fields
+                    .iter()
+                    .map(|(name, ty)| (name.clone(), ty.to_type(ctx, vars)))
+                    .collect::<BTreeMap<_, _>>();
+                let rest = match rest {
+                    Some(name) => Type::Var(var(ctx, vars, name)),
+                    None => Type::RowEmpty,
+                };
+                Type::record(fields, rest)
+            }
+        }
+    }
+}
+
+fn var(ctx: &mut InferenceContext, vars: &mut FxHashMap<String, TypeVarId>, name: &str) -> TypeVarId {
+    *vars.entry(name.to_string()).or_insert_with(|| ctx.fresh_var())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Ident(String),
+    Var(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    LBrace,
+    RBrace,
+    Comma,
+    Colon,
+    Semicolon,
+    Pipe,
+    Arrow,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(name) => write!(f, "{name}"),
+            TokenKind::Var(name) => write!(f, "'{name}"),
+            TokenKind::LParen => write!(f, "("),
+            TokenKind::RParen => write!(f, ")"),
+            TokenKind::LBracket => write!(f, "["),
+            TokenKind::RBracket => write!(f, "]"),
+            TokenKind::LBrace => write!(f, "{{"),
+            TokenKind::RBrace => write!(f, "}}"),
+            TokenKind::Comma => write!(f, ","),
+            TokenKind::Colon => write!(f, ":"),
+            TokenKind::Semicolon => write!(f, ";"),
+            TokenKind::Pipe => write!(f, "|"),
+            TokenKind::Arrow => write!(f, "->"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn error(&self, message: String) -> SignatureError {
+        SignatureError {
+            message,
+            line: self.line,
+            column: self.column,
+        }
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, SignatureError> {
+    let mut tokens = Vec::new();
+
+    for (line_index, line) in input.lines().enumerate() {
+        let mut chars = line.char_indices().peekable();
+
+        while let Some((offset, c)) = chars.next() {
+            let column = line[..offset].chars().count() + 1;
+            let kind = match c {
+                '#' => break,
+                c if c.is_whitespace() => continue,
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                '[' => TokenKind::LBracket,
+                ']' => TokenKind::RBracket,
+                '{' => TokenKind::LBrace,
+                '}' => TokenKind::RBrace,
+                ',' => TokenKind::Comma,
+                ':' => TokenKind::Colon,
+                ';' => TokenKind::Semicolon,
+                '|' => TokenKind::Pipe,
+                '-' if chars.next_if(|(_, c)| *c == '>').is_some() => TokenKind::Arrow,
+                '\'' | '_' => {
+                    let mut name = if c == '_' { String::from("_") } else { String::new() };
+                    while let Some((_, c)) = chars.next_if(|(_, c)| is_ident_char(*c)) {
+                        name.push(c);
+                    }
+                    match c {
+                        '\'' if name.is_empty() => {
+                            return Err(SignatureError {
+                                message: "expected a type variable name after `'`".to_string(),
+                                line: line_index + 1,
+                                column,
+                            });
+                        }
+                        '\'' => TokenKind::Var(name),
+                        _ => TokenKind::Ident(name),
+                    }
+                }
+                c if c.is_alphabetic() => {
+                    let mut name = String::from(c);
+                    while let Some((_, c)) = chars.next_if(|(_, c)| is_ident_char(*c)) {
+                        name.push(c);
+                    }
+                    TokenKind::Ident(name)
+                }
+                c => {
+                    return Err(SignatureError {
+                        message: format!("unexpected character `{c}`"),
+                        line: line_index + 1,
+                        column,
+                    });
+                }
+            };
+
+            tokens.push(Token {
+                kind,
+                line: line_index + 1,
+                column,
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self, offset: usize) -> Option<&TokenKind> {
+        self.tokens.get(self.pos + offset).map(|token| &token.kind)
+    }
+
+    fn next(&mut self) -> Result<Token, SignatureError> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| self.eof_error())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek_kind(0) == Some(kind) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), SignatureError> {
+        let token = self.next()?;
+        if token.kind == kind {
+            Ok(())
+        } else {
+            Err(token.error(format!("expected `{kind}`, found `{}`", token.kind)))
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, SignatureError> {
+        let token = self.next()?;
+        match &token.kind {
+            TokenKind::Ident(name) => Ok(name.clone()),
+            kind => Err(token.error(format!("expected a name, found `{kind}`"))),
+        }
+    }
+
+    fn eof_error(&self) -> SignatureError {
+        let (line, column) = self
+            .tokens
+            .last()
+            .map(|token| (token.line, token.column + token.kind.to_string().chars().count()))
+            .unwrap_or((1, 1));
+        SignatureError {
+            message: "unexpected end of input".to_string(),
+            line,
+            column,
+        }
+    }
+
+    /// Parses `def` and `module` items until the end of input or a module's `end`.
+    fn parse_items(&mut self, prefix: &str, declarations: &mut Vec<Declaration>) -> Result<(), SignatureError> {
+        while let Some(token) = self.peek() {
+            match &token.kind {
+                TokenKind::Ident(keyword) if keyword == "def" => {
+                    self.pos += 1;
+                    declarations.push(self.parse_def(prefix)?);
+                }
+                TokenKind::Ident(keyword) if keyword == "module" => {
+                    self.pos += 1;
+                    let name = self.expect_ident()?;
+                    self.expect(TokenKind::Colon)?;
+                    self.parse_items(&format!("{prefix}{name}::"), declarations)?;
+
+                    let token = self.next()?;
+                    if token.kind != TokenKind::Ident("end".to_string()) {
+                        return Err(token.error(format!("expected `end`, found `{}`", token.kind)));
+                    }
+                }
+                TokenKind::Ident(keyword) if keyword == "end" && !prefix.is_empty() => return Ok(()),
+                kind => return Err(token.error(format!("expected `def` or `module`, found `{kind}`"))),
+            }
+        }
+
+        if prefix.is_empty() {
+            Ok(())
+        } else {
+            Err(self.eof_error())
+        }
+    }
+
+    /// Parses `name(param, ...): type;` after `def`.
+    fn parse_def(&mut self, prefix: &str) -> Result<Declaration, SignatureError> {
+        let name = self.expect_ident()?;
+        self.expect(TokenKind::LParen)?;
+
+        let mut params = Vec::new();
+        while !self.eat(&TokenKind::RParen) {
+            if !params.is_empty() {
+                self.expect(TokenKind::Comma)?;
+            }
+            // An optional parameter name: `x: number`.
+            if matches!(self.peek_kind(0), Some(TokenKind::Ident(_))) && self.peek_kind(1) == Some(&TokenKind::Colon) {
+                self.pos += 2;
+            }
+            params.push(self.parse_type(false)?);
+        }
+
+        self.expect(TokenKind::Colon)?;
+        let ret = self.parse_type(false)?;
+        self.expect(TokenKind::Semicolon)?;
+
+        Ok(Declaration {
+            name: format!("{prefix}{name}"),
+            params,
+            ret,
+        })
+    }
+
+    /// Parses a type, including unions.
+    ///
+    /// In a record field, `| 'r }` ends the record with an open row instead of adding a union member.
+    fn parse_type(&mut self, in_record: bool) -> Result<SignatureType, SignatureError> {
+        let mut members = vec![self.parse_atom()?];
+
+        while self.peek_kind(0) == Some(&TokenKind::Pipe) {
+            if in_record
+                && matches!(self.peek_kind(1), Some(TokenKind::Var(_)))
+                && self.peek_kind(2) == Some(&TokenKind::RBrace)
+            {
+                break;
+            }
+            self.pos += 1;
+            members.push(self.parse_atom()?);
+        }
+
+        Ok(if members.len() == 1 {
+            members.remove(0)
+        } else {
+            SignatureType::Union(members)
+        })
+    }
+
+    fn parse_atom(&mut self) -> Result<SignatureType, SignatureError> {
+        let token = self.next()?;
+
+        match &token.kind {
+            TokenKind::Var(name) => Ok(SignatureType::Var(name.clone())),
+            TokenKind::Ident(name) => {
+                let ty = scalar_type(name).ok_or_else(|| token.error(format!("unknown type `{name}`")))?;
+                // `dynamic()` is how the checker displays a dynamic type.
+                if ty == Type::Dynamic
+                    && self.peek_kind(0) == Some(&TokenKind::LParen)
+                    && self.peek_kind(1) == Some(&TokenKind::RParen)
+                {
+                    self.pos += 2;
+                }
+                Ok(SignatureType::Concrete(ty))
+            }
+            TokenKind::LBracket => {
+                let elem = self.parse_type(false)?;
+                self.expect(TokenKind::RBracket)?;
+                Ok(SignatureType::Array(Box::new(elem)))
+            }
+            TokenKind::LParen => {
+                let mut elems = Vec::new();
+                while !self.eat(&TokenKind::RParen) {
+                    if !elems.is_empty() {
+                        self.expect(TokenKind::Comma)?;
+                    }
+                    elems.push(self.parse_type(false)?);
+                }
+
+                if self.eat(&TokenKind::Arrow) {
+                    let ret = self.parse_atom()?;
+                    Ok(SignatureType::Function(elems, Box::new(ret)))
+                } else if elems.len() == 1 {
+                    Ok(elems.remove(0))
+                } else {
+                    Ok(SignatureType::Tuple(elems))
+                }
+            }
+            TokenKind::LBrace => self.parse_braced(),
+            kind => Err(token.error(format!("expected a type, found `{kind}`"))),
+        }
+    }
+
+    /// Parses a dict or record type after `{`.
+    fn parse_braced(&mut self) -> Result<SignatureType, SignatureError> {
+        let is_record = match (self.peek_kind(0), self.peek_kind(1)) {
+            (Some(TokenKind::RBrace | TokenKind::Pipe), _) => true,
+            (Some(TokenKind::Ident(name)), Some(TokenKind::Colon)) => scalar_type(name).is_none(),
+            _ => false,
+        };
+
+        if !is_record {
+            let key = self.parse_type(false)?;
+            self.expect(TokenKind::Colon)?;
+            let value = self.parse_type(false)?;
+            self.expect(TokenKind::RBrace)?;
+            return Ok(SignatureType::Dict(Box::new(key), Box::new(value)));
+        }
+
+        let mut fields = Vec::new();
+        loop {
+            if self.eat(&TokenKind::RBrace) {
+                return Ok(SignatureType::Record(fields, None));
+            }
+            if self.eat(&TokenKind::Pipe) {
+                let token = self.next()?;
+                let TokenKind::Var(rest) = token.kind else {
+                    return Err(token.error(format!("expected a row variable, found `{}`", token.kind)));
+                };
+                self.expect(TokenKind::RBrace)?;
+                return Ok(SignatureType::Record(fields, Some(rest)));
+            }
+            if !fields.is_empty() {
+                self.expect(TokenKind::Comma)?;
+            }
+            let name = self.expect_ident()?;
+            self.expect(TokenKind::Colon)?;
+            fields.push((name, self.parse_type(true)?));
+        }
+    }
+}
+
+fn scalar_type(name: &str) -> Option<Type> {
+    Some(match name {
+        "int" => Type::Int,
+        "float" => Type::Float,
+        "number" => Type::Number,
+        "string" => Type::String,
+        "bool" => Type::Bool,
+        "symbol" => Type::Symbol,
+        "none" => Type::None,
+        "markdown" => Type::Markdown,
+        "bytes" => Type::Bytes,
+        "never" => Type::Never,
+        "dynamic" => Type::Dynamic,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn declared_types(input: &str) -> Vec<(String, String)> {
+        let signatures = Signatures::parse(input).unwrap();
+        let mut ctx = InferenceContext::new();
+        signatures
+            .declarations
+            .iter()
+            .map(|declaration| {
+                let mut vars = FxHashMap::default();
+                let params = declaration
+                    .params
+                    .iter()
+                    .map(|param| param.to_type(&mut ctx, &mut vars))
+                    .collect();
+                let ret = declaration.ret.to_type(&mut ctx, &mut vars);
+                (
+                    declaration.name.clone(),
+                    Type::function(params, ret).display_renumbered(),
+                )
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::scalars("def f(number, string): bool;", "(number, string) -> bool")]
+    #[case::param_names("def f(x: number, y: [string]): none;", "(number, [string]) -> none")]
+    #[case::type_vars("def f(['a], ('a) -> 'b): ['b];", "(['a], ('a) -> 'b) -> ['b]")]
+    #[case::dict("def f({string: number}): dynamic;", "({string: number}) -> dynamic()")]
+    #[case::dynamic_call_form("def f(): dynamic();", "() -> dynamic()")]
+    #[case::tuple("def f((string, number)): markdown;", "((string, number)) -> markdown")]
+    #[case::union("def f(string | none): string;", "((string | none)) -> string")]
+    #[case::grouped_union("def f([(string | number)]): none;", "([(number | string)]) -> none")]
+    #[case::closed_record(
+        "def f({name: string, age: number}): string;",
+        "({age: number, name: string}) -> string"
+    )]
+    #[case::open_record("def f({name: string | 'r}): string;", "({name: string | 'a}) -> string")]
+    #[case::record_union_field("def f({name: string | none}): string;", "({name: (string | none)}) -> string")]
+    #[case::empty_open_record("def f({| 'r}): bytes;", "({| 'a}) -> bytes")]
+    fn test_parse_types(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(declared_types(input), vec![("f".to_string(), expected.to_string())]);
+    }
+
+    #[test]
+    fn test_parse_modules_and_comments() {
+        let input = "# Host functions\ndef host(string): string;\nmodule csv:\n  def csv_parse(string, bool): [[string]]; # rows\n  module inner:\n    def f(): none;\n  end\nend\n";
+        let names = declared_types(input)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["host", "csv::csv_parse", "csv::inner::f"]);
+    }
+
+    #[test]
+    fn test_type_vars_are_scoped_to_declaration() {
+        let input = "def f('a): 'a;\ndef g('a): number;";
+        let signatures = Signatures::parse(input).unwrap();
+        let mut ctx = InferenceContext::new();
+        signatures.register(&mut ctx);
+
+        let f = ctx.get_builtin_overloads("f").unwrap()[0].clone();
+        let g = ctx.get_builtin_overloads("g").unwrap()[0].clone();
+        assert!(f.free_vars().iter().all(|var| !g.free_vars().contains(var)));
+    }
+
+    #[rstest]
+    #[case::unknown_type("def f(strng): none;", "1:7: unknown type `strng`")]
+    #[case::missing_semicolon("def f(): none\ndef g(): none;", "2:1: expected `;`, found `def`")]
+    #[case::unterminated_module("module m:\n  def f(): none;", "2:17: unexpected end of input")]
+    #[case::unexpected_character("def f(): none?;", "1:14: unexpected character `?`")]
+    #[case::stray_item("let x;", "1:1: expected `def` or `module`, found `let`")]
+    #[case::bad_row("def f({| string}): none;", "1:10: expected a row variable, found `string`")]
+    fn test_parse_errors(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(Signatures::parse(input).unwrap_err().to_string(), expected);
+    }
+
+    #[test]
+    fn test_format_declarations() {
+        let mut hir = Hir::default();
+        let (source_id, _) = hir.add_code(
+            None,
+            "def ident(x): x;\ndef shout(s, suffix = \"!\"): [s, suffix];\ndef _hidden(x): x;\nlet answer = 42",
+        );
+        let mut checker = crate::TypeChecker::new();
+        checker.check(&hir);
+
+        let declarations = checker.declarations(&hir, source_id);
+        assert_eq!(
+            declarations,
+            "def ident(x: 'a): 'a;\ndef shout(s: 'a): ('a, string);\ndef shout(s: 'a, suffix: string): ('a, string);\n"
+        );
+        assert_eq!(Signatures::parse(&declarations).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_register_replaces_builtin_overloads() {
+        let signatures = Signatures::parse("def len(string): number;\ndef len(bytes): number;").unwrap();
+        let mut ctx = InferenceContext::new();
+        crate::builtin::register_all(&mut ctx);
+        signatures.register(&mut ctx);
+
+        assert!(ctx.is_declared("len"));
+        assert_eq!(
+            ctx.get_builtin_overloads("len").unwrap(),
+            &[
+                Type::function(vec![Type::String], Type::Number),
+                Type::function(vec![Type::Bytes], Type::Number),
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Diagnostics for dynamic constructs, reported according to [`Strictness`].
+//!
+//! Some constructs are valid mq but cannot be typed statically. They are not errors by
+//! default; teams adopting typing gradually can opt in to having them reported:
+//!
+//! | Construct | Example |
+//! |---|---|
+//! | Untyped dict access | `x["key"]` or `x[:key]` where the type of `x` is not inferred |
+//! | `type()` branching | `if (type(x) == "string"): ...` or `match (type(x)): ...` |
+
+use mq_hir::{Hir, SymbolId, SymbolKind};
+
+use crate::constraint::{ChildrenIndex, get_non_keyword_children};
+use crate::infer::InferenceContext;
+use crate::unify::range_to_span;
+use crate::{Strictness, TypeError};
+
+/// Reports the dynamic constructs in user code, or nothing when `strictness` is [`Strictness::Off`].
+pub(crate) fn check_dynamic_constructs(
+    hir: &Hir,
+    ctx: &InferenceContext,
+    children_index: &ChildrenIndex,
+    strictness: Strictness,
+) -> Vec<TypeError> {
+    if strictness == Strictness::Off {
+        return Vec::new();
+    }
+
+    let mut errors = Vec::new();
+
+    for (symbol_id, symbol) in hir.symbols() {
+        if !matches!(symbol.kind, SymbolKind::Call) || hir.is_builtin_symbol(symbol) {
+            continue;
+        }
+
+        let found = untyped_dict_access(hir, ctx, children_index, symbol_id)
+            .or_else(|| type_branching(hir, children_index, symbol_id));
+
+        if let Some((construct, context)) = found {
+            let range = symbol.source.text_range;
+            errors.push(TypeError::DynamicConstruct {
+                construct,
+                strictness,
+                span: range.as_ref().map(range_to_span),
+                location: range,
+                context: Some(context),
+            });
+        }
+    }
+
+    errors
+}
+
+/// Detects a key access (`x["key"]`, `x[:key]`) on a variable whose type is not inferred.
+///
+/// Mirrors constraint generation, which treats a call on a non-function variable with a
+/// single string or symbol argument as a record field access.
+fn untyped_dict_access(
+    hir: &Hir,
+    ctx: &InferenceContext,
+    children_index: &ChildrenIndex,
+    symbol_id: SymbolId,
+) -> Option<(String, String)> {
+    let def_id = hir.resolve_reference_symbol(symbol_id)?;
+    let def_symbol = hir.symbol(def_id)?;
+    if !(def_symbol.is_variable()
+        || def_symbol.is_parameter()
+        || matches!(def_symbol.kind, SymbolKind::PatternVariable { .. }))
+    {
+        return None;
+    }
+
+    let [key_id] = get_non_keyword_children(hir, symbol_id, children_index)[..] else {
+        return None;
+    };
+    let key = hir.symbol(key_id)?;
+    if !matches!(key.kind, SymbolKind::String | SymbolKind::Symbol) {
+        return None;
+    }
+
+    let receiver_ty = ctx.resolve_type(ctx.get_symbol_type(def_id)?);
+    if !(receiver_ty.is_var() || receiver_ty.is_dynamic()) {
+        return None;
+    }
+
+    let name = def_symbol.value.as_deref().unwrap_or_default();
+    Some((
+        format!("untyped dict access on `{name}`"),
+        format!(
+            "the type of `{name}` is not known, so the key `{}` is not checked",
+            key.value.as_deref().unwrap_or_default()
+        ),
+    ))
+}
+
+/// Detects a call to the builtin `type()` whose result is compared or matched on.
+fn type_branching(hir: &Hir, children_index: &ChildrenIndex, symbol_id: SymbolId) -> Option<(String, String)> {
+    let symbol = hir.symbol(symbol_id)?;
+    if symbol.value.as_deref() != Some("type")
+        || hir
+            .resolve_reference_symbol(symbol_id)
+            .and_then(|def_id| hir.symbol(def_id))
+            .is_some_and(|def| !hir.is_builtin_symbol(def))
+    {
+        return None;
+    }
+
+    let parent = hir.symbol(symbol.parent?)?;
+    let branches = match parent.kind {
+        SymbolKind::BinaryOp => matches!(parent.value.as_deref(), Some("==" | "!=")),
+        SymbolKind::Match => get_non_keyword_children(hir, symbol.parent?, children_index).first() == Some(&symbol_id),
+        _ => false,
+    };
+    if !branches {
+        return None;
+    }
+
+    Some((
+        "branching on `type()`".to_string(),
+        "use a type predicate such as `is_string(x)` or `is_dict(x)` so the type can be narrowed".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Strictness, TypeChecker, TypeCheckerOptions, TypeError};
+    use mq_hir::Hir;
+    use rstest::rstest;
+
+    fn dynamic_constructs(code: &str, strictness: Strictness) -> Vec<String> {
+        let mut hir = Hir::default();
+        hir.add_code(None, code);
+        let mut checker = TypeChecker::with_options(TypeCheckerOptions {
+            strictness,
+            ..Default::default()
+        });
+
+        checker
+            .check(&hir)
+            .into_iter()
+            .filter_map(|error| match error {
+                TypeError::DynamicConstruct { construct, .. } => Some(construct),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case::param_string_key("def f(x): x[\"a\"];", vec!["untyped dict access on `x`"])]
+    #[case::param_symbol_key("def f(x): x[:a];", vec!["untyped dict access on `x`"])]
+    #[case::typed_dict("let d = {\"a\": 1} | d[\"a\"]", vec![])]
+    #[case::array_index("def f(xs): xs[0];", vec![])]
+    #[case::type_comparison("def f(x): if (type(x) == \"string\"): 1 else: 2;", vec!["branching on `type()`"])]
+    #[case::type_match("def f(x): match (type(x)): | \"string\": 1 | _: 2 end;", vec!["branching on `type()`"])]
+    #[case::type_predicate("def f(x): if (is_string(x)): 1 else: 2;", vec![])]
+    #[case::type_value("def f(x): type(x);", vec![])]
+    fn test_dynamic_constructs(#[case] code: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(dynamic_constructs(code, Strictness::Warn), expected);
+    }
+
+    #[test]
+    fn test_dynamic_constructs_off() {
+        assert!(dynamic_constructs("def f(x): x[\"a\"] | type(x) == \"dict\";", Strictness::Off).is_empty());
+    }
+
+    #[test]
+    fn test_dynamic_construct_strictness() {
+        let mut hir = Hir::default();
+        hir.add_code(None, "def f(x): x[\"a\"];");
+        let mut checker = TypeChecker::with_options(TypeCheckerOptions {
+            strictness: Strictness::Strict,
+            ..Default::default()
+        });
+
+        let errors = checker.check(&hir);
+        assert!(matches!(
+            errors.as_slice(),
+            [TypeError::DynamicConstruct {
+                strictness: Strictness::Strict,
+                location: Some(_),
+                ..
+            }]
+        ));
+    }
+}
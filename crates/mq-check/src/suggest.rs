@@ -0,0 +1,43 @@
+//! "Did you mean" suggestions for misspelled names.
+
+/// Maximum number of suggestions offered for a single name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the `candidates` close enough to `name` to be likely misspellings of it,
+/// closest first and without duplicates.
+pub(crate) fn did_you_mean<S: AsRef<str>>(name: &str, candidates: &[S]) -> Vec<String> {
+    // Allow roughly one edit per three characters, and at least two.
+    let max_distance = (name.chars().count() / 3).max(2);
+
+    let mut scored: Vec<(usize, &str)> = candidates
+        .iter()
+        .map(AsRef::as_ref)
+        .filter(|candidate| *candidate != name)
+        .map(|candidate| (strsim::levenshtein(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .collect();
+
+    scored.sort_unstable();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::closest_first("upcse", vec!["downcase", "upcase", "upcase"], vec!["upcase"])]
+    #[case::several("test", vec!["tests", "test3", "test2"], vec!["test2", "test3", "tests"])]
+    #[case::too_far("http", vec!["http_get", "http_post"], vec![])]
+    #[case::same_name_excluded("len", vec!["len"], vec![])]
+    #[case::at_most_three("ab", vec!["a", "b", "abc", "abd", "xab"], vec!["a", "abc", "abd"])]
+    fn test_did_you_mean(#[case] name: &str, #[case] candidates: Vec<&str>, #[case] expected: Vec<&str>) {
+        assert_eq!(did_you_mean(name, &candidates), expected);
+    }
+}
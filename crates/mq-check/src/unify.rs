@@ -67,6 +67,7 @@ pub fn unify(
 
         (Type::Var(_), _) | (_, Type::Var(_)) => {
             // Resolve type variable chains iteratively before unifying
+            let (t1_orig, t2_orig) = (t1, t2);
             let t1 = resolve_var_chain(ctx, t1);
             let t2 = resolve_var_chain(ctx, t2);
 
@@ -76,7 +77,14 @@ pub fn unify(
                 (Type::Var(var), ty) => (*var, ty),
                 (ty, Type::Var(var)) => (*var, ty),
                 _ => {
+                    // Both variables were already bound; a mismatch between their types is
+                    // explained by the constraints that bound them.
+                    let error_count = ctx.error_count();
                     unify(ctx, &t1, &t2, range, origin);
+                    if ctx.error_count() > error_count {
+                        let origins = ctx.type_origins(&[t1_orig.clone(), t2_orig.clone()]);
+                        ctx.attach_origins(error_count, &origins);
+                    }
                     return;
                 }
             };
@@ -96,6 +104,7 @@ pub fn unify(
 
             // Bind the type variable
             ctx.bind_type_var(var, ty.clone());
+            ctx.record_binding_origin(var, range, origin);
         }
 
         // Arrays
@@ -202,21 +211,23 @@ pub fn unify(
 
         // Union types: a union can unify with a non-union type if any of its members can.
         (Type::Union(types), other) | (other, Type::Union(types)) => {
-            // Check if the other type matches any member of the union
-            let matches_any = types.iter().any(|t| {
-                // Try to check if types can match without adding errors
-                match (t, other) {
-                    (t1, t2) if std::mem::discriminant(t1) == std::mem::discriminant(t2) => true,
-                    (Type::Var(_), _) | (_, Type::Var(_)) => true,
-                    _ => false,
+            let mut same_shape = types.iter().filter(|t| same_shape(t, other));
+
+            match (same_shape.next(), same_shape.next()) {
+                // Exactly one member has the shape of the other type (e.g. `[a]` in
+                // `string | [a]` against `[number]`): unify with it so that the type
+                // variables inside the member are bound and its contents are checked.
+                (Some(member), None) => unify(ctx, member, other, range, origin),
+                // Several members share the shape, e.g. `[string] | [number]`; allow it.
+                (Some(_), Some(_)) => {}
+                // A type variable member can stand for the other type; otherwise no
+                // member of the union can unify with it.
+                (None, _) => {
+                    if !types.iter().any(|t| t.is_var()) && !other.is_var() {
+                        ctx.report_mismatch(t1, t2, range, origin);
+                    }
                 }
-            });
-
-            if !matches_any {
-                // No member of the union can unify with the other type - report error
-                ctx.report_mismatch(t1, t2, range, origin);
             }
-            // If at least one member matches, allow it (union type semantics)
         }
 
         // Mismatch
@@ -226,6 +237,18 @@ pub fn unify(
     }
 }
 
+/// Returns true if `t1` and `t2` are built from the same type constructor, counting the
+/// pairs that [`unify`] treats as compatible (tuples with arrays, records with dicts).
+fn same_shape(t1: &Type, t2: &Type) -> bool {
+    matches!(
+        (t1, t2),
+        (Type::Tuple(_), Type::Array(_))
+            | (Type::Array(_), Type::Tuple(_))
+            | (Type::Record(..), Type::Dict(..))
+            | (Type::Dict(..), Type::Record(..))
+    ) || std::mem::discriminant(t1) == std::mem::discriminant(t2)
+}
+
 /// Unifies two record types using row polymorphism.
 ///
 /// Given `Record(f1, r1)` and `Record(f2, r2)`:
@@ -675,6 +698,34 @@ mod tests {
         assert!(ctx.take_errors().is_empty());
     }
 
+    #[test]
+    fn test_unify_union_member_binds_vars() {
+        let mut ctx = InferenceContext::new();
+        let a = ctx.fresh_var();
+        let union = Type::union(vec![Type::String, Type::array(Type::Var(a))]);
+
+        unify(
+            &mut ctx,
+            &union,
+            &Type::array(Type::Number),
+            None,
+            &ConstraintOrigin::General,
+        );
+        assert!(ctx.take_errors().is_empty());
+        assert_eq!(ctx.resolve_type(&Type::Var(a)), Type::Number);
+
+        // The contents of the matching member are checked too.
+        let union = Type::union(vec![Type::String, Type::array(Type::Number)]);
+        unify(
+            &mut ctx,
+            &union,
+            &Type::array(Type::String),
+            None,
+            &ConstraintOrigin::General,
+        );
+        assert!(!ctx.take_errors().is_empty());
+    }
+
     #[test]
     fn test_unify_functions() {
         let mut ctx = InferenceContext::new();
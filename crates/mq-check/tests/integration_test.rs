@@ -1,6 +1,7 @@
 //! Integration tests for the type checker
 
-use mq_check::{TypeChecker, TypeError};
+use mq_check::signature::Signatures;
+use mq_check::{TypeChecker, TypeCheckerOptions, TypeError};
 use mq_hir::Hir;
 use rstest::rstest;
 
@@ -309,6 +310,13 @@ fn test_match_type_label_multi_arm() {
     false,
     "type-label arms without wildcard — not exhaustive"
 )]
+// A type-label arm covers every value of its type
+#[case::type_label_covers_type(r#"match (42): | :number: 1 end"#, true, "type-label covering the matched type")]
+#[case::type_label_guarded(
+    r#"match (42): | :number if (true): 1 end"#,
+    false,
+    "guarded type-label arm does not cover the matched type"
+)]
 fn test_match_exhaustiveness_type_label(#[case] code: &str, #[case] is_exhaustive: bool, #[case] description: &str) {
     let result = check_types(code);
     let has_exhaustiveness_error = result.iter().any(|e| matches!(e, TypeError::NonExhaustiveMatch { .. }));
@@ -319,6 +327,47 @@ fn test_match_exhaustiveness_type_label(#[case] code: &str, #[case] is_exhaustiv
     );
 }
 
+#[rstest]
+#[case::after_wildcard(r#"match (42): | _: 0 | 1: 1 end"#, 1)]
+#[case::after_var_binding(r#"match (42): | x: x | _: 0 end"#, 1)]
+#[case::duplicate_literal(r#"match (42): | 1: "a" | 1: "b" | _: "c" end"#, 1)]
+#[case::duplicate_string_literal(r#"match ("a"): | "a": 1 | "a": 2 | _: 3 end"#, 1)]
+#[case::bool_covered(r#"match (true): | true: 1 | false: 0 | _: 2 end"#, 1)]
+#[case::type_label_covered(r#"match (42): | :number: 1 | _: 0 end"#, 1)]
+#[case::literal_after_type_label(r#"match (42): | :string: 1 | 1: 2 | _: 0 end"#, 1)]
+#[case::pattern_type_never_matches(r#"match (42): | "a": 1 | _: 0 end"#, 1)]
+#[case::guarded_wildcard_then_literal(r#"match (42): | x if (x > 0): 1 | 0: 2 | _: 3 end"#, 0)]
+#[case::guarded_duplicate(r#"match (42): | 1 if (true): "a" | 1: "b" | _: "c" end"#, 0)]
+#[case::array_patterns(r#"match ([1, 2]): | []: 0 | [x]: 1 | _: 2 end"#, 0)]
+#[case::unknown_type(r#"def f(x): match (x): | 1: 1 | "a": 2 | _: 3 end;"#, 0)]
+fn test_match_unreachable_arms(#[case] code: &str, #[case] unreachable: usize) {
+    let result = check_types(code);
+    let found = result
+        .iter()
+        .filter(|e| matches!(e, TypeError::UnreachableCode { location: Some(_), .. }))
+        .count();
+    assert_eq!(found, unreachable, "{code}: {result:?}");
+}
+
+#[test]
+fn test_match_unreachable_arm_reported_without_exhaustiveness() {
+    let mut hir = Hir::default();
+    hir.add_code(None, r#"match (42): | _: 0 | 1: 1 end"#);
+    let mut checker = TypeChecker::with_options(TypeCheckerOptions {
+        no_exhaustive_patterns: true,
+        ..Default::default()
+    });
+
+    let errors = checker.check(&hir);
+    assert!(
+        matches!(
+            errors.as_slice(),
+            [TypeError::UnreachableCode { reason, location: Some(_), .. }] if reason.contains("matches every value")
+        ),
+        "{errors:?}"
+    );
+}
+
 // TODO: Enable when match is properly implemented in HIR
 // #[test]
 // fn test_match_union_with_arithmetic() {
@@ -1279,6 +1328,23 @@ fn test_union_consistent_return_type_ok(#[case] code: &str, #[case] description:
     assert!(result.is_empty(), "{}: {:?}", description, result);
 }
 
+// Builtins whose parameter is a union type, e.g. `len: (string | bytes | [a] | {k: v}) -> number`
+
+#[rstest]
+#[case::len_string(r#"len("abc") + 1"#, true)]
+#[case::len_array(r#"len([1, 2]) + 1"#, true)]
+#[case::len_dict(r#"len({"a": 1}) + 1"#, true)]
+#[case::len_bytes(r#"len(to_bytes("abc")) + 1"#, true)]
+#[case::len_number("len(1)", false)]
+#[case::len_bool("len(true)", false)]
+#[case::len_returns_number(r#"upcase(len("abc"))"#, false)]
+#[case::is_empty_string(r#"is_empty("") and true"#, true)]
+#[case::is_empty_array("is_empty([]) and true", true)]
+fn test_union_parameter_builtins(#[case] code: &str, #[case] should_succeed: bool) {
+    let result = check_types(code);
+    assert_eq!(result.is_empty(), should_succeed, "{code}: {result:?}");
+}
+
 // Match Union Type Tests (correct mq syntax)
 
 #[test]
@@ -2019,3 +2085,81 @@ fn test_recursive_selector(#[case] code: &str, #[case] should_succeed: bool, #[c
         result
     );
 }
+
+/// Type checks `code` with a module directory containing `modules` and the given signatures.
+fn check_types_with_signatures(
+    dir_name: &str,
+    modules: &[(&str, &str)],
+    signatures: &str,
+    code: &str,
+) -> Vec<TypeError> {
+    let dir = std::env::temp_dir().join(dir_name);
+    std::fs::create_dir_all(&dir).unwrap();
+    for (name, module) in modules {
+        std::fs::write(dir.join(format!("{name}.mq")), module).unwrap();
+    }
+
+    let resolver = mq_lang::DefaultModuleResolver::new(vec![dir.clone()]);
+    let mut hir = Hir::new(mq_lang::ModuleLoader::new(resolver));
+    hir.builtin.disabled = true;
+    hir.add_code(None, code);
+
+    let signatures = Signatures::parse(signatures).unwrap();
+    let errors = TypeChecker::new().with_signatures(signatures).check(&hir);
+    std::fs::remove_dir_all(&dir).ok();
+    errors
+}
+
+#[rstest]
+#[case::included_call("include \"util\" | twice(1)", true)]
+#[case::included_call_mismatch("include \"util\" | twice(\"a\")", false)]
+#[case::included_return_type("include \"util\" | upcase(twice(1))", false)]
+#[case::qualified_call("import \"util\" | util::twice(1)", true)]
+#[case::qualified_call_mismatch("import \"util\" | util::twice(\"a\")", false)]
+#[case::aliased_qualified_call_mismatch("import \"util\" as u | u::twice(\"a\")", false)]
+#[case::host_function("host_upcase(\"a\") + \"b\"", true)]
+#[case::host_function_mismatch("host_upcase(1)", false)]
+fn test_signatures(#[case] code: &str, #[case] should_succeed: bool) {
+    let signatures =
+        "def twice(number): number;\nmodule util:\n  def twice(number): number;\nend\ndef host_upcase(string): string;";
+    let errors = check_types_with_signatures(
+        &format!(
+            "mq_check_signatures_{}",
+            code.chars().filter(char::is_ascii_alphanumeric).collect::<String>()
+        ),
+        &[("util", "def twice(x): x * 2;")],
+        signatures,
+        code,
+    );
+    assert_eq!(errors.is_empty(), should_succeed, "Code='{}' Errors={:?}", code, errors);
+}
+
+#[test]
+fn test_signatures_without_declaration_leave_module_untyped() {
+    let errors = check_types_with_signatures(
+        "mq_check_signatures_undeclared",
+        &[("util", "def twice(x): x * 2;")],
+        "",
+        "include \"util\" | twice(\"a\")",
+    );
+    assert!(errors.is_empty(), "Errors={:?}", errors);
+}
+
+#[rstest]
+#[case::body_not_checked("def twice(x): 1 + true; | twice(1)", true)]
+#[case::call_mismatch("def twice(x): 1 + true; | twice(\"a\")", false)]
+#[case::undeclared_body_checked("def thrice(x): 1 + true; | thrice(1)", false)]
+fn test_signatures_of_definitions(#[case] code: &str, #[case] should_succeed: bool) {
+    let hir = create_hir(code);
+    let signatures = Signatures::parse("def twice(number): number;").unwrap();
+    let errors = TypeChecker::new().with_signatures(signatures).check(&hir);
+    assert_eq!(errors.is_empty(), should_succeed, "Code='{}' Errors={:?}", code, errors);
+}
+
+#[test]
+fn test_signatures_replace_builtin_overloads() {
+    let hir = create_hir("len(\"abc\")");
+    let signatures = Signatures::parse("def len([number]): number;").unwrap();
+    let errors = TypeChecker::new().with_signatures(signatures).check(&hir);
+    assert!(!errors.is_empty());
+}
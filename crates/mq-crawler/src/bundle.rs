@@ -0,0 +1,231 @@
+//! Combined output of every crawled page into a single bundle, for ingestion pipelines.
+//!
+//! - `jsonl`: one JSON object per page with `url`, `title`, `markdown`, and `fetched_at`.
+//! - `md`: the pages' markdown concatenated, each preceded by an HTML comment header
+//!   carrying the same metadata.
+
+use serde::Serialize;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BundleFormat {
+    Jsonl,
+    Markdown,
+}
+
+impl BundleFormat {
+    /// File name of the bundle inside the output directory.
+    pub fn file_name(self) -> &'static str {
+        match self {
+            BundleFormat::Jsonl => "bundle.jsonl",
+            BundleFormat::Markdown => "bundle.md",
+        }
+    }
+}
+
+/// One crawled page in a `jsonl` bundle.
+#[derive(Debug, Serialize)]
+pub struct BundleRecord<'a> {
+    pub url: &'a str,
+    pub title: Option<&'a str>,
+    pub markdown: &'a str,
+    /// RFC 3339 timestamp of when the page was fetched.
+    pub fetched_at: &'a str,
+}
+
+/// Appends crawled pages to a bundle file, or to stdout. Safe to share between crawl workers.
+pub struct BundleWriter {
+    format: BundleFormat,
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl std::fmt::Debug for BundleWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BundleWriter").field("format", &self.format).finish()
+    }
+}
+
+impl BundleWriter {
+    /// Creates the bundle file in `output_dir`, or writes to stdout when `output_dir` is `None`.
+    /// With `append`, pages are added to an existing bundle file instead of replacing it, so a
+    /// resumed crawl keeps the pages written before it was interrupted.
+    /// Returns the writer and the path of the created file, if any.
+    pub fn create(
+        format: BundleFormat,
+        output_dir: Option<&Path>,
+        append: bool,
+    ) -> Result<(Self, Option<PathBuf>), String> {
+        match output_dir {
+            Some(dir) => {
+                let path = dir.join(format.file_name());
+                let file = fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .append(append)
+                    .truncate(!append)
+                    .open(&path)
+                    .map_err(|e| format!("Failed to create bundle file '{}': {}", path.display(), e))?;
+                Ok((Self::new(format, Box::new(BufWriter::new(file))), Some(path)))
+            }
+            None => Ok((Self::new(format, Box::new(io::stdout())), None)),
+        }
+    }
+
+    pub fn new(format: BundleFormat, writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            format,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Appends one page to the bundle.
+    pub fn write_page(&self, record: &BundleRecord<'_>) -> Result<(), String> {
+        let entry = match self.format {
+            BundleFormat::Jsonl => {
+                let mut line =
+                    serde_json::to_string(record).map_err(|e| format!("Failed to serialize bundle record: {}", e))?;
+                line.push('\n');
+                line
+            }
+            BundleFormat::Markdown => {
+                let mut header = format!("<!-- url: {} -->\n", comment_safe(record.url));
+                if let Some(title) = record.title {
+                    header.push_str(&format!("<!-- title: {} -->\n", comment_safe(title)));
+                }
+                header.push_str(&format!("<!-- fetched_at: {} -->\n\n", record.fetched_at));
+                format!("{}{}\n\n", header, record.markdown.trim_end())
+            }
+        };
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer
+            .write_all(entry.as_bytes())
+            .map_err(|e| format!("Failed to write bundle entry for {}: {}", record.url, e))
+    }
+
+    pub fn flush(&self) -> Result<(), String> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.flush().map_err(|e| format!("Failed to flush bundle: {}", e))
+    }
+}
+
+/// Keeps metadata from terminating the HTML comment it is written into.
+fn comment_safe(text: &str) -> String {
+    text.replace("-->", "--&gt;").replace('\n', " ")
+}
+
+/// Returns the text of the page's `<title>` element, if it is not empty.
+pub fn extract_title(html: &str) -> Option<String> {
+    let document = scraper::Html::parse_document(html);
+    let selector = scraper::Selector::parse("title").expect("Failed to parse 'title' selector");
+    document
+        .select(&selector)
+        .next()
+        .map(|title| title.text().collect::<String>().trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use std::sync::Arc;
+
+    /// A cloneable in-memory writer so tests can inspect what was written.
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn write_pages(format: BundleFormat) -> String {
+        let buffer = SharedBuffer::default();
+        let writer = BundleWriter::new(format, Box::new(buffer.clone()));
+        writer
+            .write_page(&BundleRecord {
+                url: "https://example.com/a",
+                title: Some("Page A"),
+                markdown: "# A\n",
+                fetched_at: "2026-01-01T00:00:00Z",
+            })
+            .unwrap();
+        writer
+            .write_page(&BundleRecord {
+                url: "https://example.com/b",
+                title: None,
+                markdown: "b",
+                fetched_at: "2026-01-01T00:00:01Z",
+            })
+            .unwrap();
+        writer.flush().unwrap();
+        String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[test]
+    fn test_write_jsonl() {
+        let output = write_pages(BundleFormat::Jsonl);
+        let lines: Vec<serde_json::Value> = output.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                serde_json::json!({"url": "https://example.com/a", "title": "Page A", "markdown": "# A\n", "fetched_at": "2026-01-01T00:00:00Z"}),
+                serde_json::json!({"url": "https://example.com/b", "title": null, "markdown": "b", "fetched_at": "2026-01-01T00:00:01Z"}),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        assert_eq!(
+            write_pages(BundleFormat::Markdown),
+            "<!-- url: https://example.com/a -->\n<!-- title: Page A -->\n<!-- fetched_at: 2026-01-01T00:00:00Z -->\n\n# A\n\n\
+             <!-- url: https://example.com/b -->\n<!-- fetched_at: 2026-01-01T00:00:01Z -->\n\nb\n\n"
+        );
+    }
+
+    #[rstest]
+    #[case("<html><head><title> Hello </title></head></html>", Some("Hello"))]
+    #[case("<html><head><title></title></head></html>", None)]
+    #[case("<p>no title</p>", None)]
+    fn test_extract_title(#[case] html: &str, #[case] expected: Option<&str>) {
+        assert_eq!(extract_title(html).as_deref(), expected);
+    }
+
+    #[test]
+    fn test_comment_safe() {
+        assert_eq!(comment_safe("a --> b\nc"), "a --&gt; b c");
+    }
+
+    #[test]
+    fn test_create_in_output_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let (_, path) = BundleWriter::create(BundleFormat::Jsonl, Some(dir.path()), false).unwrap();
+        assert_eq!(path, Some(dir.path().join("bundle.jsonl")));
+    }
+
+    #[rstest]
+    #[case::replace(false, "new\n")]
+    #[case::append(true, "old\nnew\n")]
+    fn test_create_existing_bundle(#[case] append: bool, #[case] expected: &str) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.md");
+        fs::write(&path, "old\n").unwrap();
+
+        let (writer, _) = BundleWriter::create(BundleFormat::Markdown, Some(dir.path()), append).unwrap();
+        writer.writer.lock().unwrap().write_all(b"new\n").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    }
+}
@@ -1,5 +1,9 @@
-use crate::http_client::HttpClient;
+use crate::bundle::{BundleFormat, BundleRecord, BundleWriter};
+use crate::filter::UrlFilter;
+use crate::http_client::{ConditionalFetch, HttpClient};
+use crate::revalidate::{CachedPage, ValidatorCache, query_hash};
 use crate::robots::RobotsTxt;
+use crate::state::{CrawlState, UrlState, UrlStatus};
 use crossbeam::queue::SegQueue;
 use dashmap::mapref::entry::Entry;
 use dashmap::{DashMap, DashSet};
@@ -8,15 +12,21 @@ use mq_markdown::ConversionOptions;
 use scraper::{Html, Selector};
 use serde::{Deserialize, Serialize};
 use std::io::Write;
-use std::path::Path;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::{Duration, Instant};
 use std::{fs, io};
 use tokio::sync::{Notify, RwLock, Semaphore};
 use tokio::time::sleep;
 use url::Url;
 
+/// Upper bound for a robots.txt `Crawl-delay`, so a misconfigured site cannot stall the crawl.
+const MAX_ROBOTS_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+/// Minimum interval between two saves of the crawl state while crawling.
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Debug, Default)]
 pub enum OutputFormat {
     #[default]
@@ -30,6 +40,7 @@ pub struct CrawlResult {
     pub end_time: Option<Instant>,
     pub pages_crawled: usize,
     pub pages_skipped_robots: usize,
+    pub pages_not_modified: usize,
     pub pages_failed: usize,
     pub links_discovered: usize,
     pub total_pages_visited: usize,
@@ -39,6 +50,7 @@ pub struct CrawlResult {
 pub struct CrawlResultStats {
     pub pages_crawled: usize,
     pub pages_skipped_robots: usize,
+    pub pages_not_modified: usize,
     pub pages_failed: usize,
     pub links_discovered: usize,
     pub total_pages_visited: usize,
@@ -58,6 +70,7 @@ impl CrawlResult {
         CrawlResultStats {
             pages_crawled: self.pages_crawled,
             pages_skipped_robots: self.pages_skipped_robots,
+            pages_not_modified: self.pages_not_modified,
             pages_failed: self.pages_failed,
             links_discovered: self.links_discovered,
             total_pages_visited: self.total_pages_visited,
@@ -74,6 +87,7 @@ impl CrawlResult {
                 let _ = writeln!(handle, "\n=== Crawl Statistics ===");
                 let _ = writeln!(handle, "Pages crawled successfully: {}", self.pages_crawled);
                 let _ = writeln!(handle, "Pages skipped (robots.txt): {}", self.pages_skipped_robots);
+                let _ = writeln!(handle, "Pages not modified: {}", self.pages_not_modified);
                 let _ = writeln!(handle, "Pages failed: {}", self.pages_failed);
                 let _ = writeln!(handle, "Total pages visited: {}", self.total_pages_visited);
                 let _ = writeln!(handle, "Links discovered: {}", self.links_discovered);
@@ -143,9 +157,69 @@ fn extract_links(html_content: &str, base_url: &Url) -> Vec<Url> {
     found_urls
 }
 
+/// Options of a [`Crawler`].
+///
+/// The default crawls one page at a time with a one second delay, without depth or page limits.
+#[derive(Debug, Clone)]
+pub struct CrawlerConfig {
+    /// Delay between requests to the same domain, in seconds. A longer robots.txt
+    /// `Crawl-delay` takes precedence.
+    pub crawl_delay_secs: f64,
+    /// URL of a robots.txt used instead of each domain's own.
+    pub custom_robots_path: Option<String>,
+    /// mq query applied to every page; `identity()` when unset.
+    pub mq_query: Option<String>,
+    /// Directory the output is written to; stdout when unset.
+    pub output_path: Option<String>,
+    pub concurrency: usize,
+    pub format: OutputFormat,
+    pub conversion_options: ConversionOptions,
+    /// Maximum number of links followed from the start URL.
+    pub depth_limit: Option<usize>,
+    /// Domains links are followed to; only the start URL's domain when unset, any domain when empty.
+    pub allowed_domains: Option<Vec<String>>,
+    /// URLs crawled in addition to the start URL, e.g. from a sitemap.
+    pub additional_seed_urls: Vec<Url>,
+    /// Crawls only `additional_seed_urls`, without following links.
+    pub sitemap_only: bool,
+    pub max_pages: Option<usize>,
+    pub url_filter: UrlFilter,
+    /// File the crawl state is saved to, for resuming.
+    pub state_file: Option<PathBuf>,
+    /// Writes all pages into one bundle instead of a file per page.
+    pub bundle_format: Option<BundleFormat>,
+    /// File of per-page validators used to skip unchanged pages.
+    pub validators_file: Option<PathBuf>,
+}
+
+impl Default for CrawlerConfig {
+    fn default() -> Self {
+        Self {
+            crawl_delay_secs: 1.0,
+            custom_robots_path: None,
+            mq_query: None,
+            output_path: None,
+            concurrency: 1,
+            format: OutputFormat::default(),
+            conversion_options: ConversionOptions::default(),
+            depth_limit: None,
+            allowed_domains: None,
+            additional_seed_urls: Vec::new(),
+            sitemap_only: false,
+            max_pages: None,
+            url_filter: UrlFilter::default(),
+            state_file: None,
+            bundle_format: None,
+            validators_file: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Crawler {
     allowed_domains: Option<Vec<String>>,
+    bundle_format: Option<BundleFormat>,
+    bundle_writer: Arc<OnceLock<BundleWriter>>,
     conversion_options: mq_markdown::ConversionOptions,
     crawl_delay: Duration,
     custom_robots_path: Option<String>,
@@ -155,64 +229,195 @@ pub struct Crawler {
     format: OutputFormat,
     http_client: HttpClient,
     initial_domain: String,
+    last_state_save: Arc<Mutex<Instant>>,
+    max_pages: Option<usize>,
     mq_query: String,
     output_path: Option<String>,
+    query_hash: u64,
     result: Arc<RwLock<CrawlResult>>,
+    /// Whether the crawl continues one restored from a state file.
+    resumed: bool,
     robots_cache: Arc<DashMap<String, Arc<RobotsTxt>>>,
+    sitemap_only: bool,
+    start_url: Url,
+    state_file: Option<PathBuf>,
     to_visit: Arc<SegQueue<(Url, usize)>>,
     notify: Arc<Notify>,
+    url_filter: UrlFilter,
+    url_states: Arc<DashMap<Url, UrlState>>,
     user_agent: String,
+    validator_cache: Arc<DashMap<String, CachedPage>>,
+    validators_file: Option<PathBuf>,
     visited: Arc<DashSet<Url>>,
 }
 
 impl Crawler {
-    #[allow(clippy::too_many_arguments)]
-    pub async fn new(
-        http_client: HttpClient,
-        start_url: Url,
-        crawl_delay_secs: f64,
-        custom_robots_path: Option<String>,
-        mq_query: Option<String>,
-        output_path: Option<String>,
-        concurrency: usize,
-        format: OutputFormat,
-        conversion_options: mq_markdown::ConversionOptions,
-        depth_limit: Option<usize>,
-        allowed_domains: Option<Vec<String>>,
-        additional_seed_urls: Vec<Url>,
-    ) -> Result<Self, String> {
+    pub async fn new(http_client: HttpClient, start_url: Url, config: CrawlerConfig) -> Result<Self, String> {
+        let CrawlerConfig {
+            crawl_delay_secs,
+            custom_robots_path,
+            mq_query,
+            output_path,
+            concurrency,
+            format,
+            conversion_options,
+            depth_limit,
+            allowed_domains,
+            additional_seed_urls,
+            sitemap_only,
+            max_pages,
+            url_filter,
+            state_file,
+            bundle_format,
+            validators_file,
+        } = config;
         let initial_domain = start_url
             .domain()
             .ok_or_else(|| "Start URL has no domain".to_string())?
             .to_string();
         let user_agent = format!("mq crawler/0.1 ({})", env!("CARGO_PKG_HOMEPAGE"));
+        let mq_query = mq_query.unwrap_or("identity()".to_string());
+        let query_hash = query_hash(&mq_query);
+        let validator_cache = match validators_file {
+            Some(ref path) => ValidatorCache::load(path)?.pages.into_iter().collect(),
+            None => DashMap::new(),
+        };
 
-        let to_visit = SegQueue::new();
-        to_visit.push((start_url.clone(), 0));
-        for seed_url in additional_seed_urls {
-            to_visit.push((seed_url, 0));
-        }
-
-        Ok(Self {
+        let crawler = Self {
             allowed_domains,
+            bundle_format,
+            bundle_writer: Arc::new(OnceLock::new()),
             http_client,
-            to_visit: Arc::new(to_visit),
+            to_visit: Arc::new(SegQueue::new()),
             visited: Arc::new(DashSet::new()),
             robots_cache: Arc::new(DashMap::new()),
+            sitemap_only,
+            start_url: start_url.clone(),
+            state_file,
+            url_states: Arc::new(DashMap::new()),
+            validator_cache: Arc::new(validator_cache),
+            validators_file,
+            last_state_save: Arc::new(Mutex::new(Instant::now())),
             crawl_delay: Duration::from_secs_f64(crawl_delay_secs),
             domain_last_request: Arc::new(DashMap::new()),
-            mq_query: mq_query.unwrap_or("identity()".to_string()),
+            mq_query,
+            query_hash,
             user_agent,
             output_path,
             initial_domain,
+            max_pages,
             custom_robots_path,
             result: Arc::new(RwLock::new(CrawlResult::default())),
+            resumed: false,
             notify: Arc::new(Notify::new()),
+            url_filter,
             concurrency: concurrency.max(1),
             format,
             conversion_options,
             depth_limit,
-        })
+        };
+
+        // In sitemap-only mode the start URL only determines the initial domain;
+        // the frontier consists of the sitemap URLs alone.
+        if !sitemap_only {
+            crawler.enqueue(start_url, 0);
+        }
+        for seed_url in additional_seed_urls {
+            if crawler.url_filter.is_allowed(&seed_url) {
+                crawler.enqueue(seed_url, 0);
+            } else {
+                tracing::debug!("Skipping seed URL excluded by URL filters: {}", seed_url);
+            }
+        }
+
+        Ok(crawler)
+    }
+
+    /// Replaces the initial frontier with the pending URLs of a previous crawl. URLs that
+    /// were already crawled or disallowed by robots.txt are marked as visited, and a bundle
+    /// is appended to rather than replaced, since those pages are not crawled again.
+    pub fn restore_state(&mut self, state: CrawlState) {
+        self.resumed = true;
+        if state.start_url != self.start_url.as_str() {
+            tracing::warn!(
+                "Resuming a crawl that started from {}, not {}",
+                state.start_url,
+                self.start_url
+            );
+        }
+
+        while self.to_visit.pop().is_some() {}
+        self.url_states.clear();
+
+        for (url, url_state) in state.urls {
+            let Ok(url) = Url::parse(&url) else {
+                tracing::warn!("Skipping invalid URL in crawl state: {}", url);
+                continue;
+            };
+
+            if url_state.status.is_pending() {
+                self.enqueue(url, url_state.depth);
+            } else {
+                self.visited.insert(url.clone());
+                self.url_states.insert(url, url_state);
+            }
+        }
+
+        tracing::info!(
+            "Resuming crawl with {} pending URL(s) and {} already visited.",
+            self.to_visit.len(),
+            self.visited.len()
+        );
+    }
+
+    /// Adds a URL to the frontier.
+    fn enqueue(&self, url: Url, depth: usize) {
+        if self.state_file.is_some() {
+            self.url_states.entry(url.clone()).or_insert(UrlState {
+                depth,
+                status: UrlStatus::Queued,
+            });
+        }
+        self.to_visit.push((url, depth));
+    }
+
+    /// Records the status of a URL for the state file. Does nothing unless a state file is configured.
+    fn set_url_status(&self, url: &Url, depth: usize, status: UrlStatus) {
+        if self.state_file.is_some() {
+            self.url_states.insert(url.clone(), UrlState { depth, status });
+        }
+    }
+
+    /// Writes the crawl state to the state file, if one is configured.
+    fn save_state(&self) -> Result<(), String> {
+        let Some(ref path) = self.state_file else {
+            return Ok(());
+        };
+
+        let mut last_save = self.last_state_save.lock().unwrap_or_else(|e| e.into_inner());
+        let state = CrawlState {
+            start_url: self.start_url.to_string(),
+            urls: self
+                .url_states
+                .iter()
+                .map(|entry| (entry.key().to_string(), *entry.value()))
+                .collect(),
+        };
+        state.save(path)?;
+        *last_save = Instant::now();
+        Ok(())
+    }
+
+    /// Saves the crawl state if it was last saved more than [`STATE_SAVE_INTERVAL`] ago.
+    fn save_state_if_due(&self) {
+        let due = self
+            .last_state_save
+            .lock()
+            .map(|last_save| last_save.elapsed() >= STATE_SAVE_INTERVAL)
+            .unwrap_or(true);
+        if due && let Err(e) = self.save_state() {
+            tracing::error!("{}", e);
+        }
     }
 
     async fn get_or_fetch_robots(&self, url_to_check: &Url) -> Result<Arc<RobotsTxt>, String> {
@@ -251,6 +456,12 @@ impl Crawler {
         if let Some(ref path) = self.custom_robots_path {
             startup_info.push_str(&format!(" Custom robots.txt path: '{}'.", path));
         }
+        if let Some(max_pages) = self.max_pages {
+            startup_info.push_str(&format!(" Max pages: {}.", max_pages));
+        }
+        if !self.url_filter.is_empty() {
+            startup_info.push_str(" URL filters enabled.");
+        }
         if let Some(ref path) = self.output_path {
             startup_info.push_str(&format!(" Outputting to directory: '{}'.", path));
         } else {
@@ -272,6 +483,16 @@ impl Crawler {
             }
         }
 
+        if let Some(bundle_format) = self.bundle_format {
+            let (writer, path) =
+                BundleWriter::create(bundle_format, self.output_path.as_deref().map(Path::new), self.resumed)?;
+            if let Some(path) = path {
+                tracing::info!("Writing bundle to: {:?}", path);
+            }
+            // `run` may be called again on the same crawler; the first bundle is kept.
+            let _ = self.bundle_writer.set(writer);
+        }
+
         self.run_parallel().await
     }
 
@@ -291,9 +512,18 @@ impl Crawler {
 
             // Drain the queue, spawning tasks up to the semaphore limit
             while let Some((url, depth)) = self.to_visit.pop() {
-                if self.should_skip_url_without_visited_check(&url) || !self.visited.insert(url.clone()) {
+                // Once the page budget is spent, the remaining queue is drained and discarded.
+                if self.max_pages.is_some_and(|max_pages| self.visited.len() >= max_pages) {
+                    continue;
+                }
+                if self.should_skip_url_without_visited_check(&url) {
+                    self.url_states.remove(&url);
                     continue;
                 }
+                if !self.visited.insert(url.clone()) {
+                    continue;
+                }
+                self.set_url_status(&url, depth, UrlStatus::InProgress);
 
                 let permit = semaphore.clone().acquire_owned().await.expect("Semaphore closed");
                 active_tasks.fetch_add(1, Ordering::SeqCst);
@@ -304,6 +534,7 @@ impl Crawler {
                 tokio::spawn(async move {
                     let _permit = permit;
                     crawler.process_url_with_rate_limit(url, depth).await;
+                    crawler.save_state_if_due();
                     active_tasks_clone.fetch_sub(1, Ordering::SeqCst);
                     // Wake the main loop so it can either spawn new tasks or
                     // detect the termination condition.
@@ -314,11 +545,26 @@ impl Crawler {
             // Block until a task finishes or a new URL is enqueued.
             // If notify_one() was already called since we created `notified`
             // above, this returns immediately without spinning.
-            notified.await;
+            // With a state file, Ctrl-C saves the state so the crawl can be resumed.
+            if let Some(ref path) = self.state_file {
+                tokio::select! {
+                    _ = notified => {}
+                    _ = tokio::signal::ctrl_c() => {
+                        self.save_state()?;
+                        self.finalize_crawl().await;
+                        return Err(format!(
+                            "Crawl interrupted. Resume it with --resume --state-file {}",
+                            path.display()
+                        ));
+                    }
+                }
+            } else {
+                notified.await;
+            }
         }
 
         self.finalize_crawl().await;
-        Ok(())
+        self.save_state()
     }
 
     fn should_skip_url_without_visited_check(&self, url: &Url) -> bool {
@@ -341,8 +587,32 @@ impl Crawler {
         false
     }
 
-    async fn wait_for_domain_rate_limit(&self, domain: &str) {
-        if self.crawl_delay.is_zero() {
+    /// Returns the delay between two requests to the host of `url`: the configured crawl
+    /// delay, or the host's robots.txt `Crawl-delay` if that is longer.
+    async fn domain_crawl_delay(&self, url: &Url) -> Duration {
+        let robots_delay = match self.get_or_fetch_robots(url).await {
+            Ok(robots) => robots.crawl_delay(&self.user_agent),
+            Err(_) => None,
+        };
+
+        match robots_delay {
+            Some(delay) if delay > self.crawl_delay => {
+                if delay > MAX_ROBOTS_CRAWL_DELAY {
+                    tracing::warn!(
+                        "Capping robots.txt Crawl-delay of {:?} for {} to {:?}",
+                        delay,
+                        url.domain().unwrap_or_default(),
+                        MAX_ROBOTS_CRAWL_DELAY
+                    );
+                }
+                delay.min(MAX_ROBOTS_CRAWL_DELAY).max(self.crawl_delay)
+            }
+            _ => self.crawl_delay,
+        }
+    }
+
+    async fn wait_for_domain_rate_limit(&self, domain: &str, crawl_delay: Duration) {
+        if crawl_delay.is_zero() {
             return;
         }
         loop {
@@ -353,11 +623,11 @@ impl Crawler {
             let wait = match self.domain_last_request.entry(domain.to_string()) {
                 Entry::Occupied(mut e) => {
                     let elapsed = now.duration_since(*e.get());
-                    if elapsed >= self.crawl_delay {
+                    if elapsed >= crawl_delay {
                         e.insert(now);
                         None
                     } else {
-                        Some(self.crawl_delay - elapsed)
+                        Some(crawl_delay - elapsed)
                     }
                 }
                 Entry::Vacant(e) => {
@@ -375,7 +645,8 @@ impl Crawler {
 
     async fn process_url_with_rate_limit(&self, url: Url, depth: usize) {
         if let Some(domain) = url.domain() {
-            self.wait_for_domain_rate_limit(domain).await;
+            let crawl_delay = self.domain_crawl_delay(&url).await;
+            self.wait_for_domain_rate_limit(domain, crawl_delay).await;
         }
         self.process_url(url, depth).await;
     }
@@ -388,12 +659,14 @@ impl Crawler {
             Ok(rules) => rules,
             Err(e) => {
                 tracing::error!("Failed to fetch robots.txt for {}: {}", current_url, e);
+                self.set_url_status(&current_url, current_depth, UrlStatus::Failed);
                 return;
             }
         };
 
         if !robots_rules.is_allowed(&current_url, &self.user_agent) {
             tracing::warn!("Skipping URL disallowed by robots.txt: {}", current_url);
+            self.set_url_status(&current_url, current_depth, UrlStatus::SkippedRobots);
             {
                 let mut result = self.result.write().await;
                 result.pages_skipped_robots += 1;
@@ -401,8 +674,43 @@ impl Crawler {
             return;
         }
 
-        match self.http_client.fetch(current_url.clone()).await {
-            Ok(html_content) => {
+        let cached_page = self
+            .validator_cache
+            .get(current_url.as_str())
+            .filter(|page| page.query_hash == self.query_hash)
+            .map(|page| page.clone());
+        let validators = cached_page
+            .as_ref()
+            .map(|page| page.validators.clone())
+            .unwrap_or_default();
+
+        match self
+            .http_client
+            .fetch_conditional(current_url.clone(), &validators)
+            .await
+        {
+            Ok(ConditionalFetch::NotModified) => {
+                tracing::info!("Reusing output of unchanged page: {}", current_url);
+                let page = cached_page.unwrap_or_default();
+                let fetched_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+                if let Err(e) = self.emit_page(&current_url, page.title.as_deref(), &page.markdown, &fetched_at) {
+                    tracing::error!("Failed to output markdown for {}: {}", current_url, e);
+                    self.validator_cache.remove(current_url.as_str());
+                }
+                let links: Vec<Url> = page.links.iter().filter_map(|link| Url::parse(link).ok()).collect();
+                {
+                    let mut result = self.result.write().await;
+                    result.links_discovered += links.len();
+                    result.pages_not_modified += 1;
+                }
+                self.set_url_status(&current_url, current_depth, UrlStatus::Crawled);
+                self.enqueue_links(links, current_depth);
+            }
+            Ok(ConditionalFetch::Modified {
+                content: html_content,
+                validators,
+            }) => {
+                let fetched_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
                 tracing::info!("Applying mq query to content from {}", current_url);
 
                 let query = self.mq_query.clone();
@@ -410,52 +718,75 @@ impl Crawler {
                 let conversion_options = self.conversion_options;
                 let current_url_clone = current_url.clone();
                 let self_clone = self.clone();
-                let new_links = tokio::task::spawn_blocking(move || {
-                    if let Err(e) = Self::execute_query(&query, &html_content_clone, conversion_options).map(|md| {
-                        if let Err(e) = self_clone.output_markdown(&current_url_clone, md.as_str()) {
-                            tracing::error!("Failed to output markdown for {}: {}", current_url_clone, e);
+                let processed = tokio::task::spawn_blocking(move || {
+                    // The page's output, if it was written successfully.
+                    let output = match Self::execute_query(&query, &html_content_clone, conversion_options) {
+                        Ok(md) => {
+                            let title = crate::bundle::extract_title(&html_content_clone);
+                            match self_clone.emit_page(&current_url_clone, title.as_deref(), &md, &fetched_at) {
+                                Ok(()) => Some((title, md)),
+                                Err(e) => {
+                                    tracing::error!("Failed to output markdown for {}: {}", current_url_clone, e);
+                                    None
+                                }
+                            }
                         }
-                    }) {
-                        tracing::error!(
-                            "Failed to execute mq query on content from {}: {}",
-                            current_url_clone,
-                            e
-                        );
-                    }
+                        Err(e) => {
+                            tracing::error!(
+                                "Failed to execute mq query on content from {}: {}",
+                                current_url_clone,
+                                e
+                            );
+                            None
+                        }
+                    };
 
-                    extract_links(&html_content, &current_url_clone)
+                    (extract_links(&html_content, &current_url_clone), output)
                 })
                 .await;
 
-                match new_links {
-                    Ok(new_links) => {
+                match processed {
+                    Ok((new_links, output)) => {
                         {
                             let mut result = self.result.write().await;
                             result.links_discovered += new_links.len();
                             result.pages_crawled += 1;
                         }
-
-                        {
-                            let next_depth = current_depth + 1;
-                            for link in new_links {
-                                if !self.visited.contains(&link)
-                                    && !self.should_skip_url_without_visited_check(&link)
-                                    && self.depth_limit.is_none_or(|limit| next_depth <= limit)
-                                {
-                                    self.to_visit.push((link, next_depth));
-                                    // Wake the main loop to pick up the new URL.
-                                    self.notify.notify_one();
+                        self.set_url_status(&current_url, current_depth, UrlStatus::Crawled);
+
+                        // Validators are only kept for pages whose output was written, so a
+                        // failed page is processed again on the next crawl.
+                        if self.validators_file.is_some() {
+                            match output {
+                                Some((title, markdown)) if !validators.is_empty() => {
+                                    self.validator_cache.insert(
+                                        current_url.to_string(),
+                                        CachedPage {
+                                            validators,
+                                            query_hash: self.query_hash,
+                                            links: new_links.iter().map(Url::to_string).collect(),
+                                            title,
+                                            markdown,
+                                        },
+                                    );
+                                }
+                                _ => {
+                                    self.validator_cache.remove(current_url.as_str());
                                 }
                             }
                         }
+
+                        self.enqueue_links(new_links, current_depth);
                     }
                     Err(e) => {
                         tracing::error!("Failed to execute mq query on content from {}: {}", current_url, e);
+                        self.set_url_status(&current_url, current_depth, UrlStatus::Failed);
                     }
                 }
             }
             Err(e) => {
                 tracing::error!("Failed to fetch URL {}: {}", current_url, e);
+                self.set_url_status(&current_url, current_depth, UrlStatus::Failed);
                 {
                     let mut result = self.result.write().await;
                     result.pages_failed += 1;
@@ -464,6 +795,26 @@ impl Crawler {
         }
     }
 
+    /// Queues the links found on a page at `current_depth`, unless links are not followed.
+    fn enqueue_links(&self, links: Vec<Url>, current_depth: usize) {
+        if self.sitemap_only {
+            return;
+        }
+
+        let next_depth = current_depth + 1;
+        for link in links {
+            if !self.visited.contains(&link)
+                && !self.should_skip_url_without_visited_check(&link)
+                && self.url_filter.is_allowed(&link)
+                && self.depth_limit.is_none_or(|limit| next_depth <= limit)
+            {
+                self.enqueue(link, next_depth);
+                // Wake the main loop to pick up the new URL.
+                self.notify.notify_one();
+            }
+        }
+    }
+
     fn execute_query(query: &str, input: &str, conversion_options: ConversionOptions) -> miette::Result<String> {
         let input = mq_lang::parse_html_input_with_options(input, conversion_options)?;
         let mut mq_engine = mq_lang::DefaultEngine::default();
@@ -488,6 +839,24 @@ impl Crawler {
     }
 
     async fn finalize_crawl(&self) {
+        if let Some(ref path) = self.validators_file {
+            let cache = ValidatorCache {
+                pages: self
+                    .validator_cache
+                    .iter()
+                    .map(|entry| (entry.key().clone(), entry.value().clone()))
+                    .collect(),
+            };
+            if let Err(e) = cache.save(path) {
+                tracing::error!("{}", e);
+            }
+        }
+        if let Some(bundle_writer) = self.bundle_writer.get()
+            && let Err(e) = bundle_writer.flush()
+        {
+            tracing::error!("{}", e);
+        }
+
         // Record end time and final statistics
         {
             let mut result = self.result.write().await;
@@ -503,6 +872,19 @@ impl Crawler {
         }
     }
 
+    /// Writes the output of a page to the bundle, or to the output directory or stdout.
+    fn emit_page(&self, url: &Url, title: Option<&str>, markdown: &str, fetched_at: &str) -> Result<(), String> {
+        match self.bundle_writer.get() {
+            Some(bundle_writer) => bundle_writer.write_page(&BundleRecord {
+                url: url.as_str(),
+                title,
+                markdown,
+                fetched_at,
+            }),
+            None => self.output_markdown(url, markdown),
+        }
+    }
+
     fn output_markdown(&self, url: &Url, markdown: &str) -> Result<(), String> {
         tracing::debug!("Preparing to output markdown for {}", url);
         if let Some(ref output_dir_str) = self.output_path {
@@ -699,16 +1081,12 @@ mod tests {
         Crawler::new(
             http_client,
             start_url,
-            0.0,
-            None,
-            None,
-            None,
-            1,
-            OutputFormat::Text,
-            mq_markdown::ConversionOptions::default(),
-            depth_limit,
-            allowed_domains,
-            Vec::new(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                depth_limit,
+                allowed_domains,
+                ..Default::default()
+            },
         )
         .await
         .unwrap()
@@ -763,16 +1141,10 @@ mod tests {
         let crawler = Crawler::new(
             http_client,
             start_url,
-            1.0,
-            None,
-            None,
-            None,
-            1,
-            OutputFormat::Text,
-            mq_markdown::ConversionOptions::default(),
-            Some(2),
-            None,
-            Vec::new(),
+            CrawlerConfig {
+                depth_limit: Some(2),
+                ..Default::default()
+            },
         )
         .await
         .unwrap();
@@ -784,22 +1156,9 @@ mod tests {
     async fn test_crawler_new_with_unlimited_depth() {
         let start_url = Url::parse("http://start.invalid/").unwrap();
         let http_client = HttpClient::new_reqwest(30.0).unwrap();
-        let crawler = Crawler::new(
-            http_client,
-            start_url,
-            1.0,
-            None,
-            None,
-            None,
-            1,
-            OutputFormat::Text,
-            mq_markdown::ConversionOptions::default(),
-            None,
-            None,
-            Vec::new(),
-        )
-        .await
-        .unwrap();
+        let crawler = Crawler::new(http_client, start_url, CrawlerConfig::default())
+            .await
+            .unwrap();
 
         assert_eq!(crawler.depth_limit, None);
     }
@@ -815,16 +1174,11 @@ mod tests {
         let crawler = Crawler::new(
             http_client,
             start_url.clone(),
-            0.0,
-            None,
-            None,
-            None,
-            1,
-            OutputFormat::Text,
-            mq_markdown::ConversionOptions::default(),
-            None,
-            None,
-            seed_urls.clone(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                additional_seed_urls: seed_urls.clone(),
+                ..Default::default()
+            },
         )
         .await
         .unwrap();
@@ -840,4 +1194,418 @@ mod tests {
             assert!(queued.contains(&(seed_url, 0)));
         }
     }
+
+    #[tokio::test]
+    async fn test_crawler_new_sitemap_only_skips_start_url() {
+        let start_url = Url::parse("http://start.invalid/").unwrap();
+        let http_client = HttpClient::new_reqwest(30.0).unwrap();
+        let seed_urls = vec![Url::parse("http://start.invalid/from-sitemap").unwrap()];
+        let crawler = Crawler::new(
+            http_client,
+            start_url,
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                additional_seed_urls: seed_urls.clone(),
+                sitemap_only: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut queued = Vec::new();
+        while let Some((url, _)) = crawler.to_visit.pop() {
+            queued.push(url);
+        }
+
+        assert_eq!(queued, seed_urls);
+    }
+
+    /// Serves a start page linking to `/docs/a`, `/docs/b`, and `/blog/c`, each of which is an
+    /// empty page, and returns the crawler's visited URL paths.
+    async fn crawl_linked_pages(max_pages: Option<usize>, url_filter: UrlFilter) -> Vec<String> {
+        let server = httpmock::MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/");
+                then.status(200)
+                    .body(r#"<html><body><a href="/docs/a">A</a><a href="/docs/b">B</a><a href="/blog/c">C</a></body></html>"#);
+            })
+            .await;
+        for path in ["/docs/a", "/docs/b", "/blog/c"] {
+            server
+                .mock_async(|when, then| {
+                    when.method(httpmock::Method::GET).path(path);
+                    then.status(200).body("<html><body><p>page</p></body></html>");
+                })
+                .await;
+        }
+
+        let start_url = Url::parse(&format!("http://localhost:{}/", server.port())).unwrap();
+        let output_dir = tempfile::tempdir().unwrap();
+        let mut crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            start_url,
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                custom_robots_path: Some(server.url("/robots.txt")),
+                output_path: Some(output_dir.path().to_string_lossy().to_string()),
+                max_pages,
+                url_filter,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        crawler.run().await.unwrap();
+
+        let mut paths: Vec<String> = crawler.visited.iter().map(|url| url.path().to_string()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_at_max_pages() {
+        let paths = crawl_linked_pages(Some(2), UrlFilter::default()).await;
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&"/".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_run_applies_url_filter_to_links() {
+        let url_filter = UrlFilter::new(&[], &[], &["^/docs/".to_string()], &["/b$".to_string()]).unwrap();
+        let paths = crawl_linked_pages(None, url_filter).await;
+        assert_eq!(paths, vec!["/", "/docs/a"]);
+    }
+
+    #[tokio::test]
+    async fn test_restore_state_requeues_pending_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.json");
+        let mut crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            Url::parse("http://start.invalid/").unwrap(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                state_file: Some(state_file.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut state = CrawlState {
+            start_url: "http://start.invalid/".to_string(),
+            ..Default::default()
+        };
+        for (url, depth, status) in [
+            ("http://start.invalid/", 0, UrlStatus::Crawled),
+            ("http://start.invalid/a", 1, UrlStatus::InProgress),
+            ("http://start.invalid/b", 1, UrlStatus::SkippedRobots),
+            ("http://start.invalid/c", 2, UrlStatus::Failed),
+        ] {
+            state.urls.insert(url.to_string(), UrlState { depth, status });
+        }
+        crawler.restore_state(state.clone());
+
+        let mut queued = Vec::new();
+        while let Some((url, depth)) = crawler.to_visit.pop() {
+            queued.push((url.to_string(), depth));
+        }
+        assert_eq!(
+            queued,
+            vec![
+                ("http://start.invalid/a".to_string(), 1),
+                ("http://start.invalid/c".to_string(), 2)
+            ]
+        );
+        assert!(crawler.visited.contains(&Url::parse("http://start.invalid/").unwrap()));
+        assert!(crawler.visited.contains(&Url::parse("http://start.invalid/b").unwrap()));
+
+        // Pending URLs are saved as queued.
+        crawler.save_state().unwrap();
+        let saved = CrawlState::load(&state_file).unwrap();
+        assert_eq!(saved.urls.len(), 4);
+        assert_eq!(saved.urls["http://start.invalid/a"].status, UrlStatus::Queued);
+        assert_eq!(saved.urls["http://start.invalid/c"].status, UrlStatus::Queued);
+        assert_eq!(saved.urls["http://start.invalid/b"].status, UrlStatus::SkippedRobots);
+    }
+
+    #[tokio::test]
+    async fn test_run_saves_state() {
+        let server = httpmock::MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/");
+                then.status(200)
+                    .body(r#"<html><body><a href="/missing">Missing</a></body></html>"#);
+            })
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.json");
+        let mut crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            Url::parse(&format!("http://localhost:{}/", server.port())).unwrap(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                custom_robots_path: Some(server.url("/robots.txt")),
+                output_path: Some(dir.path().join("out").to_string_lossy().to_string()),
+                state_file: Some(state_file.clone()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        crawler.run().await.unwrap();
+
+        let saved = CrawlState::load(&state_file).unwrap();
+        let statuses: Vec<(String, UrlStatus)> = saved
+            .urls
+            .into_iter()
+            .map(|(url, state)| (Url::parse(&url).unwrap().path().to_string(), state.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("/".to_string(), UrlStatus::Crawled),
+                ("/missing".to_string(), UrlStatus::Failed)
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case::robots_longer(0.5, Some("2"), Duration::from_secs(2))]
+    #[case::configured_longer(5.0, Some("2"), Duration::from_secs(5))]
+    #[case::capped(0.0, Some("3600"), MAX_ROBOTS_CRAWL_DELAY)]
+    #[case::no_robots_delay(1.0, None, Duration::from_secs(1))]
+    #[tokio::test]
+    async fn test_domain_crawl_delay(
+        #[case] crawl_delay_secs: f64,
+        #[case] robots_delay: Option<&str>,
+        #[case] expected: Duration,
+    ) {
+        let server = httpmock::MockServer::start_async().await;
+        let robots_body = match robots_delay {
+            Some(delay) => format!("User-agent: *\nCrawl-delay: {}\n", delay),
+            None => "User-agent: *\nDisallow:\n".to_string(),
+        };
+        server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/robots.txt");
+                then.status(200).body(robots_body);
+            })
+            .await;
+
+        let start_url = Url::parse(&format!("http://localhost:{}/", server.port())).unwrap();
+        let crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            start_url.clone(),
+            CrawlerConfig {
+                crawl_delay_secs,
+                custom_robots_path: Some(server.url("/robots.txt")),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(crawler.domain_crawl_delay(&start_url).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_run_writes_bundle() {
+        let server = httpmock::MockServer::start_async().await;
+        server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/");
+                then.status(200)
+                    .body("<html><head><title>Home</title></head><body><p>Hello</p></body></html>");
+            })
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let start_url = Url::parse(&format!("http://localhost:{}/", server.port())).unwrap();
+        let mut crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            start_url.clone(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                custom_robots_path: Some(server.url("/robots.txt")),
+                output_path: Some(dir.path().to_string_lossy().to_string()),
+                bundle_format: Some(BundleFormat::Jsonl),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        crawler.run().await.unwrap();
+
+        let bundle = fs::read_to_string(dir.path().join("bundle.jsonl")).unwrap();
+        let records: Vec<serde_json::Value> = bundle.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0]["url"], start_url.as_str());
+        assert_eq!(records[0]["title"], "Home");
+        assert!(records[0]["markdown"].as_str().unwrap().contains("Hello"));
+        assert!(records[0]["fetched_at"].as_str().unwrap().ends_with('Z'));
+        // Pages are not also written as individual files.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resumed_run_appends_to_bundle() {
+        let server = httpmock::MockServer::start_async().await;
+        let page_a = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/a");
+                then.status(200)
+                    .body("<html><head><title>A</title></head><body><p>Page A</p></body></html>");
+            })
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let start_url = Url::parse(&format!("http://localhost:{}/", server.port())).unwrap();
+        let bundle_path = dir.path().join("bundle.jsonl");
+        // The bundle written before the crawl was interrupted, after "/" was crawled.
+        fs::write(&bundle_path, "{\"url\":\"first\"}\n").unwrap();
+
+        let mut crawler = Crawler::new(
+            HttpClient::new_reqwest(30.0).unwrap(),
+            start_url.clone(),
+            CrawlerConfig {
+                crawl_delay_secs: 0.0,
+                custom_robots_path: Some(server.url("/robots.txt")),
+                output_path: Some(dir.path().to_string_lossy().to_string()),
+                state_file: Some(dir.path().join("state.json")),
+                bundle_format: Some(BundleFormat::Jsonl),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let mut state = CrawlState {
+            start_url: start_url.to_string(),
+            ..Default::default()
+        };
+        state.urls.insert(
+            start_url.to_string(),
+            UrlState {
+                depth: 0,
+                status: UrlStatus::Crawled,
+            },
+        );
+        state.urls.insert(
+            start_url.join("/a").unwrap().to_string(),
+            UrlState {
+                depth: 1,
+                status: UrlStatus::Queued,
+            },
+        );
+        crawler.restore_state(state);
+        crawler.run().await.unwrap();
+
+        page_a.assert_async().await;
+        let bundle = fs::read_to_string(&bundle_path).unwrap();
+        let urls: Vec<String> = bundle
+            .lines()
+            .map(|l| {
+                serde_json::from_str::<serde_json::Value>(l).unwrap()["url"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(
+            urls,
+            vec!["first".to_string(), start_url.join("/a").unwrap().to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_skips_unchanged_pages_and_follows_cached_links() {
+        let server = httpmock::MockServer::start_async().await;
+        let not_modified = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET)
+                    .path("/")
+                    .header("if-none-match", "\"v1\"");
+                then.status(304);
+            })
+            .await;
+        server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/");
+                then.status(200)
+                    .header("etag", "\"v1\"")
+                    .body(r#"<html><body><a href="/a">A</a></body></html>"#);
+            })
+            .await;
+        let page_a = server
+            .mock_async(|when, then| {
+                when.method(httpmock::Method::GET).path("/a");
+                then.status(200).body("<html><body><p>A</p></body></html>");
+            })
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let validators_file = dir.path().join("validators.json");
+        let new_crawler = |query: &str| {
+            let query = query.to_string();
+            async {
+                Crawler::new(
+                    HttpClient::new_reqwest(30.0).unwrap(),
+                    Url::parse(&format!("http://localhost:{}/", server.port())).unwrap(),
+                    CrawlerConfig {
+                        crawl_delay_secs: 0.0,
+                        custom_robots_path: Some(server.url("/robots.txt")),
+                        mq_query: Some(query),
+                        output_path: Some(dir.path().to_string_lossy().to_string()),
+                        bundle_format: Some(BundleFormat::Jsonl),
+                        validators_file: Some(validators_file.clone()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let bundled_urls = || {
+            let bundle = fs::read_to_string(dir.path().join("bundle.jsonl")).unwrap();
+            let mut urls: Vec<String> = bundle
+                .lines()
+                .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["url"].to_string())
+                .collect();
+            urls.sort();
+            urls
+        };
+
+        let mut first = new_crawler("identity()").await;
+        first.run().await.unwrap();
+        assert_eq!(first.result.read().await.pages_crawled, 2);
+        assert_eq!(first.result.read().await.pages_not_modified, 0);
+        let urls = bundled_urls();
+
+        // The unchanged page's stored output is written to the new bundle.
+        let mut second = new_crawler("identity()").await;
+        second.run().await.unwrap();
+        assert_eq!(second.result.read().await.pages_crawled, 1);
+        assert_eq!(second.result.read().await.pages_not_modified, 1);
+        assert_eq!(bundled_urls(), urls);
+
+        not_modified.assert_async().await;
+        page_a.assert_calls_async(2).await;
+
+        // Validators stored for another query are not used.
+        let mut third = new_crawler("upcase()").await;
+        third.run().await.unwrap();
+        assert_eq!(third.result.read().await.pages_crawled, 2);
+        assert_eq!(third.result.read().await.pages_not_modified, 0);
+        not_modified.assert_async().await;
+
+        // Pages whose query failed keep no validators.
+        let mut fourth = new_crawler("undefined_function()").await;
+        fourth.run().await.unwrap();
+        assert!(ValidatorCache::load(&validators_file).unwrap().pages.is_empty());
+    }
 }
@@ -0,0 +1,118 @@
+//! Include/exclude filters that scope a crawl to a subset of URLs.
+//!
+//! URL patterns are matched against the full URL (`https://example.com/docs/a?b=c`),
+//! path patterns against the URL path alone (`/docs/a`). A URL is crawled when it
+//! matches no exclude pattern and, if any include pattern is given, at least one
+//! include pattern.
+
+use regex_lite::Regex;
+use url::Url;
+
+#[derive(Debug, Clone, Default)]
+pub struct UrlFilter {
+    include_urls: Vec<Regex>,
+    exclude_urls: Vec<Regex>,
+    include_paths: Vec<Regex>,
+    exclude_paths: Vec<Regex>,
+}
+
+impl UrlFilter {
+    /// Compiles the given regex patterns, returning an error naming the first invalid one.
+    pub fn new(
+        include_urls: &[String],
+        exclude_urls: &[String],
+        include_paths: &[String],
+        exclude_paths: &[String],
+    ) -> Result<Self, String> {
+        Ok(Self {
+            include_urls: compile_patterns(include_urls)?,
+            exclude_urls: compile_patterns(exclude_urls)?,
+            include_paths: compile_patterns(include_paths)?,
+            exclude_paths: compile_patterns(exclude_paths)?,
+        })
+    }
+
+    /// Returns `true` if no patterns are configured.
+    pub fn is_empty(&self) -> bool {
+        self.include_urls.is_empty()
+            && self.exclude_urls.is_empty()
+            && self.include_paths.is_empty()
+            && self.exclude_paths.is_empty()
+    }
+
+    /// Returns `true` if `url` passes the filter.
+    pub fn is_allowed(&self, url: &Url) -> bool {
+        let url_str = url.as_str();
+        let path = url.path();
+
+        if self.exclude_urls.iter().any(|re| re.is_match(url_str))
+            || self.exclude_paths.iter().any(|re| re.is_match(path))
+        {
+            return false;
+        }
+
+        if self.include_urls.is_empty() && self.include_paths.is_empty() {
+            return true;
+        }
+
+        self.include_urls.iter().any(|re| re.is_match(url_str)) || self.include_paths.iter().any(|re| re.is_match(path))
+    }
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<Regex>, String> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).map_err(|e| format!("Invalid URL filter pattern '{}': {}", pattern, e)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn patterns(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[rstest]
+    #[case::no_patterns(&[], &[], &[], &[], "https://example.com/blog/post", true)]
+    #[case::include_path_match(&[], &[], &["^/docs/"], &[], "https://example.com/docs/intro", true)]
+    #[case::include_path_miss(&[], &[], &["^/docs/"], &[], "https://example.com/blog/post", false)]
+    #[case::include_url_match(&["^https://docs\\."], &[], &[], &[], "https://docs.example.com/a", true)]
+    #[case::include_either(&["\\?lang=en"], &[], &["^/docs/"], &[], "https://example.com/blog?lang=en", true)]
+    #[case::exclude_path(&[], &[], &[], &["\\.pdf$"], "https://example.com/docs/manual.pdf", false)]
+    #[case::exclude_url_query(&[], &["[?&]page="], &[], &[], "https://example.com/list?page=2", false)]
+    #[case::exclude_wins_over_include(&[], &[], &["^/docs/"], &["^/docs/archive/"], "https://example.com/docs/archive/v1", false)]
+    #[case::path_ignores_query(&[], &[], &[], &["page"], "https://example.com/list?page=2", true)]
+    fn test_is_allowed(
+        #[case] include_urls: &[&str],
+        #[case] exclude_urls: &[&str],
+        #[case] include_paths: &[&str],
+        #[case] exclude_paths: &[&str],
+        #[case] url: &str,
+        #[case] expected: bool,
+    ) {
+        let filter = UrlFilter::new(
+            &patterns(include_urls),
+            &patterns(exclude_urls),
+            &patterns(include_paths),
+            &patterns(exclude_paths),
+        )
+        .unwrap();
+
+        assert_eq!(filter.is_allowed(&Url::parse(url).unwrap()), expected);
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let result = UrlFilter::new(&[], &[], &patterns(&["(unclosed"]), &[]);
+        assert!(result.unwrap_err().contains("(unclosed"));
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(UrlFilter::default().is_empty());
+        assert!(!UrlFilter::new(&patterns(&["a"]), &[], &[], &[]).unwrap().is_empty());
+    }
+}
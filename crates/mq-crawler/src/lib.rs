@@ -11,6 +11,9 @@
 //! - HTML to markdown conversion
 //! - Link discovery and following
 //! - sitemap.xml ingestion as a seed-URL source
+//! - Depth, page-count, and include/exclude URL filters
+//! - Resumable crawls backed by a JSON state file
+//! - Conditional recrawls that skip pages unchanged since the last run
 //! - Crawl statistics and result tracking
 //! - Support for custom HTTP headers, cookies, and user agents
 //! - Basic and bearer authentication for protected sites
@@ -20,15 +23,19 @@
 //! # Usage
 //!
 //! ```rust,ignore
-//! use mq_crawler::crawler::Crawler;
+//! use mq_crawler::crawler::{Crawler, CrawlerConfig};
+//! use mq_crawler::http_client::HttpClient;
 //! use url::Url;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     let start_url = Url::parse("https://your-target-site.com")?;
-//!     let crawler = Crawler::new(start_url, None, 10);
-//!     let result = crawler.crawl().await?;
-//!     println!("Crawled {} pages", result.pages_crawled);
+//!     let config = CrawlerConfig {
+//!         depth_limit: Some(2),
+//!         ..Default::default()
+//!     };
+//!     let mut crawler = Crawler::new(HttpClient::new_reqwest(30.0)?, start_url, config).await?;
+//!     crawler.run().await?;
 //!     Ok(())
 //! }
 //! ```
@@ -43,7 +50,11 @@
 //! - Converts HTML pages to markdown
 //! - Tracks statistics about the crawl
 //!
+pub mod bundle;
 pub mod crawler;
+pub mod filter;
 pub mod http_client;
+pub mod revalidate;
 pub mod robots;
 pub mod sitemap;
+pub mod state;
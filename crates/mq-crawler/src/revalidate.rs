@@ -0,0 +1,113 @@
+//! Per-URL HTTP validators kept between crawls, so a recrawl can skip unchanged pages.
+//!
+//! For every page fetched with an `ETag` or `Last-Modified` header and processed successfully,
+//! the cache stores those validators together with the links found on the page and the output
+//! of the mq query. On the next crawl with the same query the page is requested conditionally;
+//! when the server answers `304 Not Modified`, the stored output is written again and the
+//! stored links are followed instead of processing the page.
+
+use crate::http_client::Validators;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CachedPage {
+    #[serde(flatten)]
+    pub validators: Validators,
+    /// [`query_hash`] of the mq query the page was processed with.
+    #[serde(default)]
+    pub query_hash: u64,
+    /// Links found on the page when it was last fetched.
+    #[serde(default)]
+    pub links: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Output of the mq query for the page.
+    #[serde(default)]
+    pub markdown: String,
+}
+
+/// Hashes an mq query, so validators stored for one query are not reused with another.
+pub fn query_hash(query: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    query.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Validators of every page, keyed by URL, serialized as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorCache {
+    pub pages: BTreeMap<String, CachedPage>,
+}
+
+impl ValidatorCache {
+    /// Reads a cache written by [`ValidatorCache::save`]. A missing file is an empty cache,
+    /// so the first crawl of a site needs no special handling.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read validators file '{}': {}", path.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse validators file '{}': {}", path.display(), e))
+    }
+
+    /// Writes the cache to `path`, replacing the file atomically.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize validators: {}", e))?;
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write validators file '{}': {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write validators file '{}': {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ValidatorCache::load(&dir.path().join("validators.json")).unwrap();
+        assert_eq!(cache, ValidatorCache::default());
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("validators.json");
+        let mut cache = ValidatorCache::default();
+        cache.pages.insert(
+            "https://example.com/".to_string(),
+            CachedPage {
+                validators: Validators {
+                    etag: Some("\"v1\"".to_string()),
+                    last_modified: None,
+                },
+                query_hash: 1,
+                links: vec!["https://example.com/a".to_string()],
+                title: None,
+                markdown: "# A".to_string(),
+            },
+        );
+
+        cache.save(&path).unwrap();
+
+        assert_eq!(ValidatorCache::load(&path).unwrap(), cache);
+        let json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"pages": {"https://example.com/": {
+                "etag": "\"v1\"",
+                "query_hash": 1,
+                "links": ["https://example.com/a"],
+                "markdown": "# A"
+            }}})
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use crate::http_client::HttpClient;
 use robots_txt::{Robots, matcher::SimpleMatcher};
 use std::sync::OnceLock;
+use std::time::Duration;
 use url::Url;
 
 // A simple wrapper for robots.txt handling.
@@ -116,12 +117,79 @@ impl RobotsTxt {
         let matcher = SimpleMatcher::new(&section.rules);
         matcher.check_path(url_to_check.path())
     }
+
+    /// Returns the `Crawl-delay` that applies to `user_agent`, if any.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.robots_text
+            .as_deref()
+            .and_then(|text| parse_crawl_delay(text, user_agent))
+    }
+}
+
+/// Extracts the `Crawl-delay` of the group matching `user_agent` from robots.txt content.
+/// A group whose `User-agent` is contained in `user_agent` takes precedence over the `*` group.
+/// `robots_txt` does not parse this directive, so it is read here.
+fn parse_crawl_delay(text: &str, user_agent: &str) -> Option<Duration> {
+    let user_agent = user_agent.to_lowercase();
+    let mut group_agents: Vec<String> = Vec::new();
+    let mut in_agent_lines = false;
+    let mut specific_delay = None;
+    let mut wildcard_delay = None;
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        if key == "user-agent" {
+            // Consecutive User-agent lines belong to the same group.
+            if !in_agent_lines {
+                group_agents.clear();
+            }
+            // An empty agent would be contained in every user agent.
+            if !value.is_empty() {
+                group_agents.push(value.to_lowercase());
+            }
+            in_agent_lines = true;
+            continue;
+        }
+        in_agent_lines = false;
+
+        if key != "crawl-delay" {
+            continue;
+        }
+        let Some(delay) = value
+            .parse::<f64>()
+            .ok()
+            .filter(|delay| delay.is_finite() && *delay >= 0.0)
+        else {
+            continue;
+        };
+
+        if group_agents
+            .iter()
+            .any(|agent| agent != "*" && user_agent.contains(agent.as_str()))
+        {
+            specific_delay.get_or_insert(delay);
+        } else if group_agents.iter().any(|agent| agent == "*") {
+            wildcard_delay.get_or_insert(delay);
+        }
+    }
+
+    // Values too large for a `Duration` saturate, so callers can cap them like any long delay.
+    specific_delay
+        .or(wildcard_delay)
+        .map(|delay| Duration::try_from_secs_f64(delay).unwrap_or(Duration::MAX))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use httpmock::{Method::GET, MockServer};
+    use rstest::rstest;
     use std::sync::Once;
     use tokio::runtime::Runtime;
 
@@ -325,4 +393,35 @@ Disallow: /forbidden/";
         let url = Url::parse("http://example.com/any/path").unwrap();
         assert!(rt.is_allowed(&url, "*"));
     }
+
+    #[rstest]
+    #[case::wildcard("User-agent: *\nCrawl-delay: 2\n", Some(2.0))]
+    #[case::fractional("User-agent: *\nCrawl-delay: 0.5\n", Some(0.5))]
+    #[case::specific_wins(
+        "User-agent: *\nCrawl-delay: 10\n\nUser-agent: mq crawler\nCrawl-delay: 1\n",
+        Some(1.0)
+    )]
+    #[case::grouped_agents(
+        "User-agent: otherbot\nUser-agent: MQ Crawler\nCrawl-delay: 3 # comment\n",
+        Some(3.0)
+    )]
+    #[case::other_agent_only("User-agent: otherbot\nCrawl-delay: 5\n", None)]
+    #[case::invalid_value("User-agent: *\nCrawl-delay: soon\n", None)]
+    #[case::negative_value("User-agent: *\nCrawl-delay: -1\n", None)]
+    #[case::no_delay("User-agent: *\nDisallow: /private\n", None)]
+    #[case::empty_agent("User-agent:\nCrawl-delay: 5\n", None)]
+    fn test_parse_crawl_delay(#[case] text: &str, #[case] expected: Option<f64>) {
+        assert_eq!(
+            parse_crawl_delay(text, "mq crawler/0.1 (https://mqlang.org/)"),
+            expected.map(Duration::from_secs_f64)
+        );
+    }
+
+    #[test]
+    fn test_parse_crawl_delay_saturates_huge_value() {
+        assert_eq!(
+            parse_crawl_delay("User-agent: *\nCrawl-delay: 1e20\n", "mq crawler"),
+            Some(Duration::MAX)
+        );
+    }
 }
@@ -0,0 +1,124 @@
+//! Persistent crawl state, used to resume an interrupted crawl.
+//!
+//! The state records every URL the crawler has queued together with its depth and
+//! status. Pages that were crawled or skipped by robots.txt are not fetched again on
+//! resume; queued, in-flight, and failed pages form the new frontier.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Status of a single URL in the crawl.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlStatus {
+    /// Waiting in the frontier.
+    Queued,
+    /// Being fetched when the state was saved.
+    InProgress,
+    /// Fetched and processed successfully.
+    Crawled,
+    /// Disallowed by robots.txt.
+    SkippedRobots,
+    /// Fetching failed.
+    Failed,
+}
+
+impl UrlStatus {
+    /// Returns `true` if the URL has to be fetched again when the crawl is resumed.
+    pub fn is_pending(self) -> bool {
+        matches!(self, UrlStatus::Queued | UrlStatus::InProgress | UrlStatus::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlState {
+    pub depth: usize,
+    pub status: UrlStatus,
+}
+
+/// Snapshot of a crawl, serialized as JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrawlState {
+    /// The start URL of the crawl the state belongs to.
+    pub start_url: String,
+    /// Every URL queued so far, keyed by URL.
+    pub urls: BTreeMap<String, UrlState>,
+}
+
+impl CrawlState {
+    /// Reads a state file written by [`CrawlState::save`].
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content =
+            fs::read_to_string(path).map_err(|e| format!("Failed to read crawl state '{}': {}", path.display(), e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse crawl state '{}': {}", path.display(), e))
+    }
+
+    /// Writes the state to `path`. The file is replaced atomically so that an
+    /// interruption while saving never leaves a truncated state behind.
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| format!("Failed to serialize crawl state: {}", e))?;
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, json)
+            .map_err(|e| format!("Failed to write crawl state '{}': {}", tmp_path.display(), e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Failed to write crawl state '{}': {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn state() -> CrawlState {
+        let mut urls = BTreeMap::new();
+        for (url, depth, status) in [
+            ("http://example.com/", 0, UrlStatus::Crawled),
+            ("http://example.com/a", 1, UrlStatus::Queued),
+            ("http://example.com/b", 1, UrlStatus::InProgress),
+            ("http://example.com/c", 1, UrlStatus::Failed),
+            ("http://example.com/d", 1, UrlStatus::SkippedRobots),
+        ] {
+            urls.insert(url.to_string(), UrlState { depth, status });
+        }
+        CrawlState {
+            start_url: "http://example.com/".to_string(),
+            urls,
+        }
+    }
+
+    #[rstest]
+    #[case(UrlStatus::Queued, true)]
+    #[case(UrlStatus::InProgress, true)]
+    #[case(UrlStatus::Failed, true)]
+    #[case(UrlStatus::Crawled, false)]
+    #[case(UrlStatus::SkippedRobots, false)]
+    fn test_is_pending(#[case] status: UrlStatus, #[case] expected: bool) {
+        assert_eq!(status.is_pending(), expected);
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl-state.json");
+
+        state().save(&path).unwrap();
+
+        assert_eq!(CrawlState::load(&path).unwrap(), state());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn test_load_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crawl-state.json");
+        fs::write(&path, "not json").unwrap();
+
+        assert!(
+            CrawlState::load(&path)
+                .unwrap_err()
+                .contains("Failed to parse crawl state")
+        );
+    }
+}
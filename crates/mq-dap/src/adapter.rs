@@ -7,6 +7,7 @@ use dap::responses::{
 use dap::types::Breakpoint;
 use mq_lang::Shared;
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::io;
 use std::path::PathBuf;
 use std::thread;
@@ -16,9 +17,14 @@ use crate::error::MqAdapterError;
 use crate::executor;
 use crate::handler::{DapDebuggerHandler, DapHandlerWrapper};
 use crate::protocol::{DapCommand, DebuggerMessage, LaunchArgs};
+use crate::trace::{ExecutionTrace, StopReason, VariableScope};
 
 type DynResult<T> = miette::Result<T, Box<dyn std::error::Error>>;
 
+/// Variables reference of the INPUT pseudo-scope. Expandable fields of the input value get
+/// the references that follow it.
+const INPUT_VARIABLES_REFERENCE: i64 = 1 << 20;
+
 /// Main DAP adapter for mq debugger
 pub struct MqAdapter {
     engine: mq_lang::DefaultEngine,
@@ -26,7 +32,19 @@ pub struct MqAdapter {
     debugger_message_rx: Option<Receiver<DebuggerMessage>>,
     debugger_message_tx: Option<Sender<DebuggerMessage>>,
     dap_command_tx: Option<Sender<DapCommand>>,
+    /// Context of the live stop; replayed stops are shown from their trace snapshots instead.
     current_debug_context: Option<mq_lang::DebugContext>,
+    /// Results of watch and hover expressions, keyed by expression and cleared on every stop.
+    watch_cache: HashMap<String, Result<String, String>>,
+    /// Stops recorded during this session, replayed by `stepBack` and `reverseContinue`.
+    trace: ExecutionTrace,
+    /// Expandable fields of the input value at the current stop, indexed by variables reference
+    /// relative to [`INPUT_VARIABLES_REFERENCE`].
+    input_fields: Vec<serde_json::Value>,
+    /// Whether the engine belongs to an mq process waiting for a client to attach.
+    attached: bool,
+    /// Signalled on `configurationDone` so an attached process starts evaluating.
+    ready_tx: Option<Sender<()>>,
 }
 
 impl Default for MqAdapter {
@@ -57,6 +75,38 @@ impl MqAdapter {
             dap_command_tx: Some(command_tx),
             query_file: None,
             current_debug_context: None,
+            watch_cache: HashMap::new(),
+            trace: ExecutionTrace::default(),
+            input_fields: Vec::new(),
+            attached: false,
+            ready_tx: None,
+        }
+    }
+
+    /// Create an adapter for an mq process that evaluates with `engine` itself.
+    ///
+    /// The debugger handler of `engine` must send its messages to `message_rx` and receive its
+    /// commands from `command_tx`. Clients `attach` instead of `launch`, and `ready_tx` is
+    /// signalled once they have finished configuring breakpoints.
+    pub(crate) fn attached(
+        engine: mq_lang::DefaultEngine,
+        message_rx: Receiver<DebuggerMessage>,
+        command_tx: Sender<DapCommand>,
+        query_file: Option<String>,
+        ready_tx: Sender<()>,
+    ) -> Self {
+        Self {
+            engine,
+            debugger_message_rx: Some(message_rx),
+            debugger_message_tx: None,
+            dap_command_tx: Some(command_tx),
+            query_file,
+            current_debug_context: None,
+            watch_cache: HashMap::new(),
+            trace: ExecutionTrace::default(),
+            input_fields: Vec::new(),
+            attached: true,
+            ready_tx: Some(ready_tx),
         }
     }
 
@@ -64,9 +114,18 @@ impl MqAdapter {
         &self.debugger_message_rx
     }
 
+    /// Get the context being shown: the replayed snapshot while replaying, otherwise the live stop
+    fn debug_context(&self) -> Option<&mq_lang::DebugContext> {
+        if self.trace.is_replaying() {
+            self.trace.current().map(|entry| &entry.context)
+        } else {
+            self.current_debug_context.as_ref()
+        }
+    }
+
     /// Get local variables from the current debug context
     fn get_local_variables_from_context(&self) -> Vec<types::Variable> {
-        if let Some(ref context) = self.current_debug_context {
+        if let Some(context) = self.debug_context() {
             context
                 .env
                 .read()
@@ -92,7 +151,7 @@ impl MqAdapter {
 
     /// Get global variables from the current debug context
     fn get_global_variables_from_context(&self) -> Vec<types::Variable> {
-        if let Some(ref context) = self.current_debug_context {
+        if let Some(context) = self.debug_context() {
             context
                 .env
                 .read()
@@ -116,6 +175,89 @@ impl MqAdapter {
         }
     }
 
+    /// Get the variables of the INPUT pseudo-scope, or of one of its expanded fields
+    ///
+    /// The top level shows the value `.` refers to at the current stop, rendered as markdown
+    /// text, followed by its structured fields.
+    fn get_input_variables(&mut self, variables_reference: i64) -> Vec<types::Variable> {
+        let Some(context) = self.debug_context() else {
+            return vec![];
+        };
+
+        if variables_reference == INPUT_VARIABLES_REFERENCE {
+            let value = context.current_value.clone();
+            let text = types::Variable {
+                name: "markdown".to_string(),
+                value: value.to_string(),
+                type_field: Some(value.name().to_string()),
+                variables_reference: 0,
+                named_variables: None,
+                indexed_variables: None,
+                presentation_hint: None,
+                evaluate_name: Some("self".to_string()),
+                memory_reference: None,
+            };
+
+            self.input_fields = vec![value.to_json_value()];
+            let fields = self.get_input_fields(0);
+            std::iter::once(text).chain(fields).collect()
+        } else {
+            usize::try_from(variables_reference - INPUT_VARIABLES_REFERENCE)
+                .map(|index| self.get_input_fields(index))
+                .unwrap_or_default()
+        }
+    }
+
+    fn get_input_fields(&mut self, index: usize) -> Vec<types::Variable> {
+        let fields: Vec<(String, serde_json::Value)> = match self.input_fields.get(index) {
+            Some(serde_json::Value::Object(map)) => map.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            Some(serde_json::Value::Array(items)) => items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), v.clone()))
+                .collect(),
+            Some(serde_json::Value::Null) | None => Vec::new(),
+            Some(value) => vec![("value".to_string(), value.clone())],
+        };
+
+        fields
+            .into_iter()
+            .map(|(name, value)| {
+                let (display, type_field) = match &value {
+                    serde_json::Value::Object(map) => (format!("{{{} fields}}", map.len()), "object"),
+                    serde_json::Value::Array(items) => (format!("[{} items]", items.len()), "array"),
+                    serde_json::Value::String(s) => (s.clone(), "string"),
+                    serde_json::Value::Number(n) => (n.to_string(), "number"),
+                    serde_json::Value::Bool(b) => (b.to_string(), "bool"),
+                    serde_json::Value::Null => ("None".to_string(), "None"),
+                };
+                let expandable = match &value {
+                    serde_json::Value::Object(map) => !map.is_empty(),
+                    serde_json::Value::Array(items) => !items.is_empty(),
+                    _ => false,
+                };
+                let variables_reference = if expandable {
+                    self.input_fields.push(value);
+                    INPUT_VARIABLES_REFERENCE + self.input_fields.len() as i64 - 1
+                } else {
+                    0
+                };
+
+                types::Variable {
+                    name,
+                    value: display,
+                    type_field: Some(type_field.to_string()),
+                    variables_reference,
+                    named_variables: None,
+                    indexed_variables: None,
+                    presentation_hint: None,
+                    evaluate_name: None,
+                    memory_reference: None,
+                }
+            })
+            .collect()
+    }
+
     /// Send log output to the DAP client
     pub fn send_log_output(&self, message: &str, server: &mut Server<impl io::Read, impl io::Write>) -> DynResult<()> {
         let event = Event::Output(events::OutputEventBody {
@@ -146,8 +288,7 @@ impl MqAdapter {
                 breakpoint,
                 ..
             } => {
-                // Store the current debug context for variable inspection
-                self.current_debug_context = Some(context);
+                self.record_stop(context, StopReason::Breakpoint(breakpoint.id));
                 debug!(line = line, "Sending stopped event for breakpoint");
 
                 let event = Event::Stopped(events::StoppedEventBody {
@@ -167,8 +308,7 @@ impl MqAdapter {
                 context,
                 ..
             } => {
-                // Store the current debug context for variable inspection
-                self.current_debug_context = Some(context);
+                self.record_stop(context, StopReason::Step);
                 debug!(line = line, "Sending stopped event for step");
 
                 let event = Event::Stopped(events::StoppedEventBody {
@@ -189,8 +329,7 @@ impl MqAdapter {
                 context,
                 ..
             } => {
-                // Store the current debug context for variable inspection
-                self.current_debug_context = Some(context);
+                self.record_stop(context, StopReason::Pause);
                 debug!(line = line, "Sending stopped event for pause");
 
                 let event = Event::Stopped(events::StoppedEventBody {
@@ -221,6 +360,89 @@ impl MqAdapter {
         Ok(())
     }
 
+    /// Store the context of a live stop for variable inspection and record it in the trace
+    fn record_stop(&mut self, context: mq_lang::DebugContext, reason: StopReason) {
+        self.current_debug_context = Some(context);
+        self.watch_cache.clear();
+        self.input_fields.clear();
+
+        if let Some(context) = &self.current_debug_context {
+            let globals = self.get_global_variables_from_context();
+            let locals = self.get_local_variables_from_context();
+            self.trace.record(context, reason, globals, locals);
+        }
+    }
+
+    /// Show the stop the trace moved to, or do nothing if it did not move
+    fn send_replayed_stop(&mut self, server: &mut Server<impl io::Read, impl io::Write>) -> DynResult<()> {
+        let (Some(entry), Some((index, len))) = (self.trace.current(), self.trace.position()) else {
+            return Ok(());
+        };
+
+        let (reason, hit_breakpoint_ids) = match entry.reason {
+            StopReason::Breakpoint(id) => (types::StoppedEventReason::Breakpoint, Some(vec![id as i64])),
+            StopReason::Step => (types::StoppedEventReason::Step, None),
+            StopReason::Pause => (types::StoppedEventReason::Pause, None),
+        };
+        self.watch_cache.clear();
+        self.input_fields.clear();
+        debug!(index = index, "Sending stopped event for replayed stop");
+
+        let description = if self.trace.is_replaying() {
+            format!("Replaying stop {} of {}", index + 1, len)
+        } else {
+            "Returned to the current stop".to_string()
+        };
+        let event = Event::Stopped(events::StoppedEventBody {
+            reason,
+            description: Some(description),
+            thread_id: Some(1),
+            preserve_focus_hint: None,
+            text: None,
+            all_threads_stopped: None,
+            hit_breakpoint_ids,
+        });
+        server.send_event(event)?;
+        Ok(())
+    }
+
+    /// Step the live program, or move forward through the trace while replaying
+    fn step(&mut self, command: DapCommand, server: &mut Server<impl io::Read, impl io::Write>) -> DynResult<()> {
+        if self.trace.is_replaying() {
+            self.trace.step_forward();
+            self.send_replayed_stop(server)
+        } else {
+            self.send_debugger_command(command)
+        }
+    }
+
+    /// Parse the implementation specific attributes of a launch request
+    fn program_args(additional_data: Option<&serde_json::Value>) -> DynResult<LaunchArgs> {
+        let additional_data = additional_data.ok_or(MqAdapterError::MissingLaunchArguments)?;
+        Ok(serde_json::from_value(additional_data.clone()).map_err(MqAdapterError::LaunchArgumentsError)?)
+    }
+
+    /// Activate the debugger and run the query in a background thread
+    fn run_program(&mut self, args: LaunchArgs) {
+        self.engine.debugger().write().unwrap().activate();
+        self.engine.load_builtin_module();
+        self.query_file = Some(args.query_file.clone());
+
+        let engine_clone = self.engine.clone();
+
+        if let Some(ref message_tx) = self.debugger_message_tx {
+            let message_tx_clone = message_tx.clone();
+
+            thread::spawn(move || {
+                if let Err(e) =
+                    executor::execute_query(engine_clone, args.query_file, args.input_file, message_tx_clone)
+                {
+                    error!(error = %e, "Failed to execute query in background thread");
+                }
+            });
+        }
+    }
+
     /// Send a command to the debugger handler
     fn send_debugger_command(&self, command: DapCommand) -> DynResult<()> {
         if let Some(ref tx) = self.dap_command_tx {
@@ -266,7 +488,7 @@ impl MqAdapter {
 
     /// Evaluate code in the current debug context
     fn eval(&self, code: &str) -> DynResult<mq_lang::RuntimeValues> {
-        let mut engine: mq_lang::DefaultEngine = if let Some(ref context) = self.current_debug_context {
+        let mut engine: mq_lang::DefaultEngine = if let Some(context) = self.debug_context() {
             mq_lang::DefaultEngine::default().switch_env(Shared::clone(&context.env))
         } else {
             return Err(Box::new(MqAdapterError::EvaluationError(Cow::Borrowed(
@@ -281,6 +503,59 @@ impl MqAdapter {
         })
     }
 
+    /// Evaluates a watch or hover expression without side effects on the paused program.
+    ///
+    /// The expression runs in a child scope of the paused environment, and its result is cached
+    /// until the next stop so watch panels can re-query on every step cheaply.
+    fn eval_watch(&mut self, expression: &str) -> Result<String, String> {
+        if let Some(result) = self.watch_cache.get(expression) {
+            return result.clone();
+        }
+
+        let Some(context) = self.debug_context() else {
+            return Err("Current context not found".to_string());
+        };
+        let result = mq_lang::DefaultEngine::default()
+            .switch_env(context.child_env())
+            .eval(expression, mq_lang::null_input().into_iter())
+            .map(|values| format_values(&values))
+            .map_err(|e| e.to_string());
+
+        self.watch_cache.insert(expression.to_string(), result.clone());
+        result
+    }
+
+    /// Evaluates `expression` in the paused environment and stores the result in the existing
+    /// variable `name`, returning the updated variable as shown in the Variables view.
+    fn set_variable(&mut self, name: &str, expression: &str, global: bool) -> Result<types::Variable, String> {
+        let Some(context) = &self.current_debug_context else {
+            return Err("Current context not found".to_string());
+        };
+        let value = mq_lang::DefaultEngine::default()
+            .switch_env(context.child_env())
+            .eval(expression, mq_lang::null_input().into_iter())
+            .map_err(|e| format!("Evaluation error: {}", e))?
+            .values()
+            .last()
+            .cloned()
+            .unwrap_or(mq_lang::RuntimeValue::NONE);
+
+        if !context.set_variable(name, value, global) {
+            return Err(format!("Undefined variable: {}", name));
+        }
+        self.watch_cache.clear();
+
+        let variables = if global {
+            self.get_global_variables_from_context()
+        } else {
+            self.get_local_variables_from_context()
+        };
+        variables
+            .into_iter()
+            .find(|variable| variable.name == name)
+            .ok_or_else(|| format!("Undefined variable: {}", name))
+    }
+
     /// Handle DAP request and send appropriate response
     pub fn handle_request(
         &mut self,
@@ -289,37 +564,28 @@ impl MqAdapter {
     ) -> DynResult<()> {
         match &req.command {
             Command::Launch(raw_args) => {
-                let additional_data = raw_args
-                    .additional_data
-                    .as_ref()
-                    .ok_or(MqAdapterError::MissingLaunchArguments)?;
-
-                let args: LaunchArgs =
-                    serde_json::from_value(additional_data.clone()).map_err(MqAdapterError::LaunchArgumentsError)?;
+                if self.attached {
+                    server.respond(req.error("This mq process is already running; use attach instead"))?;
+                    return Ok(());
+                }
 
+                let args = Self::program_args(raw_args.additional_data.as_ref())?;
                 debug!(?args, "Received launch request");
 
-                self.engine.debugger().write().unwrap().activate();
-                self.engine.load_builtin_module();
-                self.query_file = Some(args.query_file.clone());
-
-                let engine_clone = self.engine.clone();
-
-                if let Some(ref message_tx) = self.debugger_message_tx {
-                    let message_tx_clone = message_tx.clone();
-
-                    thread::spawn(move || {
-                        if let Err(e) =
-                            executor::execute_query(engine_clone, args.query_file, args.input_file, message_tx_clone)
-                        {
-                            error!(error = %e, "Failed to execute query in background thread");
-                        }
-                    });
-                }
-
+                self.run_program(args);
                 let rsp = req.success(ResponseBody::Launch);
                 server.respond(rsp)?;
             }
+            Command::Attach(_) => {
+                debug!("Received attach request");
+
+                let rsp = if self.attached {
+                    req.success(ResponseBody::Attach)
+                } else {
+                    req.error("No mq process to attach to; start one with `mq --debug-listen <PORT>`")
+                };
+                server.respond(rsp)?;
+            }
             Command::SetExceptionBreakpoints(_) => {
                 debug!("Received SetExceptionBreakpoints request");
                 let rsp = req.success(ResponseBody::SetExceptionBreakpoints(SetExceptionBreakpointsResponse {
@@ -350,6 +616,24 @@ impl MqAdapter {
                 self.engine.debugger().write().unwrap().remove_breakpoints(&source);
 
                 for breakpoint in &breakpoints_vec {
+                    // Reject conditions that don't parse up front; otherwise the error would only
+                    // surface when the breakpoint is first reached, aborting the run.
+                    if let Some(Err(message)) = breakpoint.condition.as_deref().map(validate_condition) {
+                        breakpoints_response.push(Breakpoint {
+                            verified: false,
+                            line: Some(breakpoint.line),
+                            column: breakpoint.column,
+                            end_line: None,
+                            end_column: None,
+                            source: Some(args.source.clone()),
+                            message: Some(message),
+                            id: None,
+                            instruction_reference: None,
+                            offset: None,
+                        });
+                        continue;
+                    }
+
                     let id = self.engine.debugger().write().unwrap().add_breakpoint_with_options(
                         breakpoint.line as usize,
                         breakpoint.column.map(|bp| bp as usize),
@@ -391,7 +675,7 @@ impl MqAdapter {
             Command::StackTrace(args) => {
                 debug!(?args, "Received StackTrace request");
 
-                let call_stack = if let Some(context) = &self.current_debug_context {
+                let call_stack = if let Some(context) = self.debug_context() {
                     context.call_stack.clone()
                 } else {
                     Vec::new()
@@ -405,7 +689,7 @@ impl MqAdapter {
                         .enumerate()
                         .map(|(i, frame)| {
                             let (file_name, token_range) = if i == 0 {
-                                if let Some(context) = self.current_debug_context.as_ref() {
+                                if let Some(context) = self.debug_context() {
                                     (
                                         self.get_source_file_name(Some(context.token.module_id)),
                                         context.token.range,
@@ -432,7 +716,7 @@ impl MqAdapter {
                             }
                         })
                         .collect::<Vec<_>>()
-                } else if let Some(ref context) = self.current_debug_context {
+                } else if let Some(context) = self.debug_context() {
                     vec![types::StackFrame {
                         id: 0,
                         name: format!(
@@ -465,7 +749,16 @@ impl MqAdapter {
             }
             Command::Variables(args) => {
                 debug!(?args, "Received Variables request");
-                let variables = if args.variables_reference == 1 {
+                let global = args.variables_reference == 1;
+                let variables = if args.variables_reference >= INPUT_VARIABLES_REFERENCE {
+                    self.get_input_variables(args.variables_reference)
+                } else if self.trace.is_replaying() {
+                    self.trace.variables(if global {
+                        VariableScope::Global
+                    } else {
+                        VariableScope::Local
+                    })
+                } else if global {
                     self.get_global_variables_from_context()
                 } else {
                     self.get_local_variables_from_context()
@@ -475,21 +768,28 @@ impl MqAdapter {
             }
             Command::SetVariable(args) => {
                 debug!(?args, "Received SetVariables request");
-                self.eval(format!("let {} = {}", args.name, args.value).as_str())?;
 
-                let value = args.value.clone();
-                let rsp = req.success(ResponseBody::SetVariable(SetVariableResponse {
-                    value,
-                    indexed_variables: None,
-                    named_variables: None,
-                    type_field: None,
-                    variables_reference: None,
-                }));
+                let result = if self.trace.is_replaying() {
+                    Err("Cannot set variables while replaying a previous stop".to_string())
+                } else {
+                    self.set_variable(&args.name, &args.value, args.variables_reference == 1)
+                };
+                let rsp = match result {
+                    Ok(variable) => req.success(ResponseBody::SetVariable(SetVariableResponse {
+                        value: variable.value,
+                        indexed_variables: None,
+                        named_variables: None,
+                        type_field: variable.type_field,
+                        variables_reference: None,
+                    })),
+                    Err(e) => req.error(&e),
+                };
                 server.respond(rsp)?;
             }
             Command::SetExpression(args) => {
                 debug!(?args, "Received SetExpression request");
                 self.eval(format!("let {} = {}", args.expression, args.value).as_str())?;
+                self.watch_cache.clear();
 
                 let value = args.value.clone();
                 let rsp = req.success(ResponseBody::SetExpression(SetExpressionResponse {
@@ -504,40 +804,73 @@ impl MqAdapter {
             }
             Command::Continue(_) => {
                 debug!("Received Continue request");
-                self.send_debugger_command(DapCommand::Continue)?;
                 let rsp = req.success(ResponseBody::Continue(ContinueResponse {
                     all_threads_continued: Some(true),
                 }));
                 server.respond(rsp)?;
+
+                if self.trace.is_replaying() && self.trace.continue_forward().is_some() {
+                    self.send_replayed_stop(server)?;
+                } else {
+                    self.send_debugger_command(DapCommand::Continue)?;
+                }
             }
             Command::Next(_) => {
                 debug!("Received Next request");
-                self.send_debugger_command(DapCommand::Next)?;
                 let rsp = req.success(ResponseBody::Next);
                 server.respond(rsp)?;
+                self.step(DapCommand::Next, server)?;
             }
             Command::StepIn(_) => {
                 debug!("Received StepIn request");
-                self.send_debugger_command(DapCommand::StepIn)?;
                 let rsp = req.success(ResponseBody::StepIn);
                 server.respond(rsp)?;
+                self.step(DapCommand::StepIn, server)?;
             }
             Command::StepOut(_) => {
                 debug!("Received StepOut request");
-                self.send_debugger_command(DapCommand::StepOut)?;
                 let rsp = req.success(ResponseBody::StepOut);
                 server.respond(rsp)?;
+                self.step(DapCommand::StepOut, server)?;
+            }
+            Command::StepBack(_) => {
+                debug!("Received StepBack request");
+                let rsp = req.success(ResponseBody::StepBack);
+                server.respond(rsp)?;
+
+                self.trace.step_back();
+                self.send_replayed_stop(server)?;
+            }
+            Command::ReverseContinue(_) => {
+                debug!("Received ReverseContinue request");
+                let rsp = req.success(ResponseBody::ReverseContinue);
+                server.respond(rsp)?;
+
+                self.trace.reverse_continue();
+                self.send_replayed_stop(server)?;
             }
             Command::ConfigurationDone => {
                 debug!("Received ConfigurationDone request");
                 let rsp = req.success(ResponseBody::ConfigurationDone);
                 server.respond(rsp)?;
+
+                // Breakpoints are in place, so the attached process can start evaluating.
+                if let Some(ready_tx) = self.ready_tx.take() {
+                    let _ = ready_tx.send(());
+                }
             }
-            Command::Disconnect(_) => {
+            Command::Disconnect(args) => {
                 debug!("Received Disconnect request");
 
-                // Send terminate command to debugger handler
-                let _ = self.send_debugger_command(DapCommand::Terminate);
+                // An attached process keeps running after the client detaches unless the client
+                // asks for it to be terminated.
+                let terminate = !self.attached || args.terminate_debuggee == Some(true);
+                let command = if terminate {
+                    DapCommand::Terminate
+                } else {
+                    DapCommand::Continue
+                };
+                let _ = self.send_debugger_command(command);
 
                 // Deactivate the debugger
                 self.engine.debugger().write().unwrap().deactivate();
@@ -549,24 +882,28 @@ impl MqAdapter {
             Command::Evaluate(args) => {
                 debug!(?args, "Received Evaluate request");
 
-                let rsp = match self.eval(&args.expression) {
-                    Ok(values) => {
-                        let result = values
-                            .values()
-                            .iter()
-                            .map(|v| v.to_string())
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        req.success(ResponseBody::Evaluate(EvaluateResponse {
-                            result,
-                            type_field: Some("string".to_string()),
-                            variables_reference: 0,
-                            named_variables: None,
-                            indexed_variables: None,
-                            presentation_hint: None,
-                            memory_reference: None,
-                        }))
-                    }
+                let result = if matches!(
+                    args.context,
+                    Some(types::EvaluateArgumentsContext::Watch | types::EvaluateArgumentsContext::Hover)
+                ) {
+                    self.eval_watch(&args.expression)
+                } else {
+                    self.watch_cache.clear();
+                    self.eval(&args.expression)
+                        .map(|values| format_values(&values))
+                        .map_err(|e| e.to_string())
+                };
+
+                let rsp = match result {
+                    Ok(result) => req.success(ResponseBody::Evaluate(EvaluateResponse {
+                        result,
+                        type_field: Some("string".to_string()),
+                        variables_reference: 0,
+                        named_variables: None,
+                        indexed_variables: None,
+                        presentation_hint: None,
+                        memory_reference: None,
+                    })),
                     Err(e) => req.error(&format!("Evaluation error: {}", e)),
                 };
                 server.respond(rsp)?
@@ -601,6 +938,19 @@ impl MqAdapter {
                         end_column: None,
                         presentation_hint: None,
                     },
+                    types::Scope {
+                        name: "INPUT".to_string(),
+                        variables_reference: INPUT_VARIABLES_REFERENCE,
+                        expensive: false,
+                        named_variables: None,
+                        indexed_variables: None,
+                        source: None,
+                        line: None,
+                        column: None,
+                        end_line: None,
+                        end_column: None,
+                        presentation_hint: None,
+                    },
                 ];
 
                 let rsp = req.success(ResponseBody::Scopes(ScopesResponse { scopes }));
@@ -620,11 +970,57 @@ impl MqAdapter {
     }
 }
 
+fn format_values(values: &mq_lang::RuntimeValues) -> String {
+    values
+        .values()
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Checks that a breakpoint condition is a valid mq expression.
+fn validate_condition(condition: &str) -> Result<(), String> {
+    let token_arena = Shared::new(mq_lang::SharedCell::new(mq_lang::Arena::new(16)));
+    mq_lang::parse(condition, token_arena)
+        .map(|_| ())
+        .map_err(|e| format!("Invalid breakpoint condition \"{condition}\": {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use dap::server::Server;
     use std::io::{BufReader, BufWriter, Cursor};
+    use std::sync::{Arc, Mutex};
+
+    /// Writer whose output stays readable after the server that wrote it is dropped.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl SharedOutput {
+        /// The body of the first message written.
+        fn message(&self) -> serde_json::Value {
+            let output = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            let (_, rest) = output.split_once("\r\n\r\n").unwrap();
+            serde_json::Deserializer::from_str(rest)
+                .into_iter::<serde_json::Value>()
+                .next()
+                .unwrap()
+                .unwrap()
+        }
+    }
 
     #[test]
     fn test_adapter_new_and_default() {
@@ -985,6 +1381,226 @@ mod tests {
         assert!(result.is_ok() || result.is_err());
     }
 
+    #[test]
+    fn test_eval_watch_does_not_leak_bindings() {
+        let mut adapter = MqAdapter::new();
+        adapter.current_debug_context = Some(mq_lang::DebugContext::default());
+        adapter.eval("let x = 1").unwrap();
+
+        assert_eq!(adapter.eval_watch("let y = 2 | x + y"), Ok("3".to_string()));
+        assert!(adapter.eval("y").is_err());
+    }
+
+    #[test]
+    fn test_eval_watch_is_cached_until_next_stop() {
+        let mut adapter = MqAdapter::new();
+        let input = BufReader::new(Cursor::new(Vec::new()));
+        let output = BufWriter::new(Cursor::new(Vec::new()));
+        let mut server = Server::new(input, output);
+        let context = mq_lang::DebugContext::default();
+        adapter.current_debug_context = Some(context.clone());
+        adapter.eval("let x = 1").unwrap();
+
+        assert_eq!(adapter.eval_watch("x"), Ok("1".to_string()));
+        adapter.eval("let x = 2").unwrap();
+        assert_eq!(adapter.eval_watch("x"), Ok("1".to_string()));
+
+        adapter
+            .handle_debugger_message(
+                DebuggerMessage::StepCompleted {
+                    thread_id: 1,
+                    line: 1,
+                    context,
+                },
+                &mut server,
+            )
+            .unwrap();
+        assert_eq!(adapter.eval_watch("x"), Ok("2".to_string()));
+    }
+
+    #[test]
+    fn test_eval_watch_without_context() {
+        let mut adapter = MqAdapter::new();
+        assert!(adapter.eval_watch("1 + 1").is_err());
+        assert!(adapter.watch_cache.is_empty());
+    }
+
+    #[test]
+    fn test_set_variable_updates_paused_environment() {
+        let mut adapter = MqAdapter::new();
+        adapter.current_debug_context = Some(mq_lang::DebugContext::default());
+        adapter.eval("let x = 1").unwrap();
+
+        let variable = adapter.set_variable("x", "x + 41", true).unwrap();
+
+        assert_eq!(variable.value, "42");
+        assert_eq!(variable.type_field.as_deref(), Some("number"));
+        assert_eq!(format_values(&adapter.eval("x").unwrap()), "42");
+    }
+
+    #[test]
+    fn test_set_variable_undefined() {
+        let mut adapter = MqAdapter::new();
+        adapter.current_debug_context = Some(mq_lang::DebugContext::default());
+
+        assert!(adapter.set_variable("missing", "1", true).is_err());
+        assert!(adapter.eval("missing").is_err());
+    }
+
+    #[test]
+    fn test_step_back_replays_recorded_stops() {
+        let mut adapter = MqAdapter::new();
+        let input = BufReader::new(Cursor::new(Vec::new()));
+        let output = BufWriter::new(Cursor::new(Vec::new()));
+        let mut server = Server::new(input, output);
+        let context = mq_lang::DebugContext::default();
+        adapter.current_debug_context = Some(context.clone());
+
+        for value in ["1", "2"] {
+            adapter.eval(&format!("let x = {}", value)).unwrap();
+            adapter
+                .handle_debugger_message(
+                    DebuggerMessage::StepCompleted {
+                        thread_id: 1,
+                        line: 1,
+                        context: context.clone(),
+                    },
+                    &mut server,
+                )
+                .unwrap();
+        }
+
+        let req = Request {
+            seq: 1,
+            command: Command::StepBack(dap::requests::StepBackArguments {
+                thread_id: 1,
+                single_thread: None,
+                granularity: None,
+            }),
+        };
+        adapter.handle_request(req, &mut server).unwrap();
+
+        assert!(adapter.trace.is_replaying());
+        assert_eq!(format_values(&adapter.eval("x").unwrap()), "1");
+        assert_eq!(adapter.trace.variables(VariableScope::Global)[0].value, "1");
+        let req = Request {
+            seq: 2,
+            command: Command::SetVariable(dap::requests::SetVariableArguments {
+                variables_reference: 1,
+                name: "x".to_string(),
+                value: "3".to_string(),
+                format: None,
+            }),
+        };
+        adapter.handle_request(req, &mut server).unwrap();
+        assert_eq!(format_values(&adapter.eval("x").unwrap()), "1");
+
+        let req = Request {
+            seq: 3,
+            command: Command::Next(dap::requests::NextArguments {
+                thread_id: 1,
+                single_thread: None,
+                granularity: None,
+            }),
+        };
+        adapter.handle_request(req, &mut server).unwrap();
+
+        assert!(!adapter.trace.is_replaying());
+        assert_eq!(format_values(&adapter.eval("x").unwrap()), "2");
+    }
+
+    #[test]
+    fn test_set_variable_after_step_back_updates_live_context() {
+        let mut adapter = MqAdapter::new();
+        let input = BufReader::new(Cursor::new(Vec::new()));
+        let output = BufWriter::new(Cursor::new(Vec::new()));
+        let mut server = Server::new(input, output);
+        let context = mq_lang::DebugContext::default();
+        adapter.current_debug_context = Some(context.clone());
+
+        for value in ["1", "2"] {
+            adapter.eval(&format!("let x = {}", value)).unwrap();
+            adapter
+                .handle_debugger_message(
+                    DebuggerMessage::StepCompleted {
+                        thread_id: 1,
+                        line: 1,
+                        context: context.clone(),
+                    },
+                    &mut server,
+                )
+                .unwrap();
+        }
+
+        let requests = [
+            Command::StepBack(dap::requests::StepBackArguments {
+                thread_id: 1,
+                single_thread: None,
+                granularity: None,
+            }),
+            Command::StepIn(dap::requests::StepInArguments {
+                thread_id: 1,
+                single_thread: None,
+                target_id: None,
+                granularity: None,
+            }),
+            Command::SetVariable(dap::requests::SetVariableArguments {
+                variables_reference: 1,
+                name: "x".to_string(),
+                value: "5".to_string(),
+                format: None,
+            }),
+        ];
+        for (seq, command) in requests.into_iter().enumerate() {
+            adapter
+                .handle_request(
+                    Request {
+                        seq: seq as i64,
+                        command,
+                    },
+                    &mut server,
+                )
+                .unwrap();
+        }
+
+        assert!(!adapter.trace.is_replaying());
+        let values = mq_lang::DefaultEngine::default()
+            .switch_env(Shared::clone(&context.env))
+            .eval("x", mq_lang::null_input().into_iter())
+            .unwrap();
+        assert_eq!(format_values(&values), "5");
+    }
+
+    #[test]
+    fn test_get_input_variables() {
+        let mut adapter = MqAdapter::new();
+        adapter.current_debug_context = Some(mq_lang::DebugContext {
+            current_value: mq_lang::parse_markdown_input("# Title").unwrap().remove(0),
+            ..Default::default()
+        });
+
+        let variables = adapter.get_input_variables(INPUT_VARIABLES_REFERENCE);
+        assert_eq!(variables[0].name, "markdown");
+        assert_eq!(variables[0].value, "# Title");
+        assert_eq!(variables[0].type_field.as_deref(), Some("markdown"));
+
+        let depth = variables.iter().find(|v| v.name == "depth").unwrap();
+        assert_eq!(depth.value, "1");
+        let values = variables.iter().find(|v| v.name == "values").unwrap();
+        assert!(values.variables_reference > INPUT_VARIABLES_REFERENCE);
+
+        let children = adapter.get_input_variables(values.variables_reference);
+        assert_eq!(children.len(), 1);
+        assert_eq!(children[0].name, "0");
+    }
+
+    #[test]
+    fn test_get_input_variables_without_context() {
+        let mut adapter = MqAdapter::new();
+        assert!(adapter.get_input_variables(INPUT_VARIABLES_REFERENCE).is_empty());
+        assert!(adapter.get_input_variables(INPUT_VARIABLES_REFERENCE + 1).is_empty());
+    }
+
     #[test]
     fn test_handle_request_set_variable() {
         let mut adapter = MqAdapter::new();
@@ -1128,6 +1744,71 @@ mod tests {
         assert_eq!(stored[0].log_message.as_deref(), Some("x is {x}"));
     }
 
+    #[test]
+    fn test_handle_request_set_breakpoints_rejects_invalid_condition() {
+        let mut adapter = MqAdapter::new();
+        adapter.query_file = Some("/path/to/test.mq".to_string());
+        let input = BufReader::new(Cursor::new(Vec::new()));
+        let output = BufWriter::new(Cursor::new(Vec::new()));
+        let mut server = Server::new(input, output);
+
+        let source = dap::types::Source {
+            name: Some("test.mq".to_string()),
+            path: Some("/path/to/test.mq".to_string()),
+            adapter_data: None,
+            source_reference: None,
+            presentation_hint: None,
+            origin: None,
+            checksums: None,
+            sources: None,
+        };
+
+        let breakpoints = vec![
+            dap::types::SourceBreakpoint {
+                line: 3,
+                column: None,
+                condition: Some("x >".to_string()),
+                hit_condition: None,
+                log_message: None,
+            },
+            dap::types::SourceBreakpoint {
+                line: 5,
+                column: None,
+                condition: Some("x > 1".to_string()),
+                hit_condition: None,
+                log_message: None,
+            },
+        ];
+
+        #[allow(deprecated)]
+        let req = Request {
+            seq: 1,
+            command: Command::SetBreakpoints(dap::requests::SetBreakpointsArguments {
+                source,
+                breakpoints: Some(breakpoints),
+                lines: None,
+                source_modified: None,
+            }),
+        };
+
+        let result = adapter.handle_request(req, &mut server);
+        assert!(result.is_ok());
+
+        let debugger = adapter.engine.debugger();
+        let debugger = debugger.read().unwrap();
+        let stored = debugger.list_breakpoints();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].line, 5);
+    }
+
+    #[test]
+    fn test_validate_condition() {
+        assert!(validate_condition("x > 1").is_ok());
+        assert!(validate_condition("is_string(x)").is_ok());
+        assert!(validate_condition("x >").is_err());
+        assert!(validate_condition("(x").is_err());
+    }
+
     #[test]
     fn test_handle_request_set_expression() {
         let mut adapter = MqAdapter::new();
@@ -1222,6 +1903,69 @@ mod tests {
         assert_eq!(adapter.query_file, Some("/tmp/test_query.mq".to_string()));
     }
 
+    #[test]
+    fn test_handle_request_attach_without_process() {
+        let mut adapter = MqAdapter::new();
+        let output = SharedOutput::default();
+        let mut server = Server::new(BufReader::new(Cursor::new(Vec::new())), BufWriter::new(output.clone()));
+
+        let req = Request {
+            seq: 1,
+            command: Command::Attach(dap::requests::AttachRequestArguments {
+                restart_data: None,
+                additional_data: Some(serde_json::json!({"queryFile": "/tmp/test_attach.mq"})),
+            }),
+        };
+
+        assert!(adapter.handle_request(req, &mut server).is_ok());
+        drop(server);
+
+        // A standalone adapter has no running process, so attach fails instead of launching one.
+        let response = output.message();
+        assert_eq!(response["request_seq"], 1);
+        assert_eq!(response["success"], false);
+        assert!(adapter.query_file.is_none());
+        assert!(!adapter.engine.debugger().read().unwrap().is_active());
+    }
+
+    #[test]
+    fn test_handle_request_launch_when_attached() {
+        let (_, message_rx) = crossbeam_channel::unbounded();
+        let (command_tx, _) = crossbeam_channel::unbounded();
+        let (ready_tx, ready_rx) = crossbeam_channel::bounded(1);
+        let mut adapter = MqAdapter::attached(
+            mq_lang::DefaultEngine::default(),
+            message_rx,
+            command_tx,
+            None,
+            ready_tx,
+        );
+        let output = SharedOutput::default();
+        let mut server = Server::new(BufReader::new(Cursor::new(Vec::new())), BufWriter::new(output.clone()));
+
+        let req = Request {
+            seq: 1,
+            command: Command::Launch(dap::requests::LaunchRequestArguments {
+                no_debug: None,
+                restart_data: None,
+                additional_data: Some(serde_json::json!({"queryFile": "/tmp/test_attach.mq"})),
+            }),
+        };
+        assert!(adapter.handle_request(req, &mut server).is_ok());
+        assert!(adapter.query_file.is_none());
+
+        assert!(ready_rx.try_recv().is_err());
+        let req = Request {
+            seq: 2,
+            command: Command::ConfigurationDone,
+        };
+        assert!(adapter.handle_request(req, &mut server).is_ok());
+        assert!(ready_rx.try_recv().is_ok());
+        drop(server);
+
+        assert_eq!(output.message()["success"], false);
+    }
+
     #[test]
     fn test_handle_request_stack_trace_with_context() {
         let mut adapter = MqAdapter::new();
@@ -7,8 +7,8 @@
 //!
 //! - Full DAP protocol support for mq debugging
 //! - Breakpoint management, including conditional breakpoints, hit count breakpoints, and logpoints
-//! - Step-through execution (step in, step out, step over)
-//! - Variable inspection and watch expressions
+//! - Step-through execution (step in, step out, step over), and stepping back through recorded stops
+//! - Variable inspection, including the current input value, and watch expressions
 //! - Stack trace visualization
 //! - Expression evaluation in debug context
 //!
@@ -29,7 +29,9 @@
 //! # Protocol Support
 //!
 //! This implementation supports the Debug Adapter Protocol as specified by Microsoft.
-//! The server communicates over stdin/stdout using JSON-RPC messages.
+//! The server communicates over stdin/stdout using JSON-RPC messages, or over a local TCP
+//! port with [`server::start_tcp`]. [`server::listen`] lets editors attach to an mq process
+//! that is about to evaluate its own query.
 //!
 //! # Integration
 //!
@@ -46,6 +48,7 @@
 //! - `executor`: Debug execution engine
 //! - `handler`: Request and event handlers
 //! - `server`: DAP server implementation
+//! - `trace`: Execution trace for stepping back
 
 pub mod adapter;
 pub mod error;
@@ -54,5 +57,6 @@ pub mod handler;
 pub mod log;
 pub mod protocol;
 pub mod server;
+pub mod trace;
 
 pub use server::start;
@@ -45,7 +45,7 @@ pub enum DapCommand {
     Terminate,
 }
 
-/// Launch arguments for DAP launch configuration
+/// Arguments for DAP launch and attach configurations
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LaunchArgs {
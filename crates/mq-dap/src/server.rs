@@ -1,15 +1,122 @@
+use crossbeam_channel::{Receiver, Sender};
 use dap::prelude::*;
 use std::borrow::Cow;
 use std::io::{self, BufReader, BufWriter};
+use std::net::{Ipv4Addr, TcpListener};
+use std::thread::{self, JoinHandle};
 use tracing::{debug, error, info};
 
 use crate::adapter::MqAdapter;
 use crate::error::MqAdapterError;
+use crate::handler::{DapDebuggerHandler, DapHandlerWrapper};
 use crate::log::DebugConsoleWriter;
+use crate::protocol::{DapCommand, DebuggerMessage};
 
 type DynResult<T> = miette::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
+/// Start the debug adapter over stdin/stdout.
 pub fn start() -> DynResult<()> {
+    let log_rx = init_tracing();
+    info!("Starting mq-dap debug adapter");
+
+    run_session(MqAdapter::default(), io::stdin(), io::stdout(), &log_rx)
+}
+
+/// Start the debug adapter on a local TCP port.
+///
+/// The adapter keeps listening after a client disconnects, so editors can attach to it
+/// again later. Each connection is an independent debug session.
+pub fn start_tcp(port: u16) -> DynResult<()> {
+    let log_rx = init_tracing();
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    info!(port = port, "Starting mq-dap debug adapter on TCP");
+
+    serve(listener, &log_rx)
+}
+
+/// Serve debug sessions for each client connecting to `listener`, one at a time.
+fn serve(listener: TcpListener, log_rx: &Receiver<String>) -> DynResult<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        info!(peer = ?stream.peer_addr().ok(), "Debug client connected");
+
+        if let Err(e) = run_session(MqAdapter::default(), stream.try_clone()?, stream, log_rx) {
+            error!(error = %e, "Debug session failed");
+        }
+    }
+
+    Ok(())
+}
+
+/// A debug client attached to an mq process started with `--debug-listen`.
+///
+/// The process evaluates on its own thread with the engine passed to [`listen`], stopping at
+/// the client's breakpoints, and must call [`AttachSession::finish`] once evaluation ends.
+pub struct AttachSession {
+    message_tx: Sender<DebuggerMessage>,
+    handle: JoinHandle<()>,
+}
+
+impl AttachSession {
+    /// Tell the client that evaluation has ended and wait for it to disconnect.
+    pub fn finish(self) {
+        if let Err(e) = self.message_tx.send(DebuggerMessage::Terminated) {
+            error!(error = %e, "Failed to send terminated message");
+        }
+        let _ = self.handle.join();
+    }
+}
+
+/// Wait on a local TCP port for a debug client to attach to `engine`.
+///
+/// Blocks until a client connects, sends `attach`, and finishes setting breakpoints with
+/// `configurationDone`, so evaluation never runs ahead of them. `query_file` is the source the
+/// client's breakpoints refer to, if the query was loaded from a file.
+pub fn listen(port: u16, engine: &mut mq_lang::DefaultEngine, query_file: Option<String>) -> DynResult<AttachSession> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    info!(port = port, "Waiting for a debug client to attach");
+
+    attach(listener, engine, query_file)
+}
+
+fn attach(
+    listener: TcpListener,
+    engine: &mut mq_lang::DefaultEngine,
+    query_file: Option<String>,
+) -> DynResult<AttachSession> {
+    let (message_tx, message_rx) = crossbeam_channel::unbounded::<DebuggerMessage>();
+    let (command_tx, command_rx) = crossbeam_channel::unbounded::<DapCommand>();
+    let (ready_tx, ready_rx) = crossbeam_channel::bounded::<()>(1);
+
+    let handler = DapDebuggerHandler::new(message_tx.clone());
+    engine.set_debugger_handler(Box::new(DapHandlerWrapper::new(handler, command_rx)));
+    engine.debugger().write().unwrap().activate();
+
+    let adapter = MqAdapter::attached(engine.clone(), message_rx, command_tx, query_file, ready_tx);
+    let (stream, peer) = listener.accept()?;
+    info!(peer = ?peer, "Debug client attached");
+
+    let handle = thread::spawn(move || {
+        let result = stream
+            .try_clone()
+            .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+            .and_then(|reader| run_session(adapter, reader, stream, &crossbeam_channel::never()));
+        if let Err(e) = result {
+            error!(error = %e, "Debug session failed");
+        }
+    });
+
+    if ready_rx.recv().is_err() {
+        let _ = handle.join();
+        return Err(Box::new(MqAdapterError::ProtocolError(Cow::Borrowed(
+            "Debug client disconnected before attaching",
+        ))));
+    }
+
+    Ok(AttachSession { message_tx, handle })
+}
+
+fn init_tracing() -> Receiver<String> {
     let (debug_writer, log_rx) = DebugConsoleWriter::new();
 
     #[cfg(debug_assertions)]
@@ -22,17 +129,26 @@ pub fn start() -> DynResult<()> {
         .with_writer(debug_writer)
         .init();
 
-    info!("Starting mq-dap debug adapter");
+    log_rx
+}
 
-    let mut adapter = MqAdapter::default();
-    let reader = BufReader::new(io::stdin());
-    let writer = BufWriter::new(io::stdout());
-    let mut server = Server::new(reader, writer);
+/// Run a single debug session with `adapter` until the client disconnects.
+///
+/// Requests are read on a separate thread, so stops and output from the running query reach the
+/// client as soon as they happen instead of waiting for its next request.
+fn run_session(
+    mut adapter: MqAdapter,
+    reader: impl io::Read + Send + 'static,
+    writer: impl io::Write,
+    log_rx: &Receiver<String>,
+) -> DynResult<()> {
+    let request_rx = read_requests(reader);
+    let mut server = Server::new(BufReader::new(io::empty()), BufWriter::new(writer));
 
     // First, the client sends an initialize request
-    let req = match server.poll_request()? {
-        Some(req) => req,
-        None => {
+    let req = match request_rx.recv() {
+        Ok(req) => req?,
+        Err(_) => {
             return Err(Box::new(MqAdapterError::ProtocolError(Cow::Borrowed(
                 "Missing initialize request",
             ))));
@@ -49,6 +165,7 @@ pub fn start() -> DynResult<()> {
             supports_conditional_breakpoints: Some(true),
             supports_hit_conditional_breakpoints: Some(true),
             supports_log_points: Some(true),
+            supports_step_back: Some(true),
             ..Default::default()
         };
         let rsp = req.success(ResponseBody::Initialize(capabilities));
@@ -60,40 +177,227 @@ pub fn start() -> DynResult<()> {
         ))));
     }
 
-    loop {
-        debug!("Checking for log messages");
-        // Process all available log messages
-        while let Ok(log_message) = log_rx.clone().try_recv() {
-            if let Err(e) = adapter.send_log_output(&log_message, &mut server) {
-                eprintln!("Failed to send log output: {}", e);
-            }
-        }
+    let mut message_rx = adapter
+        .debugger_message_rx()
+        .clone()
+        .unwrap_or_else(crossbeam_channel::never);
+    let mut log_rx = log_rx.clone();
 
+    loop {
         debug!("Waiting for next request or debugger message");
-        if let Some(rx) = adapter.debugger_message_rx()
-            && let Ok(message) = rx.try_recv()
-            && let Err(e) = adapter.handle_debugger_message(message, &mut server)
-        {
-            error!(error = %e, "Failed to handle debugger message");
+        crossbeam_channel::select! {
+            recv(log_rx) -> log_message => match log_message {
+                Ok(log_message) => {
+                    if let Err(e) = adapter.send_log_output(&log_message, &mut server) {
+                        eprintln!("Failed to send log output: {}", e);
+                    }
+                }
+                Err(_) => log_rx = crossbeam_channel::never(),
+            },
+            recv(message_rx) -> message => match message {
+                Ok(message) => {
+                    if let Err(e) = adapter.handle_debugger_message(message, &mut server) {
+                        error!(error = %e, "Failed to handle debugger message");
+                    }
+                }
+                Err(_) => message_rx = crossbeam_channel::never(),
+            },
+            recv(request_rx) -> req => match req {
+                Ok(req) => {
+                    if let Err(e) = adapter.handle_request(req?, &mut server) {
+                        error!(error = %e, "Failed to handle DAP request");
+                        if let Some(MqAdapterError::ProtocolError(msg)) = e.downcast_ref::<MqAdapterError>()
+                            && msg == "Shutdown"
+                        {
+                            break;
+                        }
+                    }
+                }
+                Err(_) => {
+                    info!("Client disconnected or stream ended");
+                    break;
+                }
+            },
         }
+    }
 
-        match server.poll_request()? {
-            Some(req) => {
-                if let Err(e) = adapter.handle_request(req, &mut server) {
-                    error!(error = %e, "Failed to handle DAP request");
-                    if let Some(MqAdapterError::ProtocolError(msg)) = e.downcast_ref::<MqAdapterError>()
-                        && msg == "Shutdown"
-                    {
+    Ok(())
+}
+
+/// Read requests from `reader` on a background thread until the stream ends.
+fn read_requests(reader: impl io::Read + Send + 'static) -> Receiver<Result<Request, dap::errors::ServerError>> {
+    let (request_tx, request_rx) = crossbeam_channel::unbounded();
+
+    thread::spawn(move || {
+        let mut input = Server::new(BufReader::new(reader), BufWriter::new(io::sink()));
+        loop {
+            match input.poll_request() {
+                Ok(Some(req)) => {
+                    if request_tx.send(Ok(req)).is_err() {
                         break;
                     }
                 }
+                Ok(None) => break,
+                Err(e) => {
+                    let _ = request_tx.send(Err(e));
+                    break;
+                }
             }
-            None => {
-                info!("Client disconnected or stream ended");
-                break;
-            }
         }
+    });
+
+    request_rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, Read, Write};
+    use std::net::TcpStream;
+
+    fn send(stream: &mut TcpStream, body: serde_json::Value) {
+        let body = body.to_string();
+        write!(stream, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+    }
+
+    fn receive(reader: &mut BufReader<TcpStream>) -> serde_json::Value {
+        // Each message is followed by a trailing newline, so skip blank lines before the header.
+        let mut header = String::new();
+        while header.trim().is_empty() {
+            header.clear();
+            reader.read_line(&mut header).unwrap();
+        }
+        let len: usize = header.trim().trim_start_matches("Content-Length: ").parse().unwrap();
+        reader.read_line(&mut String::new()).unwrap();
+        let mut body = vec![0; len];
+        reader.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
     }
 
-    Ok(())
+    #[test]
+    fn test_serve_accepts_sessions_over_tcp() {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (_, log_rx) = DebugConsoleWriter::new();
+        std::thread::spawn(move || serve(listener, &log_rx));
+
+        for _ in 0..2 {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            send(
+                &mut stream,
+                serde_json::json!({"seq": 1, "type": "request", "command": "initialize", "arguments": {"adapterID": "mq"}}),
+            );
+            let response = receive(&mut reader);
+            assert_eq!(response["command"], "initialize");
+            assert_eq!(response["success"], true);
+            assert_eq!(response["body"]["supportsStepBack"], true);
+            assert_eq!(receive(&mut reader)["event"], "initialized");
+
+            send(
+                &mut stream,
+                serde_json::json!({"seq": 2, "type": "request", "command": "disconnect", "arguments": {}}),
+            );
+            assert_eq!(receive(&mut reader)["command"], "disconnect");
+        }
+    }
+
+    #[test]
+    fn test_attach_debugs_running_evaluation() {
+        let dir = tempfile::tempdir().unwrap();
+        let query_file = dir.path().join("query.mq");
+        let query = "let x = 1\n| x + 1";
+        std::fs::write(&query_file, query).unwrap();
+        let query_path = query_file.to_string_lossy().to_string();
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        let source_path = query_path.clone();
+        let evaluation = std::thread::spawn(move || {
+            let mut engine = mq_lang::DefaultEngine::default();
+            engine.load_builtin_module();
+            let session = attach(listener, &mut engine, Some(source_path)).unwrap();
+            let result = engine.eval(query, mq_lang::null_input().into_iter());
+            session.finish();
+            result
+                .unwrap()
+                .values()
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+        });
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": 1, "type": "request", "command": "initialize", "arguments": {"adapterID": "mq"}}),
+        );
+        assert_eq!(receive(&mut reader)["success"], true);
+        assert_eq!(receive(&mut reader)["event"], "initialized");
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": 2, "type": "request", "command": "attach", "arguments": {}}),
+        );
+        let response = receive(&mut reader);
+        assert_eq!(response["command"], "attach");
+        assert_eq!(response["success"], true);
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": 3, "type": "request", "command": "setBreakpoints", "arguments": {
+                "source": {"path": query_path},
+                "breakpoints": [{"line": 2}]
+            }}),
+        );
+        assert_eq!(receive(&mut reader)["body"]["breakpoints"][0]["verified"], true);
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": 4, "type": "request", "command": "configurationDone"}),
+        );
+        assert_eq!(receive(&mut reader)["command"], "configurationDone");
+
+        // The process stops at the breakpoint without the client having to poll for it.
+        let stopped = receive(&mut reader);
+        assert_eq!(stopped["event"], "stopped");
+        assert_eq!(stopped["body"]["reason"], "breakpoint");
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": 5, "type": "request", "command": "evaluate", "arguments": {
+                "expression": "x", "context": "watch"
+            }}),
+        );
+        assert_eq!(receive(&mut reader)["body"]["result"], "1");
+
+        // Continue past every stop on the breakpoint line until the evaluation ends.
+        let mut seq = 6;
+        loop {
+            send(
+                &mut stream,
+                serde_json::json!({"seq": seq, "type": "request", "command": "continue", "arguments": {"threadId": 1}}),
+            );
+            seq += 1;
+            assert_eq!(receive(&mut reader)["command"], "continue");
+            match receive(&mut reader)["event"].as_str() {
+                Some("stopped") => continue,
+                event => {
+                    assert_eq!(event, Some("terminated"));
+                    break;
+                }
+            }
+        }
+
+        send(
+            &mut stream,
+            serde_json::json!({"seq": seq, "type": "request", "command": "disconnect", "arguments": {}}),
+        );
+        assert_eq!(receive(&mut reader)["command"], "disconnect");
+
+        assert_eq!(evaluation.join().unwrap(), vec!["2".to_string()]);
+    }
 }
@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use dap::types;
+
+/// Maximum number of stops kept in the execution trace; older stops are dropped first.
+pub const MAX_TRACE_ENTRIES: usize = 1000;
+
+/// Why execution stopped at a recorded point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint(usize),
+    Step,
+    Pause,
+}
+
+/// Scope a recorded variable belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VariableScope {
+    Global,
+    Local,
+}
+
+type Variables = BTreeMap<(VariableScope, String), types::Variable>;
+
+/// A recorded stop: where execution was, and how the visible variables changed since the
+/// previous stop.
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    pub context: mq_lang::DebugContext,
+    pub reason: StopReason,
+    changed: Vec<((VariableScope, String), types::Variable)>,
+    removed: Vec<(VariableScope, String)>,
+}
+
+/// Execution trace recorded while debugging, used for `stepBack` and `reverseContinue`.
+///
+/// Each stop is recorded with a snapshot of its context and the variable changes since the
+/// previous stop. Moving backwards replays the recorded stops; moving forwards again walks the
+/// trace until the live stop is reached, after which the program resumes for real.
+#[derive(Debug, Default)]
+pub struct ExecutionTrace {
+    entries: VecDeque<TraceEntry>,
+    /// Variables at the stop before the oldest recorded entry.
+    base: Variables,
+    /// Variables at the newest (live) entry.
+    latest: Variables,
+    /// Index of the replayed entry, or `None` when at the live stop.
+    position: Option<usize>,
+}
+
+impl ExecutionTrace {
+    /// Records a new live stop and leaves replay mode.
+    pub fn record(
+        &mut self,
+        context: &mq_lang::DebugContext,
+        reason: StopReason,
+        globals: Vec<types::Variable>,
+        locals: Vec<types::Variable>,
+    ) {
+        let variables: Variables = globals
+            .into_iter()
+            .map(|v| ((VariableScope::Global, v.name.clone()), v))
+            .chain(locals.into_iter().map(|v| ((VariableScope::Local, v.name.clone()), v)))
+            .collect();
+
+        let changed = variables
+            .iter()
+            .filter(|(key, variable)| {
+                self.latest.get(*key).is_none_or(|previous| {
+                    previous.value != variable.value || previous.type_field != variable.type_field
+                })
+            })
+            .map(|(key, variable)| (key.clone(), variable.clone()))
+            .collect();
+        let removed = self
+            .latest
+            .keys()
+            .filter(|key| !variables.contains_key(*key))
+            .cloned()
+            .collect();
+
+        self.entries.push_back(TraceEntry {
+            context: context.snapshot(),
+            reason,
+            changed,
+            removed,
+        });
+        self.latest = variables;
+        self.position = None;
+
+        if self.entries.len() > MAX_TRACE_ENTRIES
+            && let Some(oldest) = self.entries.pop_front()
+        {
+            apply(&mut self.base, &oldest);
+        }
+    }
+
+    /// Returns `true` while a recorded stop other than the live one is being shown.
+    pub fn is_replaying(&self) -> bool {
+        self.position.is_some()
+    }
+
+    /// Returns the index of the shown stop and the number of recorded stops.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.current_index().map(|index| (index, self.entries.len()))
+    }
+
+    /// Returns the stop being shown.
+    pub fn current(&self) -> Option<&TraceEntry> {
+        self.current_index().and_then(|index| self.entries.get(index))
+    }
+
+    /// Moves to the previous recorded stop, staying at the oldest one.
+    pub fn step_back(&mut self) -> Option<&TraceEntry> {
+        let index = self.current_index()?.saturating_sub(1);
+        self.move_to(index)
+    }
+
+    /// Moves back to the previous stop caused by a breakpoint, or to the oldest recorded stop.
+    pub fn reverse_continue(&mut self) -> Option<&TraceEntry> {
+        let current = self.current_index()?;
+        let index = (0..current)
+            .rev()
+            .find(|index| matches!(self.entries[*index].reason, StopReason::Breakpoint(_)))
+            .unwrap_or(0);
+        self.move_to(index)
+    }
+
+    /// Moves to the next recorded stop while replaying.
+    ///
+    /// Returns `None` when not replaying, in which case the live program should step instead.
+    pub fn step_forward(&mut self) -> Option<&TraceEntry> {
+        let index = self.position? + 1;
+        self.move_to(index)
+    }
+
+    /// Moves forward to the next recorded breakpoint stop while replaying.
+    ///
+    /// Returns `None` if there is no such stop, after leaving replay mode, so the live program
+    /// should continue instead.
+    pub fn continue_forward(&mut self) -> Option<&TraceEntry> {
+        let position = self.position?;
+        match (position + 1..self.entries.len())
+            .find(|index| matches!(self.entries[*index].reason, StopReason::Breakpoint(_)))
+        {
+            Some(index) => self.move_to(index),
+            None => {
+                self.position = None;
+                None
+            }
+        }
+    }
+
+    /// Returns the variables in `scope` at the stop being shown.
+    pub fn variables(&self, scope: VariableScope) -> Vec<types::Variable> {
+        let variables = match (self.position, self.current_index()) {
+            (None, _) | (_, None) => self.latest.clone(),
+            (Some(_), Some(index)) => self
+                .entries
+                .iter()
+                .take(index + 1)
+                .fold(self.base.clone(), |mut acc, entry| {
+                    apply(&mut acc, entry);
+                    acc
+                }),
+        };
+
+        variables
+            .into_iter()
+            .filter(|((variable_scope, _), _)| *variable_scope == scope)
+            .map(|(_, variable)| variable)
+            .collect()
+    }
+
+    fn current_index(&self) -> Option<usize> {
+        match self.position {
+            Some(index) => Some(index),
+            None => self.entries.len().checked_sub(1),
+        }
+    }
+
+    fn move_to(&mut self, index: usize) -> Option<&TraceEntry> {
+        let last = self.entries.len().checked_sub(1)?;
+        let index = index.min(last);
+        self.position = (index != last).then_some(index);
+        self.entries.get(index)
+    }
+}
+
+fn apply(variables: &mut Variables, entry: &TraceEntry) {
+    for key in &entry.removed {
+        variables.remove(key);
+    }
+    for (key, variable) in &entry.changed {
+        variables.insert(key.clone(), variable.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable(name: &str, value: &str) -> types::Variable {
+        types::Variable {
+            name: name.to_string(),
+            value: value.to_string(),
+            type_field: Some("number".to_string()),
+            variables_reference: 0,
+            named_variables: None,
+            indexed_variables: None,
+            presentation_hint: None,
+            evaluate_name: Some(name.to_string()),
+            memory_reference: None,
+        }
+    }
+
+    fn values(variables: Vec<types::Variable>) -> Vec<(String, String)> {
+        variables.into_iter().map(|v| (v.name, v.value)).collect()
+    }
+
+    fn trace() -> ExecutionTrace {
+        let context = mq_lang::DebugContext::default();
+        let mut trace = ExecutionTrace::default();
+        trace.record(&context, StopReason::Breakpoint(1), vec![variable("x", "1")], vec![]);
+        trace.record(
+            &context,
+            StopReason::Step,
+            vec![variable("x", "2")],
+            vec![variable("y", "3")],
+        );
+        trace.record(&context, StopReason::Breakpoint(2), vec![variable("x", "2")], vec![]);
+        trace.record(&context, StopReason::Step, vec![variable("x", "4")], vec![]);
+        trace
+    }
+
+    #[test]
+    fn test_step_back_replays_variables() {
+        let mut trace = trace();
+        assert!(!trace.is_replaying());
+        assert_eq!(trace.position(), Some((3, 4)));
+
+        let entry = trace.step_back().unwrap();
+        assert_eq!(entry.reason, StopReason::Breakpoint(2));
+        assert!(trace.is_replaying());
+        assert_eq!(
+            values(trace.variables(VariableScope::Global)),
+            vec![("x".into(), "2".into())]
+        );
+        assert!(trace.variables(VariableScope::Local).is_empty());
+
+        trace.step_back();
+        assert_eq!(
+            values(trace.variables(VariableScope::Local)),
+            vec![("y".into(), "3".into())]
+        );
+
+        trace.step_back();
+        trace.step_back();
+        assert_eq!(trace.position(), Some((0, 4)));
+        assert_eq!(
+            values(trace.variables(VariableScope::Global)),
+            vec![("x".into(), "1".into())]
+        );
+    }
+
+    #[test]
+    fn test_reverse_continue_stops_at_previous_breakpoint() {
+        let mut trace = trace();
+
+        assert_eq!(trace.reverse_continue().unwrap().reason, StopReason::Breakpoint(2));
+        assert_eq!(trace.position(), Some((2, 4)));
+        assert_eq!(trace.reverse_continue().unwrap().reason, StopReason::Breakpoint(1));
+        assert_eq!(trace.position(), Some((0, 4)));
+        assert_eq!(trace.reverse_continue().unwrap().reason, StopReason::Breakpoint(1));
+    }
+
+    #[test]
+    fn test_forward_moves_return_to_live_stop() {
+        let mut trace = trace();
+        assert!(trace.step_forward().is_none());
+
+        trace.reverse_continue();
+        trace.reverse_continue();
+        assert_eq!(trace.continue_forward().unwrap().reason, StopReason::Breakpoint(2));
+        assert_eq!(trace.step_forward().unwrap().reason, StopReason::Step);
+        assert!(!trace.is_replaying());
+        assert_eq!(
+            values(trace.variables(VariableScope::Global)),
+            vec![("x".into(), "4".into())]
+        );
+
+        trace.step_back();
+        assert!(trace.continue_forward().is_none());
+        assert!(!trace.is_replaying());
+    }
+
+    #[test]
+    fn test_oldest_entries_are_dropped() {
+        let context = mq_lang::DebugContext::default();
+        let mut trace = ExecutionTrace::default();
+        for i in 0..MAX_TRACE_ENTRIES + 5 {
+            trace.record(&context, StopReason::Step, vec![variable("i", &i.to_string())], vec![]);
+        }
+
+        assert_eq!(trace.position(), Some((MAX_TRACE_ENTRIES - 1, MAX_TRACE_ENTRIES)));
+        trace.reverse_continue();
+        assert_eq!(
+            values(trace.variables(VariableScope::Global)),
+            vec![("i".into(), "5".into())]
+        );
+    }
+
+    #[test]
+    fn test_empty_trace() {
+        let mut trace = ExecutionTrace::default();
+        assert!(trace.step_back().is_none());
+        assert!(trace.reverse_continue().is_none());
+        assert!(trace.current().is_none());
+        assert!(trace.variables(VariableScope::Global).is_empty());
+    }
+}
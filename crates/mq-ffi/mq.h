@@ -6,6 +6,96 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Type of a result value in `MqResult.value_types`.
+ */
+typedef enum MqValueType {
+  MQ_VALUE_TYPE_NONE = 0,
+  /**
+   * A string or a symbol.
+   */
+  MQ_VALUE_TYPE_STRING = 1,
+  MQ_VALUE_TYPE_NUMBER = 2,
+  MQ_VALUE_TYPE_BOOL = 3,
+  /**
+   * A markdown node, rendered as markdown.
+   */
+  MQ_VALUE_TYPE_MARKDOWN = 4,
+  MQ_VALUE_TYPE_ARRAY = 5,
+  MQ_VALUE_TYPE_DICT = 6,
+  /**
+   * Any other value, such as a function or bytes.
+   */
+  MQ_VALUE_TYPE_OTHER = 7,
+} MqValueType;
+
+/**
+ * Kind of error reported in `MqResult.error_code`.
+ */
+typedef enum MqErrorCode {
+  MQ_ERROR_CODE_OK = 0,
+  /**
+   * A null pointer or an invalid UTF-8 string was passed.
+   */
+  MQ_ERROR_CODE_INVALID_ARGUMENT = 1,
+  /**
+   * The input format is not supported.
+   */
+  MQ_ERROR_CODE_UNSUPPORTED_FORMAT = 2,
+  /**
+   * The input could not be parsed in the given format.
+   */
+  MQ_ERROR_CODE_INPUT_PARSE = 3,
+  /**
+   * The query has a syntax error.
+   */
+  MQ_ERROR_CODE_SYNTAX = 4,
+  /**
+   * The query failed while being evaluated.
+   */
+  MQ_ERROR_CODE_RUNTIME = 5,
+  /**
+   * The evaluation exceeded the timeout.
+   */
+  MQ_ERROR_CODE_TIMEOUT = 6,
+  /**
+   * A module could not be loaded.
+   */
+  MQ_ERROR_CODE_MODULE = 7,
+} MqErrorCode;
+
+/**
+ * C-compatible list marker used when rendering markdown values.
+ */
+typedef enum MqListStyle {
+  MQ_LIST_STYLE_DASH = 0,
+  MQ_LIST_STYLE_PLUS = 1,
+  MQ_LIST_STYLE_STAR = 2,
+} MqListStyle;
+
+/**
+ * C-compatible style of link URLs when rendering markdown values.
+ */
+typedef enum MqLinkUrlStyle {
+  /**
+   * `[text](url)`
+   */
+  MQ_LINK_URL_STYLE_NONE = 0,
+  /**
+   * `[text](<url>)`
+   */
+  MQ_LINK_URL_STYLE_ANGLE = 1,
+} MqLinkUrlStyle;
+
+/**
+ * C-compatible quoting of link titles when rendering markdown values.
+ */
+typedef enum MqLinkTitleStyle {
+  MQ_LINK_TITLE_STYLE_DOUBLE = 0,
+  MQ_LINK_TITLE_STYLE_SINGLE = 1,
+  MQ_LINK_TITLE_STYLE_PAREN = 2,
+} MqLinkTitleStyle;
+
 /**
  * C-compatible optimization level for AST transformations applied before evaluation.
  */
@@ -21,6 +111,22 @@ typedef struct mq_result_t {
   char **values;
   uintptr_t values_len;
   char *error_msg;
+  /**
+   * Type of each value in `values`, or NULL if there are no values.
+   */
+  enum MqValueType *value_types;
+  /**
+   * Kind of the error, or `MQ_ERROR_CODE_OK` if `error_msg` is NULL.
+   */
+  enum MqErrorCode error_code;
+  /**
+   * 1-based line of the error in the query, or 0 if the error has no position.
+   */
+  uintptr_t error_line;
+  /**
+   * 1-based column of the error in the query, or 0 if the error has no position.
+   */
+  uintptr_t error_column;
 } mq_result_t;
 
 /**
@@ -41,6 +147,67 @@ typedef struct MqConversionOptions {
   bool use_title_as_h1;
 } MqConversionOptions;
 
+/**
+ * C-compatible options for `mq_eval_with_options`.
+ * A zero-initialized struct evaluates markdown input with the default settings.
+ */
+typedef struct MqEvalOptions {
+  /**
+   * Input format: "markdown", "mdx", "html", or "text". Markdown if null.
+   */
+  const char *input_format;
+  /**
+   * Options for converting "html" input to markdown
+   */
+  struct MqConversionOptions conversion_options;
+  /**
+   * List marker of rendered list items
+   */
+  enum MqListStyle list_style;
+  /**
+   * Style of rendered link URLs
+   */
+  enum MqLinkUrlStyle link_url_style;
+  /**
+   * Quoting of rendered link titles
+   */
+  enum MqLinkTitleStyle link_title_style;
+  /**
+   * Allow the `read_file` builtins during this evaluation
+   */
+  bool allow_read;
+  /**
+   * Allow the `write_file` builtin during this evaluation
+   */
+  bool allow_write;
+  /**
+   * Allow the `http` builtin during this evaluation
+   */
+  bool allow_net;
+  /**
+   * Maximum time in milliseconds this evaluation may run, or 0 for no limit
+   */
+  uint64_t timeout_ms;
+} MqEvalOptions;
+
+/**
+ * Callback invoked by `mq_eval_stream` with each result value.
+ * `value` is only valid for the duration of the call. Return `false` to stop the evaluation.
+ */
+typedef bool (*MqValueCallback)(const char *value, void *user_data);
+
+/**
+ * Function implemented by the host and registered with `mq_register_function`.
+ *
+ * `args_json` holds `args_len` arguments encoded as JSON. Return the result as a JSON string
+ * allocated with `malloc`, or NULL for `None`. To fail the evaluation, set `*error_msg` to a
+ * message allocated with `malloc`. mq frees both strings with `free`.
+ */
+typedef char *(*MqHostFunction)(const char *const *args_json,
+                                uintptr_t args_len,
+                                void *user_data,
+                                char **error_msg);
+
 /**
  * Creates a new mq_lang engine.
  * The caller is responsible for destroying the engine using `mq_destroy`.
@@ -71,6 +238,77 @@ struct mq_result_t mq_eval(mq_context_t *engine_ptr,
                            const char *input_c,
                            const char *input_format_c);
 
+/**
+ * Evaluates mq code with the given input and options.
+ * The caller is responsible for freeing the result using `mq_free_result`.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it dereferences raw pointers. The caller must ensure:
+ * - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`
+ * - `code_c` must be a valid pointer to a null-terminated C string
+ * - `input_c` must be a valid pointer to a null-terminated C string
+ * - `options.input_format` must be a valid pointer to a null-terminated C string, or null
+ * - All string pointers must remain valid for the duration of this function call
+ * - The returned `MqResult` must be freed using `mq_free_result` to avoid memory leaks
+ *
+ * # Example
+ *
+ * ```c
+ * MqEvalOptions options = {0};
+ * options.input_format = "html";
+ * options.conversion_options.use_title_as_h1 = true;
+ * options.list_style = MQ_LIST_STYLE_STAR;
+ * options.timeout_ms = 1000;
+ *
+ * MqResult result = mq_eval_with_options(engine, ".h1", html, options);
+ * ```
+ */
+struct mq_result_t mq_eval_with_options(mq_context_t *engine_ptr,
+                                        const char *code_c,
+                                        const char *input_c,
+                                        struct MqEvalOptions options);
+
+/**
+ * Evaluates mq code with the given input, passing each result value to `callback` as soon as it
+ * is produced instead of collecting every value into an `MqResult`.
+ *
+ * Top-level input nodes are evaluated one at a time, so results arrive while the rest of the
+ * input is still being processed. Queries that use `nodes` need the whole input at once and
+ * produce their results after it has been evaluated.
+ *
+ * Returns NULL on success, including when `callback` stopped the evaluation, or an error message
+ * that must be freed with `mq_free_string`. Values passed before an error are not withdrawn.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it dereferences raw pointers. The caller must ensure:
+ * - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`
+ * - `code_c`, `input_c`, and `input_format_c` must be valid pointers to null-terminated C strings
+ * - `callback` must be safe to call with `user_data`
+ *
+ * # Example
+ *
+ * ```c
+ * bool print_value(const char* value, void* user_data) {
+ *     printf("%s\n", value);
+ *     return true;
+ * }
+ *
+ * char* error_msg = mq_eval_stream(engine, ".h", markdown, "markdown", print_value, NULL);
+ * if (error_msg != NULL) {
+ *     printf("Error: %s\n", error_msg);
+ *     mq_free_string(error_msg);
+ * }
+ * ```
+ */
+char *mq_eval_stream(mq_context_t *engine_ptr,
+                     const char *code_c,
+                     const char *input_c,
+                     const char *input_format_c,
+                     MqValueCallback callback,
+                     void *user_data);
+
 /**
  * Frees a C string allocated by Rust.
  *
@@ -178,6 +416,59 @@ void mq_set_search_paths(mq_context_t *engine_ptr,
  */
 void mq_define_string_value(mq_context_t *engine_ptr, const char *name_c, const char *value_c);
 
+/**
+ * Defines a variable that can be referenced from mq code evaluated afterwards, from a JSON value.
+ * Numbers, strings, booleans, null, arrays, and objects become the corresponding mq values.
+ *
+ * Returns NULL on success, or an error message that must be freed with `mq_free_string`.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it dereferences raw pointers. The caller must ensure:
+ * - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`, or null
+ * - `name_c` and `value_json_c` must be valid pointers to null-terminated C strings
+ *
+ * # Example
+ *
+ * ```c
+ * mq_set_var(engine, "limit", "3");
+ * mq_set_var(engine, "tags", "[\"rust\", \"c\"]");
+ * ```
+ */
+char *mq_set_var(mq_context_t *engine_ptr, const char *name_c, const char *value_json_c);
+
+/**
+ * Registers a host function callable from mq code evaluated afterwards as `name(args...)`.
+ * When a call passes one argument fewer than `arity`, the current value is passed as the first
+ * argument, so `"x" | lookup()` calls `lookup("x")` when `arity` is 1.
+ * Functions defined in mq code with the same name take precedence.
+ *
+ * Returns NULL on success, or an error message that must be freed with `mq_free_string`.
+ *
+ * # Safety
+ *
+ * This function is unsafe because it dereferences raw pointers. The caller must ensure:
+ * - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`, or null
+ * - `name_c` must be a valid pointer to a null-terminated C string
+ * - `fn_ptr` must be safe to call with `user_data` for as long as the engine is alive
+ *
+ * # Example
+ *
+ * ```c
+ * char* lookup(const char* const* args_json, size_t args_len, void* user_data, char** error_msg) {
+ *     // args_json[0] is the JSON-encoded first argument, e.g. "\"key\""
+ *     return strdup("\"value\"");
+ * }
+ *
+ * mq_register_function(engine, "lookup", 1, lookup, NULL);
+ * ```
+ */
+char *mq_register_function(mq_context_t *engine_ptr,
+                           const char *name_c,
+                           uint8_t arity,
+                           MqHostFunction fn_ptr,
+                           void *user_data);
+
 /**
  * Imports an external module by name, searched for in the paths configured via
  * `mq_set_search_paths`, making its exported definitions available to subsequent
@@ -58,13 +58,16 @@
 //!
 use libc::c_void;
 use mq_lang::DefaultEngine;
-use mq_lang::{Engine, RuntimeValue};
-use mq_markdown::{ConversionOptions, convert_html_to_markdown};
+use mq_lang::{Engine, InnerError, RuntimeError, RuntimeValue};
+use mq_markdown::{
+    ConversionOptions, ListStyle, RenderOptions, TitleSurroundStyle, UrlSurroundStyle, convert_html_to_markdown,
+};
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::os::raw::c_char;
 use std::path::PathBuf;
 use std::ptr;
+use std::time::Duration;
 
 pub type MqContext = c_void;
 
@@ -73,6 +76,69 @@ pub struct MqResult {
     pub values: *mut *mut c_char,
     pub values_len: usize,
     pub error_msg: *mut c_char,
+    /// Type of each value in `values`, or NULL if there are no values.
+    pub value_types: *mut MqValueType,
+    /// Kind of the error, or `MQ_ERROR_CODE_OK` if `error_msg` is NULL.
+    pub error_code: MqErrorCode,
+    /// 1-based line of the error in the query, or 0 if the error has no position.
+    pub error_line: usize,
+    /// 1-based column of the error in the query, or 0 if the error has no position.
+    pub error_column: usize,
+}
+
+/// Kind of error reported in `MqResult.error_code`.
+/// cbindgen:rename-all=QualifiedScreamingSnakeCase
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqErrorCode {
+    Ok = 0,
+    /// A null pointer or an invalid UTF-8 string was passed.
+    InvalidArgument = 1,
+    /// The input format is not supported.
+    UnsupportedFormat = 2,
+    /// The input could not be parsed in the given format.
+    InputParse = 3,
+    /// The query has a syntax error.
+    Syntax = 4,
+    /// The query failed while being evaluated.
+    Runtime = 5,
+    /// The evaluation exceeded the timeout.
+    Timeout = 6,
+    /// A module could not be loaded.
+    Module = 7,
+}
+
+/// Type of a result value in `MqResult.value_types`.
+/// cbindgen:rename-all=QualifiedScreamingSnakeCase
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MqValueType {
+    None = 0,
+    /// A string or a symbol.
+    String = 1,
+    Number = 2,
+    Bool = 3,
+    /// A markdown node, rendered as markdown.
+    Markdown = 4,
+    Array = 5,
+    Dict = 6,
+    /// Any other value, such as a function or bytes.
+    Other = 7,
+}
+
+impl From<&RuntimeValue> for MqValueType {
+    fn from(value: &RuntimeValue) -> Self {
+        match value {
+            RuntimeValue::None => MqValueType::None,
+            RuntimeValue::String(_) | RuntimeValue::Symbol(_) => MqValueType::String,
+            RuntimeValue::Number(_) => MqValueType::Number,
+            RuntimeValue::Boolean(_) => MqValueType::Bool,
+            RuntimeValue::Markdown(..) => MqValueType::Markdown,
+            RuntimeValue::Array(_) => MqValueType::Array,
+            RuntimeValue::Dict(_) => MqValueType::Dict,
+            _ => MqValueType::Other,
+        }
+    }
 }
 
 /// C-compatible conversion options for HTML to Markdown conversion.
@@ -97,6 +163,102 @@ impl From<MqConversionOptions> for ConversionOptions {
     }
 }
 
+/// C-compatible list marker used when rendering markdown values.
+/// cbindgen:rename-all=QualifiedScreamingSnakeCase
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum MqListStyle {
+    #[default]
+    Dash = 0,
+    Plus = 1,
+    Star = 2,
+}
+
+/// C-compatible style of link URLs when rendering markdown values.
+/// cbindgen:rename-all=QualifiedScreamingSnakeCase
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum MqLinkUrlStyle {
+    /// `[text](url)`
+    #[default]
+    None = 0,
+    /// `[text](<url>)`
+    Angle = 1,
+}
+
+/// C-compatible quoting of link titles when rendering markdown values.
+/// cbindgen:rename-all=QualifiedScreamingSnakeCase
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub enum MqLinkTitleStyle {
+    #[default]
+    Double = 0,
+    Single = 1,
+    Paren = 2,
+}
+
+/// C-compatible options for `mq_eval_with_options`.
+/// A zero-initialized struct evaluates markdown input with the default settings.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct MqEvalOptions {
+    /// Input format: "markdown", "mdx", "html", or "text". Markdown if null.
+    pub input_format: *const c_char,
+    /// Options for converting "html" input to markdown
+    pub conversion_options: MqConversionOptions,
+    /// List marker of rendered list items
+    pub list_style: MqListStyle,
+    /// Style of rendered link URLs
+    pub link_url_style: MqLinkUrlStyle,
+    /// Quoting of rendered link titles
+    pub link_title_style: MqLinkTitleStyle,
+    /// Allow the `read_file` builtins during this evaluation
+    pub allow_read: bool,
+    /// Allow the `write_file` builtin during this evaluation
+    pub allow_write: bool,
+    /// Allow the `http` builtin during this evaluation
+    pub allow_net: bool,
+    /// Maximum time in milliseconds this evaluation may run, or 0 for no limit
+    pub timeout_ms: u64,
+}
+
+impl Default for MqEvalOptions {
+    fn default() -> Self {
+        Self {
+            input_format: ptr::null(),
+            conversion_options: MqConversionOptions::default(),
+            list_style: MqListStyle::default(),
+            link_url_style: MqLinkUrlStyle::default(),
+            link_title_style: MqLinkTitleStyle::default(),
+            allow_read: false,
+            allow_write: false,
+            allow_net: false,
+            timeout_ms: 0,
+        }
+    }
+}
+
+impl MqEvalOptions {
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            list_style: match self.list_style {
+                MqListStyle::Dash => ListStyle::Dash,
+                MqListStyle::Plus => ListStyle::Plus,
+                MqListStyle::Star => ListStyle::Star,
+            },
+            link_url_style: match self.link_url_style {
+                MqLinkUrlStyle::None => UrlSurroundStyle::None,
+                MqLinkUrlStyle::Angle => UrlSurroundStyle::Angle,
+            },
+            link_title_style: match self.link_title_style {
+                MqLinkTitleStyle::Double => TitleSurroundStyle::Double,
+                MqLinkTitleStyle::Single => TitleSurroundStyle::Single,
+                MqLinkTitleStyle::Paren => TitleSurroundStyle::Paren,
+            },
+        }
+    }
+}
+
 // Helper function to convert Rust string to C string
 fn to_c_string(s: String) -> *mut c_char {
     CString::new(s).map_or_else(|_| ptr::null_mut(), |cs| cs.into_raw())
@@ -169,134 +331,355 @@ pub unsafe extern "C" fn mq_eval(
     input_format_c: *const c_char, // "markdown" or "mdx" or "text"
 ) -> MqResult {
     if engine_ptr.is_null() {
-        return MqResult {
-            values: ptr::null_mut(),
-            values_len: 0,
-            error_msg: to_c_string("Engine pointer is null".to_string()),
-        };
+        return error_result(MqErrorCode::InvalidArgument, "Engine pointer is null".to_string());
     }
     let engine = unsafe { &mut *(engine_ptr as *mut Engine) };
 
     let code = match unsafe { c_str_to_rust_str_slice(code_c) } {
         Ok(s) => s,
         Err(_) => {
-            return MqResult {
-                values: ptr::null_mut(),
-                values_len: 0,
-                error_msg: to_c_string("Invalid UTF-8 sequence in code".to_string()),
-            };
+            return error_result(
+                MqErrorCode::InvalidArgument,
+                "Invalid UTF-8 sequence in code".to_string(),
+            );
         }
     };
 
     if input_c.is_null() {
-        return MqResult {
-            values: ptr::null_mut(),
-            values_len: 0,
-            error_msg: to_c_string("Input pointer is null".to_string()),
-        };
+        return error_result(MqErrorCode::InvalidArgument, "Input pointer is null".to_string());
     }
 
     let input_str = match unsafe { c_str_to_rust_str_slice(input_c) } {
         Ok(s) => s,
         Err(_) => {
-            return MqResult {
-                values: ptr::null_mut(),
-                values_len: 0,
-                error_msg: to_c_string("Invalid UTF-8 sequence in input".to_string()),
-            };
+            return error_result(
+                MqErrorCode::InvalidArgument,
+                "Invalid UTF-8 sequence in input".to_string(),
+            );
         }
     };
 
     if input_format_c.is_null() {
-        return MqResult {
-            values: ptr::null_mut(),
-            values_len: 0,
-            error_msg: to_c_string("Input format pointer is null".to_string()),
-        };
+        return error_result(MqErrorCode::InvalidArgument, "Input format pointer is null".to_string());
     }
 
     let input_format_str = match unsafe { c_str_to_rust_str_slice(input_format_c) } {
         Ok(s) => s.to_lowercase(),
         Err(_) => {
-            return MqResult {
-                values: ptr::null_mut(),
-                values_len: 0,
-                error_msg: to_c_string("Invalid UTF-8 sequence in input_format".to_string()),
-            };
+            return error_result(
+                MqErrorCode::InvalidArgument,
+                "Invalid UTF-8 sequence in input_format".to_string(),
+            );
         }
     };
 
-    let mq_input_values: Vec<RuntimeValue> = match input_format_str.as_str() {
-        "text" => mq_lang::parse_text_input(input_str).unwrap(),
-        "markdown" => match mq_lang::parse_markdown_input(input_str) {
-            Ok(v) => v,
-            Err(e) => {
-                return MqResult {
-                    values: ptr::null_mut(),
-                    values_len: 0,
-                    error_msg: to_c_string(format!("Markdown parsing error: {}", e)),
-                };
-            }
-        },
-        "mdx" => match mq_lang::parse_mdx_input(input_str) {
-            Ok(v) => v,
-            Err(e) => {
-                return MqResult {
-                    values: ptr::null_mut(),
-                    values_len: 0,
-                    error_msg: to_c_string(format!("Markdown parsing error: {}", e)),
-                };
-            }
-        },
-        "html" => match mq_lang::parse_html_input(input_str) {
-            Ok(v) => v,
-            Err(e) => {
-                return MqResult {
-                    values: ptr::null_mut(),
-                    values_len: 0,
-                    error_msg: to_c_string(format!("Html parsing error: {}", e)),
-                };
-            }
-        },
-        _ => {
-            return MqResult {
-                values: ptr::null_mut(),
-                values_len: 0,
-                error_msg: to_c_string(format!("Unsupported input format: {}", input_format_str)),
-            };
-        }
+    let mq_input_values = match parse_input(input_str, &input_format_str, ConversionOptions::default()) {
+        Ok(values) => values,
+        Err((error_code, e)) => return error_result(error_code, e),
     };
 
     match engine.eval(code, mq_input_values.into_iter()) {
-        Ok(result_values) => {
-            let mut c_values: Vec<*mut c_char> = Vec::new();
-            let values_len = result_values.len();
-
-            for value in result_values {
-                // For now, convert all values to their string representation.
-                // More sophisticated type handling could be added later.
-                c_values.push(to_c_string(value.to_string()));
-            }
+        Ok(result_values) => values_to_result(result_values, &RenderOptions::default()),
+        Err(e) => eval_error_result(&e),
+    }
+}
 
-            let ptr = if c_values.is_empty() {
-                ptr::null_mut()
-            } else {
-                let p = c_values.as_mut_ptr();
-                std::mem::forget(c_values); // Prevent Rust from freeing the Vec's memory
-                p
-            };
+/// Evaluates mq code with the given input and options.
+/// The caller is responsible for freeing the result using `mq_free_result`.
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers. The caller must ensure:
+/// - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`
+/// - `code_c` must be a valid pointer to a null-terminated C string
+/// - `input_c` must be a valid pointer to a null-terminated C string
+/// - `options.input_format` must be a valid pointer to a null-terminated C string, or null
+/// - All string pointers must remain valid for the duration of this function call
+/// - The returned `MqResult` must be freed using `mq_free_result` to avoid memory leaks
+///
+/// # Example
+///
+/// ```c
+/// MqEvalOptions options = {0};
+/// options.input_format = "html";
+/// options.conversion_options.use_title_as_h1 = true;
+/// options.list_style = MQ_LIST_STYLE_STAR;
+/// options.timeout_ms = 1000;
+///
+/// MqResult result = mq_eval_with_options(engine, ".h1", html, options);
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_eval_with_options(
+    engine_ptr: *mut MqContext,
+    code_c: *const c_char,
+    input_c: *const c_char,
+    options: MqEvalOptions,
+) -> MqResult {
+    if engine_ptr.is_null() {
+        return error_result(MqErrorCode::InvalidArgument, "Engine pointer is null".to_string());
+    }
+    let engine = unsafe { &mut *(engine_ptr as *mut Engine) };
+
+    let code = match unsafe { c_str_to_rust_str_slice(code_c) } {
+        Ok(s) => s,
+        Err(_) => {
+            return error_result(
+                MqErrorCode::InvalidArgument,
+                "Invalid UTF-8 sequence in code".to_string(),
+            );
+        }
+    };
+
+    if input_c.is_null() {
+        return error_result(MqErrorCode::InvalidArgument, "Input pointer is null".to_string());
+    }
+
+    let input_str = match unsafe { c_str_to_rust_str_slice(input_c) } {
+        Ok(s) => s,
+        Err(_) => {
+            return error_result(
+                MqErrorCode::InvalidArgument,
+                "Invalid UTF-8 sequence in input".to_string(),
+            );
+        }
+    };
 
-            MqResult {
-                values: ptr,
-                values_len,
-                error_msg: ptr::null_mut(),
+    let input_format_str = if options.input_format.is_null() {
+        "markdown".to_string()
+    } else {
+        match unsafe { c_str_to_rust_str_slice(options.input_format) } {
+            Ok(s) => s.to_lowercase(),
+            Err(_) => {
+                return error_result(
+                    MqErrorCode::InvalidArgument,
+                    "Invalid UTF-8 sequence in input_format".to_string(),
+                );
             }
         }
-        Err(e) => MqResult {
-            values: ptr::null_mut(),
-            values_len: 0,
-            error_msg: to_c_string(format!("Error evaluating query: {}", e)),
-        },
+    };
+
+    let mq_input_values = match parse_input(input_str, &input_format_str, options.conversion_options.into()) {
+        Ok(values) => values,
+        Err((error_code, e)) => return error_result(error_code, e),
+    };
+
+    // The options apply to this call only; restore the previous settings afterwards.
+    let previous_timeout = engine.timeout();
+    let previous_capabilities = (engine.allow_read(), engine.allow_write(), engine.allow_net());
+    if options.timeout_ms > 0 {
+        engine.set_timeout(Duration::from_millis(options.timeout_ms));
+    } else {
+        engine.clear_timeout();
+    }
+    engine.set_allow_read(options.allow_read);
+    engine.set_allow_write(options.allow_write);
+    engine.set_allow_net(options.allow_net);
+
+    let result = engine.eval(code, mq_input_values.into_iter());
+
+    match previous_timeout {
+        Some(timeout) => engine.set_timeout(timeout),
+        None => engine.clear_timeout(),
+    }
+    let (allow_read, allow_write, allow_net) = previous_capabilities;
+    engine.set_allow_read(allow_read);
+    engine.set_allow_write(allow_write);
+    engine.set_allow_net(allow_net);
+
+    match result {
+        Ok(result_values) => values_to_result(result_values, &options.render_options()),
+        Err(e) => eval_error_result(&e),
+    }
+}
+
+/// Callback invoked by `mq_eval_stream` with each result value.
+/// `value` is only valid for the duration of the call. Return `false` to stop the evaluation.
+pub type MqValueCallback = Option<unsafe extern "C" fn(value: *const c_char, user_data: *mut c_void) -> bool>;
+
+/// Evaluates mq code with the given input, passing each result value to `callback` as soon as it
+/// is produced instead of collecting every value into an `MqResult`.
+///
+/// Top-level input nodes are evaluated one at a time, so results arrive while the rest of the
+/// input is still being processed. Queries that use `nodes` need the whole input at once and
+/// produce their results after it has been evaluated.
+///
+/// Returns NULL on success, including when `callback` stopped the evaluation, or an error message
+/// that must be freed with `mq_free_string`. Values passed before an error are not withdrawn.
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers. The caller must ensure:
+/// - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`
+/// - `code_c`, `input_c`, and `input_format_c` must be valid pointers to null-terminated C strings
+/// - `callback` must be safe to call with `user_data`
+///
+/// # Example
+///
+/// ```c
+/// bool print_value(const char* value, void* user_data) {
+///     printf("%s\n", value);
+///     return true;
+/// }
+///
+/// char* error_msg = mq_eval_stream(engine, ".h", markdown, "markdown", print_value, NULL);
+/// if (error_msg != NULL) {
+///     printf("Error: %s\n", error_msg);
+///     mq_free_string(error_msg);
+/// }
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_eval_stream(
+    engine_ptr: *mut MqContext,
+    code_c: *const c_char,
+    input_c: *const c_char,
+    input_format_c: *const c_char,
+    callback: MqValueCallback,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if engine_ptr.is_null() {
+        return to_c_string("Engine pointer is null".to_string());
+    }
+    let engine = unsafe { &mut *(engine_ptr as *mut Engine) };
+
+    let Some(callback) = callback else {
+        return to_c_string("Callback is null".to_string());
+    };
+
+    let code = match unsafe { c_str_to_rust_str_slice(code_c) } {
+        Ok(s) => s,
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in code".to_string()),
+    };
+
+    if input_c.is_null() {
+        return to_c_string("Input pointer is null".to_string());
+    }
+
+    let input_str = match unsafe { c_str_to_rust_str_slice(input_c) } {
+        Ok(s) => s,
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in input".to_string()),
+    };
+
+    if input_format_c.is_null() {
+        return to_c_string("Input format pointer is null".to_string());
+    }
+
+    let input_format_str = match unsafe { c_str_to_rust_str_slice(input_format_c) } {
+        Ok(s) => s.to_lowercase(),
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in input_format".to_string()),
+    };
+
+    let mq_input_values = match parse_input(input_str, &input_format_str, ConversionOptions::default()) {
+        Ok(values) => values,
+        Err((_, e)) => return to_c_string(e),
+    };
+
+    let results = match engine.eval_stream(code, mq_input_values.into_iter()) {
+        Ok(results) => results,
+        Err(e) => return to_c_string(format!("Error evaluating query: {}", e)),
+    };
+
+    for value in results {
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => return to_c_string(format!("Error evaluating query: {}", e)),
+        };
+        let Ok(value) = CString::new(value.to_string()) else {
+            continue;
+        };
+        if !unsafe { callback(value.as_ptr(), user_data) } {
+            return ptr::null_mut();
+        }
+    }
+
+    ptr::null_mut()
+}
+
+fn error_result(error_code: MqErrorCode, error_msg: String) -> MqResult {
+    MqResult {
+        values: ptr::null_mut(),
+        values_len: 0,
+        error_msg: to_c_string(error_msg),
+        value_types: ptr::null_mut(),
+        error_code,
+        error_line: 0,
+        error_column: 0,
+    }
+}
+
+// Builds the result of a failed evaluation, with the kind and position of the error.
+fn eval_error_result(error: &mq_lang::Error) -> MqResult {
+    let error_code = match &error.cause {
+        InnerError::Syntax(_) => MqErrorCode::Syntax,
+        InnerError::Runtime(RuntimeError::Timeout(_)) => MqErrorCode::Timeout,
+        InnerError::Runtime(RuntimeError::ModuleLoadError(_)) | InnerError::Module(_) => MqErrorCode::Module,
+        InnerError::Runtime(_) => MqErrorCode::Runtime,
+    };
+    let (error_line, error_column) = error.cause.token().map_or((0, 0), |token| {
+        (token.range.start.line as usize, token.range.start.column)
+    });
+
+    MqResult {
+        error_line,
+        error_column,
+        ..error_result(error_code, format!("Error evaluating query: {}", error))
+    }
+}
+
+// Parses the input according to `input_format` ("markdown", "mdx", "html", or "text").
+fn parse_input(
+    input: &str,
+    input_format: &str,
+    conversion_options: ConversionOptions,
+) -> Result<Vec<RuntimeValue>, (MqErrorCode, String)> {
+    match input_format {
+        "text" => mq_lang::parse_text_input(input).map_err(|e| format!("Text parsing error: {}", e)),
+        "markdown" => mq_lang::parse_markdown_input(input).map_err(|e| format!("Markdown parsing error: {}", e)),
+        "mdx" => mq_lang::parse_mdx_input(input).map_err(|e| format!("Markdown parsing error: {}", e)),
+        "html" => mq_lang::parse_html_input_with_options(input, conversion_options)
+            .map_err(|e| format!("Html parsing error: {}", e)),
+        _ => {
+            return Err((
+                MqErrorCode::UnsupportedFormat,
+                format!("Unsupported input format: {}", input_format),
+            ));
+        }
+    }
+    .map_err(|e| (MqErrorCode::InputParse, e))
+}
+
+// Converts the result values into C strings, rendering markdown nodes with `render_options`.
+fn values_to_result(result_values: mq_lang::RuntimeValues, render_options: &RenderOptions) -> MqResult {
+    let (c_values, value_types): (Vec<*mut c_char>, Vec<MqValueType>) = result_values
+        .into_iter()
+        .map(|value| {
+            let value_type = MqValueType::from(&value);
+            let c_value = match value {
+                RuntimeValue::Markdown(node, _) => to_c_string(node.to_string_with(render_options)),
+                value => to_c_string(value.to_string()),
+            };
+            (c_value, value_type)
+        })
+        .unzip();
+    let values_len = c_values.len();
+
+    let (values, value_types) = if c_values.is_empty() {
+        (ptr::null_mut(), ptr::null_mut())
+    } else {
+        // Boxed slices have no spare capacity, so `mq_free_result` can rebuild them from `values_len`.
+        (
+            Box::into_raw(c_values.into_boxed_slice()) as *mut *mut c_char,
+            Box::into_raw(value_types.into_boxed_slice()) as *mut MqValueType,
+        )
+    };
+
+    MqResult {
+        values,
+        values_len,
+        error_msg: ptr::null_mut(),
+        value_types,
+        error_code: MqErrorCode::Ok,
+        error_line: 0,
+        error_column: 0,
     }
 }
 
@@ -343,6 +726,16 @@ pub extern "C" fn mq_free_result(result: MqResult) {
             // The Vec itself is dropped here, freeing the memory it owned for the pointers.
         }
     }
+
+    if !result.value_types.is_null() {
+        unsafe {
+            drop(Vec::from_raw_parts(
+                result.value_types,
+                result.values_len,
+                result.values_len,
+            ));
+        }
+    }
 }
 
 /// Converts HTML to Markdown with the given conversion options.
@@ -538,6 +931,166 @@ pub unsafe extern "C" fn mq_define_string_value(
     engine.define_string_value(name, value);
 }
 
+/// Defines a variable that can be referenced from mq code evaluated afterwards, from a JSON value.
+/// Numbers, strings, booleans, null, arrays, and objects become the corresponding mq values.
+///
+/// Returns NULL on success, or an error message that must be freed with `mq_free_string`.
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers. The caller must ensure:
+/// - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`, or null
+/// - `name_c` and `value_json_c` must be valid pointers to null-terminated C strings
+///
+/// # Example
+///
+/// ```c
+/// mq_set_var(engine, "limit", "3");
+/// mq_set_var(engine, "tags", "[\"rust\", \"c\"]");
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_set_var(
+    engine_ptr: *mut MqContext,
+    name_c: *const c_char,
+    value_json_c: *const c_char,
+) -> *mut c_char {
+    if engine_ptr.is_null() {
+        return to_c_string("Engine pointer is null".to_string());
+    }
+    let engine = unsafe { &mut *(engine_ptr as *mut Engine) };
+
+    let name = match unsafe { c_str_to_rust_str_slice(name_c) } {
+        Ok(s) if !s.is_empty() => s,
+        Ok(_) => return to_c_string("Variable name is empty".to_string()),
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in name".to_string()),
+    };
+    let value_json = match unsafe { c_str_to_rust_str_slice(value_json_c) } {
+        Ok(s) => s,
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in value".to_string()),
+    };
+
+    match serde_json::from_str::<serde_json::Value>(value_json) {
+        Ok(value) => {
+            engine.define_value(name, value.into());
+            ptr::null_mut()
+        }
+        Err(e) => to_c_string(format!("Invalid JSON value for '{}': {}", name, e)),
+    }
+}
+
+/// Function implemented by the host and registered with `mq_register_function`.
+///
+/// `args_json` holds `args_len` arguments encoded as JSON. Return the result as a JSON string
+/// allocated with `malloc`, or NULL for `None`. To fail the evaluation, set `*error_msg` to a
+/// message allocated with `malloc`. mq frees both strings with `free`.
+pub type MqHostFunction = Option<
+    unsafe extern "C" fn(
+        args_json: *const *const c_char,
+        args_len: usize,
+        user_data: *mut c_void,
+        error_msg: *mut *mut c_char,
+    ) -> *mut c_char,
+>;
+
+// The user data pointer passed back to a host function. The host is responsible for it being
+// usable from whichever thread evaluates the query.
+struct HostUserData(*mut c_void);
+
+unsafe impl Send for HostUserData {}
+unsafe impl Sync for HostUserData {}
+
+impl HostUserData {
+    // Accessed through a method so that closures capture the whole wrapper, not the raw pointer.
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+/// Registers a host function callable from mq code evaluated afterwards as `name(args...)`.
+/// When a call passes one argument fewer than `arity`, the current value is passed as the first
+/// argument, so `"x" | lookup()` calls `lookup("x")` when `arity` is 1.
+/// Functions defined in mq code with the same name take precedence.
+///
+/// Returns NULL on success, or an error message that must be freed with `mq_free_string`.
+///
+/// # Safety
+///
+/// This function is unsafe because it dereferences raw pointers. The caller must ensure:
+/// - `engine_ptr` must be a valid pointer to an `Engine` created by `mq_create`, or null
+/// - `name_c` must be a valid pointer to a null-terminated C string
+/// - `fn_ptr` must be safe to call with `user_data` for as long as the engine is alive
+///
+/// # Example
+///
+/// ```c
+/// char* lookup(const char* const* args_json, size_t args_len, void* user_data, char** error_msg) {
+///     // args_json[0] is the JSON-encoded first argument, e.g. "\"key\""
+///     return strdup("\"value\"");
+/// }
+///
+/// mq_register_function(engine, "lookup", 1, lookup, NULL);
+/// ```
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_register_function(
+    engine_ptr: *mut MqContext,
+    name_c: *const c_char,
+    arity: u8,
+    fn_ptr: MqHostFunction,
+    user_data: *mut c_void,
+) -> *mut c_char {
+    if engine_ptr.is_null() {
+        return to_c_string("Engine pointer is null".to_string());
+    }
+    let engine = unsafe { &mut *(engine_ptr as *mut Engine) };
+
+    let name = match unsafe { c_str_to_rust_str_slice(name_c) } {
+        Ok(s) if !s.is_empty() => s,
+        Ok(_) => return to_c_string("Function name is empty".to_string()),
+        Err(_) => return to_c_string("Invalid UTF-8 sequence in name".to_string()),
+    };
+    let Some(func) = fn_ptr else {
+        return to_c_string("Function pointer is null".to_string());
+    };
+    let user_data = HostUserData(user_data);
+
+    engine.register_function(
+        name,
+        arity,
+        mq_lang::Shared::new(move |args: &[RuntimeValue]| {
+            let args_json: Vec<CString> = args
+                .iter()
+                .map(|arg| CString::new(arg.clone().to_json_value().to_string()).unwrap_or_default())
+                .collect();
+            let arg_ptrs: Vec<*const c_char> = args_json.iter().map(|arg| arg.as_ptr()).collect();
+            let mut error_msg: *mut c_char = ptr::null_mut();
+
+            let result = unsafe { func(arg_ptrs.as_ptr(), arg_ptrs.len(), user_data.get(), &mut error_msg) };
+
+            let error = unsafe { take_malloced_string(error_msg) };
+            let result = unsafe { take_malloced_string(result) };
+            match (error, result) {
+                (Some(error), _) => Err(error),
+                (None, Some(json)) => serde_json::from_str::<serde_json::Value>(&json)
+                    .map(RuntimeValue::from)
+                    .map_err(|e| format!("Invalid JSON returned by host function: {}", e)),
+                (None, None) => Ok(RuntimeValue::NONE),
+            }
+        }),
+    );
+
+    ptr::null_mut()
+}
+
+// Copies a string allocated by the host with `malloc` and frees it.
+unsafe fn take_malloced_string(s: *mut c_char) -> Option<String> {
+    if s.is_null() {
+        return None;
+    }
+    let value = unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned();
+    unsafe { libc::free(s as *mut c_void) };
+    Some(value)
+}
+
 /// Imports an external module by name, searched for in the paths configured via
 /// `mq_set_search_paths`, making its exported definitions available to subsequent
 /// `mq_eval` calls on the same engine.
@@ -672,6 +1225,7 @@ pub extern "C" fn mq_clear_http_cache_all(engine_ptr: *mut MqContext) -> *mut c_
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     // Helper function to create a C string for testing
     fn make_c_string(s: &str) -> *const c_char {
@@ -899,6 +1453,10 @@ mod tests {
             values: ptr::null_mut(),
             values_len: 0,
             error_msg: ptr::null_mut(),
+            value_types: ptr::null_mut(),
+            error_code: MqErrorCode::Ok,
+            error_line: 0,
+            error_column: 0,
         };
         mq_free_result(empty_result);
     }
@@ -983,6 +1541,437 @@ mod tests {
         }
     }
 
+    // Evaluates with the given options and returns the values, or the error message.
+    fn eval_with_options(code: &str, input: &str, options: MqEvalOptions) -> Result<Vec<String>, String> {
+        let engine = mq_create();
+        let code = CString::new(code).unwrap();
+        let input = CString::new(input).unwrap();
+
+        let result = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), options) };
+        let values = unsafe {
+            if result.error_msg.is_null() {
+                Ok((0..result.values_len)
+                    .map(|i| c_string_to_rust_string(*result.values.add(i)))
+                    .collect())
+            } else {
+                Err(c_string_to_rust_string(result.error_msg))
+            }
+        };
+
+        mq_free_result(result);
+        mq_destroy(engine);
+        values
+    }
+
+    unsafe extern "C" fn collect_value(value: *const c_char, user_data: *mut c_void) -> bool {
+        let values = unsafe { &mut *(user_data as *mut Vec<String>) };
+        values.push(unsafe { CStr::from_ptr(value) }.to_string_lossy().into_owned());
+        true
+    }
+
+    unsafe extern "C" fn collect_first_value(value: *const c_char, user_data: *mut c_void) -> bool {
+        unsafe { collect_value(value, user_data) };
+        false
+    }
+
+    // Streams the evaluation into a Vec, returning the values and the error message, if any.
+    fn eval_stream(code: &str, input: &str, format: &str, callback: MqValueCallback) -> (Vec<String>, Option<String>) {
+        let engine = mq_create();
+        let code = CString::new(code).unwrap();
+        let input = CString::new(input).unwrap();
+        let format = CString::new(format).unwrap();
+        let mut values: Vec<String> = Vec::new();
+
+        let error_msg = unsafe {
+            mq_eval_stream(
+                engine,
+                code.as_ptr(),
+                input.as_ptr(),
+                format.as_ptr(),
+                callback,
+                &mut values as *mut Vec<String> as *mut c_void,
+            )
+        };
+        let error = (!error_msg.is_null()).then(|| unsafe { c_string_to_rust_string(error_msg) });
+        unsafe { mq_free_string(error_msg) };
+        mq_destroy(engine);
+
+        (values, error)
+    }
+
+    #[test]
+    fn test_eval_stream() {
+        let (values, error) = eval_stream("upcase()", "a\nb\nc", "text", Some(collect_value));
+        assert_eq!(values, vec!["A", "B", "C"]);
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    fn test_eval_stream_stops_when_callback_returns_false() {
+        let (values, error) = eval_stream("upcase()", "a\nb\nc", "text", Some(collect_first_value));
+        assert_eq!(values, vec!["A"]);
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    fn test_eval_stream_with_nodes() {
+        let (values, error) = eval_stream("nodes | len()", "# a\n\n# b", "markdown", Some(collect_value));
+        assert_eq!(values, vec!["2"]);
+        assert_eq!(error, None);
+    }
+
+    #[test]
+    fn test_eval_stream_errors() {
+        let (values, error) = eval_stream("error(\"boom\")", "a\nb", "text", Some(collect_value));
+        assert!(values.is_empty());
+        assert!(error.unwrap().starts_with("Error evaluating query"));
+
+        let (_, error) = eval_stream(".h", "a", "yaml", Some(collect_value));
+        assert_eq!(error.as_deref(), Some("Unsupported input format: yaml"));
+
+        let (_, error) = eval_stream(".h", "a", "text", None);
+        assert_eq!(error.as_deref(), Some("Callback is null"));
+    }
+
+    // Evaluates `code` against a single text line and returns the first value, or the error.
+    fn eval_first(engine: *mut MqContext, code: &str) -> Result<String, String> {
+        let code = CString::new(code).unwrap();
+        let input = CString::new("input").unwrap();
+        let format = CString::new("text").unwrap();
+
+        let result = unsafe { mq_eval(engine, code.as_ptr(), input.as_ptr(), format.as_ptr()) };
+        let value = unsafe {
+            if result.error_msg.is_null() {
+                Ok(c_string_to_rust_string(*result.values))
+            } else {
+                Err(c_string_to_rust_string(result.error_msg))
+            }
+        };
+        mq_free_result(result);
+        value
+    }
+
+    #[rstest]
+    #[case::number("3", "x + 1", "4")]
+    #[case::string("\"mq\"", "upcase(x)", "MQ")]
+    #[case::array("[1, 2, 3]", "len(x)", "3")]
+    #[case::object("{\"name\": \"mq\"}", "x[:name]", "mq")]
+    fn test_set_var(#[case] value_json: &str, #[case] code: &str, #[case] expected: &str) {
+        let engine = mq_create();
+        let name = CString::new("x").unwrap();
+        let value = CString::new(value_json).unwrap();
+
+        let error_msg = unsafe { mq_set_var(engine, name.as_ptr(), value.as_ptr()) };
+        assert!(error_msg.is_null());
+        assert_eq!(eval_first(engine, code), Ok(expected.to_string()));
+
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_set_var_invalid_json() {
+        let engine = mq_create();
+        let name = CString::new("x").unwrap();
+        let value = CString::new("{invalid").unwrap();
+
+        let error_msg = unsafe { mq_set_var(engine, name.as_ptr(), value.as_ptr()) };
+        let error = unsafe { c_string_to_rust_string(error_msg) };
+        assert!(error.starts_with("Invalid JSON value for 'x'"), "{}", error);
+
+        unsafe { mq_free_string(error_msg) };
+        mq_destroy(engine);
+    }
+
+    // Returns the sum of its two number arguments, or fails if either is not a number.
+    unsafe extern "C" fn add_numbers(
+        args_json: *const *const c_char,
+        args_len: usize,
+        user_data: *mut c_void,
+        error_msg: *mut *mut c_char,
+    ) -> *mut c_char {
+        let calls = unsafe { &mut *(user_data as *mut usize) };
+        *calls += 1;
+
+        let args = unsafe { std::slice::from_raw_parts(args_json, args_len) };
+        let numbers: Vec<f64> = args
+            .iter()
+            .filter_map(|arg| unsafe { CStr::from_ptr(*arg) }.to_str().ok()?.parse().ok())
+            .collect();
+        if numbers.len() != 2 {
+            unsafe { *error_msg = libc::strdup(c"add_numbers expects numbers".as_ptr()) };
+            return ptr::null_mut();
+        }
+
+        let sum = CString::new((numbers[0] + numbers[1]).to_string()).unwrap();
+        unsafe { libc::strdup(sum.as_ptr()) }
+    }
+
+    #[test]
+    fn test_register_function() {
+        let engine = mq_create();
+        let name = CString::new("add_numbers").unwrap();
+        let mut calls: usize = 0;
+
+        let error_msg = unsafe {
+            mq_register_function(
+                engine,
+                name.as_ptr(),
+                2,
+                Some(add_numbers),
+                &mut calls as *mut usize as *mut c_void,
+            )
+        };
+        assert!(error_msg.is_null());
+
+        assert_eq!(eval_first(engine, "add_numbers(1, 2)"), Ok("3".to_string()));
+        assert_eq!(eval_first(engine, "20 | add_numbers(22)"), Ok("42".to_string()));
+        let error = eval_first(engine, "add_numbers(\"a\", 1)").unwrap_err();
+        assert!(error.contains("add_numbers expects numbers"), "{}", error);
+        assert_eq!(calls, 3);
+
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_register_function_null_function() {
+        let engine = mq_create();
+        let name = CString::new("f").unwrap();
+
+        let error_msg = unsafe { mq_register_function(engine, name.as_ptr(), 1, None, ptr::null_mut()) };
+        assert_eq!(
+            unsafe { c_string_to_rust_string(error_msg) },
+            "Function pointer is null"
+        );
+
+        unsafe { mq_free_string(error_msg) };
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_eval_with_options_defaults() {
+        let values = eval_with_options(".h", "# Title\n\ntext", MqEvalOptions::default());
+        assert_eq!(values, Ok(vec!["# Title".to_string(), "".to_string()]));
+    }
+
+    #[test]
+    fn test_eval_with_options_render_options() {
+        let options = MqEvalOptions {
+            list_style: MqListStyle::Star,
+            link_url_style: MqLinkUrlStyle::Angle,
+            link_title_style: MqLinkTitleStyle::Single,
+            ..Default::default()
+        };
+
+        let values = eval_with_options(".[] | select(!is_none())", "- [a](https://example.com \"t\")", options);
+        assert_eq!(values, Ok(vec!["* [a](<https://example.com> 't')".to_string()]));
+    }
+
+    #[test]
+    fn test_eval_with_options_html_conversion() {
+        let input_format = CString::new("html").unwrap();
+        let options = MqEvalOptions {
+            input_format: input_format.as_ptr(),
+            conversion_options: MqConversionOptions {
+                use_title_as_h1: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let values = eval_with_options(
+            ".h1 | to_text()",
+            "<html><head><title>Hello</title></head><body><p>World</p></body></html>",
+            options,
+        );
+        assert_eq!(values, Ok(vec!["Hello".to_string(), "".to_string()]));
+    }
+
+    #[test]
+    fn test_eval_with_options_timeout() {
+        let options = MqEvalOptions {
+            timeout_ms: 1,
+            ..Default::default()
+        };
+
+        let error = eval_with_options("loop: 1;", "# a", options).unwrap_err();
+        assert!(error.starts_with("Error evaluating query"), "{}", error);
+    }
+
+    #[test]
+    fn test_eval_with_options_timeout_does_not_persist() {
+        let engine = mq_create();
+        let input = CString::new("# a").unwrap();
+        let input_format = CString::new("markdown").unwrap();
+
+        let code = CString::new("loop: 1;").unwrap();
+        let options = MqEvalOptions {
+            timeout_ms: 1,
+            ..Default::default()
+        };
+        let result = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), options) };
+        assert_eq!(result.error_code, MqErrorCode::Timeout);
+        mq_free_result(result);
+
+        // Runs far longer than 1ms, so it only succeeds if the timeout was not kept.
+        let code = CString::new("var i = 0 | while (i < 20000): i = i + 1; | i").unwrap();
+        let result = unsafe { mq_eval(engine, code.as_ptr(), input.as_ptr(), input_format.as_ptr()) };
+        assert_eq!(result.error_code, MqErrorCode::Ok);
+        mq_free_result(result);
+
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_eval_with_options_capabilities_apply_to_the_call() {
+        let engine = mq_create();
+        let path = std::env::temp_dir().join("mq_ffi_eval_with_options_capabilities.md");
+        std::fs::write(&path, "content").unwrap();
+        let code = CString::new(format!("read_file(\"{}\")", path.to_string_lossy().replace('\\', "/"))).unwrap();
+        let input = CString::new("# a").unwrap();
+        let input_format = CString::new("markdown").unwrap();
+
+        let denied = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), MqEvalOptions::default()) };
+        assert_eq!(denied.error_code, MqErrorCode::Runtime);
+        mq_free_result(denied);
+
+        let options = MqEvalOptions {
+            allow_read: true,
+            ..Default::default()
+        };
+        let allowed = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), options) };
+        assert_eq!(allowed.error_code, MqErrorCode::Ok);
+        assert_eq!(unsafe { c_string_to_rust_string(*allowed.values) }, "content");
+        mq_free_result(allowed);
+
+        // The permission ends with the call that granted it.
+        let result = unsafe { mq_eval(engine, code.as_ptr(), input.as_ptr(), input_format.as_ptr()) };
+        assert_eq!(result.error_code, MqErrorCode::Runtime);
+        mq_free_result(result);
+
+        std::fs::remove_file(&path).ok();
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_eval_with_options_restores_engine_timeout() {
+        let engine = mq_create();
+        unsafe { &mut *(engine as *mut Engine) }.set_timeout(Duration::from_millis(1));
+
+        let code = CString::new(".h").unwrap();
+        let input = CString::new("# a").unwrap();
+        let result = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), MqEvalOptions::default()) };
+        assert!(result.error_msg.is_null());
+        mq_free_result(result);
+
+        assert_eq!(
+            unsafe { &*(engine as *mut Engine) }.timeout(),
+            Some(Duration::from_millis(1))
+        );
+        mq_destroy(engine);
+    }
+
+    #[test]
+    fn test_eval_with_options_unsupported_format() {
+        let input_format = CString::new("yaml").unwrap();
+        let options = MqEvalOptions {
+            input_format: input_format.as_ptr(),
+            ..Default::default()
+        };
+
+        let values = eval_with_options(".h", "a: 1", options);
+        assert_eq!(values, Err("Unsupported input format: yaml".to_string()));
+    }
+
+    #[test]
+    fn test_eval_with_options_null_engine() {
+        let code = CString::new(".h").unwrap();
+        let input = CString::new("# a").unwrap();
+
+        let result =
+            unsafe { mq_eval_with_options(ptr::null_mut(), code.as_ptr(), input.as_ptr(), MqEvalOptions::default()) };
+
+        assert!(!result.error_msg.is_null());
+        mq_free_result(result);
+    }
+
+    // Evaluates `code` with `mq_eval_with_options` and returns the raw result.
+    fn eval_result(code: &str, input: &str, input_format: &str, timeout_ms: u64) -> MqResult {
+        let engine = mq_create();
+        let code = CString::new(code).unwrap();
+        let input = CString::new(input).unwrap();
+        let input_format = CString::new(input_format).unwrap();
+        let options = MqEvalOptions {
+            input_format: input_format.as_ptr(),
+            timeout_ms,
+            ..Default::default()
+        };
+
+        let result = unsafe { mq_eval_with_options(engine, code.as_ptr(), input.as_ptr(), options) };
+        mq_destroy(engine);
+        result
+    }
+
+    #[rstest]
+    #[case::ok(".h", "# a", "markdown", 0, MqErrorCode::Ok)]
+    #[case::unsupported_format(".h", "a: 1", "yaml", 0, MqErrorCode::UnsupportedFormat)]
+    #[case::syntax("1 +", "a", "text", 0, MqErrorCode::Syntax)]
+    #[case::runtime("undefined_function()", "a", "text", 0, MqErrorCode::Runtime)]
+    #[case::timeout("loop: 1;", "# a", "markdown", 1, MqErrorCode::Timeout)]
+    #[case::module("import \"missing_module\"", "a", "text", 0, MqErrorCode::Module)]
+    fn test_error_code(
+        #[case] code: &str,
+        #[case] input: &str,
+        #[case] input_format: &str,
+        #[case] timeout_ms: u64,
+        #[case] expected: MqErrorCode,
+    ) {
+        let result = eval_result(code, input, input_format, timeout_ms);
+        assert_eq!(result.error_code, expected);
+        assert_eq!(result.error_msg.is_null(), expected == MqErrorCode::Ok);
+        mq_free_result(result);
+    }
+
+    #[test]
+    fn test_error_code_invalid_argument() {
+        let result = unsafe { mq_eval(ptr::null_mut(), ptr::null(), ptr::null(), ptr::null()) };
+        assert_eq!(result.error_code, MqErrorCode::InvalidArgument);
+        assert_eq!((result.error_line, result.error_column), (0, 0));
+        mq_free_result(result);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let result = eval_result("upcase()\n| undefined_function()", "a", "text", 0);
+        assert_eq!(result.error_code, MqErrorCode::Runtime);
+        assert_eq!((result.error_line, result.error_column), (2, 3));
+        mq_free_result(result);
+    }
+
+    #[rstest]
+    #[case::string("\"s\"", "text", MqValueType::String)]
+    #[case::number("1", "text", MqValueType::Number)]
+    #[case::bool("true", "text", MqValueType::Bool)]
+    #[case::none("None", "text", MqValueType::None)]
+    #[case::array("[1]", "text", MqValueType::Array)]
+    #[case::dict("{\"a\": 1}", "text", MqValueType::Dict)]
+    #[case::markdown("self", "markdown", MqValueType::Markdown)]
+    fn test_value_types(#[case] code: &str, #[case] input_format: &str, #[case] expected: MqValueType) {
+        let result = eval_result(code, "# a", input_format, 0);
+        assert!(result.error_msg.is_null());
+
+        let value_types = unsafe { std::slice::from_raw_parts(result.value_types, result.values_len) };
+        assert_eq!(value_types, [expected]);
+        mq_free_result(result);
+    }
+
+    #[test]
+    fn test_value_types_empty_result() {
+        let result = eval_result(".h", "", "text", 0);
+        assert_eq!(result.values_len, 0);
+        assert!(result.value_types.is_null());
+        mq_free_result(result);
+    }
+
     #[test]
     fn test_c_str_to_rust_str_slice() {
         // Test with valid C string
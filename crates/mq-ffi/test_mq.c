@@ -481,6 +481,161 @@ void test_clear_http_cache_does_not_crash() {
     printf("PASS\n");
 }
 
+void test_eval_with_options() {
+    printf("Test 24: mq_eval_with_options... ");
+
+    mq_context_t *engine = mq_create();
+
+    MqEvalOptions options = {0};
+    options.list_style = MQ_LIST_STYLE_STAR;
+    struct mq_result_t result = mq_eval_with_options(engine, ".[]", "- item", options);
+    assert_null(result.error_msg, "Should not have error");
+    assert_equals(result.values_len, 1, "Should have 1 value");
+    assert_str_equals(result.values[0], "* item", "List style mismatch");
+    mq_free_result(result);
+
+    options = (MqEvalOptions){0};
+    options.input_format = "html";
+    options.conversion_options.use_title_as_h1 = true;
+    result = mq_eval_with_options(engine, ".h1 | to_text()", "<html><head><title>Hi</title></head></html>", options);
+    assert_null(result.error_msg, "Should not have error");
+    assert_str_equals(result.values[0], "Hi", "Title should be converted to h1");
+    mq_free_result(result);
+
+    options = (MqEvalOptions){0};
+    options.timeout_ms = 1;
+    result = mq_eval_with_options(engine, "loop: 1;", "# a", options);
+    assert_not_null(result.error_msg, "Should have timeout error");
+    mq_free_result(result);
+
+    mq_destroy(engine);
+
+    printf("PASS\n");
+}
+
+typedef struct {
+    size_t count;
+    char last[64];
+} stream_state_t;
+
+bool count_values(const char *value, void *user_data) {
+    stream_state_t *state = (stream_state_t *)user_data;
+    state->count++;
+    snprintf(state->last, sizeof(state->last), "%s", value);
+    return true;
+}
+
+bool stop_after_first(const char *value, void *user_data) {
+    count_values(value, user_data);
+    return false;
+}
+
+void test_eval_stream() {
+    printf("Test 25: mq_eval_stream... ");
+
+    mq_context_t *engine = mq_create();
+
+    stream_state_t state = {0};
+    char *error_msg = mq_eval_stream(engine, "upcase()", "a\nb\nc", "text", count_values, &state);
+    assert_null(error_msg, "Should not have error");
+    assert_equals(state.count, 3, "Should stream 3 values");
+    assert_str_equals(state.last, "C", "Last value mismatch");
+
+    stream_state_t stopped = {0};
+    error_msg = mq_eval_stream(engine, "upcase()", "a\nb\nc", "text", stop_after_first, &stopped);
+    assert_null(error_msg, "Stopping should not be an error");
+    assert_equals(stopped.count, 1, "Should stop after the first value");
+
+    error_msg = mq_eval_stream(NULL, ".h", "a", "text", count_values, &state);
+    assert_not_null(error_msg, "Should have error message");
+    assert_str_equals(error_msg, "Engine pointer is null", "Error message mismatch");
+    mq_free_string(error_msg);
+
+    mq_destroy(engine);
+
+    printf("PASS\n");
+}
+
+char *greet(const char *const *args_json, size_t args_len, void *user_data, char **error_msg) {
+    const char *greeting = (const char *)user_data;
+    if (args_len != 1) {
+        *error_msg = strdup("greet expects one argument");
+        return NULL;
+    }
+
+    // args_json[0] is a JSON string such as "\"mq\"".
+    char *result = malloc(strlen(greeting) + strlen(args_json[0]) + 4);
+    sprintf(result, "\"%s %.*s\"", greeting, (int)(strlen(args_json[0]) - 2), args_json[0] + 1);
+    return result;
+}
+
+void test_set_var_and_register_function() {
+    printf("Test 26: mq_set_var + mq_register_function... ");
+
+    mq_context_t *engine = mq_create();
+
+    char *error_msg = mq_set_var(engine, "limit", "2");
+    assert_null(error_msg, "Should not have error setting a number");
+    error_msg = mq_set_var(engine, "tags", "[\"a\", \"b\", \"c\"]");
+    assert_null(error_msg, "Should not have error setting an array");
+
+    struct mq_result_t result = mq_eval(engine, "len(tags) - limit", "test", "text");
+    assert_null(result.error_msg, "Should not have error");
+    assert_str_equals(result.values[0], "1", "Variables should be typed");
+    mq_free_result(result);
+
+    error_msg = mq_set_var(engine, "bad", "{");
+    assert_not_null(error_msg, "Should reject invalid JSON");
+    mq_free_string(error_msg);
+
+    error_msg = mq_register_function(engine, "greet", 1, greet, "Hello");
+    assert_null(error_msg, "Should not have error registering a function");
+
+    result = mq_eval(engine, "greet()", "mq", "text");
+    assert_null(result.error_msg, "Should not have error calling a host function");
+    assert_str_equals(result.values[0], "Hello mq", "Host function result mismatch");
+    mq_free_result(result);
+
+    result = mq_eval(engine, "greet(\"a\", \"b\")", "mq", "text");
+    assert_not_null(result.error_msg, "Should have error for wrong arity");
+    mq_free_result(result);
+
+    mq_destroy(engine);
+
+    printf("PASS\n");
+}
+
+void test_error_codes_and_value_types() {
+    printf("Test 27: error codes and value types... ");
+
+    mq_context_t *engine = mq_create();
+
+    struct mq_result_t result = mq_eval(engine, "upcase()\n| undefined_function()", "a", "text");
+    assert(result.error_code == MQ_ERROR_CODE_RUNTIME);
+    assert(result.error_line == 2);
+    assert(result.error_column == 3);
+    mq_free_result(result);
+
+    result = mq_eval(engine, "1 +", "a", "text");
+    assert(result.error_code == MQ_ERROR_CODE_SYNTAX);
+    mq_free_result(result);
+
+    result = mq_eval(engine, ".h", "a", "yaml");
+    assert(result.error_code == MQ_ERROR_CODE_UNSUPPORTED_FORMAT);
+    mq_free_result(result);
+
+    result = mq_eval(engine, "len()", "abc", "text");
+    assert(result.error_code == MQ_ERROR_CODE_OK);
+    assert_null(result.error_msg, "Should not have error");
+    assert(result.values_len == 1);
+    assert(result.value_types[0] == MQ_VALUE_TYPE_NUMBER);
+    mq_free_result(result);
+
+    mq_destroy(engine);
+
+    printf("PASS\n");
+}
+
 int main() {
     printf("Running mq-ffi C tests...\n\n");
 
@@ -507,6 +662,10 @@ int main() {
     test_set_search_paths_edge_cases();
     test_http_allowed_domains_does_not_crash();
     test_clear_http_cache_does_not_crash();
+    test_eval_with_options();
+    test_eval_stream();
+    test_set_var_and_register_function();
+    test_error_codes_and_value_types();
 
     printf("\nAll tests passed!\n");
     return 0;
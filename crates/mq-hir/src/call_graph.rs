@@ -0,0 +1,180 @@
+use crate::{Hir, SymbolId, SymbolKind};
+
+/// A call from one function to another, found at a single call site.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallEdge {
+    /// The function or macro the call is made from, or `None` for a call at the top level of a source.
+    pub caller: Option<SymbolId>,
+    /// The function or macro being called.
+    pub callee: SymbolId,
+    /// The symbol of the call itself.
+    pub call_site: SymbolId,
+    /// Where the call is made.
+    pub range: Option<mq_lang::Range>,
+}
+
+/// Caller → callee edges between the functions and macros in a [`Hir`], in source order.
+///
+/// Functions passed by name (e.g. `map(f)`) count as being called by the function that passes them.
+#[derive(Debug, Clone, Default)]
+pub struct CallGraph {
+    edges: Vec<CallEdge>,
+}
+
+impl CallGraph {
+    pub fn edges(&self) -> &[CallEdge] {
+        &self.edges
+    }
+
+    /// Returns the calls made from `caller`, or from the top level when `caller` is `None`.
+    pub fn outgoing(&self, caller: Option<SymbolId>) -> impl Iterator<Item = &CallEdge> {
+        self.edges.iter().filter(move |edge| edge.caller == caller)
+    }
+
+    /// Returns the calls made to `callee`.
+    pub fn incoming(&self, callee: SymbolId) -> impl Iterator<Item = &CallEdge> {
+        self.edges.iter().filter(move |edge| edge.callee == callee)
+    }
+
+    /// Returns the functions reachable from top-level calls, including the ones they call in turn.
+    pub fn reachable(&self) -> Vec<SymbolId> {
+        let mut reachable = Vec::new();
+        let mut pending = self.outgoing(None).map(|edge| edge.callee).collect::<Vec<_>>();
+
+        while let Some(function) = pending.pop() {
+            if !reachable.contains(&function) {
+                reachable.push(function);
+                pending.extend(self.outgoing(Some(function)).map(|edge| edge.callee));
+            }
+        }
+
+        reachable
+    }
+}
+
+impl Hir {
+    /// Builds the call graph of every source, from the resolved references to functions and macros.
+    pub fn call_graph(&self) -> CallGraph {
+        let mut edges = self
+            .references
+            .iter()
+            .filter_map(|(ref_id, def_id)| {
+                let call_site = self.symbols.get(*ref_id)?;
+                let callee = self.symbols.get(*def_id)?;
+
+                if !matches!(call_site.kind, SymbolKind::Call | SymbolKind::Ref)
+                    || !(callee.is_function() || callee.is_macro())
+                {
+                    return None;
+                }
+
+                Some(CallEdge {
+                    caller: self.enclosing_function(*ref_id),
+                    callee: *def_id,
+                    call_site: *ref_id,
+                    range: call_site.source.text_range,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        edges.sort_by_key(|edge| self.symbol_insertion_order(edge.call_site));
+
+        CallGraph { edges }
+    }
+
+    /// Returns the innermost function or macro whose body contains `symbol_id`.
+    fn enclosing_function(&self, symbol_id: SymbolId) -> Option<SymbolId> {
+        let mut parent = self.symbols.get(symbol_id)?.parent;
+
+        while let Some(parent_id) = parent {
+            let symbol = self.symbols.get(parent_id)?;
+            if symbol.is_function() || symbol.is_macro() {
+                return Some(parent_id);
+            }
+            parent = symbol.parent;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(hir: &Hir, name: &str) -> SymbolId {
+        hir.symbols()
+            .find(|(_, symbol)| symbol.is_function() && symbol.value.as_deref() == Some(name))
+            .map(|(symbol_id, _)| symbol_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_call_graph() {
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+        hir.add_code(
+            None,
+            "def leaf(): 1;\n| def middle(): leaf() + leaf();\n| def top(): middle();\n| def unused(): leaf();\n| top()",
+        );
+
+        let leaf = function(&hir, "leaf");
+        let middle = function(&hir, "middle");
+        let top = function(&hir, "top");
+        let unused = function(&hir, "unused");
+        let graph = hir.call_graph();
+
+        let edges = graph
+            .edges()
+            .iter()
+            .map(|edge| (edge.caller, edge.callee))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                (Some(middle), leaf),
+                (Some(middle), leaf),
+                (Some(top), middle),
+                (Some(unused), leaf),
+                (None, top),
+            ]
+        );
+
+        let call_site = graph.outgoing(None).next().unwrap();
+        assert_eq!(call_site.range.map(|range| range.start.line), Some(5));
+        assert_eq!(graph.incoming(leaf).count(), 3);
+
+        let reachable = graph.reachable();
+        assert!(reachable.contains(&leaf));
+        assert!(reachable.contains(&middle));
+        assert!(!reachable.contains(&unused));
+    }
+
+    #[test]
+    fn test_call_graph_nested_function_and_function_argument() {
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+        let code = r#"def inc(x): x + 1;
+| def outer():
+  def inner(): inc(1);
+  | inner();
+| def twice(f, x): f(f(x));
+| twice(inc, 1)"#;
+        hir.add_code(None, code);
+
+        let inc = function(&hir, "inc");
+        let inner = function(&hir, "inner");
+        let outer = function(&hir, "outer");
+        let graph = hir.call_graph();
+
+        assert_eq!(
+            graph.incoming(inc).map(|edge| edge.caller).collect::<Vec<_>>(),
+            vec![Some(inner), None]
+        );
+        assert_eq!(
+            graph.incoming(inner).map(|edge| edge.caller).collect::<Vec<_>>(),
+            vec![Some(outer)]
+        );
+        assert!(!graph.reachable().contains(&outer));
+    }
+}
@@ -0,0 +1,261 @@
+use smol_str::SmolStr;
+
+/// Documentation of a symbol, parsed from its doc comments.
+///
+/// Lines before the first tag form the summary. The recognized tags are:
+///
+/// - `@param <name> <description>`
+/// - `@return <description>` (or `@returns`)
+/// - `@example`, followed by example code up to the next tag
+/// - `@deprecated [since <version>] [message]`
+/// - `@export <name>, ...`, listing the names a module exports
+///
+/// A tag's description continues on the following lines up to a blank line. A line of the
+/// form `deprecated: <message>` is read the same as `@deprecated <message>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DocComment {
+    pub summary: String,
+    pub params: Vec<DocParam>,
+    pub returns: Option<String>,
+    pub examples: Vec<String>,
+    pub deprecated: Option<Deprecation>,
+    pub exports: Vec<SmolStr>,
+}
+
+/// A documented parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DocParam {
+    pub name: SmolStr,
+    pub description: String,
+}
+
+/// Why and since when a symbol is deprecated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Deprecation {
+    pub since: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Summary,
+    Param,
+    Return,
+    Example,
+    Deprecated,
+}
+
+impl DocComment {
+    /// Parses doc comment lines, without their leading `#`.
+    pub fn parse<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut doc = DocComment::default();
+        let mut summary = Vec::new();
+        let mut section = Section::Summary;
+
+        for line in lines {
+            // Doc comments are written as `# text`; drop the space after the `#`.
+            let line = line.strip_prefix(' ').unwrap_or(line);
+            let trimmed = line.trim();
+
+            if let Some(rest) = trimmed.strip_prefix("@param") {
+                let (name, description) = rest.trim().split_once(char::is_whitespace).unwrap_or((rest.trim(), ""));
+                doc.params.push(DocParam {
+                    name: name.into(),
+                    description: description.trim().to_string(),
+                });
+                section = Section::Param;
+            } else if let Some(rest) = trimmed
+                .strip_prefix("@returns")
+                .or_else(|| trimmed.strip_prefix("@return"))
+            {
+                doc.returns = Some(rest.trim().to_string());
+                section = Section::Return;
+            } else if let Some(rest) = trimmed.strip_prefix("@example") {
+                doc.examples.push(rest.trim().to_string());
+                section = Section::Example;
+            } else if let Some(rest) = trimmed.strip_prefix("@export") {
+                doc.exports.extend(
+                    rest.split(|c: char| c == ',' || c.is_whitespace())
+                        .filter(|name| !name.is_empty())
+                        .map(SmolStr::from),
+                );
+                section = Section::Summary;
+            } else if let Some(rest) = trimmed.strip_prefix("@deprecated") {
+                doc.deprecated = Some(parse_deprecation(rest));
+                section = Section::Deprecated;
+            } else if is_deprecated_marker(trimmed) {
+                doc.deprecated = Some(Deprecation {
+                    since: None,
+                    message: trimmed
+                        .split_once(':')
+                        .map(|(_, message)| message.trim())
+                        .filter(|message| !message.is_empty())
+                        .map(str::to_string),
+                });
+                section = Section::Summary;
+            } else {
+                match section {
+                    Section::Example => {
+                        if let Some(example) = doc.examples.last_mut() {
+                            if !example.is_empty() {
+                                example.push('\n');
+                            }
+                            example.push_str(line);
+                        }
+                    }
+                    _ if trimmed.is_empty() => {
+                        section = Section::Summary;
+                        summary.push(line);
+                    }
+                    Section::Summary => summary.push(line),
+                    Section::Param => append(doc.params.last_mut().map(|param| &mut param.description), trimmed),
+                    Section::Return => append(doc.returns.as_mut(), trimmed),
+                    Section::Deprecated => append(
+                        doc.deprecated
+                            .as_mut()
+                            .map(|deprecation| deprecation.message.get_or_insert_with(String::new)),
+                        trimmed,
+                    ),
+                }
+            }
+        }
+
+        doc.summary = summary.join("\n").trim().to_string();
+        for example in doc.examples.iter_mut() {
+            *example = example.trim_end().to_string();
+        }
+
+        doc
+    }
+}
+
+/// Returns `true` if a doc line is an untagged deprecation marker.
+///
+/// The trimmed line must start with `deprecated` (case-insensitive) followed by `:`, a space,
+/// or the end of the line, so that lines merely mentioning "deprecated" are not matched.
+fn is_deprecated_marker(text: &str) -> bool {
+    let lower = text.trim().to_lowercase();
+    lower == "deprecated" || lower.starts_with("deprecated:") || lower.starts_with("deprecated ")
+}
+
+fn parse_deprecation(text: &str) -> Deprecation {
+    let text = text.trim();
+    let (since, message) = match text.strip_prefix("since ") {
+        Some(rest) => {
+            let rest = rest.trim_start();
+            let (version, message) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let (version, message) = match version.strip_suffix(':') {
+                Some(version) => (version, message),
+                None => (version, message.trim_start().strip_prefix(':').unwrap_or(message)),
+            };
+            (Some(version.to_string()), message)
+        }
+        None => (None, text.strip_prefix(':').unwrap_or(text)),
+    };
+    let message = message.trim();
+
+    Deprecation {
+        since,
+        message: (!message.is_empty()).then(|| message.to_string()),
+    }
+}
+
+fn append(description: Option<&mut String>, text: &str) {
+    if let Some(description) = description {
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_parse() {
+        let lines = [
+            " Splits a string into parts.",
+            " Empty parts are kept.",
+            " @param s The string to split",
+            " @param sep The separator,",
+            "   which may be longer than one character",
+            " @return An array of the parts",
+            " @example",
+            " split(\"a,b\", \",\")",
+            " # => [\"a\", \"b\"]",
+        ];
+        let doc = DocComment::parse(lines);
+
+        assert_eq!(doc.summary, "Splits a string into parts.\nEmpty parts are kept.");
+        assert_eq!(
+            doc.params,
+            vec![
+                DocParam {
+                    name: "s".into(),
+                    description: "The string to split".to_string(),
+                },
+                DocParam {
+                    name: "sep".into(),
+                    description: "The separator, which may be longer than one character".to_string(),
+                },
+            ]
+        );
+        assert_eq!(doc.returns.as_deref(), Some("An array of the parts"));
+        assert_eq!(
+            doc.examples,
+            vec!["split(\"a,b\", \",\")\n# => [\"a\", \"b\"]".to_string()]
+        );
+        assert_eq!(doc.deprecated, None);
+    }
+
+    #[test]
+    fn test_parse_exports() {
+        let doc = DocComment::parse([" A module.", " @export parse, format", " @export render"]);
+
+        assert_eq!(doc.summary, "A module.");
+        assert_eq!(doc.exports, vec!["parse", "format", "render"]);
+    }
+
+    #[test]
+    fn test_parse_blank_line_ends_tag_description() {
+        let doc = DocComment::parse([" @param x The input", "", " More about the function."]);
+
+        assert_eq!(doc.params[0].description, "The input");
+        assert_eq!(doc.summary, "More about the function.");
+    }
+
+    #[rstest]
+    #[case::tag_with_since(" @deprecated since 0.5 use bar instead", Some("0.5"), Some("use bar instead"))]
+    #[case::tag_with_since_and_colon(" @deprecated since 0.5: use bar instead", Some("0.5"), Some("use bar instead"))]
+    #[case::tag_only(" @deprecated", None, None)]
+    #[case::tag_with_message(" @deprecated use bar instead", None, Some("use bar instead"))]
+    #[case::marker("deprecated: use foo instead", None, Some("use foo instead"))]
+    #[case::marker_without_message("deprecated:", None, None)]
+    #[case::marker_bare("deprecated", None, None)]
+    #[case::marker_uppercase("  Deprecated:  trimmed message  ", None, Some("trimmed message"))]
+    fn test_parse_deprecated(#[case] line: &str, #[case] since: Option<&str>, #[case] message: Option<&str>) {
+        let doc = DocComment::parse([line]);
+
+        assert_eq!(
+            doc.deprecated,
+            Some(Deprecation {
+                since: since.map(str::to_string),
+                message: message.map(str::to_string),
+            })
+        );
+        assert_eq!(doc.summary, "");
+    }
+
+    #[rstest]
+    #[case::mention("use this instead of the deprecated foo API")]
+    #[case::section("see the deprecated section below")]
+    fn test_parse_mentioning_deprecated(#[case] line: &str) {
+        let doc = DocComment::parse([line]);
+
+        assert_eq!(doc.deprecated, None);
+        assert_eq!(doc.summary, line);
+    }
+}
@@ -0,0 +1,134 @@
+use mq_lang::{Position, Range};
+
+/// A change to the text of a source, as passed to [`Hir::update_code`](crate::Hir::update_code).
+///
+/// Positions use the same 1-based lines and character columns as [`mq_lang::Range`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    /// The range to replace, or `None` to replace the whole text.
+    pub range: Option<Range>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range, text: impl Into<String>) -> Self {
+        Self {
+            range: Some(range),
+            text: text.into(),
+        }
+    }
+
+    /// Creates an edit that replaces the whole text.
+    pub fn full(text: impl Into<String>) -> Self {
+        Self {
+            range: None,
+            text: text.into(),
+        }
+    }
+}
+
+/// Applies `edits` to `code` in order; each edit's range refers to the text left by the previous edits.
+pub(crate) fn apply_edits(code: &str, edits: &[TextEdit]) -> String {
+    edits.iter().fold(code.to_string(), |mut code, edit| match edit.range {
+        Some(range) => {
+            let start = byte_offset(&code, range.start);
+            let end = byte_offset(&code, range.end).max(start);
+            code.replace_range(start..end, &edit.text);
+            code
+        }
+        None => edit.text.clone(),
+    })
+}
+
+/// Converts a position to a byte offset, clamping it to the end of its line or of the text.
+pub(crate) fn byte_offset(code: &str, position: Position) -> usize {
+    let mut offset = 0;
+
+    for (i, line) in code.split_inclusive('\n').enumerate() {
+        if i + 1 == position.line as usize {
+            let content = line.strip_suffix('\n').unwrap_or(line);
+            return offset
+                + content
+                    .char_indices()
+                    .nth(position.column.saturating_sub(1))
+                    .map_or(content.len(), |(i, _)| i);
+        }
+        offset += line.len();
+    }
+
+    code.len()
+}
+
+/// Converts a byte offset to a position.
+pub(crate) fn position_at(code: &str, offset: usize) -> Position {
+    let before = &code[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    Position::new(
+        before.matches('\n').count() as u32 + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Returns the byte ranges of the changed region in `old` and `new`, excluding the common prefix and suffix.
+pub(crate) fn changed_region(old: &str, new: &str) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(old.len().min(new.len()), |((i, _), _)| i);
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+
+    (prefix..old.len() - suffix, prefix..new.len() - suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn range(start: (u32, usize), end: (u32, usize)) -> Range {
+        Range {
+            start: Position::new(start.0, start.1),
+            end: Position::new(end.0, end.1),
+        }
+    }
+
+    #[rstest]
+    #[case::insert("let x = 1", vec![TextEdit::new(range((1, 9), (1, 9)), "2")], "let x = 21")]
+    #[case::replace("let x = 1\n| x", vec![TextEdit::new(range((2, 3), (2, 4)), "add(x, 1)")], "let x = 1\n| add(x, 1)")]
+    #[case::delete_lines("a\nb\nc", vec![TextEdit::new(range((1, 2), (2, 2)), "")], "a\nc")]
+    #[case::multibyte("\"éé\" | x", vec![TextEdit::new(range((1, 8), (1, 9)), "y")], "\"éé\" | y")]
+    #[case::sequential("a", vec![TextEdit::new(range((1, 2), (1, 2)), "b"), TextEdit::new(range((1, 3), (1, 3)), "c")], "abc")]
+    #[case::full("a", vec![TextEdit::full("b")], "b")]
+    #[case::past_end("a", vec![TextEdit::new(range((3, 1), (3, 1)), "b")], "ab")]
+    fn test_apply_edits(#[case] code: &str, #[case] edits: Vec<TextEdit>, #[case] expected: &str) {
+        assert_eq!(apply_edits(code, &edits), expected);
+    }
+
+    #[rstest]
+    #[case::middle("let x = 1", "let y = 1", (4..5, 4..5))]
+    #[case::insert("ab", "axb", (1..1, 1..2))]
+    #[case::repeated("aa", "aaa", (2..2, 2..3))]
+    #[case::same("a", "a", (1..1, 1..1))]
+    fn test_changed_region(
+        #[case] old: &str,
+        #[case] new: &str,
+        #[case] expected: (std::ops::Range<usize>, std::ops::Range<usize>),
+    ) {
+        assert_eq!(changed_region(old, new), expected);
+    }
+
+    #[test]
+    fn test_position_at() {
+        assert_eq!(position_at("ab\né", 0), Position::new(1, 1));
+        assert_eq!(position_at("ab\néc", 5), Position::new(2, 2));
+        assert_eq!(byte_offset("ab\néc", Position::new(2, 2)), 5);
+    }
+}
@@ -1,7 +1,10 @@
 use smol_str::SmolStr;
 use thiserror::Error;
 
-use crate::{Hir, Symbol, SymbolKind};
+use crate::{
+    Hir, Symbol, SymbolKind,
+    export::{is_exported_name, is_top_level_definition},
+};
 
 #[derive(Debug, Error)]
 pub enum HirError {
@@ -18,6 +21,8 @@ pub enum HirError {
     },
     #[error("Included module not found: {module_name}")]
     ModuleNotFound { symbol: Symbol, module_name: SmolStr },
+    #[error("Symbol is not exported by its module: {symbol}")]
+    PrivateSymbol { symbol: Symbol, definition: Symbol },
 }
 
 #[derive(Debug, Error)]
@@ -28,12 +33,27 @@ pub enum HirWarning {
 
 impl Hir {
     pub fn errors(&self) -> Vec<HirError> {
+        let export_lists = self.export_lists();
+
         self.symbols
             .iter()
             .filter_map(|(symbol_id, symbol)| match symbol.kind {
                 SymbolKind::Call | SymbolKind::Ref => {
-                    if self.references.contains_key(&symbol_id) {
-                        None
+                    if let Some(definition) = self
+                        .references
+                        .get(&symbol_id)
+                        .and_then(|def_id| self.symbols.get(*def_id))
+                    {
+                        // Definitions of another module can only be used if the module exports them.
+                        let private = definition.source.source_id != symbol.source.source_id
+                            && !self.is_builtin_symbol(definition)
+                            && is_top_level_definition(self, definition)
+                            && !is_exported_name(&export_lists, definition);
+
+                        private.then(|| HirError::PrivateSymbol {
+                            symbol: symbol.clone(),
+                            definition: definition.clone(),
+                        })
                     } else {
                         Some(HirError::UnresolvedSymbol {
                             symbol: symbol.clone(),
@@ -109,6 +129,7 @@ impl Hir {
                     match e {
                         HirError::UnresolvedSymbol { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
                         HirError::ModuleNotFound { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
+                        HirError::PrivateSymbol { symbol, .. } => symbol.source.text_range.unwrap_or_default(),
                     },
                 )
             })
@@ -0,0 +1,136 @@
+use rustc_hash::{FxHashMap, FxHashSet};
+use smol_str::SmolStr;
+
+use crate::{Hir, SourceId, Symbol, SymbolId, SymbolKind};
+
+/// Names listed with `@export` doc comment tags, for each source that has any.
+pub(crate) type ExportLists = FxHashMap<SourceId, FxHashSet<SmolStr>>;
+
+impl Hir {
+    /// Returns the top-level functions, macros, and variables that the module at `source_id` exports.
+    ///
+    /// A module that lists names with `@export` doc comment tags exports exactly those. Otherwise it
+    /// exports every top-level definition except those whose names start with `_`.
+    pub fn exports(&self, source_id: SourceId) -> Vec<(SymbolId, &Symbol)> {
+        let export_lists = self.export_lists();
+
+        self.symbols
+            .iter()
+            .filter(|(_, symbol)| {
+                symbol.source.source_id == Some(source_id) && is_exported(self, &export_lists, symbol)
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the symbol is a top-level definition that its module exports.
+    pub fn is_exported(&self, symbol_id: SymbolId) -> bool {
+        self.symbols
+            .get(symbol_id)
+            .is_some_and(|symbol| is_exported(self, &self.export_lists(), symbol))
+    }
+
+    pub(crate) fn export_lists(&self) -> ExportLists {
+        let mut export_lists = ExportLists::default();
+
+        for (_, symbol) in &self.symbols {
+            if symbol.parent.is_none()
+                && let Some(source_id) = symbol.source.source_id
+                && symbol.doc.iter().any(|(_, text)| text.contains("@export"))
+            {
+                export_lists
+                    .entry(source_id)
+                    .or_default()
+                    .extend(symbol.doc_comment().exports);
+            }
+        }
+
+        export_lists
+    }
+}
+
+/// Returns `true` for functions, macros, and variables defined at the top level of a source or
+/// directly inside a `module`.
+pub(crate) fn is_top_level_definition(hir: &Hir, symbol: &Symbol) -> bool {
+    (symbol.is_function() || symbol.is_macro() || symbol.is_variable())
+        && symbol.parent.is_none_or(|parent| {
+            hir.symbols
+                .get(parent)
+                .is_some_and(|parent| matches!(parent.kind, SymbolKind::Module(_)))
+        })
+}
+
+/// Returns `true` if `symbol` is a top-level definition that its source exports.
+fn is_exported(hir: &Hir, export_lists: &ExportLists, symbol: &Symbol) -> bool {
+    is_top_level_definition(hir, symbol) && is_exported_name(export_lists, symbol)
+}
+
+/// Returns `true` unless the symbol's source does not export its name.
+pub(crate) fn is_exported_name(export_lists: &ExportLists, symbol: &Symbol) -> bool {
+    let Some(name) = symbol.value.as_ref() else {
+        return false;
+    };
+
+    match symbol
+        .source
+        .source_id
+        .and_then(|source_id| export_lists.get(&source_id))
+    {
+        Some(names) => names.contains(name),
+        None => !name.starts_with('_'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HirError;
+    use rstest::rstest;
+
+    fn exported_names(code: &str) -> Vec<String> {
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+        let (source_id, _) = hir.add_code(None, code);
+
+        let mut names = hir
+            .exports(source_id)
+            .into_iter()
+            .filter_map(|(_, symbol)| symbol.value.as_ref().map(|name| name.to_string()))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    #[rstest]
+    #[case::underscore_is_private("def run(): _step(1); | def _step(x): x; | let limit = 1", vec!["limit", "run"])]
+    #[case::export_list("# @export run\ndef run(): step(1); | def step(x): x; | let limit = 1", vec!["run"])]
+    #[case::export_list_includes_underscore("# @export _step\ndef run(): 1; | def _step(x): x;", vec!["_step"])]
+    #[case::nested_definitions("def run(): def inner(): 1; | inner();", vec!["run"])]
+    fn test_exports(#[case] code: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(exported_names(code), expected);
+    }
+
+    #[test]
+    fn test_private_symbol_of_included_module() {
+        let dir = std::env::temp_dir().join("mq_hir_private_symbol");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("helpers.mq"), "def visible(): _hidden(); | def _hidden(): 1;").unwrap();
+
+        let resolver = mq_lang::DefaultModuleResolver::new(vec![dir.clone()]);
+        let mut hir = Hir::new(mq_lang::ModuleLoader::new(resolver));
+        hir.builtin.disabled = true;
+        hir.add_code(None, "include \"helpers\" | visible() | _hidden()");
+
+        let errors = hir.errors();
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            HirError::PrivateSymbol { symbol, definition } => {
+                assert_eq!(symbol.value.as_deref(), Some("_hidden"));
+                assert!(definition.is_function());
+                assert_ne!(definition.source.source_id, symbol.source.source_id);
+            }
+            error => panic!("Expected PrivateSymbol error, got {:?}", error),
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
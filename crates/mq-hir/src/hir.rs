@@ -5,11 +5,13 @@ use url::Url;
 
 use crate::{
     builtin::Builtin,
+    hir::incremental::Item,
     scope::{Scope, ScopeId, ScopeKind},
     source::{Source, SourceId, SourceInfo},
     symbol::{Symbol, SymbolId, SymbolKind},
 };
 
+mod incremental;
 mod lower;
 mod query;
 
@@ -34,6 +36,10 @@ pub struct Hir {
     /// Populated by `insert_symbol` and pruned by `add_nodes` cleanup.
     /// Allows name-based lookups in `resolve.rs` to skip an O(n) full-symbol scan.
     pub(crate) name_index: FxHashMap<SmolStr, Vec<SymbolId>>,
+    /// Text of each source added with `add_code`, used by `update_code` to apply edits.
+    pub(crate) source_code: FxHashMap<SourceId, String>,
+    /// Symbols and scopes of each top-level expression, in source order.
+    pub(crate) source_items: FxHashMap<SourceId, Vec<Item>>,
 }
 
 impl Default for Hir {
@@ -72,6 +78,8 @@ impl Hir {
             source_symbols: FxHashMap::default(),
             symbol_insertion_counter: 0,
             name_index: FxHashMap::default(),
+            source_code: FxHashMap::default(),
+            source_items: FxHashMap::default(),
         }
     }
 
@@ -98,9 +106,24 @@ impl Hir {
     }
 
     pub fn add_code(&mut self, url: Option<Url>, code: &str) -> (SourceId, ScopeId) {
+        let url = url.unwrap_or(Url::parse("file:///").unwrap());
+
+        // Modules are added again by every `include`/`import` of them; skip unchanged ones.
+        if let Some(source_id) = self.source_by_url(&url)
+            && self
+                .source_code
+                .get(&source_id)
+                .is_some_and(|previous| previous == code)
+            && let Some(scope_id) = self.source_scopes.get(&source_id)
+        {
+            return (source_id, *scope_id);
+        }
+
         let (nodes, _) = mq_lang::parse_recovery(code);
+        let (source_id, scope_id) = self.add_nodes(url, &nodes);
+        self.source_code.insert(source_id, code.to_string());
 
-        self.add_nodes(url.unwrap_or(Url::parse("file:///").unwrap()), &nodes)
+        (source_id, scope_id)
     }
 
     pub fn add_builtin(&mut self) {
@@ -197,6 +220,12 @@ impl Hir {
                     .retain(|_, symbol| symbol.source.source_id != Some(*source_id));
                 // Clear the index for this source
                 self.source_symbols.remove(source_id);
+                self.source_code.remove(source_id);
+                if let Some(items) = self.source_items.remove(source_id) {
+                    for item in items {
+                        self.remove_scopes(&item.scopes);
+                    }
+                }
             })
             .unwrap_or_else(|| self.add_source(Source::new(Some(url))));
 
@@ -223,10 +252,12 @@ impl Hir {
         });
 
         self.source_scopes.insert(source_id, scope_id);
+        if let Some(scope) = self.scopes.get_mut(scope_id) {
+            scope.children.clear();
+        }
 
-        nodes.iter().for_each(|node| {
-            self.add_expr(node, source_id, scope_id, None);
-        });
+        let items = self.lower_items(nodes, source_id, scope_id);
+        self.source_items.insert(source_id, items);
         self.resolve();
 
         (source_id, scope_id)
@@ -507,6 +538,57 @@ end"#;
         assert!(hir.errors().is_empty());
     }
 
+    fn call_in_source(hir: &Hir, source_id: SourceId, name: &str) -> SymbolId {
+        hir.symbols_for_source(source_id)
+            .find(|(_, symbol)| symbol.kind == SymbolKind::Call && symbol.value.as_deref() == Some(name))
+            .map(|(symbol_id, _)| symbol_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_include_resolves_only_in_including_source() {
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+
+        let (source_a, _) = hir.add_code(
+            Some(Url::parse("file:///a.mq").unwrap()),
+            "include \"csv\" | csv_parse(\"a,b\", false)",
+        );
+        let (source_b, _) = hir.add_code(Some(Url::parse("file:///b.mq").unwrap()), "csv_parse(\"a,b\", false)");
+
+        assert!(
+            hir.resolve_reference_symbol(call_in_source(&hir, source_a, "csv_parse"))
+                .is_some()
+        );
+        assert!(
+            hir.resolve_reference_symbol(call_in_source(&hir, source_b, "csv_parse"))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_include_shares_module_definitions() {
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+
+        let code = "include \"csv\" | csv_parse(\"a,b\", false)";
+        let (source_a, _) = hir.add_code(Some(Url::parse("file:///a.mq").unwrap()), code);
+        let (source_b, _) = hir.add_code(Some(Url::parse("file:///b.mq").unwrap()), code);
+
+        let def_a = hir.resolve_reference_symbol(call_in_source(&hir, source_a, "csv_parse"));
+        let def_b = hir.resolve_reference_symbol(call_in_source(&hir, source_b, "csv_parse"));
+        assert!(def_a.is_some());
+        assert_eq!(def_a, def_b);
+
+        let sources = hir
+            .references(def_a.unwrap())
+            .into_iter()
+            .filter_map(|(_, symbol)| symbol.source.source_id)
+            .collect::<Vec<_>>();
+        assert!(sources.contains(&source_a));
+        assert!(sources.contains(&source_b));
+    }
+
     #[test]
     fn test_unused_functions() {
         let mut hir = Hir::default();
@@ -0,0 +1,459 @@
+//! Incremental re-analysis of a source after its text changes.
+//!
+//! A source is lowered as a list of top-level items, one per top-level CST node. When the text
+//! changes, items entirely before the changed region are kept as they are, items entirely after
+//! it are kept and moved by the number of inserted or removed lines, and only the items in
+//! between are lowered again. References are then re-resolved only where the change could
+//! affect them.
+
+use rustc_hash::FxHashSet;
+use smol_str::SmolStr;
+use url::Url;
+
+use crate::{
+    Hir, SymbolKind,
+    edit::{TextEdit, apply_edits, changed_region, position_at},
+    scope::ScopeId,
+    source::SourceId,
+    symbol::SymbolId,
+};
+
+/// The symbols and scopes lowered from one top-level CST node.
+#[derive(Debug, Clone)]
+pub(crate) struct Item {
+    /// Start of the node, including its leading comments.
+    pub(crate) start: mq_lang::Position,
+    pub(crate) symbols: Vec<SymbolId>,
+    /// Scopes created directly under the module scope; nested scopes are reached through them.
+    pub(crate) scopes: Vec<ScopeId>,
+}
+
+fn item_start(node: &mq_lang::CstNode) -> mq_lang::Position {
+    node.comments()
+        .iter()
+        .map(|(range, _)| range.start)
+        .chain(std::iter::once(node.range().start))
+        .min()
+        .unwrap_or_default()
+}
+
+fn shift_range(range: &mut mq_lang::Range, lines: i64) {
+    range.start.line = (range.start.line as i64 + lines) as u32;
+    range.end.line = (range.end.line as i64 + lines) as u32;
+}
+
+impl Hir {
+    /// Applies `edits` to the text of the source at `url` and re-analyzes only the affected part.
+    ///
+    /// Top-level expressions outside the changed region keep their symbols, and only references
+    /// that the change could affect are resolved again. Falls back to analyzing the whole source
+    /// when its previous text is unknown, i.e. when it was added with [`Hir::add_nodes`] or not
+    /// added yet; range edits are then applied to an empty text.
+    pub fn update_code(&mut self, url: &Url, edits: &[TextEdit]) -> (SourceId, ScopeId) {
+        let source_id = self.source_by_url(url);
+        let old_code = source_id.and_then(|source_id| self.source_code.get(&source_id).cloned());
+        let new_code = apply_edits(old_code.as_deref().unwrap_or_default(), edits);
+
+        let (Some(source_id), Some(old_code)) = (source_id, old_code) else {
+            return self.add_code(Some(url.clone()), &new_code);
+        };
+        let Some(scope_id) = self.source_scopes.get(&source_id).copied() else {
+            return self.add_code(Some(url.clone()), &new_code);
+        };
+        if old_code == new_code {
+            return (source_id, scope_id);
+        }
+        let Some(old_items) = self.source_items.remove(&source_id) else {
+            return self.add_code(Some(url.clone()), &new_code);
+        };
+
+        let (nodes, _) = mq_lang::parse_recovery(&new_code);
+        let new_starts = nodes.iter().map(|node| item_start(node)).collect::<Vec<_>>();
+
+        let (old_region, new_region) = changed_region(&old_code, &new_code);
+        let change_start = position_at(&old_code, old_region.start);
+        let old_change_end = position_at(&old_code, old_region.end);
+        let new_change_end = position_at(&new_code, new_region.end);
+        let lines = new_change_end.line as i64 - old_change_end.line as i64;
+
+        // Items that end before the change; the item the change starts in is lowered again.
+        let prefix = (0..old_items.len().saturating_sub(1))
+            .take_while(|&i| {
+                old_items[i + 1].start < change_start
+                    && new_starts.get(i) == Some(&old_items[i].start)
+                    && new_starts.get(i + 1) == Some(&old_items[i + 1].start)
+            })
+            .count();
+
+        // Items that start on a line after the change and are found again, moved, in the new text.
+        let suffix = old_items[prefix..]
+            .iter()
+            .rev()
+            .zip(new_starts[prefix..].iter().rev())
+            .take_while(|(item, new_start)| {
+                item.start.line > old_change_end.line
+                    && item.start.column == new_start.column
+                    && item.start.line as i64 + lines == new_start.line as i64
+            })
+            .count();
+
+        let mut old_items = old_items;
+        let suffix_items = old_items.split_off(old_items.len() - suffix);
+        let removed_items = old_items.split_off(prefix);
+        let prefix_items = old_items;
+
+        // Remove the symbols and scopes of the changed items.
+        let removed_symbols: FxHashSet<SymbolId> = removed_items
+            .iter()
+            .flat_map(|item| item.symbols.iter().copied())
+            .collect();
+        let mut includes_changed = removed_symbols
+            .iter()
+            .any(|symbol_id| self.is_include_symbol(*symbol_id));
+        let mut affected_names: FxHashSet<SmolStr> = removed_symbols
+            .iter()
+            .filter_map(|symbol_id| self.symbols.get(*symbol_id).and_then(|symbol| symbol.value.clone()))
+            .collect();
+        for symbol_id in &removed_symbols {
+            self.symbols.remove(*symbol_id);
+        }
+        for item in &removed_items {
+            self.remove_scopes(&item.scopes);
+        }
+
+        // Move the items after the change.
+        if lines != 0 {
+            for item in &suffix_items {
+                for symbol_id in &item.symbols {
+                    if let Some(symbol) = self.symbols.get_mut(*symbol_id) {
+                        if let Some(range) = symbol.source.text_range.as_mut() {
+                            shift_range(range, lines);
+                        }
+                        for (range, _) in symbol.doc.iter_mut() {
+                            shift_range(range, lines);
+                        }
+                    }
+                }
+                self.shift_scopes(&item.scopes, lines);
+            }
+        }
+
+        // Lower the changed items.
+        let new_items = self.lower_items(&nodes[prefix..nodes.len() - suffix], source_id, scope_id);
+        let new_symbols = new_items
+            .iter()
+            .flat_map(|item| item.symbols.iter().copied())
+            .collect::<Vec<_>>();
+        includes_changed |= new_symbols.iter().any(|symbol_id| self.is_include_symbol(*symbol_id));
+        affected_names.extend(
+            new_symbols
+                .iter()
+                .filter_map(|symbol_id| self.symbols.get(*symbol_id).and_then(|symbol| symbol.value.clone())),
+        );
+
+        let items = prefix_items
+            .into_iter()
+            .chain(new_items)
+            .chain(suffix_items)
+            .collect::<Vec<_>>();
+        self.reorder_items(source_id, scope_id, &items);
+        self.source_items.insert(source_id, items);
+        self.source_code.insert(source_id, new_code);
+
+        // Drop index entries of the removed symbols.
+        {
+            let symbols = &self.symbols;
+            if let Some(ids) = self.source_symbols.get_mut(&source_id) {
+                ids.retain(|id| symbols.contains_key(*id));
+            }
+            for name in &affected_names {
+                if let Some(ids) = self.name_index.get_mut(name) {
+                    ids.retain(|id| symbols.contains_key(*id));
+                    if ids.is_empty() {
+                        self.name_index.remove(name);
+                    }
+                }
+            }
+        }
+
+        // Resolve the new references, references to removed symbols, and references to any
+        // name whose definitions changed.
+        let mut to_resolve: FxHashSet<SymbolId> = new_symbols.into_iter().collect();
+        self.references.retain(|ref_id, def_id| {
+            if removed_symbols.contains(ref_id) {
+                false
+            } else if removed_symbols.contains(def_id) {
+                to_resolve.insert(*ref_id);
+                false
+            } else {
+                true
+            }
+        });
+
+        // Adding or removing an include changes which modules every reference can see.
+        if includes_changed {
+            self.resolve();
+            return (source_id, scope_id);
+        }
+
+        to_resolve.extend(
+            affected_names
+                .iter()
+                .flat_map(|name| self.name_index.get(name).into_iter().flatten().copied()),
+        );
+        self.resolve_symbols(to_resolve);
+
+        (source_id, scope_id)
+    }
+
+    /// Lowers top-level nodes into a source, recording the symbols and scopes of each.
+    pub(crate) fn lower_items(
+        &mut self,
+        nodes: &[mq_lang::Shared<mq_lang::CstNode>],
+        source_id: SourceId,
+        scope_id: ScopeId,
+    ) -> Vec<Item> {
+        let first_order = self.symbol_insertion_counter;
+        let mut orders = Vec::with_capacity(nodes.len());
+        let mut items = Vec::with_capacity(nodes.len());
+
+        for node in nodes {
+            let order = self.symbol_insertion_counter;
+            let children = self.scopes.get(scope_id).map_or(0, |scope| scope.children.len());
+
+            self.add_expr(node, source_id, scope_id, None);
+
+            orders.push(order);
+            items.push(Item {
+                start: item_start(node),
+                symbols: Vec::new(),
+                scopes: self
+                    .scopes
+                    .get(scope_id)
+                    .map(|scope| scope.children[children..].to_vec())
+                    .unwrap_or_default(),
+            });
+        }
+
+        // Symbols of included modules are lowered at the same time but belong to their own source.
+        for (symbol_id, symbol) in &self.symbols {
+            if symbol.source.source_id == Some(source_id) && symbol.insertion_order >= first_order {
+                let index = orders.partition_point(|order| *order <= symbol.insertion_order) - 1;
+                items[index].symbols.push(symbol_id);
+            }
+        }
+
+        items
+    }
+
+    fn is_include_symbol(&self, symbol_id: SymbolId) -> bool {
+        self.symbols.get(symbol_id).is_some_and(|symbol| {
+            matches!(
+                symbol.kind,
+                SymbolKind::Include(_) | SymbolKind::Import(_) | SymbolKind::Module(_)
+            )
+        })
+    }
+
+    pub(crate) fn remove_scopes(&mut self, scope_ids: &[ScopeId]) {
+        for scope_id in scope_ids {
+            if let Some(scope) = self.scopes.remove(*scope_id) {
+                self.remove_scopes(&scope.children);
+            }
+        }
+    }
+
+    fn shift_scopes(&mut self, scope_ids: &[ScopeId], lines: i64) {
+        for scope_id in scope_ids {
+            if let Some(scope) = self.scopes.get_mut(*scope_id) {
+                if let Some(range) = scope.source.text_range.as_mut() {
+                    shift_range(range, lines);
+                }
+                let children = scope.children.clone();
+                self.shift_scopes(&children, lines);
+            }
+        }
+    }
+
+    /// Restores source order after items were lowered out of order: the module scope's children
+    /// follow the items, and insertion orders are renumbered item by item so that the
+    /// type-checker still sees definitions in the order they appear.
+    fn reorder_items(&mut self, source_id: SourceId, scope_id: ScopeId, items: &[Item]) {
+        if let Some(scope) = self.scopes.get_mut(scope_id) {
+            scope.children = items.iter().flat_map(|item| item.scopes.iter().copied()).collect();
+        }
+
+        for item in items {
+            let mut symbol_ids = item.symbols.clone();
+            symbol_ids.sort_by_key(|symbol_id| self.symbol_insertion_order(*symbol_id));
+            for symbol_id in symbol_ids {
+                if let Some(symbol) = self.symbols.get_mut(symbol_id) {
+                    symbol.insertion_order = self.symbol_insertion_counter;
+                    self.symbol_insertion_counter += 1;
+                }
+            }
+        }
+
+        if let Some(ids) = self.source_symbols.get_mut(&source_id) {
+            let symbols = &self.symbols;
+            ids.sort_by_key(|symbol_id| symbols.get(*symbol_id).map_or(0, |symbol| symbol.insertion_order));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TextEdit;
+    use mq_lang::{Position, Range};
+    use rstest::rstest;
+
+    type Snapshot = (Vec<String>, Vec<String>, Vec<String>);
+
+    /// Returns the symbols, resolved references, and scope nesting of a source in a form that
+    /// does not depend on how it was built.
+    fn snapshot(hir: &Hir, source_id: SourceId) -> Snapshot {
+        let describe = |symbol_id: SymbolId| {
+            let symbol = &hir.symbols[symbol_id];
+            format!("{:?} {:?} {:?}", symbol.value, symbol.kind, symbol.source.text_range)
+        };
+
+        let mut symbols = hir
+            .symbols()
+            .filter(|(_, symbol)| symbol.source.source_id == Some(source_id))
+            .map(|(symbol_id, symbol)| {
+                let parent = symbol.parent.map(describe);
+                format!("{} {:?} parent={:?}", describe(symbol_id), symbol.doc, parent)
+            })
+            .collect::<Vec<_>>();
+        symbols.sort();
+
+        let mut references = hir
+            .references
+            .iter()
+            .filter(|(ref_id, _)| hir.symbols[**ref_id].source.source_id == Some(source_id))
+            .map(|(ref_id, def_id)| format!("{} -> {}", describe(*ref_id), describe(*def_id)))
+            .collect::<Vec<_>>();
+        references.sort();
+
+        let mut scopes = hir
+            .scopes
+            .iter()
+            .filter(|(_, scope)| scope.source.source_id == Some(source_id))
+            .map(|(_, scope)| {
+                format!(
+                    "{:?} {:?} children={}",
+                    scope.symbol_id().map(describe),
+                    scope.source.text_range,
+                    scope.children.len()
+                )
+            })
+            .collect::<Vec<_>>();
+        scopes.sort();
+
+        (symbols, references, scopes)
+    }
+
+    fn position_of(code: &str, needle: &str) -> Position {
+        let offset = code.find(needle).unwrap();
+        position_at(code, offset)
+    }
+
+    #[rstest]
+    #[case::change_last_line("def f(x): x + 1;\n| f(1)", "f(1)", "f(2)")]
+    #[case::change_first_line("def f(x): x + 1;\n| f(1)", "x + 1", "x + 2")]
+    #[case::rename_definition("def f(x): x + 1;\n| let y = 2\n| f(y)", "def f", "def g")]
+    #[case::insert_lines("def f(x): x;\n| let y = 1\n| f(y)", "let y = 1", "let z = 3\n| let y = 1")]
+    #[case::remove_lines("def f(x): x;\n| let z = 3\n| let y = 1\n| f(y)", "let z = 3\n| ", "")]
+    #[case::change_comment("# doc\ndef f(x): x;\n# other\ndef g(): f(1);\n| g()", "# doc", "# changed doc")]
+    #[case::break_syntax("def f(x): x;\n| let y = 1\n| f(y)", "let y = 1", "let y = ")]
+    #[case::nested_scopes(
+        "def f(x):\n  let a = x\n  | if (a): a else: 0;\n| f(1)\n| foreach (i, [1, 2]): i;",
+        "f(1)",
+        "f(2) | f(3)"
+    )]
+    #[case::shadowing("let x = 1\n| let y = x\n| x + y", "let y = x", "let x = 2\n| let y = x")]
+    fn test_update_code_matches_full_analysis(#[case] code: &str, #[case] target: &str, #[case] replacement: &str) {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let start = position_of(code, target);
+        let end = position_at(code, code.find(target).unwrap() + target.len());
+        let new_code = code.replacen(target, replacement, 1);
+
+        let mut hir = Hir::default();
+        hir.add_code(Some(url.clone()), code);
+        let (source_id, _) = hir.update_code(&url, &[TextEdit::new(Range { start, end }, replacement)]);
+
+        let mut expected = Hir::default();
+        let (expected_source_id, _) = expected.add_code(Some(url.clone()), &new_code);
+
+        assert_eq!(hir.source_code[&source_id], new_code);
+        assert_eq!(snapshot(&hir, source_id), snapshot(&expected, expected_source_id));
+        assert_eq!(hir.errors().len(), expected.errors().len());
+    }
+
+    #[test]
+    fn test_update_code_keeps_unaffected_symbols() {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let code = "def f(x): x;\n| let y = 1\n| f(y)";
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+        hir.add_code(Some(url.clone()), code);
+
+        let def_id = hir
+            .symbols()
+            .find(|(_, symbol)| matches!(symbol.kind, SymbolKind::Function(_)) && symbol.value.as_deref() == Some("f"))
+            .map(|(symbol_id, _)| symbol_id)
+            .unwrap();
+
+        let start = position_of(code, "1");
+        hir.update_code(&url, &[TextEdit::new(Range { start, end: start }, "4")]);
+
+        assert!(hir.symbols.contains_key(def_id));
+        let call_id = hir
+            .symbols()
+            .find(|(_, symbol)| symbol.kind == SymbolKind::Call && symbol.value.as_deref() == Some("f"))
+            .map(|(symbol_id, _)| symbol_id)
+            .unwrap();
+        assert_eq!(hir.resolve_reference_symbol(call_id), Some(def_id));
+    }
+
+    #[test]
+    fn test_update_code_without_previous_text() {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+
+        let (source_id, _) = hir.update_code(&url, &[TextEdit::full("let x = 1 | x")]);
+        assert_eq!(hir.source_by_url(&url), Some(source_id));
+        assert!(hir.errors().is_empty());
+
+        let (nodes, _) = mq_lang::parse_recovery("let y = 1 | y");
+        hir.add_nodes(url.clone(), &nodes);
+        hir.update_code(&url, &[TextEdit::full("let z = 1 | z")]);
+        assert!(hir.symbols().any(|(_, symbol)| symbol.value.as_deref() == Some("z")));
+        assert!(!hir.symbols().any(|(_, symbol)| symbol.value.as_deref() == Some("y")));
+    }
+
+    #[test]
+    fn test_update_code_sequential_edits() {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let mut code = "def f(x): x;".to_string();
+        let mut hir = Hir::default();
+        hir.builtin.disabled = true;
+        hir.add_code(Some(url.clone()), &code);
+
+        for line in ["| let a = f(1)", "| let b = a", "| f(b)"] {
+            let end = position_at(&code, code.len());
+            let text = format!("\n{}", line);
+            code.push_str(&text);
+            hir.update_code(&url, &[TextEdit::new(Range { start: end, end }, text)]);
+        }
+
+        let mut expected = Hir::default();
+        expected.builtin.disabled = true;
+        let (expected_source_id, _) = expected.add_code(Some(url.clone()), &code);
+        let source_id = hir.source_by_url(&url).unwrap();
+        assert_eq!(snapshot(&hir, source_id), snapshot(&expected, expected_source_id));
+        assert!(hir.errors().is_empty());
+    }
+}
@@ -464,6 +464,28 @@ impl Hir {
         }
     }
 
+    /// Returns the URL a module is analyzed under.
+    ///
+    /// Modules found on disk use their file URL, so that a module included from several sources,
+    /// or also opened on its own, is analyzed once and its definitions are shared by all of them.
+    fn module_url(&self, module_name: &str) -> Option<Url> {
+        let module_path = self
+            .module_loader
+            .get_module_path(module_name)
+            .unwrap_or(module_name.to_string());
+
+        // File URLs can't be built from paths on targets without a filesystem, such as wasm.
+        #[cfg(any(unix, windows))]
+        let file_url = std::path::absolute(&module_path)
+            .ok()
+            .filter(|path| path.is_file())
+            .and_then(|path| Url::from_file_path(path).ok());
+        #[cfg(not(any(unix, windows)))]
+        let file_url = None;
+
+        file_url.or_else(|| Url::parse(&format!("file:///{}", module_path)).ok())
+    }
+
     fn add_include_expr(
         &mut self,
         node: &mq_lang::Shared<mq_lang::CstNode>,
@@ -478,9 +500,7 @@ impl Hir {
         {
             let _ = node.children_without_token().first().map(|child| {
                 let module_name = child.name().unwrap();
-                let module_path = self.module_loader.get_module_path(&module_name);
-
-                if let Ok(url) = Url::parse(&format!("file:///{}", module_path.unwrap_or(module_name.to_string()))) {
+                if let Some(url) = self.module_url(&module_name) {
                     let code = self.module_loader.resolve(&module_name);
                     let (module_source_id, _) = self.add_code(Some(url), &code.unwrap_or_default());
 
@@ -514,9 +534,7 @@ impl Hir {
 
             let _ = children.first().map(|child| {
                 let module_name = child.name().unwrap();
-                let module_path = self.module_loader.get_module_path(&module_name);
-
-                if let Ok(url) = Url::parse(&format!("file:///{}", module_path.unwrap_or(module_name.to_string()))) {
+                if let Some(url) = self.module_url(&module_name) {
                     let code = self.module_loader.resolve(&module_name);
                     let (module_source_id, _) = self.add_code(Some(url), &code.unwrap_or_default());
 
@@ -28,7 +28,11 @@
 //! }
 //! ```
 mod builtin;
+mod call_graph;
+mod doc_comment;
+mod edit;
 mod error;
+mod export;
 mod find;
 mod hir;
 mod reference;
@@ -37,6 +41,9 @@ mod scope;
 mod source;
 mod symbol;
 
+pub use call_graph::{CallEdge, CallGraph};
+pub use doc_comment::{Deprecation, DocComment, DocParam};
+pub use edit::TextEdit;
 pub use error::{HirError, HirWarning};
 pub use hir::Hir;
 pub use scope::{Scope, ScopeId, ScopeKind};
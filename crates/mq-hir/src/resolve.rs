@@ -1,33 +1,60 @@
+use rustc_hash::FxHashMap;
 use smol_str::SmolStr;
 
-use crate::{Hir, ScopeId, SourceId, Symbol, SymbolId, SymbolKind};
+use crate::{
+    Hir, ScopeId, SourceId, Symbol, SymbolId, SymbolKind,
+    export::{ExportLists, is_exported_name, is_top_level_definition},
+};
 
 impl Hir {
     pub fn resolve(&mut self) {
+        let symbol_ids = self.symbols.keys().collect::<Vec<_>>();
+        self.resolve_symbols(symbol_ids);
+    }
+
+    /// Resolves the given reference symbols, dropping the references of those that no longer resolve.
+    pub(crate) fn resolve_symbols(&mut self, symbol_ids: impl IntoIterator<Item = SymbolId>) {
         // Extract only the fields we need instead of cloning the entire Symbol
-        let symbols_to_resolve: Vec<_> = self
-            .symbols
-            .iter()
-            .filter_map(|(ref_symbol_id, ref_symbol)| match &ref_symbol.kind {
-                SymbolKind::Ref
-                | SymbolKind::Call
-                | SymbolKind::CallDynamic
-                | SymbolKind::Argument
-                | SymbolKind::Macro(_)
-                | SymbolKind::QualifiedAccess => Some((ref_symbol_id, ref_symbol.scope, ref_symbol.value.clone())),
-                _ => None,
+        let symbols_to_resolve: Vec<_> = symbol_ids
+            .into_iter()
+            .filter_map(|ref_symbol_id| {
+                let ref_symbol = self.symbols.get(ref_symbol_id)?;
+                match &ref_symbol.kind {
+                    SymbolKind::Ref
+                    | SymbolKind::Call
+                    | SymbolKind::CallDynamic
+                    | SymbolKind::Argument
+                    | SymbolKind::Macro(_)
+                    | SymbolKind::QualifiedAccess => Some((
+                        ref_symbol_id,
+                        ref_symbol.scope,
+                        ref_symbol.source.source_id,
+                        ref_symbol.value.clone(),
+                    )),
+                    _ => None,
+                }
             })
             .collect();
+        let included_sources = self.included_sources();
+        let export_lists = self.export_lists();
+        let mut visible_sources: FxHashMap<Option<SourceId>, Vec<SourceId>> = FxHashMap::default();
 
-        for (ref_symbol_id, scope, ref_name) in symbols_to_resolve {
-            if let Some(ref_name) = ref_name {
-                let resolved = self
-                    .resolve_ref_symbol_of_scope(scope, &ref_name, ref_symbol_id)
-                    .or_else(|| self.resolve_ref_symbol_of_source(self.include_source_ids(), &ref_name));
+        for (ref_symbol_id, scope, source_id, ref_name) in symbols_to_resolve {
+            let source_ids = visible_sources
+                .entry(source_id)
+                .or_insert_with(|| self.visible_source_ids(source_id, &included_sources));
+            let resolved = ref_name.and_then(|ref_name| {
+                self.resolve_ref_symbol_of_scope(scope, &ref_name, ref_symbol_id)
+                    .or_else(|| self.resolve_ref_symbol_of_source(source_ids, &ref_name, source_id, &export_lists))
+            });
 
-                if let Some((symbol_id, _)) = resolved {
+            match resolved {
+                Some((symbol_id, _)) => {
                     self.references.insert(ref_symbol_id, symbol_id);
                 }
+                None => {
+                    self.references.remove(&ref_symbol_id);
+                }
             }
         }
     }
@@ -37,19 +64,49 @@ impl Hir {
         self.references.get(&ref_symbol_id).copied()
     }
 
-    #[inline(always)]
-    fn include_source_ids(&self) -> Vec<SourceId> {
-        let mut source_ids = Vec::new();
+    /// Returns the sources each source brings into view with `include`, `import`, or `module`.
+    fn included_sources(&self) -> FxHashMap<SourceId, Vec<SourceId>> {
+        let mut included_sources: FxHashMap<SourceId, Vec<SourceId>> = FxHashMap::default();
 
         for (_, symbol) in &self.symbols {
-            match symbol.kind {
-                SymbolKind::Include(source_id) | SymbolKind::Import(source_id) | SymbolKind::Module(source_id) => {
-                    source_ids.push(source_id);
-                }
-                _ => {}
+            if let (
+                SymbolKind::Include(included) | SymbolKind::Import(included) | SymbolKind::Module(included),
+                Some(source_id),
+            ) = (&symbol.kind, symbol.source.source_id)
+            {
+                included_sources.entry(source_id).or_default().push(*included);
             }
         }
 
+        included_sources
+    }
+
+    /// Returns the sources whose top-level definitions a reference in `source_id` can resolve to:
+    /// the modules it includes, directly or through other included modules, and the builtins.
+    ///
+    /// A reference without a source sees every included module.
+    fn visible_source_ids(
+        &self,
+        source_id: Option<SourceId>,
+        included_sources: &FxHashMap<SourceId, Vec<SourceId>>,
+    ) -> Vec<SourceId> {
+        let mut source_ids = match source_id {
+            Some(source_id) => {
+                let mut visible = Vec::new();
+                let mut pending = included_sources.get(&source_id).cloned().unwrap_or_default();
+
+                while let Some(included) = pending.pop() {
+                    if !visible.contains(&included) {
+                        visible.push(included);
+                        pending.extend(included_sources.get(&included).into_iter().flatten().copied());
+                    }
+                }
+
+                visible
+            }
+            None => included_sources.values().flatten().copied().collect(),
+        };
+
         source_ids.push(self.builtin.source_id);
 
         source_ids
@@ -71,8 +128,10 @@ impl Hir {
 
     fn resolve_ref_symbol_of_source(
         &self,
-        source_ids: Vec<SourceId>,
+        source_ids: &[SourceId],
         ref_name: &SmolStr,
+        ref_source_id: Option<SourceId>,
+        export_lists: &ExportLists,
     ) -> Option<(SymbolId, Symbol)> {
         let mut candidates = Vec::new();
 
@@ -91,8 +150,13 @@ impl Hir {
                     || symbol.is_macro()
                     || symbol.is_ident())
             {
+                // Prefer what a module exports over its private definitions of the same name.
+                let private = Some(source_id) != ref_source_id
+                    && source_id != self.builtin.source_id
+                    && is_top_level_definition(self, symbol)
+                    && !is_exported_name(export_lists, symbol);
                 let priority = self.get_symbol_priority_for_cross_source(&symbol.kind);
-                candidates.push((priority, symbol_id, symbol.clone()));
+                candidates.push(((private, priority), symbol_id, symbol.clone()));
             }
         }
 
@@ -1,5 +1,6 @@
 use std::fmt;
 
+use crate::doc_comment::DocComment;
 use crate::source::SourceInfo;
 use crate::{SourceId, scope::ScopeId};
 use itertools::Itertools;
@@ -170,6 +171,11 @@ impl Symbol {
         }
     }
 
+    /// Returns the documentation of the symbol, parsed from its doc comments.
+    pub fn doc_comment(&self) -> DocComment {
+        DocComment::parse(self.doc.iter().map(|(_, text)| text.as_str()))
+    }
+
     /// Check if documentation contains deprecated marker
     #[inline(always)]
     pub fn is_deprecated(&self) -> bool {
@@ -4,6 +4,7 @@ use crate::{Shared, Token, arena::ArenaId};
 
 pub mod code;
 pub mod constants;
+pub mod dump;
 pub mod node;
 pub mod parser;
 
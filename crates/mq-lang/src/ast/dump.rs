@@ -0,0 +1,257 @@
+use crate::{Program, Shared, Token, arena::Arena, range::Range};
+
+use super::node::{AccessTarget, Expr, Literal, Node, Params, Pattern, StringSegment};
+use std::fmt::Write;
+
+/// Renders a program as an indented tree, one AST node per line, annotated with source spans.
+///
+/// Each line has the form `<kind> [<detail>] @<start_line>:<start_col>-<end_line>:<end_col>`.
+pub fn dump(program: &Program, arena: &Arena<Shared<Token>>) -> String {
+    let mut buf = String::new();
+    for node in program {
+        node.dump_to(&mut buf, arena, 0);
+    }
+    buf
+}
+
+impl Node {
+    fn dump_to(&self, buf: &mut String, arena: &Arena<Shared<Token>>, depth: usize) {
+        let range = arena.get(self.token_id).map(|token| token.range).unwrap_or_default();
+        buf.push_str(&"  ".repeat(depth));
+        buf.push_str(&self.dump_label());
+        writeln!(buf, " @{}", format_range(&range)).unwrap();
+
+        let depth = depth + 1;
+        match &*self.expr {
+            Expr::Literal(_)
+            | Expr::Ident(_)
+            | Expr::Selector(_)
+            | Expr::SelectorChain(_)
+            | Expr::Include(_)
+            | Expr::Import(_, _)
+            | Expr::Self_
+            | Expr::Nodes
+            | Expr::Break(None)
+            | Expr::Continue => {}
+            Expr::As(_, node)
+            | Expr::Let(_, node)
+            | Expr::Var(_, node)
+            | Expr::Assign(_, node)
+            | Expr::Paren(node)
+            | Expr::Quote(node)
+            | Expr::Unquote(node)
+            | Expr::Macro(_, _, node)
+            | Expr::Break(Some(node)) => node.dump_to(buf, arena, depth),
+            Expr::Block(program) | Expr::Loop(program) | Expr::Module(_, program) => {
+                dump_program(program, buf, arena, depth)
+            }
+            Expr::Def(_, params, program) | Expr::Fn(params, program) => {
+                dump_param_defaults(params, buf, arena, depth);
+                dump_program(program, buf, arena, depth);
+            }
+            Expr::Call(_, args) | Expr::SelectorCall(_, args) => dump_program(args, buf, arena, depth),
+            Expr::CallDynamic(callable, args) => {
+                callable.dump_to(buf, arena, depth);
+                dump_program(args, buf, arena, depth);
+            }
+            Expr::And(operands) | Expr::Or(operands) => dump_program(operands, buf, arena, depth),
+            Expr::InterpolatedString(segments) => {
+                for segment in segments {
+                    if let StringSegment::Expr(node) = segment {
+                        node.dump_to(buf, arena, depth);
+                    }
+                }
+            }
+            Expr::While(cond, program) | Expr::Foreach(_, cond, program) => {
+                cond.dump_to(buf, arena, depth);
+                dump_program(program, buf, arena, depth);
+            }
+            Expr::If(branches) => {
+                for (cond, body) in branches {
+                    if let Some(cond) = cond {
+                        cond.dump_to(buf, arena, depth);
+                    }
+                    body.dump_to(buf, arena, depth);
+                }
+            }
+            Expr::Match(value, arms) => {
+                value.dump_to(buf, arena, depth);
+                for arm in arms {
+                    buf.push_str(&"  ".repeat(depth));
+                    let mut pattern = String::new();
+                    format_pattern(&arm.pattern, &mut pattern);
+                    writeln!(buf, "Arm {}", pattern).unwrap();
+                    if let Some(guard) = &arm.guard {
+                        guard.dump_to(buf, arena, depth + 1);
+                    }
+                    arm.body.dump_to(buf, arena, depth + 1);
+                }
+            }
+            Expr::QualifiedAccess(_, AccessTarget::Call(_, args)) => dump_program(args, buf, arena, depth),
+            Expr::QualifiedAccess(_, AccessTarget::Ident(_)) => {}
+            Expr::Try(try_expr, _, catch_expr) => {
+                try_expr.dump_to(buf, arena, depth);
+                catch_expr.dump_to(buf, arena, depth);
+            }
+        }
+    }
+
+    fn dump_label(&self) -> String {
+        match &*self.expr {
+            Expr::Literal(literal) => format!("Literal {}", format_literal(literal)),
+            Expr::Ident(ident) => format!("Ident {}", ident),
+            Expr::Self_ => "Self".to_string(),
+            Expr::Nodes => "Nodes".to_string(),
+            Expr::Selector(selector) => format!("Selector {}", selector),
+            Expr::SelectorChain(selectors) => format!(
+                "SelectorChain {}",
+                selectors.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(" | ")
+            ),
+            Expr::SelectorCall(selector, _) => format!("SelectorCall {}", selector),
+            Expr::Break(_) => "Break".to_string(),
+            Expr::Continue => "Continue".to_string(),
+            Expr::Paren(_) => "Paren".to_string(),
+            Expr::And(_) => "And".to_string(),
+            Expr::Or(_) => "Or".to_string(),
+            Expr::Call(ident, args) => format!("Call {}/{}", ident, args.len()),
+            Expr::CallDynamic(_, args) => format!("CallDynamic /{}", args.len()),
+            Expr::As(ident, _) => format!("As {}", ident),
+            Expr::Let(pattern, _) => {
+                let mut buf = String::from("Let ");
+                format_pattern(pattern, &mut buf);
+                buf
+            }
+            Expr::Var(pattern, _) => {
+                let mut buf = String::from("Var ");
+                format_pattern(pattern, &mut buf);
+                buf
+            }
+            Expr::Assign(ident, _) => format!("Assign {}", ident),
+            Expr::If(branches) => format!("If branches={}", branches.len()),
+            Expr::While(_, _) => "While".to_string(),
+            Expr::Loop(_) => "Loop".to_string(),
+            Expr::Foreach(item, _, _) => format!("Foreach {}", item),
+            Expr::Block(_) => "Block".to_string(),
+            Expr::Def(ident, params, _) => format!("Def {}({})", ident, format_params(params)),
+            Expr::Fn(params, _) => format!("Fn({})", format_params(params)),
+            Expr::Macro(ident, params, _) => format!("Macro {}({})", ident, format_params(params)),
+            Expr::Match(_, arms) => format!("Match arms={}", arms.len()),
+            Expr::InterpolatedString(_) => "InterpolatedString".to_string(),
+            Expr::Quote(_) => "Quote".to_string(),
+            Expr::Unquote(_) => "Unquote".to_string(),
+            Expr::Try(_, binder, _) => match binder {
+                Some(binder) => format!("Try catch({})", binder),
+                None => "Try".to_string(),
+            },
+            Expr::Module(ident, _) => format!("Module {}", ident),
+            Expr::QualifiedAccess(path, target) => {
+                let path = path.iter().map(|p| p.to_string()).collect::<Vec<_>>().join("::");
+                match target {
+                    AccessTarget::Call(ident, args) => format!("QualifiedCall {}::{}/{}", path, ident, args.len()),
+                    AccessTarget::Ident(ident) => format!("QualifiedIdent {}::{}", path, ident),
+                }
+            }
+            Expr::Include(path) => format!("Include {}", format_literal(path)),
+            Expr::Import(path, alias) => match alias {
+                Some(alias) => format!("Import {} as {}", format_literal(path), alias),
+                None => format!("Import {}", format_literal(path)),
+            },
+        }
+    }
+}
+
+fn dump_program(program: &[Shared<Node>], buf: &mut String, arena: &Arena<Shared<Token>>, depth: usize) {
+    for node in program {
+        node.dump_to(buf, arena, depth);
+    }
+}
+
+fn dump_param_defaults(params: &Params, buf: &mut String, arena: &Arena<Shared<Token>>, depth: usize) {
+    for default in params.iter().filter_map(|param| param.default.as_ref()) {
+        default.dump_to(buf, arena, depth);
+    }
+}
+
+fn format_range(range: &Range) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        range.start.line, range.start.column, range.end.line, range.end.column
+    )
+}
+
+fn format_literal(literal: &Literal) -> String {
+    match literal {
+        Literal::String(s) => format!("{:?}", s),
+        Literal::Symbol(ident) => format!(":{}", ident),
+        _ => literal.to_string(),
+    }
+}
+
+fn format_params(params: &Params) -> String {
+    params.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn format_pattern(pattern: &Pattern, buf: &mut String) {
+    match pattern {
+        Pattern::Literal(literal) => buf.push_str(&format_literal(literal)),
+        Pattern::Ident(ident) => write!(buf, "{}", ident).unwrap(),
+        Pattern::Wildcard => buf.push('_'),
+        Pattern::Array(patterns) => {
+            buf.push('[');
+            for (i, p) in patterns.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                format_pattern(p, buf);
+            }
+            buf.push(']');
+        }
+        Pattern::ArrayRest(patterns, rest) => {
+            buf.push('[');
+            for p in patterns {
+                format_pattern(p, buf);
+                buf.push_str(", ");
+            }
+            write!(buf, "..{}]", rest).unwrap();
+        }
+        Pattern::Dict(entries) => {
+            buf.push('{');
+            for (i, (key, p)) in entries.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(", ");
+                }
+                write!(buf, "{}: ", key).unwrap();
+                format_pattern(p, buf);
+            }
+            buf.push('}');
+        }
+        Pattern::Type(ident) => write!(buf, ":{}", ident).unwrap(),
+        Pattern::Or(patterns) => {
+            for (i, p) in patterns.iter().enumerate() {
+                if i > 0 {
+                    buf.push_str(" || ");
+                }
+                format_pattern(p, buf);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Shared, SharedCell, arena::Arena};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::selector(".h", "Selector .h @1:1-1:3\n")]
+    #[case::call("add(1, 2)", "Call add/2 @1:1-1:4\n  Literal 1 @1:5-1:6\n  Literal 2 @1:8-1:9\n")]
+    #[case::pipeline(
+        "let x = \"a\" | x",
+        "Let x @1:1-1:4\n  Literal \"a\" @1:9-1:12\nIdent x @1:15-1:16\n"
+    )]
+    fn test_dump(#[case] code: &str, #[case] expected: &str) {
+        let token_arena = Shared::new(SharedCell::new(Arena::new(10)));
+        let program = crate::parse(code, Shared::clone(&token_arena)).unwrap();
+        assert_eq!(crate::dump_ast(&program, token_arena), expected);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod error;
+pub mod highlight;
 pub mod incremental;
 pub mod node;
 pub mod parser;
@@ -0,0 +1,165 @@
+#[cfg(feature = "ast-json")]
+use serde::{Deserialize, Serialize};
+
+use crate::{Range, Shared, Token, TokenKind};
+
+use super::node::{Node, NodeKind, Trivia};
+
+/// Coarse lexical category of a token, used for syntax highlighting.
+#[cfg_attr(
+    feature = "ast-json",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Comment,
+    Keyword,
+    Operator,
+    Punctuation,
+    Function,
+    Module,
+    Property,
+    String,
+    Number,
+    Boolean,
+    Builtin,
+    Variable,
+}
+
+/// A classified token of a CST.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HighlightToken {
+    /// Range of the token. For comments it includes the `#` delimiter.
+    pub range: Range,
+    pub class: TokenClass,
+    pub token: Shared<Token>,
+}
+
+/// Classifies the tokens of `nodes` and their comments, in the order the CST is walked.
+///
+/// Whitespace and end-of-file tokens are skipped.
+pub fn highlight_tokens(nodes: &[Shared<Node>]) -> Vec<HighlightToken> {
+    let mut tokens = Vec::new();
+    collect_tokens(nodes, None, &mut tokens);
+    tokens
+}
+
+fn collect_tokens(nodes: &[Shared<Node>], parent: Option<&NodeKind>, out: &mut Vec<HighlightToken>) {
+    for (index, node) in nodes.iter().enumerate() {
+        for trivia in node.leading_trivia.iter().chain(node.trailing_trivia.iter()) {
+            if let Trivia::Comment(token) = trivia {
+                let mut range = token.range;
+                // The lexer's comment token starts after the `#` delimiter.
+                range.start.column = range.start.column.saturating_sub(1);
+                out.push(HighlightToken {
+                    range,
+                    class: TokenClass::Comment,
+                    token: Shared::clone(token),
+                });
+            }
+        }
+
+        if let Some(token) = &node.token
+            && let Some(class) = classify_token(&token.kind, &node.kind, parent, index)
+        {
+            out.push(HighlightToken {
+                range: token.range,
+                class,
+                token: Shared::clone(token),
+            });
+        }
+
+        collect_tokens(&node.children, Some(&node.kind), out);
+    }
+}
+
+/// The CST reuses the identifier's own token for `Call`/`MacroCall`/`QualifiedAccess`, while
+/// `def`/`import`/dict-key names are child `Ident` nodes of a keyword-bearing parent, so
+/// classification needs both the node's own kind and its parent's.
+fn classify_ident(node_kind: &NodeKind, parent: Option<&NodeKind>, index_in_parent: usize) -> TokenClass {
+    match node_kind {
+        NodeKind::Call | NodeKind::CallDynamic | NodeKind::MacroCall => TokenClass::Function,
+        NodeKind::QualifiedAccess => TokenClass::Module,
+        _ => match parent {
+            Some(NodeKind::Def) | Some(NodeKind::Macro) if index_in_parent == 0 => TokenClass::Function,
+            Some(NodeKind::Import) | Some(NodeKind::Include) | Some(NodeKind::Module) => TokenClass::Module,
+            Some(NodeKind::DictEntry) if index_in_parent == 0 => TokenClass::Property,
+            Some(NodeKind::QualifiedAccess) => TokenClass::Function,
+            _ => TokenClass::Variable,
+        },
+    }
+}
+
+fn classify_token(
+    token_kind: &TokenKind,
+    node_kind: &NodeKind,
+    parent: Option<&NodeKind>,
+    index_in_parent: usize,
+) -> Option<TokenClass> {
+    use TokenKind::*;
+
+    Some(match token_kind {
+        Def | Let | If | Elif | Else | End | While | Loop | Foreach | Include | Import | Module | Match | Fn | Do
+        | Var | Macro | Try | Catch | As | Break | Continue | Quote | Unquote => TokenClass::Keyword,
+        Self_ | Nodes | None => TokenClass::Builtin,
+        BoolLiteral(_) => TokenClass::Boolean,
+        NumberLiteral(_) => TokenClass::Number,
+        StringLiteral(_) | InterpolatedString(_) | BytesLiteral(_) | Env(_) | Selector(_) => TokenClass::String,
+        Comment(_) => TokenClass::Comment,
+        Ident(_) => classify_ident(node_kind, parent, index_in_parent),
+        And | Or | Not | Coalesce | Plus | Minus | Asterisk | Slash | Percent | Equal | EqEq | NeEq | Lt | Lte | Gt
+        | Gte | Arrow | Pipe | TildeEqual | NotTildeEqual | LeftShift | RightShift | Convert | DoubleDot
+        | DotDotDot | PlusEqual | MinusEqual | StarEqual | SlashEqual | PercentEqual | DoubleSlashEqual | PipeEqual => {
+            TokenClass::Operator
+        }
+        LParen | RParen | LBrace | RBrace | LBracket | RBracket | Colon | DoubleColon | SemiColon | Comma
+        | Question => TokenClass::Punctuation,
+        Whitespace(_) | Tab(_) | NewLine | Eof => return Option::None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("# c\n1", vec![TokenClass::Comment, TokenClass::Number])]
+    #[case("def foo(x): x + 1;", vec![
+        TokenClass::Keyword,
+        TokenClass::Function,
+        TokenClass::Punctuation,
+        TokenClass::Variable,
+        TokenClass::Punctuation,
+        TokenClass::Punctuation,
+        TokenClass::Variable,
+        TokenClass::Operator,
+        TokenClass::Number,
+        TokenClass::Punctuation,
+    ])]
+    #[case("import \"foo\"", vec![TokenClass::Keyword, TokenClass::String])]
+    #[case("{a: true}", vec![
+        TokenClass::Punctuation,
+        TokenClass::Property,
+        TokenClass::Punctuation,
+        TokenClass::Boolean,
+        TokenClass::Punctuation,
+    ])]
+    fn test_highlight_tokens(#[case] code: &str, #[case] expected: Vec<TokenClass>) {
+        let (nodes, _) = crate::parse_recovery(code);
+        let mut tokens = highlight_tokens(&nodes);
+        tokens.sort_by_key(|token| token.range.start);
+        assert_eq!(tokens.iter().map(|token| token.class).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_highlight_tokens_comment_includes_delimiter() {
+        let (nodes, _) = crate::parse_recovery("# c\n1");
+        let comment = highlight_tokens(&nodes)
+            .into_iter()
+            .find(|token| token.class == TokenClass::Comment)
+            .unwrap();
+        assert_eq!(comment.range.start.column, 1);
+    }
+}
@@ -93,6 +93,19 @@ impl ErrorReporter {
         !self.errors.is_empty()
     }
 
+    /// Returns `true` if there are errors and every one of them is caused by the input ending
+    /// early, i.e. the code could still become valid by appending more text.
+    pub fn is_incomplete(&self) -> bool {
+        self.has_errors()
+            && self.errors.iter().all(|error| match error {
+                ParseError::UnexpectedEOFDetected => true,
+                ParseError::UnexpectedToken(token)
+                | ParseError::InsufficientTokens(token)
+                | ParseError::ExpectedClosingBracket(token) => token.is_eof(),
+                ParseError::UnknownSelector(_) | ParseError::UnmatchedEnd(_) => false,
+            })
+    }
+
     pub fn error_ranges(&self, text: &str) -> Vec<(String, Range)> {
         self.to_vec()
             .iter()
@@ -11416,6 +11429,21 @@ mod tests {
         assert!(reporter.has_errors());
     }
 
+    #[rstest]
+    #[case::open_def("def f(x):", true)]
+    #[case::missing_end("def f(x):\n  x + 1", true)]
+    #[case::open_call("add(1,", true)]
+    #[case::open_array("[1, 2", true)]
+    #[case::open_string("let x = \"abc", true)]
+    #[case::trailing_pipe("let x = 1 |", true)]
+    #[case::complete("add(1, 2)", false)]
+    #[case::invalid_operator("1 +* 2", false)]
+    #[case::extra_paren("foo)", false)]
+    fn test_error_reporter_is_incomplete(#[case] code: &str, #[case] expected: bool) {
+        let (_, errors) = crate::parse_recovery(code);
+        assert_eq!(errors.is_incomplete(), expected);
+    }
+
     #[test]
     fn test_error_reporter_max_errors() {
         let mut reporter = ErrorReporter::new(2);
@@ -8,7 +8,7 @@ use crate::eval::env::Env;
 #[cfg(feature = "debugger")]
 use crate::module::ModuleId;
 use crate::{
-    ArenaId, ModuleResolver, MqResult, Range, RuntimeValue, Shared, SharedCell, TokenKind,
+    ArenaId, ModuleResolver, MqResult, ProfileEntry, Range, RuntimeValue, Shared, SharedCell, TokenKind,
     module::resolver::DefaultModuleResolver, token_alloc,
 };
 #[cfg(feature = "debugger")]
@@ -18,7 +18,7 @@ use crate::{
     ModuleLoader, Token,
     arena::Arena,
     error::{self},
-    eval::Evaluator,
+    eval::{Evaluator, HostFunction},
     optimizer::{OptimizationLevel, Optimizer},
     parse,
 };
@@ -128,6 +128,16 @@ impl<T: ModuleResolver> Engine<T> {
         self.evaluator.options.timeout = Some(timeout);
     }
 
+    /// Remove the timeout set by [`Engine::set_timeout`], so evaluation may run indefinitely.
+    pub fn clear_timeout(&mut self) {
+        self.evaluator.options.timeout = None;
+    }
+
+    /// Returns the timeout set by [`Engine::set_timeout`], or `None` if evaluation is unbounded.
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.evaluator.options.timeout
+    }
+
     /// Enables or disables the `http` builtin for the current process.
     ///
     /// Disabled by default. This is a process-wide setting (see
@@ -152,6 +162,21 @@ impl<T: ModuleResolver> Engine<T> {
         capability::set_allow_write(allow);
     }
 
+    /// Returns whether the `http` builtin is enabled for the current process.
+    pub fn allow_net(&self) -> bool {
+        capability::is_net_allowed()
+    }
+
+    /// Returns whether the `read_file`/`read_file_bytes` builtins are enabled for the current process.
+    pub fn allow_read(&self) -> bool {
+        capability::is_read_allowed()
+    }
+
+    /// Returns whether the `write_file` builtin is enabled for the current process.
+    pub fn allow_write(&self) -> bool {
+        capability::is_write_allowed()
+    }
+
     /// Set search paths for module loading.
     ///
     /// These paths will be searched when loading external modules
@@ -173,6 +198,33 @@ impl<T: ModuleResolver> Engine<T> {
         self.evaluator.define_value(name, value);
     }
 
+    /// Register a function implemented by the host application, callable from mq code as
+    /// `name(args...)`.
+    ///
+    /// Definitions in mq code take precedence over host functions, and host functions over
+    /// builtins. As with builtins, when the call has one argument fewer than `num_params`, the
+    /// current value is passed as the first argument, so `"x" | lookup()` calls `lookup("x")`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut engine = mq_lang::DefaultEngine::default();
+    /// engine.register_function(
+    ///     "double",
+    ///     1,
+    ///     mq_lang::Shared::new(|args: &[mq_lang::RuntimeValue]| match args {
+    ///         [mq_lang::RuntimeValue::Number(n)] => Ok(mq_lang::RuntimeValue::Number(*n * 2.into())),
+    ///         _ => Err("double expects a number".to_string()),
+    ///     }),
+    /// );
+    ///
+    /// let result = engine.eval("double(21)", mq_lang::null_input().into_iter()).unwrap();
+    /// assert_eq!(result.values()[0], mq_lang::RuntimeValue::Number(42.into()));
+    /// ```
+    pub fn register_function(&mut self, name: &str, num_params: u8, func: Shared<HostFunction>) {
+        self.evaluator.register_host_function(name, num_params, func);
+    }
+
     /// Load the built-in function modules.
     ///
     /// This must be called to enable access to standard functions
@@ -260,6 +312,101 @@ impl<T: ModuleResolver> Engine<T> {
             .map_err(|e| Box::new(error::Error::from_error(code, e, self.evaluator.module_loader.clone())))
     }
 
+    /// Evaluates mq code against a lazily consumed input, yielding results as each input value is
+    /// processed instead of collecting them all first.
+    ///
+    /// Any iterator works as input, including a channel receiver fed while the stream is being
+    /// consumed. The timeout applies to each input value separately, and the stream ends after the
+    /// first error. A query containing `nodes` needs every result at once, so it still buffers
+    /// the whole input before yielding anything.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut engine = mq_lang::DefaultEngine::default();
+    /// engine.load_builtin_module();
+    ///
+    /// let input = ["a", "b"].into_iter().map(|s| s.to_string().into());
+    /// let mut results = engine.eval_stream("upcase()", input).unwrap();
+    /// assert_eq!(results.next().unwrap().unwrap(), "A".to_string().into());
+    /// assert_eq!(results.next().unwrap().unwrap(), "B".to_string().into());
+    /// assert!(results.next().is_none());
+    /// ```
+    pub fn eval_stream<I: Iterator<Item = RuntimeValue>>(
+        &mut self,
+        code: &str,
+        input: I,
+    ) -> Result<EvalStream<'_, T, I>, Box<error::Error>> {
+        let compiled = self.compile(code)?;
+
+        #[cfg(feature = "debugger")]
+        self.evaluator.module_loader.set_source_code(compiled.source.clone());
+
+        let state = if compiled.program.is_empty() {
+            StreamState::Done
+        } else {
+            match self.evaluator.prepare(&compiled.program) {
+                Ok((program, nodes_program)) if nodes_program.is_empty() => StreamState::Streaming(program),
+                Ok((program, nodes_program)) => StreamState::Pending(program, nodes_program),
+                Err(e) => {
+                    return Err(Box::new(error::Error::from_error(
+                        code,
+                        e,
+                        self.evaluator.module_loader.clone(),
+                    )));
+                }
+            }
+        };
+
+        Ok(EvalStream {
+            engine: self,
+            source: compiled.source,
+            input,
+            state,
+        })
+    }
+
+    /// Evaluates mq code against each input value in parallel on the rayon thread pool.
+    ///
+    /// Each input value flows through the program independently, so the results are the same as
+    /// with [`Engine::eval`] and are returned in input order. Top-level bindings are scoped to
+    /// the input that made them. The input is collected up front, and profiling and the
+    /// debugger only observe the preparation, not the per-input evaluation.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut engine = mq_lang::DefaultEngine::default();
+    /// engine.load_builtin_module();
+    ///
+    /// let input = ["a", "b", "c"].into_iter().map(|s| s.to_string().into());
+    /// let result = engine.eval_parallel("upcase()", input).unwrap();
+    /// assert_eq!(
+    ///     result,
+    ///     vec!["A".to_string().into(), "B".to_string().into(), "C".to_string().into()].into()
+    /// );
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn eval_parallel<I: Iterator<Item = RuntimeValue>>(&mut self, code: &str, input: I) -> MqResult
+    where
+        T: Send + Sync,
+    {
+        if code.is_empty() {
+            return Ok(vec![].into());
+        }
+
+        let program = parse(code, Shared::clone(&self.token_arena))?;
+        let program = Optimizer::with_level(self.optimization_level).optimize(program);
+
+        #[cfg(feature = "debugger")]
+        self.evaluator.module_loader.set_source_code(code.to_string());
+
+        self.evaluator
+            .eval_parallel(&program, input.collect())
+            .map(|values| values.into())
+            .map_err(|e| Box::new(error::Error::from_error(code, e, self.evaluator.module_loader.clone())))
+    }
+
     /// Compiles mq code into a [`CompiledProgram`] that can be evaluated multiple times.
     ///
     /// Use this with `eval_compiled` to avoid re-parsing the same query for each input.
@@ -314,6 +461,25 @@ impl<T: ModuleResolver> Engine<T> {
             })
     }
 
+    /// Enables or disables per-call-site profiling of subsequent evaluations.
+    ///
+    /// While enabled, each call to `eval` or `eval_compiled` replaces the timings returned by
+    /// [`Engine::profile`].
+    pub fn set_profiling(&mut self, enabled: bool) {
+        self.evaluator.profiler = enabled.then(Default::default);
+    }
+
+    /// Returns the call sites of the last evaluation, slowest first.
+    ///
+    /// Empty unless profiling was enabled with [`Engine::set_profiling`].
+    pub fn profile(&self) -> Vec<ProfileEntry> {
+        self.evaluator
+            .profiler
+            .as_ref()
+            .map(|profiler| profiler.entries())
+            .unwrap_or_default()
+    }
+
     /// Returns a reference to the debugger instance.
     ///
     /// This allows interactive debugging of mq code execution when the
@@ -422,6 +588,73 @@ impl Engine<DefaultModuleResolver> {
     }
 }
 
+enum StreamState {
+    /// Evaluating one input value per call to `next`.
+    Streaming(crate::ast::Program),
+    /// Waiting to evaluate the whole input, followed by the `nodes` tail.
+    Pending(crate::ast::Program, crate::ast::Program),
+    /// Yielding the results of a `nodes` query.
+    Buffered(std::vec::IntoIter<RuntimeValue>),
+    Done,
+}
+
+/// An iterator over the results of [`Engine::eval_stream`], evaluating input values on demand.
+pub struct EvalStream<'a, T: ModuleResolver, I> {
+    engine: &'a mut Engine<T>,
+    source: String,
+    input: I,
+    state: StreamState,
+}
+
+impl<T: ModuleResolver, I: Iterator<Item = RuntimeValue>> EvalStream<'_, T, I> {
+    fn error(&mut self, error: crate::error::InnerError) -> Box<error::Error> {
+        self.state = StreamState::Done;
+        Box::new(error::Error::from_error(
+            &self.source,
+            error,
+            self.engine.evaluator.module_loader.clone(),
+        ))
+    }
+}
+
+impl<T: ModuleResolver, I: Iterator<Item = RuntimeValue>> Iterator for EvalStream<'_, T, I> {
+    type Item = Result<RuntimeValue, Box<error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match std::mem::replace(&mut self.state, StreamState::Done) {
+            StreamState::Streaming(program) => {
+                let value = self.input.next()?;
+                self.engine.evaluator.reset_deadline();
+                let result = self.engine.evaluator.eval_input(&program, value);
+                self.state = StreamState::Streaming(program);
+                Some(result.map_err(|e| self.error(e)))
+            }
+            StreamState::Pending(program, nodes_program) => {
+                let evaluator = &mut self.engine.evaluator;
+                let values = self
+                    .input
+                    .by_ref()
+                    .map(|value| evaluator.eval_input(&program, value))
+                    .collect::<Result<Vec<_>, _>>()
+                    .and_then(|values| evaluator.eval_nodes(&nodes_program, values));
+                match values {
+                    Ok(values) => {
+                        self.state = StreamState::Buffered(values.into_iter());
+                        self.next()
+                    }
+                    Err(e) => Some(Err(self.error(e))),
+                }
+            }
+            StreamState::Buffered(mut values) => {
+                let value = values.next()?;
+                self.state = StreamState::Buffered(values);
+                Some(Ok(value))
+            }
+            StreamState::Done => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::CompiledProgram;
@@ -489,6 +722,38 @@ mod tests {
         assert!(started.elapsed() < std::time::Duration::from_secs(5));
     }
 
+    fn engine_with_host_functions() -> DefaultEngine {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        engine.register_function(
+            "greet",
+            2,
+            crate::Shared::new(|args: &[crate::RuntimeValue]| match args {
+                [crate::RuntimeValue::String(greeting), crate::RuntimeValue::String(name)] => {
+                    Ok(format!("{}, {}", greeting, name).into())
+                }
+                _ => Err("greet expects two strings".to_string()),
+            }),
+        );
+        engine
+    }
+
+    #[rstest]
+    #[case::explicit_args(r#"greet("Hello", "mq")"#, Ok("Hello, mq"))]
+    #[case::current_value_as_first_arg(r#""Hi" | greet("mq")"#, Ok("Hi, mq"))]
+    #[case::user_definition_wins(r#"def greet(a, b): "user"; | greet("a", "b")"#, Ok("user"))]
+    #[case::host_error(r#"greet(1, 2)"#, Err("greet expects two strings"))]
+    #[case::wrong_arity(r#"greet("a", "b", "c")"#, Err("greet"))]
+    fn test_register_function(#[case] query: &str, #[case] expected: Result<&str, &str>) {
+        let mut engine = engine_with_host_functions();
+        let result = engine.eval(query, crate::null_input().into_iter());
+
+        match expected {
+            Ok(expected) => assert_eq!(result.unwrap().values()[0], expected.to_string().into()),
+            Err(message) => assert!(result.unwrap_err().to_string().contains(message)),
+        }
+    }
+
     #[test]
     fn test_no_timeout_by_default() {
         let mut engine = DefaultEngine::default();
@@ -496,6 +761,39 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_profile() {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        assert!(engine.profile().is_empty());
+
+        engine.set_profiling(true);
+        engine
+            .eval(
+                "def double(x): x * 2; | map([1, 2, 3], double) | len()",
+                vec!["".to_string().into()].into_iter(),
+            )
+            .unwrap();
+
+        let profile = engine.profile();
+        let calls = |name: &str| profile.iter().find(|entry| entry.name == name).map(|entry| entry.calls);
+        assert_eq!(calls("map"), Some(1));
+        assert_eq!(calls("len"), Some(1));
+        assert!(profile.windows(2).all(|w| w[0].total >= w[1].total));
+
+        // Each evaluation replaces the previous profile.
+        engine
+            .eval("upcase()", vec!["a".to_string().into()].into_iter())
+            .unwrap();
+        let profile = engine.profile();
+        assert_eq!(profile.len(), 1);
+        assert_eq!(profile[0].name, "upcase");
+        assert_eq!(profile[0].range.start.column, 1);
+
+        engine.set_profiling(false);
+        assert!(engine.profile().is_empty());
+    }
+
     #[test]
     fn test_version() {
         let version = DefaultEngine::version();
@@ -547,6 +845,64 @@ mod tests {
         assert_eq!(values.len(), 1);
     }
 
+    #[rstest]
+    #[case("upcase()", vec!["a", "b"], vec!["A", "B"])]
+    #[case("def twice(s): s + s; | twice(self)", vec!["a", "b"], vec!["aa", "bb"])]
+    #[case("", vec!["a"], vec![])]
+    fn test_eval_stream(#[case] query: &str, #[case] input: Vec<&str>, #[case] expected: Vec<&str>) {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let input = input.into_iter().map(|s| s.to_string().into());
+        let results: Vec<crate::RuntimeValue> = engine
+            .eval_stream(query, input)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let expected: Vec<crate::RuntimeValue> = expected.into_iter().map(|s| s.to_string().into()).collect();
+        assert_eq!(results, expected);
+    }
+
+    #[test]
+    fn test_eval_stream_consumes_input_lazily() {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let pulled = std::cell::Cell::new(0);
+        let input = (0..).map(|i| {
+            pulled.set(pulled.get() + 1);
+            crate::RuntimeValue::from(i)
+        });
+
+        let results: Vec<_> = engine.eval_stream("add(1)", input).unwrap().take(2).collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(pulled.get(), 2);
+    }
+
+    #[test]
+    fn test_eval_stream_matches_eval_with_nodes() {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let input = crate::parse_markdown_input("# a\n\n# b\n").unwrap();
+
+        let expected = engine.eval(".h | nodes | len()", input.clone().into_iter()).unwrap();
+        let results: Vec<crate::RuntimeValue> = engine
+            .eval_stream(".h | nodes | len()", input.into_iter())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(&results, expected.values());
+    }
+
+    #[test]
+    fn test_eval_stream_stops_after_error() {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let input = vec![1.into(), "a".to_string().into(), 2.into()].into_iter();
+
+        let mut results = engine.eval_stream("undefined_fn()", input).unwrap();
+        assert!(results.next().unwrap().is_err());
+        assert!(results.next().is_none());
+    }
+
     #[test]
     fn test_eval_import_as_alias() {
         let (temp_dir, temp_file_path) =
@@ -785,6 +1141,33 @@ mod tests {
         assert_eq!(values[0], "hello".to_string().into());
     }
 
+    #[cfg(feature = "parallel")]
+    #[rstest]
+    #[case("upcase()")]
+    #[case("let x = self | x + x")]
+    #[case("def twice(s): s + s; | twice(self)")]
+    #[case(".h | nodes | len()")]
+    fn test_eval_parallel_matches_eval(#[case] query: &str) {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let input: Vec<crate::RuntimeValue> = (0..64).map(|i| format!("v{i}").into()).collect();
+
+        let expected = engine.eval(query, input.clone().into_iter()).unwrap();
+        let result = engine.eval_parallel(query, input.into_iter()).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_eval_parallel_reports_first_error_in_input_order() {
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        let input = vec!["a".to_string().into(), 1.into(), 2.into()].into_iter();
+
+        let err = engine.eval_parallel("upcase() | error(self)", input).unwrap_err();
+        assert!(err.to_string().contains('A'), "{err}");
+    }
+
     #[cfg(feature = "sync")]
     #[test]
     fn test_engine_thread_usage_with_sync_feature() {
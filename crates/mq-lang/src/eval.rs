@@ -2,9 +2,9 @@ use std::borrow::Cow;
 use std::collections::BTreeMap;
 use std::sync::LazyLock;
 use std::time::Duration;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
 use std::time::Instant;
-#[cfg(target_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
 use web_time::Instant;
 
 #[cfg(feature = "debugger")]
@@ -44,10 +44,13 @@ pub mod builtin;
 #[cfg(feature = "debugger")]
 pub mod debugger;
 pub mod env;
+pub(crate) mod profiler;
 pub mod runtime_value;
 
 use env::Env;
+use profiler::Profiler;
 use runtime_value::RuntimeValue;
+use rustc_hash::FxHashMap;
 
 /// Number of loop iterations / function calls between wall-clock deadline checks.
 /// Must be a power of two so the check is a cheap bitmask instead of a modulo.
@@ -151,6 +154,29 @@ impl Default for Options {
     }
 }
 
+/// A function implemented by the host application and callable from mq code by name.
+///
+/// It receives the evaluated arguments and returns the result, or an error message that is
+/// reported as a runtime error. See [`Engine::register_function`](crate::Engine::register_function).
+#[cfg(not(feature = "sync"))]
+pub type HostFunction = dyn Fn(&[RuntimeValue]) -> Result<RuntimeValue, String>;
+#[cfg(feature = "sync")]
+pub type HostFunction = dyn Fn(&[RuntimeValue]) -> Result<RuntimeValue, String> + Send + Sync;
+
+#[derive(Clone)]
+pub(crate) struct RegisteredHostFunction {
+    num_params: u8,
+    func: Shared<HostFunction>,
+}
+
+impl std::fmt::Debug for RegisteredHostFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegisteredHostFunction")
+            .field("num_params", &self.num_params)
+            .finish_non_exhaustive()
+    }
+}
+
 /// The AST evaluator for executing mq programs.
 ///
 /// Evaluates abstract syntax trees and manages the runtime environment,
@@ -165,9 +191,13 @@ pub struct Evaluator<T: ModuleResolver = DefaultModuleResolver> {
     deadline: Option<Instant>,
     /// Step counter so `Instant::now()` is only sampled every `TIMEOUT_CHECK_INTERVAL` steps.
     timeout_step: u32,
+    /// Per-call-site timings for the last `eval` call; `None` unless profiling is enabled.
+    pub(crate) profiler: Option<Profiler>,
     pub(crate) options: Options,
     pub(crate) module_loader: module::ModuleLoader<T>,
     pub(crate) macro_expander: Macro,
+    /// Functions registered by the host, resolved after user definitions and before builtins.
+    host_functions: FxHashMap<Ident, RegisteredHostFunction>,
 
     #[cfg(feature = "debugger")]
     debugger: Shared<SharedCell<Debugger>>,
@@ -183,9 +213,11 @@ impl<T: ModuleResolver> Default for Evaluator<T> {
             call_stack_depth: 0,
             deadline: None,
             timeout_step: 0,
+            profiler: None,
             options: Options::default(),
             module_loader: module::ModuleLoader::new(T::default()),
             macro_expander: Macro::new(),
+            host_functions: FxHashMap::default(),
             #[cfg_attr(feature = "sync", allow(clippy::arc_with_non_send_sync))]
             #[cfg(feature = "debugger")]
             debugger: Shared::new(SharedCell::new(Debugger::new())),
@@ -203,9 +235,11 @@ impl<T: ModuleResolver> Clone for Evaluator<T> {
             call_stack_depth: self.call_stack_depth,
             deadline: self.deadline,
             timeout_step: self.timeout_step,
+            profiler: self.profiler.clone(),
             options: self.options.clone(),
             module_loader: self.module_loader.clone(),
             macro_expander: self.macro_expander.clone(),
+            host_functions: self.host_functions.clone(),
             #[cfg(feature = "debugger")]
             debugger: Shared::clone(&self.debugger),
             #[cfg(feature = "debugger")]
@@ -265,8 +299,62 @@ impl<T: ModuleResolver> Evaluator<T> {
     where
         I: Iterator<Item = RuntimeValue>,
     {
-        self.deadline = self.options.timeout.map(|timeout| Instant::now() + timeout);
-        self.timeout_step = 0;
+        let (program, nodes_program) = self.prepare(program)?;
+        let values: Result<Vec<RuntimeValue>, InnerError> = input
+            .map(|runtime_value| self.eval_input(&program, runtime_value))
+            .collect();
+
+        if nodes_program.is_empty() {
+            values
+        } else {
+            self.eval_nodes(&nodes_program, values?)
+        }
+    }
+
+    /// Like [`Evaluator::eval`], but evaluates the input values on the rayon thread pool. Results
+    /// are returned in input order, and on failure the error of the earliest failing input is.
+    #[cfg(feature = "parallel")]
+    pub(crate) fn eval_parallel(
+        &mut self,
+        program: &Program,
+        input: Vec<RuntimeValue>,
+    ) -> Result<Vec<RuntimeValue>, InnerError>
+    where
+        T: Send + Sync,
+    {
+        use rayon::prelude::*;
+
+        let (program, nodes_program) = self.prepare(program)?;
+        let evaluator = &*self;
+        let results: Vec<Result<RuntimeValue, InnerError>> = input
+            .into_par_iter()
+            .map_init(
+                || evaluator.clone(),
+                |worker, runtime_value| {
+                    // Each input gets its own scope, so its top-level bindings can't race with
+                    // those of inputs evaluated concurrently.
+                    worker.env = Shared::new(SharedCell::new(Env::with_parent(Shared::downgrade(&evaluator.env))));
+                    worker.eval_input(&program, runtime_value)
+                },
+            )
+            .collect();
+        let values = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+        if nodes_program.is_empty() {
+            Ok(values)
+        } else {
+            self.eval_nodes(&nodes_program, values)
+        }
+    }
+
+    /// Resolves includes and imports, expands macros and registers function definitions, returning
+    /// the program to run per input value and the `nodes` tail (empty unless the program contains
+    /// `nodes`) to run once over all of their results.
+    pub(crate) fn prepare(&mut self, program: &Program) -> Result<(Program, Program), InnerError> {
+        self.reset_deadline();
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.clear();
+        }
 
         // First pass: handle includes and imports, collect other nodes
         let program = program.iter().try_fold(
@@ -319,47 +407,51 @@ impl<T: ModuleResolver> Evaluator<T> {
                 Ok(nodes)
             },
         )?;
-        let nodes_index = &program.iter().position(|node| node.is_nodes());
-
-        if let Some(index) = nodes_index {
-            let (program, nodes_program) = program.split_at_mut(*index);
-            let program = program.to_vec();
-            let nodes_program = nodes_program.to_vec();
-
-            let values: Result<Vec<RuntimeValue>, InnerError> = input
-                .map(|runtime_value| match &runtime_value {
-                    RuntimeValue::Markdown(node, _) => self.eval_markdown_node(&program, node),
-                    _ => self
-                        .eval_program(&program, runtime_value, &Shared::clone(&self.env))
-                        .map_err(|e| e.into_inner_error()),
-                })
-                .collect();
-
-            if nodes_program.is_empty() {
-                values
-            } else {
-                self.eval_program(&nodes_program, values?.into(), &Shared::clone(&self.env))
-                    .map(|value| {
-                        if let RuntimeValue::Array(values) = value {
-                            Shared::unwrap_or_clone(values)
-                        } else {
-                            vec![value]
-                        }
-                    })
-                    .map_err(|e| e.into_inner_error())
-            }
-        } else {
-            input
-                .map(|runtime_value| match &runtime_value {
-                    RuntimeValue::Markdown(node, _) => self.eval_markdown_node(&program, node),
-                    _ => self
-                        .eval_program(&program, runtime_value, &Shared::clone(&self.env))
-                        .map_err(|e| e.into_inner_error()),
-                })
-                .collect()
+
+        let nodes_program = match program.iter().position(|node| node.is_nodes()) {
+            Some(index) => program.split_off(index),
+            None => Vec::new(),
+        };
+        Ok((program, nodes_program))
+    }
+
+    /// Restarts the evaluation timeout, if one is configured.
+    pub(crate) fn reset_deadline(&mut self) {
+        self.deadline = self.options.timeout.map(|timeout| Instant::now() + timeout);
+        self.timeout_step = 0;
+    }
+
+    /// Evaluates a prepared program against a single input value.
+    pub(crate) fn eval_input(
+        &mut self,
+        program: &Program,
+        runtime_value: RuntimeValue,
+    ) -> Result<RuntimeValue, InnerError> {
+        match &runtime_value {
+            RuntimeValue::Markdown(node, _) => self.eval_markdown_node(program, node),
+            _ => self
+                .eval_program(program, runtime_value, &Shared::clone(&self.env))
+                .map_err(|e| e.into_inner_error()),
         }
     }
 
+    /// Evaluates the `nodes` tail of a prepared program over the results of all input values.
+    pub(crate) fn eval_nodes(
+        &mut self,
+        nodes_program: &Program,
+        values: Vec<RuntimeValue>,
+    ) -> Result<Vec<RuntimeValue>, InnerError> {
+        self.eval_program(nodes_program, values.into(), &Shared::clone(&self.env))
+            .map(|value| {
+                if let RuntimeValue::Array(values) = value {
+                    Shared::unwrap_or_clone(values)
+                } else {
+                    vec![value]
+                }
+            })
+            .map_err(|e| e.into_inner_error())
+    }
+
     #[inline(always)]
     fn eval_markdown_node(&mut self, program: &Program, node: &mq_markdown::Node) -> Result<RuntimeValue, InnerError> {
         node.map_values(&mut |child_node| {
@@ -1315,6 +1407,13 @@ impl<T: ModuleResolver> Evaluator<T> {
                     return Ok(runtime_value.clone());
                 }
 
+                if self.profiler.is_some() {
+                    let start = Instant::now();
+                    let result = self.eval_fn(runtime_value, Shared::clone(node), ident.name, args, env);
+                    self.record_profile(node, ident.name, start.elapsed());
+                    return result;
+                }
+
                 self.eval_fn(runtime_value, Shared::clone(node), ident.name, args, env)
             }
             ast::Expr::Ident(ident) => self.eval_ident(ident.name, node.token_id, env),
@@ -1955,7 +2054,59 @@ impl<T: ModuleResolver> Evaluator<T> {
 
         match resolved {
             Ok(fn_value) => self.call_fn(&fn_value, node, ident, args, runtime_value, env),
-            Err(_) => self.eval_builtin(runtime_value, node, &ident, args, env),
+            Err(_) => match self.host_functions.get(&ident).cloned() {
+                Some(host_function) => self.eval_host_function(runtime_value, node, ident, &host_function, args, env),
+                None => self.eval_builtin(runtime_value, node, &ident, args, env),
+            },
+        }
+    }
+
+    pub(crate) fn register_host_function(&mut self, name: &str, num_params: u8, func: Shared<HostFunction>) {
+        self.host_functions
+            .insert(Ident::new(name), RegisteredHostFunction { num_params, func });
+    }
+
+    /// Calls a host function. Like builtins, the current value is passed as the first
+    /// argument when exactly one argument is missing.
+    fn eval_host_function(
+        &mut self,
+        runtime_value: &RuntimeValue,
+        node: Shared<ast::Node>,
+        ident: Ident,
+        host_function: &RegisteredHostFunction,
+        args: &ast::Args,
+        env: &Shared<SharedCell<Env>>,
+    ) -> EvalResult {
+        let mut evaluated: builtin::Args = Vec::with_capacity(host_function.num_params as usize);
+        if args.len() + 1 == host_function.num_params as usize {
+            evaluated.push(runtime_value.clone());
+        }
+        for arg in args.iter() {
+            evaluated.push(self.eval_expr(runtime_value, arg, env)?);
+        }
+
+        let result = if evaluated.len() == host_function.num_params as usize {
+            (host_function.func)(&evaluated).map_err(builtin::Error::Runtime)
+        } else {
+            Err(builtin::Error::InvalidNumberOfArguments(
+                ident.to_string(),
+                host_function.num_params,
+                args.len() as u8,
+            ))
+        };
+
+        result.map_err(|e| EvalError::from(e.to_runtime_error((*node).clone(), Shared::clone(&self.token_arena))))
+    }
+
+    /// Records the time spent in a call written in the top-level query.
+    fn record_profile(&mut self, node: &Shared<ast::Node>, name: Ident, elapsed: Duration) {
+        let token = get_token(Shared::clone(&self.token_arena), node.token_id);
+        if token.module_id != Module::TOP_LEVEL_MODULE_ID {
+            return;
+        }
+
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record(node.token_id, name, token.range, elapsed);
         }
     }
 
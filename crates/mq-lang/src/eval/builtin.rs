@@ -163,11 +163,11 @@ fn error_impl(ident: &Ident, _: &RuntimeValue, mut args: Args, _: &SharedEnv) ->
 fn print_impl(_: &Ident, current_value: &RuntimeValue, args: Args, _: &SharedEnv) -> Result<RuntimeValue, Error> {
     match args.as_slice() {
         [a] => {
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
             {
                 web_sys::console::log_1(&a.to_string().into());
             }
-            #[cfg(not(target_arch = "wasm32"))]
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             {
                 println!("{}", a);
             }
@@ -181,11 +181,11 @@ fn print_impl(_: &Ident, current_value: &RuntimeValue, args: Args, _: &SharedEnv
 fn stderr_impl(_: &Ident, current_value: &RuntimeValue, args: Args, _: &SharedEnv) -> Result<RuntimeValue, Error> {
     match args.as_slice() {
         [a] => {
-            #[cfg(target_arch = "wasm32")]
+            #[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
             {
                 web_sys::console::error_1(&a.to_string().into());
             }
-            #[cfg(not(target_arch = "wasm32"))]
+            #[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
             {
                 eprintln!("{}", a);
             }
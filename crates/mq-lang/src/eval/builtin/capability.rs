@@ -34,17 +34,17 @@ pub fn set_allow_write(allow: bool) {
     WRITE_ALLOWED.store(allow, Ordering::Relaxed);
 }
 
-#[cfg(feature = "http")]
-pub(crate) fn is_net_allowed() -> bool {
+/// Returns whether `http` is enabled for the current process.
+pub fn is_net_allowed() -> bool {
     NET_ALLOWED.load(Ordering::Relaxed)
 }
 
-#[cfg(feature = "file-io")]
-pub(crate) fn is_read_allowed() -> bool {
+/// Returns whether `read_file`/`read_file_bytes` are enabled for the current process.
+pub fn is_read_allowed() -> bool {
     READ_ALLOWED.load(Ordering::Relaxed)
 }
 
-#[cfg(feature = "file-io")]
-pub(crate) fn is_write_allowed() -> bool {
+/// Returns whether `write_file` is enabled for the current process.
+pub fn is_write_allowed() -> bool {
     WRITE_ALLOWED.load(Ordering::Relaxed)
 }
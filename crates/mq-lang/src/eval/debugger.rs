@@ -92,6 +92,47 @@ impl Default for DebugContext {
     }
 }
 
+impl DebugContext {
+    /// Creates a scope nested in the paused environment.
+    ///
+    /// Expressions evaluated in it can read every variable visible at the stop location, while
+    /// bindings they introduce (e.g. `let` in a watch expression) stay out of the paused program.
+    pub fn child_env(&self) -> Shared<SharedCell<Env>> {
+        Shared::new(SharedCell::new(Env::with_parent(Shared::downgrade(&self.env))))
+    }
+
+    /// Returns a copy of this context whose current scope is detached from the running program.
+    ///
+    /// Later assignments in the current scope do not affect the snapshot, so it can be inspected
+    /// after execution has moved on. Enclosing scopes are still shared.
+    pub fn snapshot(&self) -> Self {
+        #[cfg(not(feature = "sync"))]
+        let env = self.env.borrow().clone();
+        #[cfg(feature = "sync")]
+        let env = self.env.read().unwrap().clone();
+
+        Self {
+            env: Shared::new(SharedCell::new(env)),
+            ..self.clone()
+        }
+    }
+
+    /// Replaces the value of an existing variable in the paused environment.
+    ///
+    /// The nearest scope that defines `name` is updated, or only the global scope when `global`
+    /// is set. Returns `false` if no such variable exists.
+    pub fn set_variable(&self, name: &str, value: RuntimeValue, global: bool) -> bool {
+        let ident = crate::Ident::new(name);
+
+        #[cfg(not(feature = "sync"))]
+        let updated = self.env.borrow_mut().overwrite(ident, value, global);
+        #[cfg(feature = "sync")]
+        let updated = self.env.write().unwrap().overwrite(ident, value, global);
+
+        updated
+    }
+}
+
 /// The main debugger struct that manages breakpoints and execution state
 #[derive(Debug)]
 pub struct Debugger {
@@ -488,7 +529,7 @@ impl<T: ModuleResolver> Evaluator<T> {
 mod tests {
     use rstest::rstest;
 
-    use crate::{Arena, Range, TokenKind, ast::TokenId, eval::module::ModuleId};
+    use crate::{Arena, Ident, Range, TokenKind, ast::TokenId, eval::module::ModuleId};
 
     use super::*;
 
@@ -531,6 +572,114 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_child_env_reads_parent_without_leaking_definitions() {
+        let ctx = make_debug_context(1, 1);
+        let child = ctx.child_env();
+
+        #[cfg(not(feature = "sync"))]
+        {
+            ctx.env
+                .borrow_mut()
+                .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+            child
+                .borrow_mut()
+                .define(Ident::new("y"), RuntimeValue::Number(2.into()));
+            assert_eq!(
+                child.borrow().resolve(Ident::new("x")).unwrap(),
+                RuntimeValue::Number(1.into())
+            );
+            assert!(ctx.env.borrow().resolve(Ident::new("y")).is_err());
+        }
+        #[cfg(feature = "sync")]
+        {
+            ctx.env
+                .write()
+                .unwrap()
+                .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+            child
+                .write()
+                .unwrap()
+                .define(Ident::new("y"), RuntimeValue::Number(2.into()));
+            assert_eq!(
+                child.read().unwrap().resolve(Ident::new("x")).unwrap(),
+                RuntimeValue::Number(1.into())
+            );
+            assert!(ctx.env.read().unwrap().resolve(Ident::new("y")).is_err());
+        }
+    }
+
+    #[test]
+    fn test_snapshot_is_detached_from_later_changes() {
+        let ctx = make_debug_context(1, 1);
+        #[cfg(not(feature = "sync"))]
+        ctx.env
+            .borrow_mut()
+            .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+        #[cfg(feature = "sync")]
+        ctx.env
+            .write()
+            .unwrap()
+            .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+
+        let snapshot = ctx.snapshot();
+        assert!(ctx.set_variable("x", RuntimeValue::Number(2.into()), false));
+
+        #[cfg(not(feature = "sync"))]
+        let value = snapshot.env.borrow().resolve(Ident::new("x")).unwrap();
+        #[cfg(feature = "sync")]
+        let value = snapshot.env.read().unwrap().resolve(Ident::new("x")).unwrap();
+        assert_eq!(value, RuntimeValue::Number(1.into()));
+        assert_eq!(snapshot.token, ctx.token);
+    }
+
+    #[rstest]
+    #[case::local(false, 10.0, 1.0)]
+    #[case::global(true, 2.0, 10.0)]
+    fn test_set_variable(#[case] global: bool, #[case] expected_local: f64, #[case] expected_global: f64) {
+        let root = Shared::new(SharedCell::new(Env::default()));
+        let ctx = DebugContext {
+            env: Shared::new(SharedCell::new(Env::with_parent(Shared::downgrade(&root)))),
+            ..make_debug_context(1, 1)
+        };
+
+        #[cfg(not(feature = "sync"))]
+        {
+            root.borrow_mut()
+                .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+            ctx.env
+                .borrow_mut()
+                .define(Ident::new("x"), RuntimeValue::Number(2.into()));
+        }
+        #[cfg(feature = "sync")]
+        {
+            root.write()
+                .unwrap()
+                .define(Ident::new("x"), RuntimeValue::Number(1.into()));
+            ctx.env
+                .write()
+                .unwrap()
+                .define(Ident::new("x"), RuntimeValue::Number(2.into()));
+        }
+
+        assert!(ctx.set_variable("x", RuntimeValue::Number(10.into()), global));
+        assert!(!ctx.set_variable("undefined", RuntimeValue::Number(10.into()), global));
+
+        #[cfg(not(feature = "sync"))]
+        let (local, global_value) = (
+            ctx.env.borrow().resolve(Ident::new("x")).unwrap(),
+            root.borrow().resolve(Ident::new("x")).unwrap(),
+        );
+        #[cfg(feature = "sync")]
+        let (local, global_value) = (
+            ctx.env.read().unwrap().resolve(Ident::new("x")).unwrap(),
+            root.read().unwrap().resolve(Ident::new("x")).unwrap(),
+        );
+
+        assert_eq!(local, RuntimeValue::Number(expected_local.into()));
+        assert_eq!(global_value, RuntimeValue::Number(expected_global.into()));
+    }
+
     #[rstest]
     #[case(DebuggerCommand::Continue, false, "Continue: should not break")]
     #[case(DebuggerCommand::Quit, false, "Quit: should not break and deactivate")]
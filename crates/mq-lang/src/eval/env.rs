@@ -427,6 +427,28 @@ impl Env {
         false
     }
 
+    /// Replaces the value of an existing binding in the nearest scope that defines `ident`,
+    /// regardless of mutability. With `global`, only the outermost scope is considered.
+    ///
+    /// Returns `false` if no matching binding exists. Used by the debugger to edit variables of
+    /// a paused program.
+    #[cfg(feature = "debugger")]
+    pub(crate) fn overwrite(&mut self, ident: Ident, runtime_value: RuntimeValue, global: bool) -> bool {
+        let Some(parent) = self.parent.as_ref().and_then(|p| p.upgrade()) else {
+            return self.context.contains_key(ident) && {
+                self.context.upsert(ident, runtime_value);
+                true
+            };
+        };
+
+        if !global && self.context.contains_key(ident) {
+            self.context.upsert(ident, runtime_value);
+            return true;
+        }
+
+        borrow_env_mut!(parent).overwrite(ident, runtime_value, global)
+    }
+
     #[cfg(feature = "debugger")]
     /// Returns a vector of local variables in the current environment.
     pub fn get_local_variables(&self) -> Vec<Variable> {
@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use rustc_hash::FxHashMap;
+
+use crate::{Ident, Range, ast::TokenId};
+
+/// Timing collected for a single call site while profiling is enabled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileEntry {
+    /// Name of the called function.
+    pub name: String,
+    /// Source range of the call in the evaluated query.
+    pub range: Range,
+    /// Number of times the call site was evaluated.
+    pub calls: usize,
+    /// Wall time spent in the call, including nested calls.
+    pub total: Duration,
+}
+
+/// Collects per-call-site timings for the query being evaluated.
+///
+/// Only calls written in the top-level query are recorded; calls made from inside builtin
+/// modules are attributed to the call site in the query that triggered them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Profiler {
+    entries: FxHashMap<TokenId, (Ident, Range, usize, Duration)>,
+}
+
+impl Profiler {
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub(crate) fn record(&mut self, token_id: TokenId, name: Ident, range: Range, elapsed: Duration) {
+        let entry = self.entries.entry(token_id).or_insert((name, range, 0, Duration::ZERO));
+        entry.2 += 1;
+        entry.3 += elapsed;
+    }
+
+    /// Returns the recorded call sites, slowest first.
+    pub(crate) fn entries(&self) -> Vec<ProfileEntry> {
+        let mut entries = self
+            .entries
+            .values()
+            .map(|(name, range, calls, total)| ProfileEntry {
+                name: name.to_string(),
+                range: *range,
+                calls: *calls,
+                total: *total,
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.total.cmp(&a.total).then_with(|| a.range.cmp(&b.range)));
+        entries
+    }
+}
@@ -52,6 +52,7 @@ mod macro_expand;
 mod module;
 mod number;
 mod optimizer;
+mod query;
 mod range;
 mod selector;
 mod suggest;
@@ -79,10 +80,14 @@ pub use ast::parser::Parser as AstParser;
 pub use ast::{ast_from_json, ast_to_json};
 pub use engine::CompiledProgram;
 pub use engine::Engine;
-pub use error::Error;
+pub use engine::EvalStream;
+pub use error::runtime::RuntimeError;
+pub use error::{Error, InnerError};
+pub use eval::HostFunction;
 pub use eval::builtin::{
     BUILTIN_FUNCTION_DOC, BUILTIN_SELECTOR_DOC, BuiltinFunctionDoc, BuiltinSelectorDoc, INTERNAL_FUNCTION_DOC,
 };
+pub use eval::profiler::ProfileEntry;
 pub use eval::runtime_value::{RuntimeValue, RuntimeValues};
 pub use ident::Ident;
 pub use lexer::Options as LexerOptions;
@@ -100,12 +105,15 @@ pub use module::{
     resolver::DefaultModuleResolver, resolver::ModuleResolver,
 };
 pub use optimizer::OptimizationLevel;
+pub use query::{EmbeddedQuery, FromRuntimeValue};
 pub use range::{Position, Range};
 pub use selector::{AttrKind, Selector};
 
 pub type DefaultEngine = Engine<DefaultModuleResolver>;
 pub type DefaultModuleLoader = ModuleLoader<DefaultModuleResolver>;
 
+#[cfg(feature = "cst")]
+pub use cst::highlight::{HighlightToken, TokenClass, highlight_tokens};
 #[cfg(feature = "cst")]
 pub use cst::incremental::{IncrementalParser, TextEdit};
 #[cfg(feature = "cst")]
@@ -185,6 +193,36 @@ pub fn parse(code: &str, token_arena: TokenArena) -> Result<Program, Box<error::
     .map_err(|e| Box::new(error::Error::from_error(code, e.into(), DefaultModuleLoader::default())))
 }
 
+/// Tokenizes mq code into its lexical tokens.
+///
+/// With `LexerOptions::ignore_errors`, lexing stops at the first invalid character and the tokens
+/// read so far are returned instead of an error.
+pub fn tokenize(code: &str, options: LexerOptions) -> Result<Vec<Token>, Box<error::Error>> {
+    Lexer::new(options)
+        .tokenize(code, Module::TOP_LEVEL_MODULE_ID)
+        .map_err(|e| Box::new(error::Error::from_error(code, e.into(), DefaultModuleLoader::default())))
+}
+
+/// Applies the AST optimization passes enabled by `level` to a parsed program.
+pub fn optimize(program: Program, level: OptimizationLevel) -> Program {
+    optimizer::Optimizer::with_level(level).optimize(program)
+}
+
+/// Renders a program as an indented tree of AST nodes annotated with their source spans.
+///
+/// `token_arena` must be the arena the program was parsed with, so that node spans can be resolved.
+pub fn dump_ast(program: &Program, token_arena: TokenArena) -> String {
+    #[cfg(not(feature = "sync"))]
+    {
+        ast::dump::dump(program, &token_arena.borrow())
+    }
+
+    #[cfg(feature = "sync")]
+    {
+        ast::dump::dump(program, &token_arena.read().unwrap())
+    }
+}
+
 /// Parses an MDX string and returns an iterator over `Value` nodes.
 pub fn parse_mdx_input(input: &str) -> miette::Result<Vec<RuntimeValue>> {
     let mdx = mq_markdown::Markdown::from_mdx_str(input)?;
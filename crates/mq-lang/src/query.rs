@@ -0,0 +1,193 @@
+//! Queries embedded at compile time by the `mq_query!` macro of the `mq-query-macros` crate.
+
+use std::marker::PhantomData;
+
+use crate::{
+    ArenaId, Engine, ModuleResolver, Range, RuntimeError, RuntimeValue, Shared, Token, TokenKind,
+    error::{Error, InnerError},
+};
+
+/// A query validated and type checked at build time, evaluated into values of type `V`.
+///
+/// Values are created by `mq_query!`, which picks `V` from the inferred result type of the
+/// query and falls back to [`RuntimeValue`] when no concrete type can be inferred. The query is
+/// embedded as source, since compiled programs refer to tokens of the engine that parsed them.
+#[derive(Debug)]
+pub struct EmbeddedQuery<V> {
+    source: &'static str,
+    _value: PhantomData<fn() -> V>,
+}
+
+impl<V> EmbeddedQuery<V> {
+    pub const fn new(source: &'static str) -> Self {
+        Self {
+            source,
+            _value: PhantomData,
+        }
+    }
+
+    /// Returns the source code of the query.
+    pub fn source(&self) -> &'static str {
+        self.source
+    }
+}
+
+impl<V: FromRuntimeValue> EmbeddedQuery<V> {
+    /// Evaluates the query against the given input and converts each result into `V`.
+    ///
+    /// `None` results are skipped, the same way the CLI omits them from its output. Markdown
+    /// input yields markdown nodes, which convert to strings, numbers and booleans via their text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let mut engine = mq_lang::DefaultEngine::default();
+    /// engine.load_builtin_module();
+    ///
+    /// static QUERY: mq_lang::EmbeddedQuery<String> = mq_lang::EmbeddedQuery::new("upcase()");
+    /// let input = mq_lang::parse_text_input("hello").unwrap();
+    /// assert_eq!(QUERY.eval(&mut engine, input.into_iter()).unwrap(), vec!["HELLO".to_string()]);
+    /// ```
+    pub fn eval<R: ModuleResolver, I: Iterator<Item = RuntimeValue>>(
+        &self,
+        engine: &mut Engine<R>,
+        input: I,
+    ) -> Result<Vec<V>, Box<Error>> {
+        engine
+            .eval(self.source, input)?
+            .into_iter()
+            .filter(|value| !value.is_none())
+            .map(|value| {
+                V::from_runtime_value(value).map_err(|message| {
+                    Box::new(Error::from_error(
+                        self.source,
+                        InnerError::Runtime(RuntimeError::Runtime(
+                            Token {
+                                range: Range::default(),
+                                kind: TokenKind::Eof,
+                                module_id: ArenaId::new(0),
+                            },
+                            message,
+                        )),
+                        engine.evaluator.module_loader.clone(),
+                    ))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Conversion from a query result into a Rust value.
+pub trait FromRuntimeValue: Sized {
+    /// Converts `value`, returning a message describing the mismatch if it has another type.
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String>;
+}
+
+fn mismatch(expected: &str, value: &RuntimeValue) -> String {
+    format!("expected {}, found {}", expected, value.name())
+}
+
+impl FromRuntimeValue for RuntimeValue {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        Ok(value)
+    }
+}
+
+impl FromRuntimeValue for String {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        match value {
+            RuntimeValue::String(s) => Ok(s),
+            RuntimeValue::Markdown(..) => Ok(value.to_string()),
+            _ => Err(mismatch("string", &value)),
+        }
+    }
+}
+
+impl FromRuntimeValue for f64 {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        match &value {
+            RuntimeValue::Number(n) => Ok(n.value()),
+            RuntimeValue::Markdown(..) => value.to_string().trim().parse().map_err(|_| mismatch("number", &value)),
+            _ => Err(mismatch("number", &value)),
+        }
+    }
+}
+
+impl FromRuntimeValue for bool {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        match &value {
+            RuntimeValue::Boolean(b) => Ok(*b),
+            RuntimeValue::Markdown(..) => value.to_string().trim().parse().map_err(|_| mismatch("bool", &value)),
+            _ => Err(mismatch("bool", &value)),
+        }
+    }
+}
+
+impl<V: FromRuntimeValue> FromRuntimeValue for Option<V> {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        if value.is_none() {
+            Ok(None)
+        } else {
+            V::from_runtime_value(value).map(Some)
+        }
+    }
+}
+
+impl<V: FromRuntimeValue> FromRuntimeValue for Vec<V> {
+    fn from_runtime_value(value: RuntimeValue) -> Result<Self, String> {
+        match value {
+            RuntimeValue::Array(values) => Shared::unwrap_or_clone(values)
+                .into_iter()
+                .map(V::from_runtime_value)
+                .collect(),
+            _ => Err(mismatch("array", &value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn eval<V: FromRuntimeValue>(query: &'static str, input: &str) -> Result<Vec<V>, Box<Error>> {
+        let mut engine = crate::DefaultEngine::default();
+        engine.load_builtin_module();
+        EmbeddedQuery::<V>::new(query).eval(&mut engine, crate::parse_text_input(input).unwrap().into_iter())
+    }
+
+    #[rstest]
+    #[case("1 + 2", vec![3.0])]
+    #[case("len(self)", vec![5.0])]
+    fn test_eval_number(#[case] query: &'static str, #[case] expected: Vec<f64>) {
+        assert_eq!(eval::<f64>(query, "hello").unwrap(), expected);
+    }
+
+    #[test]
+    fn test_eval_nested() {
+        assert_eq!(
+            eval::<Vec<Option<String>>>("[\"a\", None]", "hello").unwrap(),
+            vec![vec![Some("a".to_string()), None]]
+        );
+    }
+
+    #[test]
+    fn test_eval_skips_none() {
+        assert_eq!(eval::<bool>("None", "hello").unwrap(), Vec::<bool>::new());
+    }
+
+    #[test]
+    fn test_eval_markdown() {
+        let mut engine = crate::DefaultEngine::default();
+        engine.load_builtin_module();
+        let input = crate::parse_markdown_input("# Title").unwrap();
+        let results = EmbeddedQuery::<String>::new("upcase()").eval(&mut engine, input.into_iter());
+        assert_eq!(results.unwrap(), vec!["# TITLE".to_string()]);
+    }
+
+    #[test]
+    fn test_eval_mismatch() {
+        let error = eval::<f64>("upcase()", "hello").unwrap_err();
+        assert_eq!(error.cause.to_string(), "Runtime error: expected number, found string");
+    }
+}
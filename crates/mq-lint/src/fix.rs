@@ -9,6 +9,10 @@ pub(crate) enum Core {
     Literal(String),
     Verbatim(mq_lang::Range),
     Concat(Vec<Core>),
+    /// Removes the whole top-level statement starting at the fix's range, together with the pipe
+    /// joining it to its neighbour. Resolved against the CST, since the HIR only knows where the
+    /// statement starts.
+    RemoveStatement,
 }
 
 impl Core {
@@ -21,6 +25,7 @@ impl Core {
                 .map(|part| part.resolve(source))
                 .collect::<Option<Vec<_>>>()
                 .map(|parts| parts.concat()),
+            Core::RemoveStatement => Some(String::new()),
         }
     }
 }
@@ -68,6 +73,17 @@ impl Fix {
         }
     }
 
+    /// A fix that deletes the top-level statement starting at `range.start` (e.g. an unused
+    /// `import`), along with the `|` that joins it to the rest of the program.
+    pub fn remove_statement(range: mq_lang::Range) -> Self {
+        Self {
+            range,
+            core: Core::RemoveStatement,
+            prefix: String::new(),
+            suffix: String::new(),
+        }
+    }
+
     pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
         self.prefix = prefix.into();
         self
@@ -84,8 +100,48 @@ impl Fix {
     /// A `Verbatim` core is trimmed, since rules can often only bound one side of such a range by
     /// a sibling token rather than the expression's own true end (see [`crate::LintContext::full_range`]).
     pub fn resolve(&self, source: &str) -> Option<(mq_lang::Range, String)> {
+        let range = match self.core {
+            Core::RemoveStatement => statement_removal_range(source, self.range.start)?,
+            _ => self.range,
+        };
         let core = self.core.resolve(source)?;
-        Some((self.range, format!("{}{}{}", self.prefix, core, self.suffix)))
+        Some((range, format!("{}{}{}", self.prefix, core, self.suffix)))
+    }
+}
+
+/// The range to delete so that the top-level statement starting at `start` disappears along with
+/// one adjacent pipe, leaving comments and the formatting of the remaining statements untouched.
+fn statement_removal_range(source: &str, start: mq_lang::Position) -> Option<mq_lang::Range> {
+    let (nodes, _) = mq_lang::parse_recovery(source);
+    let nodes: Vec<_> = nodes.iter().filter(|node| !node.is_eof()).collect();
+    let index = nodes.iter().position(|node| node.node_range().start == start)?;
+    let statement = nodes[index].node_range();
+
+    match (nodes.get(index + 1), nodes.get(index + 2)) {
+        // `stmt | next`: drop `stmt |` and the spaces after the pipe, so `next` keeps its place.
+        (Some(pipe), Some(_)) if pipe.is_pipe() => {
+            let pipe_end = pipe.node_range().end;
+            let line = source.split('\n').nth(pipe_end.line as usize - 1)?;
+            let padding = line
+                .chars()
+                .skip(pipe_end.column - 1)
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .count();
+            Some(mq_lang::Range {
+                start,
+                end: mq_lang::Position {
+                    line: pipe_end.line,
+                    column: pipe_end.column + padding,
+                },
+            })
+        }
+        // `prev | stmt` as the last statement: drop everything after `prev`.
+        (None, _) if index >= 2 && nodes[index - 1].is_pipe() => Some(mq_lang::Range {
+            start: nodes[index - 2].node_range().end,
+            end: statement.end,
+        }),
+        (None, _) if index == 0 => Some(statement),
+        _ => None,
     }
 }
 
@@ -216,6 +272,37 @@ mod tests {
         assert_eq!(fix.resolve(source), Some((range(1, 1, 1, 8), "x".to_string())));
     }
 
+    #[test]
+    fn resolve_remove_statement_followed_by_pipe() {
+        let source = "# csv helpers\nimport \"csv\"\n| .h1 # keep";
+        let fix = Fix::remove_statement(range(2, 1, 2, 7));
+        let (range, text) = fix.resolve(source).unwrap();
+        assert_eq!(apply_edits(source, &[(range, text)]), "# csv helpers\n.h1 # keep");
+    }
+
+    #[test]
+    fn resolve_remove_statement_on_same_line() {
+        let source = "import \"csv\" |  .h1";
+        let fix = Fix::remove_statement(range(1, 1, 1, 7));
+        let (range, text) = fix.resolve(source).unwrap();
+        assert_eq!(apply_edits(source, &[(range, text)]), ".h1");
+    }
+
+    #[test]
+    fn resolve_remove_last_statement() {
+        let source = ".h1 | import \"csv\" as c";
+        let fix = Fix::remove_statement(range(1, 7, 1, 13));
+        let (range, text) = fix.resolve(source).unwrap();
+        assert_eq!(apply_edits(source, &[(range, text)]), ".h1");
+    }
+
+    #[test]
+    fn resolve_remove_statement_without_matching_node() {
+        let source = ".h1 | .h2";
+        let fix = Fix::remove_statement(range(1, 2, 1, 4));
+        assert_eq!(fix.resolve(source), None);
+    }
+
     #[test]
     fn apply_edits_splices_single_edit() {
         let source = ".checked == false";
@@ -264,6 +264,11 @@ mod tests {
     #[case(r#".checked == true"#, ".checked")]
     #[case(r#"try: get("x") catch: none"#, r#"get("x")?"#)]
     #[case(r#"s"${x}""#, "x")]
+    #[case(
+        "# deprecated: use new_fn instead\ndef old(): 1;\ndef new_fn(): 2; | old()",
+        "# deprecated: use new_fn instead\ndef old(): 1;\ndef new_fn(): 2; | new_fn()"
+    )]
+    #[case("import \"csv\"\n| .h1", ".h1")]
     fn test_fix_source_applies_known_fixable_rules(#[case] code: &str, #[case] expected: &str) {
         let config = LintConfig::default();
         let linter = Linter::with_default_rules();
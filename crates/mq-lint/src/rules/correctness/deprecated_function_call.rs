@@ -1,9 +1,23 @@
-use crate::{Diagnostic, LintContext, LintMessage, LintRule, RuleId, Severity};
+use crate::{Diagnostic, Fix, LintContext, LintMessage, LintRule, RuleId, Severity};
 use mq_hir::SymbolKind;
-use rustc_hash::FxHashSet;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 pub struct DeprecatedFunctionCall;
 
+/// The replacement named by a deprecation comment such as `# deprecated: use new_fn instead`
+/// or ``# Deprecated, use `new_fn()` ``, if any.
+fn replacement(doc: &str) -> Option<&str> {
+    let lower = doc.to_ascii_lowercase();
+    let after_deprecated = lower.find("deprecated")?;
+    let use_at = lower[after_deprecated..].find("use ")? + after_deprecated + "use ".len();
+    let name = doc[use_at..]
+        .trim_start()
+        .trim_start_matches('`')
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()?;
+    (!name.is_empty()).then_some(name)
+}
+
 impl LintRule for DeprecatedFunctionCall {
     fn id(&self) -> RuleId {
         RuleId::DeprecatedFunctionCall
@@ -14,10 +28,19 @@ impl LintRule for DeprecatedFunctionCall {
     }
 
     fn check(&self, ctx: &LintContext<'_>) -> Vec<Diagnostic> {
-        let deprecated_functions: FxHashSet<&str> = ctx
+        let deprecated_functions: FxHashMap<&str, Option<&str>> = ctx
             .hir
             .symbols()
             .filter(|(_, s)| s.is_function() && s.is_deprecated())
+            .filter_map(|(_, s)| {
+                let replacement = s.doc.iter().find_map(|(_, text)| replacement(text));
+                Some((s.value.as_deref()?, replacement))
+            })
+            .collect();
+        let current_functions: FxHashSet<&str> = ctx
+            .hir
+            .symbols()
+            .filter(|(_, s)| s.is_function() && !s.is_deprecated())
             .filter_map(|(_, s)| s.value.as_deref())
             .collect();
 
@@ -29,9 +52,7 @@ impl LintRule for DeprecatedFunctionCall {
 
                 let name = s.value.as_deref()?;
 
-                if !deprecated_functions.contains(name) {
-                    return None;
-                }
+                let replacement = deprecated_functions.get(name)?;
 
                 let mut d = Diagnostic::new(
                     LintMessage::DeprecatedFunctionCall { name: name.to_string() },
@@ -39,6 +60,10 @@ impl LintRule for DeprecatedFunctionCall {
                 );
                 if let Some(range) = s.source.text_range {
                     d = d.with_range(range);
+                    // Only rename to a replacement that actually exists.
+                    if let Some(replacement) = replacement.filter(|r| current_functions.contains(r)) {
+                        d = d.with_fix(Fix::literal(range, replacement.to_string()));
+                    }
                 }
                 Some(d)
             })
@@ -100,6 +125,36 @@ mod tests {
         assert_eq!(diags.len(), 0);
     }
 
+    #[rstest]
+    #[case(" deprecated: use new_fn instead", Some("new_fn"))]
+    #[case(" Deprecated, use `new_fn()`", Some("new_fn"))]
+    #[case(" DEPRECATED: Use new_fn.", Some("new_fn"))]
+    #[case(" deprecated", None)]
+    #[case(" use this while it lasts", None)]
+    fn extracts_replacement(#[case] doc: &str, #[case] expected: Option<&str>) {
+        assert_eq!(replacement(doc), expected);
+    }
+
+    #[rstest]
+    #[case(
+        "# deprecated: use new_fn instead\ndef old(): 1;\ndef new_fn(): 2; | old()",
+        Some("new_fn")
+    )]
+    #[case("# deprecated: use missing instead\ndef old(): 1; | old()", None)]
+    #[case("# deprecated\ndef old(): 1;\ndef new_fn(): 2; | old()", None)]
+    fn fixes_call_with_known_replacement(#[case] code: &str, #[case] expected: Option<&str>) {
+        let diags = check(code);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(
+            diags[0]
+                .fix
+                .as_ref()
+                .and_then(|fix| fix.resolve(code))
+                .map(|(_, text)| text),
+            expected.map(str::to_string)
+        );
+    }
+
     #[test]
     fn severity_is_warn() {
         assert_eq!(DeprecatedFunctionCall.severity(), Severity::Warn);
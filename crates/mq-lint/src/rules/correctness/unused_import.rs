@@ -1,4 +1,4 @@
-use crate::{Diagnostic, LintContext, LintMessage, LintRule, RuleId, Severity};
+use crate::{Diagnostic, Fix, LintContext, LintMessage, LintRule, RuleId, Severity};
 use mq_hir::SymbolKind;
 
 pub struct UnusedImport;
@@ -32,7 +32,7 @@ impl LintRule for UnusedImport {
                 }
                 let mut d = Diagnostic::new(LintMessage::UnusedImport { name: name.to_string() }, self.severity());
                 if let Some(range) = sym.source.text_range {
-                    d = d.with_range(range);
+                    d = d.with_range(range).with_fix(Fix::remove_statement(range));
                 }
                 Some(d)
             })
@@ -0,0 +1,242 @@
+use std::{
+    borrow::Cow,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tower_lsp_server::jsonrpc;
+use tower_lsp_server::ls_types::{CodeLens, Command, Position, Range, TextDocumentIdentifier};
+use url::Url;
+
+use crate::execute_command;
+
+/// Client-side command invoked by the "Run Query" code lens, registered by the VS Code extension.
+pub const RUN_QUERY_COMMAND: &str = "mq.runQuery";
+/// Custom request that evaluates a document against its sample input.
+pub const RUN_QUERY_METHOD: &str = "mq/runQuery";
+
+/// Parameters of the `mq/runQuery` request.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQueryParams {
+    pub text_document: TextDocumentIdentifier,
+    /// Input file to run against; falls back to the server's configured sample input.
+    #[serde(default)]
+    pub input: Option<PathBuf>,
+    /// Start of the expression to run the pipeline up to; the whole document when omitted.
+    #[serde(default)]
+    pub position: Option<Position>,
+}
+
+/// Result of the `mq/runQuery` request.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQueryResult {
+    /// The input file the query was evaluated against.
+    pub input: PathBuf,
+    /// The query result rendered as Markdown.
+    pub result: String,
+}
+
+/// Places a lens on every top-level expression of the pipeline that is not a definition.
+///
+/// A document is a single pipeline, so the lens on an expression runs the pipeline up to and
+/// including it; the last one runs the whole query. Its arguments are the document URI and the
+/// expression's start position, sent back as `position` in the `mq/runQuery` request. Files that
+/// only define functions, variables, or modules have nothing to run and get no lens.
+pub(crate) fn response(url: &Url, text: &str) -> Vec<CodeLens> {
+    let (nodes, _) = mq_lang::parse_recovery(text);
+    let queries = nodes.iter().filter(|node| is_query(node)).collect::<Vec<_>>();
+
+    queries
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let range = node.node_range();
+            let start = Position::new(range.start.line - 1, (range.start.column - 1) as u32);
+            let end = Position::new(range.end.line - 1, (range.end.column - 1) as u32);
+            let title = if i + 1 == queries.len() {
+                "▶︎ Run Query"
+            } else {
+                "▶︎ Run to Here"
+            };
+
+            CodeLens {
+                range: Range::new(start, end),
+                command: Some(Command {
+                    title: title.to_string(),
+                    command: RUN_QUERY_COMMAND.to_string(),
+                    arguments: Some(vec![
+                        url.to_string().into(),
+                        serde_json::to_value(start).unwrap_or_default(),
+                    ]),
+                }),
+                data: None,
+            }
+        })
+        .collect()
+}
+
+/// Returns the part of the pipeline in `text` that ends with the query expression at `position`,
+/// or the last one starting before it. The whole text is returned when no expression follows.
+pub(crate) fn query_up_to(text: &str, position: Position) -> &str {
+    let (nodes, _) = mq_lang::parse_recovery(text);
+    let position = mq_lang::Position::new(position.line + 1, position.character as usize + 1);
+
+    let Some(index) = nodes
+        .iter()
+        .rposition(|node| is_query(node) && node.node_range().start <= position)
+    else {
+        return text;
+    };
+
+    nodes[index + 1..]
+        .iter()
+        .find(|node| node.is_pipe())
+        .map(|pipe| &text[..offset(text, pipe.range().start)])
+        .unwrap_or(text)
+}
+
+fn is_query(node: &mq_lang::CstNode) -> bool {
+    !matches!(
+        node.kind,
+        mq_lang::CstNodeKind::Def
+            | mq_lang::CstNodeKind::Macro
+            | mq_lang::CstNodeKind::Let
+            | mq_lang::CstNodeKind::Var
+            | mq_lang::CstNodeKind::Include
+            | mq_lang::CstNodeKind::Import
+            | mq_lang::CstNodeKind::Module
+            | mq_lang::CstNodeKind::Token
+            | mq_lang::CstNodeKind::Eof
+    )
+}
+
+/// Converts a 1-based line and character column into a byte offset in `text`.
+fn offset(text: &str, position: mq_lang::Position) -> usize {
+    let line_start = text
+        .split_inclusive('\n')
+        .take(position.line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum::<usize>();
+
+    text[line_start..]
+        .char_indices()
+        .nth(position.column.saturating_sub(1))
+        .map(|(i, _)| line_start + i)
+        .unwrap_or(text.len())
+}
+
+/// Evaluates `code` against the file at `input`, choosing the input format from its extension.
+///
+/// Relative input paths are resolved against the directory of the document at `url`.
+pub(crate) fn run_query(url: &Url, code: &str, input: &Path) -> jsonrpc::Result<RunQueryResult> {
+    let input = match url
+        .to_file_path()
+        .ok()
+        .and_then(|path| path.parent().map(Path::to_path_buf))
+    {
+        Some(dir) if input.is_relative() => dir.join(input),
+        _ => input.to_path_buf(),
+    };
+
+    let content = std::fs::read_to_string(&input).map_err(|e| jsonrpc::Error {
+        code: jsonrpc::ErrorCode::InvalidParams,
+        message: Cow::Owned(format!("Failed to read {}: {}", input.display(), e)),
+        data: None,
+    })?;
+    let input_format = match input.extension().and_then(|ext| ext.to_str()) {
+        Some("mdx") => "mdx",
+        Some("html" | "htm") => "html",
+        Some("txt") => "text",
+        _ => "markdown",
+    };
+
+    let result = execute_command::execute(code, &content, Some(input_format))?;
+    Ok(RunQueryResult {
+        input,
+        result: result
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::query_only(".h1", vec![(0, 0)])]
+    #[case::after_definitions("def f(x): x;\n| let y = 1\n| .h1 | f()", vec![(2, 2), (2, 8)])]
+    #[case::after_comment("# heading query\n.h2", vec![(1, 0)])]
+    #[case::multi_line_pipeline(".h1\n| to_text()\n| upcase()", vec![(0, 0), (1, 2), (2, 2)])]
+    #[case::definitions_only("def f(x): x;\n| let y = 1", vec![])]
+    #[case::empty("", vec![])]
+    fn test_code_lens(#[case] text: &str, #[case] expected: Vec<(u32, u32)>) {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let lenses = response(&url, text);
+
+        assert_eq!(
+            lenses
+                .iter()
+                .map(|lens| (lens.range.start.line, lens.range.start.character))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        for (i, lens) in lenses.iter().enumerate() {
+            let command = lens.command.as_ref().unwrap();
+            assert_eq!(command.command, RUN_QUERY_COMMAND);
+            assert_eq!(
+                command.arguments,
+                Some(vec![
+                    url.to_string().into(),
+                    serde_json::to_value(lens.range.start).unwrap()
+                ])
+            );
+            assert_eq!(command.title == "▶︎ Run Query", i + 1 == lenses.len());
+        }
+    }
+
+    #[rstest]
+    #[case::first(Position::new(0, 0), ".h1")]
+    #[case::middle(Position::new(1, 2), ".h1\n| to_text()")]
+    #[case::last(Position::new(2, 2), ".h1\n| to_text()\n| upcase()")]
+    #[case::between(Position::new(1, 0), ".h1")]
+    fn test_query_up_to(#[case] position: Position, #[case] expected: &str) {
+        assert_eq!(
+            query_up_to(".h1\n| to_text()\n| upcase()", position).trim_end(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_query_up_to_keeps_definitions() {
+        let text = "def f(x): x;\n| .h1 | f() | to_text()";
+        assert_eq!(
+            query_up_to(text, Position::new(1, 8)).trim_end(),
+            "def f(x): x;\n| .h1 | f()"
+        );
+    }
+
+    #[test]
+    fn test_run_query_relative_to_document() {
+        let dir = std::env::temp_dir().join("mq_lsp_run_query");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("sample.md"), "# Title\n\nBody\n").unwrap();
+        let url = Url::from_file_path(dir.join("query.mq")).unwrap();
+
+        let result = run_query(&url, ".h1 | to_text()", Path::new("sample.md")).unwrap();
+
+        assert_eq!(result.input, dir.join("sample.md"));
+        assert_eq!(result.result.trim(), "Title");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_run_query_missing_input() {
+        let url = Url::parse("file:///test.mq").unwrap();
+        let err = run_query(&url, ".h1", Path::new("/nonexistent/sample.md")).unwrap_err();
+        assert_eq!(err.code, jsonrpc::ErrorCode::InvalidParams);
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::{Arc, RwLock};
+
+use tower_lsp_server::ls_types::{DocumentHighlight, DocumentHighlightKind, Position, Range};
+use url::Url;
+
+fn to_range(text_range: mq_lang::Range) -> Range {
+    Range::new(
+        Position::new(text_range.start.line - 1, (text_range.start.column - 1) as u32),
+        Position::new(text_range.end.line - 1, (text_range.end.column - 1) as u32),
+    )
+}
+
+/// Returns `true` if `symbol` is the target of an assignment such as `x = 1` or `x += 1`.
+fn is_assignment_target(hir: &mq_hir::Hir, symbol: &mq_hir::Symbol) -> bool {
+    let Some(parent) = symbol.parent.and_then(|parent| hir.symbol(parent)) else {
+        return false;
+    };
+
+    matches!(parent.kind, mq_hir::SymbolKind::Assign)
+        && match (symbol.source.text_range, parent.source.text_range) {
+            (Some(target), Some(assign)) => target.start < assign.start,
+            _ => false,
+        }
+}
+
+/// Highlights every occurrence of the symbol at `position` in the current document.
+///
+/// The definition and assignments are reported as writes, all other references as reads.
+pub(crate) fn response(hir: Arc<RwLock<mq_hir::Hir>>, url: Url, position: Position) -> Option<Vec<DocumentHighlight>> {
+    let hir_guard = hir.read().unwrap();
+    let source_id = hir_guard.source_by_url(&url)?;
+
+    let (symbol_id, symbol) = hir_guard.find_symbol_in_position(
+        source_id,
+        mq_lang::Position::new(position.line + 1, (position.character + 1) as usize),
+    )?;
+
+    let def_id = match symbol.kind {
+        mq_hir::SymbolKind::Call
+        | mq_hir::SymbolKind::Ref
+        | mq_hir::SymbolKind::CallDynamic
+        | mq_hir::SymbolKind::Argument
+        | mq_hir::SymbolKind::QualifiedAccess => hir_guard.resolve_reference_symbol(symbol_id)?,
+        _ => symbol_id,
+    };
+    let def_symbol = hir_guard.symbol(def_id)?.clone();
+
+    let definition = (def_symbol, DocumentHighlightKind::WRITE);
+    let references = hir_guard.references(def_id).into_iter().map(|(_, symbol)| {
+        let kind = if is_assignment_target(&hir_guard, &symbol) {
+            DocumentHighlightKind::WRITE
+        } else {
+            DocumentHighlightKind::READ
+        };
+        (symbol, kind)
+    });
+
+    let highlights = std::iter::once(definition)
+        .chain(references)
+        .filter(|(symbol, _)| symbol.source.source_id == Some(source_id))
+        .filter_map(|(symbol, kind)| {
+            symbol.source.text_range.map(|text_range| DocumentHighlight {
+                range: to_range(text_range),
+                kind: Some(kind),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if highlights.is_empty() { None } else { Some(highlights) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mq_hir::Hir;
+
+    fn setup(code: &str) -> (Arc<RwLock<Hir>>, Url) {
+        let mut hir = Hir::default();
+        let url = Url::parse("file:///test.mq").unwrap();
+        hir.add_code(Some(url.clone()), code);
+        (Arc::new(RwLock::new(hir)), url)
+    }
+
+    fn kinds(highlights: &[DocumentHighlight]) -> Vec<(u32, DocumentHighlightKind)> {
+        let mut kinds = highlights
+            .iter()
+            .map(|highlight| (highlight.range.start.character, highlight.kind.unwrap()))
+            .collect::<Vec<_>>();
+        kinds.sort_by_key(|(character, _)| *character);
+        kinds
+    }
+
+    #[test]
+    fn test_highlight_function_from_call_site() {
+        let (hir, url) = setup("def func1(): 1; | func1() | func1()");
+
+        let highlights = response(hir, url, Position::new(0, 20)).unwrap();
+
+        assert_eq!(
+            kinds(&highlights),
+            vec![
+                (4, DocumentHighlightKind::WRITE),
+                (18, DocumentHighlightKind::READ),
+                (28, DocumentHighlightKind::READ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_variable_assignment() {
+        let (hir, url) = setup("var x = 1 | x = x + 1 | x");
+
+        let highlights = response(hir, url, Position::new(0, 4)).unwrap();
+
+        assert_eq!(
+            kinds(&highlights),
+            vec![
+                (4, DocumentHighlightKind::WRITE),
+                (12, DocumentHighlightKind::WRITE),
+                (16, DocumentHighlightKind::READ),
+                (24, DocumentHighlightKind::READ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_highlight_parameter_within_function() {
+        let (hir, url) = setup("def f(x): x + 1; | let x = 2 | x");
+
+        let highlights = response(hir, url, Position::new(0, 6)).unwrap();
+
+        assert_eq!(
+            kinds(&highlights),
+            vec![(6, DocumentHighlightKind::WRITE), (10, DocumentHighlightKind::READ)]
+        );
+    }
+
+    #[test]
+    fn test_no_highlight_without_symbol() {
+        let (hir, url) = setup("let x = 1");
+        assert!(response(hir, url, Position::new(5, 5)).is_none());
+    }
+}
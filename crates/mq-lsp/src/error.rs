@@ -32,22 +32,57 @@ fn lint_severity(severity: mq_lint::Severity) -> ls_types::DiagnosticSeverity {
     }
 }
 
+/// Type errors that describe code that runs but may misbehave are reported as warnings.
+fn type_error_severity(type_error: &mq_check::TypeError) -> ls_types::DiagnosticSeverity {
+    match type_error {
+        mq_check::TypeError::NullablePropagation { .. }
+        | mq_check::TypeError::UnreachableCode { .. }
+        | mq_check::TypeError::DynamicConstruct {
+            strictness: mq_check::Strictness::Warn,
+            ..
+        } => ls_types::DiagnosticSeverity::WARNING,
+        _ => ls_types::DiagnosticSeverity::ERROR,
+    }
+}
+
 impl From<&LspError> for ls_types::Diagnostic {
     fn from(error: &LspError) -> Self {
         match error {
-            LspError::SyntaxError((message, range)) => {
-                ls_types::Diagnostic::new_simple(lsp_range(*range), message.to_string())
-            }
-            LspError::TypeError(type_error) => match type_error.location() {
-                Some(range) => ls_types::Diagnostic::new_simple(lsp_range(range), type_error.to_string()),
-                None => ls_types::Diagnostic::new_simple(
+            LspError::SyntaxError((message, range)) => ls_types::Diagnostic::new(
+                lsp_range(*range),
+                Some(ls_types::DiagnosticSeverity::ERROR),
+                None,
+                Some("mq".to_string()),
+                message.to_string(),
+                None,
+                None,
+            ),
+            LspError::TypeError(type_error) => {
+                let range = type_error.location().map(lsp_range).unwrap_or_else(|| {
                     ls_types::Range::new(
                         ls_types::Position { line: 0, character: 0 },
                         ls_types::Position { line: 0, character: 1 },
-                    ),
-                    type_error.to_string(),
-                ),
-            },
+                    )
+                });
+                let message = match miette::Diagnostic::help(type_error) {
+                    Some(help) => format!("{type_error} (help: {help})"),
+                    None => type_error.to_string(),
+                };
+                let code = miette::Diagnostic::code(type_error).map(|code| {
+                    let code = code.to_string();
+                    NumberOrString::String(code.strip_prefix("typechecker::").unwrap_or(&code).to_string())
+                });
+                ls_types::Diagnostic::new(
+                    range,
+                    Some(type_error_severity(type_error)),
+                    code,
+                    Some("mq-check".to_string()),
+                    message,
+                    None,
+                    matches!(type_error, mq_check::TypeError::UnreachableCode { .. })
+                        .then(|| vec![ls_types::DiagnosticTag::UNNECESSARY]),
+                )
+            }
             LspError::LintWarning(diagnostic) => {
                 let range = diagnostic.range.map(lsp_range).unwrap_or_else(|| {
                     ls_types::Range::new(
@@ -76,6 +111,7 @@ impl From<&LspError> for ls_types::Diagnostic {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rstest::rstest;
 
     #[test]
     fn lint_warning_carries_rule_id_source_and_severity() {
@@ -102,6 +138,90 @@ mod tests {
         assert_eq!(lsp_diagnostic.range.start.character, 4);
     }
 
+    #[test]
+    fn type_error_carries_code_source_and_help() {
+        let type_error = mq_check::TypeError::WrongArity {
+            expected: 1,
+            found: 2,
+            span: None,
+            location: Some(mq_lang::Range {
+                start: mq_lang::Position { line: 2, column: 3 },
+                end: mq_lang::Position { line: 2, column: 8 },
+            }),
+            context: Some("remove the extra argument".to_string()),
+        };
+
+        let lsp_diagnostic: ls_types::Diagnostic = (&LspError::TypeError(type_error)).into();
+
+        assert_eq!(lsp_diagnostic.severity, Some(ls_types::DiagnosticSeverity::ERROR));
+        assert_eq!(
+            lsp_diagnostic.code,
+            Some(NumberOrString::String("wrong_arity".to_string()))
+        );
+        assert_eq!(lsp_diagnostic.source, Some("mq-check".to_string()));
+        assert!(lsp_diagnostic.message.contains("expected 1, found 2"));
+        assert!(lsp_diagnostic.message.contains("help: remove the extra argument"));
+        assert_eq!(lsp_diagnostic.range.start.line, 1);
+        assert_eq!(lsp_diagnostic.range.start.character, 2);
+        assert!(lsp_diagnostic.tags.is_none());
+    }
+
+    #[test]
+    fn unreachable_code_is_an_unnecessary_warning() {
+        let type_error = mq_check::TypeError::UnreachableCode {
+            reason: "x is always a string".to_string(),
+            span: None,
+            location: None,
+        };
+
+        let lsp_diagnostic: ls_types::Diagnostic = (&LspError::TypeError(type_error)).into();
+
+        assert_eq!(lsp_diagnostic.severity, Some(ls_types::DiagnosticSeverity::WARNING));
+        assert_eq!(
+            lsp_diagnostic.code,
+            Some(NumberOrString::String("unreachable_code".to_string()))
+        );
+        assert_eq!(lsp_diagnostic.tags, Some(vec![ls_types::DiagnosticTag::UNNECESSARY]));
+    }
+
+    #[rstest]
+    #[case(mq_check::Strictness::Warn, ls_types::DiagnosticSeverity::WARNING)]
+    #[case(mq_check::Strictness::Strict, ls_types::DiagnosticSeverity::ERROR)]
+    fn dynamic_construct_severity_follows_strictness(
+        #[case] strictness: mq_check::Strictness,
+        #[case] expected: ls_types::DiagnosticSeverity,
+    ) {
+        let type_error = mq_check::TypeError::DynamicConstruct {
+            construct: "untyped dict access on `x`".to_string(),
+            strictness,
+            span: None,
+            location: None,
+            context: None,
+        };
+
+        let lsp_diagnostic: ls_types::Diagnostic = (&LspError::TypeError(type_error)).into();
+
+        assert_eq!(lsp_diagnostic.severity, Some(expected));
+        assert_eq!(
+            lsp_diagnostic.code,
+            Some(NumberOrString::String("dynamic_construct".to_string()))
+        );
+    }
+
+    #[test]
+    fn syntax_error_is_an_error_from_mq() {
+        let range = mq_lang::Range {
+            start: mq_lang::Position { line: 1, column: 1 },
+            end: mq_lang::Position { line: 1, column: 2 },
+        };
+
+        let lsp_diagnostic: ls_types::Diagnostic =
+            (&LspError::SyntaxError(("Unexpected token".to_string(), range))).into();
+
+        assert_eq!(lsp_diagnostic.severity, Some(ls_types::DiagnosticSeverity::ERROR));
+        assert_eq!(lsp_diagnostic.source, Some("mq".to_string()));
+    }
+
     #[test]
     fn lint_warning_without_range_falls_back_to_origin() {
         let diagnostic = mq_lint::Diagnostic::new(
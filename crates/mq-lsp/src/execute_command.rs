@@ -62,7 +62,11 @@ pub(crate) fn response(params: ExecuteCommandParams) -> jsonrpc::Result<Option<s
     }
 }
 
-fn execute(code: &str, input: &str, input_format: Option<&str>) -> jsonrpc::Result<Option<serde_json::Value>> {
+pub(crate) fn execute(
+    code: &str,
+    input: &str,
+    input_format: Option<&str>,
+) -> jsonrpc::Result<Option<serde_json::Value>> {
     let mut engine = mq_lang::DefaultEngine::default();
     let input =
         match input_format.unwrap_or("markdown") {
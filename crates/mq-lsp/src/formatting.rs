@@ -0,0 +1,194 @@
+use tower_lsp_server::ls_types::{Position, Range, TextEdit};
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Formats the lines touched by `range`.
+///
+/// The range is widened to whole lines so that the formatter sees complete expressions, and
+/// the formatted lines keep the indentation of the first selected line so that a selection
+/// inside a function body stays nested. Returns `Ok(None)` if the range is outside the document.
+pub(crate) fn range_edits(
+    text: &str,
+    range: Range,
+    config: mq_formatter::FormatterConfig,
+) -> Result<Option<Vec<TextEdit>>, mq_lang::CstErrorReporter> {
+    let lines: Vec<&str> = text.lines().collect();
+    let start_line = range.start.line as usize;
+    // A selection that ends at the start of a line does not include that line.
+    let end_line = if range.end.character == 0 && range.end.line > range.start.line {
+        range.end.line as usize - 1
+    } else {
+        range.end.line as usize
+    };
+
+    if start_line > end_line || end_line >= lines.len() {
+        return Ok(None);
+    }
+
+    let selected = &lines[start_line..=end_line];
+    let base_indent = selected
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indent_of(line))
+        .min()
+        .unwrap_or_default();
+    let code = selected
+        .iter()
+        .map(|line| line.get(base_indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let formatted = mq_formatter::Formatter::new(Some(config)).format(&code)?;
+    let new_text = formatted
+        .trim_end()
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", " ".repeat(base_indent), line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    if new_text == selected.join("\n") {
+        return Ok(Some(Vec::new()));
+    }
+
+    Ok(Some(vec![TextEdit {
+        range: Range::new(
+            Position::new(start_line as u32, 0),
+            Position::new(end_line as u32, lines[end_line].chars().count() as u32),
+        ),
+        new_text,
+    }]))
+}
+
+/// Re-indents the current line after a newline or a leading `|` is typed.
+///
+/// The indentation follows the previous non-blank line: one level deeper after a line
+/// ending with `:` or `do`, one level shallower after a line ending with `;` or `end`,
+/// and the same otherwise, matching how the formatter lays out pipelines.
+pub(crate) fn on_type_edits(text: &str, position: Position, ch: &str, indent_width: usize) -> Vec<TextEdit> {
+    let lines: Vec<&str> = text.split('\n').collect();
+    let line_index = position.line as usize;
+    let Some(line) = lines.get(line_index) else {
+        return Vec::new();
+    };
+
+    let leading = line.chars().take_while(|c| c.is_whitespace()).collect::<String>();
+    let rest = &line[leading.len()..];
+    let applies = match ch {
+        "\n" => true,
+        "|" => rest.starts_with('|'),
+        _ => false,
+    };
+    if !applies {
+        return Vec::new();
+    }
+
+    let Some(previous) = lines[..line_index].iter().rev().find(|line| !line.trim().is_empty()) else {
+        return Vec::new();
+    };
+    let previous_indent = indent_of(previous);
+    let previous = previous.trim_end();
+    let indent = if previous.ends_with(':') || previous.ends_with(" do") || previous == "do" {
+        previous_indent + indent_width
+    } else if previous.ends_with(';') || previous.ends_with(" end") || previous.trim_start() == "end" {
+        previous_indent.saturating_sub(indent_width)
+    } else {
+        previous_indent
+    };
+
+    if leading == " ".repeat(indent) {
+        return Vec::new();
+    }
+
+    vec![TextEdit {
+        range: Range::new(
+            Position::new(position.line, 0),
+            Position::new(position.line, leading.chars().count() as u32),
+        ),
+        new_text: " ".repeat(indent),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn apply(text: &str, edits: &[TextEdit]) -> String {
+        let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+        for edit in edits.iter().rev() {
+            let start = edit.range.start;
+            let end = edit.range.end;
+            let prefix = lines[start.line as usize][..start.character as usize].to_string();
+            let suffix = lines[end.line as usize][end.character as usize..].to_string();
+            let replaced = format!("{}{}{}", prefix, edit.new_text, suffix);
+            lines.splice(
+                start.line as usize..=end.line as usize,
+                replaced.split('\n').map(str::to_string).collect::<Vec<_>>(),
+            );
+        }
+        lines.join("\n")
+    }
+
+    #[rstest]
+    #[case::single_line("def v():1;", (0, 0), (0, 10), "def v(): 1;")]
+    #[case::partial_line_widened("def v():1;\ndef w():2;", (1, 4), (1, 6), "def v():1;\ndef w(): 2;")]
+    #[case::keeps_nesting(
+        "def f(x):\n  let y  =  x\n  | y;",
+        (1, 0),
+        (1, 13),
+        "def f(x):\n  let y = x\n  | y;"
+    )]
+    #[case::end_at_line_start("let a=1\n| a\n", (0, 0), (1, 0), "let a = 1\n| a\n")]
+    fn test_range_edits(
+        #[case] text: &str,
+        #[case] start: (u32, u32),
+        #[case] end: (u32, u32),
+        #[case] expected: &str,
+    ) {
+        let range = Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1));
+        let edits = range_edits(text, range, Default::default()).unwrap().unwrap();
+        assert_eq!(apply(text, &edits), expected);
+    }
+
+    #[test]
+    fn test_range_edits_invalid_code() {
+        let range = Range::new(Position::new(0, 0), Position::new(0, 4));
+        assert!(range_edits("add(", range, Default::default()).is_err());
+    }
+
+    #[test]
+    fn test_range_edits_already_formatted() {
+        let range = Range::new(Position::new(0, 0), Position::new(0, 11));
+        assert_eq!(
+            range_edits("def v(): 1;", range, Default::default()).unwrap(),
+            Some(Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_range_edits_outside_document() {
+        let range = Range::new(Position::new(3, 0), Position::new(4, 0));
+        assert_eq!(range_edits("def v(): 1;", range, Default::default()).unwrap(), None);
+    }
+
+    #[rstest]
+    #[case::after_colon("def f(x):\n", (1, 0), "\n", "def f(x):\n  ")]
+    #[case::after_do("if (x) do\n", (1, 0), "\n", "if (x) do\n  ")]
+    #[case::keeps_body_indent("def f(x):\n  let y = x\n", (2, 0), "\n", "def f(x):\n  let y = x\n  ")]
+    #[case::after_semicolon("def f(x):\n  x;\n    ", (2, 4), "\n", "def f(x):\n  x;\n")]
+    #[case::leading_pipe("def f(x):\n  let y = x\n|", (2, 1), "|", "def f(x):\n  let y = x\n  |")]
+    #[case::pipe_mid_line("def f(x):\n  x |", (1, 5), "|", "def f(x):\n  x |")]
+    #[case::first_line("\n", (0, 0), "\n", "\n")]
+    fn test_on_type_edits(#[case] text: &str, #[case] position: (u32, u32), #[case] ch: &str, #[case] expected: &str) {
+        let edits = on_type_edits(text, Position::new(position.0, position.1), ch, 2);
+        assert_eq!(apply(text, &edits), expected);
+    }
+}
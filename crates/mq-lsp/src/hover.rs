@@ -4,38 +4,17 @@ use itertools::Itertools;
 use tower_lsp_server::ls_types::{Hover, HoverContents, MarkupContent, MarkupKind, Position, Range};
 use url::Url;
 
-/// Returns `true` if a doc line is a deprecation marker.
-///
-/// A line is considered a deprecation marker if its trimmed content starts
-/// with `deprecated` (case-insensitive) followed by `:`, a space, or end of string.
-/// This avoids false-positives for lines that merely *mention* "deprecated".
-fn is_deprecated_marker(text: &str) -> bool {
-    let trimmed = text.trim();
-    let lower = trimmed.to_lowercase();
-    lower == "deprecated" || lower.starts_with("deprecated:") || lower.starts_with("deprecated ")
-}
-
-/// Extracts the human-readable message from a deprecation marker line, if any.
-///
-/// For example `"deprecated: use foo instead"` returns `Some("use foo instead")`.
-fn extract_deprecated_message(text: &str) -> Option<String> {
-    let after_colon = text.trim().split_once(':')?.1.trim();
-    if after_colon.is_empty() {
-        None
-    } else {
-        Some(after_colon.to_string())
-    }
-}
-
 /// Builds a Markdown hover string from a kind label, name, signature, doc comments,
 /// deprecation status, and optional parameter list.
 ///
 /// The layout is:
 /// - A heading with the symbol name and kind (e.g. `## \`len\` — function`)
 /// - A fenced `mq` code block containing the full signature
-/// - An optional blockquote deprecation notice (when `deprecated` is `true`)
-/// - An optional `---` separator followed by non-deprecated doc lines
-/// - An optional `### Parameters` section listing each parameter
+/// - An optional blockquote deprecation notice (when `deprecated` is `true` or the docs
+///   have a deprecation tag)
+/// - An optional `---` separator followed by the doc summary
+/// - An optional `### Parameters` section listing each parameter with its `@param` description
+/// - Optional `### Returns` and `### Examples` sections from the `@return` and `@example` tags
 fn format_hover_content(
     kind_label: &str,
     name: &str,
@@ -44,45 +23,49 @@ fn format_hover_content(
     deprecated: bool,
     params: &[mq_hir::ParamInfo],
 ) -> String {
+    let doc = mq_hir::DocComment::parse(docs.iter().map(|(_, text)| text.as_str()));
     let mut sections: Vec<String> = Vec::new();
 
     sections.push(format!("### `{}` — {}", name, kind_label));
     sections.push(format!("```mq\n{}\n```", signature));
 
-    if deprecated {
-        let dep_msg = docs
-            .iter()
-            .find(|(_, text)| is_deprecated_marker(text))
-            .and_then(|(_, text)| extract_deprecated_message(text));
+    if deprecated || doc.deprecated.is_some() {
+        let deprecation = doc.deprecated.unwrap_or_default();
+        let since = deprecation
+            .since
+            .map(|since| format!(" since {}", since))
+            .unwrap_or_default();
 
-        match dep_msg {
-            Some(msg) => sections.push(format!("> ⚠️ **Deprecated**: {}", msg)),
-            None => sections.push("> ⚠️ **Deprecated**".to_string()),
+        match deprecation.message {
+            Some(msg) => sections.push(format!("> ⚠️ **Deprecated**{}: {}", since, msg)),
+            None => sections.push(format!("> ⚠️ **Deprecated**{}", since)),
         }
     }
 
-    let doc_text = docs
-        .iter()
-        .filter(|(_, text)| !is_deprecated_marker(text))
-        .map(|(_, text)| text.as_str())
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    if !doc_text.trim().is_empty() {
+    if !doc.summary.is_empty() {
         sections.push("---".to_string());
-        sections.push(doc_text);
+        sections.push(doc.summary);
     }
 
     if !params.is_empty() {
         let param_items = params
             .iter()
             .map(|p| {
-                if p.is_variadic {
+                let item = if p.is_variadic {
                     format!("- `*{}` *(variadic)*", p.name)
                 } else if p.has_default {
                     format!("- `{}` *(optional)*", p.name)
                 } else {
                     format!("- `{}`", p.name)
+                };
+
+                match doc
+                    .params
+                    .iter()
+                    .find(|param| param.name == p.name && !param.description.is_empty())
+                {
+                    Some(param) => format!("{} — {}", item, param.description),
+                    None => item,
                 }
             })
             .collect::<Vec<_>>()
@@ -90,22 +73,40 @@ fn format_hover_content(
         sections.push(format!("### Parameters\n{}", param_items));
     }
 
+    if let Some(returns) = doc.returns.filter(|returns| !returns.is_empty()) {
+        sections.push(format!("### Returns\n{}", returns));
+    }
+
+    if !doc.examples.is_empty() {
+        let examples = doc
+            .examples
+            .iter()
+            .map(|example| format!("```mq\n{}\n```", example))
+            .join("\n");
+        sections.push(format!("### Examples\n{}", examples));
+    }
+
     sections.join("\n\n")
 }
 
+fn lsp_range(range: mq_lang::Range) -> Range {
+    Range::new(
+        Position::new(range.start.line - 1, (range.start.column - 1) as u32),
+        Position::new(range.end.line - 1, (range.end.column - 1) as u32),
+    )
+}
+
 pub(crate) fn response(
     hir: Arc<RwLock<mq_hir::Hir>>,
     url: Url,
-    type_env: Option<mq_check::TypeEnv>,
+    type_checker: Option<Arc<mq_check::TypeChecker>>,
     position: Position,
 ) -> Option<Hover> {
     let source = hir.read().unwrap().source_by_url(&url);
 
     if let Some(source) = source {
-        if let Some((symbol_id, symbol)) = hir.read().unwrap().find_symbol_in_position(
-            source,
-            mq_lang::Position::new(position.line + 1, (position.character + 1) as usize),
-        ) {
+        let mq_position = mq_lang::Position::new(position.line + 1, (position.character + 1) as usize);
+        if let Some((symbol_id, symbol)) = hir.read().unwrap().find_symbol_in_position(source, mq_position) {
             match &symbol.kind {
                 mq_hir::SymbolKind::Function(_)
                 | mq_hir::SymbolKind::Macro(_)
@@ -113,7 +114,7 @@ pub(crate) fn response(
                 | mq_hir::SymbolKind::DestructuringBinding
                 | mq_hir::SymbolKind::PatternVariable { .. } => {
                     let deprecated = symbol.is_deprecated();
-                    let type_scheme = type_env.as_ref().and_then(|env| env.get(&symbol_id));
+                    let type_scheme = type_checker.as_ref().and_then(|checker| checker.type_of(symbol_id));
                     let name = symbol.value.as_deref().unwrap_or_default();
 
                     let (kind_label, signature, params) = match &symbol.kind {
@@ -143,12 +144,22 @@ pub(crate) fn response(
                             kind: MarkupKind::Markdown,
                             value: format_hover_content(kind_label, name, &signature, &symbol.doc, deprecated, &params),
                         }),
-                        range: symbol.source.text_range.map(|text_range| {
-                            Range::new(
-                                Position::new(text_range.start.line - 1, (text_range.start.column - 1) as u32),
-                                Position::new(text_range.end.line - 1, (text_range.end.column - 1) as u32),
-                            )
+                        range: symbol.source.text_range.map(lsp_range),
+                    })
+                }
+                // Expressions have no documentation, so show the inferred type of the innermost one.
+                mq_hir::SymbolKind::Parameter
+                | mq_hir::SymbolKind::Ref
+                | mq_hir::SymbolKind::Call
+                | mq_hir::SymbolKind::Selector(_) => {
+                    let span_type = type_checker.as_ref()?.type_at(source, mq_position)?;
+
+                    Some(Hover {
+                        contents: HoverContents::Markup(MarkupContent {
+                            kind: MarkupKind::Markdown,
+                            value: format!("```mq\n{}\n```", span_type.type_scheme),
                         }),
+                        range: Some(lsp_range(span_type.range)),
                     })
                 }
                 _ => None,
@@ -169,33 +180,6 @@ mod tests {
 
     // --- unit tests for helpers ---
 
-    #[test]
-    fn test_is_deprecated_marker_variants() {
-        assert!(is_deprecated_marker("deprecated"));
-        assert!(is_deprecated_marker("deprecated:"));
-        assert!(is_deprecated_marker("deprecated: use foo instead"));
-        assert!(is_deprecated_marker("Deprecated: use foo instead"));
-        assert!(is_deprecated_marker("DEPRECATED: old"));
-        assert!(is_deprecated_marker("  deprecated:  "));
-        // NOT markers – these merely mention "deprecated"
-        assert!(!is_deprecated_marker("use this instead of the deprecated foo API"));
-        assert!(!is_deprecated_marker("see the deprecated section below"));
-    }
-
-    #[test]
-    fn test_extract_deprecated_message() {
-        assert_eq!(
-            extract_deprecated_message("deprecated: use foo instead"),
-            Some("use foo instead".to_string())
-        );
-        assert_eq!(extract_deprecated_message("deprecated:"), None);
-        assert_eq!(extract_deprecated_message("deprecated"), None);
-        assert_eq!(
-            extract_deprecated_message("  Deprecated:  trimmed message  "),
-            Some("trimmed message".to_string())
-        );
-    }
-
     #[test]
     fn test_format_hover_content_no_docs() {
         let docs: Vec<mq_hir::Doc> = vec![];
@@ -271,6 +255,31 @@ mod tests {
         assert!(result.contains("- `*rest` *(variadic)*"));
     }
 
+    #[test]
+    fn test_format_hover_content_with_tags() {
+        let docs: Vec<mq_hir::Doc> = [
+            " Adds two numbers.",
+            " @param a The first number",
+            " @param b The second number",
+            " @return The sum",
+            " @example",
+            " add(1, 2)",
+            " @deprecated since 0.5: use plus instead",
+        ]
+        .into_iter()
+        .map(|text| (Default::default(), text.to_string()))
+        .collect();
+        let params = vec![mq_hir::ParamInfo::from("a"), mq_hir::ParamInfo::from("b")];
+        let result = format_hover_content("function", "add", "add(a, b)", &docs, false, &params);
+
+        assert!(result.contains("> ⚠️ **Deprecated** since 0.5: use plus instead"));
+        assert!(result.contains("---\n\nAdds two numbers."));
+        assert!(result.contains("- `a` — The first number\n- `b` — The second number"));
+        assert!(result.contains("### Returns\nThe sum"));
+        assert!(result.contains("### Examples\n```mq\nadd(1, 2)\n```"));
+        assert!(!result.contains("@param"));
+    }
+
     // --- integration tests via response() ---
 
     #[test]
@@ -409,6 +418,32 @@ def old_func(x): x + 1;"#;
         }
     }
 
+    #[test]
+    fn test_hover_parameter_type() {
+        use mq_check::TypeChecker;
+
+        let mut hir = Hir::default();
+        let url = Url::parse("file:///test.mq").unwrap();
+        hir.add_code(Some(url.clone()), "map([1, 2], fn(x): x + 1;)");
+
+        let hir = Arc::new(RwLock::new(hir));
+        let mut checker = TypeChecker::new();
+        checker.check(&hir.read().unwrap());
+        let type_checker = Some(Arc::new(checker));
+
+        let hover = response(Arc::clone(&hir), url.clone(), type_checker, Position::new(0, 15)).unwrap();
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("Expected markup content");
+        };
+        assert_eq!(content.value, "```mq\nnumber\n```");
+        assert_eq!(
+            hover.range,
+            Some(Range::new(Position::new(0, 15), Position::new(0, 16)))
+        );
+
+        assert!(response(Arc::clone(&hir), url, None, Position::new(0, 15)).is_none());
+    }
+
     #[test]
     fn test_hover_with_type_info() {
         use mq_check::TypeChecker;
@@ -420,10 +455,10 @@ def old_func(x): x + 1;"#;
         let hir = Arc::new(RwLock::new(hir));
         let mut checker = TypeChecker::new();
         checker.check(&hir.read().unwrap());
-        let type_env = Some(checker.symbol_types().clone());
+        let type_checker = Some(Arc::new(checker));
 
         let position = Position::new(0, 5);
-        let hover = response(Arc::clone(&hir), url, type_env, position);
+        let hover = response(Arc::clone(&hir), url, type_checker, position);
 
         assert!(hover.is_some());
         let hover = hover.unwrap();
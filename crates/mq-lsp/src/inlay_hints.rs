@@ -5,29 +5,26 @@ use url::Url;
 
 /// Returns inlay hints for the visible range of a document.
 ///
-/// When type checking is enabled and a `TypeEnv` is provided, this function
+/// When type checking is enabled and a checked `TypeChecker` is provided, this function
 /// produces inlay hints showing the inferred type for variable bindings and
 /// function definitions within the given range.
 pub(crate) fn response(
     hir: Arc<RwLock<mq_hir::Hir>>,
     url: Url,
-    type_env: Option<mq_check::TypeEnv>,
+    type_checker: Option<Arc<mq_check::TypeChecker>>,
     range: Range,
 ) -> Option<Vec<InlayHint>> {
-    let type_env = type_env?;
-    let source = hir.read().unwrap().source_by_url(&url)?;
-
-    let hints: Vec<InlayHint> = hir
-        .read()
-        .unwrap()
-        .symbols()
-        .filter_map(|(symbol_id, symbol)| {
-            // Only process symbols from this source file
-            if symbol.source.source_id != Some(source) {
-                return None;
-            }
-
-            let text_range = symbol.source.text_range.as_ref()?;
+    let type_checker = type_checker?;
+    let hir = hir.read().unwrap();
+    let source = hir.source_by_url(&url)?;
+
+    let hints: Vec<InlayHint> = type_checker
+        .types_by_span()
+        .iter()
+        // Only process symbols from this source file
+        .filter(|span_type| span_type.source_id == source)
+        .filter_map(|span_type| {
+            let text_range = &span_type.range;
 
             // Convert mq 1-based positions to LSP 0-based positions
             let symbol_line = text_range.start.line.saturating_sub(1);
@@ -42,9 +39,9 @@ pub(crate) fn response(
                 return None;
             }
 
-            let type_scheme = type_env.get(&symbol_id)?;
+            let type_scheme = &span_type.type_scheme;
 
-            match &symbol.kind {
+            match &hir.symbol(span_type.symbol_id)?.kind {
                 mq_hir::SymbolKind::Parameter
                 | mq_hir::SymbolKind::Variable
                 | mq_hir::SymbolKind::DestructuringBinding
@@ -87,7 +84,7 @@ mod tests {
     }
 
     #[test]
-    fn test_no_hints_without_type_env() {
+    fn test_no_hints_without_type_checker() {
         let mut hir = Hir::default();
         let url = Url::parse("file:///test.mq").unwrap();
         hir.add_code(Some(url.clone()), "let x = 1 | x");
@@ -105,9 +102,9 @@ mod tests {
         let hir = Arc::new(RwLock::new(hir));
         let mut checker = TypeChecker::new();
         checker.check(&hir.read().unwrap());
-        let type_env = Some(checker.symbol_types().clone());
+        let type_checker = Some(Arc::new(checker));
 
-        let hints = response(Arc::clone(&hir), url, type_env, make_full_range());
+        let hints = response(Arc::clone(&hir), url, type_checker, make_full_range());
         assert!(hints.is_some());
         let hints = hints.unwrap();
         assert!(!hints.is_empty(), "Should produce at least one inlay hint");
@@ -129,11 +126,11 @@ mod tests {
         let hir = Arc::new(RwLock::new(hir));
         let mut checker = TypeChecker::new();
         checker.check(&hir.read().unwrap());
-        let type_env = Some(checker.symbol_types().clone());
+        let type_checker = Some(Arc::new(checker));
 
         // Range that doesn't cover line 0
         let restricted_range = Range::new(Position::new(5, 0), Position::new(10, 100));
-        let hints = response(Arc::clone(&hir), url, type_env, restricted_range);
+        let hints = response(Arc::clone(&hir), url, type_checker, restricted_range);
         let hints = hints.unwrap_or_default();
         assert!(hints.is_empty(), "No hints should appear outside the range");
     }
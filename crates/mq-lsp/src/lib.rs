@@ -9,23 +9,29 @@
 //! - **Completion**: Offers code completion suggestions.
 //! - **Go To Definition**: Allows navigation to the definition of symbols.
 //! - **References**: Finds all references to a symbol.
+//! - **Document Highlight**: Highlights the occurrences of the symbol under the cursor, marking definitions and assignments as writes.
 //! - **Document Symbols**: Lists all symbols in a document.
 //! - **Semantic Tokens**: Provides semantic tokens for syntax highlighting.
-//! - **Formatting**: Formats the document according to the MDQ language formatting rules.
+//! - **Formatting**: Formats the whole document or a selected range, and re-indents lines as `|` and newlines are typed.
 //! - **Code Actions**: Suggests quick fixes, such as adding a missing `include` for an unresolved standard library function call, or a missing `import` for an unresolved `module::func()` qualified access.
 //! - **Refactoring**: Extracts a selected expression/pipeline into a variable or function, and inlines a variable or function back into its call sites.
 //! - **Rename**: Renames a symbol and all of its references across files.
+//! - **Code Lens**: Shows a lens on each expression of the query pipeline that evaluates it, up to that expression, against a sample input file via the custom `mq/runQuery` request.
+//! - **Workspace Configuration**: Applies module paths, builtin preloading, type checking, lint, and formatter settings from `initializationOptions` and `workspace/didChangeConfiguration`.
 //!
 //! # Usage
 //!
 //! To use this LSP server, you need to integrate it with an LSP client. The server reads from stdin and writes to stdout, making it compatible with various editors and IDEs that support LSP.
 pub mod capabilities;
 pub mod code_action;
+pub mod code_lens;
 pub mod completions;
+pub mod document_highlight;
 pub mod document_symbol;
 pub mod error;
 pub mod execute_command;
 pub mod folding_range;
+pub mod formatting;
 pub mod goto_definition;
 pub mod hover;
 pub mod inlay_hints;
@@ -34,6 +40,7 @@ pub mod references;
 pub mod rename;
 pub mod semantic_tokens;
 pub mod server;
+pub mod settings;
 pub mod signature_help;
 pub mod workspace_symbol;
 
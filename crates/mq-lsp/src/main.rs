@@ -6,11 +6,14 @@ use crate::server::LspConfig;
 
 pub mod capabilities;
 pub mod code_action;
+pub mod code_lens;
 pub mod completions;
+pub mod document_highlight;
 pub mod document_symbol;
 pub mod error;
 pub mod execute_command;
 pub mod folding_range;
+pub mod formatting;
 pub mod goto_definition;
 pub mod hover;
 pub mod inlay_hints;
@@ -19,6 +22,7 @@ pub mod references;
 pub mod rename;
 pub mod semantic_tokens;
 pub mod server;
+pub mod settings;
 pub mod signature_help;
 pub mod workspace_symbol;
 
@@ -37,6 +41,10 @@ struct Cli {
 
     #[clap(flatten)]
     lint: LintArgs,
+
+    /// Input file that the "Run Query" code lens evaluates queries against
+    #[arg(long, value_name = "FILE")]
+    sample_input: Option<PathBuf>,
 }
 
 #[derive(Clone, Debug, clap::Args, Default)]
@@ -49,6 +57,11 @@ struct TypeCheckArgs {
     #[arg(long, default_value_t = false)]
     strict_array: bool,
 
+    /// How to report dynamic constructs such as untyped dict access and `type()` branching:
+    /// `off`, `warn`, or `strict` (requires `--enable-type-checking`)
+    #[arg(long, default_value_t = mq_check::Strictness::Off)]
+    strictness: mq_check::Strictness,
+
     /// Enable tuple typing for heterogeneous arrays (e.g., [1, "hello"] → (number, string))
     #[arg(long, default_value_t = false)]
     tuple: bool,
@@ -70,6 +83,7 @@ async fn main() {
     let cli = Cli::parse();
     let type_check_config = mq_check::TypeCheckerOptions {
         strict_array: cli.type_check.strict_array,
+        strictness: cli.type_check.strictness,
         ..Default::default()
     };
 
@@ -84,6 +98,7 @@ async fn main() {
         type_check_config,
         cli.lint.enable_lint,
         lint_config,
-    );
+    )
+    .with_sample_input(cli.sample_input);
     server::start(config).await;
 }
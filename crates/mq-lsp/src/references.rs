@@ -7,6 +7,24 @@ use bimap::BiMap;
 use tower_lsp_server::ls_types::{self, Location, Position, Range};
 use url::Url;
 
+/// Returns the URI of a source that the client can open: a document it has opened, or a
+/// module file on disk that a document includes.
+pub(crate) fn source_uri(
+    hir: &mq_hir::Hir,
+    source_id: mq_hir::SourceId,
+    source_map: &BiMap<String, mq_hir::SourceId>,
+) -> Option<ls_types::Uri> {
+    source_map
+        .get_by_right(&source_id)
+        .cloned()
+        .or_else(|| {
+            hir.url_by_source(&source_id)
+                .filter(|url| url.to_file_path().is_ok_and(|path| path.is_file()))
+                .map(|url| url.to_string())
+        })
+        .and_then(|url| ls_types::Uri::from_str(&url).ok())
+}
+
 pub(crate) fn response(
     hir: Arc<RwLock<mq_hir::Hir>>,
     url: Url,
@@ -14,44 +32,46 @@ pub(crate) fn response(
     source_map: &BiMap<String, mq_hir::SourceId>,
 ) -> Option<Vec<Location>> {
     let hir_guard = hir.read().unwrap();
-    let source = hir_guard.source_by_url(&url);
-
-    if let Some(source) = source {
-        if let Some((symbol_id, _)) = hir_guard.find_symbol_in_position(
-            source,
-            mq_lang::Position::new(position.line + 1, (position.character + 1) as usize),
-        ) {
-            let locations = hir_guard
-                .references(symbol_id)
-                .iter()
-                .filter_map(|(_, symbol)| {
-                    symbol.source.text_range.and_then(|text_range| {
-                        symbol.source.source_id.and_then(|id| {
-                            source_map.get_by_right(&id).map(|url| Location {
-                                uri: ls_types::Uri::from_str(url).unwrap(),
-                                range: Range {
-                                    start: Position {
-                                        line: text_range.start.line - 1,
-                                        character: (text_range.start.column - 1) as u32,
-                                    },
-                                    end: Position {
-                                        line: text_range.end.line - 1,
-                                        character: (text_range.end.column - 1) as u32,
-                                    },
-                                },
-                            })
-                        })
-                    })
-                })
-                .collect::<Vec<_>>();
-
-            if locations.is_empty() { None } else { Some(locations) }
-        } else {
-            None
-        }
-    } else {
-        None
-    }
+    let source = hir_guard.source_by_url(&url)?;
+    let (symbol_id, symbol) = hir_guard.find_symbol_in_position(
+        source,
+        mq_lang::Position::new(position.line + 1, (position.character + 1) as usize),
+    )?;
+
+    // References are recorded against the definition, which may live in an included module.
+    let def_id = match symbol.kind {
+        mq_hir::SymbolKind::Call
+        | mq_hir::SymbolKind::Ref
+        | mq_hir::SymbolKind::CallDynamic
+        | mq_hir::SymbolKind::Argument
+        | mq_hir::SymbolKind::QualifiedAccess => hir_guard.resolve_reference_symbol(symbol_id).unwrap_or(symbol_id),
+        _ => symbol_id,
+    };
+
+    let locations = hir_guard
+        .references(def_id)
+        .iter()
+        .filter_map(|(_, symbol)| {
+            let text_range = symbol.source.text_range?;
+            let uri = source_uri(&hir_guard, symbol.source.source_id?, source_map)?;
+
+            Some(Location {
+                uri,
+                range: Range {
+                    start: Position {
+                        line: text_range.start.line - 1,
+                        character: (text_range.start.column - 1) as u32,
+                    },
+                    end: Position {
+                        line: text_range.end.line - 1,
+                        character: (text_range.end.column - 1) as u32,
+                    },
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if locations.is_empty() { None } else { Some(locations) }
 }
 #[cfg(test)]
 mod tests {
@@ -92,4 +112,29 @@ mod tests {
         let locations = result.unwrap();
         assert_eq!(locations.len(), 1);
     }
+
+    #[test]
+    fn test_response_from_call_site_across_sources() {
+        let dir = std::env::temp_dir().join("mq_lsp_references_across_sources");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("shared.mq"), "def shared(): 1;").unwrap();
+
+        let resolver = mq_lang::DefaultModuleResolver::new(vec![dir.clone()]);
+        let mut hir = Hir::new(mq_lang::ModuleLoader::new(resolver));
+        let mut source_map = BiMap::new();
+        let code = "include \"shared\" | shared()";
+        for url in ["file:///a.mq", "file:///b.mq"] {
+            let (source_id, _) = hir.add_code(Some(Url::parse(url).unwrap()), code);
+            source_map.insert(url.to_string(), source_id);
+        }
+
+        let url = Url::parse("file:///a.mq").unwrap();
+        let locations = response(Arc::new(RwLock::new(hir)), url, Position::new(0, 20), &source_map).unwrap();
+
+        let mut uris = locations.iter().map(|l| l.uri.to_string()).collect::<Vec<_>>();
+        uris.sort();
+        assert_eq!(uris, vec!["file:///a.mq", "file:///b.mq"]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -1,11 +1,10 @@
 use std::{
     collections::HashMap,
-    str::FromStr,
     sync::{Arc, RwLock},
 };
 
 use bimap::BiMap;
-use tower_lsp_server::ls_types::{self, Position, Range, TextEdit, WorkspaceEdit};
+use tower_lsp_server::ls_types::{self, Position, PrepareRenameResponse, Range, TextEdit, WorkspaceEdit};
 use url::Url;
 
 fn to_range(text_range: mq_lang::Range) -> Range {
@@ -15,17 +14,14 @@ fn to_range(text_range: mq_lang::Range) -> Range {
     )
 }
 
-/// Renames the symbol at `position` and all of its references, across every
-/// source file the HIR knows about.
-pub(crate) fn response(
-    hir: Arc<RwLock<mq_hir::Hir>>,
-    url: Url,
+/// Returns the user-defined symbol that the name at `position` refers to, or `None`
+/// if there is nothing at `position` that can be renamed.
+fn renameable_symbol(
+    hir_guard: &mq_hir::Hir,
+    url: &Url,
     position: Position,
-    new_name: &str,
-    source_map: &BiMap<String, mq_hir::SourceId>,
-) -> Option<WorkspaceEdit> {
-    let hir_guard = hir.read().unwrap();
-    let source = hir_guard.source_by_url(&url)?;
+) -> Option<(mq_hir::SymbolId, mq_hir::Symbol)> {
+    let source = hir_guard.source_by_url(url)?;
 
     let (symbol_id, symbol) = hir_guard.find_symbol_in_position(
         source,
@@ -51,7 +47,47 @@ pub(crate) fn response(
         return None;
     }
 
-    let mut symbols_to_rename = vec![(def_id, def_symbol.clone())];
+    Some((def_id, def_symbol.clone()))
+}
+
+/// Checks that the symbol at `position` can be renamed, returning its range and
+/// current name for the client to use as the rename placeholder.
+pub(crate) fn prepare_response(
+    hir: Arc<RwLock<mq_hir::Hir>>,
+    url: Url,
+    position: Position,
+) -> Option<PrepareRenameResponse> {
+    let hir_guard = hir.read().unwrap();
+    let (def_id, def_symbol) = renameable_symbol(&hir_guard, &url, position)?;
+    let source_id = hir_guard.source_by_url(&url)?;
+    let position = mq_lang::Position::new(position.line + 1, (position.character + 1) as usize);
+
+    // The definition or reference under the cursor is the span the client will replace.
+    let text_range = std::iter::once(&def_symbol)
+        .chain(hir_guard.references(def_id).iter().map(|(_, symbol)| symbol))
+        .filter(|symbol| symbol.source.source_id == Some(source_id))
+        .filter_map(|symbol| symbol.source.text_range)
+        .find(|text_range| text_range.contains(&position))?;
+
+    Some(PrepareRenameResponse::RangeWithPlaceholder {
+        range: to_range(text_range),
+        placeholder: def_symbol.value?.to_string(),
+    })
+}
+
+/// Renames the symbol at `position` and all of its references, across every opened
+/// document and every module file on disk that they include.
+pub(crate) fn response(
+    hir: Arc<RwLock<mq_hir::Hir>>,
+    url: Url,
+    position: Position,
+    new_name: &str,
+    source_map: &BiMap<String, mq_hir::SourceId>,
+) -> Option<WorkspaceEdit> {
+    let hir_guard = hir.read().unwrap();
+    let (def_id, def_symbol) = renameable_symbol(&hir_guard, &url, position)?;
+
+    let mut symbols_to_rename = vec![(def_id, def_symbol)];
     symbols_to_rename.extend(hir_guard.references(def_id));
 
     let mut changes: HashMap<ls_types::Uri, Vec<TextEdit>> = HashMap::new();
@@ -63,10 +99,7 @@ pub(crate) fn response(
         let Some(source_id) = symbol.source.source_id else {
             continue;
         };
-        let Some(file_url) = source_map.get_by_right(&source_id) else {
-            continue;
-        };
-        let Ok(uri) = ls_types::Uri::from_str(file_url) else {
+        let Some(uri) = crate::references::source_uri(&hir_guard, source_id, source_map) else {
             continue;
         };
 
@@ -102,6 +135,47 @@ mod tests {
         (Arc::new(RwLock::new(hir)), url, source_map)
     }
 
+    #[test]
+    fn test_prepare_rename_from_call_site() {
+        let code = "def func1(): 1; | func1()";
+        let (hir, url, _) = setup(code);
+
+        let result = prepare_response(hir, url, Position::new(0, 20));
+
+        assert_eq!(
+            result,
+            Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: Range::new(Position::new(0, 18), Position::new(0, 23)),
+                placeholder: "func1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_prepare_rename_variable() {
+        let code = "let val1 = 1 | val1";
+        let (hir, url, _) = setup(code);
+
+        let result = prepare_response(hir, url, Position::new(0, 5));
+
+        assert_eq!(
+            result,
+            Some(PrepareRenameResponse::RangeWithPlaceholder {
+                range: Range::new(Position::new(0, 4), Position::new(0, 8)),
+                placeholder: "val1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_prepare_rename_rejects_builtin_and_unresolved() {
+        let (hir, url, _) = setup("\"hello\" | len");
+        assert_eq!(prepare_response(hir, url, Position::new(0, 11)), None);
+
+        let (hir, url, _) = setup("totally_unknown_fn()");
+        assert_eq!(prepare_response(hir, url, Position::new(0, 5)), None);
+    }
+
     #[test]
     fn test_rename_function_and_call_sites() {
         let code = "def func1(): 1; | func1()";
@@ -285,4 +359,46 @@ mod tests {
         assert_eq!(edits.len(), 1);
         assert_eq!(edits[0].new_text, "csv_load");
     }
+
+    #[test]
+    fn test_rename_updates_included_module_file() {
+        let dir = std::env::temp_dir().join("mq_lsp_rename_included_module");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("shared.mq"), "def shared(): 1;").unwrap();
+
+        let resolver = mq_lang::DefaultModuleResolver::new(vec![dir.clone()]);
+        let mut hir = Hir::new(mq_lang::ModuleLoader::new(resolver));
+        let mut source_map = BiMap::new();
+        let code = "include \"shared\" | shared()";
+        for url in ["file:///a.mq", "file:///b.mq"] {
+            let (source_id, _) = hir.add_code(Some(Url::parse(url).unwrap()), code);
+            source_map.insert(url.to_string(), source_id);
+        }
+
+        let url = Url::parse("file:///a.mq").unwrap();
+        let changes = response(
+            Arc::new(RwLock::new(hir)),
+            url,
+            Position::new(0, 20),
+            "common",
+            &source_map,
+        )
+        .unwrap()
+        .changes
+        .unwrap();
+
+        // Both call sites and the definition in the module file, which was never opened.
+        let module_uri = Url::from_file_path(std::path::absolute(dir.join("shared.mq")).unwrap()).unwrap();
+        let mut uris = changes.keys().map(|uri| uri.to_string()).collect::<Vec<_>>();
+        uris.sort();
+        let mut expected = vec![
+            "file:///a.mq".to_string(),
+            "file:///b.mq".to_string(),
+            module_uri.to_string(),
+        ];
+        expected.sort();
+        assert_eq!(uris, expected);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -9,9 +9,11 @@ use tower_lsp_server::ls_types::DocumentRangeFormattingParams;
 use url::Url;
 
 use crate::error::LspError;
+use crate::settings::Settings;
 use crate::{
-    capabilities, code_action, completions, document_symbol, execute_command, folding_range, goto_definition, hover,
-    inlay_hints, references, rename, semantic_tokens, signature_help, workspace_symbol,
+    capabilities, code_action, code_lens, completions, document_highlight, document_symbol, execute_command,
+    folding_range, formatting, goto_definition, hover, inlay_hints, references, rename, semantic_tokens,
+    signature_help, workspace_symbol,
 };
 use tower_lsp_server::{Client, LanguageServer, LspService, Server, jsonrpc, ls_types};
 
@@ -28,14 +30,17 @@ struct Backend {
     client: Client,
     hir: Arc<RwLock<mq_hir::Hir>>,
     source_map: RwLock<BiMap<String, mq_hir::SourceId>>,
-    type_env_map: DashMap<String, mq_check::TypeEnv>,
+    type_checker_map: DashMap<String, Arc<mq_check::TypeChecker>>,
     error_map: DashMap<String, Vec<LspError>>,
     text_map: DashMap<String, Arc<String>>,
-    config: LspConfig,
+    config: RwLock<LspConfig>,
 }
 
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: ls_types::InitializeParams) -> jsonrpc::Result<ls_types::InitializeResult> {
+    async fn initialize(&self, params: ls_types::InitializeParams) -> jsonrpc::Result<ls_types::InitializeResult> {
+        if let Some(options) = params.initialization_options {
+            self.update_settings(options).await;
+        }
         self.client
             .log_message(ls_types::MessageType::INFO, "Server initialized")
             .await;
@@ -49,6 +54,10 @@ impl LanguageServer for Backend {
         Ok(())
     }
 
+    async fn did_change_configuration(&self, params: ls_types::DidChangeConfigurationParams) {
+        self.update_settings(params.settings).await;
+    }
+
     async fn did_open(&self, params: ls_types::DidOpenTextDocumentParams) {
         self.on_change(to_url(&params.text_document.uri), params.text_document.text)
             .await;
@@ -74,7 +83,7 @@ impl LanguageServer for Backend {
         // Remove error information for the closed file
         self.error_map.remove(&uri_string);
         self.text_map.remove(&uri_string);
-        self.type_env_map.remove(&uri_string);
+        self.type_checker_map.remove(&uri_string);
 
         // Remove from source map
         self.source_map.write().unwrap().remove_by_left(&uri_string);
@@ -134,6 +143,29 @@ impl LanguageServer for Backend {
         ))
     }
 
+    async fn code_lens(&self, params: ls_types::CodeLensParams) -> jsonrpc::Result<Option<Vec<ls_types::CodeLens>>> {
+        let url = to_url(&params.text_document.uri);
+        let Some(text) = self.text_map.get(&url.to_string()).map(|text| Arc::clone(text.value())) else {
+            return Ok(None);
+        };
+
+        Ok(Some(code_lens::response(&url, &text)))
+    }
+
+    async fn document_highlight(
+        &self,
+        params: ls_types::DocumentHighlightParams,
+    ) -> jsonrpc::Result<Option<Vec<ls_types::DocumentHighlight>>> {
+        let url = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+
+        Ok(document_highlight::response(
+            Arc::clone(&self.hir),
+            to_url(&url),
+            position,
+        ))
+    }
+
     async fn document_symbol(
         &self,
         params: ls_types::DocumentSymbolParams,
@@ -188,13 +220,20 @@ impl LanguageServer for Backend {
         let url = params.text_document_position_params.text_document.uri;
         let position = params.text_document_position_params.position;
 
-        let type_env = if self.config.enable_type_checking {
-            self.type_env_map.get(&url.to_string()).map(|e| e.clone())
+        let type_checker = if self.config.read().unwrap().enable_type_checking {
+            self.type_checker_map
+                .get(&url.to_string())
+                .map(|e| Arc::clone(e.value()))
         } else {
             None
         };
 
-        Ok(hover::response(Arc::clone(&self.hir), to_url(&url), type_env, position))
+        Ok(hover::response(
+            Arc::clone(&self.hir),
+            to_url(&url),
+            type_checker,
+            position,
+        ))
     }
 
     async fn signature_help(
@@ -214,15 +253,18 @@ impl LanguageServer for Backend {
     }
 
     async fn inlay_hint(&self, params: ls_types::InlayHintParams) -> jsonrpc::Result<Option<Vec<ls_types::InlayHint>>> {
-        if !self.config.enable_type_checking {
+        if !self.config.read().unwrap().enable_type_checking {
             return Ok(None);
         }
         let url = to_url(&params.text_document.uri);
-        let type_env = self.type_env_map.get(&url.to_string()).map(|e| e.clone());
+        let type_checker = self
+            .type_checker_map
+            .get(&url.to_string())
+            .map(|e| Arc::clone(e.value()));
         Ok(inlay_hints::response(
             Arc::clone(&self.hir),
             url,
-            type_env,
+            type_checker,
             params.range,
         ))
     }
@@ -259,7 +301,10 @@ impl LanguageServer for Backend {
 
         let source_id = self.source_map.read().unwrap().get_by_left(&uri_string).copied();
         let source_text = self.text_map.get(&uri_string).map(|text| Arc::clone(text.value()));
-        let lint_config = self.config.enable_lint.then(|| self.config.lint_config.clone());
+        let lint_config = {
+            let config = self.config.read().unwrap();
+            config.enable_lint.then(|| config.lint_config.clone())
+        };
 
         Ok(code_action::response(
             Arc::clone(&self.hir),
@@ -271,6 +316,14 @@ impl LanguageServer for Backend {
         ))
     }
 
+    async fn prepare_rename(
+        &self,
+        params: ls_types::TextDocumentPositionParams,
+    ) -> jsonrpc::Result<Option<ls_types::PrepareRenameResponse>> {
+        let url = to_url(&params.text_document.uri);
+        Ok(rename::prepare_response(Arc::clone(&self.hir), url, params.position))
+    }
+
     async fn rename(&self, params: ls_types::RenameParams) -> jsonrpc::Result<Option<ls_types::WorkspaceEdit>> {
         let url = to_url(&params.text_document_position.text_document.uri);
         let position = params.text_document_position.position;
@@ -314,16 +367,12 @@ impl LanguageServer for Backend {
         };
 
         let text = Arc::clone(&self.text_map.get(&params.text_document.uri.to_string()).unwrap());
-        let formatted_text = tokio::task::spawn_blocking(move || {
-            mq_formatter::Formatter::new(Some(mq_formatter::FormatterConfig {
-                indent_width: 2,
-                ..Default::default()
-            }))
-            .format(&text)
-        })
-        .await
-        .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::InternalError))?
-        .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::ParseError))?;
+        let formatter_config = self.config.read().unwrap().formatter_config.clone();
+        let formatted_text =
+            tokio::task::spawn_blocking(move || mq_formatter::Formatter::new(Some(formatter_config)).format(&text))
+                .await
+                .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::InternalError))?
+                .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::ParseError))?;
 
         // End work done progress
         if let Some(progress) = progress {
@@ -369,75 +418,117 @@ impl LanguageServer for Backend {
             None
         };
 
-        let lines: Vec<&str> = text.lines().collect();
-        let start_line = params.range.start.line as usize;
-        let start_char = params.range.start.character as usize;
-        let end_line = params.range.end.line as usize;
-        let end_char = params.range.end.character as usize;
+        let range = params.range;
+        let formatter_config = self.config.read().unwrap().formatter_config.clone();
+        let edits = tokio::task::spawn_blocking(move || formatting::range_edits(&text, range, formatter_config))
+            .await
+            .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::InternalError))?
+            .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::ParseError))?;
 
-        if start_line >= lines.len() || end_line > lines.len() || start_line > end_line {
-            return Ok(None);
+        // End work done progress
+        if let Some(progress) = progress {
+            progress.finish_with_message("Range formatting complete").await;
         }
 
-        // Extract the text in the specified range (line and character based).
-        let mut selected = String::new();
-        if start_line == end_line {
-            // Single line selection
-            let line = lines.get(start_line).unwrap_or(&"");
-            let start = start_char.min(line.len());
-            let end = end_char.min(line.len());
-            selected.push_str(&line[start..end]);
-        } else {
-            // First line: from start_char to end of line
-            let first_line = lines.get(start_line).unwrap_or(&"");
-            let start = start_char.min(first_line.len());
-            selected.push_str(&first_line[start..]);
-            selected.push('\n');
-            // Middle lines: whole lines
-            for l in (start_line + 1)..end_line {
-                if let Some(line) = lines.get(l) {
-                    selected.push_str(line);
-                    selected.push('\n');
-                }
-            }
-            // Last line: from 0 to end_char
-            if let Some(last_line) = lines.get(end_line) {
-                let end = end_char.min(last_line.len());
-                selected.push_str(&last_line[..end]);
+        Ok(edits)
+    }
+
+    async fn on_type_formatting(
+        &self,
+        params: ls_types::DocumentOnTypeFormattingParams,
+    ) -> jsonrpc::Result<Option<Vec<ls_types::TextEdit>>> {
+        let uri = params.text_document_position.text_document.uri.to_string();
+        let Some(text) = self.text_map.get(&uri).map(|text| Arc::clone(&text)) else {
+            return Ok(None);
+        };
+
+        let indent_width = self.config.read().unwrap().formatter_config.indent_width;
+        let edits = formatting::on_type_edits(&text, params.text_document_position.position, &params.ch, indent_width);
+        Ok(if edits.is_empty() { None } else { Some(edits) })
+    }
+}
+
+impl Backend {
+    /// Applies client settings and re-analyzes open documents so diagnostics reflect them.
+    ///
+    /// Changing the module search paths rebuilds the HIR, since resolved modules are cached in it.
+    async fn update_settings(&self, value: serde_json::Value) {
+        let settings = match Settings::from_value(value) {
+            Ok(settings) => settings,
+            Err(e) => {
+                self.client
+                    .log_message(ls_types::MessageType::WARNING, format!("Invalid mq settings: {e}"))
+                    .await;
+                return;
             }
+        };
+
+        let (result, module_paths_changed, preload_builtins) = {
+            let mut config = self.config.write().unwrap();
+            let module_paths = config.module_paths.clone();
+            let result = config.apply(&settings);
+            (result, module_paths != config.module_paths, config.preload_builtins)
+        };
+        if let Err(e) = result {
+            self.client
+                .log_message(ls_types::MessageType::WARNING, format!("Invalid mq settings: {e}"))
+                .await;
         }
 
-        let formatted_range = tokio::task::spawn_blocking(move || {
-            mq_formatter::Formatter::new(Some(mq_formatter::FormatterConfig {
-                indent_width: 2,
-                ..Default::default()
-            }))
-            .format(&selected)
-        })
-        .await
-        .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::InternalError))?
-        .map_err(|_| jsonrpc::Error::new(jsonrpc::ErrorCode::ParseError))?;
+        if module_paths_changed {
+            let hir = new_hir(&self.config.read().unwrap().module_paths);
+            *self.hir.write().unwrap() = hir;
+            self.source_map.write().unwrap().clear();
+        }
+        if preload_builtins {
+            self.hir.write().unwrap().add_builtin();
+        }
 
-        // End work done progress
-        if let Some(progress) = progress {
-            progress.finish_with_message("Range formatting complete").await;
+        let documents = self
+            .text_map
+            .iter()
+            .map(|entry| (entry.key().clone(), Arc::clone(entry.value())))
+            .collect::<Vec<_>>();
+        for (uri, text) in documents {
+            let Ok(url) = Url::parse(&uri) else {
+                continue;
+            };
+            self.on_change(url.clone(), text.to_string()).await;
+            self.diagnostics(url, None).await;
         }
+    }
 
-        Ok(Some(vec![ls_types::TextEdit {
-            range: params.range,
-            new_text: formatted_range,
-        }]))
+    /// Handles the custom `mq/runQuery` request used by the "Run Query" code lens.
+    async fn run_query(&self, params: code_lens::RunQueryParams) -> jsonrpc::Result<code_lens::RunQueryResult> {
+        let url = to_url(&params.text_document.uri);
+        let text = self
+            .text_map
+            .get(&url.to_string())
+            .map(|text| Arc::clone(text.value()))
+            .ok_or_else(|| jsonrpc::Error::invalid_params(format!("Document is not open: {}", url)))?;
+        let input = params
+            .input
+            .or_else(|| self.config.read().unwrap().sample_input.clone())
+            .ok_or_else(|| jsonrpc::Error::invalid_params("No sample input file is configured"))?;
+
+        let code = match params.position {
+            Some(position) => code_lens::query_up_to(&text, position),
+            None => &text,
+        };
+        code_lens::run_query(&url, code, &input)
     }
-}
 
-impl Backend {
     async fn on_change(&self, uri: Url, text: String) {
-        let (nodes, errors) = if text.is_empty() {
-            (Vec::new(), mq_lang::CstErrorReporter::default())
+        let errors = if text.is_empty() {
+            mq_lang::CstErrorReporter::default()
         } else {
-            mq_lang::parse_recovery(&text)
+            mq_lang::parse_recovery(&text).1
         };
-        let (source_id, _) = self.hir.write().unwrap().add_nodes(uri.clone(), &nodes);
+        let (source_id, _) = self
+            .hir
+            .write()
+            .unwrap()
+            .update_code(&uri, &[mq_hir::TextEdit::full(text.as_str())]);
 
         let uri_string = uri.to_string();
         let mut errors = errors
@@ -446,9 +537,13 @@ impl Backend {
             .map(|(message, range)| LspError::SyntaxError((message, range)))
             .collect::<Vec<_>>();
 
-        if errors.is_empty() && self.config.enable_type_checking {
+        let (enable_type_checking, type_checker_options) = {
+            let config = self.config.read().unwrap();
+            (config.enable_type_checking, config.type_checker_options)
+        };
+        if errors.is_empty() && enable_type_checking {
             let hir_guard = self.hir.read().unwrap();
-            let mut checker = mq_check::TypeChecker::with_options(self.config.type_checker_options);
+            let mut checker = mq_check::TypeChecker::with_options(type_checker_options);
             let type_errors = checker.check(&hir_guard);
 
             // Build a set of text ranges from the current source's symbols
@@ -459,8 +554,7 @@ impl Backend {
                 .filter_map(|(_, symbol)| symbol.source.text_range)
                 .collect();
 
-            self.type_env_map
-                .insert(uri_string.clone(), checker.symbol_types().clone());
+            self.type_checker_map.insert(uri_string.clone(), Arc::new(checker));
             errors.extend(
                 type_errors
                     .into_iter()
@@ -554,8 +648,9 @@ impl Backend {
                 }
             }));
 
-            if self.config.enable_lint {
-                let lint_ctx = mq_lint::LintContext::new(&hir_guard, *source_id, &self.config.lint_config);
+            let config = self.config.read().unwrap();
+            if config.enable_lint {
+                let lint_ctx = mq_lint::LintContext::new(&hir_guard, *source_id, &config.lint_config);
                 let linter = mq_lint::Linter::with_default_rules();
                 diagnostics.extend(
                     linter
@@ -585,6 +680,9 @@ pub struct LspConfig {
     type_checker_options: mq_check::TypeCheckerOptions,
     enable_lint: bool,
     lint_config: mq_lint::LintConfig,
+    sample_input: Option<PathBuf>,
+    formatter_config: mq_formatter::FormatterConfig,
+    preload_builtins: bool,
 }
 
 impl LspConfig {
@@ -611,26 +709,100 @@ impl LspConfig {
             type_checker_options,
             enable_lint,
             lint_config,
+            sample_input: None,
+            formatter_config: mq_formatter::FormatterConfig::default(),
+            preload_builtins: false,
         }
     }
+
+    /// Sets the input file that the "Run Query" code lens evaluates documents against.
+    pub fn with_sample_input(mut self, sample_input: Option<PathBuf>) -> Self {
+        self.sample_input = sample_input;
+        self
+    }
+
+    /// Overrides the configuration with the settings sent by the client.
+    ///
+    /// Unknown lint rule IDs are reported as an error after the remaining settings are applied.
+    pub fn apply(&mut self, settings: &Settings) -> Result<(), String> {
+        if let Some(module_paths) = &settings.module_paths {
+            self.module_paths = module_paths.clone();
+        }
+        if let Some(preload_builtins) = settings.preload_builtins {
+            self.preload_builtins = preload_builtins;
+        }
+        if let Some(type_checking) = &settings.type_checking {
+            if let Some(enabled) = type_checking.enabled {
+                self.enable_type_checking = enabled;
+            }
+            if let Some(strict_array) = type_checking.strict_array {
+                self.type_checker_options.strict_array = strict_array;
+            }
+        }
+        let strictness = settings
+            .type_checking
+            .as_ref()
+            .and_then(|type_checking| type_checking.strictness.as_deref())
+            .map(str::parse::<mq_check::Strictness>);
+        if let Some(enabled) = settings.lint.as_ref().and_then(|lint| lint.enabled) {
+            self.enable_lint = enabled;
+        }
+        if let Some(formatter) = &settings.formatter {
+            if let Some(indent_width) = formatter.indent_width {
+                self.formatter_config.indent_width = indent_width;
+            }
+            if let Some(sort_imports) = formatter.sort_imports {
+                self.formatter_config.sort_imports = sort_imports;
+            }
+            if let Some(sort_functions) = formatter.sort_functions {
+                self.formatter_config.sort_functions = sort_functions;
+            }
+            if let Some(sort_fields) = formatter.sort_fields {
+                self.formatter_config.sort_fields = sort_fields;
+            }
+            if formatter.max_width.is_some() {
+                self.formatter_config.max_width = formatter.max_width;
+            }
+        }
+        if settings.sample_input.is_some() {
+            self.sample_input = settings.sample_input.clone();
+        }
+
+        if let Some(rules) = settings.disabled_lint_rules() {
+            let mut lint_config = mq_lint::LintConfig::default();
+            for rule_id in rules? {
+                lint_config.disable_rule(rule_id);
+            }
+            self.lint_config = lint_config;
+        }
+        if let Some(strictness) = strictness {
+            self.type_checker_options.strictness = strictness?;
+        }
+
+        Ok(())
+    }
+}
+
+fn new_hir(module_paths: &[PathBuf]) -> mq_hir::Hir {
+    let resolver = mq_lang::DefaultModuleResolver::new(module_paths.to_vec());
+    mq_hir::Hir::new(mq_lang::ModuleLoader::new(resolver))
 }
 
 pub async fn start(config: LspConfig) {
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let resolver = mq_lang::DefaultModuleResolver::new(config.module_paths.clone());
-    let module_loader = mq_lang::ModuleLoader::new(resolver);
-
-    let (service, socket) = LspService::new(|client| Backend {
+    let (service, socket) = LspService::build(|client| Backend {
         client,
-        hir: Arc::new(RwLock::new(mq_hir::Hir::new(module_loader))),
+        hir: Arc::new(RwLock::new(new_hir(&config.module_paths))),
         source_map: RwLock::new(BiMap::new()),
-        type_env_map: DashMap::new(),
+        type_checker_map: DashMap::new(),
         error_map: DashMap::new(),
         text_map: DashMap::new(),
-        config,
-    });
+        config: RwLock::new(config),
+    })
+    .custom_method(code_lens::RUN_QUERY_METHOD, Backend::run_query)
+    .finish();
 
     Server::new(stdin, stdout, socket).serve(service).await;
 }
@@ -647,10 +819,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -687,10 +859,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -732,10 +904,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -762,10 +934,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -791,10 +963,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -819,10 +991,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -866,10 +1038,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -911,10 +1083,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -971,10 +1143,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1009,10 +1181,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1061,10 +1233,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1133,10 +1305,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1191,22 +1363,68 @@ mod tests {
 
         match capabilities.rename_provider {
             Some(ls_types::OneOf::Right(options)) => {
-                assert_eq!(options.prepare_provider, Some(false));
+                assert_eq!(options.prepare_provider, Some(true));
             }
             other => panic!("expected rename_provider OneOf::Right(RenameOptions), got {other:?}"),
         }
     }
 
+    #[tokio::test]
+    async fn test_run_query_uses_sample_input() {
+        let dir = std::env::temp_dir().join("mq_lsp_server_run_query");
+        std::fs::create_dir_all(&dir).unwrap();
+        let sample_input = dir.join("sample.md");
+        std::fs::write(&sample_input, "# Title\n\nBody\n").unwrap();
+
+        let (service, _) = LspService::new(|client| Backend {
+            client,
+            hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
+            source_map: RwLock::new(BiMap::new()),
+            type_checker_map: DashMap::new(),
+            error_map: DashMap::new(),
+            text_map: DashMap::new(),
+            config: RwLock::new(LspConfig::default().with_sample_input(Some(sample_input.clone()))),
+        });
+
+        let backend = service.inner();
+        let uri = Url::parse("file:///test.mq").unwrap();
+        let params = code_lens::RunQueryParams {
+            text_document: TextDocumentIdentifier { uri: to_uri(&uri) },
+            input: None,
+            position: None,
+        };
+
+        // The document has to be open before it can be run.
+        assert!(backend.run_query(params.clone()).await.is_err());
+
+        backend.on_change(uri.clone(), ".h1 | to_text()".to_string()).await;
+        let result = backend.run_query(params.clone()).await.unwrap();
+
+        assert_eq!(result.input, sample_input);
+        assert_eq!(result.result.trim(), "Title");
+
+        // A position runs the pipeline up to the expression that starts there.
+        let result = backend
+            .run_query(code_lens::RunQueryParams {
+                position: Some(ls_types::Position::new(0, 0)),
+                ..params
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.result.trim(), "# Title");
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[tokio::test]
     async fn test_rename() {
         let (service, _) = LspService::new(|client| Backend {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1242,10 +1460,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1319,10 +1537,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1363,10 +1581,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1436,10 +1654,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1481,10 +1699,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1528,10 +1746,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1574,10 +1792,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1609,10 +1827,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1646,10 +1864,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1679,10 +1897,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1727,10 +1945,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1762,10 +1980,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1806,16 +2024,16 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 false,
                 mq_check::TypeCheckerOptions::default(),
                 true,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -1839,7 +2057,8 @@ mod tests {
 
         {
             let hir_guard = backend.hir.read().unwrap();
-            let lint_ctx = mq_lint::LintContext::new(&hir_guard, source_id, &backend.config.lint_config);
+            let config = backend.config.read().unwrap();
+            let lint_ctx = mq_lint::LintContext::new(&hir_guard, source_id, &config.lint_config);
             let diagnostics = mq_lint::Linter::with_default_rules().run(&lint_ctx);
             assert!(
                 diagnostics
@@ -1859,14 +2078,14 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
-        assert!(!backend.config.enable_lint);
+        assert!(!backend.config.read().unwrap().enable_lint);
 
         let uri = Url::parse("file:///test.mq").unwrap();
         let code = "let x = .h1 | .text";
@@ -1894,10 +2113,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1938,10 +2157,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
 
         let backend = service.inner();
@@ -1989,10 +2208,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
         let backend = service.inner();
         backend.text_map.insert(uri.to_string(), text.to_string().into());
@@ -2030,10 +2249,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
         let backend = service.inner();
         backend.text_map.insert(uri.to_string(), text.to_string().into());
@@ -2070,10 +2289,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::default(),
+            config: RwLock::new(LspConfig::default()),
         });
         let backend = service.inner();
         backend.text_map.insert(uri.to_string(), text.to_string().into());
@@ -2142,16 +2361,16 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 false,
                 mq_check::TypeCheckerOptions::default(),
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2184,16 +2403,16 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 true,
                 mq_check::TypeCheckerOptions::default(),
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2227,16 +2446,16 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 true,
                 mq_check::TypeCheckerOptions::default(),
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2259,6 +2478,15 @@ mod tests {
             errors.iter().any(|e| matches!(e, LspError::TypeError(_))),
             "expected at least one type error diagnostic for arity mismatch"
         );
+        drop(errors);
+
+        let diagnostics = backend.compute_diagnostics(&uri);
+        assert!(
+            diagnostics.iter().any(|d| d.source.as_deref() == Some("mq-check")
+                && d.severity == Some(ls_types::DiagnosticSeverity::ERROR)
+                && d.code.is_some()),
+            "expected an mq-check error diagnostic with a code"
+        );
 
         // Also run diagnostics publishing to ensure the diagnostics path executes.
         backend.diagnostics(uri, None).await;
@@ -2270,16 +2498,16 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 true,
                 mq_check::TypeCheckerOptions::default(),
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2312,10 +2540,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 true,
                 mq_check::TypeCheckerOptions {
@@ -2324,7 +2552,7 @@ mod tests {
                 },
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2357,10 +2585,10 @@ mod tests {
             client,
             hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
             source_map: RwLock::new(BiMap::new()),
-            type_env_map: DashMap::new(),
+            type_checker_map: DashMap::new(),
             error_map: DashMap::new(),
             text_map: DashMap::new(),
-            config: LspConfig::new(
+            config: RwLock::new(LspConfig::new(
                 vec![],
                 true,
                 mq_check::TypeCheckerOptions {
@@ -2369,7 +2597,7 @@ mod tests {
                 },
                 false,
                 mq_lint::LintConfig::default(),
-            ),
+            )),
         });
 
         let backend = service.inner();
@@ -2395,4 +2623,90 @@ mod tests {
 
         backend.diagnostics(uri, None).await;
     }
+
+    #[tokio::test]
+    async fn test_did_change_configuration() {
+        let (service, _) = LspService::new(|client| Backend {
+            client,
+            hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
+            source_map: RwLock::new(BiMap::new()),
+            type_checker_map: DashMap::new(),
+            error_map: DashMap::new(),
+            text_map: DashMap::new(),
+            config: RwLock::new(LspConfig::default()),
+        });
+
+        let backend = service.inner();
+        let uri = Url::parse("file:///test.mq").unwrap();
+        // `add` is called with the wrong number of arguments, and `. == true` is a redundant comparison.
+        backend
+            .on_change(uri.clone(), "def add(x, y): x + y;\n| add(1) | . == true".to_string())
+            .await;
+        assert!(backend.compute_diagnostics(&uri).is_empty());
+
+        let module_path = std::env::temp_dir();
+        backend
+            .did_change_configuration(ls_types::DidChangeConfigurationParams {
+                settings: serde_json::json!({
+                    "mq": {
+                        "modulePaths": [module_path],
+                        "typeChecking": {"enabled": true, "strictness": "warn"},
+                        "lint": {"enabled": true, "disabledRules": ["naming_convention"]},
+                        "formatter": {"indentWidth": 4}
+                    }
+                }),
+            })
+            .await;
+
+        {
+            let config = backend.config.read().unwrap();
+            assert_eq!(config.module_paths, vec![module_path]);
+            assert!(config.enable_type_checking);
+            assert_eq!(config.type_checker_options.strictness, mq_check::Strictness::Warn);
+            assert!(config.enable_lint);
+            assert!(!config.lint_config.is_rule_enabled(mq_lint::RuleId::NamingConvention));
+            assert_eq!(config.formatter_config.indent_width, 4);
+        }
+
+        let diagnostics = backend.compute_diagnostics(&uri);
+        assert!(diagnostics.iter().any(|d| d.source.as_deref() == Some("mq-check")));
+        assert!(diagnostics.iter().any(|d| d.source.as_deref() == Some("mq-lint")));
+
+        let uri = Url::parse("file:///format.mq").unwrap();
+        backend.on_change(uri.clone(), "def f(x):\nx;".to_string()).await;
+        let edits = backend
+            .formatting(ls_types::DocumentFormattingParams {
+                text_document: ls_types::TextDocumentIdentifier { uri: to_uri(&uri) },
+                options: Default::default(),
+                work_done_progress_params: Default::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(edits[0].new_text.contains("\n    "));
+    }
+
+    #[tokio::test]
+    async fn test_initialize_with_invalid_settings_keeps_config() {
+        let (service, _) = LspService::new(|client| Backend {
+            client,
+            hir: Arc::new(RwLock::new(mq_hir::Hir::default())),
+            source_map: RwLock::new(BiMap::new()),
+            type_checker_map: DashMap::new(),
+            error_map: DashMap::new(),
+            text_map: DashMap::new(),
+            config: RwLock::new(LspConfig::default()),
+        });
+
+        let backend = service.inner();
+        let result = backend
+            .initialize(ls_types::InitializeParams {
+                initialization_options: Some(serde_json::json!({"typeChecking": {"enabled": "yes"}})),
+                ..Default::default()
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert!(!backend.config.read().unwrap().enable_type_checking);
+    }
 }
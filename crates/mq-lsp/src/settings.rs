@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Client settings sent via `initializationOptions` or `workspace/didChangeConfiguration`.
+///
+/// Settings may be sent either directly or nested under an `mq` section. Omitted settings keep
+/// their current value, so clients can send only what changed.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Directories searched for `include`/`import` modules.
+    pub module_paths: Option<Vec<PathBuf>>,
+    /// Loads builtin function docs eagerly instead of on the first opened document.
+    pub preload_builtins: Option<bool>,
+    pub type_checking: Option<TypeCheckingSettings>,
+    pub lint: Option<LintSettings>,
+    pub formatter: Option<FormatterSettings>,
+    /// Input file that the "Run Query" code lens evaluates queries against.
+    pub sample_input: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TypeCheckingSettings {
+    pub enabled: Option<bool>,
+    pub strict_array: Option<bool>,
+    /// How dynamic constructs are reported: `off`, `warn`, or `strict`.
+    pub strictness: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LintSettings {
+    pub enabled: Option<bool>,
+    /// Lint rule IDs to disable; replaces the previously disabled rules.
+    pub disabled_rules: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FormatterSettings {
+    pub indent_width: Option<usize>,
+    pub sort_imports: Option<bool>,
+    pub sort_functions: Option<bool>,
+    pub sort_fields: Option<bool>,
+    pub max_width: Option<usize>,
+}
+
+impl Settings {
+    /// Parses settings from a client payload, unwrapping an `mq` section if present.
+    pub fn from_value(value: serde_json::Value) -> Result<Self, serde_json::Error> {
+        match value {
+            serde_json::Value::Object(mut map) if map.contains_key("mq") => {
+                serde_json::from_value(map.remove("mq").unwrap_or_default())
+            }
+            serde_json::Value::Null => Ok(Self::default()),
+            value => serde_json::from_value(value),
+        }
+    }
+
+    /// Returns the lint rules to disable, reporting unknown rule IDs as errors.
+    pub(crate) fn disabled_lint_rules(&self) -> Option<Result<Vec<mq_lint::RuleId>, String>> {
+        self.lint
+            .as_ref()
+            .and_then(|lint| lint.disabled_rules.as_ref())
+            .map(|rules| rules.iter().map(|rule| rule.parse()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+    use serde_json::json;
+
+    #[rstest]
+    #[case::nested(json!({"mq": {"preloadBuiltins": true}}))]
+    #[case::flat(json!({"preloadBuiltins": true}))]
+    fn test_from_value(#[case] value: serde_json::Value) {
+        let settings = Settings::from_value(value).unwrap();
+        assert_eq!(settings.preload_builtins, Some(true));
+        assert_eq!(settings.module_paths, None);
+    }
+
+    #[test]
+    fn test_from_value_all_settings() {
+        let settings = Settings::from_value(json!({
+            "mq": {
+                "modulePaths": ["/modules"],
+                "typeChecking": {"enabled": true, "strictArray": true, "strictness": "warn"},
+                "lint": {"enabled": true, "disabledRules": ["unused_variable"]},
+                "formatter": {"indentWidth": 4, "sortImports": true},
+                "sampleInput": "sample.md"
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(settings.module_paths, Some(vec![PathBuf::from("/modules")]));
+        assert_eq!(
+            settings.type_checking,
+            Some(TypeCheckingSettings {
+                enabled: Some(true),
+                strict_array: Some(true),
+                strictness: Some("warn".to_string()),
+            })
+        );
+        assert_eq!(
+            settings.disabled_lint_rules(),
+            Some(Ok(vec![mq_lint::RuleId::UnusedVariable]))
+        );
+        let formatter = settings.formatter.unwrap();
+        assert_eq!(formatter.indent_width, Some(4));
+        assert_eq!(formatter.sort_imports, Some(true));
+        assert_eq!(formatter.max_width, None);
+        assert_eq!(settings.sample_input, Some(PathBuf::from("sample.md")));
+    }
+
+    #[test]
+    fn test_from_value_null() {
+        assert_eq!(
+            Settings::from_value(serde_json::Value::Null).unwrap(),
+            Settings::default()
+        );
+    }
+
+    #[test]
+    fn test_unknown_lint_rule() {
+        let settings = Settings::from_value(json!({"lint": {"disabledRules": ["no_such_rule"]}})).unwrap();
+        assert!(settings.disabled_lint_rules().unwrap().is_err());
+    }
+}
@@ -43,18 +43,25 @@ fn extract_front_matter_from_head_ref(html: &Html) -> Option<BTreeMap<String, se
     // Extract <meta> tags only from within <head>
     if let Ok(meta_selector) = Selector::parse("meta") {
         let mut keywords: Vec<serde_yaml::Value> = Vec::new();
+        let mut open_graph = serde_yaml::Mapping::new();
+        let mut twitter = serde_yaml::Mapping::new();
+        let mut published_time = None;
+        let mut date = None;
 
         for meta_node in head_element.select(&meta_selector) {
-            if let (Some(name_attr), Some(content_attr)) =
-                (meta_node.value().attr("name"), meta_node.value().attr("content"))
+            // OpenGraph tags use `property`, most others (including Twitter cards) use `name`.
+            let key_attr = meta_node
+                .value()
+                .attr("name")
+                .or_else(|| meta_node.value().attr("property"));
+            if let (Some(key_attr), Some(content_attr)) = (key_attr, meta_node.value().attr("content"))
                 && !content_attr.is_empty()
             {
-                match name_attr.to_lowercase().as_str() {
+                let key = key_attr.to_lowercase();
+                let content = serde_yaml::Value::String(content_attr.to_string());
+                match key.as_str() {
                     "description" => {
-                        fm_map.insert(
-                            "description".to_string(),
-                            serde_yaml::Value::String(content_attr.to_string()),
-                        );
+                        fm_map.insert("description".to_string(), content);
                     }
                     "keywords" => {
                         content_attr
@@ -64,12 +71,31 @@ fn extract_front_matter_from_head_ref(html: &Html) -> Option<BTreeMap<String, se
                             .for_each(|k| keywords.push(serde_yaml::Value::String(k.to_string())));
                     }
                     "author" => {
-                        fm_map.insert(
-                            "author".to_string(),
-                            serde_yaml::Value::String(content_attr.to_string()),
-                        );
+                        fm_map.insert("author".to_string(), content);
+                    }
+                    "article:published_time" => {
+                        published_time.get_or_insert(content);
+                    }
+                    "date" | "pubdate" => {
+                        date.get_or_insert(content);
+                    }
+                    "article:modified_time" => {
+                        fm_map.entry("modified".to_string()).or_insert(content);
+                    }
+                    _ => {
+                        // The first value wins for repeated properties such as `og:image`.
+                        if let Some(property) = key.strip_prefix("og:") {
+                            let property = serde_yaml::Value::String(property.to_string());
+                            if !open_graph.contains_key(&property) {
+                                open_graph.insert(property, content);
+                            }
+                        } else if let Some(property) = key.strip_prefix("twitter:") {
+                            let property = serde_yaml::Value::String(property.to_string());
+                            if !twitter.contains_key(&property) {
+                                twitter.insert(property, content);
+                            }
+                        }
                     }
-                    _ => {}
                 }
             }
         }
@@ -77,6 +103,31 @@ fn extract_front_matter_from_head_ref(html: &Html) -> Option<BTreeMap<String, se
         if !keywords.is_empty() {
             fm_map.insert("keywords".to_string(), serde_yaml::Value::Sequence(keywords));
         }
+        if let Some(published) = published_time.or(date) {
+            fm_map.insert("published".to_string(), published);
+        }
+        if !open_graph.is_empty() {
+            fm_map.insert("og".to_string(), serde_yaml::Value::Mapping(open_graph));
+        }
+        if !twitter.is_empty() {
+            fm_map.insert("twitter".to_string(), serde_yaml::Value::Mapping(twitter));
+        }
+    }
+
+    if let Some(canonical) = Selector::parse("link[rel=\"canonical\"][href]")
+        .ok()
+        .and_then(|sel| head_element.select(&sel).next())
+        .and_then(|link| link.value().attr("href"))
+        .filter(|href| !href.is_empty())
+    {
+        fm_map.insert(
+            "canonical".to_string(),
+            serde_yaml::Value::String(canonical.to_string()),
+        );
+    }
+
+    if let Some(lang) = html.root_element().value().attr("lang").filter(|lang| !lang.is_empty()) {
+        fm_map.insert("language".to_string(), serde_yaml::Value::String(lang.to_string()));
     }
 
     if fm_map.is_empty() { None } else { Some(fm_map) }
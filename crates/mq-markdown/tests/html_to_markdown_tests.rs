@@ -217,6 +217,38 @@ fn assert_conversion_with_options(html: &str, expected_markdown: &str, options:
     },
     "Just a paragraph", // Meta tag not in <head> context
 )]
+#[case::front_matter_open_graph_and_twitter(
+    "<html><head><meta property=\"og:title\" content=\"OG Title\"><meta property=\"og:image\" content=\"https://example.com/a.png\"><meta property=\"og:image\" content=\"https://example.com/b.png\"><meta name=\"twitter:card\" content=\"summary\"></head><body><p>Body</p></body></html>",
+    ConversionOptions {
+        generate_front_matter: true,
+        ..ConversionOptions::default()
+    },
+    "---\nog:\n  title: OG Title\n  image: https://example.com/a.png\ntwitter:\n  card: summary\n---\n\nBody",
+)]
+#[case::front_matter_canonical_and_language(
+    "<html lang=\"en-US\"><head><link rel=\"canonical\" href=\"https://example.com/page\"></head><body><p>Body</p></body></html>",
+    ConversionOptions {
+        generate_front_matter: true,
+        ..ConversionOptions::default()
+    },
+    "---\ncanonical: https://example.com/page\nlanguage: en-US\n---\n\nBody",
+)]
+#[case::front_matter_article_dates(
+    "<html><head><meta name=\"date\" content=\"2024-01-01\"><meta property=\"article:published_time\" content=\"2024-01-02T10:00:00Z\"><meta property=\"article:modified_time\" content=\"2024-02-01T10:00:00Z\"></head><body><p>Body</p></body></html>",
+    ConversionOptions {
+        generate_front_matter: true,
+        ..ConversionOptions::default()
+    },
+    "---\nmodified: 2024-02-01T10:00:00Z\npublished: 2024-01-02T10:00:00Z\n---\n\nBody",
+)]
+#[case::front_matter_date_fallback(
+    "<html><head><meta name=\"date\" content=\"2024-01-01\"></head><body><p>Body</p></body></html>",
+    ConversionOptions {
+        generate_front_matter: true,
+        ..ConversionOptions::default()
+    },
+    "---\npublished: 2024-01-01\n---\n\nBody",
+)]
 #[case::script_tag_leading_newline_stripping(
     "<script>\n  var x = 1;\n</script>",
     ConversionOptions {
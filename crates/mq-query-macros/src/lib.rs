@@ -0,0 +1,163 @@
+use mq_check::{TypeChecker, types::Type};
+use mq_hir::{Hir, HirError};
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{LitStr, parse_macro_input};
+
+/// Embeds an mq query that is parsed and type checked at build time.
+///
+/// Syntax errors, unresolved symbols and type errors fail the build instead of surfacing on the
+/// first evaluation. The macro evaluates to a `&'static mq_lang::EmbeddedQuery<T>`, where `T` is
+/// the Rust counterpart of the inferred result type of the query:
+///
+/// - `number` → `f64`, `string` → `String`, `bool` → `bool`
+/// - `[T]` → `Vec<T>`, and `T` or `None` inside an array → `Option<T>`
+/// - tuples whose elements share a type → `Vec<T>`
+/// - anything else, including results that can't be inferred → `mq_lang::RuntimeValue`
+///
+/// `None` results are skipped by `EmbeddedQuery::eval`, so a top-level `T` or `None` result is
+/// typed as `T`. The query is embedded as source rather than as a serialized program, because
+/// compiled programs refer to tokens owned by the engine that parsed them; it's parsed again by
+/// the engine it's evaluated with.
+///
+/// # Example
+/// ```ignore
+/// let mut engine = mq_lang::DefaultEngine::default();
+/// engine.load_builtin_module();
+///
+/// let input = mq_lang::parse_text_input("hello").unwrap();
+/// let lengths: Vec<f64> = mq_query!("len(self)").eval(&mut engine, input.into_iter()).unwrap();
+///
+/// // Fails to compile: `upcase` takes a string.
+/// mq_query!("upcase(1)");
+/// ```
+#[proc_macro]
+pub fn mq_query(input: TokenStream) -> TokenStream {
+    let query = parse_macro_input!(input as LitStr);
+
+    match result_type(&query.value()) {
+        Ok(ty) => quote! {{
+            static QUERY: ::mq_lang::EmbeddedQuery<#ty> = ::mq_lang::EmbeddedQuery::new(#query);
+            &QUERY
+        }}
+        .into(),
+        Err(message) => syn::Error::new(query.span(), message).to_compile_error().into(),
+    }
+}
+
+/// Checks `code` and returns the Rust type of its results.
+fn result_type(code: &str) -> Result<TokenStream2, String> {
+    mq_lang::DefaultEngine::default()
+        .compile(code)
+        .map_err(|e| format!("invalid mq query: {}", e))?;
+
+    let mut hir = Hir::default();
+    let (source_id, _) = hir.add_code(None, code);
+    // Errors of the builtin module depend on the features mq-lang is built with, so only the
+    // query itself is checked.
+    if let Some(error) = hir.errors().into_iter().find(|error| {
+        let (HirError::UnresolvedSymbol { symbol, .. }
+        | HirError::ModuleNotFound { symbol, .. }
+        | HirError::PrivateSymbol { symbol, .. }) = error;
+        symbol.source.source_id == Some(source_id)
+    }) {
+        return Err(error.to_string());
+    }
+
+    let mut checker = TypeChecker::new();
+    if let Some(error) = checker.check(&hir).into_iter().next() {
+        return Err(match error.location() {
+            Some(range) => format!("{} at {}:{}", error, range.start.line, range.start.column),
+            None => error.to_string(),
+        });
+    }
+
+    // The results of a query are the values of its last top-level expression.
+    let result = hir
+        .symbols()
+        .filter(|(_, symbol)| symbol.parent.is_none() && symbol.source.source_id == Some(source_id))
+        .max_by_key(|(symbol_id, _)| hir.symbol_insertion_order(*symbol_id))
+        .and_then(|(symbol_id, _)| checker.type_of(symbol_id));
+
+    Ok(match result.map(|scheme| &scheme.ty) {
+        Some(Type::Union(types)) if types.contains(&Type::None) => match non_none(types) {
+            Some(ty) => rust_type(ty),
+            None => runtime_value(),
+        },
+        Some(ty) => rust_type(ty),
+        None => runtime_value(),
+    })
+}
+
+fn rust_type(ty: &Type) -> TokenStream2 {
+    match ty {
+        Type::Int | Type::Float | Type::Number => quote!(f64),
+        Type::String => quote!(::std::string::String),
+        Type::Bool => quote!(bool),
+        Type::Array(elem) => {
+            let elem = rust_type(elem);
+            quote!(::std::vec::Vec<#elem>)
+        }
+        Type::Tuple(elems) => rust_type(&Type::array(Type::union(elems.clone()))),
+        Type::Union(types) if types.contains(&Type::None) => match non_none(types) {
+            Some(ty) => {
+                let ty = rust_type(ty);
+                quote!(::std::option::Option<#ty>)
+            }
+            None => runtime_value(),
+        },
+        _ => runtime_value(),
+    }
+}
+
+/// Returns the only type of `types` besides `None`.
+fn non_none(types: &[Type]) -> Option<&Type> {
+    match types
+        .iter()
+        .filter(|ty| **ty != Type::None)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [ty] => Some(ty),
+        _ => None,
+    }
+}
+
+fn runtime_value() -> TokenStream2 {
+    quote!(::mq_lang::RuntimeValue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("upcase()", "String")]
+    #[case("1 + 2", "f64")]
+    #[case("len(self)", "f64")]
+    #[case("\"a\" | upcase()", "String")]
+    #[case("is_h1()", "bool")]
+    #[case("if (true): 1 else: None", "f64")]
+    #[case("[1, 2]", "Vec<f64>")]
+    #[case("[\"a\", None]", "Vec<::std::option::Option<::std::string::String>>")]
+    #[case("[1, \"a\"]", "Vec<::mq_lang::RuntimeValue>")]
+    #[case(".h1 | upcase()", "RuntimeValue")]
+    #[case("def f(x): x; | f(\"a\")", "RuntimeValue")]
+    #[case(".h1", "RuntimeValue")]
+    #[case("self", "RuntimeValue")]
+    fn test_result_type(#[case] code: &str, #[case] expected: &str) {
+        let ty = result_type(code).unwrap().to_string().replace(' ', "");
+        assert!(ty.ends_with(expected), "{} is not {}", ty, expected);
+    }
+
+    #[rstest]
+    #[case("upcase(", "invalid mq query")]
+    #[case("undefined_function()", "undefined_function")]
+    #[case("upcase(1)", "no matching overload at 1:1")]
+    fn test_result_type_error(#[case] code: &str, #[case] expected: &str) {
+        let error = result_type(code).unwrap_err();
+        assert!(error.contains(expected), "{}", error);
+    }
+}
@@ -0,0 +1,33 @@
+use mq_query_macros::mq_query;
+
+fn engine() -> mq_lang::DefaultEngine {
+    let mut engine = mq_lang::DefaultEngine::default();
+    engine.load_builtin_module();
+    engine
+}
+
+#[test]
+fn test_typed_results() {
+    let mut engine = engine();
+    let input = || mq_lang::parse_text_input("hello").unwrap().into_iter();
+
+    let upcased: Vec<String> = mq_query!("upcase()").eval(&mut engine, input()).unwrap();
+    assert_eq!(upcased, vec!["HELLO".to_string()]);
+
+    let lengths: Vec<f64> = mq_query!("len(self)").eval(&mut engine, input()).unwrap();
+    assert_eq!(lengths, vec![5.0]);
+
+    let parts: Vec<Vec<String>> = mq_query!("split(\"l\")").eval(&mut engine, input()).unwrap();
+    assert_eq!(parts, vec![vec!["he".to_string(), "".to_string(), "o".to_string()]]);
+}
+
+#[test]
+fn test_untyped_results() {
+    let mut engine = engine();
+    let input = mq_lang::parse_markdown_input("# Title\n\ntext").unwrap();
+
+    let query = mq_query!(".h1 | to_text()");
+    let results: Vec<mq_lang::RuntimeValue> = query.eval(&mut engine, input.into_iter()).unwrap();
+    assert_eq!(query.source(), ".h1 | to_text()");
+    assert_eq!(results.len(), 2);
+}
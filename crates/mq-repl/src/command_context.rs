@@ -1,4 +1,10 @@
-use std::{fmt, fs, io::Write, process::Command as ProcessCommand};
+use std::{
+    fmt, fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Command as ProcessCommand,
+    time::{Duration, Instant, SystemTime},
+};
 
 #[cfg(all(feature = "clipboard", not(target_os = "android")))]
 use arboard::Clipboard;
@@ -26,6 +32,7 @@ pub enum CommandOutput {
 pub enum Command {
     Clear,
     Copy,
+    Doc(String),
     Edit,
     Env(String, String),
     Eval(String),
@@ -33,9 +40,16 @@ pub enum Command {
     History,
     LoadFile(String),
     NotFound(String),
+    Profile(Option<bool>),
     Quit,
+    Reload,
+    Render(bool),
     Reset,
+    Restore(String),
     SaveFile(String),
+    Session(String),
+    Source(String),
+    Time,
     Vars,
     Version,
 }
@@ -56,6 +70,7 @@ impl fmt::Display for Command {
         match self {
             Command::Clear => write!(f, "/clear"),
             Command::Copy => write!(f, "/copy"),
+            Command::Doc(_) => write!(f, "/doc"),
             Command::Edit => write!(f, "/edit"),
             Command::Env(_, _) => write!(f, "/env"),
             Command::Help => write!(f, "/help"),
@@ -63,7 +78,14 @@ impl fmt::Display for Command {
             Command::Quit => write!(f, "/quit"),
             Command::LoadFile(_) => write!(f, "/load"),
             Command::SaveFile(_) => write!(f, "/save"),
+            Command::Source(_) => write!(f, "/source"),
+            Command::Reload => write!(f, "/reload"),
             Command::Reset => write!(f, "/reset"),
+            Command::Render(_) => write!(f, "/render"),
+            Command::Profile(_) => write!(f, "/profile"),
+            Command::Time => write!(f, "/time"),
+            Command::Restore(_) => write!(f, "/restore"),
+            Command::Session(_) => write!(f, "/session"),
             Command::Vars => write!(f, "/vars"),
             Command::Eval(_) => write!(f, "/eval"),
             Command::Version => write!(f, "/version"),
@@ -77,6 +99,7 @@ impl Command {
         match self {
             Command::Clear => format!("{:<12}{}", "/clear", "Clear the terminal screen"),
             Command::Copy => format!("{:<12}{}", "/copy", "Copy the execution results to the clipboard"),
+            Command::Doc(_) => format!("{:<12}{}", "/doc", "Show documentation for a function or selector"),
             Command::Edit => format!("{:<12}{}", "/edit", "Edit the current buffer in external editor"),
             Command::Env(_, _) => format!("{:<12}{}", "/env", "Set environment variables (key value)"),
             Command::Help => format!("{:<12}{}", "/help", "Print command help"),
@@ -84,7 +107,23 @@ impl Command {
             Command::Quit => format!("{:<12}{}", "/quit", "Quit evaluation and exit"),
             Command::LoadFile(_) => format!("{:<12}{}", "/load", "Load a markdown file"),
             Command::SaveFile(_) => format!("{:<12}{}", "/save", "Save a current result to a file"),
+            Command::Source(_) => format!("{:<12}{}", "/source", "Load definitions from an mq file"),
+            Command::Reload => format!("{:<12}{}", "/reload", "Reload files loaded with /source"),
             Command::Reset => format!("{:<12}{}", "/reset", "Reset REPL state (clear variables and input)"),
+            Command::Restore(_) => format!("{:<12}{}", "/restore", "Restore a session saved with /session"),
+            Command::Render(_) => format!(
+                "{:<12}{}",
+                "/render", "Render markdown results with ANSI styling (on|off)"
+            ),
+            Command::Profile(_) => format!(
+                "{:<12}{}",
+                "/profile", "Profile evaluations (on|off), or show the slowest calls of the last one"
+            ),
+            Command::Time => format!("{:<12}{}", "/time", "Show the wall time of the last evaluation"),
+            Command::Session(_) => format!(
+                "{:<12}{}",
+                "/session", "Save definitions, variables and input to a session file"
+            ),
             Command::Vars => format!("{:<12}{}", "/vars", "List bound variables"),
             Command::Eval(_) => format!("{:<12}{}", "/eval", ""),
             Command::NotFound(_) => format!("{:<12}{}", "/not_found", ""),
@@ -98,6 +137,7 @@ impl From<String> for Command {
         match s.as_str().split_whitespace().collect::<Vec<&str>>().as_slice() {
             ["/clear"] => Command::Clear,
             ["/copy"] => Command::Copy,
+            ["/doc", name] => Command::Doc(name.to_string()),
             ["/edit"] => Command::Edit,
             ["/env", name, value] => Command::Env(name.to_string(), value.to_string()),
             ["/help"] => Command::Help,
@@ -105,7 +145,17 @@ impl From<String> for Command {
             ["/quit"] => Command::Quit,
             ["/load", file_path] => Command::LoadFile(file_path.to_string()),
             ["/save", file_path] => Command::SaveFile(file_path.to_string()),
+            ["/source", file_path] => Command::Source(file_path.to_string()),
+            ["/reload"] => Command::Reload,
             ["/reset"] => Command::Reset,
+            ["/render", "on"] => Command::Render(true),
+            ["/render", "off"] => Command::Render(false),
+            ["/profile"] => Command::Profile(None),
+            ["/profile", "on"] => Command::Profile(Some(true)),
+            ["/profile", "off"] => Command::Profile(Some(false)),
+            ["/time"] => Command::Time,
+            ["/restore", file_path] => Command::Restore(file_path.to_string()),
+            ["/session", file_path] => Command::Session(file_path.to_string()),
             ["/vars"] => Command::Vars,
             ["/version"] => Command::Version,
             _ if s.starts_with("/") => Command::NotFound(s),
@@ -121,8 +171,29 @@ pub struct CommandContext {
     pub(crate) hir: mq_hir::Hir,
     pub(crate) source_id: mq_hir::SourceId,
     pub(crate) scope_id: mq_hir::ScopeId,
+    /// Whether markdown results are rendered with ANSI styling instead of shown as raw markdown.
+    pub(crate) render: bool,
+    /// Whether evaluations record per-call timings for `/profile`.
+    profiling: bool,
+    /// Files loaded with `/source`, with the modification time seen when they were last loaded.
+    sourced_files: Vec<(PathBuf, Option<SystemTime>)>,
+    /// Warnings raised while running the last command, collected with [`CommandContext::take_warnings`].
+    warnings: Vec<String>,
+    /// Evaluated code that only defines functions, variables or modules, replayed by `/restore`.
+    definitions: Vec<String>,
+    /// Wall time and per-call profile of the last evaluated query, reported by `/time` and `/profile`.
+    last_eval: Option<(Duration, Vec<mq_lang::ProfileEntry>)>,
 }
 
+/// Number of call sites listed by `/profile`.
+const PROFILE_LIMIT: usize = 10;
+
+/// Name of the variable that holds the saved input in a session file.
+const SESSION_INPUT_VAR: &str = "__session_input";
+
+/// First line of a session file.
+const SESSION_HEADER: &str = "# mq REPL session. Restore with `/restore <file>`.";
+
 impl CommandContext {
     pub fn new(engine: mq_lang::DefaultEngine, input: Vec<mq_lang::RuntimeValue>) -> Self {
         let mut hir = mq_hir::Hir::default();
@@ -137,7 +208,188 @@ impl CommandContext {
             hir,
             source_id,
             scope_id,
+            render: false,
+            profiling: false,
+            sourced_files: Vec::new(),
+            warnings: Vec::new(),
+            definitions: Vec::new(),
+            last_eval: None,
+        }
+    }
+
+    /// Evaluates a query against the current input, recording its timing for `/time` and `/profile`.
+    fn eval_query(&mut self, code: &str) -> miette::Result<mq_lang::RuntimeValues> {
+        let start = Instant::now();
+        let result = self.engine.eval(code, self.input.clone().into_iter()).map_err(|e| *e)?;
+        self.last_eval = Some((start.elapsed(), self.engine.profile()));
+        Ok(result)
+    }
+
+    fn profile(&self) -> miette::Result<Vec<String>> {
+        if !self.profiling {
+            return Err(miette!("Profiling is off; enable it with `/profile on`"));
+        }
+        let (elapsed, profile) = self
+            .last_eval
+            .as_ref()
+            .ok_or_else(|| miette!("No query has been evaluated yet"))?;
+        if profile.is_empty() {
+            return Ok(vec![format!("No calls recorded (total {:.3?})", elapsed)]);
+        }
+
+        let mut lines = vec![format!(
+            "{:>12}  {:>8}  {:<10}  {}",
+            "time", "calls", "location", "name"
+        )];
+        lines.extend(profile.iter().take(PROFILE_LIMIT).map(|entry| {
+            format!(
+                "{:>12}  {:>8}  {:<10}  {}",
+                format!("{:.3?}", entry.total),
+                entry.calls,
+                format!("{}:{}", entry.range.start.line, entry.range.start.column),
+                entry.name
+            )
+        }));
+        lines.push(format!("total {:.3?}", elapsed));
+        Ok(lines)
+    }
+
+    /// Records `code` for `/session` if every top-level expression in it is a definition.
+    fn record_definitions(&mut self, code: &str) {
+        let token_arena = mq_lang::Shared::new(mq_lang::SharedCell::new(mq_lang::Arena::new(100)));
+        let Ok(program) = mq_lang::parse(code, token_arena) else {
+            return;
+        };
+
+        let is_definition = |node: &mq_lang::Shared<mq_lang::AstNode>| {
+            matches!(
+                &*node.expr,
+                mq_lang::AstExpr::Def(..)
+                    | mq_lang::AstExpr::Let(..)
+                    | mq_lang::AstExpr::Var(..)
+                    | mq_lang::AstExpr::Macro(..)
+                    | mq_lang::AstExpr::Module(..)
+                    | mq_lang::AstExpr::Import(..)
+                    | mq_lang::AstExpr::Include(..)
+            )
+        };
+        if !program.is_empty() && program.iter().all(is_definition) {
+            self.definitions.push(code.trim().to_string());
+        }
+    }
+
+    /// Renders the session as mq code: the recorded definitions followed by the current input
+    /// bound to [`SESSION_INPUT_VAR`].
+    ///
+    /// Each input value is saved as `{"markdown": ...}` for Markdown nodes or `{"value": ...}`
+    /// holding an mq literal otherwise, so that `/restore` brings back values of the same type.
+    fn session(&self) -> String {
+        let input = self.input.iter().map(session_entry).collect::<Vec<_>>().join(", ");
+        let mut code = vec![SESSION_HEADER.to_string()];
+        code.extend(self.definitions.iter().map(|definition| format!("{}\n|", definition)));
+        code.push(format!("let {} = [{}]", SESSION_INPUT_VAR, input));
+        code.join("\n") + "\n"
+    }
+
+    /// Replaces the current session with one saved by `/session`.
+    fn restore(&mut self, path: &Path) -> miette::Result<()> {
+        let code = fs::read_to_string(path).into_diagnostic()?;
+        self.execute("/reset")?;
+
+        let input = self
+            .engine
+            .eval(
+                &format!("{} | {}", code.trim_end(), SESSION_INPUT_VAR),
+                self.input.clone().into_iter(),
+            )
+            .map_err(|e| *e)?;
+        let input = match input.values().first() {
+            Some(mq_lang::RuntimeValue::Array(entries)) => entries
+                .iter()
+                .map(restore_entry)
+                .collect::<miette::Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect(),
+            // Sessions saved before input entries were typed hold the input as one Markdown string.
+            Some(mq_lang::RuntimeValue::String(markdown)) => parse_markdown(markdown)?,
+            _ => return Err(miette!("Invalid session file: {} is missing", SESSION_INPUT_VAR)),
+        };
+
+        let definitions = code
+            .rsplit_once(&format!("let {} =", SESSION_INPUT_VAR))
+            .map(|(definitions, _)| {
+                definitions
+                    .trim_start()
+                    .trim_start_matches(SESSION_HEADER)
+                    .trim_end()
+                    .trim_end_matches('|')
+                    .trim()
+            })
+            .unwrap_or_default();
+        if !definitions.is_empty() {
+            self.hir.add_line_of_code(self.source_id, self.scope_id, definitions);
+            self.definitions.push(definitions.to_string());
         }
+        self.input = input;
+        Ok(())
+    }
+
+    /// Evaluates the definitions in an mq file into the session without changing the current input.
+    fn source_file(&mut self, path: &Path) -> miette::Result<()> {
+        let code = fs::read_to_string(path).into_diagnostic()?;
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        let url = std::path::absolute(path)
+            .ok()
+            .and_then(|path| url::Url::from_file_path(path).ok())
+            .ok_or_else(|| miette!("Invalid file path: {}", path.display()))?;
+
+        self.engine
+            .eval(&code, self.input.clone().into_iter())
+            .map_err(|e| *e)?;
+        // Each file gets its own HIR source, so reloading it replaces the symbols it defined before.
+        self.hir.add_code(Some(url), &code);
+        self.record_definitions(&code);
+
+        match self.sourced_files.iter_mut().find(|(p, _)| p == path) {
+            Some(entry) => entry.1 = modified,
+            None => self.sourced_files.push((path.to_path_buf(), modified)),
+        }
+        Ok(())
+    }
+
+    /// Stops watching files loaded with `/source` that no longer exist, leaving a warning for each.
+    fn forget_deleted_files(&mut self) {
+        let warnings = &mut self.warnings;
+        self.sourced_files.retain(|(path, _)| {
+            let exists = path.exists();
+            if !exists {
+                warnings.push(format!("{} no longer exists and will not be reloaded", path.display()));
+            }
+            exists
+        });
+    }
+
+    /// Returns the warnings raised since the last call, for the front end to report.
+    pub fn take_warnings(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Re-sources every file loaded with `/source` whose modification time has changed.
+    fn reload_changed_files(&mut self) -> miette::Result<Vec<PathBuf>> {
+        self.forget_deleted_files();
+        let changed: Vec<PathBuf> = self
+            .sourced_files
+            .iter()
+            .filter(|(path, modified)| fs::metadata(path).and_then(|m| m.modified()).ok() != *modified)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &changed {
+            self.source_file(path)?;
+        }
+        Ok(changed)
     }
 
     pub fn completions(&self, line: &str, pos: usize) -> (usize, Vec<CompletionItem>) {
@@ -215,6 +467,58 @@ impl CommandContext {
         (start, matches)
     }
 
+    /// Builds the `/doc` output for a function or selector.
+    ///
+    /// Functions defined in the session take precedence over builtins of the same name.
+    fn doc(&self, name: &str) -> Option<Vec<String>> {
+        let user_function = |builtin: bool| {
+            self.hir.symbols().find_map(|(_, symbol)| match &symbol.kind {
+                mq_hir::SymbolKind::Function(params)
+                    if symbol.value.as_deref() == Some(name) && self.hir.is_builtin_symbol(symbol) == builtin =>
+                {
+                    Some((
+                        params.iter().map(|p| p.name.to_string()).collect::<Vec<_>>(),
+                        symbol.doc.iter().map(|(_, doc)| doc.clone()).collect::<Vec<_>>(),
+                    ))
+                }
+                _ => None,
+            })
+        };
+
+        let (params, description) = if let Some(function) = user_function(false) {
+            function
+        } else if let Some(doc) = mq_lang::BUILTIN_FUNCTION_DOC.get(name) {
+            (
+                doc.params.iter().map(|p| p.to_string()).collect(),
+                vec![doc.description.to_string()],
+            )
+        } else if let Some(doc) = mq_lang::BUILTIN_SELECTOR_DOC.get(name) {
+            (
+                doc.params.iter().map(|p| p.to_string()).collect(),
+                vec![doc.description.to_string()],
+            )
+        } else {
+            user_function(true)?
+        };
+
+        let signature = if name.starts_with('.') && params.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}({})", name, params.join(", "))
+        };
+
+        let mut lines = vec![signature.clone(), "".to_string()];
+        lines.extend(description.into_iter().filter(|line| !line.trim().is_empty()));
+        if !params.is_empty() {
+            lines.push("".to_string());
+            lines.push("Parameters:".to_string());
+            lines.extend(params.iter().map(|p| format!("  {}", p)));
+        }
+        lines.push("".to_string());
+        lines.push(format!("Usage: {}", signature));
+        Some(lines)
+    }
+
     fn builtin_display(name: &str) -> String {
         if let Some(doc) = mq_lang::BUILTIN_FUNCTION_DOC.get(name) {
             if doc.params.is_empty() {
@@ -241,11 +545,15 @@ impl CommandContext {
                 hir.add_builtin();
                 let mut engine = mq_lang::DefaultEngine::default();
                 engine.load_builtin_module();
+                engine.set_profiling(self.profiling);
                 self.hir = hir;
                 self.source_id = source_id;
                 self.scope_id = scope_id;
                 self.engine = engine;
                 self.input = self.initial_input.clone();
+                self.sourced_files.clear();
+                self.definitions.clear();
+                self.last_eval = None;
                 Ok(CommandOutput::None)
             }
             Command::Copy => {
@@ -310,14 +618,19 @@ impl CommandContext {
                 if code.is_empty() {
                     Ok(CommandOutput::None)
                 } else {
-                    let eval_result = self.engine.eval(code, self.input.clone().into_iter()).map_err(|e| *e)?;
+                    let eval_result = self.eval_query(code)?;
 
                     self.hir.add_line_of_code(self.source_id, self.scope_id, code);
+                    self.record_definitions(code);
                     self.input = eval_result.values().clone();
 
                     Ok(CommandOutput::Value(eval_result.values().clone()))
                 }
             }
+            Command::Doc(name) => self
+                .doc(&name)
+                .map(CommandOutput::String)
+                .ok_or_else(|| miette!("No documentation found for `{}`", name)),
             Command::Env(name, value) => {
                 unsafe { std::env::set_var(name, value) };
                 Ok(CommandOutput::None)
@@ -358,6 +671,52 @@ impl CommandContext {
                         Ok(CommandOutput::None)
                     })
             }
+            Command::Source(file_path) => {
+                self.source_file(Path::new(&file_path))?;
+                Ok(CommandOutput::None)
+            }
+            Command::Reload => {
+                self.forget_deleted_files();
+                if self.sourced_files.is_empty() {
+                    return Err(miette!("No files have been loaded with /source"));
+                }
+
+                let paths: Vec<PathBuf> = self.sourced_files.iter().map(|(path, _)| path.clone()).collect();
+                for path in &paths {
+                    self.source_file(path)?;
+                }
+                Ok(CommandOutput::String(
+                    paths
+                        .iter()
+                        .map(|path| format!("Reloaded {}", path.display()))
+                        .collect(),
+                ))
+            }
+            Command::Render(render) => {
+                self.render = render;
+                Ok(CommandOutput::None)
+            }
+            Command::Time => {
+                let (elapsed, _) = self
+                    .last_eval
+                    .as_ref()
+                    .ok_or_else(|| miette!("No query has been evaluated yet"))?;
+                Ok(CommandOutput::String(vec![format!("{:.3?}", elapsed)]))
+            }
+            Command::Profile(None) => self.profile().map(CommandOutput::String),
+            Command::Profile(Some(profiling)) => {
+                self.profiling = profiling;
+                self.engine.set_profiling(profiling);
+                Ok(CommandOutput::None)
+            }
+            Command::Session(file_path) => {
+                fs::write(file_path, self.session()).into_diagnostic()?;
+                Ok(CommandOutput::None)
+            }
+            Command::Restore(file_path) => {
+                self.restore(Path::new(&file_path))?;
+                Ok(CommandOutput::None)
+            }
             Command::SaveFile(file_path) => {
                 let content = self.input.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("\n");
                 fs::write(file_path, content).into_diagnostic()?;
@@ -409,19 +768,89 @@ impl CommandContext {
                     return Ok(CommandOutput::None);
                 }
 
-                let result = self.engine.eval(&code, self.input.clone().into_iter());
+                self.reload_changed_files()?;
+                let result = self.eval_query(&code)?;
 
-                result
-                    .map(|result| {
-                        self.hir.add_line_of_code(self.source_id, self.scope_id, &code);
-                        self.input = result.values().clone();
-                        Ok(CommandOutput::Value(result.values().clone()))
-                    })
-                    .map_err(|e| *e)?
+                self.hir.add_line_of_code(self.source_id, self.scope_id, &code);
+                self.record_definitions(&code);
+                self.input = result.values().clone();
+                Ok(CommandOutput::Value(result.values().clone()))
             }
         }
     }
 }
+/// Renders one input value as a session entry; see [`CommandContext::session`].
+fn session_entry(value: &mq_lang::RuntimeValue) -> String {
+    match value {
+        mq_lang::RuntimeValue::Markdown(..) => format!("{{\"markdown\": \"{}\"}}", escape_string(&value.to_string())),
+        _ => format!("{{\"value\": {}}}", literal(value)),
+    }
+}
+
+/// Renders `value` as an mq expression that evaluates to an equal value. Values without a
+/// literal form, such as functions, are saved as their string representation.
+fn literal(value: &mq_lang::RuntimeValue) -> String {
+    match value {
+        mq_lang::RuntimeValue::Number(n) if n.value().is_finite() => format!("{:?}", n.value()),
+        mq_lang::RuntimeValue::Boolean(b) => b.to_string(),
+        mq_lang::RuntimeValue::None => "None".to_string(),
+        mq_lang::RuntimeValue::Symbol(symbol) => format!(":{}", symbol),
+        mq_lang::RuntimeValue::Array(items) => {
+            format!("[{}]", items.iter().map(literal).collect::<Vec<_>>().join(", "))
+        }
+        mq_lang::RuntimeValue::Dict(map) => format!(
+            "{{{}}}",
+            map.iter()
+                .map(|(key, value)| format!("\"{}\": {}", escape_string(&key.to_string()), literal(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        mq_lang::RuntimeValue::String(s) => format!("\"{}\"", escape_string(s)),
+        _ => format!("\"{}\"", escape_string(&value.to_string())),
+    }
+}
+
+/// Turns a session entry back into input values; a Markdown entry may parse into several nodes.
+fn restore_entry(entry: &mq_lang::RuntimeValue) -> miette::Result<Vec<mq_lang::RuntimeValue>> {
+    let mq_lang::RuntimeValue::Dict(entry) = entry else {
+        return Err(miette!("Invalid session input entry: {}", entry));
+    };
+
+    match (
+        entry.get(&mq_lang::Ident::new("markdown")),
+        entry.get(&mq_lang::Ident::new("value")),
+    ) {
+        (Some(mq_lang::RuntimeValue::String(markdown)), _) => parse_markdown(markdown),
+        (_, Some(value)) => Ok(vec![value.clone()]),
+        _ => Err(miette!("Invalid session input entry")),
+    }
+}
+
+fn parse_markdown(markdown: &str) -> miette::Result<Vec<mq_lang::RuntimeValue>> {
+    Ok(mq_markdown::Markdown::from_markdown_str(markdown)?
+        .nodes
+        .into_iter()
+        .map(mq_lang::RuntimeValue::from)
+        .collect())
+}
+
+/// Escapes `s` so that it can be embedded in an mq string literal.
+fn escape_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use scopeguard::defer;
@@ -509,6 +938,14 @@ mod tests {
                 Command::Version => assert!(help.contains("/version")),
                 Command::LoadFile(_) => assert!(help.contains("/load")),
                 Command::SaveFile(_) => assert!(help.contains("/save")),
+                Command::Doc(_) => assert!(help.contains("/doc")),
+                Command::Source(_) => assert!(help.contains("/source")),
+                Command::Reload => assert!(help.contains("/reload")),
+                Command::Session(_) => assert!(help.contains("/session")),
+                Command::Profile(_) => assert!(help.contains("/profile")),
+                Command::Render(_) => assert!(help.contains("/render")),
+                Command::Time => assert!(help.contains("/time")),
+                Command::Restore(_) => assert!(help.contains("/restore")),
                 Command::Env(_, _) => assert!(help.contains("/env")),
                 Command::Eval(_) => assert!(help.contains("/eval")),
                 Command::NotFound(_) => assert!(help.contains("/not_found")),
@@ -700,4 +1137,282 @@ mod tests {
         let result = ctx.execute("").unwrap();
         assert!(matches!(result, CommandOutput::None));
     }
+
+    #[test]
+    fn test_execute_source_and_reload() {
+        let engine = mq_lang::DefaultEngine::default();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+        let (_, temp_file_path) = create_file("test_execute_source.mq", "def double(x): x * 2;");
+
+        defer! {
+            if temp_file_path.exists() {
+                std::fs::remove_file(&temp_file_path).expect("Failed to delete temp file");
+            }
+        }
+
+        let result = ctx.execute(&format!("/source {}", temp_file_path.to_str().unwrap()));
+        assert!(matches!(result, Ok(CommandOutput::None)));
+
+        let CommandOutput::Value(values) = ctx.execute("double(21)").unwrap() else {
+            panic!("Expected Value output");
+        };
+        assert_eq!(values[0].to_string(), "42");
+
+        // Changing the file is picked up on the next evaluation without an explicit /reload.
+        std::fs::write(&temp_file_path, "def double(x): x * 3;").unwrap();
+        File::options()
+            .write(true)
+            .open(&temp_file_path)
+            .unwrap()
+            .set_modified(SystemTime::now() + std::time::Duration::from_secs(10))
+            .unwrap();
+
+        let CommandOutput::Value(values) = ctx.execute("double(2)").unwrap() else {
+            panic!("Expected Value output");
+        };
+        assert_eq!(values[0].to_string(), "6");
+
+        let CommandOutput::String(lines) = ctx.execute("/reload").unwrap() else {
+            panic!("Expected String output");
+        };
+        assert_eq!(lines, vec![format!("Reloaded {}", temp_file_path.display())]);
+    }
+
+    #[test]
+    fn test_execute_reload_replaces_hir_symbols() {
+        let engine = mq_lang::DefaultEngine::default();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+        let (_, temp_file_path) = create_file("test_execute_reload_hir.mq", "def triple(x): x * 3;");
+
+        defer! {
+            if temp_file_path.exists() {
+                std::fs::remove_file(&temp_file_path).expect("Failed to delete temp file");
+            }
+        }
+
+        ctx.execute(&format!("/source {}", temp_file_path.to_str().unwrap()))
+            .unwrap();
+        std::fs::write(&temp_file_path, "def triple(x): x + x + x;").unwrap();
+        ctx.execute("/reload").unwrap();
+
+        let count = ctx
+            .hir
+            .symbols()
+            .filter(|(_, symbol)| symbol.value.as_deref() == Some("triple"))
+            .count();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_execute_after_sourced_file_is_deleted() {
+        let engine = mq_lang::DefaultEngine::default();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+        let (_, temp_file_path) = create_file("test_execute_deleted_source.mq", "def half(x): x / 2;");
+
+        ctx.execute(&format!("/source {}", temp_file_path.to_str().unwrap()))
+            .unwrap();
+        std::fs::remove_file(&temp_file_path).unwrap();
+
+        let CommandOutput::Value(values) = ctx.execute("half(4)").unwrap() else {
+            panic!("Expected Value output");
+        };
+        assert_eq!(values[0].to_string(), "2");
+        assert!(ctx.sourced_files.is_empty());
+        assert_eq!(
+            ctx.take_warnings(),
+            vec![format!(
+                "{} no longer exists and will not be reloaded",
+                temp_file_path.display()
+            )]
+        );
+        assert!(ctx.take_warnings().is_empty());
+    }
+
+    #[test]
+    fn test_execute_session_and_restore() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["".to_string().into()]);
+        let (_, session_path) = create_file("test_execute_session.mq", "");
+
+        defer! {
+            if session_path.exists() {
+                std::fs::remove_file(&session_path).expect("Failed to delete temp file");
+            }
+        }
+
+        ctx.execute("def greet(x): s\"Hello, ${x}\";").unwrap();
+        ctx.execute("let name = \"mq\"").unwrap();
+        ctx.input = mq_markdown::Markdown::from_markdown_str("# Title\n\n\"quoted\" text\n")
+            .unwrap()
+            .nodes
+            .into_iter()
+            .map(mq_lang::RuntimeValue::from)
+            .collect();
+        let input = ctx.input.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        let result = ctx.execute(&format!("/session {}", session_path.to_str().unwrap()));
+        assert!(matches!(result, Ok(CommandOutput::None)));
+
+        ctx.execute("/reset").unwrap();
+        assert!(ctx.execute("greet(name)").is_err());
+
+        let result = ctx.execute(&format!("/restore {}", session_path.to_str().unwrap()));
+        assert!(matches!(result, Ok(CommandOutput::None)));
+        assert_eq!(ctx.input.iter().map(|v| v.to_string()).collect::<Vec<_>>(), input);
+
+        let CommandOutput::Value(values) = ctx.execute("greet(name)").unwrap() else {
+            panic!("Expected Value output");
+        };
+        assert_eq!(values[0].to_string(), "Hello, mq");
+
+        // Restoring records the definitions again so that the session can be saved once more.
+        ctx.execute(&format!("/session {}", session_path.to_str().unwrap()))
+            .unwrap();
+        ctx.execute(&format!("/restore {}", session_path.to_str().unwrap()))
+            .unwrap();
+        let CommandOutput::Value(values) = ctx.execute("greet(name)").unwrap() else {
+            panic!("Expected Value output");
+        };
+        assert_eq!(values[0].to_string(), "Hello, mq");
+    }
+
+    #[test]
+    fn test_execute_session_and_restore_typed_input() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["initial".to_string().into()]);
+        let (_, session_path) = create_file("test_execute_session_typed.mq", "");
+
+        defer! {
+            if session_path.exists() {
+                std::fs::remove_file(&session_path).expect("Failed to delete temp file");
+            }
+        }
+
+        let CommandOutput::Value(values) = ctx
+            .execute(r#"[1, 2.5, -3, true, None, :sym, "line\n\"two\"", {"a": [1, "b"], "c": {"d": false}}, ""]"#)
+            .unwrap()
+        else {
+            panic!("Expected Value output");
+        };
+        let mq_lang::RuntimeValue::Array(values) = &values[0] else {
+            panic!("Expected an array");
+        };
+        let values = values.to_vec();
+        ctx.input = values.clone();
+
+        ctx.execute(&format!("/session {}", session_path.to_str().unwrap()))
+            .unwrap();
+        ctx.execute("/reset").unwrap();
+        ctx.execute(&format!("/restore {}", session_path.to_str().unwrap()))
+            .unwrap();
+        assert_eq!(ctx.input, values);
+
+        // An empty input is restored as empty rather than replaced by the initial input.
+        ctx.input = vec![];
+        ctx.execute(&format!("/session {}", session_path.to_str().unwrap()))
+            .unwrap();
+        ctx.execute(&format!("/restore {}", session_path.to_str().unwrap()))
+            .unwrap();
+        assert!(ctx.input.is_empty());
+    }
+
+    #[test]
+    fn test_execute_time_and_profile() {
+        let mut engine = mq_lang::DefaultEngine::default();
+        engine.load_builtin_module();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+
+        assert!(ctx.execute("/time").is_err());
+        assert!(ctx.execute("/profile").is_err());
+
+        // Profiling is off until requested.
+        ctx.execute("def double(x): x * 2; | map([1, 2, 3], double)").unwrap();
+        assert!(ctx.engine.profile().is_empty());
+        assert!(ctx.execute("/profile").is_err());
+
+        let CommandOutput::String(lines) = ctx.execute("/time").unwrap() else {
+            panic!("Expected String output");
+        };
+        assert_eq!(lines.len(), 1);
+
+        assert!(matches!(ctx.execute("/profile on"), Ok(CommandOutput::None)));
+        ctx.execute("def double(x): x * 2; | map([1, 2, 3], double)").unwrap();
+
+        let CommandOutput::String(lines) = ctx.execute("/profile").unwrap() else {
+            panic!("Expected String output");
+        };
+        assert!(lines[0].contains("calls"));
+        assert!(lines.iter().any(|line| line.ends_with("  map")));
+        assert!(lines.last().unwrap().starts_with("total "));
+
+        // Profiling stays on across /reset until turned off.
+        ctx.execute("/reset").unwrap();
+        assert!(ctx.profiling);
+        assert!(matches!(ctx.execute("/profile off"), Ok(CommandOutput::None)));
+        ctx.execute("[1, 2, 3] | len()").unwrap();
+        assert!(ctx.engine.profile().is_empty());
+        assert!(ctx.execute("/profile").is_err());
+    }
+
+    #[test]
+    fn test_execute_render() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["".to_string().into()]);
+        assert!(!ctx.render);
+
+        assert!(matches!(ctx.execute("/render on"), Ok(CommandOutput::None)));
+        assert!(ctx.render);
+
+        assert!(matches!(ctx.execute("/render off"), Ok(CommandOutput::None)));
+        assert!(!ctx.render);
+
+        assert!(ctx.execute("/render maybe").is_err());
+    }
+
+    #[test]
+    fn test_execute_reload_without_source() {
+        let engine = mq_lang::DefaultEngine::default();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+        assert!(ctx.execute("/reload").is_err());
+    }
+
+    #[test]
+    fn test_execute_doc_builtin() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["".to_string().into()]);
+
+        let CommandOutput::String(lines) = ctx.execute("/doc add").unwrap() else {
+            panic!("Expected String output");
+        };
+        let doc = mq_lang::BUILTIN_FUNCTION_DOC.get("add").unwrap();
+        assert_eq!(lines[0], format!("add({})", doc.params.join(", ")));
+        assert!(lines.contains(&doc.description.to_string()));
+        assert!(lines.contains(&"Parameters:".to_string()));
+    }
+
+    #[test]
+    fn test_execute_doc_selector() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["".to_string().into()]);
+
+        let CommandOutput::String(lines) = ctx.execute("/doc .h").unwrap() else {
+            panic!("Expected String output");
+        };
+        assert!(lines.contains(&mq_lang::BUILTIN_SELECTOR_DOC.get(".h").unwrap().description.to_string()));
+    }
+
+    #[test]
+    fn test_execute_doc_user_function() {
+        let mut engine = mq_lang::DefaultEngine::default();
+        engine.load_builtin_module();
+        let mut ctx = CommandContext::new(engine, vec!["".to_string().into()]);
+        ctx.execute("# Doubles a number.\ndef double(x): x * 2;").unwrap();
+
+        let CommandOutput::String(lines) = ctx.execute("/doc double").unwrap() else {
+            panic!("Expected String output");
+        };
+        assert_eq!(lines[0], "double(x)");
+        assert!(lines.iter().any(|line| line.contains("Doubles a number.")));
+    }
+
+    #[test]
+    fn test_execute_doc_not_found() {
+        let mut ctx = CommandContext::new(mq_lang::DefaultEngine::default(), vec!["".to_string().into()]);
+        assert!(ctx.execute("/doc no_such_function").is_err());
+    }
 }
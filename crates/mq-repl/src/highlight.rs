@@ -0,0 +1,261 @@
+use colored::*;
+use mq_lang::{LexerOptions, Position, Token, TokenKind};
+use std::borrow::Cow;
+
+const COMMANDS_PATTERN: &str = r"^(/clear|/copy|/doc|/edit|/env|/help|/history|/quit|/load|/profile|/reload|/render|/reset|/restore|/save|/session|/source|/time|/vars|/version)\b";
+
+/// Highlights mq syntax using the token kinds produced by the mq lexer.
+///
+/// Lexing is lossy so that incomplete input can still be highlighted while typing: anything
+/// after the first invalid character is left as is. Brackets without a counterpart are shown
+/// in red, and the bracket matching the one at the cursor is shown in bold.
+pub(crate) fn highlight_mq_syntax(line: &str, pos: usize) -> Cow<'_, str> {
+    if let Ok(re) = regex_lite::Regex::new(COMMANDS_PATTERN)
+        && let Some(m) = re.find(line)
+    {
+        return Cow::Owned(format!("{}{}", m.as_str().bright_green(), &line[m.end()..]));
+    }
+
+    let tokens = lex(line);
+    let spans: Vec<(usize, usize)> = tokens.iter().map(|token| token_span(line, token)).collect();
+    let brackets = match_brackets(&tokens);
+    let cursor_pair = cursor_bracket(&spans, &brackets, pos);
+
+    let mut result = String::with_capacity(line.len());
+    let mut last = 0;
+    for (i, (token, &(start, end))) in tokens.iter().zip(&spans).enumerate() {
+        if start < last || end <= start {
+            continue;
+        }
+
+        result.push_str(&line[last..start]);
+        let text = &line[start..end];
+        let styled = match brackets[i] {
+            BracketState::Unmatched => text.bright_red().bold().to_string(),
+            BracketState::Matched(_) if cursor_pair.is_some_and(|(a, b)| a == i || b == i) => {
+                text.bold().underline().to_string()
+            }
+            _ => style_token(&token.kind, text),
+        };
+        result.push_str(&styled);
+        last = end;
+    }
+    result.push_str(&line[last..]);
+
+    Cow::Owned(result)
+}
+
+/// Returns the first bracket that has no counterpart, if any.
+pub(crate) fn find_unmatched_closing_bracket(code: &str) -> Option<char> {
+    let tokens = lex(code);
+    match_brackets(&tokens)
+        .iter()
+        .zip(&tokens)
+        .find_map(|(state, token)| match (state, &token.kind) {
+            (BracketState::Unmatched, TokenKind::RParen) => Some(')'),
+            (BracketState::Unmatched, TokenKind::RBracket) => Some(']'),
+            (BracketState::Unmatched, TokenKind::RBrace) => Some('}'),
+            _ => None,
+        })
+}
+
+fn lex(code: &str) -> Vec<Token> {
+    mq_lang::tokenize(
+        code,
+        LexerOptions {
+            ignore_errors: true,
+            include_spaces: false,
+        },
+    )
+    .unwrap_or_default()
+    .into_iter()
+    .filter(|token| !token.is_eof())
+    .collect()
+}
+
+fn style_token(kind: &TokenKind, text: &str) -> String {
+    match kind {
+        TokenKind::Def
+        | TokenKind::Let
+        | TokenKind::Var
+        | TokenKind::If
+        | TokenKind::Elif
+        | TokenKind::Else
+        | TokenKind::End
+        | TokenKind::While
+        | TokenKind::Loop
+        | TokenKind::Foreach
+        | TokenKind::Self_
+        | TokenKind::Nodes
+        | TokenKind::Fn
+        | TokenKind::Break
+        | TokenKind::Continue
+        | TokenKind::Include
+        | TokenKind::Import
+        | TokenKind::Module
+        | TokenKind::Match
+        | TokenKind::Try
+        | TokenKind::Catch
+        | TokenKind::Do
+        | TokenKind::Macro
+        | TokenKind::Quote
+        | TokenKind::Unquote
+        | TokenKind::As
+        | TokenKind::BoolLiteral(_)
+        | TokenKind::None => text.bright_blue().to_string(),
+        TokenKind::StringLiteral(_) | TokenKind::InterpolatedString(_) | TokenKind::BytesLiteral(_) => {
+            text.bright_green().to_string()
+        }
+        TokenKind::NumberLiteral(_) => text.bright_magenta().to_string(),
+        TokenKind::Selector(_) => text.bright_cyan().to_string(),
+        TokenKind::Env(_) => text.cyan().to_string(),
+        TokenKind::Comment(_) => text.dimmed().to_string(),
+        TokenKind::Ident(_)
+        | TokenKind::LParen
+        | TokenKind::RParen
+        | TokenKind::LBracket
+        | TokenKind::RBracket
+        | TokenKind::LBrace
+        | TokenKind::RBrace
+        | TokenKind::Comma
+        | TokenKind::Eof
+        | TokenKind::NewLine
+        | TokenKind::Tab(_)
+        | TokenKind::Whitespace(_) => text.to_string(),
+        _ => text.bright_yellow().to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BracketState {
+    NotBracket,
+    Matched(usize),
+    Unmatched,
+}
+
+/// Pairs up brackets so that each one records the index of its counterpart.
+fn match_brackets(tokens: &[Token]) -> Vec<BracketState> {
+    let mut states = vec![BracketState::NotBracket; tokens.len()];
+    let mut stack: Vec<(usize, &TokenKind)> = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        match &token.kind {
+            TokenKind::LParen | TokenKind::LBracket | TokenKind::LBrace => {
+                states[i] = BracketState::Unmatched;
+                stack.push((i, &token.kind));
+            }
+            kind @ (TokenKind::RParen | TokenKind::RBracket | TokenKind::RBrace) => {
+                let opening = match kind {
+                    TokenKind::RParen => TokenKind::LParen,
+                    TokenKind::RBracket => TokenKind::LBracket,
+                    _ => TokenKind::LBrace,
+                };
+                match stack.last() {
+                    Some((open, open_kind)) if **open_kind == opening => {
+                        states[*open] = BracketState::Matched(i);
+                        states[i] = BracketState::Matched(*open);
+                        stack.pop();
+                    }
+                    _ => states[i] = BracketState::Unmatched,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    states
+}
+
+/// Returns the token indices of the bracket pair touching the cursor, if any.
+fn cursor_bracket(spans: &[(usize, usize)], brackets: &[BracketState], pos: usize) -> Option<(usize, usize)> {
+    spans
+        .iter()
+        .zip(brackets)
+        .enumerate()
+        .filter(|(_, ((start, end), _))| *start == pos || *end == pos)
+        .find_map(|(i, (_, state))| match state {
+            BracketState::Matched(other) => Some((i, *other)),
+            _ => None,
+        })
+}
+
+/// Converts a token's line/column range into a byte span of `code`.
+fn token_span(code: &str, token: &Token) -> (usize, usize) {
+    let start = byte_offset(code, &token.range.start);
+    let end = byte_offset(code, &token.range.end).max(start);
+    (start, end)
+}
+
+fn byte_offset(code: &str, position: &Position) -> usize {
+    let line_start = code
+        .split_inclusive('\n')
+        .take(position.line.saturating_sub(1) as usize)
+        .map(str::len)
+        .sum::<usize>();
+    let line = code[line_start..].split('\n').next().unwrap_or_default();
+    line_start
+        + line
+            .char_indices()
+            .nth(position.column.saturating_sub(1))
+            .map(|(i, _)| i)
+            .unwrap_or(line.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn plain(s: &str) -> String {
+        let re = regex_lite::Regex::new(r"\x1b\[[0-9;]*m").unwrap();
+        re.replace_all(s, "").to_string()
+    }
+
+    #[rstest]
+    #[case::keyword("let x = 42")]
+    #[case::command("/help")]
+    #[case::string_with_slash(r#""hello/world""#)]
+    #[case::operators("x = 1 + 2")]
+    #[case::selector(".h1 | upcase()")]
+    #[case::incomplete_string(r#"let x = "abc"#)]
+    #[case::multibyte(r#"let x = "日本語" | len(x)"#)]
+    #[case::multiline("def f(x):\n  x + 1\nend")]
+    fn test_highlight_preserves_text(#[case] line: &str) {
+        colored::control::set_override(true);
+        assert_eq!(plain(&highlight_mq_syntax(line, line.len())), line);
+    }
+
+    #[test]
+    fn test_highlight_colors_tokens() {
+        colored::control::set_override(true);
+        let result = highlight_mq_syntax("let x = 42", 0);
+        assert!(result.contains(&"let".bright_blue().to_string()));
+        assert!(result.contains(&"42".bright_magenta().to_string()));
+        assert!(result.contains(&"=".bright_yellow().to_string()));
+    }
+
+    #[test]
+    fn test_highlight_unmatched_bracket() {
+        colored::control::set_override(true);
+        let result = highlight_mq_syntax("add(1, 2))", 0);
+        assert!(result.contains(&")".bright_red().bold().to_string()));
+    }
+
+    #[test]
+    fn test_highlight_cursor_bracket_pair() {
+        colored::control::set_override(true);
+        let result = highlight_mq_syntax("f(1)", 4);
+        assert!(result.contains(&"(".bold().underline().to_string()));
+        assert!(result.contains(&")".bold().underline().to_string()));
+    }
+
+    #[rstest]
+    #[case::balanced("add(1, [2, {\"a\": 3}])", None)]
+    #[case::open_only("add(1,", None)]
+    #[case::extra_paren("add(1))", Some(')'))]
+    #[case::mismatched("[1, 2)", Some(')'))]
+    #[case::bracket_in_string(r#""(]""#, None)]
+    fn test_find_unmatched_closing_bracket(#[case] code: &str, #[case] expected: Option<char>) {
+        assert_eq!(find_unmatched_closing_bracket(code), expected);
+    }
+}
@@ -14,6 +14,7 @@
 //! repl.run().unwrap();
 //! ```
 mod command_context;
+mod highlight;
 mod repl;
 
 pub use repl::Repl;
@@ -1,4 +1,5 @@
 use crate::command_context::{Command, CommandContext, CommandOutput};
+use crate::highlight::{find_unmatched_closing_bracket, highlight_mq_syntax};
 use colored::*;
 use miette::IntoDiagnostic;
 use mq_lang::RuntimeValue;
@@ -12,79 +13,6 @@ use rustyline::{
 };
 use std::{borrow::Cow, cell::RefCell, fs, rc::Rc};
 
-/// Highlight mq syntax with keywords and commands.
-///
-/// Collects all matches on the original string before applying any ANSI codes, then
-/// selects non-overlapping matches by priority so that later passes (e.g. operators)
-/// cannot re-match characters inside already-highlighted tokens such as strings or
-/// commands that start with `/`.
-fn highlight_mq_syntax(line: &str) -> Cow<'_, str> {
-    // (start, end, priority, colored_replacement) — lower priority number wins
-    let mut matches: Vec<(usize, usize, u8, String)> = Vec::new();
-
-    let commands_pattern = r"^(/clear|/copy|/edit|/env|/help|/history|/quit|/load|/reset|/vars|/version)\b";
-    if let Ok(re) = regex_lite::Regex::new(commands_pattern) {
-        for m in re.find_iter(line) {
-            matches.push((m.start(), m.end(), 0, m.as_str().bright_green().to_string()));
-        }
-    }
-
-    if let Ok(re) = regex_lite::Regex::new(r#""([^"\\]|\\.)*""#) {
-        for m in re.find_iter(line) {
-            matches.push((m.start(), m.end(), 1, m.as_str().bright_green().to_string()));
-        }
-    }
-
-    let keywords_pattern = r"\b(def|let|if|elif|else|end|while|foreach|self|nodes|fn|break|continue|include|true|false|None|match|import|module|do|var|macro|quote|unquote)\b";
-    if let Ok(re) = regex_lite::Regex::new(keywords_pattern) {
-        for m in re.find_iter(line) {
-            matches.push((m.start(), m.end(), 2, m.as_str().bright_blue().to_string()));
-        }
-    }
-
-    if let Ok(re) = regex_lite::Regex::new(r"\b\d+\b") {
-        for m in re.find_iter(line) {
-            matches.push((m.start(), m.end(), 3, m.as_str().bright_magenta().to_string()));
-        }
-    }
-
-    let operators_pattern =
-        r"(\/\/=|<<|>>|\|\||\?\?|<=|>=|==|!=|=~|&&|\+=|-=|\*=|\/=|\|=|=|\||:|;|\?|!|\+|-|\*|\/|%|<|>|@)";
-    if let Ok(re) = regex_lite::Regex::new(operators_pattern) {
-        for m in re.find_iter(line) {
-            matches.push((m.start(), m.end(), 4, m.as_str().bright_yellow().to_string()));
-        }
-    }
-
-    // Sort by start position; ties broken by priority (lower = higher priority).
-    matches.sort_by(|a, b| a.0.cmp(&b.0).then(a.2.cmp(&b.2)));
-
-    // Greedily select non-overlapping matches.
-    let mut selected: Vec<(usize, usize, String)> = Vec::new();
-    let mut covered_end = 0usize;
-    for (start, end, _, replacement) in matches {
-        if start >= covered_end {
-            covered_end = end;
-            selected.push((start, end, replacement));
-        }
-    }
-
-    let mut result = String::new();
-    let mut pos = 0;
-    for (start, end, replacement) in selected {
-        if start > pos {
-            result.push_str(&line[pos..start]);
-        }
-        result.push_str(&replacement);
-        pos = end;
-    }
-    if pos < line.len() {
-        result.push_str(&line[pos..]);
-    }
-
-    Cow::Owned(result)
-}
-
 /// Format a markdown node with type-specific colors.
 fn format_markdown_node(node: &mq_markdown::Node) -> String {
     let s = node.to_string();
@@ -122,6 +50,30 @@ fn format_runtime_value(value: &mq_lang::RuntimeValue) -> Option<String> {
     Some(s)
 }
 
+/// Format evaluation results for display.
+///
+/// With `render` enabled, results made up only of markdown nodes are rendered as a single
+/// ANSI-styled document instead of being colored node by node.
+fn format_runtime_values(values: &[mq_lang::RuntimeValue], render: bool) -> Vec<String> {
+    let nodes = values
+        .iter()
+        .filter(|value| !value.is_empty())
+        .map(|value| match value {
+            RuntimeValue::Markdown(node, _) => Some((**node).clone()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>();
+
+    match nodes {
+        Some(nodes) if render && !nodes.is_empty() => {
+            let markdown = mq_markdown::Markdown::new(nodes);
+            let rendered = markdown.to_colored_string_with_theme(&mq_markdown::ColorTheme::from_env());
+            vec![rendered.trim_end().to_string()]
+        }
+        _ => values.iter().filter_map(format_runtime_value).collect(),
+    }
+}
+
 /// Get the appropriate prompt symbol based on character availability
 fn get_prompt() -> &'static str {
     if is_char_available() { "❯ " } else { "> " }
@@ -281,23 +233,35 @@ impl Highlighter for MqLineHelper {
         true
     }
 
-    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
-        highlight_mq_syntax(line)
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        highlight_mq_syntax(line, pos)
+    }
+}
+
+/// Decides whether the input is submitted, rejected, or continued on the next line.
+///
+/// A blank line always submits the input so that a multi-line entry can be forced through.
+fn validate_input(input: &str) -> ValidationResult {
+    if input.is_empty() || input.ends_with("\n") || input.starts_with("/") {
+        return ValidationResult::Valid(None);
+    }
+
+    if let Some(bracket) = find_unmatched_closing_bracket(input) {
+        return ValidationResult::Invalid(Some(format!("  {}", format!("unmatched `{}`", bracket).bright_red())));
+    }
+
+    // Only keep reading lines while the input could still become valid; genuine syntax
+    // errors are submitted so that the evaluator can report them.
+    if mq_lang::parse_recovery(input).1.is_incomplete() {
+        ValidationResult::Incomplete
+    } else {
+        ValidationResult::Valid(None)
     }
 }
 
 impl Validator for MqLineHelper {
     fn validate(&self, ctx: &mut ValidationContext<'_>) -> Result<ValidationResult, ReadlineError> {
-        let input = ctx.input();
-        if input.is_empty() || input.ends_with("\n") || input.starts_with("/") {
-            return Ok(ValidationResult::Valid(None));
-        }
-
-        if mq_lang::parse_recovery(input).1.has_errors() {
-            Ok(ValidationResult::Incomplete)
-        } else {
-            Ok(ValidationResult::Valid(None))
-        }
+        Ok(validate_input(ctx.input()))
     }
 
     fn validate_while_typing(&self) -> bool {
@@ -319,7 +283,11 @@ impl Completer for MqLineHelper {
             })
             .collect::<Vec<_>>();
 
-        if line.starts_with(Command::LoadFile("".to_string()).to_string().as_str()) {
+        if line.starts_with(Command::LoadFile("".to_string()).to_string().as_str())
+            || line.starts_with(Command::Source("".to_string()).to_string().as_str())
+            || line.starts_with(Command::Session("".to_string()).to_string().as_str())
+            || line.starts_with(Command::Restore("".to_string()).to_string().as_str())
+        {
             let (_, file_completions) = self.file_completer.complete_path(line, pos)?;
             completions.extend(file_completions);
         }
@@ -419,16 +387,24 @@ impl Repl {
 
             match readline {
                 Ok(line) => {
-                    editor.add_history_entry(&line).unwrap();
+                    // Multi-line entries are stored whole; the trailing blank line used to
+                    // force submission is not part of the entry.
+                    editor.add_history_entry(line.trim_end()).unwrap();
 
-                    match self.command_context.borrow_mut().execute(&line) {
+                    let result = self.command_context.borrow_mut().execute(&line);
+                    for warning in self.command_context.borrow_mut().take_warnings() {
+                        eprintln!("Warning: {}", warning);
+                    }
+
+                    match result {
                         Ok(CommandOutput::String(s)) => {
                             if !s.is_empty() {
                                 println!("{}", s.join("\n"))
                             }
                         }
                         Ok(CommandOutput::Value(runtime_values)) => {
-                            let lines: Vec<String> = runtime_values.iter().filter_map(format_runtime_value).collect();
+                            let render = self.command_context.borrow().render;
+                            let lines = format_runtime_values(&runtime_values, render);
                             if !lines.is_empty() {
                                 println!("{}", lines.join("\n"))
                             }
@@ -438,7 +414,9 @@ impl Repl {
                                 .history()
                                 .iter()
                                 .enumerate()
-                                .map(|(i, entry)| format!("  {:>4}  {}", i + 1, entry.dimmed()))
+                                .map(|(i, entry)| {
+                                    format!("  {:>4}  {}", i + 1, entry.replace('\n', "\n        ").dimmed())
+                                })
                                 .collect();
                             if entries.is_empty() {
                                 println!("  No history.");
@@ -492,54 +470,21 @@ mod tests {
     }
 
     #[test]
-    fn test_highlight_mq_syntax() {
-        let result = highlight_mq_syntax("let x = 42");
-        assert!(result.contains("let"));
-
-        let result = highlight_mq_syntax("/help");
-        assert!(result.contains("help"));
-
-        let result = highlight_mq_syntax("x = 1 + 2");
-        assert!(result.contains("="));
-        assert!(result.contains("+"));
-
-        let result = highlight_mq_syntax(r#""hello world""#);
-        assert!(result.contains("hello world"));
-
-        let result = highlight_mq_syntax("42");
-        assert!(result.contains("42"));
-    }
-
-    #[test]
-    fn test_highlight_string_with_slash() {
-        // The `/` inside a string literal must not be re-highlighted as an operator,
-        // which would break the ANSI codes and corrupt the display.
-        let result = highlight_mq_syntax(r#""hello/world""#);
-        assert!(
-            result.contains("hello/world"),
-            "slash inside string was broken by operator pass"
-        );
-    }
-
-    #[test]
-    fn test_highlight_command_slash_not_broken() {
-        // The leading `/` of a command must stay inside the command's color span and
-        // not be re-wrapped by the operator highlighter.
-        let result = highlight_mq_syntax("/help");
-        assert!(
-            result.contains("/help"),
-            "leading slash of command was broken by operator pass"
-        );
-    }
-
-    #[test]
-    fn test_highlight_operator_slash_outside_string() {
-        // A bare `/` that is not inside a string or command should still be highlighted.
-        let result = highlight_mq_syntax("a / b");
-        assert!(
-            result.contains("/"),
-            "bare slash operator should still appear in output"
-        );
+    fn test_validate_input() {
+        assert!(matches!(validate_input("add(1, 2)"), ValidationResult::Valid(None)));
+        assert!(matches!(validate_input("def f(x):"), ValidationResult::Incomplete));
+        assert!(matches!(
+            validate_input("def f(x):\n  x + 1"),
+            ValidationResult::Incomplete
+        ));
+        assert!(matches!(
+            validate_input("def f(x):\n  x + 1\nend"),
+            ValidationResult::Valid(None)
+        ));
+        assert!(matches!(validate_input("def f(x):\n"), ValidationResult::Valid(None)));
+        assert!(matches!(validate_input("1 +* 2"), ValidationResult::Valid(None)));
+        assert!(matches!(validate_input("add(1))"), ValidationResult::Invalid(Some(_))));
+        assert!(matches!(validate_input("/help"), ValidationResult::Valid(None)));
     }
 
     #[test]
@@ -570,6 +515,36 @@ mod tests {
         assert!(format_runtime_value(&v).is_none());
     }
 
+    #[test]
+    fn test_format_runtime_values_render() {
+        let values: Vec<mq_lang::RuntimeValue> = mq_markdown::Markdown::from_markdown_str("# Title\n\n- item\n")
+            .unwrap()
+            .nodes
+            .into_iter()
+            .map(mq_lang::RuntimeValue::from)
+            .collect();
+
+        assert_eq!(format_runtime_values(&values, false).len(), 2);
+
+        let rendered = format_runtime_values(&values, true);
+        assert_eq!(rendered.len(), 1);
+        assert!(rendered[0].contains("Title"));
+        assert!(rendered[0].contains("item"));
+        assert!(rendered[0].contains('\x1b'));
+    }
+
+    #[test]
+    fn test_format_runtime_values_render_mixed() {
+        let values = vec![
+            mq_lang::RuntimeValue::Number(1.into()),
+            mq_lang::RuntimeValue::String("a".to_string()),
+        ];
+        assert_eq!(
+            format_runtime_values(&values, true),
+            format_runtime_values(&values, false)
+        );
+    }
+
     #[test]
     fn test_is_char_available_utf8_env() {
         // Save original env vars
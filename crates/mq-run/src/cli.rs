@@ -13,6 +13,7 @@ use std::path::Path;
 use std::process::Command;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 use std::{fs, path::PathBuf};
 use which::which;
 
@@ -22,8 +23,19 @@ use which::which;
 // processing can fan out across rayon worker threads.
 static HAD_TRUTHY_OUTPUT: AtomicBool = AtomicBool::new(false);
 
+// Tracks whether any non-empty value was produced during a run.
+// Used by --quiet / -q to decide the grep-compatible exit code.
+static HAD_NON_EMPTY_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+/// Exit code used by --quiet when no non-empty result was produced (grep compatible).
+const EXIT_CODE_NO_MATCH: i32 = 1;
+/// Exit code used by --quiet when an error occurred (grep compatible).
+const EXIT_CODE_ERROR: i32 = 2;
+
+use crate::explain;
 use crate::grep;
 use crate::reference;
+use crate::stats;
 
 #[derive(Parser, Debug, Default)]
 #[command(name = "mq")]
@@ -98,6 +110,16 @@ pub struct Cli {
     /// No timeout by default.
     #[arg(long, value_name = "SECONDS")]
     timeout: Option<f64>,
+
+    /// Wait on this local TCP port for a debugger to attach, then evaluate under it.
+    /// Input files are evaluated one at a time while the debugger is attached.
+    #[cfg(feature = "debugger")]
+    #[arg(
+        long = "debug-listen",
+        value_name = "PORT",
+        conflicts_with_all = ["stream", "eval_all", "raw_slurp", "count", "output_dir"]
+    )]
+    debug_listen: Option<u16>,
 }
 
 #[cfg(unix)]
@@ -140,23 +162,87 @@ enum InputFormat {
 
 impl InputFormat {
     fn from_extension(ext: &str) -> Self {
+        Self::from_known_extension(ext).unwrap_or_default()
+    }
+
+    fn from_known_extension(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
-            "md" | "markdown" => Self::Markdown,
-            "mdx" => Self::Mdx,
-            "html" | "htm" => Self::Html,
-            "txt" | "log" => Self::Raw,
-            "jsonl" | "ndjson" => Self::Text,
-            "cbor" => Self::Cbor,
-            "csv" => Self::Csv,
-            "json" => Self::Json,
-            "psv" => Self::Psv,
-            "toml" => Self::Toml,
-            "toon" => Self::Toon,
-            "tsv" => Self::Tsv,
-            "xml" => Self::Xml,
-            "yaml" | "yml" => Self::Yaml,
-            _ => Self::Markdown,
+            "md" | "markdown" => Some(Self::Markdown),
+            "mdx" => Some(Self::Mdx),
+            "html" | "htm" => Some(Self::Html),
+            "txt" | "log" => Some(Self::Raw),
+            "jsonl" | "ndjson" => Some(Self::Text),
+            "cbor" => Some(Self::Cbor),
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            "psv" => Some(Self::Psv),
+            "toml" => Some(Self::Toml),
+            "toon" => Some(Self::Toon),
+            "tsv" => Some(Self::Tsv),
+            "xml" => Some(Self::Xml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Detects the format of a document from its content.
+    ///
+    /// Leading frontmatter is skipped. Documents starting with an `<!DOCTYPE html>`, `<html>`,
+    /// `<head>` or `<body>` tag are HTML; documents with top-level `import`/`export` statements
+    /// or JSX component tags (`<Component`) outside code fences are MDX. Everything else is Markdown.
+    fn sniff(content: &str) -> Self {
+        let body = Self::strip_frontmatter(content.trim_start_matches('\u{feff}').trim_start());
+        let head = body
+            .trim_start()
+            .chars()
+            .take(16)
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        if ["<!doctype html", "<html", "<head", "<body"]
+            .iter()
+            .any(|tag| head.starts_with(tag))
+        {
+            return Self::Html;
+        }
+
+        let mut in_code_fence = false;
+        for line in body.lines() {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                in_code_fence = !in_code_fence;
+                continue;
+            }
+            if in_code_fence {
+                continue;
+            }
+
+            let is_esm = line.starts_with("import ") || line.starts_with("export ");
+            let is_jsx = trimmed
+                .strip_prefix('<')
+                .and_then(|rest| rest.chars().next())
+                .is_some_and(|c| c.is_ascii_uppercase());
+            if is_esm || is_jsx {
+                return Self::Mdx;
+            }
+        }
+
+        Self::Markdown
+    }
+
+    fn strip_frontmatter(content: &str) -> &str {
+        for delimiter in ["---", "+++"] {
+            if let Some(rest) = content
+                .strip_prefix(delimiter)
+                .filter(|rest| rest.starts_with('\n') || rest.starts_with("\r\n"))
+            {
+                return rest
+                    .find(&format!("\n{}", delimiter))
+                    .map(|end| &rest[end + 1 + delimiter.len()..])
+                    .unwrap_or(content);
+            }
         }
+        content
     }
 
     fn needs_binary_read(&self) -> bool {
@@ -286,7 +372,7 @@ struct InputArgs {
     #[arg(short, long, default_value_t = false)]
     from_file: bool,
 
-    /// Set input format
+    /// Set input format. When omitted, the format is detected per file from its extension, falling back to its content
     #[arg(short = 'I', long, value_enum)]
     input_format: Option<InputFormat>,
 
@@ -321,6 +407,11 @@ struct InputArgs {
     #[arg(long = "slurpfile", num_args = 2, value_names = ["NAME", "FILE"])]
     slurp_file: Option<Vec<String>>,
 
+    /// Read NUL-separated input file paths from FILE (`-` for stdin), e.g. the output of
+    /// `find -print0`. The paths are processed after any files given on the command line.
+    #[arg(long = "files0-from", value_name = "FILE")]
+    files0_from: Option<PathBuf>,
+
     /// Enable streaming mode for processing large files line by line
     #[arg(long, default_value_t = false)]
     stream: bool,
@@ -329,11 +420,21 @@ struct InputArgs {
     /// instead of once per file. Enables cross-file aggregation in a single query.
     #[arg(
         long = "eval-all",
+        visible_alias = "slurp",
         default_value_t = false,
         conflicts_with_all = ["update", "count", "stream", "separator"]
     )]
     eval_all: bool,
 
+    /// Concatenate the contents of all input files into a single string and evaluate the
+    /// query once against it (like jq's `-Rs`).
+    #[arg(
+        long = "raw-slurp",
+        default_value_t = false,
+        conflicts_with_all = ["eval_all", "input_format", "update", "count", "stream", "separator"]
+    )]
+    raw_slurp: bool,
+
     /// Allow HTTP imports from additional domain(s) beyond the default.
     /// By default only `raw.githubusercontent.com/harehare` is permitted.
     /// Use `github.com/{user}/{repo}` to allow a specific repository (expanded automatically),
@@ -419,6 +520,27 @@ struct OutputArgs {
     #[clap(short = 'o', long = "output", value_name = "FILE")]
     output_file: Option<PathBuf>,
 
+    /// Process every Markdown file under the input directories and write each result to the
+    /// same relative path under DIR
+    #[clap(long, value_name = "DIR", conflicts_with_all = ["output_file", "count", "quiet", "stream"])]
+    output_dir: Option<PathBuf>,
+
+    /// Print each result value as-is on its own line, without markdown rendering of the whole result
+    #[arg(short = 'r', long = "raw-output", default_value_t = false, conflicts_with_all = ["output_format", "update"])]
+    raw_output: bool,
+
+    /// Like --raw-output, but don't print a newline after each value
+    #[arg(short = 'j', long = "join-output", default_value_t = false, conflicts_with_all = ["output_format", "update", "nul_output", "output_separator"])]
+    join_output: bool,
+
+    /// Like --raw-output, but print a NUL character instead of a newline after each value
+    #[arg(short = '0', long = "nul-output", default_value_t = false, conflicts_with_all = ["output_format", "update", "output_separator"])]
+    nul_output: bool,
+
+    /// Like --raw-output, but print SEP instead of a newline after each value
+    #[arg(long = "output-separator", value_name = "SEP", conflicts_with_all = ["output_format", "update"])]
+    output_separator: Option<String>,
+
     /// Colorize markdown output
     #[arg(short = 'C', long = "color-output", default_value_t = false)]
     color_output: bool,
@@ -440,6 +562,21 @@ struct OutputArgs {
     #[arg(short = 'e', long = "exit-status", default_value_t = false)]
     exit_status: bool,
 
+    /// Suppress all output and report the result only through the exit code.
+    /// Exits with 0 if at least one non-empty result was produced, 1 if none,
+    /// and 2 if an error occurred. Mirrors grep -q.
+    #[arg(short = 'q', long = "quiet", default_value_t = false, conflicts_with = "count")]
+    quiet: bool,
+
+    /// Show a progress bar on stderr while processing multiple files. Only drawn when stderr
+    /// is a terminal.
+    #[arg(long, default_value_t = false)]
+    progress: bool,
+
+    /// Print a summary of files processed, matches, errors and elapsed time to stderr.
+    #[arg(long, default_value_t = false)]
+    stats: bool,
+
     /// Output only the count of matching (non-None) results. Mirrors grep -c.
     /// With multiple files, prints "filename: N" per file and "total: N" at the end.
     #[arg(short = 'c', long = "count", default_value_t = false, conflicts_with_all = ["update", "stream"])]
@@ -464,6 +601,21 @@ impl OutputArgs {
         (before, after)
     }
 
+    /// Returns the string written after each value when one of the raw output modes is enabled.
+    fn value_terminator(&self) -> Option<&str> {
+        if self.join_output {
+            Some("")
+        } else if self.nul_output {
+            Some("\0")
+        } else if let Some(separator) = &self.output_separator {
+            Some(separator)
+        } else if self.raw_output {
+            Some("\n")
+        } else {
+            None
+        }
+    }
+
     /// Applies skip/limit pagination to a vector of values.
     ///
     /// Call this on compact (non-empty) values so that N refers to visible results.
@@ -484,13 +636,29 @@ enum Commands {
     Repl,
     /// Start a debug adapter for mq
     #[cfg(feature = "debugger")]
-    Dap,
+    Dap {
+        /// Listen on this local TCP port instead of stdin/stdout, so editors can attach to it
+        #[arg(long, value_name = "PORT")]
+        port: Option<u16>,
+    },
     /// Generate a shell completion script and print it to stdout
     Completion {
         /// Shell to generate the completion script for
         #[arg(value_enum)]
         shell: CompletionShell,
     },
+    /// Print the tokens, AST, and optimized AST of a query with source spans
+    Explain {
+        /// Query to explain
+        #[arg(value_name = "QUERY")]
+        query: String,
+        /// Load the query from the file
+        #[arg(short, long, default_value_t = false)]
+        from_file: bool,
+        /// Optimization level used to produce the optimized AST
+        #[arg(short = 'O', long = "optimize-level", value_enum, default_value_t = OptimizeLevel::Full)]
+        optimize_level: OptimizeLevel,
+    },
 }
 
 /// Shell targets supported by the `completion` subcommand.
@@ -654,6 +822,10 @@ impl Cli {
                 "  {} - Generate a shell completion script and print it to stdout",
                 "completion".green()
             ),
+            format!(
+                "  {} - Print the tokens, AST, and optimized AST of a query with source spans",
+                "explain".green()
+            ),
         ];
 
         #[cfg(feature = "debugger")]
@@ -780,15 +952,54 @@ impl Cli {
                 mq_repl::Repl::with_engine(engine, vec![mq_lang::RuntimeValue::String("".to_string())]).run()
             }
             #[cfg(feature = "debugger")]
-            Some(Commands::Dap) => mq_dap::start().map_err(|e| miette!(e.to_string())),
+            Some(Commands::Dap { port }) => match port {
+                Some(port) => mq_dap::server::start_tcp(*port),
+                None => mq_dap::start(),
+            }
+            .map_err(|e| miette!(e.to_string())),
             Some(Commands::Completion { shell }) => Self::generate_completion(shell),
+            Some(Commands::Explain {
+                query,
+                from_file,
+                optimize_level,
+            }) => {
+                let query = if *from_file {
+                    fs::read_to_string(query).into_diagnostic()?
+                } else {
+                    query.clone()
+                };
+                let output = explain::explain(&query, optimize_level.clone().into())?;
+                let mut stdout = io::stdout().lock();
+                Self::write_ignore_pipe(&mut stdout, output.as_bytes())
+            }
             None => {
+                let started = Instant::now();
                 let result = if self.input.stream {
                     self.process_streaming()
                 } else {
                     self.process_batch()
                 };
 
+                if result.is_err() {
+                    stats::record_error();
+                }
+                if self.output.stats {
+                    eprintln!("{}", stats::Summary::collect(started.elapsed()));
+                }
+
+                // --quiet / -q: grep-compatible exit codes. 0 when at least one
+                // non-empty value was produced, 1 when none, 2 on error.
+                if self.output.quiet {
+                    return match result {
+                        Ok(()) if HAD_NON_EMPTY_OUTPUT.load(Ordering::Relaxed) => Ok(()),
+                        Ok(()) => std::process::exit(EXIT_CODE_NO_MATCH),
+                        Err(e) => {
+                            eprintln!("{:?}", e);
+                            std::process::exit(EXIT_CODE_ERROR)
+                        }
+                    };
+                }
+
                 // --exit-status / -e: exit with code 1 if no truthy value was
                 // produced. Mirrors jq's behaviour: false and null are falsy;
                 // everything else (including empty string, 0, [], {}) is truthy.
@@ -997,11 +1208,12 @@ impl Cli {
         Ok(
             match self.input.input_format.as_ref().cloned().unwrap_or_else(|| {
                 if let Some(file) = file {
-                    InputFormat::from_extension(&file.extension().unwrap_or_default().to_string_lossy())
+                    InputFormat::from_known_extension(&file.extension().unwrap_or_default().to_string_lossy())
+                        .unwrap_or_else(|| InputFormat::sniff(text))
                 } else if io::stdin().is_terminal() {
                     InputFormat::Null
                 } else {
-                    InputFormat::Markdown
+                    InputFormat::sniff(text)
                 }
             }) {
                 // Native formats
@@ -1072,6 +1284,16 @@ impl Cli {
             runtime_values
         };
 
+        let matches = runtime_values.values().iter().filter(|v| !v.is_empty()).count();
+        stats::record_matches(matches);
+
+        if self.output.quiet {
+            if matches > 0 {
+                HAD_NON_EMPTY_OUTPUT.store(true, Ordering::Relaxed);
+            }
+            return Ok(());
+        }
+
         if let Some(input) = grep_input {
             let (before, after) = self.output.context_counts();
             grep::print_grep(
@@ -1095,29 +1317,10 @@ impl Cli {
         file: &Option<PathBuf>,
         content: &ContentData,
     ) -> miette::Result<()> {
-        let effective_query;
-        let query = match self.auto_query_prefix(file) {
-            Some(prefix) => {
-                effective_query = format!("{} | {}", prefix, query);
-                effective_query.as_str()
-            }
-            None => query,
-        };
-
-        if let Some(f) = file {
-            self.set_file_vars(engine, f);
-        }
-
         let input = self.resolve_input(file, content)?;
         let is_grep = matches!(self.output.output_format, OutputFormat::Grep);
         let grep_input: Option<Vec<mq_lang::RuntimeValue>> = is_grep.then(|| input.clone());
-
-        let runtime_values = if self.output.update {
-            let results = engine.eval(query, input.clone().into_iter()).map_err(|e| *e)?;
-            self.apply_update(input, results)?
-        } else {
-            engine.eval(query, input.into_iter()).map_err(|e| *e)?
-        };
+        let runtime_values = self.evaluate(engine, query, file, input)?;
 
         if let Some(separator) = &self.output.separator {
             let separator = engine
@@ -1132,6 +1335,122 @@ impl Cli {
         self.emit_results(runtime_values, grep_input, file)
     }
 
+    /// Evaluates `query` against `input`, applying the auto-import prefix, the `__FILE__`-family
+    /// vars and `--update` for `file`.
+    fn evaluate(
+        &self,
+        engine: &mut mq_lang::DefaultEngine,
+        query: &str,
+        file: &Option<PathBuf>,
+        input: Vec<mq_lang::RuntimeValue>,
+    ) -> miette::Result<mq_lang::RuntimeValues> {
+        let query = self.effective_query(query, file);
+
+        if let Some(f) = file {
+            self.set_file_vars(engine, f);
+        }
+
+        if self.output.update {
+            let results = engine.eval(&query, input.clone().into_iter()).map_err(|e| *e)?;
+            self.apply_update(input, results)
+        } else {
+            Ok(engine.eval(&query, input.into_iter()).map_err(|e| *e)?)
+        }
+    }
+
+    /// Applies the query to every Markdown file under the input paths and writes each result to
+    /// the mirrored path under `output_dir`.
+    ///
+    /// Directories are walked recursively and only `.md`, `.markdown` and `.mdx` files are
+    /// processed; files given directly are written to `output_dir` by file name. Symlinked
+    /// directories and `output_dir` itself are skipped, and inputs that would be written to the
+    /// same output path are rejected before anything is written.
+    fn process_output_dir(&self, query: &str, output_dir: &Path) -> miette::Result<()> {
+        let Some(inputs) = self.input_files()? else {
+            return Err(miette!("--output-dir requires at least one input file or directory"));
+        };
+
+        let exclude = output_dir.canonicalize().ok();
+        let mut outputs: BTreeMap<PathBuf, PathBuf> = BTreeMap::new();
+        let mut files = Vec::new();
+        for input in &inputs {
+            let input_files = if input.is_dir() {
+                let mut dir_files = Vec::new();
+                Self::collect_markdown_files(input, exclude.as_deref(), &mut dir_files)?;
+                dir_files
+                    .into_iter()
+                    .map(|file| {
+                        let relative = file.strip_prefix(input).unwrap_or(&file).to_path_buf();
+                        (file, relative)
+                    })
+                    .collect::<Vec<_>>()
+            } else {
+                let relative = PathBuf::from(input.file_name().unwrap_or_default());
+                vec![(input.clone(), relative)]
+            };
+
+            for (file, relative) in input_files {
+                let output_file = output_dir.join(relative);
+                if let Some(previous) = outputs.insert(output_file.clone(), file.clone()) {
+                    return Err(miette!(
+                        "--output-dir would write both {} and {} to {}",
+                        previous.display(),
+                        file.display(),
+                        output_file.display()
+                    ));
+                }
+                files.push((file, output_file));
+            }
+        }
+
+        let mut engine = self.create_engine()?;
+        for (file, output_file) in files {
+            let content: ContentData = fs::read_to_string(&file).into_diagnostic()?.into();
+            let file = Some(file);
+            let input = self.resolve_input(&file, &content)?;
+            let runtime_values = self.evaluate(&mut engine, query, &file, input)?;
+
+            if let Some(parent) = output_file.parent() {
+                fs::create_dir_all(parent).into_diagnostic()?;
+            }
+            self.print_to(runtime_values, Some(&output_file))?;
+            stats::record_files(1);
+        }
+
+        Ok(())
+    }
+
+    /// Recursively collects Markdown files under `dir`, sorted by path.
+    ///
+    /// Symlinked directories are not followed, so a link cycle can't recurse forever, and the
+    /// `exclude` directory (the canonical output directory) is skipped.
+    fn collect_markdown_files(dir: &Path, exclude: Option<&Path>, files: &mut Vec<PathBuf>) -> miette::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .into_diagnostic()?
+            .map(|entry| entry.and_then(|e| Ok((e.path(), e.file_type()?))))
+            .collect::<Result<Vec<_>, _>>()
+            .into_diagnostic()?;
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (path, file_type) in entries {
+            if file_type.is_dir() {
+                if exclude.is_some_and(|exclude| path.canonicalize().is_ok_and(|path| path == exclude)) {
+                    continue;
+                }
+                Self::collect_markdown_files(&path, exclude, files)?;
+            } else if file_type.is_symlink() && path.is_dir() {
+                continue;
+            } else if matches!(
+                InputFormat::from_known_extension(&path.extension().unwrap_or_default().to_string_lossy()),
+                Some(InputFormat::Markdown | InputFormat::Mdx)
+            ) {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the effective query string combining any auto-prefix with the base query.
     fn effective_query(&self, query: &str, file: &Option<PathBuf>) -> String {
         match self.auto_query_prefix(file) {
@@ -1151,6 +1470,16 @@ impl Cli {
 
     fn process_batch(&self) -> Result<(), miette::Error> {
         let query = self.get_query()?;
+
+        if let Some(output_dir) = &self.output.output_dir {
+            return self.process_output_dir(&query, output_dir);
+        }
+
+        #[cfg(feature = "debugger")]
+        if let Some(port) = self.debug_listen {
+            return self.process_debug_listen(&query, port);
+        }
+
         let files = self.read_contents()?;
 
         if self.output.count {
@@ -1158,14 +1487,27 @@ impl Cli {
         }
 
         if self.input.eval_all {
+            stats::record_files(files.len());
             return self.execute_eval_all(&query, &files);
         }
 
-        if files.len() > self.parallel_threshold {
+        if self.input.raw_slurp {
+            stats::record_files(files.len());
+            return self.execute_raw_slurp(&query, &files);
+        }
+
+        let progress = stats::ProgressBar::new(files.len(), self.output.progress);
+        let track = |result: miette::Result<()>| {
+            stats::record_files(1);
+            progress.tick();
+            result
+        };
+
+        let result = if files.len() > self.parallel_threshold {
             files.par_iter().try_for_each(|(file, content)| {
                 let mut engine = self.create_engine()?;
-                self.execute(&mut engine, &query, file, content)
-            })?;
+                track(self.execute(&mut engine, &query, file, content))
+            })
         } else {
             let mut engine = self.create_engine()?;
 
@@ -1173,17 +1515,37 @@ impl Cli {
             if files.len() > 1 && self.all_files_same_prefix(&files) && self.output.separator.is_none() {
                 let effective = self.effective_query(&query, &files[0].0);
                 let program = engine.compile(&effective).map_err(|e| *e)?;
-                for (file, content) in &files {
-                    self.execute_compiled(&mut engine, &program, file, content)?;
-                }
+                files
+                    .iter()
+                    .try_for_each(|(file, content)| track(self.execute_compiled(&mut engine, &program, file, content)))
             } else {
                 files
                     .iter()
-                    .try_for_each(|(file, content)| self.execute(&mut engine, &query, file, content))?;
+                    .try_for_each(|(file, content)| track(self.execute(&mut engine, &query, file, content)))
             }
-        }
+        };
 
-        Ok(())
+        progress.finish();
+        result
+    }
+
+    /// Evaluates the query against each input while a debugger attached on `port` controls it.
+    #[cfg(feature = "debugger")]
+    fn process_debug_listen(&self, query: &str, port: u16) -> miette::Result<()> {
+        let files = self.read_contents()?;
+        let mut engine = self.create_engine()?;
+        let query_file = self.query.clone().filter(|_| self.input.from_file);
+
+        eprintln!("Waiting for a debugger to attach on 127.0.0.1:{}", port);
+        let session = mq_dap::server::listen(port, &mut engine, query_file).map_err(|e| miette!(e.to_string()))?;
+
+        let result = files.iter().try_for_each(|(file, content)| {
+            stats::record_files(1);
+            self.execute(&mut engine, query, file, content)
+        });
+
+        session.finish();
+        result
     }
 
     /// `__FILE__`-family vars aren't set here: no single file is "current" once combined.
@@ -1212,6 +1574,21 @@ impl Cli {
         self.emit_results(runtime_values, grep_input, &None)
     }
 
+    /// Like `execute_eval_all`, but passes the concatenated file contents as one raw string.
+    fn execute_raw_slurp(&self, query: &str, files: &[(Option<PathBuf>, ContentData)]) -> miette::Result<()> {
+        let combined = files
+            .iter()
+            .map(|(_, content)| String::from_utf8_lossy(content.as_bytes()))
+            .collect::<String>();
+
+        let mut engine = self.create_engine()?;
+        let runtime_values = engine
+            .eval(query, mq_lang::raw_input(&combined).into_iter())
+            .map_err(|e| *e)?;
+
+        self.emit_results(runtime_values, None, &None)
+    }
+
     fn execute_compiled(
         &self,
         engine: &mut mq_lang::DefaultEngine,
@@ -1279,6 +1656,8 @@ impl Cli {
 
         for (file, content) in files {
             let count = self.count_file(&mut engine, query, file, content)?;
+            stats::record_files(1);
+            stats::record_matches(count);
             total += count;
             if multiple_files {
                 let name = file
@@ -1322,8 +1701,8 @@ impl Cli {
         F: FnMut(Option<&PathBuf>, String) -> miette::Result<()>,
     {
         // If files are specified, process each file line by line
-        if let Some(files) = &self.files {
-            for file in files {
+        if let Some(files) = self.input_files()? {
+            for file in &files {
                 let file_handle = fs::File::open(file).into_diagnostic()?;
                 let reader = io::BufReader::new(file_handle);
                 for line_result in reader.lines() {
@@ -1361,13 +1740,47 @@ impl Cli {
             })
     }
 
+    /// Returns the files given on the command line followed by the NUL-separated paths read
+    /// from `--files0-from`, or `None` when neither is given.
+    fn input_files(&self) -> miette::Result<Option<Vec<PathBuf>>> {
+        let Some(source) = &self.input.files0_from else {
+            return Ok(self.files.clone());
+        };
+
+        let list = if source.as_os_str() == "-" {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf).into_diagnostic()?;
+            buf
+        } else {
+            fs::read(source).into_diagnostic()?
+        };
+
+        let mut files = self.files.clone().unwrap_or_default();
+        files.extend(
+            list.split(|b| *b == b'\0')
+                .filter(|path| !path.is_empty())
+                .map(Self::path_from_bytes),
+        );
+        Ok(Some(files))
+    }
+
+    #[cfg(unix)]
+    fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+
+    #[cfg(not(unix))]
+    fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+
     fn read_contents(&self) -> miette::Result<Vec<(Option<PathBuf>, ContentData)>> {
         if matches!(self.input.input_format, Some(InputFormat::Null)) {
             return Ok(vec![(None, ContentData::empty())]);
         }
 
-        self.files
-            .clone()
+        self.input_files()?
             .map(|files| {
                 let load_contents: miette::Result<Vec<ContentData>> = files
                     .iter()
@@ -1543,8 +1956,16 @@ impl Cli {
     }
 
     fn print(&self, runtime_values: mq_lang::RuntimeValues) -> miette::Result<()> {
+        self.print_to(runtime_values, self.output.output_file.as_deref())
+    }
+
+    fn print_to(&self, runtime_values: mq_lang::RuntimeValues, output_file: Option<&Path>) -> miette::Result<()> {
+        if self.output.quiet {
+            return Ok(());
+        }
+
         let stdout = io::stdout();
-        let mut handle: Box<dyn Write> = if let Some(output_file) = &self.output.output_file {
+        let mut handle: Box<dyn Write> = if let Some(output_file) = output_file {
             let file = fs::File::create(output_file).into_diagnostic()?;
             Box::new(BufWriter::new(file))
         } else if self.output.unbuffered {
@@ -1559,66 +1980,78 @@ impl Cli {
             HAD_TRUTHY_OUTPUT.store(true, Ordering::Relaxed);
         }
 
-        match self.output.output_format {
-            OutputFormat::Raw => {
-                for value in runtime_values {
-                    match value {
-                        mq_lang::RuntimeValue::Bytes(b) => Self::write_ignore_pipe(&mut handle, b)?,
-                        _ => Self::write_ignore_pipe(&mut handle, value.to_string().as_bytes())?,
-                    }
+        if let Some(terminator) = self.output.value_terminator() {
+            for value in runtime_values.iter().filter(|v| !v.is_none()) {
+                match value {
+                    mq_lang::RuntimeValue::Bytes(b) => Self::write_ignore_pipe(&mut handle, b)?,
+                    _ => Self::write_ignore_pipe(&mut handle, value.to_string().as_bytes())?,
                 }
+                Self::write_ignore_pipe(&mut handle, terminator.as_bytes())?;
             }
-            OutputFormat::Json => {
-                let theme = (self.output.color_output && !Self::is_no_color()).then(mq_markdown::ColorTheme::from_env);
-                let json_str = crate::output::json::runtime_values_to_json(runtime_values, theme.as_ref())?;
-                Self::write_ignore_pipe(&mut handle, json_str.as_bytes())?;
-            }
-            OutputFormat::Html => {
-                let markdown = self.build_markdown(runtime_values);
-                Self::write_ignore_pipe(&mut handle, markdown.to_html().as_bytes())?;
-            }
-            OutputFormat::Text => {
-                let markdown = self.build_markdown(runtime_values);
-                Self::write_ignore_pipe(&mut handle, markdown.to_text().as_bytes())?;
-            }
-            OutputFormat::Markdown if self.output.color_output && !Self::is_no_color() => {
-                let markdown = self.build_markdown(runtime_values);
-                let theme = mq_markdown::ColorTheme::from_env();
-                Self::write_ignore_pipe(&mut handle, markdown.to_colored_string_with_theme(&theme).as_bytes())?;
-            }
-            OutputFormat::Markdown => {
-                let markdown = self.build_markdown(runtime_values);
-                Self::write_ignore_pipe(&mut handle, markdown.to_string().as_bytes())?;
-            }
-            OutputFormat::Table => {
-                let theme = (self.output.color_output && !Self::is_no_color()).then(mq_markdown::ColorTheme::from_env);
-                let table = crate::output::table::runtime_values_to_table(runtime_values, theme.as_ref());
-                Self::write_ignore_pipe(&mut handle, format!("{}\n", table).as_bytes())?;
-            }
-            OutputFormat::Grep => {
-                let markdown = self.build_markdown(runtime_values);
-                Self::write_ignore_pipe(&mut handle, markdown.to_string().as_bytes())?;
-            }
-            OutputFormat::Csv => {
-                let csv_str = crate::output::csv::runtime_values_to_csv(runtime_values)?;
-                Self::write_ignore_pipe(&mut handle, csv_str.as_bytes())?;
-            }
-            OutputFormat::Toml => {
-                let toml_str = crate::output::toml::runtime_values_to_toml(runtime_values)?;
-                Self::write_ignore_pipe(&mut handle, toml_str.as_bytes())?;
-            }
-            OutputFormat::Xml => {
-                let xml_str = crate::output::xml::runtime_values_to_xml(runtime_values)?;
-                Self::write_ignore_pipe(&mut handle, xml_str.as_bytes())?;
-            }
-            OutputFormat::Yaml => {
-                let yaml_str = crate::output::yaml::runtime_values_to_yaml(runtime_values)?;
-                Self::write_ignore_pipe(&mut handle, yaml_str.as_bytes())?;
+        } else {
+            match self.output.output_format {
+                OutputFormat::Raw => {
+                    for value in runtime_values {
+                        match value {
+                            mq_lang::RuntimeValue::Bytes(b) => Self::write_ignore_pipe(&mut handle, b)?,
+                            _ => Self::write_ignore_pipe(&mut handle, value.to_string().as_bytes())?,
+                        }
+                    }
+                }
+                OutputFormat::Json => {
+                    let theme =
+                        (self.output.color_output && !Self::is_no_color()).then(mq_markdown::ColorTheme::from_env);
+                    let json_str = crate::output::json::runtime_values_to_json(runtime_values, theme.as_ref())?;
+                    Self::write_ignore_pipe(&mut handle, json_str.as_bytes())?;
+                }
+                OutputFormat::Html => {
+                    let markdown = self.build_markdown(runtime_values);
+                    Self::write_ignore_pipe(&mut handle, markdown.to_html().as_bytes())?;
+                }
+                OutputFormat::Text => {
+                    let markdown = self.build_markdown(runtime_values);
+                    Self::write_ignore_pipe(&mut handle, markdown.to_text().as_bytes())?;
+                }
+                OutputFormat::Markdown if self.output.color_output && !Self::is_no_color() => {
+                    let markdown = self.build_markdown(runtime_values);
+                    let theme = mq_markdown::ColorTheme::from_env();
+                    Self::write_ignore_pipe(&mut handle, markdown.to_colored_string_with_theme(&theme).as_bytes())?;
+                }
+                OutputFormat::Markdown => {
+                    let markdown = self.build_markdown(runtime_values);
+                    Self::write_ignore_pipe(&mut handle, markdown.to_string().as_bytes())?;
+                }
+                OutputFormat::Table => {
+                    let theme =
+                        (self.output.color_output && !Self::is_no_color()).then(mq_markdown::ColorTheme::from_env);
+                    let table = crate::output::table::runtime_values_to_table(runtime_values, theme.as_ref());
+                    Self::write_ignore_pipe(&mut handle, format!("{}\n", table).as_bytes())?;
+                }
+                OutputFormat::Grep => {
+                    let markdown = self.build_markdown(runtime_values);
+                    Self::write_ignore_pipe(&mut handle, markdown.to_string().as_bytes())?;
+                }
+                OutputFormat::Csv => {
+                    let csv_str = crate::output::csv::runtime_values_to_csv(runtime_values)?;
+                    Self::write_ignore_pipe(&mut handle, csv_str.as_bytes())?;
+                }
+                OutputFormat::Toml => {
+                    let toml_str = crate::output::toml::runtime_values_to_toml(runtime_values)?;
+                    Self::write_ignore_pipe(&mut handle, toml_str.as_bytes())?;
+                }
+                OutputFormat::Xml => {
+                    let xml_str = crate::output::xml::runtime_values_to_xml(runtime_values)?;
+                    Self::write_ignore_pipe(&mut handle, xml_str.as_bytes())?;
+                }
+                OutputFormat::Yaml => {
+                    let yaml_str = crate::output::yaml::runtime_values_to_yaml(runtime_values)?;
+                    Self::write_ignore_pipe(&mut handle, yaml_str.as_bytes())?;
+                }
+                OutputFormat::None => {}
             }
-            OutputFormat::None => {}
         }
 
-        if !self.output.unbuffered
+        if (output_file.is_some() || !self.output.unbuffered)
             && let Err(e) = handle.flush()
             && e.kind() != std::io::ErrorKind::BrokenPipe
         {
@@ -3320,6 +3753,21 @@ mod tests {
         assert_eq!(InputFormat::from_extension(ext), expected);
     }
 
+    #[rstest]
+    #[case::markdown("# Title\n\nSome text", InputFormat::Markdown)]
+    #[case::empty("", InputFormat::Markdown)]
+    #[case::frontmatter_markdown("---\ntitle: a\n---\n# Title", InputFormat::Markdown)]
+    #[case::doctype("<!DOCTYPE html>\n<html><body><h1>a</h1></body></html>", InputFormat::Html)]
+    #[case::html_tag("  <html lang=\"en\">\n<body></body></html>", InputFormat::Html)]
+    #[case::inline_html_in_markdown("# Title\n\n<div>a</div>", InputFormat::Markdown)]
+    #[case::jsx_component("# Title\n\n<Chart data={data} />", InputFormat::Mdx)]
+    #[case::esm_import("import { Chart } from './chart'\n\n# Title", InputFormat::Mdx)]
+    #[case::frontmatter_mdx("---\ntitle: a\n---\nexport const meta = {}\n# Title", InputFormat::Mdx)]
+    #[case::jsx_in_code_fence("# Title\n\n```jsx\n<Chart />\nimport a from 'a'\n```", InputFormat::Markdown)]
+    fn test_sniff(#[case] content: &str, #[case] expected: InputFormat) {
+        assert_eq!(InputFormat::sniff(content), expected);
+    }
+
     #[rstest]
     #[case("file.json", Some(r#"import "json" | json::json_parse()"#))]
     #[case("file.yaml", Some(r#"import "yaml" | yaml::yaml_parse()"#))]
@@ -0,0 +1,72 @@
+use mq_lang::{Arena, OptimizationLevel, Shared, SharedCell};
+
+/// Builds a human-readable explanation of how a query is parsed and optimized.
+///
+/// The output has three sections:
+/// - `Tokens`: every lexical token with its source span
+/// - `AST`: the parsed syntax tree, one node per line with source spans
+/// - `Optimized AST`: the tree after the optimization passes for `level` have run
+///
+/// This is intended for users and bug reporters who want to see what mq does with a query
+/// before it is evaluated.
+pub(crate) fn explain(query: &str, level: OptimizationLevel) -> miette::Result<String> {
+    let mut output = String::new();
+
+    let tokens = mq_lang::tokenize(query, mq_lang::LexerOptions::default()).map_err(|e| *e)?;
+    output.push_str("# Tokens\n\n");
+    for token in tokens.iter().filter(|token| !token.is_eof()) {
+        output.push_str(&format!(
+            "{}:{}-{}:{} {:?}\n",
+            token.range.start.line, token.range.start.column, token.range.end.line, token.range.end.column, token.kind
+        ));
+    }
+
+    let token_arena = Shared::new(SharedCell::new(Arena::new(tokens.len() + 1)));
+    let program = mq_lang::parse(query, Shared::clone(&token_arena)).map_err(|e| *e)?;
+    output.push_str("\n# AST\n\n");
+    output.push_str(&mq_lang::dump_ast(&program, Shared::clone(&token_arena)));
+
+    let optimized = mq_lang::optimize(program, level);
+    output.push_str(&format!("\n# Optimized AST ({})\n\n", level_name(level)));
+    output.push_str(&mq_lang::dump_ast(&optimized, token_arena));
+
+    Ok(output)
+}
+
+fn level_name(level: OptimizationLevel) -> &'static str {
+    match level {
+        OptimizationLevel::None => "none",
+        OptimizationLevel::Basic => "basic",
+        OptimizationLevel::Full => "full",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[test]
+    fn test_explain_contains_sections() {
+        let output = explain(".h | upcase()", OptimizationLevel::Full).unwrap();
+        assert!(output.contains("# Tokens"));
+        assert!(output.contains("# AST"));
+        assert!(output.contains("# Optimized AST (full)"));
+        assert!(output.contains("Selector .h @1:1-1:3"));
+        assert!(output.contains("Call upcase/0"));
+    }
+
+    #[rstest]
+    #[case::none(OptimizationLevel::None, "Call add/2")]
+    #[case::full(OptimizationLevel::Full, "Literal 3")]
+    fn test_explain_optimized_ast(#[case] level: OptimizationLevel, #[case] expected: &str) {
+        let output = explain("add(1, 2)", level).unwrap();
+        let optimized = output.split("# Optimized AST").nth(1).unwrap();
+        assert!(optimized.contains(expected), "{}", optimized);
+    }
+
+    #[test]
+    fn test_explain_syntax_error() {
+        assert!(explain("add(1,", OptimizationLevel::Full).is_err());
+    }
+}
@@ -43,9 +43,11 @@
 //! ```
 
 pub mod cli;
+pub(crate) mod explain;
 pub(crate) mod grep;
 pub(crate) mod output;
 pub(crate) mod reference;
+pub(crate) mod stats;
 
 #[cfg(feature = "debugger")]
 pub mod debugger;
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+// Run-wide counters for --stats and --progress. Plain atomics (rather than
+// thread_locals) because batch processing can fan out across rayon worker threads.
+static FILES_PROCESSED: AtomicUsize = AtomicUsize::new(0);
+static MATCHES: AtomicUsize = AtomicUsize::new(0);
+static ERRORS: AtomicUsize = AtomicUsize::new(0);
+
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+pub(crate) fn record_files(count: usize) {
+    FILES_PROCESSED.fetch_add(count, Ordering::Relaxed);
+}
+
+pub(crate) fn record_matches(count: usize) {
+    MATCHES.fetch_add(count, Ordering::Relaxed);
+}
+
+pub(crate) fn record_error() {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// End-of-run summary printed by `--stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Summary {
+    pub files: usize,
+    pub matches: usize,
+    pub errors: usize,
+    pub elapsed: Duration,
+}
+
+impl Summary {
+    pub(crate) fn collect(elapsed: Duration) -> Self {
+        Self {
+            files: FILES_PROCESSED.load(Ordering::Relaxed),
+            matches: MATCHES.load(Ordering::Relaxed),
+            errors: ERRORS.load(Ordering::Relaxed),
+            elapsed,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "files: {}, matches: {}, errors: {}, elapsed: {:.3}s",
+            self.files,
+            self.matches,
+            self.errors,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// A single-line progress bar drawn on stderr.
+///
+/// The bar is only drawn when stderr is a terminal, so redirected or piped runs stay quiet.
+pub(crate) struct ProgressBar {
+    total: usize,
+    enabled: bool,
+}
+
+impl ProgressBar {
+    pub(crate) fn new(total: usize, requested: bool) -> Self {
+        Self {
+            total,
+            enabled: requested && total > 1 && io::stderr().is_terminal(),
+        }
+    }
+
+    /// Redraws the bar with the number of files processed so far.
+    pub(crate) fn tick(&self) {
+        if self.enabled {
+            let _ = write!(
+                io::stderr().lock(),
+                "\r{}",
+                render(FILES_PROCESSED.load(Ordering::Relaxed), self.total)
+            );
+        }
+    }
+
+    /// Clears the bar so that subsequent stderr output starts on a clean line.
+    pub(crate) fn finish(&self) {
+        if self.enabled {
+            let _ = write!(io::stderr().lock(), "\r\x1b[2K");
+        }
+    }
+}
+
+fn render(done: usize, total: usize) -> String {
+    let done = done.min(total);
+    let filled = (done * PROGRESS_BAR_WIDTH)
+        .checked_div(total)
+        .unwrap_or(PROGRESS_BAR_WIDTH);
+    format!(
+        "[{}{}] {}/{}",
+        "#".repeat(filled),
+        " ".repeat(PROGRESS_BAR_WIDTH - filled),
+        done,
+        total
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::start(0, 10, "[                              ] 0/10")]
+    #[case::half(5, 10, "[###############               ] 5/10")]
+    #[case::done(10, 10, "[##############################] 10/10")]
+    #[case::overflow(12, 10, "[##############################] 10/10")]
+    #[case::empty(0, 0, "[##############################] 0/0")]
+    fn test_render(#[case] done: usize, #[case] total: usize, #[case] expected: &str) {
+        assert_eq!(render(done, total), expected);
+    }
+
+    #[test]
+    fn test_summary_display() {
+        let summary = Summary {
+            files: 3,
+            matches: 7,
+            errors: 1,
+            elapsed: Duration::from_millis(1500),
+        };
+        assert_eq!(summary.to_string(), "files: 3, matches: 7, errors: 1, elapsed: 1.500s");
+    }
+}
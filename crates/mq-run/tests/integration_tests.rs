@@ -641,6 +641,277 @@ In {year}, the snowfall was above average.
     Ok(())
 }
 
+#[rstest]
+#[case::html(
+    "<!DOCTYPE html>\n<html><body><h1>Sample Title</h1></body></html>",
+    ".h1",
+    "# Sample Title\n"
+)]
+#[case::mdx("# Title\n\n<Chart year={2023} />\n", "select(is_mdx())", "<Chart year={2023} />\n")]
+#[case::markdown("# Title\n\n<div>inline</div>\n", ".h1", "# Title\n")]
+fn test_input_format_detected_from_content(
+    #[case] content: &str,
+    #[case] query: &str,
+    #[case] expected: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--unbuffered")
+        .arg(query)
+        .write_stdin(content)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    let (_, temp_file_path) = create_file(
+        &format!(
+            "test_input_format_detected_from_content_{}",
+            query.len() + content.len()
+        ),
+        content,
+    );
+    let temp_file_path_clone = temp_file_path.clone();
+
+    defer! {
+        if temp_file_path_clone.exists() {
+            std::fs::remove_file(&temp_file_path_clone).expect("Failed to delete temp file");
+        }
+    }
+
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--unbuffered")
+        .arg(query)
+        .arg(temp_file_path.to_string_lossy().to_string())
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_output_dir_mirrors_input_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = tempfile::tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    std::fs::create_dir_all(input_dir.path().join("guide"))?;
+    std::fs::write(input_dir.path().join("index.md"), "# Index\n\nbody\n")?;
+    std::fs::write(input_dir.path().join("guide/intro.md"), "# Intro\n\n## Setup\n")?;
+    std::fs::write(input_dir.path().join("notes.txt"), "# Not markdown\n")?;
+
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--output-dir")
+        .arg(output_dir.path())
+        .arg(".h1")
+        .arg(input_dir.path())
+        .assert()
+        .success()
+        .stdout("");
+
+    assert_eq!(
+        std::fs::read_to_string(output_dir.path().join("index.md"))?,
+        "# Index\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(output_dir.path().join("guide/intro.md"))?,
+        "# Intro\n"
+    );
+    assert!(!output_dir.path().join("notes.txt").exists());
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+#[rstest]
+#[case::markdown(&[])]
+#[case::raw_output(&["--raw-output"])]
+fn test_output_write_error_is_reported(#[case] flags: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    cargo::cargo_bin_cmd!("mq")
+        .args(flags)
+        .arg("--output")
+        .arg("/dev/full")
+        .arg(".h1")
+        .write_stdin("# Title\n")
+        .assert()
+        .failure();
+
+    Ok(())
+}
+
+#[test]
+fn test_output_dir_rejects_duplicate_output_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = tempfile::tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    std::fs::create_dir_all(input_dir.path().join("a"))?;
+    std::fs::create_dir_all(input_dir.path().join("b"))?;
+    std::fs::write(input_dir.path().join("a/README.md"), "# A\n")?;
+    std::fs::write(input_dir.path().join("b/README.md"), "# B\n")?;
+
+    let assert = cargo::cargo_bin_cmd!("mq")
+        .arg("--output-dir")
+        .arg(output_dir.path())
+        .arg(".h1")
+        .arg(input_dir.path().join("a/README.md"))
+        .arg(input_dir.path().join("b/README.md"))
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr).to_string();
+    assert!(stderr.contains("--output-dir would write both"), "{}", stderr);
+
+    assert!(!output_dir.path().join("README.md").exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_output_dir_inside_input_tree() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = tempfile::tempdir()?;
+    let output_dir = input_dir.path().join("out");
+    std::fs::create_dir_all(&output_dir)?;
+    std::fs::write(input_dir.path().join("index.md"), "# Index\n")?;
+    std::fs::write(output_dir.join("stale.md"), "# Stale\n")?;
+
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .arg(".h1")
+        .arg(input_dir.path())
+        .assert()
+        .success();
+
+    assert_eq!(std::fs::read_to_string(output_dir.join("index.md"))?, "# Index\n");
+    assert!(!output_dir.join("out").exists());
+
+    Ok(())
+}
+
+#[cfg(unix)]
+#[test]
+fn test_output_dir_skips_symlinked_directories() -> Result<(), Box<dyn std::error::Error>> {
+    let input_dir = tempfile::tempdir()?;
+    let output_dir = tempfile::tempdir()?;
+    std::fs::create_dir_all(input_dir.path().join("guide"))?;
+    std::fs::write(input_dir.path().join("guide/intro.md"), "# Intro\n")?;
+    std::os::unix::fs::symlink(input_dir.path(), input_dir.path().join("guide/loop"))?;
+
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--output-dir")
+        .arg(output_dir.path())
+        .arg(".h1")
+        .arg(input_dir.path())
+        .timeout(std::time::Duration::from_secs(30))
+        .assert()
+        .success();
+
+    assert_eq!(
+        std::fs::read_to_string(output_dir.path().join("guide/intro.md"))?,
+        "# Intro\n"
+    );
+    assert!(!output_dir.path().join("guide/loop").exists());
+
+    Ok(())
+}
+
+#[rstest]
+#[case::slurp("--slurp", ".h1", "# One\n# Two\n")]
+#[case::raw_slurp("--raw-slurp", "len()", "19\n")]
+fn test_slurp_modes(
+    #[case] flag: &str,
+    #[case] query: &str,
+    #[case] expected: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let first = temp_dir.path().join("one.md");
+    let second = temp_dir.path().join("two.md");
+    std::fs::write(&first, "# One\n\ntext\n")?;
+    std::fs::write(&second, "# Two\n\n")?;
+
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--unbuffered")
+        .arg(flag)
+        .arg(query)
+        .arg(&first)
+        .arg(&second)
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[rstest]
+#[case::raw_output(vec!["-r"], "x\ny\n")]
+#[case::join_output(vec!["--join-output"], "xy")]
+#[case::nul_output(vec!["--nul-output"], "x\0y\0")]
+#[case::output_separator(vec!["--output-separator", ", "], "x, y, ")]
+fn test_output_framing(#[case] flags: Vec<&str>, #[case] expected: &str) -> Result<(), Box<dyn std::error::Error>> {
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--unbuffered")
+        .args(flags)
+        .arg(".list | to_text()")
+        .write_stdin("- x\n- y\n")
+        .assert()
+        .success()
+        .stdout(expected.to_string());
+
+    Ok(())
+}
+
+#[test]
+fn test_output_framing_conflicts_with_output_format() {
+    cargo::cargo_bin_cmd!("mq")
+        .args(["-r", "-F", "json", "."])
+        .write_stdin("# Title\n")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_files0_from_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let first = temp_dir.path().join("with space.md");
+    let second = temp_dir.path().join("other.md");
+    std::fs::write(&first, "# First\n")?;
+    std::fs::write(&second, "# Second\n")?;
+
+    let list = format!("{}\0{}\0", first.display(), second.display());
+    cargo::cargo_bin_cmd!("mq")
+        .arg("--unbuffered")
+        .args(["--files0-from", "-", "-0", ".h1 | to_text()"])
+        .write_stdin(list)
+        .assert()
+        .success()
+        .stdout("First\0Second\0");
+
+    Ok(())
+}
+
+#[test]
+fn test_stats_summary() -> Result<(), Box<dyn std::error::Error>> {
+    let temp_dir = tempfile::tempdir()?;
+    let first = temp_dir.path().join("one.md");
+    let second = temp_dir.path().join("two.md");
+    std::fs::write(&first, "# One\n\n## Sub\n")?;
+    std::fs::write(&second, "# Two\n")?;
+
+    let assert = cargo::cargo_bin_cmd!("mq")
+        .args(["--stats", "--progress", ".h"])
+        .arg(&first)
+        .arg(&second)
+        .assert()
+        .success()
+        .stdout("# One\n\n## Sub\n# Two\n");
+
+    // The progress bar is not drawn because stderr is not a terminal.
+    let stderr = String::from_utf8(assert.get_output().stderr.clone())?;
+    assert!(
+        stderr.starts_with("files: 2, matches: 3, errors: 0, elapsed: "),
+        "{}",
+        stderr
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_read_file() -> Result<(), Box<dyn std::error::Error>> {
     let (_, temp_file_path) = create_file("test_read_file.md", "test");
@@ -1082,6 +1353,30 @@ fn test_exit_status_parallel_batch(
     Ok(())
 }
 
+#[rstest]
+#[case::match_found(vec!["--quiet", ".h1"], "# title\n\nbody", 0)]
+#[case::no_match(vec!["-q", ".h2"], "# title\n\nbody", 1)]
+#[case::bare_false_is_non_empty(vec!["-q", "-I", "null", "false"], "", 0)]
+#[case::bare_null(vec!["-q", "-I", "null", "None"], "", 1)]
+#[case::parse_error(vec!["-q", "add(1,"], "# title", 2)]
+#[case::runtime_error(vec!["-q", "-I", "null", "error(\"boom\")"], "", 2)]
+fn test_quiet_exit_codes(
+    #[case] args: Vec<&str>,
+    #[case] input: &str,
+    #[case] expected_code: i32,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo::cargo_bin_cmd!("mq");
+    let mut assert = cmd.args(args);
+
+    if !input.is_empty() {
+        assert = assert.write_stdin(input);
+    }
+
+    assert.assert().code(expected_code).stdout("");
+
+    Ok(())
+}
+
 #[rstest]
 #[case::two_matches("# h1\n\n## h2a\n\n## h2b\n", ".h2", "2\n")]
 #[case::no_matches("# h1\n\nbody\n", ".h2", "0\n")]
@@ -1240,3 +1535,16 @@ fn test_completion(#[case] shell: &str, #[case] expected_substring: &str) -> Res
 
     Ok(())
 }
+
+#[test]
+fn test_explain_subcommand() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo::cargo_bin_cmd!("mq");
+    let assert = cmd.args(["explain", "add(1, 2)"]).assert();
+    let output = String::from_utf8(assert.success().get_output().stdout.clone())?;
+
+    assert!(output.contains("# Tokens\n\n1:1-1:4 Ident(\"add\")"));
+    assert!(output.contains("# AST\n\nCall add/2 @1:1-1:4\n  Literal 1 @1:5-1:6"));
+    assert!(output.contains("# Optimized AST (full)\n\nLiteral 3"));
+
+    Ok(())
+}
@@ -1,48 +1,30 @@
 //! Syntax highlighting for mq source embedded in the HTML coverage report.
 //!
-//! Classifies tokens by walking the CST returned by [`mq_lang::parse_recovery`]
-//! and renders each source line as HTML with `<span class="tok-*">` wrappers,
+//! Classifies the tokens of the CST returned by [`mq_lang::parse_recovery`] with
+//! [`mq_lang::highlight_tokens`] and renders each source line as HTML with `<span class="tok-*">` wrappers,
 //! colored via the `tok-*` CSS classes defined in `coverage::HTML_STYLE`
 //! (palette taken from the [Tarn](https://github.com/harehare/tarn-theme) theme).
 
-use mq_lang::{CstNode, CstNodeKind, CstTrivia, Shared, TokenKind};
+use mq_lang::{CstNode, Shared, TokenClass};
 use rustc_hash::FxHashMap;
 
 use crate::coverage::html_escape;
 
-/// Coarse lexical category used to pick a `tok-*` CSS class for a token.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum TokenClass {
-    Comment,
-    Keyword,
-    Operator,
-    Punctuation,
-    Function,
-    Module,
-    Property,
-    String,
-    Number,
-    Boolean,
-    Builtin,
-    Variable,
-}
-
-impl TokenClass {
-    fn css_class(self) -> &'static str {
-        match self {
-            TokenClass::Comment => "tok-comment",
-            TokenClass::Keyword => "tok-keyword",
-            TokenClass::Operator => "tok-operator",
-            TokenClass::Punctuation => "tok-punctuation",
-            TokenClass::Function => "tok-function",
-            TokenClass::Module => "tok-module",
-            TokenClass::Property => "tok-property",
-            TokenClass::String => "tok-string",
-            TokenClass::Number => "tok-number",
-            TokenClass::Boolean => "tok-boolean",
-            TokenClass::Builtin => "tok-builtin",
-            TokenClass::Variable => "tok-variable",
-        }
+/// Returns the `tok-*` CSS class for tokens of `class`.
+fn css_class(class: TokenClass) -> &'static str {
+    match class {
+        TokenClass::Comment => "tok-comment",
+        TokenClass::Keyword => "tok-keyword",
+        TokenClass::Operator => "tok-operator",
+        TokenClass::Punctuation => "tok-punctuation",
+        TokenClass::Function => "tok-function",
+        TokenClass::Module => "tok-module",
+        TokenClass::Property => "tok-property",
+        TokenClass::String => "tok-string",
+        TokenClass::Number => "tok-number",
+        TokenClass::Boolean => "tok-boolean",
+        TokenClass::Builtin => "tok-builtin",
+        TokenClass::Variable => "tok-variable",
     }
 }
 
@@ -55,81 +37,16 @@ struct Span {
     class: TokenClass,
 }
 
-/// The CST reuses the identifier's own node/token for `Call`/`MacroCall`/
-/// `QualifiedAccess` (its `kind` changes but the token stays the identifier),
-/// while `def`/`import`/dict-key names are separate child `Ident` nodes of a
-/// keyword-bearing parent — so classification needs both the node's own kind
-/// and its parent context.
-fn classify_ident(node_kind: &CstNodeKind, parent: Option<&CstNodeKind>, index_in_parent: usize) -> TokenClass {
-    match node_kind {
-        CstNodeKind::Call | CstNodeKind::CallDynamic | CstNodeKind::MacroCall => TokenClass::Function,
-        CstNodeKind::QualifiedAccess => TokenClass::Module,
-        _ => match parent {
-            Some(CstNodeKind::Def) | Some(CstNodeKind::Macro) if index_in_parent == 0 => TokenClass::Function,
-            Some(CstNodeKind::Import) | Some(CstNodeKind::Include) | Some(CstNodeKind::Module) => TokenClass::Module,
-            Some(CstNodeKind::DictEntry) if index_in_parent == 0 => TokenClass::Property,
-            Some(CstNodeKind::QualifiedAccess) => TokenClass::Function,
-            _ => TokenClass::Variable,
-        },
-    }
-}
-
-fn classify_token(
-    token_kind: &TokenKind,
-    node_kind: &CstNodeKind,
-    parent: Option<&CstNodeKind>,
-    index_in_parent: usize,
-) -> Option<TokenClass> {
-    use TokenKind::*;
-
-    Some(match token_kind {
-        Def | Let | If | Elif | Else | End | While | Loop | Foreach | Include | Import | Module | Match | Fn | Do
-        | Var | Macro | Try | Catch | As | Break | Continue | Quote | Unquote => TokenClass::Keyword,
-        Self_ | Nodes | None => TokenClass::Builtin,
-        BoolLiteral(_) => TokenClass::Boolean,
-        NumberLiteral(_) => TokenClass::Number,
-        StringLiteral(_) | InterpolatedString(_) | BytesLiteral(_) | Env(_) | Selector(_) => TokenClass::String,
-        Comment(_) => TokenClass::Comment,
-        Ident(_) => classify_ident(node_kind, parent, index_in_parent),
-        And | Or | Not | Coalesce | Plus | Minus | Asterisk | Slash | Percent | Equal | EqEq | NeEq | Lt | Lte | Gt
-        | Gte | Arrow | Pipe | TildeEqual | NotTildeEqual | LeftShift | RightShift | Convert | DoubleDot
-        | DotDotDot | PlusEqual | MinusEqual | StarEqual | SlashEqual | PercentEqual | DoubleSlashEqual | PipeEqual => {
-            TokenClass::Operator
-        }
-        LParen | RParen | LBrace | RBrace | LBracket | RBracket | Colon | DoubleColon | SemiColon | Comma
-        | Question => TokenClass::Punctuation,
-        Whitespace(_) | Tab(_) | NewLine | Eof => return Option::None,
-    })
-}
-
-fn collect_spans(nodes: &[Shared<CstNode>], parent: Option<&CstNodeKind>, out: &mut Vec<Span>) {
-    for (index, node) in nodes.iter().enumerate() {
-        for trivia in node.leading_trivia.iter().chain(node.trailing_trivia.iter()) {
-            if let CstTrivia::Comment(token) = trivia {
-                out.push(Span {
-                    line: token.range.start.line,
-                    // The lexer's comment token starts after the `#` delimiter;
-                    // pull the span back one column so `#` is highlighted too.
-                    start_col: token.range.start.column.saturating_sub(1),
-                    end_col: token.range.end.column,
-                    class: TokenClass::Comment,
-                });
-            }
-        }
-
-        if let Some(token) = &node.token
-            && let Some(class) = classify_token(&token.kind, &node.kind, parent, index)
-        {
-            out.push(Span {
-                line: token.range.start.line,
-                start_col: token.range.start.column,
-                end_col: token.range.end.column,
-                class,
-            });
-        }
-
-        collect_spans(&node.children, Some(&node.kind), out);
-    }
+fn collect_spans(nodes: &[Shared<CstNode>]) -> Vec<Span> {
+    mq_lang::highlight_tokens(nodes)
+        .into_iter()
+        .map(|token| Span {
+            line: token.range.start.line,
+            start_col: token.range.start.column,
+            end_col: token.range.end.column,
+            class: token.class,
+        })
+        .collect()
 }
 
 fn render_line(line: &str, spans: &[Span]) -> String {
@@ -157,7 +74,7 @@ fn render_line(line: &str, spans: &[Span]) -> String {
         }
         html.push_str(&format!(
             "<span class=\"{}\">{}</span>",
-            span.class.css_class(),
+            css_class(span.class),
             escape_range(start, end)
         ));
         cursor = end.min(chars.len());
@@ -174,8 +91,7 @@ fn render_line(line: &str, spans: &[Span]) -> String {
 /// suitable for a `<td class="code">` cell, in source line order.
 pub(crate) fn highlight_lines(content: &str) -> Vec<String> {
     let (nodes, _) = mq_lang::parse_recovery(content);
-    let mut spans = Vec::new();
-    collect_spans(&nodes, None, &mut spans);
+    let spans = collect_spans(&nodes);
 
     let mut by_line: FxHashMap<u32, Vec<Span>> = FxHashMap::default();
     for span in spans {
@@ -12,6 +12,7 @@ use crate::coverage::{self, CoverageData, CoverageFormat, CoverageHandler, FileC
 enum TestAnnotation {
     Test,
     Parametrize { params_expr: String },
+    Fixture { path: String },
 }
 
 /// A test function discovered in a `.mq` file.
@@ -23,12 +24,24 @@ enum DiscoveredTest {
         params_expr: String,
         arity: usize,
     },
+    /// A test called with the parsed nodes of a fixture input file as its only argument.
+    Fixture {
+        name: String,
+        path: String,
+    },
+}
+
+/// A discovered test together with the 1-based line of its `def`.
+#[derive(Debug, PartialEq)]
+struct LocatedTest {
+    test: DiscoveredTest,
+    line: u32,
 }
 
 /// Discovers and runs mq test functions from `.mq` files.
 ///
 /// A function is treated as a test if its name starts with `test_`, it is
-/// preceded by `# @test` / `# [test]`, or preceded by `# @parametrize(expr)`.
+/// preceded by `# @test` / `# [test]`, `# @parametrize(expr)`, or `# @fixture("path")`.
 /// The runner auto-generates the `run_tests(...)` call from discovered tests.
 pub struct TestRunner {
     files: Vec<PathBuf>,
@@ -99,10 +112,11 @@ impl TestRunner {
                 continue;
             }
 
-            let query = Self::build_test_query(&content, &tests);
+            let query = Self::build_test_query(&content, &tests, file);
             let mut engine = mq_lang::DefaultEngine::default();
             engine.load_builtin_module();
             engine.define_string_value("TEST_FILE", file.to_string_lossy().as_ref());
+            Self::define_fixtures(&mut engine, file, &tests)?;
 
             // Resolve relative `include` statements in the test file.
             if let Some(parent) = file.parent()
@@ -174,12 +188,12 @@ impl TestRunner {
         Ok(())
     }
 
-    fn discover_tests(content: &str) -> Vec<DiscoveredTest> {
+    fn discover_tests(content: &str) -> Vec<LocatedTest> {
         let (nodes, _) = mq_lang::parse_recovery(content);
         Self::discover_tests_in(&nodes)
     }
 
-    fn discover_tests_in(nodes: &[mq_lang::Shared<mq_lang::CstNode>]) -> Vec<DiscoveredTest> {
+    fn discover_tests_in(nodes: &[mq_lang::Shared<mq_lang::CstNode>]) -> Vec<LocatedTest> {
         let mut tests = Vec::new();
 
         for node in nodes {
@@ -201,23 +215,25 @@ impl TestRunner {
                 continue;
             }
 
-            match Self::find_test_annotation(&node.leading_trivia) {
-                Some(TestAnnotation::Test) => {
-                    tests.push(DiscoveredTest::Simple(func_name));
-                }
+            let test = match Self::find_test_annotation(&node.leading_trivia) {
+                Some(TestAnnotation::Test) => DiscoveredTest::Simple(func_name),
                 Some(TestAnnotation::Parametrize { params_expr }) => {
                     let arity = Self::get_arity(node);
-                    tests.push(DiscoveredTest::Parametrized {
+                    DiscoveredTest::Parametrized {
                         name: func_name,
                         params_expr,
                         arity,
-                    });
-                }
-                None if func_name.starts_with("test_") => {
-                    tests.push(DiscoveredTest::Simple(func_name));
+                    }
                 }
-                None => {}
-            }
+                Some(TestAnnotation::Fixture { path }) => DiscoveredTest::Fixture { name: func_name, path },
+                None if func_name.starts_with("test_") => DiscoveredTest::Simple(func_name),
+                None => continue,
+            };
+
+            tests.push(LocatedTest {
+                test,
+                line: node.range().start.line,
+            });
         }
 
         tests
@@ -225,7 +241,7 @@ impl TestRunner {
 
     /// Parses a comment into a `TestAnnotation`.
     ///
-    /// Supported forms: `@test`, `[test]`, `@parametrize(expr)`.
+    /// Supported forms: `@test`, `[test]`, `@parametrize(expr)`, `@fixture("path")`.
     /// Unknown `@name(...)` annotations are silently ignored.
     fn parse_annotation(comment: &str) -> Option<TestAnnotation> {
         let s = comment.trim();
@@ -248,6 +264,10 @@ impl TestRunner {
 
         match name {
             "parametrize" => Some(TestAnnotation::Parametrize { params_expr: args }),
+            "fixture" => {
+                let path = args.strip_prefix('"')?.strip_suffix('"')?;
+                Some(TestAnnotation::Fixture { path: path.to_string() })
+            }
             _ => None,
         }
     }
@@ -267,30 +287,40 @@ impl TestRunner {
     ///
     /// Simple tests are `[test_case(...)]`; parametrized tests expand via
     /// `map(zip(range(...), params), fn(...))`. `flatten` merges both into one list.
-    fn build_test_query(content: &str, tests: &[DiscoveredTest]) -> String {
+    /// Every case records its `file:line` location so failures can point back to the `def`.
+    fn build_test_query(content: &str, tests: &[LocatedTest], file: &Path) -> String {
         let cases = tests
             .iter()
-            .map(|test| match test {
-                DiscoveredTest::Simple(name) => {
-                    let display = name.strip_prefix("test_").unwrap_or(name);
-                    format!("  [test_case(\"{display}\", {name})]")
-                }
-                DiscoveredTest::Parametrized {
-                    name,
-                    params_expr,
-                    arity,
-                } => {
-                    let display = name.strip_prefix("test_").unwrap_or(name);
-                    let arg_list = (0..*arity)
-                        .map(|i| format!("__ic[1][{i}]"))
-                        .collect::<Vec<_>>()
-                        .join(", ");
-                    format!(
-                        "  map(\
-                            zip(range(0, len({params_expr})), {params_expr}), \
-                            fn(__ic): test_case(\"{display}[\" + to_string(__ic[0]) + \"]\", \
-                            fn(): {name}({arg_list}) ;) ;)"
-                    )
+            .enumerate()
+            .map(|(index, LocatedTest { test, line })| {
+                let location = Self::string_literal(&format!("{}:{line}", file.display()).replace('\\', "/"));
+                match test {
+                    DiscoveredTest::Simple(name) => {
+                        let display = name.strip_prefix("test_").unwrap_or(name);
+                        format!("  [test_case(\"{display}\", {name}, {location})]")
+                    }
+                    DiscoveredTest::Parametrized {
+                        name,
+                        params_expr,
+                        arity,
+                    } => {
+                        let display = name.strip_prefix("test_").unwrap_or(name);
+                        let arg_list = (0..*arity)
+                            .map(|i| format!("__ic[1][{i}]"))
+                            .collect::<Vec<_>>()
+                            .join(", ");
+                        format!(
+                            "  map(\
+                                zip(range(0, len({params_expr})), {params_expr}), \
+                                fn(__ic): test_case(\"{display}[\" + to_string(__ic[0]) + \"]\", \
+                                fn(): {name}({arg_list}) ;, {location}) ;)"
+                        )
+                    }
+                    DiscoveredTest::Fixture { name, .. } => {
+                        let display = name.strip_prefix("test_").unwrap_or(name);
+                        let fixture = Self::fixture_var(index);
+                        format!("  [test_case(\"{display}\", fn(): {name}({fixture}) ;, {location})]")
+                    }
                 }
             })
             .collect::<Vec<_>>()
@@ -298,6 +328,65 @@ impl TestRunner {
 
         format!("{content}\n| run_tests(flatten([\n{cases}\n]))")
     }
+
+    /// Quotes `s` as an mq string literal.
+    fn string_literal(s: &str) -> String {
+        let mut literal = String::with_capacity(s.len() + 2);
+        literal.push('"');
+        for ch in s.chars() {
+            match ch {
+                '\\' => literal.push_str("\\\\"),
+                '"' => literal.push_str("\\\""),
+                '\n' => literal.push_str("\\n"),
+                '\t' => literal.push_str("\\t"),
+                '\r' => literal.push_str("\\r"),
+                _ => literal.push(ch),
+            }
+        }
+        literal.push('"');
+        literal
+    }
+
+    /// Name of the variable holding the fixture input of the test at `index`.
+    fn fixture_var(index: usize) -> String {
+        format!("__fixture_{index}")
+    }
+
+    /// Loads every fixture referenced by `tests` and binds its parsed nodes to the
+    /// variable used by the generated query. Fixture paths are relative to the test file.
+    fn define_fixtures(engine: &mut mq_lang::DefaultEngine, file: &Path, tests: &[LocatedTest]) -> miette::Result<()> {
+        for (index, located) in tests.iter().enumerate() {
+            let DiscoveredTest::Fixture { path, .. } = &located.test else {
+                continue;
+            };
+
+            let fixture_path = file.parent().unwrap_or(Path::new("")).join(path);
+            let content = fs::read_to_string(&fixture_path)
+                .map_err(|e| miette::miette!("Failed to read fixture {}: {}", fixture_path.display(), e))?;
+            let ext = fixture_path
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+            let nodes = match ext.as_str() {
+                "mdx" => mq_lang::parse_mdx_input(&content)?,
+                "html" | "htm" => mq_lang::parse_html_input(&content)?,
+                "md" | "markdown" => mq_lang::parse_markdown_input(&content)?,
+                // Other files are passed as a single string rather than an array of one.
+                _ => {
+                    engine.define_value(&Self::fixture_var(index), content.into());
+                    continue;
+                }
+            };
+
+            engine.define_value(
+                &Self::fixture_var(index),
+                mq_lang::RuntimeValue::Array(mq_lang::Shared::new(nodes)),
+            );
+        }
+
+        Ok(())
+    }
 }
 
 /// Builds the command that opens `path` in the OS default application for
@@ -330,6 +419,17 @@ mod tests {
     use super::*;
     use rstest::rstest;
 
+    fn discover(content: &str) -> Vec<DiscoveredTest> {
+        TestRunner::discover_tests(content)
+            .into_iter()
+            .map(|located| located.test)
+            .collect()
+    }
+
+    fn located(tests: Vec<DiscoveredTest>) -> Vec<LocatedTest> {
+        tests.into_iter().map(|test| LocatedTest { test, line: 1 }).collect()
+    }
+
     #[rstest]
     #[case("@test", Some(TestAnnotation::Test))]
     #[case("  @test  ", Some(TestAnnotation::Test))]
@@ -356,6 +456,11 @@ mod tests {
     #[case("not an annotation", None)]
     #[case("@", None)]
     #[case("@parametrize", None)]
+    #[case(
+        "@fixture(\"fixtures/input.md\")",
+        Some(TestAnnotation::Fixture { path: "fixtures/input.md".to_string() })
+    )]
+    #[case("@fixture(fixtures/input.md)", None)]
     fn test_parse_annotation(#[case] input: &str, #[case] expected: Option<TestAnnotation>) {
         assert_eq!(TestRunner::parse_annotation(input), expected);
     }
@@ -411,7 +516,7 @@ mod tests {
         ]
     )]
     fn test_discover_tests_simple(#[case] content: &str, #[case] expected: Vec<DiscoveredTest>) {
-        assert_eq!(TestRunner::discover_tests(content), expected);
+        assert_eq!(discover(content), expected);
     }
 
     #[rstest]
@@ -445,7 +550,7 @@ mod tests {
         #[case] expected_params_expr: &str,
         #[case] expected_arity: usize,
     ) {
-        let tests = TestRunner::discover_tests(content);
+        let tests = discover(content);
         assert_eq!(tests.len(), 1);
         match &tests[0] {
             DiscoveredTest::Parametrized {
@@ -469,7 +574,7 @@ mod tests {
             "# @parametrize([[\"hello\", 5]])\n",
             "def test_len(s, n):\n  None\nend\n",
         );
-        let tests = TestRunner::discover_tests(content);
+        let tests = discover(content);
         assert_eq!(tests.len(), 2);
         assert!(matches!(&tests[0], DiscoveredTest::Parametrized { name, .. } if name == "test_add"));
         assert!(matches!(&tests[1], DiscoveredTest::Parametrized { name, .. } if name == "test_len"));
@@ -482,7 +587,7 @@ mod tests {
             "# @test\ndef annotated():\n  None\nend\n\n",
             "# @parametrize([[1, 2]])\ndef test_param(a, b):\n  None\nend\n",
         );
-        let tests = TestRunner::discover_tests(content);
+        let tests = discover(content);
         assert_eq!(tests.len(), 3);
         assert!(matches!(&tests[0], DiscoveredTest::Simple(n) if n == "test_simple"));
         assert!(matches!(&tests[1], DiscoveredTest::Simple(n) if n == "annotated"));
@@ -497,7 +602,7 @@ mod tests {
             "  def test_add(a, b):\n  None\nend\n",
             "end\n",
         );
-        let tests = TestRunner::discover_tests(content);
+        let tests = discover(content);
         assert_eq!(tests.len(), 1);
         assert!(
             matches!(&tests[0], DiscoveredTest::Parametrized { name, arity, .. } if name == "test_add" && *arity == 2)
@@ -507,16 +612,16 @@ mod tests {
     #[test]
     fn test_discover_tests_ignores_unknown_annotation() {
         let content = "# @skip\ndef my_check():\n  None\nend\n";
-        let tests = TestRunner::discover_tests(content);
+        let tests = discover(content);
         assert!(tests.is_empty());
     }
 
     #[rstest]
-    #[case(vec![DiscoveredTest::Simple("test_foo".to_string())], "[test_case(\"foo\", test_foo)]")]
-    #[case(vec![DiscoveredTest::Simple("test_is_array".to_string())], "[test_case(\"is_array\", test_is_array)]")]
-    #[case(vec![DiscoveredTest::Simple("my_check".to_string())], "[test_case(\"my_check\", my_check)]")]
+    #[case(vec![DiscoveredTest::Simple("test_foo".to_string())], "[test_case(\"foo\", test_foo, \"tests.mq:1\")]")]
+    #[case(vec![DiscoveredTest::Simple("test_is_array".to_string())], "[test_case(\"is_array\", test_is_array, \"tests.mq:1\")]")]
+    #[case(vec![DiscoveredTest::Simple("my_check".to_string())], "[test_case(\"my_check\", my_check, \"tests.mq:1\")]")]
     fn test_build_test_query_simple_cases(#[case] tests: Vec<DiscoveredTest>, #[case] expected: &str) {
-        let query = TestRunner::build_test_query("content", &tests);
+        let query = TestRunner::build_test_query("content", &located(tests), Path::new("tests.mq"));
         assert!(query.starts_with("content\n"), "query must start with original content");
         assert!(query.contains("flatten(["), "must use flatten");
         assert!(query.contains(expected), "expected {expected:?} in:\n{query}");
@@ -553,7 +658,7 @@ mod tests {
         #[case] expected_call: &str,
         #[case] expected_label: &str,
     ) {
-        let query = TestRunner::build_test_query("content", &[test]);
+        let query = TestRunner::build_test_query("content", &located(vec![test]), Path::new("tests.mq"));
         assert!(query.contains("flatten(["), "must use flatten");
         assert!(query.contains("map("), "must use map");
         assert!(query.contains("zip(range("), "must use zip+range");
@@ -573,9 +678,9 @@ mod tests {
             DiscoveredTest::Simple("test_foo".to_string()),
             DiscoveredTest::Simple("test_bar".to_string()),
         ];
-        let query = TestRunner::build_test_query("content", &tests);
-        assert!(query.contains("[test_case(\"foo\", test_foo)]"));
-        assert!(query.contains("[test_case(\"bar\", test_bar)]"));
+        let query = TestRunner::build_test_query("content", &located(tests), Path::new("tests.mq"));
+        assert!(query.contains("[test_case(\"foo\", test_foo, \"tests.mq:1\")]"));
+        assert!(query.contains("[test_case(\"bar\", test_bar, \"tests.mq:1\")]"));
         assert!(query.contains("flatten(["));
     }
 
@@ -589,8 +694,8 @@ mod tests {
                 arity: 2,
             },
         ];
-        let query = TestRunner::build_test_query("content", &tests);
-        assert!(query.contains("[test_case(\"foo\", test_foo)]"));
+        let query = TestRunner::build_test_query("content", &located(tests), Path::new("tests.mq"));
+        assert!(query.contains("[test_case(\"foo\", test_foo, \"tests.mq:1\")]"));
         assert!(query.contains("map("));
         assert!(query.contains("test_len(__ic[1][0], __ic[1][1])"));
         assert!(query.contains("flatten(["));
@@ -600,10 +705,96 @@ mod tests {
     fn test_build_test_query_preserves_content() {
         let content = "include \"test\"\n|\ndef helper(): None end";
         let tests = vec![DiscoveredTest::Simple("test_foo".to_string())];
-        let query = TestRunner::build_test_query(content, &tests);
+        let query = TestRunner::build_test_query(content, &located(tests), Path::new("tests.mq"));
         assert!(query.starts_with(content));
     }
 
+    #[test]
+    fn test_discover_tests_records_def_line() {
+        let content = "include \"test\"\n|\n\ndef test_foo():\n  None\nend\n\n# @test\ndef check():\n  None\nend\n";
+        let lines: Vec<u32> = TestRunner::discover_tests(content).iter().map(|t| t.line).collect();
+        assert_eq!(lines, vec![4, 9]);
+    }
+
+    #[test]
+    fn test_discover_tests_fixture() {
+        let content = "# @fixture(\"input.md\")\ndef check_headings(input):\n  None\nend\n";
+        assert_eq!(
+            discover(content),
+            vec![DiscoveredTest::Fixture {
+                name: "check_headings".to_string(),
+                path: "input.md".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_build_test_query_fixture() {
+        let tests = vec![
+            DiscoveredTest::Simple("test_foo".to_string()),
+            DiscoveredTest::Fixture {
+                name: "test_headings".to_string(),
+                path: "input.md".to_string(),
+            },
+        ];
+        let query = TestRunner::build_test_query("content", &located(tests), Path::new("tests.mq"));
+        assert!(query.contains("[test_case(\"headings\", fn(): test_headings(__fixture_1) ;, \"tests.mq:1\")]"));
+    }
+
+    #[test]
+    fn test_run_with_fixture_input() {
+        let dir = temp_project_dir("fixture_input");
+        fs::write(dir.join("input.md"), "# Title\n\n## Section\n\nbody\n").unwrap();
+        let test_file = dir.join("tests.mq");
+        fs::write(
+            &test_file,
+            "include \"test\"\n|\n\n# @fixture(\"input.md\")\ndef test_headings(input):\n  assert_eq(len(compact(map(input, fn(n): n | .h;))), 2)\nend\n",
+        )
+        .unwrap();
+
+        assert!(TestRunner::new(vec![test_file]).run().is_ok());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_run_with_text_fixture_input() {
+        let dir = temp_project_dir("text_fixture_input");
+        fs::write(dir.join("input.txt"), "hello").unwrap();
+        let test_file = dir.join("tests.mq");
+        fs::write(
+            &test_file,
+            "include \"test\"\n|\n\n# @fixture(\"input.txt\")\ndef test_text(input):\n  assert_eq(input, \"hello\")\nend\n",
+        )
+        .unwrap();
+
+        assert!(TestRunner::new(vec![test_file]).run().is_ok());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_build_test_query_escapes_location() {
+        let tests = vec![DiscoveredTest::Simple("test_foo".to_string())];
+        let query = TestRunner::build_test_query("content", &located(tests), Path::new("a\"b.mq"));
+        assert!(query.contains("\"a\\\"b.mq:1\""), "{}", query);
+    }
+
+    #[test]
+    fn test_run_with_missing_fixture_errors() {
+        let dir = temp_project_dir("missing_fixture");
+        let test_file = dir.join("tests.mq");
+        fs::write(
+            &test_file,
+            "include \"test\"\n|\n\n# @fixture(\"missing.md\")\ndef test_headings(input):\n  None\nend\n",
+        )
+        .unwrap();
+
+        assert!(TestRunner::new(vec![test_file]).run().is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
     #[rstest]
     #[case("macos", "open", Vec::<&str>::new())]
     #[case("windows", "cmd", vec!["/C", "start", ""])]
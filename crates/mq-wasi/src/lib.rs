@@ -0,0 +1,227 @@
+//! WASI build of the mq engine for WebAssembly runtimes.
+//!
+//! Built for `wasm32-wasip1`, this crate produces a reactor module (`mq_wasi.wasm`) that
+//! plugin systems and serverless runtimes based on wasmtime, wazero, or any other WASI host
+//! can load without a JavaScript glue layer. The host interface works on raw bytes in the
+//! module's linear memory:
+//!
+//! 1. Allocate buffers with `mq_alloc` and copy the UTF-8 query and input into them.
+//! 2. Call `mq_eval` with the buffers and an input format; it returns `0` on success.
+//! 3. Read the output (or the error message) from `mq_output_ptr` / `mq_output_len`.
+//! 4. Release the buffers with `mq_dealloc`.
+//!
+//! The engine is kept between calls, so modules loaded by a query stay loaded. The output
+//! buffer is overwritten by the next `mq_eval`.
+//!
+//! WASI preview 1 has no threads, so the engine is built without the `sync` feature.
+//! File access from queries (`read_file` and friends) is only compiled in with the
+//! `file-io` feature, and is further limited to the directories the host preopens.
+
+use mq_lang::{DefaultEngine, RuntimeValue};
+use std::cell::RefCell;
+
+/// `mq_eval` finished and the output buffer holds the results.
+pub const MQ_OK: u32 = 0;
+/// `mq_eval` failed and the output buffer holds the error message.
+pub const MQ_ERROR: u32 = 1;
+
+/// Format of the input passed to `mq_eval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum InputFormat {
+    Markdown = 0,
+    Mdx = 1,
+    Html = 2,
+    /// Plain text, one value per line.
+    Text = 3,
+    /// The whole input as a single string value.
+    Raw = 4,
+    /// No input; the query runs once against an empty value.
+    Null = 5,
+}
+
+impl TryFrom<u32> for InputFormat {
+    type Error = String;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(InputFormat::Markdown),
+            1 => Ok(InputFormat::Mdx),
+            2 => Ok(InputFormat::Html),
+            3 => Ok(InputFormat::Text),
+            4 => Ok(InputFormat::Raw),
+            5 => Ok(InputFormat::Null),
+            _ => Err(format!("Unsupported input format: {}", value)),
+        }
+    }
+}
+
+thread_local! {
+    static ENGINE: RefCell<DefaultEngine> = RefCell::new({
+        let mut engine = DefaultEngine::default();
+        engine.load_builtin_module();
+        engine
+    });
+    static OUTPUT: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Allocates `len` bytes in linear memory for the host to write into.
+/// The buffer must be released with `mq_dealloc` using the same `len`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mq_alloc(len: usize) -> *mut u8 {
+    let mut buf = Vec::<u8>::with_capacity(len);
+    let ptr = buf.as_mut_ptr();
+    std::mem::forget(buf);
+    ptr
+}
+
+/// Releases a buffer allocated by `mq_alloc`.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by `mq_alloc(len)` and must not be used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_dealloc(ptr: *mut u8, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe {
+        drop(Vec::from_raw_parts(ptr, 0, len));
+    }
+}
+
+/// Evaluates the query against the input and stores the output, one value per line.
+/// Returns `MQ_OK`, or `MQ_ERROR` with the error message stored as the output.
+///
+/// # Safety
+///
+/// `code_ptr` and `input_ptr` must point to `code_len` and `input_len` readable bytes.
+/// A null pointer is treated as an empty string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn mq_eval(
+    code_ptr: *const u8,
+    code_len: usize,
+    input_ptr: *const u8,
+    input_len: usize,
+    input_format: u32,
+) -> u32 {
+    let result = unsafe { bytes_to_str(code_ptr, code_len, "code") }.and_then(|code| {
+        let input = unsafe { bytes_to_str(input_ptr, input_len, "input") }?;
+        let input_format = InputFormat::try_from(input_format)?;
+        eval(code, input, input_format)
+    });
+
+    let (status, output) = match result {
+        Ok(output) => (MQ_OK, output),
+        Err(message) => (MQ_ERROR, message),
+    };
+    OUTPUT.with(|buf| *buf.borrow_mut() = output.into_bytes());
+    status
+}
+
+/// Returns a pointer to the output of the last `mq_eval`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mq_output_ptr() -> *const u8 {
+    OUTPUT.with(|buf| buf.borrow().as_ptr())
+}
+
+/// Returns the length in bytes of the output of the last `mq_eval`.
+#[unsafe(no_mangle)]
+pub extern "C" fn mq_output_len() -> usize {
+    OUTPUT.with(|buf| buf.borrow().len())
+}
+
+unsafe fn bytes_to_str<'a>(ptr: *const u8, len: usize, name: &str) -> Result<&'a str, String> {
+    if ptr.is_null() || len == 0 {
+        return Ok("");
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(ptr, len) };
+    std::str::from_utf8(bytes).map_err(|_| format!("Invalid UTF-8 sequence in {}", name))
+}
+
+/// Evaluates `code` with the shared engine and renders the non-empty results as text.
+pub fn eval(code: &str, input: &str, input_format: InputFormat) -> Result<String, String> {
+    let input = parse_input(input, input_format)?;
+
+    ENGINE.with(|engine| {
+        engine
+            .borrow_mut()
+            .eval(code, input.into_iter())
+            .map(|values| {
+                values
+                    .into_iter()
+                    .filter(|value| !value.is_none())
+                    .map(|value| value.to_string())
+                    .filter(|value| !value.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .map_err(|e| format!("Error evaluating query: {}", e))
+    })
+}
+
+fn parse_input(input: &str, input_format: InputFormat) -> Result<Vec<RuntimeValue>, String> {
+    match input_format {
+        InputFormat::Markdown => {
+            mq_lang::parse_markdown_input(input).map_err(|e| format!("Markdown parsing error: {}", e))
+        }
+        InputFormat::Mdx => mq_lang::parse_mdx_input(input).map_err(|e| format!("Markdown parsing error: {}", e)),
+        InputFormat::Html => mq_lang::parse_html_input(input).map_err(|e| format!("Html parsing error: {}", e)),
+        InputFormat::Text => mq_lang::parse_text_input(input).map_err(|e| format!("Text parsing error: {}", e)),
+        InputFormat::Raw => Ok(mq_lang::raw_input(input)),
+        InputFormat::Null => Ok(mq_lang::null_input()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn output() -> String {
+        let bytes = unsafe { std::slice::from_raw_parts(mq_output_ptr(), mq_output_len()) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn call_eval(code: &str, input: &str, input_format: u32) -> u32 {
+        unsafe { mq_eval(code.as_ptr(), code.len(), input.as_ptr(), input.len(), input_format) }
+    }
+
+    #[rstest]
+    #[case::markdown(".h", "# Title\n\ntext\n\n## Sub", InputFormat::Markdown, "# Title\n## Sub")]
+    #[case::html(".h1 | to_text()", "<h1>Title</h1>", InputFormat::Html, "Title")]
+    #[case::text("upcase()", "a\nb", InputFormat::Text, "A\nB")]
+    #[case::raw("len()", "a\nb", InputFormat::Raw, "3")]
+    #[case::null("1 + 2", "", InputFormat::Null, "3")]
+    fn test_eval(#[case] code: &str, #[case] input: &str, #[case] input_format: InputFormat, #[case] expected: &str) {
+        assert_eq!(call_eval(code, input, input_format as u32), MQ_OK);
+        assert_eq!(output(), expected);
+    }
+
+    #[test]
+    fn test_eval_error() {
+        assert_eq!(call_eval("1 +", "", InputFormat::Null as u32), MQ_ERROR);
+        assert!(output().starts_with("Error evaluating query"));
+
+        assert_eq!(call_eval("1", "", 99), MQ_ERROR);
+        assert_eq!(output(), "Unsupported input format: 99");
+    }
+
+    #[test]
+    fn test_eval_keeps_definitions() {
+        assert_eq!(call_eval("def twice(x): x * 2;", "", InputFormat::Null as u32), MQ_OK);
+        assert_eq!(call_eval("twice(21)", "", InputFormat::Null as u32), MQ_OK);
+        assert_eq!(output(), "42");
+    }
+
+    #[test]
+    fn test_alloc_and_dealloc() {
+        let ptr = mq_alloc(16);
+        assert!(!ptr.is_null());
+        unsafe {
+            std::ptr::copy_nonoverlapping(b"hello".as_ptr(), ptr, 5);
+            assert_eq!(bytes_to_str(ptr, 5, "input"), Ok("hello"));
+            mq_dealloc(ptr, 16);
+        }
+    }
+}
@@ -31,10 +31,77 @@ export interface InlayHint {
   label: string,
 }
 
+/** A markdown node, e.g. `{ type: "Heading", depth: 1, values: [...], position: {...} }`. */
+export interface MarkdownNode {
+  type: string;
+  [key: string]: unknown;
+}
+
+export type Value = null | boolean | number | string | MarkdownNode | Value[] | { [key: string]: Value };
+
 export interface HoverResult {
   content: string;
 }
 
+export type TokenClass =
+  | 'comment' | 'keyword' | 'operator' | 'punctuation' | 'function' | 'module'
+  | 'property' | 'string' | 'number' | 'boolean' | 'builtin' | 'variable';
+
+export interface SyntaxToken {
+  startLine: number,
+  startColumn: number,
+  endLine: number,
+  endColumn: number,
+  class: TokenClass,
+  /** Name of the lexer token kind, e.g. `Ident` or `StringLiteral`. */
+  tokenKind: string,
+}
+
+export interface SyntaxNode {
+  /** Name of the CST node kind, e.g. `Call` or `Def`. */
+  kind: string,
+  /** Source text of the node's own token, if it has one. */
+  text: string | null,
+  startLine: number,
+  startColumn: number,
+  endLine: number,
+  endColumn: number,
+  children: SyntaxNode[],
+}
+
+export interface SyntaxTree {
+  nodes: SyntaxNode[],
+  errors: Diagnostic[],
+}
+
+export interface CompletionItem {
+  label: string,
+  kind: 'function' | 'variable' | 'selector',
+  /** Text to insert, with `${n:param}` placeholders for function arguments. */
+  insertText: string,
+  doc: string,
+  deprecated: boolean,
+}
+
+export interface FormatOptions {
+  /** Spaces per indentation level. Defaults to 2. */
+  indentWidth?: number,
+  sortImports?: boolean,
+  sortFunctions?: boolean,
+  sortFields?: boolean,
+  /** Line width at which long pipelines are wrapped. Disabled if omitted. */
+  maxWidth?: number,
+}
+
+export interface BuiltinDoc {
+  name: string,
+  kind: 'function' | 'selector',
+  /** The standard module to import for the function, or `null` for builtins. */
+  module: string | null,
+  params: string[],
+  description: string,
+}
+
 export interface Options {
     isUpdate: boolean,
     inputFormat: 'markdown' | 'text' | 'mdx' | 'html' | 'null' | 'raw' | null,
@@ -51,7 +118,39 @@ export function definedValues(code: string, module?: string): Promise<ReadonlyAr
 export function diagnostics(code: string, enableTypeCheck?: boolean): Promise<ReadonlyArray<Diagnostic>>;
 export function hover(code: string, line: number, column: number): Promise<HoverResult | null>;
 export function inlayHints(code: string): Promise<ReadonlyArray<InlayHint>>;
+/** Classified tokens of `code` for syntax highlighting, in source order. Lexing stops at the first invalid character. */
+export function tokens(code: string): Promise<ReadonlyArray<SyntaxToken>>;
+/** The concrete syntax tree of `code` and its syntax errors, parsed with error recovery. */
+export function cst(code: string): Promise<SyntaxTree>;
+/** Completion candidates at `line`/`column` (1-based): symbols in scope and builtins, or a module's members after `module::`. */
+export function completions(code: string, line: number, column: number): Promise<ReadonlyArray<CompletionItem>>;
 export function run(code: string, content: string, options: Options): Promise<string>;
+export function format(code: string, options?: FormatOptions): Promise<string>;
+/** Reference documentation of the builtin functions, selectors, and standard modules. */
+export function builtinDocs(format?: 'json'): Promise<ReadonlyArray<BuiltinDoc>>;
+export function builtinDocs(format: 'markdown' | 'html'): Promise<string>;
+/**
+ * Runs a query chunk by chunk and calls `onResult` with the rendered markdown of each chunk of `chunkSize`
+ * (default 100) top-level input nodes, yielding to the event loop in between. If `onResult` returns a
+ * promise, the next chunk waits for it.
+ */
+export function runStream(
+  code: string,
+  content: string,
+  options: Options,
+  onResult: (output: string) => void | Promise<void>,
+  chunkSize?: number,
+): Promise<void>;
+/** Runs a query and returns the results as values instead of rendered markdown. */
+export function runValues(code: string, content: string, options: Options): Promise<ReadonlyArray<Value>>;
+/**
+ * Registers a callback that resolves `include "name"` / `import "name"` to module source text, e.g. fetched
+ * from a CDN or read from a virtual filesystem. Returning `null` or `undefined` falls back to OPFS.
+ * Pass `null` to remove the callback.
+ */
+export function setModuleResolver(
+  resolver: ((name: string) => string | null | undefined | Promise<string | null | undefined>) | null,
+): void;
 /** Clears mutable HTTP module cache (HEAD/branch imports) and their mq.lock entries. Versioned (tagged) cache and lock entries are preserved. */
 export function clearHttpCache(): Promise<void>;
 /** Clears all HTTP module cache including versioned (tagged) imports, and deletes mq.lock. */
@@ -99,6 +198,108 @@ pub struct HoverResult {
     content: String,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxToken {
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+    class: mq_lang::TokenClass,
+    /// Name of the lexer token kind, e.g. `Ident` or `StringLiteral`.
+    token_kind: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxNode {
+    /// Name of the CST node kind, e.g. `Call` or `Def`.
+    kind: String,
+    /// Source text of the node's own token, if it has one.
+    text: Option<String>,
+    start_line: u32,
+    start_column: u32,
+    end_line: u32,
+    end_column: u32,
+    children: Vec<SyntaxNode>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTree {
+    nodes: Vec<SyntaxNode>,
+    errors: Vec<Diagnostic>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum CompletionKind {
+    Function,
+    Variable,
+    Selector,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    label: String,
+    kind: CompletionKind,
+    /// Text to insert, with `${n:param}` placeholders for function arguments.
+    insert_text: String,
+    doc: String,
+    deprecated: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatOptions {
+    indent_width: Option<usize>,
+    sort_imports: Option<bool>,
+    sort_functions: Option<bool>,
+    sort_fields: Option<bool>,
+    max_width: Option<usize>,
+}
+
+impl From<FormatOptions> for mq_formatter::FormatterConfig {
+    fn from(options: FormatOptions) -> Self {
+        let default = Self::default();
+        Self {
+            indent_width: options.indent_width.unwrap_or(default.indent_width),
+            sort_imports: options.sort_imports.unwrap_or(default.sort_imports),
+            sort_functions: options.sort_functions.unwrap_or(default.sort_functions),
+            sort_fields: options.sort_fields.unwrap_or(default.sort_fields),
+            max_width: options.max_width.or(default.max_width),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BuiltinDocKind {
+    Function,
+    Selector,
+}
+
+/// Reference documentation of a builtin function, selector, or standard module function.
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BuiltinDoc {
+    name: String,
+    kind: BuiltinDocKind,
+    /// The standard module to import for the function, or `None` for builtins.
+    module: Option<String>,
+    params: Vec<String>,
+    description: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DocsFormat {
+    Json,
+    Markdown,
+    Html,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[wasm_bindgen(js_name=RunOptions, skip_typescript)]
@@ -311,6 +512,8 @@ pub struct WasmModuleResolver {
     http_resolver: Rc<RefCell<mq_lang::HttpModuleResolver<WasmFetcher>>>,
     /// Direct handle to the WasmFetcher; shares the same Rc data as the clone held by `http_resolver`.
     fetcher: WasmFetcher,
+    /// Module contents returned by the callback registered with `setModuleResolver`, keyed by module name.
+    js_modules: Rc<RefCell<HashMap<String, String>>>,
     #[cfg(feature = "opfs")]
     /// Cache of preloaded local `.mq` module contents (from OPFS), keyed by module name.
     cache: Rc<RefCell<HashMap<String, String>>>,
@@ -337,6 +540,7 @@ impl Default for WasmModuleResolver {
         Self {
             http_resolver: Rc::new(RefCell::new(http_resolver)),
             fetcher,
+            js_modules: Rc::new(RefCell::new(HashMap::new())),
             #[cfg(feature = "opfs")]
             cache: Rc::new(RefCell::new(HashMap::new())),
             #[cfg(feature = "opfs")]
@@ -453,6 +657,65 @@ impl WasmModuleResolver {
         self.cache.borrow_mut().clear();
     }
 
+    /// Resolves the local modules imported by `code`, and transitively by those modules, with the
+    /// callback registered by `setModuleResolver`.
+    ///
+    /// The callback may return the source text, a promise of it, or `null`/`undefined` to leave the
+    /// module to OPFS. Does nothing if no callback is registered.
+    pub async fn preload_js_modules(&self, code: &str) -> Result<(), JsValue> {
+        let Some(resolver) = JS_MODULE_RESOLVER.with(|resolver| resolver.borrow().clone()) else {
+            return Ok(());
+        };
+
+        let mut visited: std::collections::HashSet<String> = std::collections::HashSet::new();
+        let mut queue: std::collections::VecDeque<String> = extract_local_import_names(code).into_iter().collect();
+
+        while let Some(name) = queue.pop_front() {
+            if !visited.insert(name.clone()) || mq_lang::STANDARD_MODULES.contains_key(name.as_str()) {
+                continue;
+            }
+
+            let cached = self.js_modules.borrow().get(&name).cloned();
+            let content = match cached {
+                Some(content) => Some(content),
+                None => {
+                    let mut returned = resolver
+                        .call1(&JsValue::NULL, &JsValue::from_str(&name))
+                        .map_err(|e| module_resolver_error(&name, e))?;
+                    if let Some(promise) = returned.dyn_ref::<js_sys::Promise>() {
+                        returned = wasm_bindgen_futures::JsFuture::from(promise.clone())
+                            .await
+                            .map_err(|e| module_resolver_error(&name, e))?;
+                    }
+
+                    if returned.is_null() || returned.is_undefined() {
+                        None
+                    } else {
+                        let content = returned.as_string().ok_or_else(|| {
+                            JsValue::from_str(&format!(
+                                "Module resolver returned a non-string value for module '{}'",
+                                name
+                            ))
+                        })?;
+                        self.js_modules.borrow_mut().insert(name.clone(), content.clone());
+                        Some(content)
+                    }
+                }
+            };
+
+            #[cfg(feature = "opfs")]
+            let content = content.or_else(|| self.cache.borrow().get(&name).cloned());
+
+            for dep in content.as_deref().map(extract_local_import_names).unwrap_or_default() {
+                if !visited.contains(&dep) {
+                    queue.push_back(dep);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Pre-fetches HTTP/GitHub import URLs found directly in `code` (top-level only).
     ///
     /// Only imports written in the user's own code are resolved; HTTP imports inside
@@ -515,6 +778,10 @@ impl mq_lang::ModuleResolver for WasmModuleResolver {
             return Ok(content_fn().to_string());
         }
 
+        if let Some(content) = self.js_modules.borrow().get(module_name) {
+            return Ok(content.clone());
+        }
+
         #[cfg(feature = "opfs")]
         if let Some(content) = self.cache.borrow().get(module_name) {
             return Ok(content.clone());
@@ -562,6 +829,27 @@ impl mq_lang::ModuleResolver for WasmModuleResolver {
     }
 }
 
+thread_local! {
+    /// Callback registered by `setModuleResolver`.
+    static JS_MODULE_RESOLVER: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+/// Registers a callback that resolves `include "name"` and `import "name"` to module source text,
+/// e.g. from a CDN or a virtual filesystem. Passing `null` removes it.
+#[wasm_bindgen(js_name=setModuleResolver, skip_typescript)]
+pub fn set_module_resolver(resolver: Option<js_sys::Function>) {
+    JS_MODULE_RESOLVER.with(|current| *current.borrow_mut() = resolver);
+}
+
+fn module_resolver_error(name: &str, error: JsValue) -> JsValue {
+    let message = error
+        .dyn_ref::<js_sys::Error>()
+        .map(|e| String::from(e.message()))
+        .or_else(|| error.as_string())
+        .unwrap_or_else(|| format!("{:?}", error));
+    JsValue::from_str(&format!("Failed to resolve module '{}': {}", name, message))
+}
+
 /// Removes mutable HTTP module cache and their `mq.lock` entries (matches `--refresh-modules`).
 #[wasm_bindgen(js_name=clearHttpCache)]
 pub async fn clear_http_cache() -> Result<(), JsValue> {
@@ -615,20 +903,17 @@ pub async fn clear_all_http_cache() -> Result<(), JsValue> {
     Ok(())
 }
 
-#[wasm_bindgen(js_name=run, skip_typescript)]
-pub async fn run(code: &str, content: &str, options: JsValue) -> Result<String, JsValue> {
-    let options: Options = serde_wasm_bindgen::from_value(options)
-        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?;
-
+/// Creates an engine with the builtins loaded and the modules imported by `code` preloaded.
+async fn create_engine(code: &str, options: &Options) -> Result<mq_lang::Engine<WasmModuleResolver>, JsValue> {
     let resolver = WasmModuleResolver::new();
     resolver.initialize().await;
     if let Some(ref domains) = options.allowed_domains {
         resolver.set_allowed_domains(domains.clone());
     }
     resolver.preload_modules(code).await;
+    resolver.preload_js_modules(code).await?;
     resolver.preload_http_modules(code).await;
 
-    let is_update = options.is_update;
     let mut engine = mq_lang::Engine::new(resolver);
 
     engine.load_builtin_module();
@@ -636,7 +921,11 @@ pub async fn run(code: &str, content: &str, options: JsValue) -> Result<String,
         engine.set_timeout(std::time::Duration::from_millis(timeout_ms as u64));
     }
 
-    let input = match options.input_format.as_ref().unwrap_or(&InputFormat::Markdown) {
+    Ok(engine)
+}
+
+fn parse_input(content: &str, options: &Options) -> Result<Vec<mq_lang::RuntimeValue>, JsValue> {
+    match options.input_format.as_ref().unwrap_or(&InputFormat::Markdown) {
         InputFormat::Text => mq_lang::parse_text_input(content),
         InputFormat::Html => mq_lang::parse_html_input(content),
         InputFormat::Mdx => mq_lang::parse_mdx_input(content),
@@ -644,55 +933,172 @@ pub async fn run(code: &str, content: &str, options: JsValue) -> Result<String,
         InputFormat::Raw => Ok(mq_lang::raw_input(content)),
         InputFormat::Null => Ok(mq_lang::null_input()),
     }
-    .map_err(|e| JsValue::from_str(&format!("Failed to parse input content: {}", e)))?;
+    .map_err(|e| JsValue::from_str(&format!("Failed to parse input content: {}", e)))
+}
+
+/// Applies `isUpdate`: with markdown input, the results are merged back into the input document.
+fn apply_update(
+    input: Vec<mq_lang::RuntimeValue>,
+    result_values: mq_lang::RuntimeValues,
+    options: &Options,
+) -> mq_lang::RuntimeValues {
+    if matches!(options.input_format, Some(InputFormat::Markdown)) && options.is_update {
+        let values: mq_lang::RuntimeValues = input.into();
+        values.update_with(result_values)
+    } else {
+        result_values
+    }
+}
+
+/// Evaluates `code` against `content` with the module resolution and input parsing configured
+/// by `options`, returning the raw result values.
+async fn eval(code: &str, content: &str, options: &Options) -> Result<mq_lang::RuntimeValues, JsValue> {
+    let mut engine = create_engine(code, options).await?;
+    let input = parse_input(content, options)?;
 
     engine
         .eval(code, input.clone().into_iter())
         .map_err(|e| JsValue::from_str(&format!("{}", &e)))
-        .map(|result_values| {
-            let values = if matches!(options.input_format, Some(InputFormat::Markdown)) && is_update {
-                let values: mq_lang::RuntimeValues = input.into();
-                values.update_with(result_values)
-            } else {
-                result_values
-            };
+        .map(|result_values| apply_update(input, result_values, options))
+}
 
-            let mut markdown = mq_markdown::Markdown::new(
-                values
-                    .into_iter()
-                    .map(|runtime_value| match runtime_value {
-                        mq_lang::RuntimeValue::Markdown(node, _) => *node,
-                        _ => runtime_value.to_string().into(),
-                    })
-                    .collect(),
-            );
-            markdown.set_options(mq_markdown::RenderOptions {
-                list_style: options
-                    .list_style
-                    .map(|style| match style {
-                        ListStyle::Dash => mq_markdown::ListStyle::Dash,
-                        ListStyle::Plus => mq_markdown::ListStyle::Plus,
-                        ListStyle::Star => mq_markdown::ListStyle::Star,
-                    })
-                    .unwrap_or_default(),
-                link_title_style: options
-                    .link_title_style
-                    .map(|style| match style {
-                        TitleSurroundStyle::Double => mq_markdown::TitleSurroundStyle::Double,
-                        TitleSurroundStyle::Single => mq_markdown::TitleSurroundStyle::Single,
-                        TitleSurroundStyle::Paren => mq_markdown::TitleSurroundStyle::Paren,
-                    })
-                    .unwrap_or_default(),
-                link_url_style: options
-                    .link_url_style
-                    .map(|style| match style {
-                        UrlSurroundStyle::Angle => mq_markdown::UrlSurroundStyle::Angle,
-                        UrlSurroundStyle::None => mq_markdown::UrlSurroundStyle::None,
-                    })
-                    .unwrap_or_default(),
-            });
-            markdown.to_string()
-        })
+fn render_markdown(values: mq_lang::RuntimeValues, options: &Options) -> String {
+    let mut markdown = mq_markdown::Markdown::new(
+        values
+            .into_iter()
+            .map(|runtime_value| match runtime_value {
+                mq_lang::RuntimeValue::Markdown(node, _) => *node,
+                _ => runtime_value.to_string().into(),
+            })
+            .collect(),
+    );
+    markdown.set_options(mq_markdown::RenderOptions {
+        list_style: options
+            .list_style
+            .as_ref()
+            .map(|style| match style {
+                ListStyle::Dash => mq_markdown::ListStyle::Dash,
+                ListStyle::Plus => mq_markdown::ListStyle::Plus,
+                ListStyle::Star => mq_markdown::ListStyle::Star,
+            })
+            .unwrap_or_default(),
+        link_title_style: options
+            .link_title_style
+            .as_ref()
+            .map(|style| match style {
+                TitleSurroundStyle::Double => mq_markdown::TitleSurroundStyle::Double,
+                TitleSurroundStyle::Single => mq_markdown::TitleSurroundStyle::Single,
+                TitleSurroundStyle::Paren => mq_markdown::TitleSurroundStyle::Paren,
+            })
+            .unwrap_or_default(),
+        link_url_style: options
+            .link_url_style
+            .as_ref()
+            .map(|style| match style {
+                UrlSurroundStyle::Angle => mq_markdown::UrlSurroundStyle::Angle,
+                UrlSurroundStyle::None => mq_markdown::UrlSurroundStyle::None,
+            })
+            .unwrap_or_default(),
+    });
+    markdown.to_string()
+}
+
+#[wasm_bindgen(js_name=run, skip_typescript)]
+pub async fn run(code: &str, content: &str, options: JsValue) -> Result<String, JsValue> {
+    let options: Options = serde_wasm_bindgen::from_value(options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?;
+
+    eval(code, content, &options)
+        .await
+        .map(|values| render_markdown(values, &options))
+}
+
+/// Number of input nodes evaluated between two `runStream` callbacks when no chunk size is given.
+const DEFAULT_STREAM_CHUNK_SIZE: u32 = 100;
+
+/// Runs `code` like [`run`], but evaluates the input `chunk_size` top-level nodes at a time and
+/// passes the rendered markdown of each chunk to `on_result` as soon as it is ready.
+///
+/// Control returns to the event loop between chunks, so large documents do not block the page.
+/// If `on_result` returns a promise, the next chunk waits for it to settle. Queries that use
+/// `nodes` need the whole input at once, so their first chunk is ready only after the whole input
+/// is evaluated. The timeout in `options` applies to each input node.
+#[wasm_bindgen(js_name=runStream, skip_typescript)]
+pub async fn run_stream(
+    code: &str,
+    content: &str,
+    options: JsValue,
+    on_result: js_sys::Function,
+    chunk_size: Option<u32>,
+) -> Result<(), JsValue> {
+    let options: Options = serde_wasm_bindgen::from_value(options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?;
+
+    let mut engine = create_engine(code, &options).await?;
+    let input = parse_input(content, &options)?;
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_STREAM_CHUNK_SIZE).max(1) as usize;
+
+    let mut results = engine
+        .eval_stream(code, input.clone().into_iter())
+        .map_err(|e| JsValue::from_str(&format!("{}", &e)))?;
+    let mut inputs = input.into_iter();
+
+    for index in 0.. {
+        let result_values = results
+            .by_ref()
+            .take(chunk_size)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| JsValue::from_str(&format!("{}", &e)))?;
+        if result_values.is_empty() {
+            break;
+        }
+        if index > 0 {
+            yield_to_event_loop().await?;
+        }
+
+        let chunk = inputs.by_ref().take(result_values.len()).collect();
+        let output = render_markdown(apply_update(chunk, result_values.into(), &options), &options);
+
+        let returned = on_result.call1(&JsValue::NULL, &JsValue::from_str(&output))?;
+        if let Ok(promise) = returned.dyn_into::<js_sys::Promise>() {
+            wasm_bindgen_futures::JsFuture::from(promise).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves on the next macrotask (`setTimeout(0)`), letting the browser render and handle input.
+async fn yield_to_event_loop() -> Result<(), JsValue> {
+    let promise = js_sys::Promise::new(&mut |resolve, _reject| {
+        let set_timeout = js_sys::Reflect::get(&js_sys::global(), &JsValue::from_str("setTimeout"))
+            .and_then(|set_timeout| set_timeout.dyn_into::<js_sys::Function>());
+        let _ = match set_timeout {
+            Ok(set_timeout) => set_timeout.call2(&JsValue::NULL, &resolve, &JsValue::from(0)),
+            Err(_) => resolve.call0(&JsValue::NULL),
+        };
+    });
+    wasm_bindgen_futures::JsFuture::from(promise).await.map(|_| ())
+}
+
+/// Runs `code` like [`run`], but returns the results as JavaScript values instead of rendered markdown.
+///
+/// Markdown nodes become objects with a `type` field, dicts become plain objects, and
+/// functions and modules become `null`.
+#[wasm_bindgen(js_name=runValues, skip_typescript)]
+pub async fn run_values(code: &str, content: &str, options: JsValue) -> Result<JsValue, JsValue> {
+    let options: Options = serde_wasm_bindgen::from_value(options)
+        .map_err(|e| JsValue::from_str(&format!("Failed to parse options: {}", e)))?;
+
+    let values = eval(code, content, &options)
+        .await?
+        .into_iter()
+        .map(mq_lang::RuntimeValue::to_json_value)
+        .collect::<Vec<_>>();
+
+    values
+        .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+        .map_err(|e| JsValue::from_str(&format!("Failed to convert results: {}", e)))
 }
 
 #[wasm_bindgen(js_name=toAst)]
@@ -705,13 +1111,138 @@ pub async fn to_ast(code: &str) -> Result<String, JsValue> {
         })
 }
 
-#[wasm_bindgen(js_name=format)]
-pub async fn format(code: &str) -> Result<String, JsValue> {
-    mq_formatter::Formatter::default()
+#[wasm_bindgen(js_name=format, skip_typescript)]
+pub async fn format(code: &str, options: JsValue) -> Result<String, JsValue> {
+    let options: FormatOptions = if options.is_undefined() || options.is_null() {
+        FormatOptions::default()
+    } else {
+        serde_wasm_bindgen::from_value(options)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse format options: {}", e)))?
+    };
+
+    mq_formatter::Formatter::new(Some(options.into()))
         .format(code)
         .map_err(|e| JsValue::from_str(&format!("{:?}", &e)))
 }
 
+/// Collects the reference documentation of every builtin function, selector, and standard
+/// module function, sorted by section and name.
+fn builtin_docs() -> Vec<BuiltinDoc> {
+    let mut docs = mq_lang::BUILTIN_FUNCTION_DOC
+        .iter()
+        .filter(|(name, _)| !name.starts_with('_'))
+        .map(|(name, doc)| BuiltinDoc {
+            name: name.to_string(),
+            kind: BuiltinDocKind::Function,
+            module: None,
+            params: doc.params.iter().map(|param| param.to_string()).collect(),
+            description: doc.description.to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut hir = mq_hir::Hir::default();
+    hir.add_builtin();
+    let builtin_source_id = hir.builtin.source_id;
+    docs.extend(
+        mq_function_docs(&hir, builtin_source_id, None)
+            .filter(|doc| !mq_lang::BUILTIN_FUNCTION_DOC.contains_key(doc.name.as_str())),
+    );
+
+    docs.extend(mq_lang::BUILTIN_SELECTOR_DOC.iter().map(|(name, doc)| BuiltinDoc {
+        name: name.to_string(),
+        kind: BuiltinDocKind::Selector,
+        module: None,
+        params: doc.params.iter().map(|param| param.to_string()).collect(),
+        description: doc.description.to_string(),
+    }));
+
+    for (module_name, source) in mq_lang::STANDARD_MODULES.iter() {
+        let (source_id, _) = hir.add_code(None, source());
+        docs.extend(mq_function_docs(&hir, source_id, Some(module_name)).collect::<Vec<_>>());
+    }
+
+    docs.sort_by_key(|doc| {
+        (
+            doc.module.clone(),
+            doc.kind == BuiltinDocKind::Selector,
+            doc.name.clone(),
+        )
+    });
+    docs
+}
+
+/// Returns the documentation of the public top-level functions defined in `source_id`.
+fn mq_function_docs<'a>(
+    hir: &'a mq_hir::Hir,
+    source_id: mq_hir::SourceId,
+    module: Option<&'a str>,
+) -> impl Iterator<Item = BuiltinDoc> + 'a {
+    hir.find_symbols_in_source(source_id)
+        .into_iter()
+        .filter_map(move |symbol| {
+            let mq_hir::SymbolKind::Function(params) = &symbol.kind else {
+                return None;
+            };
+            let name = symbol.value.as_deref()?;
+            if symbol.parent.is_some() || name.starts_with('_') {
+                return None;
+            }
+
+            Some(BuiltinDoc {
+                name: name.to_string(),
+                kind: BuiltinDocKind::Function,
+                module: module.map(str::to_string),
+                params: params.iter().map(|param| param.to_string()).collect(),
+                description: symbol.doc.iter().map(|(_, doc)| doc.trim()).join(" "),
+            })
+        })
+}
+
+/// Renders the reference documentation as Markdown, one table per section.
+fn builtin_docs_markdown(docs: &[BuiltinDoc]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+    let mut markdown = String::new();
+
+    for ((module, kind), entries) in &docs.iter().chunk_by(|doc| (doc.module.as_deref(), doc.kind)) {
+        match (module, kind) {
+            (None, BuiltinDocKind::Function) => markdown.push_str("## Built-in Functions\n\n"),
+            (None, BuiltinDocKind::Selector) => markdown.push_str("## Selectors\n\n"),
+            (Some(module), _) => markdown.push_str(&format!("## Module: {}\n\n`import \"{}\"`\n\n", module, module)),
+        }
+        markdown.push_str("| name | params | description |\n|------|--------|-------------|\n");
+        for doc in entries {
+            markdown.push_str(&format!(
+                "| {} | {} | {} |\n",
+                cell(&doc.name),
+                cell(&doc.params.join(", ")),
+                cell(&doc.description)
+            ));
+        }
+        markdown.push('\n');
+    }
+
+    markdown
+}
+
+/// Generates the reference documentation of the builtin functions, selectors, and standard
+/// modules as JSON (an array of entries, the default), Markdown, or HTML.
+#[wasm_bindgen(js_name=builtinDocs, skip_typescript)]
+pub async fn builtin_docs_export(format: JsValue) -> Result<JsValue, JsValue> {
+    let format: DocsFormat = if format.is_undefined() || format.is_null() {
+        DocsFormat::Json
+    } else {
+        serde_wasm_bindgen::from_value(format).map_err(|e| JsValue::from_str(&format!("Unknown docs format: {}", e)))?
+    };
+    let docs = builtin_docs();
+
+    match format {
+        DocsFormat::Json => serde_wasm_bindgen::to_value(&docs)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize docs: {}", e))),
+        DocsFormat::Markdown => Ok(JsValue::from_str(&builtin_docs_markdown(&docs))),
+        DocsFormat::Html => Ok(JsValue::from_str(&mq_markdown::to_html(&builtin_docs_markdown(&docs)))),
+    }
+}
+
 #[wasm_bindgen(js_name=htmlToMarkdown)]
 pub async fn html_to_markdown(html_input: &str, options: Option<ConversionOptions>) -> Result<String, JsValue> {
     mq_markdown::convert_html_to_markdown(html_input, options.map(Into::into).unwrap_or_default())
@@ -970,6 +1501,156 @@ pub async fn hover(code: &str, line: u32, column: u32) -> JsValue {
     serde_wasm_bindgen::to_value(&result).unwrap_or(JsValue::NULL)
 }
 
+/// Returns the name of an enum variant from its `Debug` output, without the payload.
+fn variant_name(debug: String) -> String {
+    match debug.find(['(', ' ', '{']) {
+        Some(index) => debug[..index].to_string(),
+        None => debug,
+    }
+}
+
+fn syntax_tokens(code: &str) -> Vec<SyntaxToken> {
+    let (nodes, _) = mq_lang::parse_recovery(code);
+    let mut tokens: Vec<SyntaxToken> = mq_lang::highlight_tokens(&nodes)
+        .into_iter()
+        .map(|token| SyntaxToken {
+            start_line: token.range.start.line,
+            start_column: token.range.start.column as u32,
+            end_line: token.range.end.line,
+            end_column: token.range.end.column as u32,
+            class: token.class,
+            token_kind: variant_name(format!("{:?}", token.token.kind)),
+        })
+        .collect();
+    tokens.sort_by_key(|token| (token.start_line, token.start_column));
+    tokens
+}
+
+fn syntax_node(node: &mq_lang::CstNode) -> SyntaxNode {
+    let range = node.node_range();
+    SyntaxNode {
+        kind: variant_name(format!("{:?}", node.kind)),
+        text: node.token.as_ref().map(|token| token.to_string()),
+        start_line: range.start.line,
+        start_column: range.start.column as u32,
+        end_line: range.end.line,
+        end_column: range.end.column as u32,
+        children: node.children.iter().map(|child| syntax_node(child)).collect(),
+    }
+}
+
+/// Returns the classified tokens of `code` for syntax highlighting, in source order.
+#[wasm_bindgen(js_name=tokens, skip_typescript)]
+pub async fn tokens(code: &str) -> JsValue {
+    serde_wasm_bindgen::to_value(&syntax_tokens(code)).unwrap_or_else(|_| JsValue::from(js_sys::Array::new()))
+}
+
+/// Returns the concrete syntax tree of `code`, parsed with error recovery, and its syntax errors.
+#[wasm_bindgen(js_name=cst, skip_typescript)]
+pub async fn cst(code: &str) -> Result<JsValue, JsValue> {
+    let (nodes, errors) = mq_lang::parse_recovery(code);
+    let tree = SyntaxTree {
+        nodes: nodes
+            .iter()
+            .filter(|node| !node.is_eof())
+            .map(|node| syntax_node(node))
+            .collect(),
+        errors: errors
+            .error_ranges(code)
+            .iter()
+            .map(|(message, range)| Diagnostic {
+                start_line: range.start.line,
+                start_column: range.start.column as u32,
+                end_line: range.end.line,
+                end_column: range.end.column as u32,
+                message: message.to_owned(),
+            })
+            .collect(),
+    };
+
+    serde_wasm_bindgen::to_value(&tree).map_err(|e| JsValue::from_str(&format!("Failed to serialize CST: {}", e)))
+}
+
+fn completion_items(code: &str, line: u32, column: u32) -> Vec<CompletionItem> {
+    let mut hir = mq_hir::Hir::default();
+    let (source_id, _) = hir.add_code(None, code);
+    let position = mq_lang::Position::new(line, column as usize);
+
+    // After `module::`, only the members of that module are candidates.
+    let module_symbols = (column > 3)
+        .then(|| hir.find_symbol_in_position(source_id, mq_lang::Position::new(line, column as usize - 3)))
+        .flatten()
+        .and_then(|(_, symbol)| match symbol.kind {
+            mq_hir::SymbolKind::QualifiedAccess => {
+                let module_name = symbol.value.as_ref()?;
+                hir.symbols().find_map(|(_, module_symbol)| match module_symbol.kind {
+                    mq_hir::SymbolKind::Module(module_source_id) | mq_hir::SymbolKind::Import(module_source_id)
+                        if module_symbol.value.as_ref() == Some(module_name) =>
+                    {
+                        Some(hir.find_symbols_in_module(module_source_id))
+                    }
+                    _ => None,
+                })
+            }
+            mq_hir::SymbolKind::Module(module_source_id) => Some(hir.find_symbols_in_module(module_source_id)),
+            _ => None,
+        });
+
+    let symbols = module_symbols.unwrap_or_else(|| {
+        let scope_id = hir
+            .find_scope_in_position(source_id, position)
+            .map(|(scope_id, _)| scope_id)
+            .unwrap_or_else(|| hir.find_scope_by_source(&source_id));
+        hir.find_symbols_in_scope(scope_id)
+            .into_iter()
+            .chain(hir.find_symbols_in_source(hir.builtin.source_id))
+            .unique_by(|symbol| symbol.value.clone())
+            .collect()
+    });
+
+    symbols
+        .iter()
+        .filter_map(|symbol| {
+            let name = symbol.value.as_deref()?.to_string();
+            let (kind, insert_text) = match &symbol.kind {
+                mq_hir::SymbolKind::Function(params) | mq_hir::SymbolKind::Macro(params) => (
+                    CompletionKind::Function,
+                    format!(
+                        "{}({})",
+                        name,
+                        params
+                            .iter()
+                            .enumerate()
+                            .map(|(i, param)| format!("${{{}:{}}}", i + 1, param))
+                            .join(", ")
+                    ),
+                ),
+                mq_hir::SymbolKind::Parameter
+                | mq_hir::SymbolKind::Variable
+                | mq_hir::SymbolKind::DestructuringBinding
+                | mq_hir::SymbolKind::PatternVariable { .. } => (CompletionKind::Variable, name.clone()),
+                mq_hir::SymbolKind::Selector(_) => (CompletionKind::Selector, name.clone()),
+                _ => return None,
+            };
+
+            Some(CompletionItem {
+                label: name,
+                kind,
+                insert_text,
+                doc: symbol.doc.iter().map(|(_, doc)| doc).join("\n"),
+                deprecated: symbol.is_deprecated(),
+            })
+        })
+        .collect()
+}
+
+/// Returns completion candidates at `line`/`column` (1-based).
+#[wasm_bindgen(js_name=completions, skip_typescript)]
+pub async fn completions(code: &str, line: u32, column: u32) -> JsValue {
+    serde_wasm_bindgen::to_value(&completion_items(code, line, column))
+        .unwrap_or_else(|_| JsValue::from(js_sys::Array::new()))
+}
+
 /// Name of the OPFS subdirectory used to store cached HTTP modules.
 #[cfg(feature = "opfs")]
 const HTTP_CACHE_DIR: &str = "http_cache";
@@ -1139,7 +1820,6 @@ async fn write_opfs_lock(root: &opfs::persistent::DirectoryHandle, lock: &mq_lan
 }
 
 /// Parses `code` and returns all import/include paths that are local module names (not URLs).
-#[cfg(feature = "opfs")]
 fn extract_local_import_names(code: &str) -> Vec<String> {
     let token_arena = mq_lang::Shared::new(mq_lang::SharedCell::new(mq_lang::Arena::new(1024)));
     let Ok(program) = mq_lang::parse(code, token_arena) else {
@@ -1298,6 +1978,159 @@ mod tests {
         assert_eq!(result.await.unwrap(), "[test](<https://example.com>)\n");
     }
 
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_syntax_tokens() {
+        use mq_lang::TokenClass;
+
+        let tokens = syntax_tokens("# c\ndef foo(x): x + 1;")
+            .into_iter()
+            .map(|token| (token.class, token.token_kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens,
+            vec![
+                (TokenClass::Comment, "Comment".to_string()),
+                (TokenClass::Keyword, "Def".to_string()),
+                (TokenClass::Function, "Ident".to_string()),
+                (TokenClass::Punctuation, "LParen".to_string()),
+                (TokenClass::Variable, "Ident".to_string()),
+                (TokenClass::Punctuation, "RParen".to_string()),
+                (TokenClass::Punctuation, "Colon".to_string()),
+                (TokenClass::Variable, "Ident".to_string()),
+                (TokenClass::Operator, "Plus".to_string()),
+                (TokenClass::Number, "NumberLiteral".to_string()),
+                (TokenClass::Punctuation, "SemiColon".to_string()),
+            ]
+        );
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_syntax_node() {
+        let (nodes, _) = mq_lang::parse_recovery("foo(1)");
+        let node = syntax_node(&nodes[0]);
+
+        assert_eq!(node.kind, "Call");
+        assert_eq!(node.text.as_deref(), Some("foo"));
+        assert_eq!((node.start_column, node.end_column), (1, 7));
+        assert_eq!(
+            node.children
+                .iter()
+                .map(|child| child.kind.as_str())
+                .collect::<Vec<_>>(),
+            vec!["Token", "Literal", "Token"]
+        );
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_cst_reports_errors() {
+        let tree: serde_json::Value = serde_wasm_bindgen::from_value(cst("foo(").await.unwrap()).unwrap();
+        assert!(!tree["errors"].as_array().unwrap().is_empty());
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_completion_items_include_user_functions_and_builtins() {
+        let items = completion_items("def foo(x): x;\n| ", 2, 3);
+        let foo = items.iter().find(|item| item.label == "foo").unwrap();
+
+        assert_eq!(foo.kind, CompletionKind::Function);
+        assert_eq!(foo.insert_text, "foo(${1:x})");
+        assert!(items.iter().any(|item| item.label == "upcase"));
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_completion_items_module_members() {
+        let items = completion_items("module m: def bar(): 1; end | m::", 1, 35);
+        assert_eq!(
+            items.iter().map(|item| item.label.as_str()).collect::<Vec<_>>(),
+            vec!["bar"]
+        );
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_script_run_stream() {
+        let chunks = Rc::new(RefCell::new(Vec::new()));
+        let on_result = {
+            let chunks = Rc::clone(&chunks);
+            Closure::<dyn FnMut(String)>::new(move |chunk: String| chunks.borrow_mut().push(chunk))
+        };
+
+        run_stream(
+            ".h",
+            "# A\n\n# B\n\n# C",
+            serde_wasm_bindgen::to_value(&Options {
+                is_update: false,
+                input_format: None,
+                list_style: None,
+                link_title_style: None,
+                link_url_style: None,
+                allowed_domains: None,
+                timeout_ms: None,
+            })
+            .unwrap(),
+            on_result.as_ref().unchecked_ref::<js_sys::Function>().clone(),
+            Some(2),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(*chunks.borrow(), vec!["# A\n# B\n".to_string(), "# C\n".to_string()]);
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_script_run_values() {
+        let result = run_values(
+            "[1, true, {\"a\": \"b\"}, None]",
+            "",
+            serde_wasm_bindgen::to_value(&Options {
+                is_update: false,
+                input_format: Some(InputFormat::Null),
+                list_style: None,
+                link_title_style: None,
+                link_url_style: None,
+                allowed_domains: None,
+                timeout_ms: None,
+            })
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        let values: serde_json::Value = serde_wasm_bindgen::from_value(result).unwrap();
+        assert_eq!(values, serde_json::json!([[1.0, true, {"a": "b"}, null]]));
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_script_run_values_markdown() {
+        let result = run_values(
+            ".h",
+            "# Title",
+            serde_wasm_bindgen::to_value(&Options {
+                is_update: false,
+                input_format: None,
+                list_style: None,
+                link_title_style: None,
+                link_url_style: None,
+                allowed_domains: None,
+                timeout_ms: None,
+            })
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+        let values: serde_json::Value = serde_wasm_bindgen::from_value(result).unwrap();
+        assert_eq!(values[0]["type"], "Heading");
+        assert_eq!(values[0]["depth"], 1);
+        assert_eq!(values[0]["values"][0]["value"], "Title");
+    }
+
     #[allow(unused)]
     #[wasm_bindgen_test]
     async fn test_script_run_timeout() {
@@ -1346,14 +2179,56 @@ mod tests {
     #[allow(unused)]
     #[wasm_bindgen_test]
     async fn test_script_format() {
-        let result = format(r#"downcase()|ltrimstr("hello")|upcase()|trim()"#).await.unwrap();
+        let result = format(r#"downcase()|ltrimstr("hello")|upcase()|trim()"#, JsValue::UNDEFINED)
+            .await
+            .unwrap();
         assert_eq!(result, r#"downcase() | ltrimstr("hello") | upcase() | trim()"#);
     }
 
     #[allow(unused)]
     #[wasm_bindgen_test]
     async fn test_script_format_invalid() {
-        assert!(format("x=>").await.is_err());
+        assert!(format("x=>", JsValue::UNDEFINED).await.is_err());
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_script_format_with_options() {
+        let options = serde_wasm_bindgen::to_value(&FormatOptions {
+            indent_width: Some(4),
+            ..FormatOptions::default()
+        })
+        .unwrap();
+        let result = format("def f(x):\nx;", options).await.unwrap();
+        assert_eq!(result, "def f(x):\n    x;\n");
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_builtin_docs() {
+        let docs = builtin_docs();
+
+        let map = docs
+            .iter()
+            .find(|doc| doc.name == "map" && doc.module.is_none())
+            .unwrap();
+        assert_eq!(map.kind, BuiltinDocKind::Function);
+        assert_eq!(map.params, vec!["v", "f"]);
+        assert!(docs.iter().any(|doc| doc.name == "upcase" && doc.module.is_none()));
+        assert!(docs.iter().any(|doc| doc.kind == BuiltinDocKind::Selector));
+        assert!(docs.iter().any(|doc| doc.module.as_deref() == Some("csv")));
+        assert!(docs.iter().all(|doc| !doc.name.starts_with('_')));
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    fn test_builtin_docs_markdown() {
+        let markdown = builtin_docs_markdown(&builtin_docs());
+
+        assert!(markdown.starts_with("## Built-in Functions\n\n| name | params | description |"));
+        assert!(markdown.contains("\n## Selectors\n"));
+        assert!(markdown.contains("\n## Module: csv\n\n`import \"csv\"`\n"));
+        assert!(mq_lang::parse_markdown_input(&markdown).is_ok());
     }
 
     #[allow(unused)]
@@ -1883,6 +2758,68 @@ mod tests {
         assert!(mq_lang::ModuleResolver::resolve(&resolver, "pll_multi_b").is_ok());
     }
 
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_preload_js_modules_resolves_transitive_dependencies() {
+        // js_a imports js_b; js_b is returned as a promise.
+        set_module_resolver(Some(js_sys::Function::new_with_args(
+            "name",
+            r#"return name === "js_a" ? 'import "js_b"\ndef fa(x): x;' : name === "js_b" ? Promise.resolve("def fb(x): x;") : null;"#,
+        )));
+        let resolver = WasmModuleResolver::new();
+        let result = resolver.preload_js_modules(r#"import "js_a""#).await;
+        set_module_resolver(None);
+
+        assert!(result.is_ok());
+        assert!(mq_lang::ModuleResolver::resolve(&resolver, "js_a").is_ok());
+        assert!(mq_lang::ModuleResolver::resolve(&resolver, "js_b").is_ok());
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_preload_js_modules_reports_resolver_error() {
+        set_module_resolver(Some(js_sys::Function::new_with_args(
+            "name",
+            r#"throw new Error("not found: " + name);"#,
+        )));
+        let result = WasmModuleResolver::new()
+            .preload_js_modules(r#"include "js_missing""#)
+            .await;
+        set_module_resolver(None);
+
+        assert_eq!(
+            result.unwrap_err().as_string().unwrap(),
+            "Failed to resolve module 'js_missing': not found: js_missing"
+        );
+    }
+
+    #[allow(unused)]
+    #[wasm_bindgen_test]
+    async fn test_script_run_with_js_module_resolver() {
+        set_module_resolver(Some(js_sys::Function::new_with_args(
+            "name",
+            r#"return name === "greet" ? 'def greet(x): "Hello, " + x;' : null;"#,
+        )));
+        let result = run(
+            r#"include "greet" | greet("mq")"#,
+            "",
+            serde_wasm_bindgen::to_value(&Options {
+                is_update: false,
+                input_format: Some(InputFormat::Null),
+                list_style: None,
+                link_title_style: None,
+                link_url_style: None,
+                allowed_domains: None,
+                timeout_ms: None,
+            })
+            .unwrap(),
+        )
+        .await;
+        set_module_resolver(None);
+
+        assert_eq!(result.unwrap(), "Hello, mq\n");
+    }
+
     #[cfg(feature = "opfs")]
     #[allow(unused)]
     #[wasm_bindgen_test]
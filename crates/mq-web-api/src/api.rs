@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use miette::miette;
 use mq_formatter::{Formatter, FormatterConfig};
@@ -6,7 +6,10 @@ use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
-/// Maximum number of documents allowed in a single `POST /api/v1/batch` request.
+use crate::limits::ExecutionLimits;
+
+/// Maximum number of documents allowed in a single `POST /api/v1/batch` or
+/// `POST /api/v1/query/files` request.
 pub const MAX_BATCH_SIZE: usize = 100;
 
 #[derive(Deserialize, Serialize, ToSchema, Clone, Debug)]
@@ -34,13 +37,18 @@ pub struct QueryApiResponse {
 }
 
 /// Request body for `POST /api/v1/batch`. Runs `query` against each entry
-/// of `inputs`, avoiding one HTTP round trip per document.
+/// of `inputs`, and each entry of `items` with its own query, avoiding one
+/// HTTP round trip per document.
 #[derive(Deserialize, Serialize, ToSchema, Clone, Debug)]
 pub struct BatchApiRequest {
+    /// Query run against every entry of `inputs`. Required when `inputs` is not empty.
     #[schema(example = ".h")]
-    pub query: String,
-    /// At most [`MAX_BATCH_SIZE`] entries are accepted.
+    pub query: Option<String>,
+    #[serde(default)]
     pub inputs: Vec<String>,
+    /// `{query, input}` pairs, for documents that each need a different query.
+    #[serde(default)]
+    pub items: Vec<BatchItemRequest>,
     pub input_format: Option<InputFormat>,
     /// Names of builtin modules to load (e.g. "json", "csv", "table").
     #[schema(example = json!(["table"]))]
@@ -50,6 +58,14 @@ pub struct BatchApiRequest {
     pub aggregate: Option<bool>,
 }
 
+/// A single document of a batch with its own query.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Debug)]
+pub struct BatchItemRequest {
+    #[schema(example = ".h1")]
+    pub query: String,
+    pub input: Option<String>,
+}
+
 /// Result of running the batch query against a single input document.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BatchItemResult {
@@ -59,7 +75,8 @@ pub struct BatchItemResult {
     pub error: Option<String>,
 }
 
-/// Response body for `POST /api/v1/batch`. `items` is ordered like `inputs`.
+/// Response body for `POST /api/v1/batch`. `items` is ordered like `inputs`,
+/// followed by the entries of the request's `items`.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct BatchApiResponse {
     pub items: Vec<BatchItemResult>,
@@ -118,6 +135,26 @@ impl InputFormat {
             Self::Markdown | Self::Mdx | Self::Text | Self::Html | Self::Raw | Self::Null => None,
         }
     }
+
+    /// Infers the format of an uploaded file from its extension.
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = std::path::Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "mdx" => Some(Self::Mdx),
+            "html" | "htm" => Some(Self::Html),
+            "txt" => Some(Self::Text),
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "psv" => Some(Self::Psv),
+            "json" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "xml" => Some(Self::Xml),
+            "toon" => Some(Self::Toon),
+            _ => None,
+        }
+    }
 }
 
 /// Output format for query results.
@@ -137,6 +174,45 @@ pub enum OutputFormat {
     None,
 }
 
+impl OutputFormat {
+    /// File extension of a rendered result, or `None` to keep the input file's.
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Self::Html => Some("html"),
+            Self::Text => Some("txt"),
+            Self::Json => Some("json"),
+            Self::Markdown | Self::None => None,
+        }
+    }
+}
+
+/// A file uploaded to `POST /api/v1/query/files`, either directly or inside a zip archive.
+#[derive(Debug, Clone)]
+pub struct InputFile {
+    pub path: String,
+    pub content: Vec<u8>,
+}
+
+/// Multi-file request for `POST /api/v1/query/files`, assembled from a
+/// multipart upload. Each file's format is inferred from its extension unless
+/// `input_format` is given.
+#[derive(Debug, Clone)]
+pub struct FilesApiRequest {
+    pub query: String,
+    pub input_format: Option<InputFormat>,
+    pub output_format: Option<OutputFormat>,
+    pub files: Vec<InputFile>,
+}
+
+/// Response body for `POST /api/v1/query/files`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct FilesApiResponse {
+    /// Result of each file, keyed by its path.
+    pub files: BTreeMap<String, BatchItemResult>,
+    /// Paths of files skipped because their format couldn't be inferred.
+    pub skipped: Vec<String>,
+}
+
 /// Request body for `POST /api/check`.
 #[derive(Deserialize, Serialize, ToSchema, Clone, Debug)]
 pub struct CheckApiRequest {
@@ -184,6 +260,25 @@ pub struct FormatApiResponse {
     pub formatted: String,
 }
 
+/// Request body for `POST /api/v1/convert/html-to-markdown`.
+#[derive(Deserialize, Serialize, ToSchema, Clone, Debug)]
+pub struct ConvertApiRequest {
+    #[schema(example = "<h1>Title</h1><p>Hello <strong>world</strong></p>")]
+    pub html: String,
+    /// Emit `<script>` contents as fenced code blocks instead of dropping them.
+    pub extract_scripts_as_code_blocks: Option<bool>,
+    /// Generate YAML front matter from the document's `<title>` and `<meta>` tags.
+    pub generate_front_matter: Option<bool>,
+    /// Use the document's `<title>` as a leading `#` heading.
+    pub use_title_as_h1: Option<bool>,
+}
+
+/// Response body for `POST /api/v1/convert/html-to-markdown`.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConvertApiResponse {
+    pub markdown: String,
+}
+
 /// Documentation for a single builtin function.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct FunctionDoc {
@@ -240,35 +335,54 @@ pub struct LintApiResponse {
     pub diagnostics: Vec<LintDiagnostic>,
 }
 
-pub fn query(request: ApiRequest, timeout: std::time::Duration) -> miette::Result<QueryApiResponse> {
-    execute_query(request, timeout)
+pub fn query(request: ApiRequest, limits: &ExecutionLimits) -> miette::Result<QueryApiResponse> {
+    execute_query(request, limits)
 }
 
-/// Runs `request.query` against every document in `request.inputs` in
-/// parallel, each with its own engine instance.
-pub fn batch_query(request: BatchApiRequest, timeout: std::time::Duration) -> miette::Result<BatchApiResponse> {
-    if request.inputs.len() > MAX_BATCH_SIZE {
+/// Runs `request.query` against every document in `request.inputs`, then every
+/// `request.items` entry with its own query, in parallel on the current rayon
+/// pool, each with its own engine instance.
+pub fn batch_query(request: BatchApiRequest, limits: &ExecutionLimits) -> miette::Result<BatchApiResponse> {
+    let batch_size = request.inputs.len() + request.items.len();
+    if batch_size > MAX_BATCH_SIZE {
         return Err(miette!(
             "Batch request exceeds maximum of {} documents (got {})",
             MAX_BATCH_SIZE,
-            request.inputs.len()
+            batch_size
         ));
     }
 
-    let items = request
+    let shared_query = match (&request.query, request.inputs.is_empty()) {
+        (Some(query), _) => query.clone(),
+        (None, true) => String::new(),
+        (None, false) => return Err(miette!("`query` is required when `inputs` is not empty")),
+    };
+
+    let documents: Vec<(&str, &str)> = request
         .inputs
+        .iter()
+        .map(|input| (shared_query.as_str(), input.as_str()))
+        .chain(
+            request
+                .items
+                .iter()
+                .map(|item| (item.query.as_str(), item.input.as_deref().unwrap_or_default())),
+        )
+        .collect();
+
+    let items = documents
         .par_iter()
-        .map(|input| {
+        .map(|(query, input)| {
             let item_request = ApiRequest {
-                query: request.query.clone(),
-                input: Some(input.clone()),
+                query: query.to_string(),
+                input: Some(input.to_string()),
                 input_format: request.input_format.clone(),
                 modules: request.modules.clone(),
                 args: request.args.clone(),
                 output_format: request.output_format.clone(),
                 aggregate: request.aggregate,
             };
-            match execute_query(item_request, timeout) {
+            match execute_query(item_request, limits) {
                 Ok(response) => BatchItemResult {
                     results: response.results,
                     error: None,
@@ -284,6 +398,75 @@ pub fn batch_query(request: BatchApiRequest, timeout: std::time::Duration) -> mi
     Ok(BatchApiResponse { items })
 }
 
+/// Runs `request.query` against every uploaded file in parallel on the current
+/// rayon pool, each with its own engine instance.
+pub fn files_query(request: FilesApiRequest, limits: &ExecutionLimits) -> miette::Result<FilesApiResponse> {
+    if request.files.len() > MAX_BATCH_SIZE {
+        return Err(miette!(
+            "Request exceeds maximum of {} files (got {})",
+            MAX_BATCH_SIZE,
+            request.files.len()
+        ));
+    }
+
+    let mut paths = std::collections::HashSet::new();
+    if let Some(file) = request.files.iter().find(|file| !paths.insert(file.path.as_str())) {
+        return Err(miette!("Duplicate file path '{}'", file.path));
+    }
+
+    let (documents, skipped): (Vec<_>, Vec<_>) = request
+        .files
+        .iter()
+        .map(|file| {
+            (
+                file,
+                request
+                    .input_format
+                    .clone()
+                    .or_else(|| InputFormat::from_path(&file.path)),
+            )
+        })
+        .partition(|(_, input_format)| input_format.is_some());
+
+    let files = documents
+        .par_iter()
+        .map(|(file, input_format)| {
+            let result = std::str::from_utf8(&file.content)
+                .map_err(|_| miette!("File is not valid UTF-8"))
+                .and_then(|input| {
+                    execute_query(
+                        ApiRequest {
+                            query: request.query.clone(),
+                            input: Some(input.to_string()),
+                            input_format: input_format.clone(),
+                            modules: None,
+                            args: None,
+                            output_format: request.output_format.clone(),
+                            aggregate: None,
+                        },
+                        limits,
+                    )
+                });
+            let item = match result {
+                Ok(response) => BatchItemResult {
+                    results: response.results,
+                    error: None,
+                },
+                Err(e) => BatchItemResult {
+                    results: vec![],
+                    error: Some(e.to_string()),
+                },
+            };
+            (file.path.clone(), item)
+        })
+        .collect();
+
+    Ok(FilesApiResponse {
+        files,
+        skipped: skipped.into_iter().map(|(file, _)| file.path.clone()).collect(),
+    })
+}
+
 /// Type-checks the given query and returns any errors found.
 ///
 /// Always returns HTTP 200 — errors are data, not exceptional failures.
@@ -343,6 +526,18 @@ pub fn format_query(request: FormatApiRequest) -> miette::Result<FormatApiRespon
     Ok(FormatApiResponse { formatted })
 }
 
+/// Converts an HTML document to Markdown.
+pub fn convert_html_to_markdown(request: ConvertApiRequest) -> miette::Result<ConvertApiResponse> {
+    let options = mq_markdown::ConversionOptions {
+        extract_scripts_as_code_blocks: request.extract_scripts_as_code_blocks.unwrap_or(false),
+        generate_front_matter: request.generate_front_matter.unwrap_or(false),
+        use_title_as_h1: request.use_title_as_h1.unwrap_or(false),
+    };
+    let markdown = mq_markdown::convert_html_to_markdown(&request.html, options)
+        .map_err(|e| miette!("Conversion error: {}", e))?;
+    Ok(ConvertApiResponse { markdown })
+}
+
 /// Lists all builtin mq functions with their documentation.
 pub fn list_functions() -> FunctionsApiResponse {
     let mut functions: Vec<FunctionDoc> = mq_lang::BUILTIN_FUNCTION_DOC
@@ -413,14 +608,25 @@ fn type_error_kind(e: &mq_check::TypeError) -> String {
         mq_check::TypeError::NullablePropagation { .. } => "nullable_propagation",
         mq_check::TypeError::UnreachableCode { .. } => "unreachable_code",
         mq_check::TypeError::NonExhaustiveMatch { .. } => "non_exhaustive_match",
+        mq_check::TypeError::DynamicConstruct { .. } => "dynamic_construct",
     }
     .to_string()
 }
 
-fn execute_query(request: ApiRequest, timeout: std::time::Duration) -> miette::Result<QueryApiResponse> {
+/// An engine set up for a request, with the query and parsed input ready to evaluate.
+struct PreparedQuery {
+    engine: mq_lang::DefaultEngine,
+    query: String,
+    input: Vec<mq_lang::RuntimeValue>,
+    output_format: OutputFormat,
+}
+
+fn prepare_query(request: ApiRequest, limits: &ExecutionLimits) -> miette::Result<PreparedQuery> {
+    limits.check_query(&request.query)?;
+
     let mut engine = mq_lang::DefaultEngine::default();
     engine.load_builtin_module();
-    engine.set_timeout(timeout);
+    limits.apply(&mut engine);
 
     if let Some(modules) = &request.modules {
         for module_name in modules {
@@ -468,19 +674,23 @@ fn execute_query(request: ApiRequest, timeout: std::time::Duration) -> miette::R
         | InputFormat::Toon => mq_lang::raw_input(&request.input.unwrap_or_default()),
     };
 
-    let runtime_values = engine
-        .eval(&query, input.into_iter())
-        .map_err(|e| miette!("Error executing query: {}", e))?;
+    Ok(PreparedQuery {
+        engine,
+        query,
+        input,
+        output_format: request.output_format.unwrap_or_default(),
+    })
+}
 
-    let nodes: Vec<mq_markdown::Node> = runtime_values
-        .values()
-        .iter()
-        .flat_map(runtime_value_to_nodes)
-        .collect();
+fn render_results(
+    runtime_values: &[mq_lang::RuntimeValue],
+    output_format: &OutputFormat,
+) -> miette::Result<Vec<String>> {
+    let nodes: Vec<mq_markdown::Node> = runtime_values.iter().flat_map(runtime_value_to_nodes).collect();
 
     let markdown = mq_markdown::Markdown::new(nodes);
 
-    let results = match request.output_format.unwrap_or_default() {
+    let results = match output_format {
         OutputFormat::Html => vec![markdown.to_html()],
         OutputFormat::Text => vec![markdown.to_text()],
         OutputFormat::Json => vec![
@@ -495,9 +705,83 @@ fn execute_query(request: ApiRequest, timeout: std::time::Duration) -> miette::R
     .filter(|s| !s.is_empty())
     .collect();
 
+    Ok(results)
+}
+
+/// Renders the values one at a time, checking the running byte count against
+/// the result limit, so an oversized result is rejected before the combined
+/// output for every value is built.
+fn check_rendered_size(
+    runtime_values: &[mq_lang::RuntimeValue],
+    output_format: &OutputFormat,
+    limits: &ExecutionLimits,
+) -> miette::Result<()> {
+    let mut result_bytes = 0;
+    for value in runtime_values {
+        for result in render_results(std::slice::from_ref(value), output_format)? {
+            result_bytes += result.len();
+            limits.check_result_size(result_bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Wraps an evaluation error, keeping exceeded limits as [`LimitExceeded`] so
+/// handlers can report them with their own status.
+fn eval_error(error: Box<mq_lang::Error>) -> miette::Report {
+    match ExecutionLimits::classify(&error) {
+        Some(limit) => limit.into(),
+        None => miette!("Error executing query: {}", error),
+    }
+}
+
+fn execute_query(request: ApiRequest, limits: &ExecutionLimits) -> miette::Result<QueryApiResponse> {
+    let PreparedQuery {
+        mut engine,
+        query,
+        input,
+        output_format,
+    } = prepare_query(request, limits)?;
+
+    let runtime_values = engine.eval(&query, input.into_iter()).map_err(eval_error)?;
+    check_rendered_size(runtime_values.values(), &output_format, limits)?;
+    let results = render_results(runtime_values.values(), &output_format)?;
+    limits.check_result_size(results.iter().map(String::len).sum())?;
+
     Ok(QueryApiResponse { results })
 }
 
+/// Evaluates the request one input node at a time, passing each rendered
+/// result to `emit` as soon as it's produced. Queries that use `nodes` see the
+/// whole input at once, as with [`query`]. Stops early when `emit` returns `false`.
+pub fn stream_query(
+    request: ApiRequest,
+    limits: &ExecutionLimits,
+    mut emit: impl FnMut(String) -> bool,
+) -> miette::Result<()> {
+    let PreparedQuery {
+        mut engine,
+        query,
+        input,
+        output_format,
+    } = prepare_query(request, limits)?;
+
+    let mut result_bytes = 0;
+    for value in engine.eval_stream(&query, input.into_iter()).map_err(eval_error)? {
+        let value = value.map_err(eval_error)?;
+        for result in render_results(std::slice::from_ref(&value), &output_format)? {
+            result_bytes += result.len();
+            limits.check_result_size(result_bytes)?;
+            if !emit(result) {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
 fn collect_markdown_nodes(value: &mq_lang::RuntimeValue, nodes: &mut Vec<mq_markdown::Node>) {
     match value {
         mq_lang::RuntimeValue::Markdown(node, _) => nodes.push((**node).clone()),
@@ -580,6 +864,7 @@ fn runtime_value_to_nodes(runtime_value: &mq_lang::RuntimeValue) -> Vec<mq_markd
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::limits::LimitExceeded;
     use rstest::rstest;
 
     #[rstest]
@@ -601,7 +886,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok(), "{:?}", result.err());
         assert!(!result.unwrap().results.is_empty());
     }
@@ -631,7 +916,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         assert!(!result.unwrap().results.is_empty());
     }
@@ -647,7 +932,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
     }
 
@@ -662,7 +947,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_err());
     }
 
@@ -678,7 +963,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
     }
 
@@ -694,7 +979,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_err());
     }
 
@@ -712,7 +997,7 @@ mod tests {
             output_format: None,
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert!(!resp.results.is_empty());
@@ -729,7 +1014,7 @@ mod tests {
             output_format: Some(OutputFormat::Html),
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert!(!resp.results.is_empty());
@@ -747,7 +1032,7 @@ mod tests {
             output_format: Some(OutputFormat::None),
             aggregate: None,
         };
-        let result = query(req, std::time::Duration::from_secs(10));
+        let result = query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         assert!(result.unwrap().results.is_empty());
     }
@@ -755,15 +1040,16 @@ mod tests {
     #[test]
     fn test_batch_query_multiple_documents() {
         let req = BatchApiRequest {
-            query: ".h1".to_string(),
+            query: Some(".h1".to_string()),
             inputs: vec!["# Title One".to_string(), "# Title Two".to_string()],
+            items: vec![],
             input_format: Some(InputFormat::Markdown),
             modules: None,
             args: None,
             output_format: None,
             aggregate: None,
         };
-        let result = batch_query(req, std::time::Duration::from_secs(10));
+        let result = batch_query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert_eq!(resp.items.len(), 2);
@@ -775,15 +1061,16 @@ mod tests {
     #[test]
     fn test_batch_query_preserves_order_and_isolates_errors() {
         let req = BatchApiRequest {
-            query: "invalid query".to_string(),
+            query: Some("invalid query".to_string()),
             inputs: vec!["# Title One".to_string()],
+            items: vec![],
             input_format: Some(InputFormat::Markdown),
             modules: None,
             args: None,
             output_format: None,
             aggregate: None,
         };
-        let result = batch_query(req, std::time::Duration::from_secs(10));
+        let result = batch_query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         let resp = result.unwrap();
         assert_eq!(resp.items.len(), 1);
@@ -794,15 +1081,16 @@ mod tests {
     #[test]
     fn test_batch_query_empty_inputs() {
         let req = BatchApiRequest {
-            query: ".h1".to_string(),
+            query: Some(".h1".to_string()),
             inputs: vec![],
+            items: vec![],
             input_format: Some(InputFormat::Markdown),
             modules: None,
             args: None,
             output_format: None,
             aggregate: None,
         };
-        let result = batch_query(req, std::time::Duration::from_secs(10));
+        let result = batch_query(req, &ExecutionLimits::default());
         assert!(result.is_ok());
         assert!(result.unwrap().items.is_empty());
     }
@@ -810,18 +1098,216 @@ mod tests {
     #[test]
     fn test_batch_query_exceeds_max_size() {
         let req = BatchApiRequest {
-            query: ".h1".to_string(),
+            query: Some(".h1".to_string()),
             inputs: vec!["# Title".to_string(); MAX_BATCH_SIZE + 1],
+            items: vec![],
             input_format: Some(InputFormat::Markdown),
             modules: None,
             args: None,
             output_format: None,
             aggregate: None,
         };
-        let result = batch_query(req, std::time::Duration::from_secs(10));
+        let result = batch_query(req, &ExecutionLimits::default());
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_batch_query_items_with_own_queries() {
+        let req = BatchApiRequest {
+            query: Some(".h1".to_string()),
+            inputs: vec!["# Title One".to_string()],
+            items: vec![
+                BatchItemRequest {
+                    query: ".h2".to_string(),
+                    input: Some("# Title\n\n## Sub".to_string()),
+                },
+                BatchItemRequest {
+                    query: "invalid query".to_string(),
+                    input: None,
+                },
+            ],
+            input_format: Some(InputFormat::Markdown),
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        };
+        let resp = batch_query(req, &ExecutionLimits::default()).unwrap();
+        assert_eq!(resp.items.len(), 3);
+        assert_eq!(resp.items[0].results, vec!["# Title One\n"]);
+        assert_eq!(resp.items[1].results, vec!["## Sub\n"]);
+        assert!(resp.items[2].error.is_some());
+    }
+
+    #[test]
+    fn test_batch_query_inputs_without_query() {
+        let req = BatchApiRequest {
+            query: None,
+            inputs: vec!["# Title".to_string()],
+            items: vec![],
+            input_format: None,
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        };
+        assert!(batch_query(req, &ExecutionLimits::default()).is_err());
+    }
+
+    fn stream(request: ApiRequest) -> miette::Result<Vec<String>> {
+        let mut results = Vec::new();
+        stream_query(request, &ExecutionLimits::default(), |result| {
+            results.push(result);
+            true
+        })?;
+        Ok(results)
+    }
+
+    #[rstest]
+    #[case::per_node(".h", None, vec!["# A\n", "## B\n"])]
+    #[case::aggregate("len()", Some(true), vec!["3\n"])]
+    fn test_stream_query(#[case] query: &str, #[case] aggregate: Option<bool>, #[case] expected: Vec<&str>) {
+        let req = ApiRequest {
+            query: query.to_string(),
+            input: Some("# A\n\ntext\n\n## B".to_string()),
+            input_format: Some(InputFormat::Markdown),
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate,
+        };
+        assert_eq!(stream(req).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_stream_query_stops_when_emit_returns_false() {
+        let req = ApiRequest {
+            query: ".h".to_string(),
+            input: Some("# A\n\n# B\n\n# C".to_string()),
+            input_format: Some(InputFormat::Markdown),
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        };
+        let mut count = 0;
+        stream_query(req, &ExecutionLimits::default(), |_| {
+            count += 1;
+            false
+        })
+        .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[rstest]
+    #[case::query_too_long(
+        ExecutionLimits { max_query_length: 2, ..Default::default() },
+        LimitExceeded::QueryTooLong { max: 2, actual: 3 }
+    )]
+    #[case::result_too_large(
+        ExecutionLimits { max_result_bytes: 4, ..Default::default() },
+        LimitExceeded::ResultTooLarge { max: 4 }
+    )]
+    fn test_query_limit_exceeded(#[case] limits: ExecutionLimits, #[case] expected: LimitExceeded) {
+        let req = ApiRequest {
+            query: ".h1".to_string(),
+            input: Some("# Title".to_string()),
+            input_format: None,
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        };
+        let err = query(req.clone(), &limits).unwrap_err();
+        assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&expected));
+
+        let err = stream_query(req, &limits, |_| true).unwrap_err();
+        assert_eq!(err.downcast_ref::<LimitExceeded>(), Some(&expected));
+    }
+
+    #[test]
+    fn test_check_rendered_size_stops_at_limit() {
+        let values: Vec<mq_lang::RuntimeValue> = (0..1000).map(|i| format!("value {}", i).into()).collect();
+        let limits = ExecutionLimits {
+            max_result_bytes: 32,
+            ..Default::default()
+        };
+
+        let err = check_rendered_size(&values, &OutputFormat::Text, &limits).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<LimitExceeded>(),
+            Some(&LimitExceeded::ResultTooLarge { max: 32 })
+        );
+        assert!(check_rendered_size(&values[..2], &OutputFormat::Text, &limits).is_ok());
+    }
+
+    fn input_file(path: &str, content: &str) -> InputFile {
+        InputFile {
+            path: path.to_string(),
+            content: content.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_files_query() {
+        let req = FilesApiRequest {
+            query: ".h1".to_string(),
+            input_format: None,
+            output_format: None,
+            files: vec![
+                input_file("docs/a.md", "# A\n\ntext"),
+                input_file("docs/b.html", "<h1>B</h1><p>text</p>"),
+                input_file("docs/image.png", "binary"),
+                InputFile {
+                    path: "docs/c.md".to_string(),
+                    content: vec![0xff, 0xfe],
+                },
+            ],
+        };
+
+        let resp = files_query(req, &ExecutionLimits::default()).unwrap();
+        assert_eq!(resp.files["docs/a.md"].results, vec!["# A\n"]);
+        assert_eq!(resp.files["docs/b.html"].results, vec!["# B\n"]);
+        assert!(resp.files["docs/c.md"].error.is_some());
+        assert_eq!(resp.skipped, vec!["docs/image.png"]);
+    }
+
+    #[test]
+    fn test_files_query_duplicate_path() {
+        let req = FilesApiRequest {
+            query: ".h1".to_string(),
+            input_format: Some(InputFormat::Markdown),
+            output_format: None,
+            files: vec![input_file("a.md", "# A"), input_file("a.md", "# B")],
+        };
+        assert!(files_query(req, &ExecutionLimits::default()).is_err());
+    }
+
+    #[rstest]
+    #[case("docs/a.md", Some(InputFormat::Markdown))]
+    #[case("A.MDX", Some(InputFormat::Mdx))]
+    #[case("page.htm", Some(InputFormat::Html))]
+    #[case("data.yml", Some(InputFormat::Yaml))]
+    #[case("image.png", None)]
+    #[case("Makefile", None)]
+    fn test_input_format_from_path(#[case] path: &str, #[case] expected: Option<InputFormat>) {
+        assert_eq!(InputFormat::from_path(path), expected);
+    }
+
+    #[test]
+    fn test_stream_query_invalid_query() {
+        let req = ApiRequest {
+            query: "invalid query".to_string(),
+            input: Some("# A".to_string()),
+            input_format: None,
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        };
+        assert!(stream(req).is_err());
+    }
+
     #[test]
     fn test_check_valid_query() {
         let req = CheckApiRequest {
@@ -870,6 +1356,32 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[rstest]
+    #[case::simple(
+        "<h1>Title</h1><p>Hello <strong>world</strong></p>",
+        None,
+        "# Title\n\nHello **world**"
+    )]
+    #[case::title_as_h1(
+        "<html><head><title>Page</title></head><body><p>text</p></body></html>",
+        Some(true),
+        "# Page\n\ntext"
+    )]
+    fn test_convert_html_to_markdown(
+        #[case] html: &str,
+        #[case] use_title_as_h1: Option<bool>,
+        #[case] expected: &str,
+    ) {
+        let req = ConvertApiRequest {
+            html: html.to_string(),
+            extract_scripts_as_code_blocks: None,
+            generate_front_matter: None,
+            use_title_as_h1,
+        };
+        let result = convert_html_to_markdown(req).unwrap();
+        assert_eq!(result.markdown.trim_end(), expected);
+    }
+
     #[test]
     fn test_list_functions() {
         let resp = list_functions();
@@ -0,0 +1,252 @@
+//! Optional API key authentication with per-key quotas.
+//!
+//! Keys are configured with the `API_KEYS` environment variable as a
+//! comma-separated list of `name:key` or `name:key:requests_per_window`
+//! entries. A request authenticates by sending its key in the `X-API-Key`
+//! header or as `Authorization: Bearer <key>`; it is then rate limited by the
+//! key's quota instead of the per-IP limit. With no keys configured, API key
+//! authentication is disabled entirely.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use utoipa::ToSchema;
+
+use crate::rate_limiter::{RateLimitConfig, RateLimitError, RateLimiter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiKeyDefinition {
+    /// Name the key is reported under in logs and usage responses.
+    pub name: String,
+    pub key: String,
+    /// Requests allowed per rate limit window; `None` uses the global limit.
+    pub requests_per_window: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ApiKeyConfig {
+    pub keys: Vec<ApiKeyDefinition>,
+    /// When true, requests without a valid key are rejected. Otherwise they
+    /// fall back to the per-IP rate limit.
+    pub required: bool,
+}
+
+impl ApiKeyConfig {
+    /// Parses the `API_KEYS` format: `name:key[:requests_per_window]`, comma-separated.
+    pub fn parse_keys(value: &str) -> Result<Vec<ApiKeyDefinition>, String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let name = parts.next().unwrap_or_default();
+                let key = parts.next().unwrap_or_default();
+                if name.is_empty() || key.is_empty() {
+                    return Err(format!("Invalid API key entry '{}', expected name:key", name));
+                }
+                let requests_per_window = parts
+                    .next()
+                    .map(|limit| {
+                        limit
+                            .parse::<i64>()
+                            .ok()
+                            .filter(|limit| *limit > 0)
+                            .ok_or_else(|| format!("Invalid request limit '{}' for API key '{}'", limit, name))
+                    })
+                    .transpose()?;
+
+                Ok(ApiKeyDefinition {
+                    name: name.to_string(),
+                    key: key.to_string(),
+                    requests_per_window,
+                })
+            })
+            .collect()
+    }
+}
+
+/// The API key a request authenticated with, stored in the request's extensions.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedKey(pub Arc<ApiKey>);
+
+#[derive(Debug)]
+pub struct ApiKey {
+    pub name: String,
+    limiter: RateLimiter,
+    total_requests: AtomicU64,
+}
+
+impl ApiKey {
+    /// Counts a request against the key's quota.
+    pub async fn check_and_increment(&self) -> Result<(), RateLimitError> {
+        self.total_requests.fetch_add(1, Ordering::Relaxed);
+        self.limiter.check_and_increment(&self.name).await
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    pub async fn usage(&self) -> ApiKeyUsageResponse {
+        ApiKeyUsageResponse {
+            name: self.name.clone(),
+            requests_in_window: self
+                .limiter
+                .get_current_usage(&self.name)
+                .await
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+            requests_per_window: self.limiter.requests_per_window(),
+            window_size_seconds: self.limiter.window_size_seconds(),
+            total_requests: self.total_requests.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Response body for `GET /api/v1/usage`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyUsageResponse {
+    pub name: String,
+    /// Requests made in the current rate limit window.
+    pub requests_in_window: i64,
+    pub requests_per_window: i64,
+    pub window_size_seconds: i64,
+    /// Requests made since the server started.
+    pub total_requests: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct ApiKeyStore {
+    /// Keys are stored as SHA-256 digests so lookups compare fixed-length
+    /// values in constant time.
+    keys: Vec<([u8; 32], Arc<ApiKey>)>,
+    required: bool,
+}
+
+impl ApiKeyStore {
+    pub fn new(config: &ApiKeyConfig, rate_limit: &RateLimitConfig) -> Self {
+        let keys = config
+            .keys
+            .iter()
+            .map(|definition| {
+                let limiter = RateLimiter::new(RateLimitConfig {
+                    requests_per_window: definition.requests_per_window.unwrap_or(rate_limit.requests_per_window),
+                    ..rate_limit.clone()
+                });
+                let key = ApiKey {
+                    name: definition.name.clone(),
+                    limiter,
+                    total_requests: AtomicU64::new(0),
+                };
+                (digest(&definition.key), Arc::new(key))
+            })
+            .collect();
+
+        Self {
+            keys,
+            required: config.required,
+        }
+    }
+
+    /// Returns `true` if any API key is configured.
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// Returns `true` if requests without a valid key must be rejected.
+    pub fn is_required(&self) -> bool {
+        self.is_enabled() && self.required
+    }
+
+    /// Finds the key matching `key`. Every stored key is compared, so the time
+    /// taken doesn't reveal how much of a key matched.
+    pub fn find(&self, key: &str) -> Option<Arc<ApiKey>> {
+        let presented = digest(key);
+        self.keys.iter().fold(None, |found, (stored, api_key)| {
+            if bool::from(stored.ct_eq(&presented)) {
+                Some(Arc::clone(api_key))
+            } else {
+                found
+            }
+        })
+    }
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn definition(name: &str, key: &str, requests_per_window: Option<i64>) -> ApiKeyDefinition {
+        ApiKeyDefinition {
+            name: name.to_string(),
+            key: key.to_string(),
+            requests_per_window,
+        }
+    }
+
+    #[rstest]
+    #[case("ci:secret", vec![definition("ci", "secret", None)])]
+    #[case("ci:secret:500, docs:other", vec![definition("ci", "secret", Some(500)), definition("docs", "other", None)])]
+    #[case("", vec![])]
+    fn test_parse_keys(#[case] value: &str, #[case] expected: Vec<ApiKeyDefinition>) {
+        assert_eq!(ApiKeyConfig::parse_keys(value), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("ci")]
+    #[case(":secret")]
+    #[case("ci:secret:many")]
+    #[case("ci:secret:0")]
+    #[case("ci:secret:-1")]
+    fn test_parse_keys_invalid(#[case] value: &str) {
+        assert!(ApiKeyConfig::parse_keys(value).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_per_key_quota_and_usage() {
+        let config = ApiKeyConfig {
+            keys: vec![definition("ci", "secret", Some(2))],
+            required: true,
+        };
+        let store = ApiKeyStore::new(&config, &RateLimitConfig::default());
+        assert!(store.is_required());
+        assert!(store.find("wrong").is_none());
+
+        let key = store.find("secret").unwrap();
+        key.check_and_increment().await.unwrap();
+        key.check_and_increment().await.unwrap();
+        assert!(matches!(
+            key.check_and_increment().await,
+            Err(RateLimitError::LimitExceeded { requests: 3, limit: 2 })
+        ));
+
+        let usage = key.usage().await;
+        assert_eq!(usage.name, "ci");
+        assert_eq!(usage.requests_in_window, 3);
+        assert_eq!(usage.requests_per_window, 2);
+        assert_eq!(usage.total_requests, 3);
+    }
+
+    #[test]
+    fn test_disabled_without_keys() {
+        let store = ApiKeyStore::new(
+            &ApiKeyConfig {
+                keys: vec![],
+                required: true,
+            },
+            &RateLimitConfig::default(),
+        );
+        assert!(!store.is_enabled());
+        assert!(!store.is_required());
+    }
+}
@@ -0,0 +1,255 @@
+//! Zip archive handling for `POST /api/v1/query/files`.
+//!
+//! Uploaded `.zip` files are expanded into their contained files, and results
+//! can be returned as a zip archive mirroring the uploaded paths.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Cursor, Read, Write};
+
+use axum::http::StatusCode;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::api::{FilesApiResponse, InputFile, MAX_BATCH_SIZE, OutputFormat};
+use crate::problem::ProblemDetails;
+
+/// Maximum total size of the files extracted from uploaded archives.
+pub const MAX_EXTRACTED_BYTES: usize = 32 * 1024 * 1024;
+
+/// Name of the archive entry listing the files that failed.
+pub const ERRORS_FILE_NAME: &str = "_errors.json";
+
+/// A file part of a multipart upload.
+#[derive(Debug, Clone)]
+pub struct Upload {
+    pub file_name: String,
+    pub content_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl Upload {
+    fn is_zip(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.contains("zip"))
+            || self.file_name.to_ascii_lowercase().ends_with(".zip")
+    }
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum ArchiveError {
+    #[error("Invalid zip archive '{0}': {1}")]
+    Invalid(String, String),
+    #[error("Archive entry '{0}' has an unsafe path")]
+    UnsafePath(String),
+    #[error("Archives contain more than {0} files")]
+    TooManyFiles(usize),
+    #[error("Extracted files exceed maximum of {0} bytes")]
+    TooLarge(usize),
+}
+
+impl ArchiveError {
+    pub fn to_problem(&self) -> ProblemDetails {
+        let status = match self {
+            ArchiveError::TooManyFiles(_) | ArchiveError::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ArchiveError::Invalid(..) | ArchiveError::UnsafePath(_) => StatusCode::BAD_REQUEST,
+        };
+        ProblemDetails::new(status)
+            .with_title("Invalid archive")
+            .with_detail("error", &self.to_string())
+    }
+}
+
+/// Turns uploads into input files, expanding zip archives into their entries.
+pub fn collect_files(uploads: Vec<Upload>) -> Result<Vec<InputFile>, ArchiveError> {
+    let mut files = Vec::new();
+    let mut extracted_bytes = 0;
+
+    for upload in uploads {
+        if !upload.is_zip() {
+            files.push(InputFile {
+                path: upload.file_name,
+                content: upload.data,
+            });
+            continue;
+        }
+
+        let mut archive = ZipArchive::new(Cursor::new(upload.data))
+            .map_err(|e| ArchiveError::Invalid(upload.file_name.clone(), e.to_string()))?;
+        for index in 0..archive.len() {
+            let entry = archive
+                .by_index(index)
+                .map_err(|e| ArchiveError::Invalid(upload.file_name.clone(), e.to_string()))?;
+            if entry.is_dir() || entry.name().starts_with("__MACOSX/") {
+                continue;
+            }
+            let path = entry
+                .enclosed_name()
+                .and_then(|path| path.to_str().map(|path| path.replace('\\', "/")))
+                .ok_or_else(|| ArchiveError::UnsafePath(entry.name().to_string()))?;
+
+            if files.len() >= MAX_BATCH_SIZE {
+                return Err(ArchiveError::TooManyFiles(MAX_BATCH_SIZE));
+            }
+
+            // The declared size can't be trusted, so reading stops one byte past the budget.
+            let remaining = MAX_EXTRACTED_BYTES - extracted_bytes;
+            let mut content = Vec::new();
+            entry
+                .take(remaining as u64 + 1)
+                .read_to_end(&mut content)
+                .map_err(|e| ArchiveError::Invalid(upload.file_name.clone(), e.to_string()))?;
+            if content.len() > remaining {
+                return Err(ArchiveError::TooLarge(MAX_EXTRACTED_BYTES));
+            }
+            extracted_bytes += content.len();
+
+            files.push(InputFile { path, content });
+        }
+    }
+
+    Ok(files)
+}
+
+/// Packs each file's results into a zip archive at the file's path, with the
+/// extension of `output_format`. Failed files are listed in [`ERRORS_FILE_NAME`].
+pub fn write_archive(response: &FilesApiResponse, output_format: &OutputFormat) -> zip::result::ZipResult<Vec<u8>> {
+    let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    let mut names = HashSet::new();
+    let mut errors = BTreeMap::new();
+
+    for (path, item) in &response.files {
+        if let Some(error) = &item.error {
+            errors.insert(path.as_str(), error.as_str());
+            continue;
+        }
+
+        let name = match output_format.extension() {
+            Some(extension) => {
+                let renamed = std::path::Path::new(path)
+                    .with_extension(extension)
+                    .to_string_lossy()
+                    .into_owned();
+                // `a.md` and `a.mdx` would both become `a.html`.
+                if names.contains(&renamed) {
+                    format!("{}.{}", path, extension)
+                } else {
+                    renamed
+                }
+            }
+            None => path.clone(),
+        };
+        writer.start_file(name.as_str(), options)?;
+        writer.write_all(item.results.join("\n").as_bytes())?;
+        names.insert(name);
+    }
+
+    if !errors.is_empty() {
+        writer.start_file(ERRORS_FILE_NAME, options)?;
+        writer.write_all(serde_json::to_string_pretty(&errors).unwrap_or_default().as_bytes())?;
+    }
+
+    Ok(writer.finish()?.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::BatchItemResult;
+
+    fn zip_upload(entries: &[(&str, &str)]) -> Upload {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            writer.start_file(*name, SimpleFileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        Upload {
+            file_name: "docs.zip".to_string(),
+            content_type: None,
+            data: writer.finish().unwrap().into_inner(),
+        }
+    }
+
+    fn read_entries(data: Vec<u8>) -> BTreeMap<String, String> {
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        (0..archive.len())
+            .map(|index| {
+                let mut entry = archive.by_index(index).unwrap();
+                let mut content = String::new();
+                entry.read_to_string(&mut content).unwrap();
+                (entry.name().to_string(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_collect_files_expands_archives() {
+        let uploads = vec![
+            Upload {
+                file_name: "README.md".to_string(),
+                content_type: Some("text/markdown".to_string()),
+                data: b"# Readme".to_vec(),
+            },
+            zip_upload(&[("docs/a.md", "# A"), ("__MACOSX/docs/._a.md", ""), ("docs/b.md", "# B")]),
+        ];
+
+        let paths: Vec<String> = collect_files(uploads)
+            .unwrap()
+            .into_iter()
+            .map(|file| file.path)
+            .collect();
+        assert_eq!(paths, vec!["README.md", "docs/a.md", "docs/b.md"]);
+    }
+
+    #[test]
+    fn test_collect_files_rejects_unsafe_paths() {
+        let result = collect_files(vec![zip_upload(&[("../escape.md", "# A")])]);
+        assert_eq!(
+            result.unwrap_err(),
+            ArchiveError::UnsafePath("../escape.md".to_string())
+        );
+    }
+
+    #[test]
+    fn test_collect_files_rejects_invalid_archive() {
+        let result = collect_files(vec![Upload {
+            file_name: "docs.zip".to_string(),
+            content_type: None,
+            data: b"not a zip".to_vec(),
+        }]);
+        assert!(matches!(result, Err(ArchiveError::Invalid(..))));
+    }
+
+    #[test]
+    fn test_write_archive() {
+        let mut files = BTreeMap::new();
+        files.insert(
+            "a.md".to_string(),
+            BatchItemResult {
+                results: vec!["<h1>A</h1>".to_string()],
+                error: None,
+            },
+        );
+        files.insert(
+            "a.mdx".to_string(),
+            BatchItemResult {
+                results: vec!["<h1>B</h1>".to_string()],
+                error: None,
+            },
+        );
+        files.insert(
+            "b.md".to_string(),
+            BatchItemResult {
+                results: vec![],
+                error: Some("failed".to_string()),
+            },
+        );
+        let response = FilesApiResponse { files, skipped: vec![] };
+
+        let entries = read_entries(write_archive(&response, &OutputFormat::Html).unwrap());
+        assert_eq!(entries["a.html"], "<h1>A</h1>");
+        assert_eq!(entries["a.mdx.html"], "<h1>B</h1>");
+        assert!(entries[ERRORS_FILE_NAME].contains("failed"));
+        assert_eq!(entries.len(), 3);
+    }
+}
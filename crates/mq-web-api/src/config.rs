@@ -1,3 +1,6 @@
+use crate::api_key::ApiKeyConfig;
+use crate::jobs::JobQueueConfig;
+use crate::limits::ExecutionLimits;
 use crate::query_cache::QueryCacheConfig;
 use crate::rate_limiter::RateLimitConfig;
 use std::{env, time::Duration};
@@ -10,14 +13,29 @@ pub struct Config {
     pub log_format: LogFormat,
     pub cors_origins: Vec<String>,
     pub rate_limit: RateLimitConfig,
+    /// API keys and whether one is required; see [`crate::api_key`].
+    pub api_keys: ApiKeyConfig,
     /// OTLP exporter endpoint (e.g. `http://localhost:4317`). Requires the `otel` feature.
     pub otel_endpoint: Option<String>,
     /// Service name reported to the OpenTelemetry collector.
     pub otel_service_name: String,
-    /// Maximum duration a single query evaluation may run before it's aborted.
-    pub query_timeout: Duration,
+    /// Maximum size of a request body in bytes (`/api/v1/jobs` has its own limit).
+    pub max_body_bytes: usize,
+    /// Limits applied to every query evaluation.
+    pub limits: ExecutionLimits,
     /// Short-lived cache for repeated `{query, input, input_format, args}` combinations.
     pub query_cache: QueryCacheConfig,
+    /// Maximum number of batch documents evaluated at the same time, across all requests.
+    pub batch_concurrency: usize,
+    /// Limits of the background queue behind `/api/v1/jobs`.
+    pub jobs: JobQueueConfig,
+}
+
+/// A configuration the server refuses to start with.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Invalid API_KEYS value: {0}")]
+    InvalidApiKeys(String),
 }
 
 #[derive(Debug, Clone)]
@@ -35,16 +53,20 @@ impl Default for Config {
             log_format: LogFormat::Json,
             cors_origins: vec!["*".to_string()],
             rate_limit: RateLimitConfig::default(),
+            api_keys: ApiKeyConfig::default(),
             otel_endpoint: None,
             otel_service_name: "mq-web-api".to_string(),
-            query_timeout: Duration::from_secs(10),
+            max_body_bytes: 2 * 1024 * 1024,
+            limits: ExecutionLimits::default(),
             query_cache: QueryCacheConfig::default(),
+            batch_concurrency: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            jobs: JobQueueConfig::default(),
         }
     }
 }
 
 impl Config {
-    pub fn from_env() -> Self {
+    pub fn from_env() -> Result<Self, ConfigError> {
         let mut config = Self::default();
 
         if let Ok(host) = env::var("HOST") {
@@ -122,6 +144,20 @@ impl Config {
             }
         }
 
+        if let Ok(api_keys) = env::var("API_KEYS") {
+            config.set_api_keys(&api_keys)?;
+        }
+
+        if let Ok(required_str) = env::var("API_KEY_REQUIRED") {
+            match required_str.parse::<bool>() {
+                Ok(required) => config.api_keys.required = required,
+                Err(_) => eprintln!(
+                    "Warning: Invalid API_KEY_REQUIRED value '{}', using default {}",
+                    required_str, config.api_keys.required
+                ),
+            }
+        }
+
         if let Ok(endpoint) = env::var("OTEL_EXPORTER_OTLP_ENDPOINT")
             && !endpoint.is_empty()
         {
@@ -136,11 +172,54 @@ impl Config {
 
         if let Ok(timeout_str) = env::var("QUERY_TIMEOUT_SECONDS") {
             if let Ok(timeout) = timeout_str.parse::<u64>() {
-                config.query_timeout = Duration::from_secs(timeout);
+                config.limits.timeout = Duration::from_secs(timeout);
             } else {
                 eprintln!(
                     "Warning: Invalid QUERY_TIMEOUT_SECONDS value '{}', using default {:?}",
-                    timeout_str, config.query_timeout
+                    timeout_str, config.limits.timeout
+                );
+            }
+        }
+
+        if let Ok(max_body_str) = env::var("MAX_BODY_BYTES") {
+            if let Ok(max_body_bytes) = max_body_str.parse::<usize>() {
+                config.max_body_bytes = max_body_bytes;
+            } else {
+                eprintln!(
+                    "Warning: Invalid MAX_BODY_BYTES value '{}', using default {}",
+                    max_body_str, config.max_body_bytes
+                );
+            }
+        }
+
+        if let Ok(max_query_str) = env::var("MAX_QUERY_LENGTH") {
+            if let Ok(max_query_length) = max_query_str.parse::<usize>() {
+                config.limits.max_query_length = max_query_length;
+            } else {
+                eprintln!(
+                    "Warning: Invalid MAX_QUERY_LENGTH value '{}', using default {}",
+                    max_query_str, config.limits.max_query_length
+                );
+            }
+        }
+
+        if let Ok(depth_str) = env::var("MAX_CALL_STACK_DEPTH") {
+            match depth_str.parse::<u32>() {
+                Ok(depth) if depth > 0 => config.limits.max_call_stack_depth = Some(depth),
+                _ => eprintln!(
+                    "Warning: Invalid MAX_CALL_STACK_DEPTH value '{}', using the engine default",
+                    depth_str
+                ),
+            }
+        }
+
+        if let Ok(max_result_str) = env::var("MAX_RESULT_BYTES") {
+            if let Ok(max_result_bytes) = max_result_str.parse::<usize>() {
+                config.limits.max_result_bytes = max_result_bytes;
+            } else {
+                eprintln!(
+                    "Warning: Invalid MAX_RESULT_BYTES value '{}', using default {}",
+                    max_result_str, config.limits.max_result_bytes
                 );
             }
         }
@@ -177,7 +256,82 @@ impl Config {
             }
         }
 
-        config
+        if let Ok(concurrency_str) = env::var("BATCH_CONCURRENCY") {
+            match concurrency_str.parse::<usize>() {
+                Ok(concurrency) if concurrency > 0 => config.batch_concurrency = concurrency,
+                _ => eprintln!(
+                    "Warning: Invalid BATCH_CONCURRENCY value '{}', using default {}",
+                    concurrency_str, config.batch_concurrency
+                ),
+            }
+        }
+
+        if let Ok(workers_str) = env::var("JOB_WORKERS") {
+            match workers_str.parse::<usize>() {
+                Ok(workers) if workers > 0 => config.jobs.workers = workers,
+                _ => eprintln!(
+                    "Warning: Invalid JOB_WORKERS value '{}', using default {}",
+                    workers_str, config.jobs.workers
+                ),
+            }
+        }
+
+        if let Ok(max_pending_str) = env::var("JOB_MAX_PENDING") {
+            if let Ok(max_pending) = max_pending_str.parse::<usize>() {
+                config.jobs.max_pending = max_pending;
+            } else {
+                eprintln!(
+                    "Warning: Invalid JOB_MAX_PENDING value '{}', using default {}",
+                    max_pending_str, config.jobs.max_pending
+                );
+            }
+        }
+
+        if let Ok(max_input_str) = env::var("JOB_MAX_INPUT_BYTES") {
+            if let Ok(max_input_bytes) = max_input_str.parse::<usize>() {
+                config.jobs.max_input_bytes = max_input_bytes;
+            } else {
+                eprintln!(
+                    "Warning: Invalid JOB_MAX_INPUT_BYTES value '{}', using default {}",
+                    max_input_str, config.jobs.max_input_bytes
+                );
+            }
+        }
+
+        if let Ok(timeout_str) = env::var("JOB_TIMEOUT_SECONDS") {
+            if let Ok(timeout) = timeout_str.parse::<u64>() {
+                config.jobs.job_timeout = Duration::from_secs(timeout);
+            } else {
+                eprintln!(
+                    "Warning: Invalid JOB_TIMEOUT_SECONDS value '{}', using default {:?}",
+                    timeout_str, config.jobs.job_timeout
+                );
+            }
+        }
+
+        if let Ok(ttl_str) = env::var("JOB_RESULT_TTL_SECONDS") {
+            if let Ok(ttl) = ttl_str.parse::<u64>() {
+                config.jobs.result_ttl = Duration::from_secs(ttl);
+            } else {
+                eprintln!(
+                    "Warning: Invalid JOB_RESULT_TTL_SECONDS value '{}', using default {:?}",
+                    ttl_str, config.jobs.result_ttl
+                );
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Applies an `API_KEYS` value. Unlike other settings, an invalid value is an error, since
+    /// ignoring it would turn authentication off.
+    fn set_api_keys(&mut self, value: &str) -> Result<(), ConfigError> {
+        let keys = ApiKeyConfig::parse_keys(value).map_err(ConfigError::InvalidApiKeys)?;
+        // Keys are configured to restrict access, so anonymous
+        // requests are rejected unless explicitly allowed.
+        self.api_keys.required = !keys.is_empty();
+        self.api_keys.keys = keys;
+        Ok(())
     }
 
     pub fn bind_address(&self) -> String {
@@ -232,4 +386,19 @@ mod tests {
         };
         assert_eq!(config_443.server_url(), "https://example.com");
     }
+
+    #[test]
+    fn test_set_api_keys() {
+        let mut config = Config::default();
+        config.set_api_keys("ci:secret").unwrap();
+        assert!(config.api_keys.required);
+        assert_eq!(config.api_keys.keys.len(), 1);
+    }
+
+    #[test]
+    fn test_set_api_keys_invalid() {
+        let mut config = Config::default();
+        assert!(matches!(config.set_api_keys("ci"), Err(ConfigError::InvalidApiKeys(_))));
+        assert!(config.api_keys.keys.is_empty());
+    }
 }
@@ -1,32 +1,45 @@
 use axum::{
     extract::rejection::QueryRejection,
-    extract::{FromRequestParts, Path, Query, State},
-    http::{StatusCode, request::Parts},
-    response::Json,
+    extract::{Extension, FromRequestParts, Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode, header, request::Parts},
+    response::{
+        IntoResponse, Json, Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
+use futures::Stream;
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::sync::Arc;
 use std::sync::OnceLock;
-use std::time::Duration;
 use tracing::{debug, error, info};
 use utoipa::{OpenApi, ToSchema};
 
 use crate::{
     api::{
-        ApiRequest, BatchApiRequest, BatchApiResponse, BatchItemResult, CheckApiRequest, CheckApiResponse, CheckError,
+        ApiRequest, BatchApiRequest, BatchApiResponse, BatchItemRequest, BatchItemResult, CheckApiRequest,
+        CheckApiResponse, CheckError, ConvertApiRequest, ConvertApiResponse, FilesApiRequest, FilesApiResponse,
         FormatApiRequest, FormatApiResponse, FunctionDoc, FunctionsApiResponse, InputFormat, LintApiRequest,
         LintApiResponse, LintDiagnostic, OutputFormat, QueryApiResponse, SelectorDoc, SelectorsApiResponse,
     },
+    api_key::{ApiKeyUsageResponse, AuthenticatedKey},
+    archive::Upload,
+    jobs::{JobApiResponse, JobError, JobQueue, JobStatus},
+    limits::{ExecutionLimits, LimitExceeded},
     problem::ProblemDetails,
     query_cache::{self, QueryCache},
 };
 
 #[derive(Clone)]
 pub struct AppState {
-    /// Maximum duration a single query evaluation may run before it's aborted.
-    pub query_timeout: Duration,
+    /// Limits applied to every query evaluation.
+    pub limits: ExecutionLimits,
     /// Short-lived cache of `execute_query` results.
     pub query_cache: Arc<QueryCache>,
+    /// Thread pool that runs batch documents, bounding how many are evaluated at once.
+    pub batch_pool: Arc<rayon::ThreadPool>,
+    /// Background queue behind `/api/v1/jobs`.
+    pub job_queue: Arc<JobQueue>,
 }
 
 /// Returns a cached response for `request` if one is fresh, otherwise runs
@@ -49,8 +62,8 @@ async fn execute_query_with_cache(
         return Ok(Ok(cached));
     }
 
-    let timeout = state.query_timeout;
-    let result = tokio::task::spawn_blocking(move || crate::api::query(request, timeout)).await?;
+    let limits = state.limits.clone();
+    let result = tokio::task::spawn_blocking(move || crate::api::query(request, &limits)).await?;
 
     if let (Ok(response), Some(key)) = (&result, cache_key) {
         state.query_cache.insert(key, response.clone()).await;
@@ -59,6 +72,17 @@ async fn execute_query_with_cache(
     Ok(result)
 }
 
+/// Maps a failed query to a problem response: an exceeded execution limit
+/// keeps its own status, anything else is a 400 with `title`.
+fn query_problem(e: &miette::Report, title: &str) -> ProblemDetails {
+    match e.downcast_ref::<LimitExceeded>() {
+        Some(limit) => limit.to_problem(),
+        None => ProblemDetails::new(StatusCode::BAD_REQUEST)
+            .with_title(title)
+            .with_detail("error", &e.to_string()),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct QueryParams {
     pub query: String,
@@ -113,13 +137,20 @@ pub async fn health_check() -> Json<HealthResponse> {
     paths(
         get_query_api,
         post_query_api,
+        post_stream_query_api,
         post_shorthand_query_api,
         post_batch_api,
+        post_files_query_api,
+        post_job_api,
+        get_job_api,
+        get_job_result_api,
         post_check_api,
         post_format_api,
+        post_convert_html_to_markdown_api,
         get_functions_api,
         get_selectors_api,
         post_lint_api,
+        get_usage_api,
         openapi_json
     ),
     components(
@@ -128,13 +159,19 @@ pub async fn health_check() -> Json<HealthResponse> {
         schemas(OutputFormat),
         schemas(QueryApiResponse),
         schemas(BatchApiRequest),
+        schemas(BatchItemRequest),
         schemas(BatchApiResponse),
         schemas(BatchItemResult),
+        schemas(FilesApiResponse),
+        schemas(JobApiResponse),
+        schemas(JobStatus),
         schemas(CheckApiRequest),
         schemas(CheckApiResponse),
         schemas(CheckError),
         schemas(FormatApiRequest),
         schemas(FormatApiResponse),
+        schemas(ConvertApiRequest),
+        schemas(ConvertApiResponse),
         schemas(FunctionDoc),
         schemas(FunctionsApiResponse),
         schemas(SelectorDoc),
@@ -142,6 +179,7 @@ pub async fn health_check() -> Json<HealthResponse> {
         schemas(LintApiRequest),
         schemas(LintApiResponse),
         schemas(LintDiagnostic),
+        schemas(ApiKeyUsageResponse),
     ),
     tags(
         (name = "mq-api", description = "Markdown Query API")
@@ -155,6 +193,8 @@ pub struct ApiDoc;
     responses(
         (status = 200, description = "Query executed successfully", body = QueryApiResponse),
         (status = 400, description = "Invalid request parameters"),
+        (status = 413, description = "Query exceeds the maximum query length"),
+        (status = 422, description = "Evaluation exceeded the timeout, call stack depth, or result size limit"),
     ),
     params(
         ("query" = String, Query, description = "mq query string to execute"),
@@ -201,9 +241,7 @@ pub async fn get_query_api(
         }
         Err(e) => {
             error!("Failed to process query '{}': {}", query_str, e);
-            Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
-                .with_title("Invalid query")
-                .with_detail("error", &e.to_string()))
+            Err(query_problem(&e, "Invalid query"))
         }
     }
 }
@@ -214,6 +252,8 @@ pub async fn get_query_api(
     responses(
         (status = 200, description = "Query executed successfully", body = QueryApiResponse),
         (status = 400, description = "Invalid request parameters"),
+        (status = 413, description = "Query exceeds the maximum query length"),
+        (status = 422, description = "Evaluation exceeded the timeout, call stack depth, or result size limit"),
     ),
     request_body = ApiRequest
 )]
@@ -241,19 +281,63 @@ pub async fn post_query_api(
         }
         Err(e) => {
             error!("Failed to process query '{}': {}", query_str, e);
-            Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
-                .with_title("Invalid query")
-                .with_detail("error", &e.to_string()))
+            Err(query_problem(&e, "Invalid query"))
         }
     }
 }
 
+/// Number of rendered results buffered between the evaluating thread and the
+/// SSE response before evaluation waits for the client to catch up.
+const STREAM_BUFFER_SIZE: usize = 16;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/query/stream",
+    responses(
+        (status = 200, description = "Server-sent events: a `result` event per result as it's produced, then `done` with the number of results, or `error` if the query fails", content_type = "text/event-stream", body = String),
+    ),
+    request_body = ApiRequest
+)]
+pub async fn post_stream_query_api(
+    State(state): State<AppState>,
+    Json(request): Json<ApiRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    debug!("POST /query/stream called with query: {}", request.query);
+
+    let (tx, rx) = tokio::sync::mpsc::channel(STREAM_BUFFER_SIZE);
+    let limits = state.limits.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let query_str = request.query.clone();
+        let mut count = 0usize;
+        // A failed send means the client went away, so evaluation stops there.
+        let result = crate::api::stream_query(request, &limits, |result| {
+            count += 1;
+            tx.blocking_send(Event::default().event("result").data(result)).is_ok()
+        });
+        let last_event = match result {
+            Ok(()) => {
+                info!("Successfully streamed query: {}, results count: {}", query_str, count);
+                Event::default().event("done").data(count.to_string())
+            }
+            Err(e) => {
+                error!("Failed to stream query '{}': {}", query_str, e);
+                Event::default().event("error").data(e.to_string())
+            }
+        };
+        let _ = tx.blocking_send(last_event);
+    });
+
+    let stream = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|event| (Ok(event), rx)) });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
 #[utoipa::path(
     post,
     path = "/api/v1/batch",
     responses(
         (status = 200, description = "Batch processed (see per-item `error` fields for per-document failures)", body = BatchApiResponse),
-        (status = 400, description = "Invalid request parameters, or `inputs` exceeds the batch size limit"),
+        (status = 400, description = "Invalid request parameters, or `inputs` and `items` exceed the batch size limit"),
     ),
     request_body = BatchApiRequest
 )]
@@ -262,14 +346,15 @@ pub async fn post_batch_api(
     Json(request): Json<BatchApiRequest>,
 ) -> Result<Json<BatchApiResponse>, ProblemDetails> {
     debug!(
-        "POST /batch called with query: {}, {} documents",
+        "POST /batch called with query: {:?}, {} documents",
         request.query,
-        request.inputs.len()
+        request.inputs.len() + request.items.len()
     );
 
-    let query_str = request.query.clone();
-    let timeout = state.query_timeout;
-    match tokio::task::spawn_blocking(move || crate::api::batch_query(request, timeout))
+    let query_str = request.query.clone().unwrap_or_default();
+    let limits = state.limits.clone();
+    let batch_pool = Arc::clone(&state.batch_pool);
+    match tokio::task::spawn_blocking(move || batch_pool.install(|| crate::api::batch_query(request, &limits)))
         .await
         .map_err(|e| {
             error!("Batch task panicked: {}", e);
@@ -294,6 +379,222 @@ pub async fn post_batch_api(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/query/files",
+    request_body(content = String, content_type = "multipart/form-data", description = "A `query` field, optional `input_format` and `output_format` fields, and one or more file parts. `.zip` file parts are expanded into the files they contain. Each file's format is inferred from its extension unless `input_format` is given."),
+    responses(
+        (status = 200, description = "Files processed (see per-file `error` fields for failures). With `Accept: application/zip`, a zip archive of the results at the uploaded paths instead", body = FilesApiResponse),
+        (status = 400, description = "Invalid multipart request or zip archive"),
+        (status = 413, description = "Upload exceeds the file count or size limits"),
+    )
+)]
+pub async fn post_files_query_api(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Response, ProblemDetails> {
+    debug!("POST /query/files called");
+
+    let mut query = None;
+    let mut input_format = None;
+    let mut output_format = None;
+    let mut uploads = Vec::new();
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        ProblemDetails::new(e.status())
+            .with_title("Invalid multipart request")
+            .with_detail("error", &e.body_text())
+    })? {
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        let data = field.bytes().await.map_err(|e| {
+            ProblemDetails::new(e.status())
+                .with_title("Invalid multipart request")
+                .with_detail("error", &e.body_text())
+        })?;
+
+        match (file_name, name.as_str()) {
+            (Some(file_name), _) => uploads.push(Upload {
+                file_name,
+                content_type,
+                data: data.to_vec(),
+            }),
+            (None, "query") => query = Some(String::from_utf8_lossy(&data).into_owned()),
+            (None, "input_format") => {
+                input_format =
+                    serde_json::from_str::<InputFormat>(&format!("\"{}\"", String::from_utf8_lossy(&data))).ok()
+            }
+            (None, "output_format") => {
+                output_format =
+                    serde_json::from_str::<OutputFormat>(&format!("\"{}\"", String::from_utf8_lossy(&data))).ok()
+            }
+            (None, _) => debug!("Ignoring unknown multipart field: {}", name),
+        }
+    }
+
+    let Some(query) = query else {
+        return Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+            .with_title("Invalid multipart request")
+            .with_detail("error", "The `query` field is required"));
+    };
+
+    let wants_zip = headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("application/zip"));
+    let rendered_format = output_format.clone().unwrap_or_default();
+    let query_str = query.clone();
+    let limits = state.limits.clone();
+    let batch_pool = Arc::clone(&state.batch_pool);
+
+    let response = tokio::task::spawn_blocking(move || {
+        let files = crate::archive::collect_files(uploads).map_err(|e| e.to_problem())?;
+        let request = FilesApiRequest {
+            query,
+            input_format,
+            output_format,
+            files,
+        };
+        batch_pool
+            .install(|| crate::api::files_query(request, &limits))
+            .map_err(|e| query_problem(&e, "Invalid files request"))
+    })
+    .await
+    .map_err(|e| {
+        error!("Files task panicked: {}", e);
+        ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .with_title("Internal error")
+            .with_detail("error", &e.to_string())
+    })?
+    .inspect_err(|_| error!("Failed to process files query '{}'", query_str))?;
+
+    info!(
+        "Successfully processed files query: {}, files: {}, skipped: {}",
+        query_str,
+        response.files.len(),
+        response.skipped.len()
+    );
+
+    if !wants_zip {
+        return Ok(Json(response).into_response());
+    }
+
+    let archive = crate::archive::write_archive(&response, &rendered_format).map_err(|e| {
+        error!("Failed to write result archive: {}", e);
+        ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR)
+            .with_title("Internal error")
+            .with_detail("error", &e.to_string())
+    })?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"results.zip\""),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs",
+    responses(
+        (status = 202, description = "Job queued; poll `GET /api/v1/jobs/{id}` for its status", body = JobApiResponse),
+        (status = 413, description = "`input` exceeds the job input size limit"),
+        (status = 503, description = "Too many pending jobs"),
+    ),
+    request_body = ApiRequest
+)]
+pub async fn post_job_api(
+    State(state): State<AppState>,
+    Json(request): Json<ApiRequest>,
+) -> Result<(StatusCode, Json<JobApiResponse>), ProblemDetails> {
+    debug!("POST /jobs called with query: {}", request.query);
+
+    match state.job_queue.submit(request).await {
+        Ok(id) => {
+            info!("Queued job {}", id);
+            Ok((
+                StatusCode::ACCEPTED,
+                Json(JobApiResponse {
+                    id,
+                    status: JobStatus::Queued,
+                    error: None,
+                }),
+            ))
+        }
+        Err(e) => {
+            error!("Failed to queue job: {}", e);
+            let status = match e {
+                JobError::InputTooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+                JobError::QueueFull(_) => StatusCode::SERVICE_UNAVAILABLE,
+            };
+            Err(ProblemDetails::new(status)
+                .with_title("Job rejected")
+                .with_detail("error", &e.to_string()))
+        }
+    }
+}
+
+fn job_not_found(id: &str) -> ProblemDetails {
+    ProblemDetails::new(StatusCode::NOT_FOUND)
+        .with_title("Job not found")
+        .with_detail("error", &format!("No job with id '{}', or its result has expired", id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}",
+    params(("id" = String, Path, description = "Job id returned by `POST /api/v1/jobs`")),
+    responses(
+        (status = 200, description = "Job status", body = JobApiResponse),
+        (status = 404, description = "Unknown or expired job"),
+    )
+)]
+pub async fn get_job_api(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<JobApiResponse>, ProblemDetails> {
+    debug!("GET /jobs/{} called", id);
+    state
+        .job_queue
+        .status(&id)
+        .await
+        .map(Json)
+        .ok_or_else(|| job_not_found(&id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}/result",
+    params(("id" = String, Path, description = "Job id returned by `POST /api/v1/jobs`")),
+    responses(
+        (status = 200, description = "Query results of the completed job", body = QueryApiResponse),
+        (status = 400, description = "The job failed"),
+        (status = 404, description = "Unknown or expired job"),
+        (status = 409, description = "The job has not finished yet"),
+    )
+)]
+pub async fn get_job_result_api(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<QueryApiResponse>, ProblemDetails> {
+    debug!("GET /jobs/{}/result called", id);
+
+    match state.job_queue.result(&id).await {
+        Some((_, Some(result))) => Ok(Json(result)),
+        Some((job, None)) if job.status == JobStatus::Failed => Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+            .with_title("Invalid query")
+            .with_detail("error", job.error.as_deref().unwrap_or_default())),
+        Some((job, None)) => Err(ProblemDetails::new(StatusCode::CONFLICT)
+            .with_title("Job not finished")
+            .with_detail("status", job.status.as_str())),
+        None => Err(job_not_found(&id)),
+    }
+}
+
 /// Guesses `body`'s format from its leading bytes, ignoring leading
 /// whitespace and a UTF-8 BOM. Used by the shorthand endpoint to auto-select
 /// `input_format` when the caller doesn't pass `?input_format=`.
@@ -333,6 +634,8 @@ fn sniff_input_format(body: &str) -> Option<InputFormat> {
     responses(
         (status = 200, description = "Query executed successfully", body = QueryApiResponse),
         (status = 400, description = "Invalid query or request"),
+        (status = 413, description = "Query exceeds the maximum query length"),
+        (status = 422, description = "Evaluation exceeded the timeout, call stack depth, or result size limit"),
     )
 )]
 pub async fn post_shorthand_query_api(
@@ -377,9 +680,7 @@ pub async fn post_shorthand_query_api(
         }
         Err(e) => {
             error!("Failed to process shorthand query '{}': {}", query, e);
-            Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
-                .with_title("Invalid query")
-                .with_detail("error", &e.to_string()))
+            Err(query_problem(&e, "Invalid query"))
         }
     }
 }
@@ -450,6 +751,42 @@ pub async fn post_format_api(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/convert/html-to-markdown",
+    responses(
+        (status = 200, description = "Conversion completed", body = ConvertApiResponse),
+        (status = 400, description = "Invalid HTML"),
+    ),
+    request_body = ConvertApiRequest
+)]
+pub async fn post_convert_html_to_markdown_api(
+    State(_state): State<AppState>,
+    Json(request): Json<ConvertApiRequest>,
+) -> Result<Json<ConvertApiResponse>, ProblemDetails> {
+    debug!("POST /convert/html-to-markdown called");
+
+    match tokio::task::spawn_blocking(move || crate::api::convert_html_to_markdown(request))
+        .await
+        .map_err(|e| {
+            error!("Conversion task panicked: {}", e);
+            ProblemDetails::new(StatusCode::INTERNAL_SERVER_ERROR)
+                .with_title("Internal error")
+                .with_detail("error", &e.to_string())
+        })? {
+        Ok(response) => {
+            info!("HTML to Markdown conversion completed successfully");
+            Ok(Json(response))
+        }
+        Err(e) => {
+            error!("HTML to Markdown conversion failed: {}", e);
+            Err(ProblemDetails::new(StatusCode::BAD_REQUEST)
+                .with_title("Conversion error")
+                .with_detail("error", &e.to_string()))
+        }
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/functions",
@@ -504,6 +841,26 @@ pub async fn post_lint_api(
     Json(response)
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/usage",
+    responses(
+        (status = 200, description = "Usage counters of the API key the request was made with", body = ApiKeyUsageResponse),
+        (status = 401, description = "The request was made without an API key"),
+    )
+)]
+pub async fn get_usage_api(
+    api_key: Option<Extension<AuthenticatedKey>>,
+) -> Result<Json<ApiKeyUsageResponse>, ProblemDetails> {
+    debug!("GET /usage called");
+    match api_key {
+        Some(Extension(AuthenticatedKey(api_key))) => Ok(Json(api_key.usage().await)),
+        None => Err(ProblemDetails::new(StatusCode::UNAUTHORIZED)
+            .with_title("Unauthorized")
+            .with_detail("error", "Usage is only tracked for requests made with an API key")),
+    }
+}
+
 static OPENAPI_SPEC: OnceLock<utoipa::openapi::OpenApi> = OnceLock::new();
 
 #[utoipa::path(
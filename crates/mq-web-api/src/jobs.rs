@@ -0,0 +1,314 @@
+//! In-memory queue of asynchronous query jobs for `/api/v1/jobs`.
+//!
+//! A submitted job is evaluated in the background by at most
+//! [`JobQueueConfig::workers`] concurrent workers, so multi-megabyte documents
+//! don't tie up request handlers or run into proxy timeouts. Finished jobs are
+//! kept for [`JobQueueConfig::result_ttl`] and dropped lazily on the next submit.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, Semaphore};
+use tracing::{debug, error};
+use utoipa::ToSchema;
+
+use crate::api::{ApiRequest, QueryApiResponse};
+use crate::limits::ExecutionLimits;
+
+#[derive(Debug, Clone)]
+pub struct JobQueueConfig {
+    /// Maximum number of jobs evaluated at the same time.
+    pub workers: usize,
+    /// Maximum number of queued and running jobs; further submissions are rejected.
+    pub max_pending: usize,
+    /// Maximum size of a job's `input` in bytes.
+    pub max_input_bytes: usize,
+    /// Maximum duration a single job may run before it's aborted.
+    pub job_timeout: Duration,
+    /// How long a finished job's result stays available.
+    pub result_ttl: Duration,
+}
+
+impl Default for JobQueueConfig {
+    fn default() -> Self {
+        Self {
+            workers: 2,
+            max_pending: 100,
+            max_input_bytes: 10 * 1024 * 1024,
+            job_timeout: Duration::from_secs(60),
+            result_ttl: Duration::from_secs(600),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Completed | JobStatus::Failed)
+    }
+}
+
+/// Response body for `POST /api/v1/jobs` and `GET /api/v1/jobs/{id}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct JobApiResponse {
+    pub id: String,
+    pub status: JobStatus,
+    /// Set when `status` is `failed`.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Eq)]
+pub enum JobError {
+    #[error("Input exceeds maximum of {max} bytes (got {actual})")]
+    InputTooLarge { max: usize, actual: usize },
+    #[error("Job queue is full ({0} pending jobs)")]
+    QueueFull(usize),
+}
+
+struct JobEntry {
+    status: JobStatus,
+    result: Option<QueryApiResponse>,
+    error: Option<String>,
+    finished_at: Option<Instant>,
+}
+
+impl JobEntry {
+    fn to_response(&self, id: &str) -> JobApiResponse {
+        JobApiResponse {
+            id: id.to_string(),
+            status: self.status,
+            error: self.error.clone(),
+        }
+    }
+}
+
+pub struct JobQueue {
+    jobs: Arc<Mutex<HashMap<String, JobEntry>>>,
+    workers: Arc<Semaphore>,
+    config: JobQueueConfig,
+    limits: ExecutionLimits,
+}
+
+impl JobQueue {
+    /// Creates a queue whose jobs are evaluated under `limits`, with the
+    /// timeout replaced by [`JobQueueConfig::job_timeout`].
+    pub fn new(config: JobQueueConfig, limits: ExecutionLimits) -> Self {
+        Self {
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            workers: Arc::new(Semaphore::new(config.workers.max(1))),
+            limits: ExecutionLimits {
+                timeout: config.job_timeout,
+                ..limits
+            },
+            config,
+        }
+    }
+
+    /// Queues `request` for evaluation and returns the new job's id.
+    pub async fn submit(&self, request: ApiRequest) -> Result<String, JobError> {
+        let input_len = request.input.as_ref().map_or(0, String::len);
+        if input_len > self.config.max_input_bytes {
+            return Err(JobError::InputTooLarge {
+                max: self.config.max_input_bytes,
+                actual: input_len,
+            });
+        }
+
+        let id = uuid::Uuid::new_v4().to_string();
+        {
+            let mut jobs = self.jobs.lock().await;
+            let ttl = self.config.result_ttl;
+            jobs.retain(|_, job| job.finished_at.is_none_or(|at| at.elapsed() < ttl));
+
+            let pending = jobs.values().filter(|job| !job.status.is_finished()).count();
+            if pending >= self.config.max_pending {
+                return Err(JobError::QueueFull(pending));
+            }
+
+            jobs.insert(
+                id.clone(),
+                JobEntry {
+                    status: JobStatus::Queued,
+                    result: None,
+                    error: None,
+                    finished_at: None,
+                },
+            );
+        }
+
+        let jobs = Arc::clone(&self.jobs);
+        let workers = Arc::clone(&self.workers);
+        let limits = self.limits.clone();
+        let job_id = id.clone();
+
+        tokio::spawn(async move {
+            let Ok(_permit) = workers.acquire_owned().await else {
+                return;
+            };
+            set_status(&jobs, &job_id, JobStatus::Running).await;
+            debug!("Running job {}", job_id);
+
+            let result = tokio::task::spawn_blocking(move || crate::api::query(request, &limits)).await;
+
+            let mut jobs = jobs.lock().await;
+            let Some(job) = jobs.get_mut(&job_id) else {
+                return;
+            };
+            match result {
+                Ok(Ok(response)) => {
+                    job.status = JobStatus::Completed;
+                    job.result = Some(response);
+                }
+                Ok(Err(e)) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(e.to_string());
+                }
+                Err(e) => {
+                    error!("Job {} panicked: {}", job_id, e);
+                    job.status = JobStatus::Failed;
+                    job.error = Some("Internal error".to_string());
+                }
+            }
+            job.finished_at = Some(Instant::now());
+        });
+
+        Ok(id)
+    }
+
+    /// Returns the status of the job, or `None` if it's unknown or expired.
+    pub async fn status(&self, id: &str) -> Option<JobApiResponse> {
+        let jobs = self.jobs.lock().await;
+        jobs.get(id).map(|job| job.to_response(id))
+    }
+
+    /// Returns the status of the job together with its result, once completed.
+    pub async fn result(&self, id: &str) -> Option<(JobApiResponse, Option<QueryApiResponse>)> {
+        let jobs = self.jobs.lock().await;
+        jobs.get(id).map(|job| (job.to_response(id), job.result.clone()))
+    }
+}
+
+async fn set_status(jobs: &Mutex<HashMap<String, JobEntry>>, id: &str, status: JobStatus) {
+    if let Some(job) = jobs.lock().await.get_mut(id) {
+        job.status = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(query: &str, input: &str) -> ApiRequest {
+        ApiRequest {
+            query: query.to_string(),
+            input: Some(input.to_string()),
+            input_format: None,
+            modules: None,
+            args: None,
+            output_format: None,
+            aggregate: None,
+        }
+    }
+
+    async fn wait_until_finished(queue: &JobQueue, id: &str) -> JobApiResponse {
+        for _ in 0..200 {
+            let status = queue.status(id).await.unwrap();
+            if status.status.is_finished() {
+                return status;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    #[tokio::test]
+    async fn test_submit_and_complete() {
+        let queue = JobQueue::new(JobQueueConfig::default(), ExecutionLimits::default());
+        let id = queue.submit(request(".h1", "# Title\n\ntext")).await.unwrap();
+
+        let status = wait_until_finished(&queue, &id).await;
+        assert_eq!(status.status, JobStatus::Completed);
+
+        let (_, result) = queue.result(&id).await.unwrap();
+        assert_eq!(result.unwrap().results, vec!["# Title\n"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_job() {
+        let queue = JobQueue::new(JobQueueConfig::default(), ExecutionLimits::default());
+        let id = queue.submit(request("invalid query", "# Title")).await.unwrap();
+
+        let status = wait_until_finished(&queue, &id).await;
+        assert_eq!(status.status, JobStatus::Failed);
+        assert!(status.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_input_too_large() {
+        let queue = JobQueue::new(
+            JobQueueConfig {
+                max_input_bytes: 4,
+                ..Default::default()
+            },
+            ExecutionLimits::default(),
+        );
+
+        let result = queue.submit(request(".h1", "# Title")).await;
+        assert_eq!(result, Err(JobError::InputTooLarge { max: 4, actual: 7 }));
+    }
+
+    #[tokio::test]
+    async fn test_queue_full() {
+        let queue = JobQueue::new(
+            JobQueueConfig {
+                max_pending: 0,
+                ..Default::default()
+            },
+            ExecutionLimits::default(),
+        );
+
+        let result = queue.submit(request(".h1", "# Title")).await;
+        assert_eq!(result, Err(JobError::QueueFull(0)));
+    }
+
+    #[tokio::test]
+    async fn test_expired_jobs_are_dropped() {
+        let queue = JobQueue::new(
+            JobQueueConfig {
+                result_ttl: Duration::ZERO,
+                ..Default::default()
+            },
+            ExecutionLimits::default(),
+        );
+        let id = queue.submit(request(".h1", "# Title")).await.unwrap();
+        wait_until_finished(&queue, &id).await;
+
+        queue.submit(request(".h1", "# Title")).await.unwrap();
+        assert!(queue.status(&id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_job() {
+        let queue = JobQueue::new(JobQueueConfig::default(), ExecutionLimits::default());
+        assert!(queue.status("missing").await.is_none());
+    }
+}
@@ -43,6 +43,7 @@
 //! The API includes configurable rate limiting to prevent abuse:
 //!
 //! - Per-IP rate limits
+//! - Optional API keys with per-key quotas
 //! - Configurable request windows
 //! - Automatic cleanup of expired entries
 //!
@@ -56,10 +57,14 @@
 //! - `RATE_LIMIT_WINDOW` - Time window in seconds
 //!
 pub mod api;
+pub mod api_key;
+pub mod archive;
 pub mod banner;
 pub mod cleanup;
 pub mod config;
 pub mod handlers;
+pub mod jobs;
+pub mod limits;
 pub mod middleware;
 pub mod problem;
 pub mod query_cache;
@@ -68,7 +73,10 @@ pub mod routes;
 pub mod server;
 
 pub use api::{ApiRequest, InputFormat, query};
+pub use api_key::{ApiKeyConfig, ApiKeyStore};
 pub use cleanup::CleanupService;
-pub use config::Config;
+pub use config::{Config, ConfigError};
+pub use jobs::{JobQueue, JobQueueConfig};
+pub use limits::{ExecutionLimits, LimitExceeded};
 pub use query_cache::{QueryCache, QueryCacheConfig};
 pub use rate_limiter::{RateLimitConfig, RateLimitError, RateLimiter};
@@ -0,0 +1,162 @@
+//! Per-request execution limits.
+//!
+//! Every evaluated query is bounded by [`ExecutionLimits`]: its length, the
+//! engine's timeout and call stack depth, and the total size of its rendered
+//! results. The engine has no allocation accounting, so the result size is the
+//! memory budget a request can actually be held to. Exceeding a limit yields a
+//! [`LimitExceeded`] error that handlers turn into a problem response.
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+
+use crate::problem::ProblemDetails;
+
+#[derive(Debug, Clone)]
+pub struct ExecutionLimits {
+    /// Maximum duration a single query evaluation may run before it's aborted.
+    pub timeout: Duration,
+    /// Maximum length of a query in bytes.
+    pub max_query_length: usize,
+    /// Maximum depth of nested function calls; `None` keeps the engine default.
+    pub max_call_stack_depth: Option<u32>,
+    /// Maximum total size of a request's rendered results in bytes.
+    pub max_result_bytes: usize,
+}
+
+impl Default for ExecutionLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_query_length: 16 * 1024,
+            max_call_stack_depth: None,
+            max_result_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl ExecutionLimits {
+    /// Applies the limits enforced by the engine itself.
+    pub fn apply(&self, engine: &mut mq_lang::DefaultEngine) {
+        engine.set_timeout(self.timeout);
+        if let Some(depth) = self.max_call_stack_depth {
+            engine.set_max_call_stack_depth(depth);
+        }
+    }
+
+    pub fn check_query(&self, query: &str) -> Result<(), LimitExceeded> {
+        if query.len() > self.max_query_length {
+            return Err(LimitExceeded::QueryTooLong {
+                max: self.max_query_length,
+                actual: query.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks `used` bytes of rendered results against the result budget.
+    pub fn check_result_size(&self, used: usize) -> Result<(), LimitExceeded> {
+        if used > self.max_result_bytes {
+            return Err(LimitExceeded::ResultTooLarge {
+                max: self.max_result_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts an engine error into [`LimitExceeded`] if it was caused by one
+    /// of the engine-enforced limits.
+    pub fn classify(error: &mq_lang::Error) -> Option<LimitExceeded> {
+        match &error.cause {
+            mq_lang::InnerError::Runtime(mq_lang::RuntimeError::Timeout(timeout)) => {
+                Some(LimitExceeded::Timeout(*timeout))
+            }
+            mq_lang::InnerError::Runtime(mq_lang::RuntimeError::RecursionError(depth)) => {
+                Some(LimitExceeded::CallStackDepth(*depth))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, miette::Diagnostic)]
+pub enum LimitExceeded {
+    #[error("Query exceeds maximum length of {max} bytes (got {actual})")]
+    QueryTooLong { max: usize, actual: usize },
+    #[error("Query evaluation exceeded the time limit of {0:?}")]
+    Timeout(Duration),
+    #[error("Query exceeded the maximum call stack depth of {0}")]
+    CallStackDepth(u32),
+    #[error("Query results exceed the maximum of {max} bytes")]
+    ResultTooLarge { max: usize },
+}
+
+impl LimitExceeded {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            LimitExceeded::QueryTooLong { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            LimitExceeded::Timeout(_) | LimitExceeded::CallStackDepth(_) | LimitExceeded::ResultTooLarge { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            LimitExceeded::QueryTooLong { .. } => "Query too long",
+            LimitExceeded::Timeout(_) => "Query timed out",
+            LimitExceeded::CallStackDepth(_) => "Call stack depth exceeded",
+            LimitExceeded::ResultTooLarge { .. } => "Result too large",
+        }
+    }
+
+    pub fn to_problem(&self) -> ProblemDetails {
+        ProblemDetails::new(self.status())
+            .with_title(self.title())
+            .with_detail("error", &self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(".h1", Ok(()))]
+    #[case(".h1 | .text", Err(LimitExceeded::QueryTooLong { max: 8, actual: 11 }))]
+    fn test_check_query(#[case] query: &str, #[case] expected: Result<(), LimitExceeded>) {
+        let limits = ExecutionLimits {
+            max_query_length: 8,
+            ..Default::default()
+        };
+        assert_eq!(limits.check_query(query), expected);
+    }
+
+    #[rstest]
+    #[case(LimitExceeded::QueryTooLong { max: 1, actual: 2 }, StatusCode::PAYLOAD_TOO_LARGE)]
+    #[case(LimitExceeded::Timeout(Duration::from_secs(1)), StatusCode::UNPROCESSABLE_ENTITY)]
+    #[case(LimitExceeded::CallStackDepth(8), StatusCode::UNPROCESSABLE_ENTITY)]
+    #[case(LimitExceeded::ResultTooLarge { max: 1 }, StatusCode::UNPROCESSABLE_ENTITY)]
+    fn test_status(#[case] error: LimitExceeded, #[case] expected: StatusCode) {
+        assert_eq!(error.status(), expected);
+    }
+
+    #[test]
+    fn test_classify_recursion_error() {
+        let mut engine = mq_lang::DefaultEngine::default();
+        ExecutionLimits {
+            max_call_stack_depth: Some(4),
+            ..Default::default()
+        }
+        .apply(&mut engine);
+
+        let error = engine
+            .eval("def f(x): f(x); | f(1)", mq_lang::null_input().into_iter())
+            .unwrap_err();
+        assert_eq!(
+            ExecutionLimits::classify(&error),
+            Some(LimitExceeded::CallStackDepth(4))
+        );
+    }
+}
@@ -10,7 +10,7 @@ use mq_web_api::{
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::from_env();
+    let config = Config::from_env()?;
     print_banner(&config);
     init_tracing(&config);
     start_server(config).await
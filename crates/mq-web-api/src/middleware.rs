@@ -1,3 +1,7 @@
+pub mod api_key;
+pub mod body_limit;
 pub mod rate_limit;
 
+pub use api_key::api_key_middleware;
+pub use body_limit::body_limit_middleware;
 pub use rate_limit::rate_limit_middleware;
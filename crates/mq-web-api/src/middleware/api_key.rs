@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::{
+    api_key::{ApiKeyStore, AuthenticatedKey},
+    middleware::rate_limit::{add_rate_limit_headers, create_rate_limit_exceeded_response},
+    problem::ProblemDetails,
+    rate_limiter::RateLimitError,
+};
+
+/// Paths that are reachable without an API key even when one is required.
+const PUBLIC_PATHS: &[&str] = &["/health"];
+
+pub async fn api_key_middleware(
+    State(api_keys): State<Arc<ApiKeyStore>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !api_keys.is_enabled() || PUBLIC_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let Some(presented_key) = extract_api_key(&request) else {
+        if api_keys.is_required() {
+            return unauthorized("An API key is required");
+        }
+        return next.run(request).await;
+    };
+
+    let Some(api_key) = api_keys.find(&presented_key) else {
+        warn!("Rejected request with unknown API key");
+        return unauthorized("Invalid API key");
+    };
+
+    match api_key.check_and_increment().await {
+        Ok(()) => {
+            debug!("API key quota check passed for key: {}", api_key.name);
+            request.extensions_mut().insert(AuthenticatedKey(Arc::clone(&api_key)));
+
+            let mut response = next.run(request).await;
+            if let Ok(current_usage) = api_key.limiter().get_current_usage(&api_key.name).await {
+                add_rate_limit_headers(&mut response, current_usage.unwrap_or(1), api_key.limiter());
+            }
+            response
+        }
+        Err(RateLimitError::LimitExceeded { requests, limit }) => {
+            warn!(
+                "Quota exceeded for API key '{}': {}/{} requests",
+                api_key.name, requests, limit
+            );
+
+            let mut response = create_rate_limit_exceeded_response(requests, limit);
+            add_rate_limit_headers(&mut response, requests, api_key.limiter());
+            response
+        }
+        Err(err) => {
+            warn!("Quota check error for API key '{}': {}", api_key.name, err);
+            quota_unavailable()
+        }
+    }
+}
+
+fn extract_api_key(request: &Request) -> Option<String> {
+    if let Some(key) = request.headers().get("x-api-key")
+        && let Ok(key) = key.to_str()
+    {
+        return Some(key.trim().to_string());
+    }
+
+    request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| {
+            let (scheme, key) = value.split_once(' ')?;
+            scheme.eq_ignore_ascii_case("Bearer").then(|| key.trim().to_string())
+        })
+}
+
+/// A key's quota couldn't be checked; reject rather than let the request
+/// through unmetered.
+fn quota_unavailable() -> Response {
+    ProblemDetails::new(StatusCode::SERVICE_UNAVAILABLE)
+        .with_title("Service unavailable")
+        .with_detail("error", "Unable to check the API key quota")
+        .into_response()
+}
+
+fn unauthorized(message: &str) -> Response {
+    let mut response = ProblemDetails::new(StatusCode::UNAUTHORIZED)
+        .with_title("Unauthorized")
+        .with_detail("error", message)
+        .into_response();
+    response
+        .headers_mut()
+        .insert("WWW-Authenticate", axum::http::HeaderValue::from_static("Bearer"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_key::{ApiKeyConfig, ApiKeyDefinition};
+    use crate::rate_limiter::RateLimitConfig;
+    use axum::{Router, body::Body, middleware, routing::get};
+    use rstest::rstest;
+    use tower::ServiceExt;
+
+    fn app(required: bool) -> Router {
+        let config = ApiKeyConfig {
+            keys: vec![ApiKeyDefinition {
+                name: "ci".to_string(),
+                key: "secret".to_string(),
+                requests_per_window: Some(1),
+            }],
+            required,
+        };
+        let store = Arc::new(ApiKeyStore::new(&config, &RateLimitConfig::default()));
+        Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/api", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(store, api_key_middleware))
+    }
+
+    async fn status(app: Router, path: &str, header: Option<(&str, &str)>) -> StatusCode {
+        let mut builder = axum::http::Request::builder().uri(path);
+        if let Some((name, value)) = header {
+            builder = builder.header(name, value);
+        }
+        app.oneshot(builder.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[rstest]
+    #[case::valid_header(true, "/api", Some(("x-api-key", "secret")), StatusCode::OK)]
+    #[case::valid_bearer(true, "/api", Some(("authorization", "Bearer secret")), StatusCode::OK)]
+    #[case::lowercase_bearer(true, "/api", Some(("authorization", "bearer secret")), StatusCode::OK)]
+    #[case::other_scheme(true, "/api", Some(("authorization", "Basic secret")), StatusCode::UNAUTHORIZED)]
+    #[case::invalid_key(true, "/api", Some(("x-api-key", "wrong")), StatusCode::UNAUTHORIZED)]
+    #[case::invalid_key_optional(false, "/api", Some(("x-api-key", "wrong")), StatusCode::UNAUTHORIZED)]
+    #[case::missing_key(true, "/api", None, StatusCode::UNAUTHORIZED)]
+    #[case::missing_key_optional(false, "/api", None, StatusCode::OK)]
+    #[case::public_path(true, "/health", None, StatusCode::OK)]
+    #[tokio::test]
+    async fn test_api_key_middleware(
+        #[case] required: bool,
+        #[case] path: &str,
+        #[case] header: Option<(&str, &str)>,
+        #[case] expected: StatusCode,
+    ) {
+        assert_eq!(status(app(required), path, header).await, expected);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_quota_exceeded() {
+        let app = app(true);
+        let header = Some(("x-api-key", "secret"));
+        assert_eq!(status(app.clone(), "/api", header).await, StatusCode::OK);
+        assert_eq!(status(app, "/api", header).await, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[test]
+    fn test_quota_unavailable() {
+        assert_eq!(quota_unavailable().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
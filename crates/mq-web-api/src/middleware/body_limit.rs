@@ -0,0 +1,70 @@
+use axum::{
+    extract::Request,
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::problem::ProblemDetails;
+
+/// Upper bound on the rejection text read back from a plain 413 response.
+const MAX_REJECTION_BYTES: usize = 4 * 1024;
+
+/// Rewrites the plain-text 413 responses produced by axum's body limit as
+/// problem responses, so every exceeded limit is reported the same way.
+pub async fn body_limit_middleware(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE || is_json(&response) {
+        return response;
+    }
+
+    let body = axum::body::to_bytes(response.into_body(), MAX_REJECTION_BYTES)
+        .await
+        .unwrap_or_default();
+    let message = String::from_utf8_lossy(&body);
+    warn!("Rejected request body: {}", message);
+
+    ProblemDetails::new(StatusCode::PAYLOAD_TOO_LARGE)
+        .with_title("Request body too large")
+        .with_detail("error", &message)
+        .into_response()
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.contains("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Json, Router, body::Body, extract::DefaultBodyLimit, middleware, routing::post};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_body_limit_rejection_is_problem() {
+        let app = Router::new()
+            .route(
+                "/",
+                post(|Json(value): Json<serde_json::Value>| async move { Json(value) }),
+            )
+            .layer(DefaultBodyLimit::max(8))
+            .layer(middleware::from_fn(body_limit_middleware));
+
+        let request = axum::http::Request::post("/")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"query": ".h1"}"#))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let problem: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(problem["status"], 413);
+        assert_eq!(problem["title"], "Request body too large");
+    }
+}
@@ -7,6 +7,7 @@ use axum::{
 use std::{net::SocketAddr, sync::Arc};
 use tracing::{debug, warn};
 
+use crate::api_key::AuthenticatedKey;
 use crate::rate_limiter::{RateLimitError, RateLimiter};
 
 pub async fn rate_limit_middleware(
@@ -14,6 +15,11 @@ pub async fn rate_limit_middleware(
     request: Request,
     next: Next,
 ) -> Response {
+    // Requests with an API key were already counted against the key's quota.
+    if request.extensions().get::<AuthenticatedKey>().is_some() {
+        return next.run(request).await;
+    }
+
     let identifier = extract_identifier(&request);
 
     match rate_limiter.check_and_increment(&identifier).await {
@@ -78,7 +84,7 @@ fn extract_identifier(request: &Request) -> String {
     "unknown".to_string()
 }
 
-fn create_rate_limit_exceeded_response(requests: i64, limit: i64) -> Response {
+pub(crate) fn create_rate_limit_exceeded_response(requests: i64, limit: i64) -> Response {
     let body = serde_json::json!({
         "error": "Rate limit exceeded",
         "message": format!("Too many requests: {}/{} requests in current window", requests, limit),
@@ -94,7 +100,7 @@ fn create_rate_limit_exceeded_response(requests: i64, limit: i64) -> Response {
         .into_response()
 }
 
-fn add_rate_limit_headers(response: &mut Response, current_usage: i64, rate_limiter: &RateLimiter) {
+pub(crate) fn add_rate_limit_headers(response: &mut Response, current_usage: i64, rate_limiter: &RateLimiter) {
     let headers = response.headers_mut();
 
     // Add current usage
@@ -1,5 +1,6 @@
 use axum::{
     Router,
+    extract::DefaultBodyLimit,
     http::Method,
     middleware,
     response::Redirect,
@@ -16,20 +17,34 @@ use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
+    api_key::ApiKeyStore,
     config::Config,
     handlers::{
-        ApiDoc, AppState, get_functions_api, get_query_api, get_selectors_api, health_check, post_batch_api,
-        post_check_api, post_format_api, post_lint_api, post_query_api, post_shorthand_query_api,
+        ApiDoc, AppState, get_functions_api, get_job_api, get_job_result_api, get_query_api, get_selectors_api,
+        get_usage_api, health_check, post_batch_api, post_check_api, post_convert_html_to_markdown_api,
+        post_files_query_api, post_format_api, post_job_api, post_lint_api, post_query_api, post_shorthand_query_api,
+        post_stream_query_api,
     },
-    middleware::rate_limit_middleware,
+    jobs::JobQueue,
+    middleware::{api_key_middleware, body_limit_middleware, rate_limit_middleware},
     query_cache::QueryCache,
     rate_limiter::RateLimiter,
 };
 
-pub fn create_router(config: &Config, rate_limiter: Arc<RateLimiter>) -> Router {
+/// Builds the application router. Fails if the batch thread pool can't be created.
+pub fn create_router(config: &Config, rate_limiter: Arc<RateLimiter>) -> Result<Router, rayon::ThreadPoolBuildError> {
+    let api_keys = Arc::new(ApiKeyStore::new(&config.api_keys, &config.rate_limit));
+
     let state = AppState {
-        query_timeout: config.query_timeout,
+        limits: config.limits.clone(),
         query_cache: Arc::new(QueryCache::new(config.query_cache.clone())),
+        batch_pool: Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(config.batch_concurrency)
+                .thread_name(|i| format!("mq-batch-{}", i))
+                .build()?,
+        ),
+        job_queue: Arc::new(JobQueue::new(config.jobs.clone(), config.limits.clone())),
     };
 
     let cors = if config.cors_origins.contains(&"*".to_string()) {
@@ -57,14 +72,28 @@ pub fn create_router(config: &Config, rate_limiter: Arc<RateLimiter>) -> Router
 
     let v1_routes = Router::new()
         .route("/query", get(get_query_api).post(post_query_api))
+        .route("/query/stream", post(post_stream_query_api))
+        .route("/query/files", post(post_files_query_api))
         .route("/batch", post(post_batch_api))
+        // Jobs exist for inputs larger than `max_body_bytes`; the queue
+        // enforces its own limit on `input`, plus room for the JSON envelope.
+        .route(
+            "/jobs",
+            post(post_job_api).layer(DefaultBodyLimit::max(
+                config.jobs.max_input_bytes.saturating_add(64 * 1024),
+            )),
+        )
+        .route("/jobs/{id}", get(get_job_api))
+        .route("/jobs/{id}/result", get(get_job_result_api))
         .route("/check", post(post_check_api))
         .route("/format", post(post_format_api))
+        .route("/convert/html-to-markdown", post(post_convert_html_to_markdown_api))
         .route("/functions", get(get_functions_api))
         .route("/selectors", get(get_selectors_api))
-        .route("/lint", post(post_lint_api));
+        .route("/lint", post(post_lint_api))
+        .route("/usage", get(get_usage_api));
 
-    Router::new()
+    Ok(Router::new()
         .merge(SwaggerUi::new("/docs").url("/api/v1/openapi.json", ApiDoc::openapi()))
         .route("/health", get(health_check))
         .nest("/api/v1", v1_routes)
@@ -83,12 +112,16 @@ pub fn create_router(config: &Config, rate_limiter: Arc<RateLimiter>) -> Router
         // Curl-friendly shortcut: query in the path, raw Markdown body.
         // Static routes above (e.g. /health, /docs, /api/v1/*) take precedence.
         .route("/{query}", post(post_shorthand_query_api))
+        .layer(DefaultBodyLimit::max(config.max_body_bytes))
         .layer(
             ServiceBuilder::new()
+                .layer(middleware::from_fn(body_limit_middleware))
                 .layer(CompressionLayer::new())
                 .layer(TraceLayer::new_for_http())
                 .layer(cors),
         )
         .layer(middleware::from_fn_with_state(rate_limiter, rate_limit_middleware))
-        .with_state(state)
+        // Added last so it runs first: keyed requests skip the per-IP limit.
+        .layer(middleware::from_fn_with_state(api_keys, api_key_middleware))
+        .with_state(state))
 }
@@ -115,12 +115,14 @@ pub async fn start_server(config: Config) -> Result<(), Box<dyn std::error::Erro
     let rate_limiter = Arc::new(RateLimiter::new(config.rate_limit.clone()));
     info!("Rate limiter initialized successfully");
 
-    let app = create_router(&config, rate_limiter.clone()).layer(TraceLayer::new_for_http().on_response(
-        |response: &axum::response::Response, latency: Duration, _span: &tracing::Span| {
-            let ms = latency.as_secs_f64() * 1000.0;
-            info!("response latency: {:.2}ms, status: {}", ms, response.status());
-        },
-    ));
+    let app = create_router(&config, rate_limiter.clone())
+        .map_err(|e| format!("Failed to create batch thread pool: {}", e))?
+        .layer(TraceLayer::new_for_http().on_response(
+            |response: &axum::response::Response, latency: Duration, _span: &tracing::Span| {
+                let ms = latency.as_secs_f64() * 1000.0;
+                info!("response latency: {:.2}ms, status: {}", ms, response.status());
+            },
+        ));
 
     let bind_address = config.bind_address();
     let listener = tokio::net::TcpListener::bind(&bind_address)
@@ -141,10 +143,22 @@ pub async fn start_server(config: Config) -> Result<(), Box<dyn std::error::Erro
     info!("  RATE_LIMIT_REQUESTS_PER_WINDOW: Requests per window (default: 100)");
     info!("  RATE_LIMIT_WINDOW_SIZE_SECONDS: Window size in seconds (default: 3600)");
     info!("  RATE_LIMIT_CLEANUP_INTERVAL_SECONDS: Cleanup interval in seconds (default: 3600)");
+    info!("  API_KEYS: Comma-separated name:key[:requests_per_window] API keys (default: none)");
+    info!("  API_KEY_REQUIRED: Reject requests without a valid API key (default: true when API_KEYS is set)");
     info!("  QUERY_TIMEOUT_SECONDS: Max seconds a single query may run before it's aborted (default: 10)");
+    info!("  MAX_BODY_BYTES: Max request body size in bytes (default: 2097152)");
+    info!("  MAX_QUERY_LENGTH: Max query length in bytes (default: 16384)");
+    info!("  MAX_CALL_STACK_DEPTH: Max depth of nested function calls (default: engine default)");
+    info!("  MAX_RESULT_BYTES: Max total size of a request's results in bytes (default: 16777216)");
     info!("  QUERY_CACHE_ENABLED: Cache repeated query/input combinations (default: true)");
     info!("  QUERY_CACHE_TTL_SECONDS: How long a cached result stays fresh (default: 30)");
     info!("  QUERY_CACHE_MAX_ENTRIES: Max number of cached query results (default: 1000)");
+    info!("  BATCH_CONCURRENCY: Max batch documents evaluated at the same time (default: CPU count)");
+    info!("  JOB_WORKERS: Max background jobs evaluated at the same time (default: 2)");
+    info!("  JOB_MAX_PENDING: Max queued and running background jobs (default: 100)");
+    info!("  JOB_MAX_INPUT_BYTES: Max input size of a background job in bytes (default: 10485760)");
+    info!("  JOB_TIMEOUT_SECONDS: Max seconds a background job may run before it's aborted (default: 60)");
+    info!("  JOB_RESULT_TTL_SECONDS: How long a finished job's result stays available (default: 600)");
 
     // Start cleanup service
     let mut cleanup_service = CleanupService::new(
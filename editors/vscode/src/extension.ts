@@ -346,6 +346,38 @@ function registerMqExecutionCommands(context: vscode.ExtensionContext) {
     ),
   );
 
+  // Invoked by the "Run Query" code lens of the LSP server
+  context.subscriptions.push(
+    vscode.commands.registerCommand(
+      "mq.runQuery",
+      async (uri: string, position?: { line: number; character: number }) => {
+        if (!client) {
+          vscode.window.showErrorMessage("LSP server is not running");
+          return;
+        }
+
+        try {
+          const result = await client.sendRequest<{
+            input: string;
+            result: string;
+          }>("mq/runQuery", { textDocument: { uri }, position });
+          const outputChannel = vscode.window.createOutputChannel(
+            "mq LSP Output",
+            "markdown",
+          );
+          outputChannel.clear();
+          outputChannel.appendLine(result.result);
+          outputChannel.show();
+        } catch (error) {
+          await vscode.window.showErrorMessage(
+            `Failed to run query: ${error instanceof Error ? error.message : "Unknown error"
+            }`,
+          );
+        }
+      },
+    ),
+  );
+
   // Initialize Code Lens provider
   updateCodeLensProvider(context);
 
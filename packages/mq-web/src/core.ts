@@ -5,18 +5,46 @@ import init, {
   InlayHint,
   HoverResult,
   ConversionOptions,
+  SyntaxToken,
+  SyntaxTree,
+  CompletionItem,
+  Value,
+  FormatOptions,
+  BuiltinDoc,
 } from "./mq_wasm";
 
 // Type definitions for WASM module
 interface WasmModule {
   run: (code: string, content: string, options: Options) => Promise<string>;
+  runStream: (
+    code: string,
+    content: string,
+    options: Options,
+    onResult: (output: string) => void | Promise<void>,
+    chunkSize?: number,
+  ) => Promise<void>;
+  runValues: (
+    code: string,
+    content: string,
+    options: Options,
+  ) => Promise<readonly Value[]>;
   toAst: (code: string) => Promise<string>;
-  format: (code: string) => Promise<string>;
+  format: (code: string, options?: FormatOptions) => Promise<string>;
+  builtinDocs: (
+    format: "json" | "markdown" | "html",
+  ) => Promise<ReadonlyArray<BuiltinDoc> | string>;
   diagnostics: (
     code: string,
     enableTypeCheck?: boolean,
   ) => Promise<readonly Diagnostic[]>;
   inlayHints: (code: string) => Promise<readonly InlayHint[]>;
+  tokens: (code: string) => Promise<readonly SyntaxToken[]>;
+  cst: (code: string) => Promise<SyntaxTree>;
+  completions: (
+    code: string,
+    line: number,
+    column: number,
+  ) => Promise<readonly CompletionItem[]>;
   hover: (
     code: string,
     line: number,
@@ -32,9 +60,18 @@ interface WasmModule {
   ): Promise<string>;
   toHtml(markdown_input: string): Promise<string>;
   clearHttpCache(): Promise<void>;
+  setModuleResolver(resolver: ModuleResolver | null): void;
   clearAllHttpCache(): Promise<void>;
 }
 
+/**
+ * Resolves a module name used in `include` / `import` to its source text.
+ * Returning `null` or `undefined` leaves the module unresolved.
+ */
+export type ModuleResolver = (
+  name: string,
+) => string | null | undefined | Promise<string | null | undefined>;
+
 let wasmModule: WasmModule | null = null;
 
 async function initWasm(): Promise<WasmModule> {
@@ -49,15 +86,22 @@ async function initWasm(): Promise<WasmModule> {
 
       wasmModule = {
         run: wasmImport.run,
+        runStream: wasmImport.runStream,
+        runValues: wasmImport.runValues,
         toAst: wasmImport.toAst,
         format: wasmImport.format,
+        builtinDocs: wasmImport.builtinDocs,
         diagnostics: wasmImport.diagnostics,
         inlayHints: wasmImport.inlayHints,
+        tokens: wasmImport.tokens,
+        cst: wasmImport.cst,
+        completions: wasmImport.completions,
         hover: wasmImport.hover,
         definedValues: wasmImport.definedValues,
         htmlToMarkdown: wasmImport.htmlToMarkdown,
         toHtml: wasmImport.toHtml,
         clearHttpCache: wasmImport.clearHttpCache,
+        setModuleResolver: wasmImport.setModuleResolver,
         clearAllHttpCache: wasmImport.clearAllHttpCache,
       };
     } catch (error) {
@@ -87,6 +131,58 @@ export async function run(
   });
 }
 
+/**
+ * Run an mq query incrementally, calling `onResult` with the rendered output of each chunk of
+ * `chunkSize` (default 100) top-level input nodes.
+ *
+ * Control returns to the event loop between chunks, so processing a large document does not
+ * freeze the page. If `onResult` returns a promise, the next chunk waits for it.
+ */
+export async function runStream(
+  code: string,
+  content: string,
+  onResult: (output: string) => void | Promise<void>,
+  options: Partial<Options> & { chunkSize?: number } = {},
+): Promise<void> {
+  const wasm = await initWasm();
+  const { chunkSize, ...runOptions } = options;
+  return await wasm.runStream(
+    code,
+    content,
+    {
+      isUpdate: false,
+      inputFormat: "markdown",
+      listStyle: "dash",
+      linkUrlStyle: "none",
+      linkTitleStyle: "paren",
+      ...runOptions,
+    },
+    onResult,
+    chunkSize,
+  );
+}
+
+/**
+ * Run an mq query and return the results as JavaScript values instead of rendered markdown.
+ *
+ * Markdown nodes are returned as objects with a `type` field, dicts as plain objects.
+ */
+export async function runValues(
+  code: string,
+  content: string,
+  options: Partial<Options> = {},
+): Promise<ReadonlyArray<Value>> {
+  const wasm = await initWasm();
+  return await wasm.runValues(code, content, {
+    isUpdate: false,
+    inputFormat: "markdown",
+    listStyle: "dash",
+    linkUrlStyle: "none",
+    linkTitleStyle: "paren",
+    ...options,
+  });
+}
+
 /**
  * Convert mq code to its AST (Abstract Syntax Tree) representation.
  */
@@ -98,9 +194,27 @@ export async function toAst(code: string): Promise<string> {
 /**
  * Format mq code
  */
-export async function format(code: string): Promise<string> {
+export async function format(
+  code: string,
+  options?: FormatOptions,
+): Promise<string> {
+  const wasm = await initWasm();
+  return await wasm.format(code, options);
+}
+
+/**
+ * Get the reference documentation of the builtin functions, selectors, and standard modules,
+ * as an array of entries (`"json"`, the default) or as a rendered `"markdown"` or `"html"` document.
+ */
+export async function builtinDocs(
+  format?: "json",
+): Promise<ReadonlyArray<BuiltinDoc>>;
+export async function builtinDocs(format: "markdown" | "html"): Promise<string>;
+export async function builtinDocs(
+  format: "json" | "markdown" | "html" = "json",
+): Promise<ReadonlyArray<BuiltinDoc> | string> {
   const wasm = await initWasm();
-  return await wasm.format(code);
+  return await wasm.builtinDocs(format);
 }
 
 /**
@@ -124,6 +238,34 @@ export async function inlayHints(
   return await wasm.inlayHints(code);
 }
 
+/**
+ * Get the classified tokens of mq code for syntax highlighting, in source order.
+ */
+export async function tokens(code: string): Promise<ReadonlyArray<SyntaxToken>> {
+  const wasm = await initWasm();
+  return await wasm.tokens(code);
+}
+
+/**
+ * Get the concrete syntax tree of mq code, parsed with error recovery, and its syntax errors.
+ */
+export async function cst(code: string): Promise<SyntaxTree> {
+  const wasm = await initWasm();
+  return await wasm.cst(code);
+}
+
+/**
+ * Get completion candidates at the given position (1-based line and column).
+ */
+export async function completions(
+  code: string,
+  line: number,
+  column: number,
+): Promise<ReadonlyArray<CompletionItem>> {
+  const wasm = await initWasm();
+  return await wasm.completions(code, line, column);
+}
+
 /**
  * Get defined values from mq code
  */
@@ -168,6 +310,17 @@ export async function toHtml(markdownInput: string): Promise<string> {
   return await wasm.toHtml(markdownInput);
 }
 
+/**
+ * Registers a callback that resolves `include "name"` and `import "name"` to module source text,
+ * e.g. fetched from a CDN or read from a virtual filesystem. Pass `null` to remove it.
+ */
+export async function setModuleResolver(
+  resolver: ModuleResolver | null,
+): Promise<void> {
+  const wasm = await initWasm();
+  wasm.setModuleResolver(resolver);
+}
+
 /**
  * Clears mutable HTTP module cache (HEAD/branch imports).
  * Versioned (tagged) cache is preserved.
@@ -12,7 +12,8 @@
  */
 
 // Re-export everything from core and types
-export { run, format, diagnostics, inlayHints, definedValues, hover, toAst, toHtml, htmlToMarkdown, clearHttpCache, clearAllHttpCache } from "./core.js";
+export { run, runStream, runValues, format, builtinDocs, diagnostics, inlayHints, tokens, cst, completions, definedValues, hover, toAst, toHtml, htmlToMarkdown, clearHttpCache, clearAllHttpCache, setModuleResolver } from "./core.js";
+export type { ModuleResolver } from "./core.js";
 
 export type {
   Options,
@@ -21,5 +22,14 @@ export type {
   HoverResult,
   DefinedValue,
   DefinedValueType,
+  MarkdownNode,
+  Value,
+  TokenClass,
+  SyntaxToken,
+  SyntaxNode,
+  SyntaxTree,
+  CompletionItem,
+  FormatOptions,
+  BuiltinDoc,
 } from "../mq-wasm/mq_wasm.js";
 
@@ -1,13 +1,20 @@
 import { describe, it, expect } from "vitest";
 import {
   run,
+  runStream,
+  runValues,
+  setModuleResolver,
   format,
+  builtinDocs,
   toAst,
   toHtml,
   htmlToMarkdown,
   diagnostics,
   inlayHints,
   definedValues,
+  tokens,
+  cst,
+  completions,
 } from "../src/index";
 
 describe("run", () => {
@@ -42,6 +49,51 @@ describe("run", () => {
   });
 });
 
+describe("runStream", () => {
+  it("calls back once per chunk", async () => {
+    const chunks: string[] = [];
+    await runStream(
+      ".h",
+      "# A\n\n# B\n\n# C",
+      (chunk) => {
+        chunks.push(chunk);
+      },
+      { chunkSize: 2 },
+    );
+    expect(chunks).toEqual(["# A\n# B\n", "# C\n"]);
+  });
+});
+
+describe("runValues", () => {
+  it("returns markdown nodes as objects", async () => {
+    const [heading] = await runValues(".h", "# Title");
+    expect(heading).toMatchObject({ type: "Heading", depth: 1 });
+  });
+
+  it("returns arrays, dicts, numbers, and booleans as values", async () => {
+    const result = await runValues('[1, true, {"a": "b"}, None]', "", {
+      inputFormat: "null",
+    });
+    expect(result).toEqual([[1, true, { a: "b" }, null]]);
+  });
+});
+
+describe("setModuleResolver", () => {
+  it("resolves includes with the registered callback", async () => {
+    await setModuleResolver(async (name) =>
+      name === "greet" ? 'def greet(x): "Hello, " + x;' : null,
+    );
+    try {
+      const result = await run('include "greet" | greet("mq")', "", {
+        inputFormat: "null",
+      });
+      expect(result).toBe("Hello, mq\n");
+    } finally {
+      await setModuleResolver(null);
+    }
+  });
+});
+
 describe("format", () => {
   it("formats mq code with spaces around pipe", async () => {
     const result = await format("map(to_text)|select(gt(5))");
@@ -54,6 +106,28 @@ describe("format", () => {
   });
 });
 
+describe("format with options", () => {
+  it("uses the given indent width", async () => {
+    const result = await format("def f(x):\nx;", { indentWidth: 4 });
+    expect(result).toBe("def f(x):\n    x;\n");
+  });
+});
+
+describe("builtinDocs", () => {
+  it("returns builtin and module functions as entries", async () => {
+    const docs = await builtinDocs();
+    expect(docs).toContainEqual(
+      expect.objectContaining({ name: "map", kind: "function", module: null }),
+    );
+    expect(docs.some((doc) => doc.module === "csv")).toBe(true);
+  });
+
+  it("renders the reference as html", async () => {
+    const html = await builtinDocs("html");
+    expect(html).toContain("<h2>Built-in Functions</h2>");
+  });
+});
+
 describe("toAst", () => {
   it("returns a non-empty AST string", async () => {
     const result = await toAst(".[]");
@@ -127,3 +201,36 @@ describe("definedValues", () => {
     expect(result.some((v) => v.name === "double")).toBe(true);
   });
 });
+
+describe("tokens", () => {
+  it("classifies tokens for highlighting", async () => {
+    const result = await tokens('def f(x): "a";');
+    expect(result.map((token) => token.class)).toEqual([
+      "keyword",
+      "function",
+      "punctuation",
+      "variable",
+      "punctuation",
+      "punctuation",
+      "string",
+      "punctuation",
+    ]);
+  });
+});
+
+describe("cst", () => {
+  it("returns nodes and syntax errors", async () => {
+    const tree = await cst("foo(");
+    expect(tree.nodes.length).toBeGreaterThan(0);
+    expect(tree.errors.length).toBeGreaterThan(0);
+  });
+});
+
+describe("completions", () => {
+  it("includes user-defined functions", async () => {
+    const items = await completions("def foo(x): x;\n| ", 2, 3);
+    expect(items).toContainEqual(
+      expect.objectContaining({ label: "foo", kind: "function" }),
+    );
+  });
+});